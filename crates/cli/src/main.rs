@@ -0,0 +1,440 @@
+//! DAM command-line interface
+//!
+//! Scriptable access to ingestion and search without the Tauri UI. The
+//! index is persisted under `<data-dir>/index` so state survives between
+//! invocations.
+
+use anyhow::{bail, Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use futures::StreamExt;
+use index::{EmbeddingType, IndexService, SearchResult, SharedIndex};
+use chrono::{DateTime, Utc};
+use ingest::{ImportLog, IngestService};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use tracing::warn;
+use schema::{Asset, AssetType, IntegrityStatus};
+use uuid::Uuid;
+
+/// Digital Asset Manager
+#[derive(Parser)]
+#[command(name = "dam", version, about)]
+struct Cli {
+    /// Directory holding persistent state (the index lives in `<data-dir>/index`)
+    #[arg(long, global = true, default_value = "data")]
+    data_dir: PathBuf,
+    
+    /// Print results as JSON instead of human-readable text
+    #[arg(long, global = true)]
+    json: bool,
+    
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Ingest files or directories and add them to the index
+    Ingest {
+        /// Files or directories to ingest
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+        
+        /// Check each file for truncation or corruption (slow: decodes images)
+        #[arg(long)]
+        verify: bool,
+        
+        /// Continue an interrupted import of directories, skipping files
+        /// an earlier run already indexed
+        #[arg(long)]
+        resume: bool,
+    },
+    
+    /// Full-text search over indexed assets
+    Search {
+        /// Search query
+        query: String,
+        
+        /// Maximum number of results
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+    
+    /// Show index statistics
+    Stats,
+    
+    /// Re-run format detection on assets indexed as unknown and reindex
+    /// the ones now recognized
+    Redetect,
+    
+    /// List recorded import events (kept in `<data-dir>/imports.jsonl`)
+    Imports {
+        /// Only events at or after this time (RFC 3339, e.g. 2024-05-01T00:00:00Z)
+        #[arg(long)]
+        since: Option<DateTime<Utc>>,
+    },
+    
+    /// Find assets similar to an indexed asset
+    Similar {
+        /// Asset ID to compare against
+        asset_id: Uuid,
+        
+        /// Embedding used for comparison
+        #[arg(long, value_enum, default_value_t = SimilarityKind::Visual)]
+        kind: SimilarityKind,
+        
+        /// Maximum number of results
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+        
+        /// Minimum similarity (0-1), overriding the index configuration
+        #[arg(long)]
+        min_similarity: Option<f32>,
+        
+        /// Diversity (0-1) of visual or text results, overriding the index
+        /// configuration; higher values skip near-duplicates
+        #[arg(long)]
+        diversity: Option<f32>,
+    },
+    
+    /// Run AI tagging on an indexed asset and store the results
+    Tag {
+        /// Asset ID to tag
+        asset_id: Uuid,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum SimilarityKind {
+    Visual,
+    Text,
+    /// Visual and text similarity blended by the configured weights
+    Combined,
+}
+
+impl SimilarityKind {
+    /// Single embedding type compared, or None for the combined ranking
+    fn embedding_type(self) -> Option<EmbeddingType> {
+        match self {
+            SimilarityKind::Visual => Some(EmbeddingType::Visual),
+            SimilarityKind::Text => Some(EmbeddingType::Text),
+            SimilarityKind::Combined => None,
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Logs go to stderr so stdout stays clean for scripting
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("warn")),
+        )
+        .with_writer(std::io::stderr)
+        .init();
+    
+    let cli = Cli::parse();
+    
+    let index_dir = cli.data_dir.join("index");
+    let mut index = IndexService::with_storage_dir(&index_dir)
+        .with_context(|| format!("Failed to open index at {}", index_dir.display()))?;
+    let import_log = Arc::new(ImportLog::new(cli.data_dir.join("imports.jsonl")));
+    
+    match cli.command {
+        Command::Ingest { paths, verify, resume: false } => ingest(index, paths, verify, import_log, cli.json).await,
+        Command::Ingest { paths, verify, resume: true } => resume_ingest(index, paths, verify, import_log, cli.json).await,
+        Command::Search { query, limit } => {
+            // Results are printed as they load, best first
+            let mut results = index.search_text_stream(&query, limit).await?;
+            let mut printed = 0;
+            while let Some(result) = results.next().await {
+                print_result(&result, cli.json);
+                printed += 1;
+            }
+            if !cli.json && printed == 0 {
+                println!("No results");
+            }
+            Ok(())
+        }
+        Command::Stats => {
+            let stats = index.get_stats();
+            if cli.json {
+                println!("{}", serde_json::to_string_pretty(&stats)?);
+            } else {
+                println!("Documents:         {}", stats.total_documents);
+                println!("Terms:             {}", stats.total_terms);
+                println!("Avg terms/doc:     {:.1}", stats.avg_terms_per_doc);
+                println!("Visual embeddings: {}", stats.visual_embeddings);
+                println!("Text embeddings:   {}", stats.text_embeddings);
+            }
+            Ok(())
+        }
+        Command::Redetect => redetect(&mut index, cli.json).await,
+        Command::Imports { since } => {
+            let entries = import_log.entries_since(since.unwrap_or(DateTime::<Utc>::MIN_UTC)).await?;
+            if cli.json {
+                println!("{}", serde_json::to_string_pretty(&entries)?);
+            } else {
+                for entry in entries {
+                    let asset = entry.asset_id.map(|id| id.to_string()).unwrap_or_else(|| "-".to_string());
+                    println!(
+                        "{}  {:?}  {}  {}  {:?}",
+                        entry.timestamp.to_rfc3339(),
+                        entry.operation,
+                        asset,
+                        entry.source_path.display(),
+                        entry.outcome,
+                    );
+                }
+            }
+            Ok(())
+        }
+        Command::Similar { asset_id, kind, limit, min_similarity, diversity } => {
+            let results = match kind.embedding_type() {
+                Some(embedding_type) => {
+                    let diversity = diversity.unwrap_or(index.config().similarity_diversity);
+                    index.find_similar_with_diversity(asset_id, embedding_type, limit, min_similarity, diversity).await?
+                }
+                None => index.find_similar_combined(asset_id, limit, min_similarity).await?,
+            };
+            print_results(&results, cli.json)
+        }
+        Command::Tag { asset_id } => tag(&mut index, asset_id, cli.json).await,
+    }
+}
+
+/// Ingest each path (recursing into directories) and index the assets
+#[cfg(feature = "ai")]
+async fn ingest(index: IndexService, paths: Vec<PathBuf>, verify: bool, import_log: Arc<ImportLog>, json: bool) -> Result<()> {
+    let service = IngestService::new()?
+        .with_integrity_check(verify)
+        .with_import_log(import_log);
+    let index = SharedIndex::new(index);
+    let processing = process::ProcessingService::new()?;
+    let report = processing.import_and_process(
+        &service,
+        &index,
+        &paths,
+        &process::ImportOptions::without_ai(),
+        |_| {},
+        &AtomicBool::new(false),
+    ).await?;
+    
+    for asset_id in &report.indexed {
+        if let Some(document) = index.read().await.get_asset_document(*asset_id)? {
+            print_imported(document.asset_id, &document.file_path, &document.asset_type, &document.integrity, json);
+        }
+    }
+    for failure in &report.failures {
+        warn!("Failed to import {} ({:?}): {}", failure.path.display(), failure.stage, failure.error);
+    }
+    
+    if !report.failures.is_empty() {
+        bail!("{} path(s) could not be imported", report.failures.len());
+    }
+    
+    Ok(())
+}
+
+/// Ingest each path (recursing into directories) and index the assets
+#[cfg(not(feature = "ai"))]
+async fn ingest(mut index: IndexService, paths: Vec<PathBuf>, verify: bool, import_log: Arc<ImportLog>, json: bool) -> Result<()> {
+    let service = IngestService::new()?
+        .with_integrity_check(verify)
+        .with_import_log(import_log);
+    let mut failures = 0;
+    
+    for path in paths {
+        let (operation, assets) = if path.is_dir() {
+            let report = service.ingest_directory_report(&path).await?;
+            for location in &report.inaccessible {
+                warn!("Cannot read {} ({:?}): {}", location.path.display(), location.kind, location.message);
+            }
+            (ingest::ImportOperation::Directory, report.assets)
+        } else {
+            (ingest::ImportOperation::File, vec![service.ingest_file(&path).await?])
+        };
+        
+        for asset in assets {
+            let stored = index.index_asset(&asset).await;
+            service.record_stored(operation, &asset, &stored).await;
+            match stored {
+                Ok(()) => print_imported(asset.id, &asset.current_path, &asset.asset_type, &asset.integrity, json),
+                Err(e) => {
+                    warn!("Failed to index {}: {}", asset.current_path.display(), e);
+                    failures += 1;
+                }
+            }
+        }
+    }
+    
+    if failures > 0 {
+        bail!("{} asset(s) could not be indexed", failures);
+    }
+    
+    Ok(())
+}
+
+/// Ingest directories, skipping files an earlier run already indexed
+/// 
+/// Progress is saved after every batch, so an interrupted run continues
+/// where it stopped; files that failed are retried.
+async fn resume_ingest(index: IndexService, paths: Vec<PathBuf>, verify: bool, import_log: Arc<ImportLog>, json: bool) -> Result<()> {
+    let service = IngestService::new()?
+        .with_integrity_check(verify)
+        .with_import_log(import_log);
+    let index = SharedIndex::new(index);
+    let cancel = AtomicBool::new(false);
+    let mut failures = 0;
+    
+    for path in paths {
+        if !path.is_dir() {
+            bail!("--resume only applies to directories: {}", path.display());
+        }
+        let report = service.resume_directory(&path, &cancel, |assets| {
+            let index = index.clone();
+            async move {
+                let mut stored = Vec::new();
+                for asset in assets {
+                    match index.index_asset(&asset).await {
+                        Ok(()) => stored.push(asset),
+                        Err(e) => warn!("Failed to index {}: {}", asset.current_path.display(), e),
+                    }
+                }
+                stored
+            }
+        }).await?;
+        
+        for asset in &report.assets {
+            print_imported(asset.id, &asset.current_path, &asset.asset_type, &asset.integrity, json);
+        }
+        for location in &report.inaccessible {
+            warn!("Cannot read {} ({:?}): {}", location.path.display(), location.kind, location.message);
+        }
+        failures += report.failed;
+    }
+    
+    if failures > 0 {
+        bail!("{} file(s) could not be imported; run again to retry them", failures);
+    }
+    
+    Ok(())
+}
+
+/// Print an imported asset, flagging suspect files
+fn print_imported(asset_id: Uuid, path: &Path, asset_type: &AssetType, integrity: &IntegrityStatus, json: bool) {
+    if json {
+        println!("{}", serde_json::json!({
+            "asset_id": asset_id,
+            "path": path,
+            "asset_type": asset_type,
+            "integrity": integrity,
+        }));
+    } else if integrity.is_suspect() {
+        println!("{}  {}  ({:?})", asset_id, path.display(), integrity);
+    } else {
+        println!("{}  {}", asset_id, path.display());
+    }
+}
+
+/// Upgrade unknown and unsupported assets that the current detectors recognize
+async fn redetect(index: &mut IndexService, json: bool) -> Result<()> {
+    let service = IngestService::new()?;
+    let report = index.redetect_unknowns(|document| {
+        let service = &service;
+        async move {
+            let mut asset = Asset::new(document.file_path, document.asset_type);
+            asset.id = document.asset_id;
+            if let Some(original_path) = document.original_path {
+                asset.original_path = original_path;
+            }
+            asset.format.supported = document.format_supported;
+            asset.created_at = document.created_at;
+            asset.tags = document.tags;
+            service.upgrade_asset(&asset).await
+        }
+    }).await?;
+    
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        for (asset_id, reason) in &report.failed {
+            println!("{}  failed: {}", asset_id, reason);
+        }
+        println!("Upgraded {} of {} unknown or unsupported assets", report.upgraded.len(), report.checked);
+    }
+    
+    Ok(())
+}
+
+/// Tag an indexed image with the AI tagging service
+#[cfg(feature = "ai")]
+async fn tag(index: &mut IndexService, asset_id: Uuid, json: bool) -> Result<()> {
+    let document = index.get_asset_document(asset_id)?
+        .with_context(|| format!("Asset not found: {}", asset_id))?;
+    
+    let tagging = process::TaggingService::new()?;
+    let result = tagging.tag_image(&document.file_path).await?;
+    let tags: Vec<String> = result.tags.iter().map(|(tag, _)| tag.clone()).collect();
+    
+    index.update_with_ai_results(
+        asset_id,
+        Some(tags.clone()),
+        result.caption.clone(),
+        None,
+        Some(result.embedding.clone()),
+        None,
+    ).await?;
+    
+    if json {
+        println!("{}", serde_json::json!({
+            "asset_id": asset_id,
+            "tags": result.tags,
+            "caption": result.caption,
+        }));
+    } else {
+        println!("{}", tags.join(", "));
+    }
+    
+    Ok(())
+}
+
+/// Tagging requires the `ai` feature
+#[cfg(not(feature = "ai"))]
+async fn tag(_index: &mut IndexService, _asset_id: Uuid, _json: bool) -> Result<()> {
+    bail!("AI tagging is not available in this build; rebuild with `--features ai`")
+}
+
+/// Print search results as a table or JSON lines
+fn print_results(results: &[SearchResult], json: bool) -> Result<()> {
+    for result in results {
+        print_result(result, json);
+    }
+    
+    if !json && results.is_empty() {
+        println!("No results");
+    }
+    
+    Ok(())
+}
+
+/// Print one search result as a table row or a JSON line
+fn print_result(result: &SearchResult, json: bool) {
+    if json {
+        println!("{}", serde_json::json!({
+            "asset_id": result.document.asset_id,
+            "path": result.document.file_path,
+            "score": result.score,
+            "match_reason": result.match_reason,
+        }));
+    } else {
+        println!(
+            "{:.3}  {}  {}",
+            result.score,
+            result.document.asset_id,
+            result.document.file_path.display()
+        );
+    }
+}
@@ -0,0 +1,118 @@
+//! Cache of recent search results
+//!
+//! A server answering the same popular searches over and over would scan
+//! the text index and load every matching document from sled each time.
+//! `QueryCache` keeps the result sets of recent queries, keyed by the
+//! normalized query and its parameters, and evicts the least recently used
+//! entry when full. It knows nothing about the index; `IndexService` clears
+//! it whenever a document or the configuration changes, so a cached result
+//! is never older than the last write.
+
+use crate::document::SearchResult;
+use std::collections::HashMap;
+
+/// Size-bounded LRU cache of search result sets
+#[derive(Debug)]
+pub struct QueryCache {
+    capacity: usize,
+    /// Result set and last use of each query
+    entries: HashMap<String, (u64, Vec<SearchResult>)>,
+    /// Increases on every access, ordering entries by last use
+    clock: u64,
+}
+
+impl QueryCache {
+    /// Cache holding up to `capacity` result sets; 0 disables it
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            clock: 0,
+        }
+    }
+
+    /// Whether results are cached at all
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Number of cached result sets
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether nothing is cached
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Cached results of a query, marking it as recently used
+    pub fn get(&mut self, key: &str) -> Option<Vec<SearchResult>> {
+        self.clock += 1;
+        let clock = self.clock;
+        self.entries.get_mut(key).map(|(last_used, results)| {
+            *last_used = clock;
+            results.clone()
+        })
+    }
+
+    /// Cache the results of a query, evicting the least recently used entry if full
+    pub fn insert(&mut self, key: String, results: Vec<SearchResult>) {
+        if !self.is_enabled() {
+            return;
+        }
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            let oldest = self.entries.iter()
+                .min_by_key(|(_, (last_used, _))| *last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.clock += 1;
+        self.entries.insert(key, (self.clock, results));
+    }
+
+    /// Drop every cached result
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Change the capacity, dropping every cached result
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.clear();
+    }
+}
+
+/// Cache key of a query: kind, whitespace-normalized text and parameters
+pub fn cache_key(kind: &str, text: &str, params: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    format!("{}\u{0}{}\u{0}{}", kind, text, params)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lru_eviction() {
+        let mut cache = QueryCache::new(2);
+        cache.insert(cache_key("text", "red  car", "10"), Vec::new());
+        cache.insert(cache_key("text", "boat", "10"), Vec::new());
+
+        // Using an entry protects it from eviction
+        assert!(cache.get(&cache_key("text", " red car ", "10")).is_some());
+        cache.insert(cache_key("text", "plane", "10"), Vec::new());
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&cache_key("text", "boat", "10")).is_none());
+        assert!(cache.get(&cache_key("text", "red car", "10")).is_some());
+
+        // Other parameters are other entries
+        assert!(cache.get(&cache_key("text", "red car", "20")).is_none());
+
+        let mut disabled = QueryCache::new(0);
+        disabled.insert(cache_key("text", "boat", "10"), Vec::new());
+        assert!(disabled.is_empty());
+    }
+}
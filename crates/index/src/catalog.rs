@@ -0,0 +1,386 @@
+//! Catalog export and import
+//!
+//! Dumps indexed documents as JSON lines or CSV for reporting and
+//! migration, and rebuilds documents from such a dump. Embeddings are not
+//! part of the catalog; they are regenerated by AI processing.
+
+use crate::document::AssetDocument;
+use crate::error::IndexError;
+use chrono::{DateTime, Utc};
+use schema::{Asset, AssetType, DamResult};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+use uuid::Uuid;
+
+/// Column order for CSV catalogs
+const CSV_HEADER: [&str; 11] = [
+    "id", "path", "asset_type", "file_size", "tags", "ai_tags",
+    "caption", "width", "height", "created_at", "modified_at",
+];
+
+/// Separator used for tag lists inside a single CSV field
+const TAG_SEPARATOR: char = ';';
+
+/// Serialization format for catalog export/import
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExportFormat {
+    /// One JSON object per line
+    JsonLines,
+    /// Comma-separated values with a header row
+    Csv,
+}
+
+/// A single catalog entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CatalogRecord {
+    /// Asset ID
+    pub id: Uuid,
+    pub path: PathBuf,
+    pub asset_type: AssetType,
+    pub file_size: u64,
+    pub tags: Vec<String>,
+    pub ai_tags: Vec<String>,
+    pub caption: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub created_at: DateTime<Utc>,
+    pub modified_at: DateTime<Utc>,
+}
+
+impl CatalogRecord {
+    /// Build a record from an indexed document
+    pub fn from_document(document: &AssetDocument) -> Self {
+        Self {
+            id: document.asset_id,
+            path: document.file_path.clone(),
+            asset_type: document.asset_type.clone(),
+            file_size: document.file_size,
+            tags: document.tags.clone(),
+            ai_tags: document.ai_tags.clone(),
+            caption: document.ai_caption.clone(),
+            width: document.dimensions.map(|(w, _)| w),
+            height: document.dimensions.map(|(_, h)| h),
+            created_at: document.created_at,
+            modified_at: document.modified_at,
+        }
+    }
+    
+    /// Rebuild a searchable document (without embeddings)
+    pub fn into_document(self) -> AssetDocument {
+        let mut asset = Asset::new(self.path, self.asset_type);
+        asset.id = self.id;
+        asset.file_size = self.file_size;
+        asset.tags = self.tags;
+        asset.created_at = self.created_at;
+        asset.modified_at = self.modified_at;
+        
+        let mut document = AssetDocument::from_asset(&asset);
+        document.ai_tags = self.ai_tags;
+        document.ai_caption = self.caption;
+        document.dimensions = self.width.zip(self.height);
+        // The catalog does not record format support; known types are taken as supported
+        document.format_supported = document.asset_type != AssetType::Unknown;
+        document.mark_existing_results_done();
+        document.update_search_text();
+        document.calculate_quality_score();
+        document
+    }
+    
+    /// Update an existing document of the same asset with the record
+    /// 
+    /// Fields the catalog does not carry, such as the transcription,
+    /// extracted text, description, previews, embeddings and user
+    /// metadata, are kept from the document.
+    pub fn merge_into(self, mut document: AssetDocument) -> AssetDocument {
+        let record = self.into_document();
+        document.file_path = record.file_path;
+        document.filename = record.filename;
+        document.title = record.title;
+        document.asset_type = record.asset_type;
+        document.file_size = record.file_size;
+        document.tags = record.tags;
+        document.created_at = record.created_at;
+        document.modified_at = record.modified_at;
+        document.ai_tags = record.ai_tags;
+        document.ai_caption = record.ai_caption;
+        document.dimensions = record.dimensions.or(document.dimensions);
+        document.mark_existing_results_done();
+        document.update_search_text();
+        document.calculate_quality_score();
+        document
+    }
+    
+    /// Fields in `CSV_HEADER` order
+    fn to_csv_fields(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.path.to_string_lossy().to_string(),
+            format!("{:?}", self.asset_type),
+            self.file_size.to_string(),
+            join_tags(&self.tags),
+            join_tags(&self.ai_tags),
+            self.caption.clone().unwrap_or_default(),
+            self.width.map(|w| w.to_string()).unwrap_or_default(),
+            self.height.map(|h| h.to_string()).unwrap_or_default(),
+            self.created_at.to_rfc3339(),
+            self.modified_at.to_rfc3339(),
+        ]
+    }
+    
+    /// Parse a CSV row in `CSV_HEADER` order
+    fn from_csv_fields(fields: &[String]) -> Result<Self, String> {
+        if fields.len() != CSV_HEADER.len() {
+            return Err(format!("expected {} fields, found {}", CSV_HEADER.len(), fields.len()));
+        }
+        
+        let optional_u32 = |value: &str, name: &str| -> Result<Option<u32>, String> {
+            if value.is_empty() {
+                Ok(None)
+            } else {
+                value.parse().map(Some).map_err(|_| format!("invalid {}: {}", name, value))
+            }
+        };
+        let timestamp = |value: &str, name: &str| -> Result<DateTime<Utc>, String> {
+            DateTime::parse_from_rfc3339(value)
+                .map(|t| t.with_timezone(&Utc))
+                .map_err(|_| format!("invalid {}: {}", name, value))
+        };
+        
+        Ok(Self {
+            id: fields[0].parse().map_err(|_| format!("invalid id: {}", fields[0]))?,
+            path: PathBuf::from(&fields[1]),
+            asset_type: parse_asset_type(&fields[2])?,
+            file_size: fields[3].parse().map_err(|_| format!("invalid file_size: {}", fields[3]))?,
+            tags: split_tags(&fields[4]),
+            ai_tags: split_tags(&fields[5]),
+            caption: Some(fields[6].clone()).filter(|c| !c.is_empty()),
+            width: optional_u32(&fields[7], "width")?,
+            height: optional_u32(&fields[8], "height")?,
+            created_at: timestamp(&fields[9], "created_at")?,
+            modified_at: timestamp(&fields[10], "modified_at")?,
+        })
+    }
+}
+
+/// Write records in the given format
+pub fn write_catalog<W: Write>(
+    records: impl IntoIterator<Item = CatalogRecord>,
+    format: ExportFormat,
+    mut writer: W,
+) -> DamResult<usize> {
+    let mut count = 0;
+    
+    if format == ExportFormat::Csv {
+        let header: Vec<String> = CSV_HEADER.iter().map(|s| s.to_string()).collect();
+        write_csv_row(&mut writer, &header)?;
+    }
+    
+    for record in records {
+        match format {
+            ExportFormat::JsonLines => {
+                serde_json::to_writer(&mut writer, &record)?;
+                writer.write_all(b"\n")?;
+            }
+            ExportFormat::Csv => write_csv_row(&mut writer, &record.to_csv_fields())?,
+        }
+        count += 1;
+    }
+    
+    writer.flush()?;
+    Ok(count)
+}
+
+/// Read records in the given format
+pub fn read_catalog<R: BufRead>(format: ExportFormat, mut reader: R) -> DamResult<Vec<CatalogRecord>> {
+    match format {
+        ExportFormat::JsonLines => {
+            let mut records = Vec::new();
+            for (number, line) in reader.lines().enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let record = serde_json::from_str(&line).map_err(|e| {
+                    IndexError::SerializationError(format!("Catalog line {}: {}", number + 1, e))
+                })?;
+                records.push(record);
+            }
+            Ok(records)
+        }
+        ExportFormat::Csv => {
+            let mut content = String::new();
+            reader.read_to_string(&mut content)?;
+            
+            let mut rows = parse_csv(&content)
+                .map_err(|e| IndexError::SerializationError(format!("Invalid catalog CSV: {}", e)))?
+                .into_iter();
+            
+            match rows.next() {
+                Some(header) if header == CSV_HEADER => {}
+                _ => return Err(IndexError::SerializationError(
+                    "Catalog CSV is missing the expected header row".to_string()
+                ).into()),
+            }
+            
+            rows.enumerate()
+                .map(|(number, fields)| {
+                    CatalogRecord::from_csv_fields(&fields).map_err(|e| {
+                        IndexError::SerializationError(format!("Catalog row {}: {}", number + 1, e)).into()
+                    })
+                })
+                .collect()
+        }
+    }
+}
+
+/// Parse an asset type from its `Debug` name
+fn parse_asset_type(value: &str) -> Result<AssetType, String> {
+    match value {
+        "Image" => Ok(AssetType::Image),
+        "ThreeD" => Ok(AssetType::ThreeD),
+        "Audio" => Ok(AssetType::Audio),
+        "Video" => Ok(AssetType::Video),
+        "Document" => Ok(AssetType::Document),
+        "Archive" => Ok(AssetType::Archive),
+        "Unknown" => Ok(AssetType::Unknown),
+        other => Err(format!("invalid asset_type: {}", other)),
+    }
+}
+
+/// Join tags with `TAG_SEPARATOR`, backslash-escaping separators inside tags
+fn join_tags(tags: &[String]) -> String {
+    tags.iter()
+        .map(|tag| tag.replace('\\', "\\\\").replace(TAG_SEPARATOR, "\\;"))
+        .collect::<Vec<_>>()
+        .join(&TAG_SEPARATOR.to_string())
+}
+
+/// Inverse of `join_tags`
+fn split_tags(value: &str) -> Vec<String> {
+    let mut tags = Vec::new();
+    let mut current = String::new();
+    let mut chars = value.chars();
+    
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => current.extend(chars.next()),
+            TAG_SEPARATOR => tags.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    
+    if !current.is_empty() || !tags.is_empty() {
+        tags.push(current);
+    }
+    tags
+}
+
+/// Write one CSV row, quoting fields that need it
+fn write_csv_row<W: Write>(writer: &mut W, fields: &[String]) -> std::io::Result<()> {
+    let line = fields.iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    writeln!(writer, "{}", line)
+}
+
+/// Parse RFC 4180 CSV, allowing quoted fields with embedded newlines
+fn parse_csv(content: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = content.chars().peekable();
+    
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+    
+    if in_quotes {
+        return Err("unterminated quoted field".to_string());
+    }
+    
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn sample_record() -> CatalogRecord {
+        CatalogRecord {
+            id: Uuid::new_v4(),
+            path: PathBuf::from("/library/beach, sunset.jpg"),
+            asset_type: AssetType::Image,
+            file_size: 2048,
+            tags: vec!["summer".to_string(), "a;b".to_string()],
+            ai_tags: vec!["ocean".to_string()],
+            caption: Some("A \"golden\" sunset,\nover the sea".to_string()),
+            width: Some(1920),
+            height: Some(1080),
+            created_at: Utc::now(),
+            modified_at: Utc::now(),
+        }
+    }
+    
+    #[test]
+    fn test_csv_round_trip_with_special_characters() {
+        let record = sample_record();
+        let mut buffer = Vec::new();
+        write_catalog(vec![record.clone()], ExportFormat::Csv, &mut buffer).unwrap();
+        
+        let records = read_catalog(ExportFormat::Csv, buffer.as_slice()).unwrap();
+        assert_eq!(records, vec![record]);
+    }
+    
+    #[test]
+    fn test_json_lines_round_trip() {
+        let records = vec![sample_record(), sample_record()];
+        let mut buffer = Vec::new();
+        let count = write_catalog(records.clone(), ExportFormat::JsonLines, &mut buffer).unwrap();
+        
+        assert_eq!(count, 2);
+        assert_eq!(String::from_utf8_lossy(&buffer).lines().count(), 2);
+        assert_eq!(read_catalog(ExportFormat::JsonLines, buffer.as_slice()).unwrap(), records);
+    }
+    
+    #[test]
+    fn test_tag_escaping() {
+        let tags = vec!["plain".to_string(), "semi;colon".to_string(), "back\\slash".to_string()];
+        assert_eq!(split_tags(&join_tags(&tags)), tags);
+        assert!(split_tags("").is_empty());
+    }
+}
@@ -3,9 +3,35 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
-use schema::{Asset, AssetType};
-use std::path::PathBuf;
+use schema::{Asset, AssetType, DamError, DamResult, DetectionMethod, IntegrityStatus, ProcessingStatus, SearchQuery, StepStatus, Waveform};
+use std::path::{Path, PathBuf};
 use std::collections::HashMap;
+use crate::vector::{DistanceMetric, EvictionPolicy};
+use crate::language::TextLanguage;
+
+/// Current layout version of stored `AssetDocument`s
+/// 
+/// Bump this when a field is added or the way a derived field is computed
+/// changes, and teach `AssetDocument::migrate` to bring older documents up
+/// to date. New fields must be `#[serde(default)]` so older documents still
+/// deserialize.
+/// 
+/// - 0: documents stored before versioning was introduced
+/// - 1: adds `schema_version`
+/// - 2: adds `rating` and `favorite`
+/// - 3: adds `text_embedding_chunks`
+/// - 4: adds `captured_at`
+/// - 5: adds `waveform`
+/// - 6: adds `processing_status`
+/// - 7: adds `integrity`
+/// - 8: fills `metadata` with custom metadata and indexes its values
+/// - 9: adds `transcription_segments`
+/// - 10: adds `needs_deep_processing`
+/// - 11: adds `contact_sheet_path` and `contact_sheet_grid`
+/// - 12: adds `user_metadata`; `metadata` only holds values read from the file
+/// - 13: adds `original_path` and `format_supported`
+/// - 14: adds `detection_method` and `format_confidence`
+pub const DOCUMENT_SCHEMA_VERSION: u32 = 14;
 
 /// A searchable document representing an indexed asset
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,12 +44,29 @@ pub struct AssetDocument {
     pub file_path: PathBuf,
     pub filename: String,
     pub asset_type: AssetType,
+    /// Where the file was first ingested from; None for documents stored
+    /// before it was recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_path: Option<PathBuf>,
+    /// Whether the detected format is fully supported
+    #[serde(default)]
+    pub format_supported: bool,
+    /// How the format was detected; `Unknown` for documents stored before
+    /// it was recorded
+    #[serde(default)]
+    pub detection_method: DetectionMethod,
+    /// Confidence in the detected format, from 0 to 1
+    #[serde(default)]
+    pub format_confidence: f32,
     
     /// File metadata
     pub file_size: u64,
     pub created_at: DateTime<Utc>,
     pub modified_at: DateTime<Utc>,
     pub indexed_at: DateTime<Utc>,
+    /// When the content was captured (EXIF `DateTimeOriginal`), if known
+    #[serde(default)]
+    pub captured_at: Option<DateTime<Utc>>,
     
     /// Searchable text content
     pub title: String,
@@ -31,6 +74,16 @@ pub struct AssetDocument {
     pub tags: Vec<String>,
     pub transcription: Option<String>,
     pub extracted_text: Option<String>,
+    /// Timed segments of the transcription, with speakers when diarized;
+    /// `transcription` is then their text joined by spaces
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transcription_segments: Vec<TranscriptionSegment>,
+    
+    /// User curation, updated without reindexing text
+    #[serde(default)]
+    pub rating: Option<u8>,
+    #[serde(default)]
+    pub favorite: bool,
     
     /// Visual/audio analysis results
     pub ai_tags: Vec<String>,
@@ -46,17 +99,50 @@ pub struct AssetDocument {
     /// Preview information
     pub preview_path: Option<PathBuf>,
     pub thumbnail_path: Option<PathBuf>,
+    /// Audio peaks computed with the waveform preview
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub waveform: Option<Waveform>,
+    /// Contact sheet of a video and its `(rows, columns)`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contact_sheet_path: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contact_sheet_grid: Option<(u32, u32)>,
     
     /// Vector embeddings for similarity search
     pub visual_embedding: Option<Vec<f32>>,
     pub text_embedding: Option<Vec<f32>>,
+    /// Embeddings of overlapping windows of long text; `text_embedding`
+    /// then holds their mean
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub text_embedding_chunks: Vec<Vec<f32>>,
+    
+    /// Which AI steps have run, are running or failed
+    #[serde(default)]
+    pub processing_status: ProcessingStatus,
+    
+    /// Outcome of the ingest integrity check
+    #[serde(default)]
+    pub integrity: IntegrityStatus,
     
-    /// Additional metadata
+    /// Fast-imported without metadata; the deep pass has not finished yet
+    #[serde(default)]
+    pub needs_deep_processing: bool,
+    
+    /// Custom key/value metadata from parsers and sidecars, rebuilt from
+    /// the file on every re-index
     pub metadata: HashMap<String, String>,
     
+    /// Custom key/value metadata set by users, overriding file values
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub user_metadata: HashMap<String, String>,
+    
     /// Search optimization
     pub search_text: String, // Combined searchable text
     pub quality_score: f32,  // For ranking
+    
+    /// Layout version this document was stored with
+    #[serde(default)]
+    pub schema_version: u32,
 }
 
 impl AssetDocument {
@@ -75,15 +161,24 @@ impl AssetDocument {
             file_path: asset.current_path.clone(),
             filename: filename.clone(),
             asset_type: asset.asset_type.clone(),
+            original_path: Some(asset.original_path.clone()),
+            format_supported: asset.format.supported,
+            detection_method: asset.format.detection_method,
+            format_confidence: asset.format.confidence,
             file_size: asset.file_size,
             created_at: asset.created_at,
             modified_at: asset.modified_at,
             indexed_at: now,
+            captured_at: crate::timeline::capture_date(&asset.metadata.custom),
             title: filename.clone(),
             description: None,
             tags: asset.tags.clone(),
             transcription: asset.metadata.audio.as_ref().and_then(|a| a.transcription.clone()),
-            extracted_text: None,
+            extracted_text: asset.metadata.document.as_ref().map(|d| d.extracted_text.clone())
+                .or_else(|| asset.metadata.archive.as_ref().map(archive_search_text)),
+            transcription_segments: Vec::new(),
+            rating: asset.rating,
+            favorite: asset.favorite,
             ai_tags: Vec::new(),
             ai_caption: None,
             dominant_colors: Vec::new(),
@@ -94,18 +189,87 @@ impl AssetDocument {
             frame_rate: asset.metadata.video.as_ref().map(|v| v.fps),
             preview_path: asset.preview.as_ref().map(|p| p.thumbnail_path.clone()),
             thumbnail_path: asset.preview.as_ref().map(|p| p.thumbnail_path.clone()),
+            waveform: asset.preview.as_ref().and_then(|p| p.waveform.clone()),
+            contact_sheet_path: asset.preview.as_ref()
+                .filter(|p| p.contact_sheet_grid.is_some())
+                .and_then(|p| p.rendered_preview.clone()),
+            contact_sheet_grid: asset.preview.as_ref().and_then(|p| p.contact_sheet_grid),
             visual_embedding: asset.embedding.clone(),
             text_embedding: None,
-            metadata: HashMap::new(),
+            text_embedding_chunks: Vec::new(),
+            processing_status: ProcessingStatus::default(),
+            integrity: asset.integrity.clone(),
+            needs_deep_processing: asset.needs_deep_processing,
+            metadata: asset.metadata.custom.clone(),
+            user_metadata: HashMap::new(),
             search_text: String::new(),
             quality_score: 1.0,
+            schema_version: DOCUMENT_SCHEMA_VERSION,
         };
         
         // Build search text from available fields
         doc.update_search_text();
+        doc.mark_existing_results_done();
         doc
     }
     
+    /// Bring a document stored by an older version up to date
+    /// 
+    /// Re-derives computed fields from the stored data and returns whether
+    /// anything changed, so the caller knows to write the document back.
+    pub fn migrate(&mut self) -> bool {
+        if self.schema_version >= DOCUMENT_SCHEMA_VERSION {
+            return false;
+        }
+        
+        // Earlier layouts mixed user edits into `metadata`; keep them all
+        // as user values rather than lose the edits on the next re-index
+        if self.schema_version < 12 && self.user_metadata.is_empty() {
+            self.user_metadata = self.metadata.clone();
+        }
+        // Support was not recorded; re-detection still checks `Unknown` assets
+        if self.schema_version < 13 {
+            self.format_supported = true;
+        }
+        self.update_search_text();
+        self.calculate_quality_score();
+        self.mark_existing_results_done();
+        self.schema_version = DOCUMENT_SCHEMA_VERSION;
+        true
+    }
+    
+    /// Raw text of an indexed field, as fed to the text index
+    pub fn field_text(&self, field: &str) -> Option<String> {
+        match field {
+            "filename" => Some(self.filename.clone()),
+            "title" => Some(self.title.clone()),
+            "tags" => Some(self.tags.join(" ")),
+            "ai_tags" => Some(self.ai_tags.join(" ")),
+            "description" => self.description.clone(),
+            "transcription" => self.transcription.clone(),
+            "ai_caption" => self.ai_caption.clone(),
+            "extracted_text" => self.extracted_text.clone(),
+            "asset_type" => Some(format!("{:?}", self.asset_type).to_lowercase()),
+            "custom" => Some(self.custom_text()).filter(|text| !text.is_empty()),
+            _ => None,
+        }
+    }
+    
+    /// Custom metadata from the file with the user's values applied
+    pub fn custom_metadata(&self) -> HashMap<String, String> {
+        let mut custom = self.metadata.clone();
+        custom.extend(self.user_metadata.iter().map(|(key, value)| (key.clone(), value.clone())));
+        custom
+    }
+    
+    /// Custom metadata values, ordered by key so the text is stable
+    fn custom_text(&self) -> String {
+        let custom = self.custom_metadata();
+        let mut entries: Vec<(&String, &String)> = custom.iter().collect();
+        entries.sort();
+        entries.into_iter().map(|(_, value)| value.as_str()).collect::<Vec<_>>().join(" ")
+    }
+    
     /// Update the combined search text field
     pub fn update_search_text(&mut self) {
         let mut search_parts = Vec::new();
@@ -136,6 +300,9 @@ impl AssetDocument {
         // Asset type
         search_parts.push(format!("{:?}", self.asset_type).to_lowercase());
         
+        // Custom metadata
+        search_parts.push(self.custom_text());
+        
         // Technical metadata
         if let Some((w, h)) = self.dimensions {
             search_parts.push(format!("{}x{}", w, h));
@@ -161,12 +328,41 @@ impl AssetDocument {
         self.update_search_text();
     }
     
-    /// Set transcription
+    /// Set transcription, dropping segments of an earlier transcription
     pub fn set_transcription(&mut self, transcription: String) {
         self.transcription = Some(transcription);
+        self.transcription_segments.clear();
+        self.update_search_text();
+    }
+    
+    /// Set a segmented transcription; its text becomes `transcription`
+    pub fn set_transcription_segments(&mut self, segments: Vec<TranscriptionSegment>) {
+        let text = segments.iter().map(|segment| segment.text.trim()).collect::<Vec<_>>().join(" ");
+        self.transcription = Some(text);
+        self.transcription_segments = segments;
         self.update_search_text();
     }
     
+    /// Whether any transcription segment is attributed to `speaker`
+    pub fn has_speaker(&self, speaker: &str) -> bool {
+        self.transcription_segments.iter().any(|segment| segment.is_spoken_by(speaker))
+    }
+    
+    /// Whether word `position` of the transcription was said by `speaker`
+    /// 
+    /// Positions count whitespace-separated words, as the text index does.
+    pub fn is_spoken_by(&self, position: usize, speaker: &str) -> bool {
+        let mut start = 0;
+        for segment in &self.transcription_segments {
+            let end = start + segment.text.split_whitespace().count();
+            if position < end {
+                return segment.is_spoken_by(speaker);
+            }
+            start = end;
+        }
+        false
+    }
+    
     /// Set AI caption
     pub fn set_ai_caption(&mut self, caption: String) {
         self.ai_caption = Some(caption);
@@ -181,6 +377,112 @@ impl AssetDocument {
     /// Set text embedding
     pub fn set_text_embedding(&mut self, embedding: Vec<f32>) {
         self.text_embedding = Some(embedding);
+        self.text_embedding_chunks.clear();
+    }
+    
+    /// Set the chunk embeddings of a long text, with their mean as the
+    /// single text embedding
+    pub fn set_text_embedding_chunks(&mut self, chunks: Vec<Vec<f32>>) {
+        self.text_embedding = crate::vector::mean_pool(&chunks);
+        self.text_embedding_chunks = chunks;
+    }
+    
+    /// Keep AI results from a previous version of this document
+    /// 
+    /// Fields already populated on `self` are left untouched.
+    pub fn carry_forward_ai_results(&mut self, previous: AssetDocument) {
+        if self.ai_tags.is_empty() {
+            self.ai_tags = previous.ai_tags;
+        }
+        if self.dominant_colors.is_empty() {
+            self.dominant_colors = previous.dominant_colors;
+        }
+        self.ai_caption = self.ai_caption.take().or(previous.ai_caption);
+        if self.transcription.is_none() {
+            self.transcription = previous.transcription;
+            self.transcription_segments = previous.transcription_segments;
+        }
+        self.visual_embedding = self.visual_embedding.take().or(previous.visual_embedding);
+        if self.text_embedding.is_none() {
+            self.text_embedding = previous.text_embedding;
+            self.text_embedding_chunks = previous.text_embedding_chunks;
+        }
+        self.processing_status = previous.processing_status;
+        self.mark_existing_results_done();
+        self.update_search_text();
+    }
+    
+    /// Whether the document passes the structured filters of a query
+    /// 
+    /// Checks everything except the text and semantic parts: asset type,
+    /// tags (all required, manual or AI), extensions, creation date, file
+    /// size, pixel dimensions, rating, favorites, integrity, custom metadata
+    /// and whether the filtered speaker appears in the transcript.
+    pub fn matches_filters(&self, query: &SearchQuery) -> bool {
+        if query.asset_type.as_ref().is_some_and(|asset_type| *asset_type != self.asset_type) {
+            return false;
+        }
+        
+        let has_tag = |wanted: &String| {
+            self.tags.iter().chain(&self.ai_tags).any(|tag| tag.eq_ignore_ascii_case(wanted))
+        };
+        if !query.tags.iter().all(has_tag) {
+            return false;
+        }
+        
+        if !query.extensions.is_empty() {
+            let extension = Path::new(&self.filename)
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            if !query.extensions.iter().any(|wanted| wanted.trim_start_matches('.').eq_ignore_ascii_case(&extension)) {
+                return false;
+            }
+        }
+        
+        if let Some(range) = &query.date_range {
+            if range.start.is_some_and(|start| self.created_at < start)
+                || range.end.is_some_and(|end| self.created_at > end) {
+                return false;
+            }
+        }
+        
+        if let Some(range) = &query.size_range {
+            if range.min.is_some_and(|min| self.file_size < min)
+                || range.max.is_some_and(|max| self.file_size > max) {
+                return false;
+            }
+        }
+        
+        if let Some(filter) = &query.dimensions {
+            if !self.dimensions.is_some_and(|(width, height)| filter.matches(width, height)) {
+                return false;
+            }
+        }
+        
+        if let Some(min_rating) = query.min_rating {
+            if self.rating.map_or(true, |rating| rating < min_rating) {
+                return false;
+            }
+        }
+        
+        if query.favorites_only && !self.favorite {
+            return false;
+        }
+        
+        if query.suspect_only && !self.integrity.is_suspect() {
+            return false;
+        }
+        
+        if query.speaker.as_ref().is_some_and(|speaker| !self.has_speaker(speaker)) {
+            return false;
+        }
+        
+        if query.custom.is_empty() {
+            return true;
+        }
+        let custom = self.custom_metadata();
+        query.custom.iter().all(|filter| filter.matches(&custom))
     }
     
     /// Calculate quality score based on available metadata
@@ -224,6 +526,34 @@ impl AssetDocument {
         self.quality_score = score;
     }
     
+    /// Check whether the document already has a given AI result
+    pub fn has_ai_result(&self, kind: AiKind) -> bool {
+        match kind {
+            AiKind::VisualEmbedding => self.visual_embedding.is_some(),
+            AiKind::TextEmbedding => self.text_embedding.is_some(),
+            AiKind::Tags => !self.ai_tags.is_empty(),
+            AiKind::Transcription => self.transcription.is_some(),
+        }
+    }
+    
+    /// Mark steps whose results are already present as done
+    /// 
+    /// Covers results that arrived with the asset (e.g. an embedded
+    /// transcription) and documents stored before the status was tracked.
+    pub fn mark_existing_results_done(&mut self) {
+        let status = &mut self.processing_status;
+        let steps = [
+            (&mut status.tagging, !self.ai_tags.is_empty() || self.ai_caption.is_some()),
+            (&mut status.transcription, self.transcription.is_some()),
+            (&mut status.embedding, self.visual_embedding.is_some() || self.text_embedding.is_some()),
+        ];
+        for (step, has_result) in steps {
+            if has_result && *step == StepStatus::NotStarted {
+                *step = StepStatus::Done;
+            }
+        }
+    }
+    
     /// Get all searchable text fields as a vector
     pub fn get_searchable_fields(&self) -> Vec<&str> {
         let mut fields = vec![self.filename.as_str(), self.title.as_str(), self.search_text.as_str()];
@@ -245,15 +575,223 @@ impl AssetDocument {
     }
 }
 
+/// Build searchable text from an archive listing
+/// 
+/// Path separators are replaced by spaces so every file and folder name
+/// inside the archive is indexed as its own term.
+fn archive_search_text(archive: &schema::ArchiveMetadata) -> String {
+    archive.entries.iter()
+        .map(|entry| entry.replace(['/', '\\'], " "))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A timed piece of a transcription
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptionSegment {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub text: String,
+    /// Speaker label from diarization, e.g. "2" or "Host"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<String>,
+}
+
+impl TranscriptionSegment {
+    /// Whether the segment is attributed to `speaker`, ignoring case
+    pub fn is_spoken_by(&self, speaker: &str) -> bool {
+        self.speaker.as_ref().is_some_and(|label| label.eq_ignore_ascii_case(speaker))
+    }
+}
+
+/// Kind of AI processing result stored on a document
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AiKind {
+    /// Visual embedding for similarity search
+    VisualEmbedding,
+    /// Text embedding for semantic search
+    TextEmbedding,
+    /// AI-generated tags
+    Tags,
+    /// Audio transcription
+    Transcription,
+}
+
+impl AiKind {
+    /// Check whether this kind of processing applies to an asset type
+    pub fn applies_to(&self, asset_type: &AssetType) -> bool {
+        match self {
+            AiKind::VisualEmbedding | AiKind::Tags => matches!(asset_type, AssetType::Image | AssetType::Video),
+            AiKind::Transcription => matches!(asset_type, AssetType::Audio | AssetType::Video),
+            AiKind::TextEmbedding => true,
+        }
+    }
+}
+
+/// Fields indexed by the text search, with their default boosts
+pub const DEFAULT_FIELD_WEIGHTS: [(&str, f32); 10] = [
+    ("filename", 2.0),
+    ("title", 1.8),
+    ("tags", 2.5),
+    ("ai_tags", 2.0),
+    ("description", 1.5),
+    ("transcription", 1.8),
+    ("ai_caption", 1.6),
+    ("extracted_text", 1.4),
+    ("asset_type", 1.2),
+    ("custom", 1.5),
+];
+
+/// Per-field boosts applied to text matches at query time
+/// 
+/// Fields without an explicit weight use their default boost, so a
+/// config only needs to list the fields it changes. A weight of 0
+/// excludes the field from matching.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct FieldWeights {
+    weights: HashMap<String, f32>,
+}
+
+impl FieldWeights {
+    /// Weight for a field, falling back to its default boost (1.0 for unknown fields)
+    pub fn get(&self, field: &str) -> f32 {
+        self.weights.get(field).copied().unwrap_or_else(|| {
+            DEFAULT_FIELD_WEIGHTS.iter()
+                .find(|(name, _)| *name == field)
+                .map(|(_, weight)| *weight)
+                .unwrap_or(1.0)
+        })
+    }
+    
+    /// Override the weight of a single field
+    pub fn with(mut self, field: impl Into<String>, weight: f32) -> Self {
+        self.weights.insert(field.into(), weight);
+        self
+    }
+    
+    /// Match only the given fields, keeping their default boosts
+    pub fn only(fields: &[&str]) -> Self {
+        let weights = DEFAULT_FIELD_WEIGHTS.iter()
+            .filter(|(name, _)| !fields.contains(name))
+            .map(|(name, _)| (name.to_string(), 0.0))
+            .collect();
+        Self { weights }
+    }
+    
+    /// Check that all overrides are finite and non-negative
+    pub fn validate(&self) -> DamResult<()> {
+        for (field, weight) in &self.weights {
+            if !weight.is_finite() || *weight < 0.0 {
+                return Err(DamError::configuration(format!(
+                    "Field weight for {} must be non-negative, got {}", field, weight
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Score multipliers per asset type
+/// 
+/// Types without an entry keep a neutral boost of 1.0, so ranking is
+/// unchanged unless a type is explicitly preferred or demoted.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TypeBoosts {
+    boosts: HashMap<AssetType, f32>,
+}
+
+impl TypeBoosts {
+    /// Boost for an asset type (1.0 if not set)
+    pub fn get(&self, asset_type: &AssetType) -> f32 {
+        self.boosts.get(asset_type).copied().unwrap_or(1.0)
+    }
+    
+    /// Set the boost of a single asset type
+    pub fn with(mut self, asset_type: AssetType, boost: f32) -> Self {
+        self.boosts.insert(asset_type, boost);
+        self
+    }
+    
+    /// Whether every type has a boost of 1.0
+    pub fn is_neutral(&self) -> bool {
+        self.boosts.values().all(|&boost| boost == 1.0)
+    }
+    
+    /// Check that all boosts are finite and non-negative
+    pub fn validate(&self) -> DamResult<()> {
+        for (asset_type, boost) in &self.boosts {
+            if !boost.is_finite() || *boost < 0.0 {
+                return Err(DamError::configuration(format!(
+                    "Type boost for {:?} must be non-negative, got {}", asset_type, boost
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Weights of visual and text similarity in combined "more like this"
+/// rankings
+/// 
+/// Only the ratio matters; when the source asset has just one kind of
+/// embedding, that kind alone decides the ranking.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimilarityWeights {
+    pub visual: f32,
+    pub text: f32,
+}
+
+impl Default for SimilarityWeights {
+    fn default() -> Self {
+        Self { visual: 0.6, text: 0.4 }
+    }
+}
+
+impl SimilarityWeights {
+    /// Check that the weights are finite, non-negative and not both zero
+    pub fn validate(&self) -> DamResult<()> {
+        for (name, weight) in [("visual", self.visual), ("text", self.text)] {
+            if !weight.is_finite() || weight < 0.0 {
+                return Err(DamError::configuration(format!(
+                    "Similarity weight {} must be non-negative, got {}", name, weight
+                )));
+            }
+        }
+        if self.visual == 0.0 && self.text == 0.0 {
+            return Err(DamError::configuration("At least one similarity weight must be positive"));
+        }
+        Ok(())
+    }
+}
+
 /// Search index configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct IndexConfig {
-    /// Maximum number of results to return
+    /// Storage directory for the index (defaults to `data/index`)
+    pub storage_dir: Option<PathBuf>,
+    
+    /// Directory of generated previews, whose size is reported in the
+    /// index statistics; not counted when unset
+    pub preview_dir: Option<PathBuf>,
+    
+    /// Hard cap on results returned by any search, regardless of the
+    /// `max_results` requested by the caller
     pub max_results: usize,
     
-    /// Minimum similarity score for vector search
+    /// Minimum similarity score for vector search (cosine metric)
     pub min_similarity: f32,
     
+    /// How embeddings are compared; must match the embedding model
+    pub distance_metric: DistanceMetric,
+    
+    /// Largest distance still considered a match (Euclidean and Manhattan
+    /// metrics); unlimited when unset
+    pub max_distance: Option<f32>,
+    
     /// Weights for different search components
     pub text_weight: f32,
     pub tag_weight: f32,
@@ -264,18 +802,235 @@ pub struct IndexConfig {
     
     /// Minimum query length
     pub min_query_length: usize,
+    
+    /// Minimum length of indexed terms in characters (numbers are always
+    /// indexed); changing it requires a reindex
+    pub min_term_length: usize,
+    
+    /// Per-field text boosts, applied at query time
+    pub field_weights: FieldWeights,
+    
+    /// Per-asset-type score multipliers, e.g. to prefer videos
+    pub type_boosts: TypeBoosts,
+    
+    /// Blend of visual and text similarity for combined similar-asset search
+    pub similarity_weights: SimilarityWeights,
+    
+    /// Trade-off between relevance and variety in similar-asset results,
+    /// in [0, 1]; 0 ranks purely by similarity, higher values push back
+    /// results that look like ones already ranked above them
+    pub similarity_diversity: f32,
+    
+    /// Minimum similarity for cross-modal (text to image) search; text and
+    /// image embeddings of a matching pair score much lower than two
+    /// similar images, so this is well below `min_similarity`
+    pub cross_modal_min_similarity: f32,
+    
+    /// Index runs of CJK/Thai-style scripts (written without spaces) as
+    /// character bigrams; when disabled, such runs stay a single term
+    pub cjk_bigrams: bool,
+    
+    /// Also match AI tags that often occur together with the query terms,
+    /// e.g. `puppy` for `dog`
+    pub query_expansion: bool,
+    
+    /// Most related AI tags added per query term
+    pub expansion_terms: usize,
+    
+    /// Score multiplier for matches on added terms, in [0, 1]
+    pub expansion_weight: f32,
+    
+    /// Language whose stemmer and stop words are applied to indexed text
+    /// and queries; `Auto` picks the dominant language of the library when
+    /// it is loaded. Changing it requires a reindex
+    pub language: TextLanguage,
+    
+    /// Reuse the results of repeated text and structured searches until
+    /// the index changes
+    pub query_cache: bool,
+    
+    /// Most result sets kept by the query cache; least recently used ones
+    /// are evicted first
+    pub query_cache_size: usize,
+    
+    /// Most documents whose embeddings are kept in memory; the rest are
+    /// read back from the database when searched, which is slower but
+    /// bounds memory on large libraries. Unlimited when unset
+    pub max_resident_embeddings: Option<usize>,
+    
+    /// Which documents' embeddings leave memory first once the limit is reached
+    pub eviction_policy: EvictionPolicy,
 }
 
 impl Default for IndexConfig {
     fn default() -> Self {
         Self {
+            storage_dir: None,
+            preview_dir: None,
             max_results: 100,
             min_similarity: 0.7,
+            distance_metric: DistanceMetric::default(),
+            max_distance: None,
             text_weight: 1.0,
             tag_weight: 1.5,
             vector_weight: 0.8,
             fuzzy_matching: true,
             min_query_length: 2,
+            min_term_length: 2,
+            field_weights: FieldWeights::default(),
+            type_boosts: TypeBoosts::default(),
+            similarity_weights: SimilarityWeights::default(),
+            similarity_diversity: 0.0,
+            cross_modal_min_similarity: 0.2,
+            cjk_bigrams: true,
+            query_expansion: false,
+            expansion_terms: 3,
+            expansion_weight: 0.3,
+            language: TextLanguage::default(),
+            query_cache: false,
+            query_cache_size: 256,
+            max_resident_embeddings: None,
+            eviction_policy: EvictionPolicy::default(),
+        }
+    }
+}
+
+impl IndexConfig {
+    /// Load configuration from a TOML or JSON file
+    /// 
+    /// The format is chosen by file extension (`.json` for JSON, anything
+    /// else is parsed as TOML). Missing fields fall back to defaults.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> DamResult<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| DamError::configuration(format!("Failed to read index config {}: {}", path.display(), e)))?;
+        
+        let is_json = path.extension()
+            .map(|ext| ext.eq_ignore_ascii_case("json"))
+            .unwrap_or(false);
+        
+        let config: IndexConfig = if is_json {
+            serde_json::from_str(&content)
+                .map_err(|e| DamError::configuration(format!("Invalid index config {}: {}", path.display(), e)))?
+        } else {
+            toml::from_str(&content)
+                .map_err(|e| DamError::configuration(format!("Invalid index config {}: {}", path.display(), e)))?
+        };
+        
+        config.validate()?;
+        Ok(config)
+    }
+    
+    /// Validate weights and thresholds
+    pub fn validate(&self) -> DamResult<()> {
+        let weights = [
+            ("text_weight", self.text_weight),
+            ("tag_weight", self.tag_weight),
+            ("vector_weight", self.vector_weight),
+        ];
+        
+        for (name, weight) in weights {
+            if !weight.is_finite() || weight < 0.0 {
+                return Err(DamError::configuration(format!(
+                    "{} must be non-negative, got {}", name, weight
+                )));
+            }
+        }
+        
+        self.field_weights.validate()?;
+        self.type_boosts.validate()?;
+        self.similarity_weights.validate()?;
+        
+        if self.min_term_length == 0 {
+            return Err(DamError::configuration("min_term_length must be at least 1"));
+        }
+        
+        if !(0.0..=1.0).contains(&self.expansion_weight) {
+            return Err(DamError::configuration(format!(
+                "expansion_weight must be in [0, 1], got {}", self.expansion_weight
+            )));
+        }
+        
+        if !(0.0..=1.0).contains(&self.similarity_diversity) {
+            return Err(DamError::configuration(format!(
+                "similarity_diversity must be in [0, 1], got {}", self.similarity_diversity
+            )));
+        }
+        
+        if self.max_results == 0 {
+            return Err(DamError::configuration("max_results must be at least 1"));
+        }
+        
+        if self.max_resident_embeddings == Some(0) {
+            return Err(DamError::configuration("max_resident_embeddings must be at least 1 when set"));
+        }
+        
+        if self.query_cache && self.query_cache_size == 0 {
+            return Err(DamError::configuration("query_cache_size must be at least 1 when the query cache is enabled"));
+        }
+        
+        if !(0.0..=1.0).contains(&self.min_similarity) {
+            return Err(DamError::configuration(format!(
+                "min_similarity must be in [0, 1], got {}", self.min_similarity
+            )));
+        }
+        
+        if !(0.0..=1.0).contains(&self.cross_modal_min_similarity) {
+            return Err(DamError::configuration(format!(
+                "cross_modal_min_similarity must be in [0, 1], got {}", self.cross_modal_min_similarity
+            )));
+        }
+        
+        if let Some(max_distance) = self.max_distance {
+            if !max_distance.is_finite() || max_distance < 0.0 {
+                return Err(DamError::configuration(format!(
+                    "max_distance must be non-negative, got {}", max_distance
+                )));
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Lowest vector score accepted as a match, on the scale of the
+    /// configured metric
+    /// 
+    /// This is `min_similarity` for cosine and `-max_distance` for the
+    /// distance metrics.
+    pub fn similarity_threshold(&self) -> f32 {
+        self.similarity_threshold_with(self.min_similarity)
+    }
+    
+    /// Threshold for a query with its own minimum similarity
+    /// 
+    /// The override is validated like `min_similarity`. Similarities are
+    /// not comparable to distances, so under a distance metric, where
+    /// `max_distance` applies, an override is rejected rather than ignored.
+    pub fn query_similarity_threshold(&self, min_similarity: Option<f32>) -> DamResult<f32> {
+        let Some(min_similarity) = min_similarity else {
+            return Ok(self.similarity_threshold());
+        };
+        
+        if self.distance_metric.is_distance() {
+            return Err(DamError::configuration(format!(
+                "min_similarity does not apply to the {:?} metric; set max_distance instead", self.distance_metric
+            )));
+        }
+        
+        if !(0.0..=1.0).contains(&min_similarity) {
+            return Err(DamError::configuration(format!(
+                "min_similarity must be in [0, 1], got {}", min_similarity
+            )));
+        }
+        
+        Ok(self.similarity_threshold_with(min_similarity))
+    }
+    
+    fn similarity_threshold_with(&self, min_similarity: f32) -> f32 {
+        if self.distance_metric.is_distance() {
+            self.max_distance.map_or(f32::NEG_INFINITY, |distance| -distance)
+        } else {
+            min_similarity
         }
     }
 }
@@ -299,6 +1054,10 @@ pub struct SearchResult {
     
     /// Reason for match
     pub match_reason: String,
+    
+    /// How the score was computed, only filled in when requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<ScoreExplanation>,
 }
 
 impl SearchResult {
@@ -312,20 +1071,109 @@ impl SearchResult {
             vector_score: 0.0,
             highlights: Vec::new(),
             match_reason: String::new(),
+            explanation: None,
         }
     }
     
     /// Calculate combined score using weights
     pub fn calculate_weighted_score(&mut self, config: &IndexConfig) {
+        self.calculate_weighted_score_with(config, &config.type_boosts);
+    }
+    
+    /// Calculate combined score using weights and per-query type boosts
+    pub fn calculate_weighted_score_with(&mut self, config: &IndexConfig, type_boosts: &TypeBoosts) {
         self.score = (self.text_score * config.text_weight)
             + (self.tag_score * config.tag_weight)
             + (self.vector_score * config.vector_weight);
         
-        // Apply quality bonus
+        // Apply quality bonus and content-type preference
         self.score *= self.document.quality_score;
+        self.score *= type_boosts.get(&self.document.asset_type);
+        
+        if let Some(explanation) = &mut self.explanation {
+            explanation.text_score = self.text_score;
+            explanation.text_weight = config.text_weight;
+            explanation.tag_score = self.tag_score;
+            explanation.tag_weight = config.tag_weight;
+            explanation.vector_score = self.vector_score;
+            explanation.vector_weight = config.vector_weight;
+            explanation.quality_multiplier = self.document.quality_score;
+            explanation.type_boost = type_boosts.get(&self.document.asset_type);
+            explanation.score = self.score;
+        }
+    }
+}
+
+/// Search results of one asset type, e.g. a "Videos (3)" section
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchGroup {
+    /// Asset type of every result in the group
+    pub asset_type: AssetType,
+    
+    /// Matches of this type in total, including those cut by the limit
+    pub total: usize,
+    
+    /// Best matches of this type, ranked within the group
+    pub results: Vec<SearchResult>,
+}
+
+/// Breakdown of a search result's score
+/// 
+/// `score = (text_score * text_weight + tag_score * tag_weight +
+/// vector_score * vector_weight) * quality_multiplier * type_boost`. Plain
+/// text searches weight the text score 1 and skip the quality multiplier.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScoreExplanation {
+    /// Contribution of each matched term, per field
+    pub terms: Vec<TermExplanation>,
+    /// Multiplier for matching every query term; `text_score` is the sum of
+    /// the term scores times this
+    pub phrase_multiplier: f32,
+    pub text_score: f32,
+    pub text_weight: f32,
+    pub tag_score: f32,
+    pub tag_weight: f32,
+    pub vector_score: f32,
+    pub vector_weight: f32,
+    pub quality_multiplier: f32,
+    pub type_boost: f32,
+    /// Final score of the result
+    pub score: f32,
+}
+
+impl Default for ScoreExplanation {
+    fn default() -> Self {
+        Self {
+            terms: Vec::new(),
+            phrase_multiplier: 1.0,
+            text_score: 0.0,
+            text_weight: 0.0,
+            tag_score: 0.0,
+            tag_weight: 0.0,
+            vector_score: 0.0,
+            vector_weight: 0.0,
+            quality_multiplier: 1.0,
+            type_boost: 1.0,
+            score: 0.0,
+        }
     }
 }
 
+/// How one term matching in one field contributed to the text score
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TermExplanation {
+    pub term: String,
+    pub field: String,
+    /// Occurrences of the term in the field
+    pub term_frequency: usize,
+    pub idf: f32,
+    /// Field weight
+    pub boost: f32,
+    /// `term_frequency * idf * boost`, reduced for terms added by query
+    /// expansion
+    pub score: f32,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -355,6 +1203,44 @@ mod tests {
         assert!(doc.search_text.contains("image"));
     }
     
+    #[test]
+    fn test_config_validation() {
+        assert!(IndexConfig::default().validate().is_ok());
+        
+        let mut config = IndexConfig::default();
+        config.tag_weight = -1.0;
+        assert!(config.validate().is_err());
+        
+        let mut config = IndexConfig::default();
+        config.min_similarity = 1.5;
+        assert!(config.validate().is_err());
+        
+        let mut config = IndexConfig::default();
+        config.max_distance = Some(-1.0);
+        assert!(config.validate().is_err());
+    }
+    
+    #[test]
+    fn test_config_from_toml_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("index.toml");
+        std::fs::write(&path, "storage_dir = \"library/index\"\nmin_similarity = 0.5\nvector_weight = 2.0\n").unwrap();
+        
+        let config = IndexConfig::from_file(&path).unwrap();
+        assert_eq!(config.storage_dir, Some(PathBuf::from("library/index")));
+        assert_eq!(config.min_similarity, 0.5);
+        assert_eq!(config.vector_weight, 2.0);
+        assert_eq!(config.max_results, 100);
+        assert_eq!(config.similarity_threshold(), 0.5);
+        
+        std::fs::write(&path, "distance_metric = \"euclidean\"\nmax_distance = 1.2\n").unwrap();
+        let config = IndexConfig::from_file(&path).unwrap();
+        assert_eq!(config.distance_metric, DistanceMetric::Euclidean);
+        assert_eq!(config.similarity_threshold(), -1.2);
+        assert_eq!(config.query_similarity_threshold(None).unwrap(), -1.2);
+        assert!(config.query_similarity_threshold(Some(0.5)).is_err());
+    }
+    
     #[test]
     fn test_quality_score_calculation() {
         let asset = Asset {
@@ -2,6 +2,7 @@
 
 use schema::DamError;
 use thiserror::Error;
+use uuid::Uuid;
 
 #[derive(Error, Debug)]
 pub enum IndexError {
@@ -18,7 +19,7 @@ pub enum IndexError {
     SearchFailed(String),
     
     #[error("Vector operation failed: {0}")]
-    VectorError(String),
+    Vector(#[from] VectorError),
     
     #[error("Serialization error: {0}")]
     SerializationError(String),
@@ -27,9 +28,65 @@ pub enum IndexError {
     CorruptedIndex(String),
 }
 
+/// Failures of the embedding vector store
+/// 
+/// Kept distinct so callers can tell bad input (a dimension mismatch, a
+/// degenerate embedding) from a state that resolves itself once assets are
+/// processed (no embeddings).
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum VectorError {
+    #[error("Embedding dimension mismatch: expected {expected}, got {got}")]
+    DimensionMismatch { expected: usize, got: usize },
+    
+    #[error("Embedding has zero magnitude (norm {magnitude}); the model likely failed")]
+    ZeroMagnitude { magnitude: f32 },
+    
+    #[error("Embedding contains NaN or infinite values")]
+    NonFinite,
+    
+    #[error("No embeddings have been indexed")]
+    EmptyStore,
+    
+    #[error("No embedding found for document: {0}")]
+    DocumentMissing(Uuid),
+    
+    #[error("{0}")]
+    Other(String),
+}
+
 impl From<IndexError> for DamError {
     fn from(err: IndexError) -> Self {
-        DamError::search(err.to_string())
+        match err {
+            IndexError::Vector(err) => err.into(),
+            // Storage failures are usually transient (locks, disk pressure)
+            IndexError::DatabaseError(_) => DamError::storage(err.to_string()),
+            IndexError::IndexNotFound(_) | IndexError::DocumentNotFound(_) => {
+                DamError::resource_not_available(err.to_string())
+            }
+            // Retrying cannot fix data that does not decode
+            IndexError::SerializationError(_) | IndexError::CorruptedIndex(_) => {
+                DamError::invalid_operation(err.to_string())
+            }
+            IndexError::SearchFailed(_) => DamError::search(err.to_string()),
+        }
+    }
+}
+
+impl From<VectorError> for DamError {
+    fn from(err: VectorError) -> Self {
+        match err {
+            VectorError::DimensionMismatch { .. }
+            | VectorError::ZeroMagnitude { .. }
+            | VectorError::NonFinite => DamError::InvalidOperation {
+                message: err.to_string(),
+            },
+            VectorError::EmptyStore => DamError::ResourceNotAvailable {
+                resource: err.to_string(),
+            },
+            // The document is gone, so retrying cannot find it
+            VectorError::DocumentMissing(document_id) => DamError::document_not_found(document_id),
+            VectorError::Other(message) => DamError::search(message),
+        }
     }
 }
 
@@ -44,3 +101,29 @@ impl From<serde_json::Error> for IndexError {
         IndexError::SerializationError(err.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::ErrorCategory;
+    
+    #[test]
+    fn test_error_conversion_keeps_category() {
+        let err: DamError = IndexError::DatabaseError("locked".to_string()).into();
+        assert_eq!(err.category(), ErrorCategory::System);
+        assert!(err.is_recoverable());
+        
+        let err: DamError = IndexError::CorruptedIndex("bad record".to_string()).into();
+        assert!(!err.is_recoverable());
+        
+        let err: DamError = IndexError::SearchFailed("query".to_string()).into();
+        assert_eq!(err.category(), ErrorCategory::Search);
+        
+        let err: DamError = IndexError::Vector(VectorError::EmptyStore).into();
+        assert!(matches!(err, DamError::ResourceNotAvailable { .. }));
+        
+        let err: DamError = IndexError::Vector(VectorError::DocumentMissing(Uuid::nil())).into();
+        assert!(matches!(err, DamError::DocumentNotFound { .. }));
+        assert!(!err.is_recoverable());
+    }
+}
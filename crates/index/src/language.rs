@@ -0,0 +1,158 @@
+//! Language-specific stemming and stop words for text search
+//!
+//! Indexed text and queries go through the same stemmer and stop-word list,
+//! so `photos` finds `photo` and `le chat` finds `chats`. Changing the
+//! language changes the indexed terms and requires a reindex.
+
+use rust_stemmers::{Algorithm, Stemmer};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Fewest stop words a corpus must contain before `Auto` trusts a detection
+const MIN_DETECTION_HITS: usize = 3;
+
+/// Language used to stem terms and drop stop words
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextLanguage {
+    /// Detect the dominant language of the indexed documents
+    Auto,
+    #[default]
+    English,
+    French,
+    German,
+    Spanish,
+    Italian,
+    Portuguese,
+    Dutch,
+}
+
+impl TextLanguage {
+    /// Languages `Auto` can choose from
+    pub const DETECTABLE: [TextLanguage; 7] = [
+        TextLanguage::English,
+        TextLanguage::French,
+        TextLanguage::German,
+        TextLanguage::Spanish,
+        TextLanguage::Italian,
+        TextLanguage::Portuguese,
+        TextLanguage::Dutch,
+    ];
+
+    /// Snowball algorithm of the language, `None` for `Auto`
+    fn algorithm(&self) -> Option<Algorithm> {
+        match self {
+            TextLanguage::Auto => None,
+            TextLanguage::English => Some(Algorithm::English),
+            TextLanguage::French => Some(Algorithm::French),
+            TextLanguage::German => Some(Algorithm::German),
+            TextLanguage::Spanish => Some(Algorithm::Spanish),
+            TextLanguage::Italian => Some(Algorithm::Italian),
+            TextLanguage::Portuguese => Some(Algorithm::Portuguese),
+            TextLanguage::Dutch => Some(Algorithm::Dutch),
+        }
+    }
+
+    /// Stemmer for the language, `None` for `Auto`
+    pub fn stemmer(&self) -> Option<Stemmer> {
+        self.algorithm().map(Stemmer::create)
+    }
+
+    /// Common function words that are not worth indexing
+    pub fn stop_words(&self) -> &'static [&'static str] {
+        match self {
+            TextLanguage::Auto => &[],
+            TextLanguage::English => &[
+                "a", "an", "and", "are", "as", "at", "be", "by", "for", "from", "in", "is", "it",
+                "its", "of", "on", "or", "that", "the", "this", "to", "was", "were", "with",
+            ],
+            TextLanguage::French => &[
+                "au", "aux", "avec", "ce", "ces", "dans", "de", "des", "du", "en", "est", "et",
+                "il", "la", "le", "les", "leur", "mais", "par", "pour", "qui", "sur", "un", "une",
+            ],
+            TextLanguage::German => &[
+                "auf", "aus", "bei", "das", "dem", "den", "der", "des", "die", "ein", "eine",
+                "einem", "einen", "einer", "ist", "im", "mit", "nicht", "oder", "sich", "und",
+                "von", "zu", "zum",
+            ],
+            TextLanguage::Spanish => &[
+                "al", "como", "con", "de", "del", "el", "en", "es", "la", "las", "lo", "los",
+                "para", "por", "que", "se", "su", "sus", "un", "una", "y",
+            ],
+            TextLanguage::Italian => &[
+                "al", "alla", "che", "con", "da", "dei", "del", "della", "di", "e", "gli", "il",
+                "in", "la", "le", "nel", "nella", "per", "un", "una", "uno",
+            ],
+            TextLanguage::Portuguese => &[
+                "ao", "com", "da", "das", "de", "do", "dos", "e", "em", "na", "nas", "no", "nos",
+                "o", "os", "para", "por", "que", "um", "uma",
+            ],
+            TextLanguage::Dutch => &[
+                "aan", "de", "een", "en", "het", "in", "is", "met", "niet", "of", "op", "te",
+                "van", "voor", "zijn",
+            ],
+        }
+    }
+
+    /// Whether a lowercase word is a stop word in this language
+    pub fn is_stop_word(&self, word: &str) -> bool {
+        self.stop_words().contains(&word)
+    }
+
+    /// Guess the dominant language of some texts from their stop words
+    ///
+    /// Returns `None` when too few stop words occur to tell, e.g. for
+    /// collections that only have tags and filenames.
+    pub fn detect<'a>(texts: impl IntoIterator<Item = &'a str>) -> Option<TextLanguage> {
+        let mut hits: HashMap<TextLanguage, usize> = HashMap::new();
+
+        for text in texts {
+            for word in text.to_lowercase().split(|c: char| !c.is_alphabetic()) {
+                for language in Self::DETECTABLE {
+                    if language.is_stop_word(word) {
+                        *hits.entry(language).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+
+        // Ties go to the language listed first, so English wins by default
+        Self::DETECTABLE.into_iter()
+            .map(|language| (language, hits.get(&language).copied().unwrap_or(0)))
+            .filter(|(_, count)| *count >= MIN_DETECTION_HITS)
+            .fold(None, |best: Option<(TextLanguage, usize)>, (language, count)| match best {
+                Some((_, best_count)) if best_count >= count => best,
+                _ => Some((language, count)),
+            })
+            .map(|(language, _)| language)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_language() {
+        let french = ["Le chat noir dort sur le canapé", "Une photo de la plage avec des enfants"];
+        assert_eq!(TextLanguage::detect(french), Some(TextLanguage::French));
+
+        let german = ["Der Hund spielt im Garten mit dem Ball", "Ein Foto von der Küste"];
+        assert_eq!(TextLanguage::detect(german), Some(TextLanguage::German));
+
+        assert_eq!(TextLanguage::detect(["sunset", "beach", "IMG_2024"]), None);
+    }
+
+    #[test]
+    fn test_stemming() {
+        let english = TextLanguage::English.stemmer().unwrap();
+        assert_eq!(english.stem("photos"), english.stem("photo"));
+
+        let french = TextLanguage::French.stemmer().unwrap();
+        assert_eq!(french.stem("chats"), french.stem("chat"));
+
+        assert!(TextLanguage::Auto.stemmer().is_none());
+        assert!(TextLanguage::German.is_stop_word("und"));
+        assert!(!TextLanguage::English.is_stop_word("und"));
+    }
+}
@@ -6,31 +6,78 @@
 //! - Hybrid search combining text and vector results
 //! - Persistent storage using sled database
 
-use schema::{DamResult, Asset};
+use schema::{
+    retry_recoverable, retry_recoverable_async, DamError, DamResult, Asset, AssetType, NotificationLevel, PreviewInfo, ProcessMessage,
+    ProcessingResult, ProcessingTaskType, SearchQuery, SortCriteria, StepStatus, UiEvents, DEFAULT_RETRY_ATTEMPTS,
+    DEFAULT_RETRY_DELAY, MAX_RATING,
+};
 use std::path::{Path, PathBuf};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 use tracing::{info, warn, debug};
 use serde::{Serialize, Deserialize};
+use futures::stream::{self, BoxStream, StreamExt};
 
 pub mod error;
 pub mod document;
 pub mod vector;
 pub mod text_search;
+pub mod catalog;
+pub mod snippet;
+pub mod recent;
+pub mod timeline;
+pub mod language;
+pub mod progress;
+pub mod shared;
+pub mod cache;
+pub mod top_k;
 
 pub use error::*;
 pub use document::*;
 pub use vector::*;
 pub use text_search::*;
+pub use catalog::*;
+pub use snippet::*;
+pub use recent::*;
+pub use timeline::*;
+pub use language::*;
+pub use progress::*;
+pub use shared::SharedIndex;
+pub use cache::QueryCache;
+pub use top_k::*;
 
 /// Main search and indexing service
+/// 
+/// Reads (searches, lookups, stats) take `&self` and writes take
+/// `&mut self`; the service does no locking of its own. To use one index
+/// from several tasks, wrap it in a `SharedIndex`, which lets reads run
+/// concurrently and gives writes exclusive access.
 pub struct IndexService {
     /// Text search index
     text_index: TextIndex,
     /// Vector similarity store
     vector_store: VectorStore,
+    /// Documents ordered by timestamp
+    recency: RecencyIndex,
+    /// AI processing progress per document
+    processing: ProcessingIndex,
+    /// Document ID of each indexed asset
+    asset_documents: HashMap<Uuid, Uuid>,
     /// Document storage (sled database)
     doc_store: sled::Db,
+    /// Results of recent searches, cleared on every write
+    query_cache: Mutex<QueryCache>,
+    /// Size of the preview directory and when it was measured
+    preview_size: Mutex<Option<(Instant, u64)>>,
+    /// Progress and notifications of long operations
+    events: UiEvents,
+    /// Text tower for searching images by description, if a model is set
+    cross_modal_encoder: Option<Arc<dyn CrossModalEncoder>>,
+    /// Embedding dimensions of the active embedders, per type, as declared
+    /// through `ensure_embedding_dimension`
+    declared_dimensions: HashMap<EmbeddingType, usize>,
     /// Configuration
     config: IndexConfig,
     /// Storage directory
@@ -46,7 +93,22 @@ impl IndexService {
     
     /// Create index service with custom storage directory
     pub fn with_storage_dir<P: AsRef<Path>>(storage_dir: P) -> DamResult<Self> {
-        let storage_dir = storage_dir.as_ref().to_path_buf();
+        Self::open(storage_dir.as_ref().to_path_buf(), IndexConfig::default())
+    }
+    
+    /// Create index service from a TOML or JSON config file
+    /// 
+    /// Uses the configured `storage_dir`, falling back to `data/index`.
+    pub fn with_config<P: AsRef<Path>>(config_path: P) -> DamResult<Self> {
+        let config = IndexConfig::from_file(config_path)?;
+        let storage_dir = config.storage_dir.clone()
+            .unwrap_or_else(|| PathBuf::from("data/index"));
+        Self::open(storage_dir, config)
+    }
+    
+    /// Open the index at a storage directory with the given configuration
+    fn open(storage_dir: PathBuf, config: IndexConfig) -> DamResult<Self> {
+        config.validate()?;
         
         info!("Initializing index service with storage: {}", storage_dir.display());
         
@@ -58,14 +120,22 @@ impl IndexService {
         let doc_store = sled::open(db_path)
             .map_err(|e| IndexError::DatabaseError(e.to_string()))?;
         
-        let config = IndexConfig::default();
         let text_index = TextIndex::new(config.clone());
-        let vector_store = VectorStore::new();
+        let vector_store = Self::build_vector_store(&config, &doc_store);
+        let query_cache = Mutex::new(QueryCache::new(Self::query_cache_capacity(&config)));
         
         let mut service = Self {
             text_index,
             vector_store,
+            recency: RecencyIndex::new(),
+            processing: ProcessingIndex::new(),
+            asset_documents: HashMap::new(),
             doc_store,
+            query_cache,
+            preview_size: Mutex::new(None),
+            events: UiEvents::new(),
+            cross_modal_encoder: None,
+            declared_dimensions: HashMap::new(),
             config,
             storage_dir,
         };
@@ -77,27 +147,218 @@ impl IndexService {
         Ok(service)
     }
     
+    /// Report long operations on a shared event channel
+    pub fn with_events(mut self, events: UiEvents) -> Self {
+        self.events = events;
+        self
+    }
+    
+    /// Count the previews stored in `dir` in the storage statistics
+    pub fn with_preview_dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.config.preview_dir = Some(dir.into());
+        *self.preview_size.get_mut().unwrap_or_else(PoisonError::into_inner) = None;
+        self
+    }
+    
+    /// Search images by description with a CLIP-style text encoder
+    pub fn with_cross_modal_encoder(mut self, encoder: Arc<dyn CrossModalEncoder>) -> Self {
+        self.set_cross_modal_encoder(Some(encoder));
+        self
+    }
+    
+    /// Replace the text encoder of `search_cross_modal`, or remove it
+    /// 
+    /// The encoder has to match the model that produced the stored visual
+    /// embeddings.
+    pub fn set_cross_modal_encoder(&mut self, encoder: Option<Arc<dyn CrossModalEncoder>>) {
+        self.cross_modal_encoder = encoder;
+    }
+    
+    /// Channel on which progress and notifications are emitted
+    pub fn events(&self) -> &UiEvents {
+        &self.events
+    }
+    
+    /// Get the current configuration
+    pub fn config(&self) -> &IndexConfig {
+        &self.config
+    }
+    
+    /// Replace the search configuration
+    /// 
+    /// Weights and thresholds are applied at query time, so no reindex is
+    /// needed; a new distance metric reloads the stored embeddings. The
+    /// storage directory of a running service is not changed.
+    pub fn set_config(&mut self, config: IndexConfig) -> DamResult<()> {
+        config.validate()?;
+        
+        let metric_changed = config.distance_metric != self.vector_store.metric();
+        let residency_changed = config.max_resident_embeddings != self.config.max_resident_embeddings
+            || config.eviction_policy != self.config.eviction_policy;
+        self.text_index.set_config(config.clone());
+        self.query_cache().set_capacity(Self::query_cache_capacity(&config));
+        if config.preview_dir != self.config.preview_dir {
+            *self.preview_size.get_mut().unwrap_or_else(PoisonError::into_inner) = None;
+        }
+        self.config = config;
+        
+        if metric_changed {
+            let documents: Vec<AssetDocument> = self.iter_documents().filter_map(Result::ok).collect();
+            let mut vector_store = Self::build_vector_store(&self.config, &self.doc_store);
+            vector_store.load_from_documents(&documents)?;
+            self.vector_store = vector_store;
+        } else if residency_changed {
+            self.vector_store.set_residency_limit(
+                self.config.max_resident_embeddings,
+                self.config.eviction_policy,
+                Arc::new(StoredEmbeddings::new(&self.doc_store)),
+            );
+        }
+        
+        info!("Updated index configuration");
+        Ok(())
+    }
+    
     /// Add or update an asset in the search index
     pub async fn index_asset(&mut self, asset: &Asset) -> DamResult<()> {
         debug!("Indexing asset: {}", asset.current_path.display());
         
+        let previous = self.find_document_by_asset_id(&asset.id)?;
+        let (document, content_changed) = self.prepare_document(asset, previous);
+        
+        // Store first, so a failed write leaves the in-memory indexes untouched
+        self.store_document(&document).await?;
+        self.index_document(&document, content_changed)?;
+        
+        debug!("Successfully indexed asset: {}", asset.current_path.display());
+        Ok(())
+    }
+    
+    /// Add or update many assets with a single database write
+    /// 
+    /// Returns one result per asset, in order; assets that fail are skipped
+    /// without affecting the rest of the batch. The in-memory indexes are
+    /// only updated once the batch is stored.
+    pub async fn index_assets(&mut self, assets: &[Asset]) -> DamResult<Vec<DamResult<()>>> {
+        info!("Indexing batch of {} assets", assets.len());
+        
+        let asset_ids: HashSet<Uuid> = assets.iter().map(|asset| asset.id).collect();
+        let mut existing = self.find_documents_by_asset_ids(&asset_ids)?;
+        
+        let mut batch = sled::Batch::default();
+        let mut prepared = Vec::with_capacity(assets.len());
+        let mut results: Vec<DamResult<()>> = Vec::with_capacity(assets.len());
+        
+        for (position, asset) in assets.iter().enumerate() {
+            let (document, content_changed) = self.prepare_document(asset, existing.remove(&asset.id));
+            match serde_json::to_vec(&document) {
+                Ok(json) => {
+                    batch.insert(document.id.as_bytes(), json);
+                    // A repeated asset in the same batch updates this document
+                    existing.insert(asset.id, document.clone());
+                    prepared.push((position, document, content_changed));
+                    results.push(Ok(()));
+                }
+                Err(e) => results.push(Err(e.into())),
+            }
+        }
+        
+        // A failed write returns before memory is touched, keeping it in step with the store
+        self.apply_batch(batch).await?;
+        for (position, document, content_changed) in prepared {
+            if let Err(e) = self.index_document(&document, content_changed) {
+                results[position] = Err(e);
+            }
+        }
+        
+        for (asset, result) in assets.iter().zip(&results) {
+            if let Err(e) = result {
+                warn!("Failed to index asset {}: {}", asset.current_path.display(), e);
+            }
+        }
+        
+        let failed = results.iter().filter(|result| result.is_err()).count();
+        debug!("Indexed {} of {} assets", assets.len() - failed, assets.len());
+        if failed > 0 {
+            self.events.notify(
+                NotificationLevel::Warning,
+                "Indexing incomplete",
+                format!("{} of {} assets could not be indexed", failed, assets.len()),
+            );
+        }
+        Ok(results)
+    }
+    
+    /// Build the document for an asset
+    /// 
+    /// `previous` is the asset's existing document, if any. Also returns
+    /// whether the content changed since `previous`. Nothing is stored or
+    /// indexed; see `index_document`.
+    fn prepare_document(&self, asset: &Asset, previous: Option<AssetDocument>) -> (AssetDocument, bool) {
         let mut document = AssetDocument::from_asset(asset);
+        let mut content_changed = false;
+        
+        // Re-indexing an asset reuses its existing document rather than adding a second one
+        if let Some(previous) = previous {
+            document.id = previous.id;
+            document.indexed_at = previous.indexed_at;
+            
+            // Ratings set through the index survive re-ingestion
+            document.rating = document.rating.or(previous.rating);
+            document.favorite |= previous.favorite;
+            
+            // Custom metadata set by users survives; file values are re-read
+            document.user_metadata = previous.user_metadata.clone();
+            
+            content_changed = previous.file_size != document.file_size
+                || previous.modified_at != document.modified_at;
+            
+            if content_changed {
+                // AI results describe the old content; drop them until reprocessed
+                debug!("Content changed for asset {}, discarding previous AI results", asset.id);
+            } else {
+                // An asset re-read without its preview, as by the deep pass
+                // after a fast import, keeps the stored one
+                if document.thumbnail_path.is_none() {
+                    document.preview_path = previous.preview_path.clone();
+                    document.thumbnail_path = previous.thumbnail_path.clone();
+                    document.waveform = previous.waveform.clone();
+                    document.contact_sheet_path = previous.contact_sheet_path.clone();
+                    document.contact_sheet_grid = previous.contact_sheet_grid;
+                }
+                document.carry_forward_ai_results(previous);
+            }
+        }
         
         // Calculate quality score
         document.calculate_quality_score();
         
-        // Add to text index
-        self.text_index.add_document(&document)?;
-        
-        // Store document in database
-        let doc_json = serde_json::to_vec(&document)?;
-        self.doc_store.insert(document.id.as_bytes(), doc_json)
-            .map_err(|e| IndexError::DatabaseError(e.to_string()))?;
-        
-        debug!("Successfully indexed asset: {}", asset.current_path.display());
+        (document, content_changed)
+    }
+    
+    /// Add a stored document to the in-memory indexes
+    /// 
+    /// With `content_changed`, the document's old embeddings are dropped.
+    fn index_document(&mut self, document: &AssetDocument, content_changed: bool) -> DamResult<()> {
+        if content_changed {
+            self.vector_store.remove_document(&document.id);
+        }
+        self.text_index.add_document(document)?;
+        self.recency.insert(document);
+        self.processing.insert(document);
+        self.asset_documents.insert(document.asset_id, document.id);
         Ok(())
     }
     
+    /// Drop a removed document from the in-memory indexes
+    fn forget_document(&mut self, document: &AssetDocument) {
+        self.text_index.remove_document(&document.id);
+        self.vector_store.remove_document(&document.id);
+        self.recency.remove(&document.id);
+        self.processing.remove(&document.id);
+        self.asset_documents.remove(&document.asset_id);
+    }
+    
     /// Update document with AI processing results
     pub async fn update_with_ai_results(
         &mut self, 
@@ -114,7 +375,18 @@ impl IndexService {
         let mut document = self.find_document_by_asset_id(&asset_id)?
             .ok_or_else(|| IndexError::DocumentNotFound(format!("Asset not found: {}", asset_id)))?;
         
-        // Update with AI results
+        // Update with AI results, marking the steps that produced them as done
+        let status = &mut document.processing_status;
+        if tags.is_some() || caption.is_some() {
+            status.tagging = StepStatus::Done;
+        }
+        if transcription.is_some() {
+            status.transcription = StepStatus::Done;
+        }
+        if visual_embedding.is_some() || text_embedding.is_some() {
+            status.embedding = StepStatus::Done;
+        }
+        
         if let Some(tags) = tags {
             document.add_ai_tags(tags);
         }
@@ -127,382 +399,3903 @@ impl IndexService {
             document.set_transcription(transcription);
         }
         
-        if let Some(embedding) = visual_embedding {
+        // Embeddings are checked up front, so adding them after the write cannot fail
+        if let Some(embedding) = &visual_embedding {
+            self.vector_store.check_visual_embedding(embedding)?;
             document.set_visual_embedding(embedding.clone());
-            self.vector_store.add_visual_embedding(document.id, embedding)?;
         }
         
-        if let Some(embedding) = text_embedding {
+        if let Some(embedding) = &text_embedding {
+            self.vector_store.check_text_embeddings(std::slice::from_ref(embedding))?;
             document.set_text_embedding(embedding.clone());
-            self.vector_store.add_text_embedding(document.id, embedding)?;
         }
         
         // Recalculate quality score
         document.calculate_quality_score();
         
-        // Update text index
-        self.text_index.add_document(&document)?;
+        // Store first, so a failed write leaves the in-memory indexes untouched
+        self.store_document(&document).await?;
         
-        // Update document storage
-        let doc_json = serde_json::to_vec(&document)?;
-        self.doc_store.insert(document.id.as_bytes(), doc_json)
-            .map_err(|e| IndexError::DatabaseError(e.to_string()))?;
+        if let Some(embedding) = visual_embedding {
+            self.vector_store.add_visual_embedding(document.id, embedding)?;
+        }
+        if let Some(embedding) = text_embedding {
+            self.vector_store.add_text_embedding(document.id, embedding)?;
+        }
+        self.text_index.add_document(&document)?;
+        self.processing.insert(&document);
         
         debug!("Successfully updated AI results for asset: {}", asset_id);
         Ok(())
     }
     
-    /// Remove an asset from the index
-    pub async fn remove_asset(&mut self, asset_id: Uuid) -> DamResult<()> {
-        debug!("Removing asset from index: {}", asset_id);
+    /// Store a segmented transcription, e.g. with speakers from diarization
+    /// 
+    /// Replaces the asset's transcription with the segments' text and marks
+    /// the transcription step done. Segments with speaker labels make the
+    /// transcript searchable per speaker via `speaker:`.
+    pub async fn update_transcription_segments(&mut self, asset_id: Uuid, segments: Vec<TranscriptionSegment>) -> DamResult<()> {
+        debug!("Updating {} transcription segments for asset: {}", segments.len(), asset_id);
         
-        // Find document
-        if let Some(document) = self.find_document_by_asset_id(&asset_id)? {
-            // Remove from text index
-            self.text_index.remove_document(&document.id);
-            
-            // Remove from vector store
-            self.vector_store.remove_document(&document.id);
-            
-            // Remove from document storage
-            self.doc_store.remove(document.id.as_bytes())
-                .map_err(|e| IndexError::DatabaseError(e.to_string()))?;
-            
-            debug!("Successfully removed asset from index: {}", asset_id);
-        }
+        let mut document = self.find_document_by_asset_id(&asset_id)?
+            .ok_or_else(|| IndexError::DocumentNotFound(format!("Asset not found: {}", asset_id)))?;
         
+        document.set_transcription_segments(segments);
+        document.processing_status.transcription = StepStatus::Done;
+        document.calculate_quality_score();
+        
+        self.store_document(&document).await?;
+        self.text_index.add_document(&document)?;
+        self.processing.insert(&document);
         Ok(())
     }
     
-    /// Search for assets using text query
-    pub async fn search_text(&self, query: &str, max_results: usize) -> DamResult<Vec<SearchResult>> {
-        debug!("Text search query: '{}'", query);
+    /// Store the chunk embeddings of a long text (transcript, document)
+    /// 
+    /// Each chunk is searchable on its own, so a query matching any part of
+    /// the text finds the asset. The mean of the chunks becomes the
+    /// document's single text embedding.
+    pub async fn update_text_embedding_chunks(&mut self, asset_id: Uuid, chunks: Vec<Vec<f32>>) -> DamResult<()> {
+        debug!("Updating {} text embedding chunks for asset: {}", chunks.len(), asset_id);
         
-        let text_matches = self.text_index.search(query, max_results)?;
-        let mut results = Vec::new();
+        let mut document = self.find_document_by_asset_id(&asset_id)?
+            .ok_or_else(|| IndexError::DocumentNotFound(format!("Asset not found: {}", asset_id)))?;
         
-        for text_match in text_matches {
-            if let Some(document) = self.get_document(&text_match.document_id)? {
-                let mut result = SearchResult::new(document, text_match.score);
-                result.text_score = text_match.score;
-                result.match_reason = format!("Text match in: {}", 
-                    text_match.matches.iter()
-                        .map(|m| m.field_name.as_str())
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                );
-                result.highlights = text_match.matches.iter()
-                    .map(|m| format!("{}: {}", m.field_name, m.match_text))
-                    .collect();
-                
-                results.push(result);
-            }
-        }
+        self.vector_store.check_text_embeddings(&chunks)?;
+        document.set_text_embedding_chunks(chunks.clone());
+        document.processing_status.embedding = StepStatus::Done;
+        document.calculate_quality_score();
         
-        debug!("Text search returned {} results", results.len());
-        Ok(results)
+        self.store_document(&document).await?;
+        self.vector_store.add_text_embeddings(document.id, chunks)?;
+        self.processing.insert(&document);
+        Ok(())
     }
     
-    /// Search for visually similar assets
-    pub async fn search_visual_similar(&self, query_embedding: &[f32], max_results: usize) -> DamResult<Vec<SearchResult>> {
-        debug!("Visual similarity search with {} dimensional embedding", query_embedding.len());
-        
-        let vector_matches = self.vector_store.find_visual_similar(
-            query_embedding, 
-            max_results, 
-            self.config.min_similarity
-        )?;
-        
-        let mut results = Vec::new();
+    /// Stored embedding of an asset, or None if it has none of this type
+    /// 
+    /// Returns the vector exactly as similarity search uses it: with the
+    /// default cosine metric it is normalized to unit length, so external
+    /// tools can compare vectors with a plain dot product. Assets with a
+    /// chunked text embedding get the normalized mean of their chunks.
+    pub fn get_embedding(&self, asset_id: Uuid, embedding_type: EmbeddingType) -> DamResult<Option<Vec<f32>>> {
+        let Some(document) = self.find_document_by_asset_id(&asset_id)? else {
+            return Err(DamError::asset_not_found(asset_id));
+        };
+        Ok(self.vector_store.embedding(&document.id, embedding_type))
+    }
+    
+    /// Dimension of the stored embeddings of a type, if any are stored
+    pub fn embedding_dimension(&self, embedding_type: EmbeddingType) -> Option<usize> {
+        self.vector_store.dimension(embedding_type)
+    }
+    
+    /// Drop every stored embedding of one type
+    /// 
+    /// Embeddings of different dimensions cannot be compared, so switching
+    /// to a model with another output size has to start from an empty set
+    /// rather than mix old and new vectors. Affected documents lose the
+    /// embedding, and their embedding step is reset once no embedding is
+    /// left, so they are processed again. Returns the number of documents changed.
+    pub async fn clear_embeddings(&mut self, embedding_type: EmbeddingType) -> DamResult<usize> {
+        info!("Clearing all {:?} embeddings", embedding_type);
         
-        for vector_match in vector_matches {
-            if let Some(document) = self.get_document(&vector_match.document_id)? {
-                let mut result = SearchResult::new(document, vector_match.similarity);
-                result.vector_score = vector_match.similarity;
-                result.match_reason = "Visual similarity".to_string();
-                
-                results.push(result);
+        let mut cleared = 0;
+        let documents: Vec<AssetDocument> = self.iter_documents().collect::<DamResult<_>>()?;
+        for mut document in documents {
+            match embedding_type {
+                EmbeddingType::Visual if document.visual_embedding.is_some() => {
+                    document.visual_embedding = None;
+                }
+                EmbeddingType::Text if document.text_embedding.is_some() => {
+                    document.text_embedding = None;
+                    document.text_embedding_chunks.clear();
+                }
+                _ => continue,
+            }
+            if document.visual_embedding.is_none() && document.text_embedding.is_none() {
+                document.processing_status.embedding = StepStatus::NotStarted;
             }
+            document.calculate_quality_score();
+            if let Err(e) = self.store_document(&document).await {
+                // Documents stored so far lost the embedding; resync the vectors with them
+                self.rebuild_vector_store()?;
+                return Err(e);
+            }
+            self.processing.insert(&document);
+            cleared += 1;
         }
         
-        debug!("Visual similarity search returned {} results", results.len());
-        Ok(results)
+        self.vector_store.clear_type(embedding_type);
+        Ok(cleared)
     }
     
-    /// Find assets similar to a specific asset
-    pub async fn find_similar(&self, asset_id: Uuid, embedding_type: EmbeddingType, max_results: usize) -> DamResult<Vec<SearchResult>> {
-        debug!("Finding similar assets to: {}", asset_id);
-        
-        // Find document
-        let document = self.find_document_by_asset_id(&asset_id)?
-            .ok_or_else(|| IndexError::DocumentNotFound(format!("Asset not found: {}", asset_id)))?;
-        
-        let vector_matches = self.vector_store.find_similar_to_document(
-            &document.id,
-            embedding_type,
-            max_results,
-            self.config.min_similarity
-        )?;
-        
-        let mut results = Vec::new();
-        
-        for vector_match in vector_matches {
-            if let Some(document) = self.get_document(&vector_match.document_id)? {
-                let mut result = SearchResult::new(document, vector_match.similarity);
-                result.vector_score = vector_match.similarity;
-                result.match_reason = format!("Similar to asset {}", asset_id);
-                
-                results.push(result);
+    /// Report the embedding dimensions stored across the library
+    /// 
+    /// Embeddings from a different model than the active one cannot be
+    /// loaded into the vector store, so their assets silently drop out of
+    /// similarity search. Each document is checked against the dimension
+    /// the active embedder declared through `ensure_embedding_dimension`,
+    /// or the most common stored one if none was declared.
+    pub fn audit_embeddings(&self) -> DamResult<EmbeddingAudit> {
+        let mut documents = Vec::new();
+        for document in self.iter_documents() {
+            match document {
+                Ok(document) => documents.push(document),
+                Err(e) => warn!("Skipping unreadable document in embedding audit: {}", e),
             }
         }
-        
-        debug!("Similarity search returned {} results", results.len());
-        Ok(results)
+        Ok(self.audit_documents(&documents))
     }
     
-    /// Hybrid search combining text and vector search
-    pub async fn search_hybrid(&self, query: &str, query_embedding: Option<&[f32]>, max_results: usize) -> DamResult<Vec<SearchResult>> {
-        debug!("Hybrid search: '{}' with embedding: {}", query, query_embedding.is_some());
-        
-        let mut all_results: HashMap<Uuid, SearchResult> = HashMap::new();
-        
-        // Text search
-        if !query.trim().is_empty() {
-            let text_results = self.search_text(query, max_results * 2).await?;
-            for mut result in text_results {
-                result.calculate_weighted_score(&self.config);
-                all_results.insert(result.document.id, result);
+    /// Check the embedding dimensions of documents, as `audit_embeddings`
+    fn audit_documents(&self, documents: &[AssetDocument]) -> EmbeddingAudit {
+        let mut visual_dims: Vec<(Uuid, Vec<usize>)> = Vec::new();
+        let mut text_dims: Vec<(Uuid, Vec<usize>)> = Vec::new();
+        for document in documents {
+            if let Some(embedding) = &document.visual_embedding {
+                visual_dims.push((document.asset_id, vec![embedding.len()]));
             }
-        }
-        
-        // Vector search
-        if let Some(embedding) = query_embedding {
-            let vector_results = self.search_visual_similar(embedding, max_results * 2).await?;
-            for mut result in vector_results {
-                result.calculate_weighted_score(&self.config);
-                
-                // Combine with existing text result if present
-                if let Some(existing) = all_results.get_mut(&result.document.id) {
-                    existing.vector_score = result.vector_score;
-                    existing.score = (existing.text_score * self.config.text_weight) 
-                        + (result.vector_score * self.config.vector_weight);
-                    existing.match_reason = format!("{} + Visual similarity", existing.match_reason);
-                } else {
-                    all_results.insert(result.document.id, result);
-                }
+            let text_chunks: Vec<usize> = if document.text_embedding_chunks.is_empty() {
+                document.text_embedding.iter().map(Vec::len).collect()
+            } else {
+                document.text_embedding_chunks.iter().map(Vec::len).collect()
+            };
+            if !text_chunks.is_empty() {
+                text_dims.push((document.asset_id, text_chunks));
             }
         }
         
-        // Sort and limit results
-        let mut results: Vec<SearchResult> = all_results.into_values().collect();
-        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
-        results.truncate(max_results);
-        
-        debug!("Hybrid search returned {} results", results.len());
-        Ok(results)
-    }
-    
-    /// Get search statistics
-    pub fn get_stats(&self) -> IndexStats {
-        let text_stats = self.text_index.get_stats();
-        let vector_stats = self.vector_store.get_stats();
-        
-        IndexStats {
-            total_documents: text_stats.total_documents,
-            total_terms: text_stats.total_terms,
-            avg_terms_per_doc: text_stats.avg_terms_per_doc,
-            visual_embeddings: vector_stats.visual_embeddings_count,
-            text_embeddings: vector_stats.text_embeddings_count,
-            visual_dimension: vector_stats.visual_dimension,
-            text_dimension: vector_stats.text_dimension,
+        let declared = |embedding_type| self.declared_dimensions.get(&embedding_type).copied();
+        EmbeddingAudit {
+            visual: DimensionAudit::new(declared(EmbeddingType::Visual), visual_dims),
+            text: DimensionAudit::new(declared(EmbeddingType::Text), text_dims),
+            requeued: Vec::new(),
         }
     }
     
-    /// Clear all indexes
-    pub async fn clear(&mut self) -> DamResult<()> {
-        info!("Clearing all search indexes");
+    /// Clear embeddings that do not match the library's dimension
+    /// 
+    /// Runs `audit_embeddings` and drops the mismatched embeddings from
+    /// their documents. Their embedding step is reset, and for visual
+    /// embeddings the tagging step that produces them too, so the next
+    /// processing run embeds them again with the current model. Returns
+    /// the audit from before the repair, listing the requeued assets.
+    pub async fn repair_embeddings(&mut self) -> DamResult<EmbeddingAudit> {
+        let mut audit = self.audit_embeddings()?;
+        let visual: HashSet<Uuid> = audit.visual.mismatched.iter().copied().collect();
+        let text: HashSet<Uuid> = audit.text.mismatched.iter().copied().collect();
         
-        self.text_index.clear();
-        self.vector_store.clear();
-        self.doc_store.clear()
-            .map_err(|e| IndexError::DatabaseError(e.to_string()))?;
+        let mut requeued: Vec<Uuid> = visual.union(&text).copied().collect();
+        requeued.sort();
+        for asset_id in &requeued {
+            let Some(mut document) = self.find_document_by_asset_id(asset_id)? else {
+                continue;
+            };
+            if visual.contains(asset_id) {
+                document.visual_embedding = None;
+                document.processing_status.tagging = StepStatus::NotStarted;
+            }
+            if text.contains(asset_id) {
+                document.text_embedding = None;
+                document.text_embedding_chunks.clear();
+            }
+            document.processing_status.embedding = StepStatus::NotStarted;
+            document.calculate_quality_score();
+            self.store_document(&document).await?;
+            self.processing.insert(&document);
+        }
         
-        Ok(())
+        if !requeued.is_empty() {
+            info!("Cleared mismatched embeddings of {} assets for re-embedding", requeued.len());
+            // The store may have adopted the odd dimension when it loaded
+            self.rebuild_vector_store()?;
+        }
+        audit.requeued = requeued;
+        Ok(audit)
     }
     
-    /// Reload documents from storage
-    fn reload_from_storage(&mut self) -> DamResult<()> {
-        info!("Reloading documents from storage");
-        
+    /// Rebuild the vector store from the embeddings stored in documents
+    /// 
+    /// A quick recovery when the in-memory vectors no longer match the
+    /// database, e.g. after a crash; unlike reopening the index it leaves
+    /// the text index alone. Each type expects the dimension checked by
+    /// `audit_embeddings`, and embeddings of another dimension are skipped
+    /// like degenerate ones.
+    pub fn rebuild_vector_store(&mut self) -> DamResult<EmbeddingLoadStats> {
         let mut documents = Vec::new();
-        
-        // Load all documents from storage
-        for result in self.doc_store.iter() {
-            let (_, value) = result.map_err(|e| IndexError::DatabaseError(e.to_string()))?;
-            if let Ok(document) = serde_json::from_slice::<AssetDocument>(&value) {
-                documents.push(document);
+        let mut unreadable = 0;
+        for document in self.iter_documents() {
+            match document {
+                Ok(document) => documents.push(document),
+                Err(e) => {
+                    warn!("Skipping unreadable document: {}", e);
+                    unreadable += 1;
+                }
             }
         }
+        let mut stats = self.load_vector_store(&documents)?;
+        stats.unreadable_documents = unreadable;
         
-        info!("Loaded {} documents from storage", documents.len());
-        
-        // Rebuild text index
-        for doc in &documents {
-            if let Err(e) = self.text_index.add_document(doc) {
-                warn!("Failed to add document to text index: {}", e);
-            }
+        info!(
+            "Rebuilt vector store with {} visual and {} text embeddings",
+            stats.visual_loaded, stats.text_loaded
+        );
+        if stats.skipped() > 0 {
+            warn!("Skipped {} invalid embeddings while rebuilding the vector store", stats.skipped());
         }
-        
-        // Rebuild vector store
-        if let Err(e) = self.vector_store.load_from_documents(&documents) {
-            warn!("Failed to load vector embeddings: {}", e);
+        if unreadable > 0 {
+            warn!("{} unreadable documents have no embeddings loaded", unreadable);
         }
-        
-        info!("Successfully reloaded search indexes");
-        Ok(())
+        Ok(stats)
     }
     
-    /// Get document by ID
-    fn get_document(&self, doc_id: &Uuid) -> DamResult<Option<AssetDocument>> {
-        if let Some(data) = self.doc_store.get(doc_id.as_bytes())
-            .map_err(|e| IndexError::DatabaseError(e.to_string()))? {
-            let document: AssetDocument = serde_json::from_slice(&data)?;
-            Ok(Some(document))
-        } else {
-            Ok(None)
+    /// Replace the vector store with one filled from documents
+    /// 
+    /// The expected dimensions come from the audit rather than from
+    /// whichever embedding happens to load first.
+    fn load_vector_store(&mut self, documents: &[AssetDocument]) -> DamResult<EmbeddingLoadStats> {
+        let audit = self.audit_documents(documents);
+        let mut vector_store = Self::build_vector_store(&self.config, &self.doc_store);
+        if let Some(dimension) = audit.visual.expected {
+            vector_store.expect_dimension(EmbeddingType::Visual, dimension);
+        }
+        if let Some(dimension) = audit.text.expected {
+            vector_store.expect_dimension(EmbeddingType::Text, dimension);
         }
+        let stats = vector_store.load_from_documents(documents)?;
+        self.vector_store = vector_store;
+        Ok(stats)
     }
     
-    /// Find document by asset ID
-    fn find_document_by_asset_id(&self, asset_id: &Uuid) -> DamResult<Option<AssetDocument>> {
+    /// Make the stored embeddings of a type match an embedder's dimension
+    /// 
+    /// Call when the active embedder may have changed. Embeddings of another
+    /// dimension are dropped as by `clear_embeddings`, so their assets get
+    /// re-embedded, and vectors of any other dimension are rejected from
+    /// then on. Returns whether embeddings were dropped.
+    pub async fn ensure_embedding_dimension(&mut self, embedding_type: EmbeddingType, dimension: usize) -> DamResult<bool> {
+        let cleared = match self.vector_store.dimension(embedding_type) {
+            Some(current) if current != dimension => {
+                info!("{:?} embedding dimension changed from {} to {}", embedding_type, current, dimension);
+                self.clear_embeddings(embedding_type).await?;
+                true
+            }
+            _ => false,
+        };
+        self.vector_store.expect_dimension(embedding_type, dimension);
+        self.declared_dimensions.insert(embedding_type, dimension);
+        Ok(cleared)
+    }
+    
+    /// Documents of fast-imported assets still waiting for the deep pass
+    pub fn pending_deep_processing(&self) -> DamResult<Vec<AssetDocument>> {
+        let mut pending = Vec::new();
+        for document in self.iter_documents() {
+            match document {
+                Ok(document) if document.needs_deep_processing => pending.push(document),
+                Ok(_) => {}
+                Err(e) => warn!("Skipping unreadable document: {}", e),
+            }
+        }
+        Ok(pending)
+    }
+    
+    /// Mark whether an asset still needs the deep pass
+    pub async fn set_needs_deep_processing(&mut self, asset_id: Uuid, needed: bool) -> DamResult<()> {
+        let mut document = self.find_document_by_asset_id(&asset_id)?
+            .ok_or_else(|| IndexError::DocumentNotFound(format!("Asset not found: {}", asset_id)))?;
+        if document.needs_deep_processing == needed {
+            return Ok(());
+        }
+        document.needs_deep_processing = needed;
+        self.store_document(&document).await
+    }
+    
+    /// Record the progress of one AI processing step on an asset
+    /// 
+    /// Only the status is stored; results go through
+    /// `update_with_ai_results`. Task types that are not tracked steps
+    /// (image editing, video analysis) are ignored.
+    pub async fn set_processing_step(&mut self, asset_id: Uuid, task_type: &ProcessingTaskType, status: StepStatus) -> DamResult<()> {
+        let mut document = self.find_document_by_asset_id(&asset_id)?
+            .ok_or_else(|| IndexError::DocumentNotFound(format!("Asset not found: {}", asset_id)))?;
+        
+        let Some(step) = document.processing_status.step_mut(task_type) else {
+            return Ok(());
+        };
+        if *step == status {
+            return Ok(());
+        }
+        *step = status;
+        
+        self.store_document(&document).await?;
+        self.processing.insert(&document);
+        Ok(())
+    }
+    
+    /// Apply a processing queue event to the asset it concerns
+    /// 
+    /// `Started` marks the step in progress, `Failed` records the reason
+    /// and `Completed` stores the result and marks the step done. Other
+    /// messages are ignored.
+    pub async fn apply_processing_event(&mut self, event: &ProcessMessage) -> DamResult<()> {
+        match event {
+            ProcessMessage::Started { asset_id, task_type, .. } => {
+                self.set_processing_step(*asset_id, task_type, StepStatus::InProgress).await
+            }
+            ProcessMessage::Failed { asset_id, task_type, error, .. } => {
+                self.set_processing_step(*asset_id, task_type, StepStatus::Failed { reason: error.clone() }).await
+            }
+            ProcessMessage::Completed { asset_id, result, .. } => match result.clone() {
+                ProcessingResult::Tags { tags } => {
+                    self.update_with_ai_results(*asset_id, Some(tags), None, None, None, None).await
+                }
+                ProcessingResult::Transcription { text } => {
+                    self.update_with_ai_results(*asset_id, None, None, Some(text), None, None).await
+                }
+                ProcessingResult::Embedding { vector } => {
+                    self.update_with_ai_results(*asset_id, None, None, None, None, Some(vector)).await
+                }
+                ProcessingResult::Combined { tags, embedding, transcription } => {
+                    self.update_with_ai_results(*asset_id, tags, None, transcription, embedding, None).await
+                }
+                ProcessingResult::EditedImage { .. } => Ok(()),
+            },
+            _ => Ok(()),
+        }
+    }
+    
+    /// Iterate over AI processing steps that failed, for retrying
+    /// 
+    /// Yields `(asset_id, file_path, task_type)` for every failed step, so
+    /// only that step needs to be queued again.
+    pub fn iter_failed_processing(&self) -> impl Iterator<Item = (Uuid, PathBuf, ProcessingTaskType)> + '_ {
+        self.iter_documents()
+            .filter_map(Result::ok)
+            .flat_map(|document| {
+                document.processing_status.failed_steps()
+                    .into_iter()
+                    .map(move |task_type| (document.asset_id, document.file_path.clone(), task_type))
+            })
+    }
+    
+    /// Iterate over indexed assets that are still missing an AI result
+    /// 
+    /// Yields `(asset_id, file_path)` for every document whose asset type
+    /// supports `kind` but has no stored result yet. Documents are read
+    /// lazily from storage in key order, so a backfill job that is
+    /// interrupted can simply call this again: assets already updated via
+    /// `update_with_ai_results` are skipped on the next pass.
+    pub fn iter_unprocessed(&self, kind: AiKind) -> impl Iterator<Item = (Uuid, PathBuf)> + '_ {
+        self.doc_store.iter()
+            .filter_map(|result| match result {
+                Ok((_, value)) => serde_json::from_slice::<AssetDocument>(&value).ok(),
+                Err(e) => {
+                    warn!("Failed to read document while scanning for unprocessed assets: {}", e);
+                    None
+                }
+            })
+            .filter(move |document| kind.applies_to(&document.asset_type) && !document.has_ai_result(kind))
+            .map(|document| (document.asset_id, document.file_path))
+    }
+    
+    /// Update the stored path of an asset after it was moved on disk
+    /// 
+    /// Refreshes `file_path`/`filename` and re-tokenizes the filename, keeping
+    /// tags, AI results and embeddings untouched. The title is only updated
+    /// if it still mirrors the old filename.
+    pub async fn update_asset_path<P: AsRef<Path>>(&mut self, asset_id: Uuid, new_path: P) -> DamResult<()> {
+        let new_path = new_path.as_ref();
+        
+        let mut document = self.find_document_by_asset_id(&asset_id)?
+            .ok_or_else(|| IndexError::DocumentNotFound(format!("Asset not found: {}", asset_id)))?;
+        
+        let filename = new_path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        
+        if document.title == document.filename {
+            document.title = filename.clone();
+        }
+        document.file_path = new_path.to_path_buf();
+        document.filename = filename;
+        document.update_search_text();
+        
+        self.store_document(&document).await?;
+        self.text_index.add_document(&document)?;
+        
+        debug!("Updated path for asset {}: {}", asset_id, new_path.display());
+        Ok(())
+    }
+    
+    /// Replace the stored preview of an asset after regenerating it
+    pub async fn update_preview(&mut self, asset_id: Uuid, preview: &PreviewInfo) -> DamResult<()> {
+        let mut document = self.find_document_by_asset_id(&asset_id)?
+            .ok_or_else(|| IndexError::DocumentNotFound(format!("Asset not found: {}", asset_id)))?;
+        
+        document.preview_path = Some(preview.thumbnail_path.clone());
+        document.thumbnail_path = Some(preview.thumbnail_path.clone());
+        if preview.waveform.is_some() {
+            document.waveform = preview.waveform.clone();
+        }
+        if preview.contact_sheet_grid.is_some() {
+            document.contact_sheet_path = preview.rendered_preview.clone();
+            document.contact_sheet_grid = preview.contact_sheet_grid;
+        }
+        document.calculate_quality_score();
+        
+        self.store_document(&document).await?;
+        
+        debug!("Updated preview for asset {}", asset_id);
+        Ok(())
+    }
+    
+    /// Min/max peaks of an audio asset in `buckets` buckets, for drawing an
+    /// interactive waveform
+    /// 
+    /// Peaks are computed once when the preview is generated and only
+    /// combined here. Returns an empty list for assets without a waveform.
+    pub fn get_waveform(&self, asset_id: Uuid, buckets: usize) -> DamResult<Vec<(f32, f32)>> {
+        let document = self.find_document_by_asset_id(&asset_id)?
+            .ok_or_else(|| IndexError::DocumentNotFound(format!("Asset not found: {}", asset_id)))?;
+        
+        Ok(document.waveform.map(|waveform| waveform.resample(buckets)).unwrap_or_default())
+    }
+    
+    /// Document and visually similar assets of an asset, for detail views
+    /// 
+    /// Saves callers a document lookup plus a `find_similar` call. Up to
+    /// `DETAIL_SIMILAR_ASSETS` similar assets are listed, most similar
+    /// first; assets without a visual embedding list none.
+    pub fn get_asset_details(&self, asset_id: Uuid) -> DamResult<AssetDetails> {
+        let document = self.find_document_by_asset_id(&asset_id)?
+            .ok_or_else(|| IndexError::DocumentNotFound(format!("Asset not found: {}", asset_id)))?;
+        
+        let mut similar = Vec::new();
+        if document.visual_embedding.is_some() {
+            let vector_matches = self.vector_store.find_similar_to_document(
+                &document.id,
+                EmbeddingType::Visual,
+                DETAIL_SIMILAR_ASSETS,
+                self.config.similarity_threshold()
+            )?;
+            
+            for vector_match in vector_matches {
+                if let Some(similar_document) = self.get_document(&vector_match.document_id)? {
+                    similar.push((similar_document.asset_id, vector_match.similarity));
+                }
+            }
+        }
+        
+        Ok(AssetDetails { document, similar })
+    }
+    
+    /// Set or clear the star rating of an asset
+    /// 
+    /// Only the stored document is rewritten; the text index is untouched.
+    pub async fn set_rating(&mut self, asset_id: Uuid, rating: Option<u8>) -> DamResult<()> {
+        if let Some(rating) = rating.filter(|rating| *rating > MAX_RATING) {
+            return Err(DamError::invalid_operation(format!(
+                "Rating must be between 0 and {}, got {}", MAX_RATING, rating
+            )));
+        }
+        
+        let mut document = self.find_document_by_asset_id(&asset_id)?
+            .ok_or_else(|| IndexError::DocumentNotFound(format!("Asset not found: {}", asset_id)))?;
+        document.rating = rating;
+        self.store_document(&document).await?;
+        
+        debug!("Set rating of asset {} to {:?}", asset_id, rating);
+        Ok(())
+    }
+    
+    /// Mark or unmark an asset as favorite
+    /// 
+    /// Only the stored document is rewritten; the text index is untouched.
+    pub async fn set_favorite(&mut self, asset_id: Uuid, favorite: bool) -> DamResult<()> {
+        let mut document = self.find_document_by_asset_id(&asset_id)?
+            .ok_or_else(|| IndexError::DocumentNotFound(format!("Asset not found: {}", asset_id)))?;
+        document.favorite = favorite;
+        self.store_document(&document).await?;
+        
+        debug!("Set favorite of asset {} to {}", asset_id, favorite);
+        Ok(())
+    }
+    
+    /// Set a custom metadata field of an asset, such as a project code or license
+    /// 
+    /// The value becomes searchable text and can be filtered on with
+    /// `custom:key=value`. It overrides a value read from the file under the
+    /// same key and survives re-indexing. Keys cannot be empty or contain
+    /// whitespace or `=`.
+    pub async fn set_custom_metadata(&mut self, asset_id: Uuid, key: &str, value: &str) -> DamResult<()> {
+        if key.is_empty() || key.contains(|c: char| c.is_whitespace() || c == '=') {
+            return Err(DamError::invalid_operation(format!(
+                "Invalid custom metadata key: '{}'", key
+            )));
+        }
+        
+        let mut document = self.find_document_by_asset_id(&asset_id)?
+            .ok_or_else(|| IndexError::DocumentNotFound(format!("Asset not found: {}", asset_id)))?;
+        document.user_metadata.insert(key.to_string(), value.to_string());
+        self.reindex_custom_metadata(&mut document).await?;
+        
+        debug!("Set custom metadata {}={} on asset {}", key, value, asset_id);
+        Ok(())
+    }
+    
+    /// Remove a custom metadata field a user set on an asset
+    /// 
+    /// Returns whether the field was set. A value read from the file under
+    /// the same key shows again.
+    pub async fn remove_custom_metadata(&mut self, asset_id: Uuid, key: &str) -> DamResult<bool> {
+        let mut document = self.find_document_by_asset_id(&asset_id)?
+            .ok_or_else(|| IndexError::DocumentNotFound(format!("Asset not found: {}", asset_id)))?;
+        if document.user_metadata.remove(key).is_none() {
+            return Ok(false);
+        }
+        self.reindex_custom_metadata(&mut document).await?;
+        
+        debug!("Removed custom metadata {} from asset {}", key, asset_id);
+        Ok(true)
+    }
+    
+    /// Refresh the searchable text of a document after a custom metadata edit
+    async fn reindex_custom_metadata(&mut self, document: &mut AssetDocument) -> DamResult<()> {
+        document.update_search_text();
+        self.store_document(document).await?;
+        self.text_index.add_document(document)?;
+        Ok(())
+    }
+    
+    /// IDs of all indexed assets
+    pub fn asset_ids(&self) -> DamResult<Vec<Uuid>> {
+        let mut ids = Vec::new();
         for result in self.doc_store.iter() {
             let (_, value) = result.map_err(|e| IndexError::DatabaseError(e.to_string()))?;
             if let Ok(document) = serde_json::from_slice::<AssetDocument>(&value) {
-                if document.asset_id == *asset_id {
-                    return Ok(Some(document));
+                ids.push(document.asset_id);
+            }
+        }
+        Ok(ids)
+    }
+    
+    /// Stream every stored document in key order
+    /// 
+    /// Documents are read and deserialized lazily, one at a time. A record
+    /// that cannot be read or deserialized yields an `Err` item and the
+    /// iteration continues with the next record.
+    pub fn iter_documents(&self) -> impl Iterator<Item = DamResult<AssetDocument>> + '_ {
+        self.doc_store.iter().map(|result| {
+            let (key, value) = result.map_err(|e| IndexError::DatabaseError(e.to_string()))?;
+            serde_json::from_slice::<AssetDocument>(&value).map_err(|e| {
+                let doc_id = Uuid::from_slice(&key).map(|id| id.to_string()).unwrap_or_default();
+                IndexError::SerializationError(format!("Invalid document {}: {}", doc_id, e)).into()
+            })
+        })
+    }
+    
+    /// Re-run format detection on assets indexed as `Unknown` or with an
+    /// unsupported format
+    /// 
+    /// The index cannot detect formats itself, so `upgrade` is called with
+    /// each such document and returns the re-ingested asset (same
+    /// asset ID) if a current detector recognizes the file, e.g. via
+    /// `IngestService::upgrade_asset`. Upgraded assets are reindexed under
+    /// their existing document. A failure is recorded in the report and
+    /// does not stop the batch.
+    pub async fn redetect_unknowns<F, Fut>(&mut self, mut upgrade: F) -> DamResult<RedetectReport>
+    where
+        F: FnMut(AssetDocument) -> Fut,
+        Fut: std::future::Future<Output = DamResult<Option<Asset>>>,
+    {
+        let unknowns: Vec<AssetDocument> = self.iter_documents()
+            .filter_map(|result| result.map_err(|e| warn!("Skipping unreadable document: {}", e)).ok())
+            .filter(|document| document.asset_type == AssetType::Unknown || !document.format_supported)
+            .collect();
+        info!("Re-running format detection on {} unknown or unsupported assets", unknowns.len());
+        
+        let mut report = RedetectReport { checked: unknowns.len(), ..RedetectReport::default() };
+        let title = "Re-detecting unknown files";
+        self.events.progress(title, 0, report.checked, None);
+        for (position, document) in unknowns.into_iter().enumerate() {
+            let asset_id = document.asset_id;
+            let result = match upgrade(document).await {
+                Ok(Some(asset)) if asset.id != asset_id => Err(DamError::invalid_operation(format!(
+                    "Upgraded asset {} does not match document of asset {}", asset.id, asset_id
+                ))),
+                Ok(Some(asset)) => self.index_asset(&asset).await.map(|()| true),
+                Ok(None) => Ok(false),
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(true) => report.upgraded.push(asset_id),
+                Ok(false) => {}
+                Err(e) => {
+                    warn!("Failed to upgrade asset {}: {}", asset_id, e);
+                    report.failed.push((asset_id, e.to_string()));
+                }
+            }
+            self.events.progress(title, position + 1, report.checked, None);
+        }
+        
+        info!("Upgraded {} of {} unknown or unsupported assets", report.upgraded.len(), report.checked);
+        self.events.hide_progress();
+        let level = if report.failed.is_empty() { NotificationLevel::Success } else { NotificationLevel::Warning };
+        self.events.notify(
+            level,
+            "Format detection finished",
+            format!("Upgraded {} of {} assets, {} failed", report.upgraded.len(), report.checked, report.failed.len()),
+        );
+        Ok(report)
+    }
+    
+    /// Group assets into a timeline by their best available date
+    /// 
+    /// Uses the capture date, falling back to `created_at`. Buckets are
+    /// ordered newest first and hold asset IDs, newest first; assets with no
+    /// plausible date are collected in a trailing `DateBucket::Undated`.
+    pub fn group_by_date(&self, granularity: DateGranularity) -> DamResult<Vec<(DateBucket, Vec<Uuid>)>> {
+        let documents: Vec<AssetDocument> = self.iter_documents()
+            .filter_map(|result| result.map_err(|e| warn!("Skipping unreadable document: {}", e)).ok())
+            .collect();
+        
+        Ok(group_documents(&documents, granularity)
+            .into_iter()
+            .map(|(bucket, documents)| (bucket, documents.into_iter().map(|document| document.asset_id).collect()))
+            .collect())
+    }
+    
+    /// Indexed document of the asset whose file is at `path`
+    /// 
+    /// Scans every document, so it suits occasional lookups such as a
+    /// changed sidecar, not bulk use.
+    pub fn find_document_by_path(&self, path: &Path) -> DamResult<Option<AssetDocument>> {
+        for document in self.iter_documents() {
+            match document {
+                Ok(document) if document.file_path == path => return Ok(Some(document)),
+                Ok(_) => {}
+                Err(e) => warn!("Skipping unreadable document: {}", e),
+            }
+        }
+        Ok(None)
+    }
+    
+    /// Get the indexed document for an asset
+    pub fn get_asset_document(&self, asset_id: Uuid) -> DamResult<Option<AssetDocument>> {
+        self.find_document_by_asset_id(&asset_id)
+    }
+    
+    /// Remove an asset from the index
+    pub async fn remove_asset(&mut self, asset_id: Uuid) -> DamResult<()> {
+        debug!("Removing asset from index: {}", asset_id);
+        
+        // Find document
+        if let Some(document) = self.find_document_by_asset_id(&asset_id)? {
+            // Delete from storage first, then from the in-memory indexes
+            self.delete_document(&document.id).await?;
+            self.forget_document(&document);
+            
+            debug!("Successfully removed asset from index: {}", asset_id);
+        }
+        
+        Ok(())
+    }
+    
+    /// Remove many assets with a single database write
+    /// 
+    /// Returns one result per ID, in order; IDs that are not indexed are
+    /// reported as `DocumentNotFound` and do not affect the rest.
+    pub async fn remove_assets(&mut self, asset_ids: &[Uuid]) -> DamResult<Vec<DamResult<()>>> {
+        info!("Removing batch of {} assets from index", asset_ids.len());
+        
+        let wanted: HashSet<Uuid> = asset_ids.iter().copied().collect();
+        let mut documents = self.find_documents_by_asset_ids(&wanted)?;
+        
+        let mut batch = sled::Batch::default();
+        let mut removed = HashSet::new();
+        let mut removed_documents: Vec<AssetDocument> = Vec::new();
+        let mut results = Vec::with_capacity(asset_ids.len());
+        
+        for asset_id in asset_ids {
+            if let Some(document) = documents.remove(asset_id) {
+                batch.remove(document.id.as_bytes());
+                removed_documents.push(document);
+                removed.insert(*asset_id);
+                results.push(Ok(()));
+            } else if removed.contains(asset_id) {
+                // Listed twice; already removed by this batch
+                results.push(Ok(()));
+            } else {
+                results.push(Err(IndexError::DocumentNotFound(format!("Asset not found: {}", asset_id)).into()));
+            }
+        }
+        
+        // As with indexing, memory only changes once the store has
+        self.apply_batch(batch).await?;
+        for document in &removed_documents {
+            self.forget_document(document);
+        }
+        
+        debug!("Removed {} of {} assets from index", removed.len(), asset_ids.len());
+        Ok(results)
+    }
+    
+    /// Search for assets using text query
+    pub async fn search_text(&self, query: &str, max_results: usize) -> DamResult<Vec<SearchResult>> {
+        self.search_text_weighted(query, max_results, &self.config.field_weights, false).await
+    }
+    
+    /// Search for assets using text query with per-query field weights
+    /// 
+    /// With `explain`, each result carries a `ScoreExplanation`. Queries
+    /// with `custom:key=value` terms are filtered on custom metadata, and
+    /// `speaker:label` restricts transcript matches to that speaker.
+    pub async fn search_text_weighted(&self, query: &str, max_results: usize, weights: &FieldWeights, explain: bool) -> DamResult<Vec<SearchResult>> {
+        let key = self.query_cache_key("text", query, &(max_results, weights, explain))?;
+        if let Some(results) = key.as_deref().and_then(|key| self.query_cache().get(key)) {
+            debug!("Text search query '{}' served from cache", query);
+            return Ok(results);
+        }
+        
+        let results = self.search_text_uncached(query, max_results, weights, explain).await?;
+        if let Some(key) = key {
+            self.query_cache().insert(key, results.clone());
+        }
+        Ok(results)
+    }
+    
+    /// Text search whose results arrive one at a time, best first
+    /// 
+    /// Matches are ranked in memory and the best `max_results` kept in a
+    /// bounded heap; a result's document is only read from the database
+    /// when the stream reaches it, so the first page can be shown before
+    /// the rest is loaded. Results and their order equal `search_text`,
+    /// but no total is known up front. Queries with filters, and libraries
+    /// with type boosts, need every document to rank and are searched in
+    /// full before the first result. Documents that fail to load are
+    /// skipped with a warning.
+    /// 
+    /// The stream holds its own handle to the database rather than a
+    /// borrow of the service, so a `SharedIndex` read guard can be dropped
+    /// once it is returned, and it yields to other tasks between reads.
+    pub async fn search_text_stream(&self, query: &str, max_results: usize) -> DamResult<BoxStream<'static, SearchResult>> {
+        let weights = &self.config.field_weights;
+        weights.validate()?;
+        let key = self.query_cache_key("text", query, &(max_results, weights, false))?;
+        if let Some(results) = key.as_deref().and_then(|key| self.query_cache().get(key)) {
+            debug!("Text search query '{}' served from cache", query);
+            return Ok(stream::iter(results).boxed());
+        }
+        
+        let mut filtered = SearchQuery::text_search(query);
+        filtered.extract_filters();
+        if !filtered.custom.is_empty() || filtered.speaker.is_some() || !self.config.type_boosts.is_neutral() {
+            let results = self.search_text(query, max_results).await?;
+            return Ok(stream::iter(results).boxed());
+        }
+        
+        let top = self.text_index.top_matches(query, self.effective_max_results(max_results), weights)?;
+        debug!("Streaming {} of {} text matches for '{}'", top.len(), top.seen(), query);
+        let state = (self.doc_store.clone(), self.config.type_boosts.clone(), top.into_best_first());
+        let results = stream::unfold(state, |(doc_store, type_boosts, mut matches)| async move {
+            loop {
+                let text_match = matches.next()?;
+                tokio::task::yield_now().await;
+                match load_document(&doc_store, &text_match.document_id) {
+                    Ok(Some(document)) => {
+                        let boost = type_boosts.get(&document.asset_type);
+                        let result = match_result(document, &text_match, boost);
+                        return Some((result, (doc_store, type_boosts, matches)));
+                    }
+                    Ok(None) => {}
+                    Err(e) => warn!("Skipping streamed search result: {}", e),
+                }
+            }
+        });
+        Ok(results.boxed())
+    }
+    
+    async fn search_text_uncached(&self, query: &str, max_results: usize, weights: &FieldWeights, explain: bool) -> DamResult<Vec<SearchResult>> {
+        debug!("Text search query: '{}'", query);
+        weights.validate()?;
+        
+        let mut filtered = SearchQuery::text_search(query).limit(max_results);
+        filtered.explain = explain;
+        filtered.extract_filters();
+        if !filtered.custom.is_empty() || filtered.speaker.is_some() {
+            return self.search_with_weights(&filtered, weights).await;
+        }
+        
+        let max_results = self.effective_max_results(max_results);
+        
+        // Fetch extra candidates when boosts may reorder results
+        let type_boosts = &self.config.type_boosts;
+        let candidates = if type_boosts.is_neutral() {
+            max_results
+        } else {
+            max_results.saturating_mul(2)
+        };
+        
+        let text_matches = self.text_index.search_with_weights(query, candidates, weights)?;
+        let mut results = self.text_results(text_matches, type_boosts, weights, explain, None)?;
+        
+        if !type_boosts.is_neutral() {
+            results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+            results.truncate(max_results);
+        }
+        
+        debug!("Text search returned {} results", results.len());
+        Ok(results)
+    }
+    
+    /// Load the documents of text matches and build boosted results
+    /// 
+    /// `weights` must be the field weights the matches were scored with;
+    /// they are only read to explain scores. With a `speaker`, only
+    /// transcript words that speaker said count as matches, and documents
+    /// left without any are dropped.
+    fn text_results(
+        &self,
+        text_matches: Vec<TextMatch>,
+        type_boosts: &TypeBoosts,
+        weights: &FieldWeights,
+        explain: bool,
+        speaker: Option<&str>,
+    ) -> DamResult<Vec<SearchResult>> {
+        let mut results = Vec::new();
+        
+        for text_match in text_matches {
+            if let Some(result) = self.text_result(text_match, type_boosts, weights, explain, speaker)? {
+                results.push(result);
+            }
+        }
+        
+        Ok(results)
+    }
+    
+    /// Result of a single text match, None if its document is gone or
+    /// the speaker filter drops it
+    fn text_result(
+        &self,
+        mut text_match: TextMatch,
+        type_boosts: &TypeBoosts,
+        weights: &FieldWeights,
+        explain: bool,
+        speaker: Option<&str>,
+    ) -> DamResult<Option<SearchResult>> {
+        let Some(document) = self.get_document(&text_match.document_id)? else {
+            return Ok(None);
+        };
+        
+        if let Some(speaker) = speaker {
+            let total: f32 = text_match.matches.iter().map(|m| m.score).sum();
+            text_match.matches.retain(|field_match| {
+                field_match.field_name == "transcription" && document.is_spoken_by(field_match.position, speaker)
+            });
+            if text_match.matches.is_empty() {
+                return Ok(None);
+            }
+            // Score only what the speaker said
+            let kept: f32 = text_match.matches.iter().map(|m| m.score).sum();
+            if total > 0.0 {
+                text_match.score *= kept / total;
+            }
+        }
+        
+        let boost = type_boosts.get(&document.asset_type);
+        let mut result = match_result(document, &text_match, boost);
+        
+        if explain {
+            let terms = self.explain_terms(&text_match.matches, weights);
+            let term_total: f32 = terms.iter().map(|term| term.score).sum();
+            result.explanation = Some(ScoreExplanation {
+                terms,
+                phrase_multiplier: if term_total != 0.0 { text_match.score / term_total } else { 1.0 },
+                text_score: text_match.score,
+                text_weight: 1.0,
+                type_boost: boost,
+                score: result.score,
+                ..ScoreExplanation::default()
+            });
+        }
+        
+        Ok(Some(result))
+    }
+    
+    /// Per-term, per-field breakdown of the matches of one document
+    fn explain_terms(&self, matches: &[FieldMatch], weights: &FieldWeights) -> Vec<TermExplanation> {
+        let mut terms: Vec<TermExplanation> = Vec::new();
+        
+        for field_match in matches {
+            let existing = terms.iter_mut()
+                .find(|term| term.term == field_match.match_text && term.field == field_match.field_name);
+            match existing {
+                Some(term) => {
+                    term.term_frequency += 1;
+                    term.score += field_match.score;
+                }
+                None => terms.push(TermExplanation {
+                    term: field_match.match_text.clone(),
+                    field: field_match.field_name.clone(),
+                    term_frequency: 1,
+                    idf: self.text_index.term_idf(&field_match.match_text),
+                    boost: weights.get(&field_match.field_name),
+                    score: field_match.score,
+                }),
+            }
+        }
+        
+        terms
+    }
+    
+    /// Search with the filters, sort order and paging of a `SearchQuery`
+    /// 
+    /// Without query text every document is a candidate, ranked by quality
+    /// score. `semantic_query` needs an embedding and is not handled here;
+    /// use `search_hybrid` for that. `custom:key=value` terms in the text
+    /// are added to the custom metadata filters and a `speaker:label` term
+    /// sets the speaker filter.
+    pub async fn search(&self, query: &SearchQuery) -> DamResult<Vec<SearchResult>> {
+        // The text is part of the key in normalized form only
+        let params = SearchQuery { text: None, ..query.clone() };
+        let key = self.query_cache_key("search", query.text.as_deref().unwrap_or(""), &params)?;
+        if let Some(results) = key.as_deref().and_then(|key| self.query_cache().get(key)) {
+            debug!("Search served from cache");
+            return Ok(results);
+        }
+        
+        let results = self.search_with_weights(query, &self.config.field_weights).await?;
+        if let Some(key) = key {
+            self.query_cache().insert(key, results.clone());
+        }
+        Ok(results)
+    }
+    
+    /// `search` with explicit field weights for the text part
+    async fn search_with_weights(&self, query: &SearchQuery, weights: &FieldWeights) -> DamResult<Vec<SearchResult>> {
+        let mut query = query.clone();
+        query.extract_filters();
+        let query = &query;
+        
+        let limit = self.effective_max_results(query.limit.unwrap_or(self.config.max_results));
+        let offset = query.offset.unwrap_or(0);
+        let text = query.text.as_deref().unwrap_or("").trim();
+        debug!("Search query: '{}' (limit {}, offset {})", text, limit, offset);
+        
+        let mut results = if text.is_empty() {
+            self.iter_documents()
+                .filter_map(|document| match document {
+                    Ok(document) => {
+                        let score = document.quality_score;
+                        let mut result = SearchResult::new(document, score);
+                        if query.explain {
+                            result.explanation = Some(ScoreExplanation {
+                                quality_multiplier: score,
+                                score,
+                                ..ScoreExplanation::default()
+                            });
+                        }
+                        Some(result)
+                    }
+                    Err(e) => {
+                        warn!("Skipping document during search: {}", e);
+                        None
+                    }
+                })
+                .collect()
+        } else {
+            // Filters apply after ranking, so every text match is a candidate
+            let text_matches = self.text_index.search_with_weights(text, usize::MAX, weights)?;
+            self.text_results(text_matches, &self.config.type_boosts, weights, query.explain, query.speaker.as_deref())?
+        };
+        
+        results.retain(|result| result.document.matches_filters(query));
+        sort_results(&mut results, query.sort.as_ref().unwrap_or(&SortCriteria::Relevance));
+        
+        let results: Vec<SearchResult> = results.into_iter().skip(offset).take(limit).collect();
+        debug!("Search returned {} results", results.len());
+        Ok(results)
+    }
+    
+    /// Hybrid search with results grouped by asset type
+    /// 
+    /// Each group holds the `per_group_limit` best matches of its type,
+    /// ranked within the group, and how many matches the type had in all.
+    /// Groups are ordered by their best result. Results are scored as in
+    /// `search_hybrid`: text matches, joined by cross-modal matches when a
+    /// text encoder is set. The text index is searched once and matches are
+    /// split by the document types kept in memory, so only the documents
+    /// that make it into a group are loaded, rather than running one
+    /// filtered search per type. An empty query lists every asset by
+    /// quality, like `search("")`. Queries with `custom:` or `speaker:`
+    /// terms need every document and go through `search`, whose result cap
+    /// then also bounds the group totals, as it bounds cross-modal matches.
+    pub async fn search_grouped(&self, query: &str, per_group_limit: usize) -> DamResult<Vec<SearchGroup>> {
+        debug!("Grouped search query: '{}'", query);
+        let per_group_limit = self.effective_max_results(per_group_limit);
+        let weights = &self.config.field_weights;
+        let type_boosts = &self.config.type_boosts;
+        let mut groups: Vec<SearchGroup> = Vec::new();
+        
+        let mut filtered = SearchQuery::text_search(query).limit(self.config.max_results);
+        filtered.extract_filters();
+        let text = filtered.text.as_deref().unwrap_or("").trim();
+        let semantic = self.cross_modal_encoder.is_some() && !text.is_empty();
+        // Text matches counted in a group, kept or not
+        let mut matched = HashSet::new();
+        
+        let mut results = if !filtered.custom.is_empty() || filtered.speaker.is_some() {
+            let results = self.search_with_weights(&filtered, weights).await?;
+            for result in &results {
+                group_mut(&mut groups, &result.document.asset_type).total += 1;
+            }
+            results
+        } else if text.is_empty() {
+            let mut best: HashMap<AssetType, TopK<SearchResult>> = HashMap::new();
+            for document in self.iter_documents() {
+                let document = match document {
+                    Ok(document) => document,
+                    Err(e) => {
+                        warn!("Skipping document during grouped search: {}", e);
+                        continue;
+                    }
+                };
+                group_mut(&mut groups, &document.asset_type).total += 1;
+                let score = document.quality_score;
+                best.entry(document.asset_type.clone())
+                    .or_insert_with(|| TopK::new(per_group_limit))
+                    .push(score, SearchResult::new(document, score));
+            }
+            best.into_values().flat_map(TopK::into_best_first).collect()
+        } else {
+            // Matches arrive best first, so each group keeps its leading ones;
+            // vector scores can still reorder them, as in `search_hybrid`
+            let candidates = if semantic { per_group_limit.saturating_mul(2) } else { per_group_limit };
+            let mut kept = Vec::new();
+            for text_match in self.text_index.search_with_weights(text, usize::MAX, weights)? {
+                let Some(asset_type) = self.processing.asset_type(&text_match.document_id) else {
+                    continue;
+                };
+                let group = group_mut(&mut groups, asset_type);
+                group.total += 1;
+                matched.insert(text_match.document_id);
+                if group.total <= candidates {
+                    kept.push(text_match);
+                }
+            }
+            self.text_results(kept, type_boosts, weights, false, None)?
+        };
+        
+        if !text.is_empty() {
+            for result in &mut results {
+                result.calculate_weighted_score_with(&self.config, type_boosts);
+            }
+        }
+        
+        if semantic {
+            matched.extend(results.iter().map(|result| result.document.id));
+            match self.search_cross_modal(text, self.config.max_results, None).await {
+                Ok(vector_results) => {
+                    let positions: HashMap<Uuid, usize> = results.iter().enumerate()
+                        .map(|(position, result)| (result.document.id, position))
+                        .collect();
+                    for mut result in vector_results {
+                        if !result.document.matches_filters(&filtered) {
+                            continue;
+                        }
+                        if let Some(&position) = positions.get(&result.document.id) {
+                            let existing = &mut results[position];
+                            existing.vector_score = result.vector_score;
+                            existing.calculate_weighted_score_with(&self.config, type_boosts);
+                            existing.match_reason = format!("{} + Visual similarity", existing.match_reason);
+                        } else if !matched.contains(&result.document.id) {
+                            result.calculate_weighted_score_with(&self.config, type_boosts);
+                            group_mut(&mut groups, &result.document.asset_type).total += 1;
+                            results.push(result);
+                        }
+                    }
                 }
+                Err(e) => warn!("Grouped search continues without cross-modal matches: {}", e),
+            }
+        }
+        
+        for result in results {
+            group_mut(&mut groups, &result.document.asset_type).results.push(result);
+        }
+        for group in &mut groups {
+            sort_results(&mut group.results, &SortCriteria::Relevance);
+            group.results.truncate(per_group_limit);
+        }
+        
+        let best = |group: &SearchGroup| group.results.first().map_or(0.0, |result| result.score);
+        groups.sort_by(|a, b| best(b).partial_cmp(&best(a)).unwrap_or(std::cmp::Ordering::Equal));
+        
+        debug!("Grouped search returned {} groups", groups.len());
+        Ok(groups)
+    }
+    
+    /// Search for visually similar assets
+    /// 
+    /// `min_similarity` overrides the configured threshold for this query.
+    /// Results are diversified by the configured `similarity_diversity`.
+    pub async fn search_visual_similar(&self, query_embedding: &[f32], max_results: usize, min_similarity: Option<f32>) -> DamResult<Vec<SearchResult>> {
+        self.search_visual_similar_with_diversity(query_embedding, max_results, min_similarity, self.config.similarity_diversity).await
+    }
+    
+    /// `search_visual_similar` with a per-query diversity in [0, 1]
+    /// 
+    /// Above 0, results are re-ranked by Maximal Marginal Relevance so that
+    /// near-duplicates of a higher result make way for other matches; the
+    /// first result is still the most similar one.
+    pub async fn search_visual_similar_with_diversity(
+        &self,
+        query_embedding: &[f32],
+        max_results: usize,
+        min_similarity: Option<f32>,
+        diversity: f32,
+    ) -> DamResult<Vec<SearchResult>> {
+        debug!("Visual similarity search with {} dimensional embedding", query_embedding.len());
+        let max_results = self.effective_max_results(max_results);
+        
+        let vector_matches = self.vector_store.find_visual_similar(
+            query_embedding, 
+            diversity_candidates(max_results, diversity)?, 
+            self.config.query_similarity_threshold(min_similarity)?
+        )?;
+        let vector_matches = self.vector_store.diversify(vector_matches, max_results, diversity);
+        
+        let mut results = Vec::new();
+        
+        for vector_match in vector_matches {
+            if let Some(document) = self.get_document(&vector_match.document_id)? {
+                let mut result = SearchResult::new(document, vector_match.similarity);
+                result.vector_score = vector_match.similarity;
+                result.match_reason = "Visual similarity".to_string();
+                
+                results.push(result);
+            }
+        }
+        
+        debug!("Visual similarity search returned {} results", results.len());
+        Ok(results)
+    }
+    
+    /// Search for assets whose text is semantically similar to a query
+    /// 
+    /// Long texts stored as chunks score by their best matching chunk.
+    /// `min_similarity` overrides the configured threshold for this query.
+    pub async fn search_text_similar(&self, query_embedding: &[f32], max_results: usize, min_similarity: Option<f32>) -> DamResult<Vec<SearchResult>> {
+        debug!("Text similarity search with {} dimensional embedding", query_embedding.len());
+        let max_results = self.effective_max_results(max_results);
+        
+        let vector_matches = self.vector_store.find_text_similar(
+            query_embedding,
+            max_results,
+            self.config.query_similarity_threshold(min_similarity)?
+        )?;
+        
+        let mut results = Vec::new();
+        
+        for vector_match in vector_matches {
+            if let Some(document) = self.get_document(&vector_match.document_id)? {
+                let mut result = SearchResult::new(document, vector_match.similarity);
+                result.vector_score = vector_match.similarity;
+                result.match_reason = "Text similarity".to_string();
+                
+                results.push(result);
+            }
+        }
+        
+        debug!("Text similarity search returned {} results", results.len());
+        Ok(results)
+    }
+    
+    /// Search images by a description of their content
+    /// 
+    /// The query is embedded by the cross-modal encoder, e.g. CLIP's text
+    /// tower, and compared with the stored visual embeddings, so untagged
+    /// photos are found too. `min_similarity` overrides the configured
+    /// `cross_modal_min_similarity`. Fails if no encoder is set.
+    pub async fn search_cross_modal(&self, text_query: &str, max_results: usize, min_similarity: Option<f32>) -> DamResult<Vec<SearchResult>> {
+        let encoder = self.cross_modal_encoder.clone()
+            .ok_or_else(|| DamError::configuration("No cross-modal text encoder is set"))?;
+        let text_query = text_query.trim().to_string();
+        if text_query.is_empty() {
+            return Ok(Vec::new());
+        }
+        debug!("Cross-modal search for '{}'", text_query);
+        
+        // The forward pass blocks, so it runs off the async runtime
+        let query_embedding = tokio::task::spawn_blocking(move || encoder.encode_text(&text_query))
+            .await
+            .map_err(|e| DamError::invalid_operation(format!("Text encoder task failed: {}", e)))??;
+        
+        // Distance metrics use `max_distance` instead
+        let min_similarity = min_similarity
+            .or_else(|| (!self.config.distance_metric.is_distance()).then_some(self.config.cross_modal_min_similarity));
+        let mut results = self.search_visual_similar(&query_embedding, max_results, min_similarity).await?;
+        for result in &mut results {
+            result.match_reason = "Semantic visual match".to_string();
+        }
+        Ok(results)
+    }
+    
+    /// Find assets similar to a specific asset
+    /// 
+    /// `min_similarity` overrides the configured threshold for this query.
+    /// Results are diversified by the configured `similarity_diversity`.
+    pub async fn find_similar(
+        &self,
+        asset_id: Uuid,
+        embedding_type: EmbeddingType,
+        max_results: usize,
+        min_similarity: Option<f32>,
+    ) -> DamResult<Vec<SearchResult>> {
+        self.find_similar_with_diversity(asset_id, embedding_type, max_results, min_similarity, self.config.similarity_diversity).await
+    }
+    
+    /// `find_similar` with a per-query diversity in [0, 1]
+    /// 
+    /// See `search_visual_similar_with_diversity`; candidates are compared
+    /// by the same kind of embedding they were found by.
+    pub async fn find_similar_with_diversity(
+        &self,
+        asset_id: Uuid,
+        embedding_type: EmbeddingType,
+        max_results: usize,
+        min_similarity: Option<f32>,
+        diversity: f32,
+    ) -> DamResult<Vec<SearchResult>> {
+        debug!("Finding similar assets to: {}", asset_id);
+        let max_results = self.effective_max_results(max_results);
+        
+        // Find document
+        let document = self.find_document_by_asset_id(&asset_id)?
+            .ok_or_else(|| IndexError::DocumentNotFound(format!("Asset not found: {}", asset_id)))?;
+        
+        let vector_matches = self.vector_store.find_similar_to_document(
+            &document.id,
+            embedding_type,
+            diversity_candidates(max_results, diversity)?,
+            self.config.query_similarity_threshold(min_similarity)?
+        )?;
+        let vector_matches = self.vector_store.diversify(vector_matches, max_results, diversity);
+        
+        let mut results = Vec::new();
+        
+        for vector_match in vector_matches {
+            if let Some(document) = self.get_document(&vector_match.document_id)? {
+                let mut result = SearchResult::new(document, vector_match.similarity);
+                result.vector_score = vector_match.similarity;
+                result.match_reason = format!("Similar to asset {}", asset_id);
+                
+                results.push(result);
+            }
+        }
+        
+        debug!("Similarity search returned {} results", results.len());
+        Ok(results)
+    }
+    
+    /// Find assets similar to an asset by both its visual and text embeddings
+    /// 
+    /// Rankings are blended with the configured `similarity_weights`.
+    pub async fn find_similar_combined(
+        &self,
+        asset_id: Uuid,
+        max_results: usize,
+        min_similarity: Option<f32>,
+    ) -> DamResult<Vec<SearchResult>> {
+        self.find_similar_combined_with_weights(asset_id, &self.config.similarity_weights, max_results, min_similarity).await
+    }
+    
+    /// `find_similar_combined` with per-query weights
+    /// 
+    /// Each candidate scores the weighted mean of its visual and text
+    /// similarity, counting a kind it was not found by as 0, and appears
+    /// once. If the source asset has only one kind of embedding, that kind
+    /// ranks alone; it is an error only if it has neither.
+    pub async fn find_similar_combined_with_weights(
+        &self,
+        asset_id: Uuid,
+        weights: &SimilarityWeights,
+        max_results: usize,
+        min_similarity: Option<f32>,
+    ) -> DamResult<Vec<SearchResult>> {
+        debug!("Finding assets similar to {} by visual and text embeddings", asset_id);
+        weights.validate()?;
+        let max_results = self.effective_max_results(max_results);
+        let threshold = self.config.query_similarity_threshold(min_similarity)?;
+        
+        let document = self.find_document_by_asset_id(&asset_id)?
+            .ok_or_else(|| IndexError::DocumentNotFound(format!("Asset not found: {}", asset_id)))?;
+        
+        // Extra candidates so documents ranked lower by one kind can still win overall
+        let candidates = max_results.saturating_mul(2);
+        let mut scores: HashMap<Uuid, (f32, f32)> = HashMap::new();
+        let mut used_weight = 0.0;
+        let mut used_kinds = Vec::new();
+        
+        for (embedding_type, weight) in [(EmbeddingType::Visual, weights.visual), (EmbeddingType::Text, weights.text)] {
+            if weight == 0.0 {
+                continue;
+            }
+            let kind = format!("{:?}", embedding_type).to_lowercase();
+            let matches = match self.vector_store.find_similar_to_document(&document.id, embedding_type, candidates, threshold) {
+                Ok(matches) => matches,
+                // The source has no embedding of this kind; rank by the other
+                Err(VectorError::DocumentMissing(_) | VectorError::EmptyStore) => continue,
+                Err(e) => return Err(e.into()),
+            };
+            
+            used_weight += weight;
+            used_kinds.push(kind);
+            for vector_match in matches {
+                let entry = scores.entry(vector_match.document_id).or_insert((0.0, 0.0));
+                entry.0 += weight * vector_match.similarity;
+                entry.1 = entry.1.max(vector_match.similarity);
+            }
+        }
+        
+        if used_kinds.is_empty() {
+            return Err(VectorError::DocumentMissing(document.id).into());
+        }
+        
+        let mut results = Vec::new();
+        for (document_id, (weighted, best)) in scores {
+            if let Some(document) = self.get_document(&document_id)? {
+                let mut result = SearchResult::new(document, weighted / used_weight);
+                result.vector_score = best;
+                result.match_reason = format!("Similar to asset {} ({})", asset_id, used_kinds.join(" + "));
+                results.push(result);
+            }
+        }
+        
+        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        results.truncate(max_results);
+        debug!("Combined similarity search returned {} results", results.len());
+        Ok(results)
+    }
+    
+    /// Hybrid search combining text and vector search
+    pub async fn search_hybrid(&self, query: &str, query_embedding: Option<&[f32]>, max_results: usize) -> DamResult<Vec<SearchResult>> {
+        self.search_hybrid_with_boosts(query, query_embedding, max_results, &self.config.type_boosts, false).await
+    }
+    
+    /// Hybrid search with per-query asset type boosts, e.g. "prefer videos"
+    /// 
+    /// With `explain`, each result carries a `ScoreExplanation`.
+    pub async fn search_hybrid_with_boosts(
+        &self,
+        query: &str,
+        query_embedding: Option<&[f32]>,
+        max_results: usize,
+        type_boosts: &TypeBoosts,
+        explain: bool,
+    ) -> DamResult<Vec<SearchResult>> {
+        debug!("Hybrid search: '{}' with embedding: {}", query, query_embedding.is_some());
+        type_boosts.validate()?;
+        let max_results = self.effective_max_results(max_results);
+        
+        // Candidate lists are clamped to the cap as well
+        let candidates = max_results.saturating_mul(2);
+        
+        let mut all_results: HashMap<Uuid, SearchResult> = HashMap::new();
+        
+        // Text search
+        if !query.trim().is_empty() {
+            let text_results = self.search_text_weighted(query, candidates, &self.config.field_weights, explain).await?;
+            for mut result in text_results {
+                result.calculate_weighted_score_with(&self.config, type_boosts);
+                all_results.insert(result.document.id, result);
+            }
+        }
+        
+        // Vector search
+        if let Some(embedding) = query_embedding {
+            let vector_results = self.search_visual_similar_with_diversity(embedding, candidates, None, 0.0).await?;
+            for mut result in vector_results {
+                if explain {
+                    result.explanation = Some(ScoreExplanation::default());
+                }
+                result.calculate_weighted_score_with(&self.config, type_boosts);
+                
+                // Combine with existing text result if present
+                if let Some(existing) = all_results.get_mut(&result.document.id) {
+                    existing.vector_score = result.vector_score;
+                    existing.calculate_weighted_score_with(&self.config, type_boosts);
+                    existing.match_reason = format!("{} + Visual similarity", existing.match_reason);
+                } else {
+                    all_results.insert(result.document.id, result);
+                }
+            }
+        }
+        
+        // Sort and limit results
+        let mut results: Vec<SearchResult> = all_results.into_values().collect();
+        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+        results.truncate(max_results);
+        
+        debug!("Hybrid search returned {} results", results.len());
+        Ok(results)
+    }
+    
+    /// Cache capacity a configuration asks for; 0 when the cache is off
+    fn query_cache_capacity(config: &IndexConfig) -> usize {
+        if config.query_cache { config.query_cache_size } else { 0 }
+    }
+    
+    /// Lock the query cache
+    /// 
+    /// The cache holds no invariants across a panic, so a poisoned lock is
+    /// simply taken over.
+    fn query_cache(&self) -> MutexGuard<'_, QueryCache> {
+        self.query_cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+    
+    /// Cache key of a query, or None when the cache is disabled
+    /// 
+    /// Parameters go through `serde_json::Value`, whose maps are sorted,
+    /// so equal parameters always give the same key.
+    fn query_cache_key<P: Serialize>(&self, kind: &str, text: &str, params: &P) -> DamResult<Option<String>> {
+        if !self.query_cache().is_enabled() {
+            return Ok(None);
+        }
+        let params = serde_json::to_value(params)?.to_string();
+        Ok(Some(cache::cache_key(kind, text, &params)))
+    }
+    
+    /// Drop all cached search results; called by every write
+    fn invalidate_query_cache(&self) {
+        self.query_cache().clear();
+    }
+    
+    /// Clamp a requested result count to `IndexConfig::max_results`
+    /// 
+    /// Every search entry point applies this, so callers can never make the
+    /// service assemble more results than the configured cap.
+    fn effective_max_results(&self, requested: usize) -> usize {
+        requested.min(self.config.max_results)
+    }
+    
+    /// Most recent documents by the given timestamp, newest first
+    /// 
+    /// Pages through an in-memory ordering, so the cost depends on
+    /// `offset + limit` rather than the size of the index.
+    pub fn recent(&self, by: RecencyField, limit: usize, offset: usize) -> DamResult<Vec<AssetDocument>> {
+        let mut documents = Vec::with_capacity(limit);
+        for doc_id in self.recency.newest(by, limit, offset) {
+            if let Some(document) = self.get_document(&doc_id)? {
+                documents.push(document);
+            }
+        }
+        Ok(documents)
+    }
+    
+    /// Get search statistics
+    pub fn get_stats(&self) -> IndexStats {
+        let text_stats = self.text_index.get_stats();
+        let vector_stats = self.vector_store.get_stats();
+        
+        IndexStats {
+            total_documents: text_stats.total_documents,
+            total_terms: text_stats.total_terms,
+            avg_terms_per_doc: text_stats.avg_terms_per_doc,
+            visual_embeddings: vector_stats.visual_embeddings_count,
+            text_embeddings: vector_stats.text_embeddings_count,
+            visual_dimension: vector_stats.visual_dimension,
+            text_dimension: vector_stats.text_dimension,
+            processing: self.processing.stats(),
+            database_bytes: self.doc_store.size_on_disk().unwrap_or_else(|e| {
+                warn!("Could not read index database size: {}", e);
+                0
+            }),
+            preview_bytes: self.config.preview_dir.as_deref().map(|dir| self.preview_bytes(dir)),
+            vector_memory_bytes: self.vector_store.memory_bytes(),
+            resident_embeddings: vector_stats.resident_documents,
+            spilled_embeddings: vector_stats.spilled_documents,
+            last_indexed_at: self.recency.latest_indexed(),
+        }
+    }
+    
+    /// Size of the preview directory, measured at most every `PREVIEW_SIZE_MAX_AGE`
+    fn preview_bytes(&self, dir: &Path) -> u64 {
+        let mut measured = self.preview_size.lock().unwrap_or_else(PoisonError::into_inner);
+        match *measured {
+            Some((at, size)) if at.elapsed() < PREVIEW_SIZE_MAX_AGE => size,
+            _ => {
+                let size = directory_size(dir);
+                *measured = Some((Instant::now(), size));
+                size
+            }
+        }
+    }
+    
+    /// Clear all indexes
+    pub async fn clear(&mut self) -> DamResult<()> {
+        info!("Clearing all search indexes");
+        
+        self.text_index.clear();
+        self.vector_store.clear();
+        self.recency.clear();
+        self.processing.clear();
+        self.asset_documents.clear();
+        self.invalidate_query_cache();
+        self.doc_store.clear()
+            .map_err(|e| IndexError::DatabaseError(e.to_string()))?;
+        Self::clear_spilled_embeddings(&self.doc_store);
+        
+        Ok(())
+    }
+    
+    /// Compact the document database and rebuild the in-memory indexes
+    /// 
+    /// Live records are copied into a fresh database that replaces the old
+    /// one, dropping the space held by removed and overwritten documents.
+    /// The text, vector and recency indexes are then rebuilt from the live
+    /// documents. Takes `&mut self`, so nothing else can use the service
+    /// meanwhile; meant as a maintenance action after large deletions.
+    pub async fn compact(&mut self) -> DamResult<CompactionStats> {
+        info!("Compacting search index at {}", self.storage_dir.display());
+        // Compaction has no meaningful fraction; the progress only marks it as running
+        self.events.progress("Compacting index", 0, 1, None);
+        let result = self.compact_storage().await;
+        self.events.hide_progress();
+        
+        match &result {
+            Ok(stats) => self.events.notify(
+                NotificationLevel::Success,
+                "Index compacted",
+                format!("Reclaimed {} bytes across {} documents", stats.reclaimed_bytes(), stats.documents),
+            ),
+            Err(e) => self.events.notify(NotificationLevel::Error, "Index compaction failed", e.to_string()),
+        }
+        result
+    }
+    
+    /// Copy live records into a fresh database and rebuild the indexes
+    async fn compact_storage(&mut self) -> DamResult<CompactionStats> {
+        
+        self.doc_store.flush_async().await
+            .map_err(|e| IndexError::DatabaseError(e.to_string()))?;
+        let bytes_before = self.doc_store.size_on_disk()
+            .map_err(|e| IndexError::DatabaseError(e.to_string()))?;
+        
+        let db_path = self.storage_dir.join("documents.db");
+        let compact_path = self.storage_dir.join("documents.db.compact");
+        let old_path = self.storage_dir.join("documents.db.old");
+        
+        // Leftovers of an interrupted compaction; a backup is only stale
+        // while the database itself is in place
+        if compact_path.exists() {
+            std::fs::remove_dir_all(&compact_path)?;
+        }
+        if old_path.exists() && db_path.exists() {
+            std::fs::remove_dir_all(&old_path)?;
+        }
+        
+        // Records are copied verbatim, so unreadable documents survive for a later version
+        let mut documents = 0;
+        {
+            let compacted = sled::open(&compact_path)
+                .map_err(|e| IndexError::DatabaseError(e.to_string()))?;
+            for result in self.doc_store.iter() {
+                let (key, value) = result.map_err(|e| IndexError::DatabaseError(e.to_string()))?;
+                compacted.insert(key, value)
+                    .map_err(|e| IndexError::DatabaseError(e.to_string()))?;
+                documents += 1;
+            }
+            compacted.flush_async().await
+                .map_err(|e| IndexError::DatabaseError(e.to_string()))?;
+        }
+        
+        // The old database has to be closed before its directory can be moved,
+        // including the handle a limited vector store reads spilled embeddings from
+        let placeholder = sled::Config::new().temporary(true).open()
+            .map_err(|e| IndexError::DatabaseError(e.to_string()))?;
+        self.vector_store = VectorStore::with_metric(self.config.distance_metric);
+        drop(std::mem::replace(&mut self.doc_store, placeholder));
+        
+        // Whatever happens, reopen a database and rebuild the indexes from it:
+        // the compacted one, or the original if the swap or reopening failed.
+        // Only if no database opens does the placeholder stay, and then the
+        // error is returned.
+        let (doc_store, swapped) = swap_and_reopen(&db_path, &compact_path, &old_path, sled::open)?;
+        self.doc_store = doc_store;
+        
+        // Rebuild the in-memory indexes without any stale state
+        self.text_index = TextIndex::new(self.config.clone());
+        self.vector_store = Self::build_vector_store(&self.config, &self.doc_store);
+        self.recency = RecencyIndex::new();
+        self.processing = ProcessingIndex::new();
+        self.asset_documents.clear();
+        self.reload_from_storage()?;
+        self.invalidate_query_cache();
+        
+        if let Err(e) = swapped {
+            warn!("Failed to swap in compacted database, kept the original: {}", e);
+            return Err(IndexError::DatabaseError(format!("Failed to swap in compacted database: {}", e)).into());
+        }
+        if let Err(e) = std::fs::remove_dir_all(&old_path) {
+            warn!("Failed to remove old database {}: {}", old_path.display(), e);
+        }
+        
+        let stats = CompactionStats {
+            documents,
+            bytes_before,
+            bytes_after: self.doc_store.size_on_disk()
+                .map_err(|e| IndexError::DatabaseError(e.to_string()))?,
+        };
+        info!("Compacted {} documents, reclaimed {} bytes", documents, stats.reclaimed_bytes());
+        Ok(stats)
+    }
+    
+    /// Export every indexed document as a catalog
+    /// 
+    /// Returns the number of records written. Embeddings are not exported.
+    pub fn export_catalog<W: std::io::Write>(&self, format: ExportFormat, writer: W) -> DamResult<usize> {
+        let records = self.iter_documents()
+            .filter_map(|result| match result {
+                Ok(document) => Some(document),
+                Err(e) => {
+                    warn!("Failed to read document during catalog export: {}", e);
+                    None
+                }
+            })
+            .map(|document| CatalogRecord::from_document(&document));
+        
+        let count = write_catalog(records, format, writer)?;
+        info!("Exported {} catalog records", count);
+        Ok(count)
+    }
+    
+    /// Import a catalog, creating or updating documents by asset ID
+    /// 
+    /// Existing documents keep everything the catalog does not carry, such
+    /// as embeddings and transcriptions; new documents have none until AI
+    /// processing runs again. Returns the number of records imported.
+    pub async fn import_catalog<R: std::io::BufRead>(&mut self, format: ExportFormat, reader: R) -> DamResult<usize> {
+        let records = read_catalog(format, reader)?;
+        
+        let asset_ids: HashSet<Uuid> = records.iter().map(|record| record.id).collect();
+        let mut existing = self.find_documents_by_asset_ids(&asset_ids)?;
+        
+        let count = records.len();
+        for record in records {
+            let document = match existing.remove(&record.id) {
+                Some(previous) => record.merge_into(previous),
+                None => record.into_document(),
+            };
+            
+            // Store first, so a failed write leaves the in-memory indexes untouched
+            self.store_document(&document).await?;
+            self.index_document(&document, false)?;
+        }
+        
+        info!("Imported {} catalog records", count);
+        Ok(count)
+    }
+    
+    /// Reload documents from storage
+    fn reload_from_storage(&mut self) -> DamResult<()> {
+        info!("Reloading documents from storage");
+        
+        let mut documents = Vec::new();
+        let mut migrated = 0;
+        let mut unreadable = 0;
+        
+        // Load all documents from storage, upgrading ones written by older versions
+        for result in self.doc_store.iter() {
+            let (key, value) = result.map_err(|e| IndexError::DatabaseError(e.to_string()))?;
+            let mut document = match serde_json::from_slice::<AssetDocument>(&value) {
+                Ok(document) => document,
+                Err(e) => {
+                    // Left in storage untouched so a later version can still recover it
+                    warn!("Skipping unreadable document {:?}: {}", key, e);
+                    unreadable += 1;
+                    continue;
+                }
+            };
+            
+            if document.schema_version > DOCUMENT_SCHEMA_VERSION {
+                warn!(
+                    "Document {} has schema version {}, newer than supported version {}",
+                    document.id, document.schema_version, DOCUMENT_SCHEMA_VERSION
+                );
+            } else if document.migrate() {
+                self.store_document_blocking(&document)?;
+                migrated += 1;
+            }
+            documents.push(document);
+        }
+        
+        info!("Loaded {} documents from storage", documents.len());
+        if migrated > 0 {
+            info!("Migrated {} documents to schema version {}", migrated, DOCUMENT_SCHEMA_VERSION);
+        }
+        if unreadable > 0 {
+            warn!("{} stored documents could not be read and need to be re-ingested", unreadable);
+        }
+        
+        // Rebuild text index, settling an `Auto` language on the loaded library first
+        if self.config.language == TextLanguage::Auto {
+            let language = self.text_index.detect_language(&documents);
+            info!("Detected {:?} as the dominant text language", language);
+        }
+        for doc in &documents {
+            if let Err(e) = self.text_index.add_document(doc) {
+                warn!("Failed to add document to text index: {}", e);
+            }
+            self.recency.insert(doc);
+            self.processing.insert(doc);
+            self.asset_documents.insert(doc.asset_id, doc.id);
+        }
+        
+        // Rebuild vector store
+        if let Err(e) = self.load_vector_store(&documents) {
+            warn!("Failed to load vector embeddings: {}", e);
+        }
+        
+        info!("Successfully reloaded search indexes");
+        Ok(())
+    }
+    
+    /// Empty vector store for a configuration, spilling to the document database
+    /// 
+    /// Embeddings spilled by an earlier store are dropped; the new one
+    /// spills its own as it fills up.
+    fn build_vector_store(config: &IndexConfig, doc_store: &sled::Db) -> VectorStore {
+        Self::clear_spilled_embeddings(doc_store);
+        let vector_store = VectorStore::with_metric(config.distance_metric);
+        match config.max_resident_embeddings {
+            Some(max_resident) => vector_store.with_residency_limit(
+                max_resident,
+                config.eviction_policy,
+                Arc::new(StoredEmbeddings::new(doc_store)),
+            ),
+            None => vector_store,
+        }
+    }
+    
+    /// Drop the embeddings spilled to their own tree
+    fn clear_spilled_embeddings(doc_store: &sled::Db) {
+        if let Err(e) = doc_store.open_tree(SPILLED_EMBEDDINGS_TREE).and_then(|tree| tree.clear()) {
+            warn!("Failed to clear spilled embeddings: {}", e);
+        }
+    }
+    
+    /// Write a document to storage, retrying transient database failures
+    async fn store_document(&self, document: &AssetDocument) -> DamResult<()> {
+        let doc_json = serde_json::to_vec(document)?;
+        self.invalidate_query_cache();
+        retry_recoverable_async(DEFAULT_RETRY_ATTEMPTS, DEFAULT_RETRY_DELAY, || {
+            std::future::ready(self.insert_document_json(document, &doc_json))
+        }).await
+    }
+    
+    /// Delete a document from storage, retrying transient database failures
+    async fn delete_document(&self, doc_id: &Uuid) -> DamResult<()> {
+        self.invalidate_query_cache();
+        retry_recoverable_async(DEFAULT_RETRY_ATTEMPTS, DEFAULT_RETRY_DELAY, || {
+            std::future::ready(self.doc_store.remove(doc_id.as_bytes())
+                .map(|_| ())
+                .map_err(|e| DamError::from(IndexError::DatabaseError(e.to_string()))))
+        }).await
+    }
+    
+    /// Write a document to storage while the index is being opened
+    /// 
+    /// Opening is synchronous, so retries wait on the calling thread.
+    fn store_document_blocking(&self, document: &AssetDocument) -> DamResult<()> {
+        let doc_json = serde_json::to_vec(document)?;
+        self.invalidate_query_cache();
+        retry_recoverable(DEFAULT_RETRY_ATTEMPTS, DEFAULT_RETRY_DELAY, || {
+            self.insert_document_json(document, &doc_json)
+        })
+    }
+    
+    fn insert_document_json(&self, document: &AssetDocument, doc_json: &[u8]) -> DamResult<()> {
+        self.doc_store.insert(document.id.as_bytes(), doc_json)
+            .map_err(|e| IndexError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+    
+    /// Apply a batch of writes atomically and flush it to disk, retrying
+    /// transient database failures
+    async fn apply_batch(&self, batch: sled::Batch) -> DamResult<()> {
+        self.invalidate_query_cache();
+        retry_recoverable_async(DEFAULT_RETRY_ATTEMPTS, DEFAULT_RETRY_DELAY, || {
+            std::future::ready(self.doc_store.apply_batch(batch.clone())
+                .map_err(|e| DamError::from(IndexError::DatabaseError(e.to_string()))))
+        }).await?;
+        
+        self.doc_store.flush_async().await
+            .map_err(|e| IndexError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+    
+    /// Get document by ID
+    fn get_document(&self, doc_id: &Uuid) -> DamResult<Option<AssetDocument>> {
+        load_document(&self.doc_store, doc_id)
+    }
+    
+    /// Find document by asset ID
+    fn find_document_by_asset_id(&self, asset_id: &Uuid) -> DamResult<Option<AssetDocument>> {
+        match self.asset_documents.get(asset_id) {
+            Some(doc_id) => self.get_document(doc_id),
+            None => Ok(None),
+        }
+    }
+    
+    /// Find the documents of several assets
+    fn find_documents_by_asset_ids(&self, asset_ids: &HashSet<Uuid>) -> DamResult<HashMap<Uuid, AssetDocument>> {
+        let mut documents = HashMap::new();
+        for asset_id in asset_ids {
+            if let Some(document) = self.find_document_by_asset_id(asset_id)? {
+                documents.insert(*asset_id, document);
+            }
+        }
+        Ok(documents)
+    }
+}
+
+/// Read a document from the document store
+fn load_document(doc_store: &sled::Db, doc_id: &Uuid) -> DamResult<Option<AssetDocument>> {
+    if let Some(data) = doc_store.get(doc_id.as_bytes())
+        .map_err(|e| IndexError::DatabaseError(e.to_string()))? {
+        let document: AssetDocument = serde_json::from_slice(&data)?;
+        Ok(Some(document))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Search result for a text match, scaled by the type boost of its document
+fn match_result(document: AssetDocument, text_match: &TextMatch, boost: f32) -> SearchResult {
+    let mut result = SearchResult::new(document, text_match.score * boost);
+    result.text_score = text_match.score;
+    result.match_reason = format!("Text match in: {}", 
+        text_match.matches.iter()
+            .map(|m| m.field_name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    result.highlights = extract_snippets(&result.document, &text_match.matches);
+    result
+}
+
+/// Group of an asset type, added empty if there is none yet
+fn group_mut<'a>(groups: &'a mut Vec<SearchGroup>, asset_type: &AssetType) -> &'a mut SearchGroup {
+    let position = match groups.iter().position(|group| group.asset_type == *asset_type) {
+        Some(position) => position,
+        None => {
+            groups.push(SearchGroup {
+                asset_type: asset_type.clone(),
+                total: 0,
+                results: Vec::new(),
+            });
+            groups.len() - 1
+        }
+    };
+    &mut groups[position]
+}
+
+/// Order results by a sort criterion
+/// 
+/// Results are ranked by relevance first and the sort is stable, so ties
+/// keep their relevance order.
+fn sort_results(results: &mut [SearchResult], sort: &SortCriteria) {
+    use std::cmp::Ordering;
+    
+    let directed = |ordering: Ordering, ascending: bool| if ascending { ordering } else { ordering.reverse() };
+    
+    results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+    
+    match *sort {
+        SortCriteria::Relevance => {}
+        SortCriteria::CreatedDate { ascending } => {
+            results.sort_by(|a, b| directed(a.document.created_at.cmp(&b.document.created_at), ascending));
+        }
+        SortCriteria::ModifiedDate { ascending } => {
+            results.sort_by(|a, b| directed(a.document.modified_at.cmp(&b.document.modified_at), ascending));
+        }
+        SortCriteria::FileSize { ascending } => {
+            results.sort_by(|a, b| directed(a.document.file_size.cmp(&b.document.file_size), ascending));
+        }
+        SortCriteria::Filename { ascending } => {
+            results.sort_by(|a, b| directed(
+                a.document.filename.to_lowercase().cmp(&b.document.filename.to_lowercase()),
+                ascending,
+            ));
+        }
+        SortCriteria::AssetType { ascending } => {
+            results.sort_by(|a, b| directed(
+                a.document.asset_type.display_name().cmp(b.document.asset_type.display_name()),
+                ascending,
+            ));
+        }
+        SortCriteria::Rating { ascending } => {
+            // Unrated assets come last in either direction
+            results.sort_by(|a, b| match (a.document.rating, b.document.rating) {
+                (Some(x), Some(y)) => directed(x.cmp(&y), ascending),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            });
+        }
+    }
+}
+
+impl Default for IndexService {
+    fn default() -> Self {
+        Self::new().expect("Failed to create IndexService")
+    }
+}
+
+/// Index statistics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexStats {
+    pub total_documents: usize,
+    pub total_terms: usize,
+    pub avg_terms_per_doc: f32,
+    pub visual_embeddings: usize,
+    pub text_embeddings: usize,
+    pub visual_dimension: Option<usize>,
+    pub text_dimension: Option<usize>,
+    /// How much of the library has been through AI processing
+    pub processing: ProcessingStats,
+    /// Size of the document database on disk
+    pub database_bytes: u64,
+    /// Size of the files in the preview directory; None if none is configured
+    /// 
+    /// Measured at most once a minute, so it may lag behind recent imports.
+    pub preview_bytes: Option<u64>,
+    /// Estimated memory held by the in-memory vector store
+    pub vector_memory_bytes: u64,
+    /// Documents whose embeddings are held in memory
+    pub resident_embeddings: usize,
+    /// Documents whose embeddings were spilled to the database
+    pub spilled_embeddings: usize,
+    /// When the most recently added asset was indexed
+    pub last_indexed_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl From<&IndexStats> for schema::IndexMessage {
+    /// Statistics message for other components; the index size is the
+    /// document database on disk
+    fn from(stats: &IndexStats) -> Self {
+        schema::IndexMessage::Stats {
+            document_count: stats.total_documents,
+            index_size_bytes: stats.database_bytes,
+            last_updated: stats.last_indexed_at.unwrap_or(chrono::DateTime::<chrono::Utc>::MIN_UTC),
+        }
+    }
+}
+
+/// Tree holding spilled embeddings as raw floats
+const SPILLED_EMBEDDINGS_TREE: &str = "spilled_embeddings";
+
+/// Embeddings read back from storage after being spilled
+/// 
+/// Spilled embeddings are written to their own tree as little-endian
+/// floats, so searches that reach them skip decoding whole documents.
+/// Anything missing there is read from the stored document instead.
+#[derive(Debug)]
+struct StoredEmbeddings {
+    documents: sled::Db,
+    spilled: Option<sled::Tree>,
+}
+
+impl StoredEmbeddings {
+    fn new(db: &sled::Db) -> Self {
+        let spilled = db.open_tree(SPILLED_EMBEDDINGS_TREE)
+            .map_err(|e| warn!("Spilled embeddings will be read from documents: {}", e))
+            .ok();
+        Self { documents: db.clone(), spilled }
+    }
+    
+    fn key(doc_id: &Uuid, embedding_type: &EmbeddingType) -> [u8; 17] {
+        let mut key = [0; 17];
+        key[..16].copy_from_slice(doc_id.as_bytes());
+        key[16] = match embedding_type {
+            EmbeddingType::Visual => 0,
+            EmbeddingType::Text => 1,
+        };
+        key
+    }
+}
+
+impl EmbeddingSource for StoredEmbeddings {
+    fn load(&self, doc_id: &Uuid, embedding_type: &EmbeddingType) -> Option<Vec<Vec<f32>>> {
+        if let Some(spilled) = &self.spilled {
+            if let Ok(Some(data)) = spilled.get(Self::key(doc_id, embedding_type)) {
+                if let Some(chunks) = decode_embeddings(&data) {
+                    return Some(chunks);
+                }
+            }
+        }
+        
+        let data = self.documents.get(doc_id.as_bytes()).ok()??;
+        let document: AssetDocument = serde_json::from_slice(&data).ok()?;
+        match embedding_type {
+            EmbeddingType::Visual => document.visual_embedding.map(|embedding| vec![embedding]),
+            EmbeddingType::Text if !document.text_embedding_chunks.is_empty() => Some(document.text_embedding_chunks),
+            EmbeddingType::Text => document.text_embedding.map(|embedding| vec![embedding]),
+        }
+    }
+    
+    fn spill(&self, doc_id: &Uuid, embedding_type: &EmbeddingType, chunks: &[Vec<f32>]) {
+        let Some(spilled) = &self.spilled else {
+            return;
+        };
+        if let Err(e) = spilled.insert(Self::key(doc_id, embedding_type), encode_embeddings(chunks)) {
+            warn!("Failed to store spilled embeddings of {}: {}", doc_id, e);
+        }
+    }
+    
+    fn forget(&self, doc_id: &Uuid) {
+        let Some(spilled) = &self.spilled else {
+            return;
+        };
+        for embedding_type in [EmbeddingType::Visual, EmbeddingType::Text] {
+            if let Err(e) = spilled.remove(Self::key(doc_id, &embedding_type)) {
+                warn!("Failed to remove spilled embeddings of {}: {}", doc_id, e);
+            }
+        }
+    }
+}
+
+/// Chunks of one dimension as a little-endian `u32` dimension followed by the floats
+fn encode_embeddings(chunks: &[Vec<f32>]) -> Vec<u8> {
+    let dimension = chunks.first().map_or(0, Vec::len);
+    let mut data = Vec::with_capacity(4 + chunks.len() * dimension * 4);
+    data.extend((dimension as u32).to_le_bytes());
+    for value in chunks.iter().flatten() {
+        data.extend(value.to_le_bytes());
+    }
+    data
+}
+
+/// Chunks written by `encode_embeddings`, None if the data is malformed
+fn decode_embeddings(data: &[u8]) -> Option<Vec<Vec<f32>>> {
+    let dimension = u32::from_le_bytes(data.get(..4)?.try_into().ok()?) as usize;
+    let values = &data[4..];
+    if dimension == 0 || values.len() % (dimension * 4) != 0 {
+        return None;
+    }
+    let floats: Vec<f32> = values.chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .collect();
+    Some(floats.chunks(dimension).map(<[f32]>::to_vec).collect())
+}
+
+/// Swap in the compacted database and open whichever database ends up in place
+/// 
+/// If the compacted database will not open, it is moved back to
+/// `compact_path` and the original reopened. Returns the opened database
+/// and the outcome of the swap; the original is only left at `old_path`
+/// when the swap succeeded or could not be undone.
+fn swap_and_reopen(
+    db_path: &Path,
+    compact_path: &Path,
+    old_path: &Path,
+    open: impl Fn(&Path) -> sled::Result<sled::Db>,
+) -> DamResult<(sled::Db, std::io::Result<()>)> {
+    let mut swapped = swap_database(db_path, compact_path, old_path);
+    if swapped.is_ok() {
+        match open(db_path) {
+            Ok(db) => return Ok((db, swapped)),
+            Err(e) => {
+                warn!("Compacted database would not open, restoring the original: {}", e);
+                std::fs::rename(db_path, compact_path)
+                    .and_then(|()| std::fs::rename(old_path, db_path))?;
+                swapped = Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    format!("compacted database would not open: {}", e),
+                ));
+            }
+        }
+    }
+    
+    let open_path = if db_path.exists() { db_path } else { old_path };
+    let db = open(open_path).map_err(|e| IndexError::DatabaseError(e.to_string()))?;
+    Ok((db, swapped))
+}
+
+/// Move the compacted database in place of the original, kept at `old_path`
+/// 
+/// If the compacted one cannot be moved in, the original is moved back.
+fn swap_database(db_path: &Path, compact_path: &Path, old_path: &Path) -> std::io::Result<()> {
+    std::fs::rename(db_path, old_path)?;
+    if let Err(e) = std::fs::rename(compact_path, db_path) {
+        std::fs::rename(old_path, db_path)?;
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// How long a measured preview directory size is reported before walking it again
+const PREVIEW_SIZE_MAX_AGE: Duration = Duration::from_secs(60);
+
+/// Total size of the files below a directory; unreadable entries count as empty
+fn directory_size(dir: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => directory_size(&entry.path()),
+            Ok(file_type) if file_type.is_file() => entry.metadata().map(|m| m.len()).unwrap_or(0),
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Vector matches to fetch for `max_results` results at the given diversity
+fn diversity_candidates(max_results: usize, diversity: f32) -> DamResult<usize> {
+    if !(0.0..=1.0).contains(&diversity) {
+        return Err(DamError::configuration(format!("diversity must be in [0, 1], got {}", diversity)));
+    }
+    Ok(if diversity > 0.0 { max_results.saturating_mul(DIVERSITY_CANDIDATE_FACTOR) } else { max_results })
+}
+
+/// Similar assets listed by `IndexService::get_asset_details`
+pub const DETAIL_SIMILAR_ASSETS: usize = 8;
+
+/// Everything a detail view shows for one asset
+/// 
+/// The document carries tags, AI results, technical metadata and preview
+/// paths.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssetDetails {
+    pub document: AssetDocument,
+    /// Visually similar assets with their similarity, most similar first
+    pub similar: Vec<(Uuid, f32)>,
+}
+
+/// Outcome of `IndexService::redetect_unknowns`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RedetectReport {
+    /// `Unknown` assets that were checked
+    pub checked: usize,
+    /// Assets now recognized, reindexed with their new type and metadata
+    pub upgraded: Vec<Uuid>,
+    /// Assets whose upgrade failed, with the reason
+    pub failed: Vec<(Uuid, String)>,
+}
+
+/// Outcome of `IndexService::audit_embeddings` and `repair_embeddings`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EmbeddingAudit {
+    pub visual: DimensionAudit,
+    pub text: DimensionAudit,
+    /// Assets whose mismatched embeddings a repair cleared for re-embedding
+    pub requeued: Vec<Uuid>,
+}
+
+impl EmbeddingAudit {
+    /// Whether every stored embedding has its type's dimension
+    pub fn is_consistent(&self) -> bool {
+        self.visual.mismatched.is_empty() && self.text.mismatched.is_empty()
+    }
+}
+
+/// Embedding dimensions of one type across the library
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DimensionAudit {
+    /// Dimension the documents are checked against
+    pub expected: Option<usize>,
+    /// Documents per embedding dimension; a document with chunks of
+    /// several dimensions counts under each
+    pub dimensions: BTreeMap<usize, usize>,
+    /// Assets with an embedding of another dimension
+    pub mismatched: Vec<Uuid>,
+}
+
+impl DimensionAudit {
+    /// Check documents' dimensions against the declared one, else the most common one
+    fn new(declared: Option<usize>, documents: Vec<(Uuid, Vec<usize>)>) -> Self {
+        let mut dimensions = BTreeMap::new();
+        for (_, dims) in &documents {
+            let distinct: HashSet<usize> = dims.iter().copied().collect();
+            for dim in distinct {
+                *dimensions.entry(dim).or_insert(0) += 1;
+            }
+        }
+        
+        // Most common dimension, the larger one on ties
+        let expected = declared.or_else(|| {
+            dimensions.iter().max_by_key(|(dim, count)| (**count, **dim)).map(|(dim, _)| *dim)
+        });
+        let mismatched = documents.into_iter()
+            .filter(|(_, dims)| expected.map_or(false, |expected| dims.iter().any(|dim| *dim != expected)))
+            .map(|(asset_id, _)| asset_id)
+            .collect();
+        
+        Self { expected, dimensions, mismatched }
+    }
+}
+
+/// Outcome of `IndexService::compact`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompactionStats {
+    /// Documents kept
+    pub documents: usize,
+    /// Database size before compaction, in bytes
+    pub bytes_before: u64,
+    /// Database size after compaction, in bytes
+    pub bytes_after: u64,
+}
+
+impl CompactionStats {
+    /// Disk space freed by the compaction
+    pub fn reclaimed_bytes(&self) -> u64 {
+        self.bytes_before.saturating_sub(self.bytes_after)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::{AssetType, FileFormat, AssetMetadata, VersionInfo, DetectionMethod, ProcessingStatus, IntegrityStatus, DimensionFilter, Orientation};
+    use std::path::PathBuf;
+    use chrono::Utc;
+    use tempfile::TempDir;
+    
+    fn create_test_asset(filename: &str) -> Asset {
+        let path = PathBuf::from(filename);
+        let now = Utc::now();
+        
+        Asset {
+            id: Uuid::new_v4(),
+            original_path: path.clone(),
+            current_path: path,
+            asset_type: AssetType::Image,
+            file_size: 1024,
+            format: FileFormat {
+                extension: "jpg".to_string(),
+                mime_type: Some("image/jpeg".to_string()),
+                version: None,
+                supported: true,
+                detection_method: DetectionMethod::MagicBytes,
+                confidence: 1.0,
+            },
+            created_at: now,
+            modified_at: now,
+            tags: Vec::new(),
+            rating: None,
+            favorite: false,
+            metadata: AssetMetadata::default(),
+            preview: None,
+            embedding: None,
+            version_info: VersionInfo {
+                current_version: "v1".to_string(),
+                version_count: 1,
+                last_snapshot: now,
+                has_changes: false,
+            },
+            integrity: IntegrityStatus::Unchecked,
+            needs_deep_processing: false,
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_index_service_creation() {
+        let temp_dir = TempDir::new().unwrap();
+        let service = IndexService::with_storage_dir(temp_dir.path());
+        assert!(service.is_ok());
+    }
+    
+    #[tokio::test]
+    async fn test_asset_indexing_and_search() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        // Create test asset
+        let asset = create_test_asset("vacation_photo.jpg");
+        let asset_id = asset.id;
+        
+        // Index asset
+        service.index_asset(&asset).await.unwrap();
+        
+        // Search for it
+        let results = service.search_text("vacation", 10).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document.asset_id, asset_id);
+        
+        // Remove asset
+        service.remove_asset(asset_id).await.unwrap();
+        
+        // Should not find it anymore
+        let results = service.search_text("vacation", 10).await.unwrap();
+        assert_eq!(results.len(), 0);
+    }
+    
+    #[tokio::test]
+    async fn test_ai_results_update() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        // Create and index asset
+        let asset = create_test_asset("test.jpg");
+        let asset_id = asset.id;
+        service.index_asset(&asset).await.unwrap();
+        
+        // Update with AI results
+        service.update_with_ai_results(
+            asset_id,
+            Some(vec!["cat".to_string(), "cute".to_string()]),
+            Some("A cute cat sitting on a chair".to_string()),
+            None,
+            Some(vec![0.1, 0.2, 0.3, 0.4]),
+            None
+        ).await.unwrap();
+        
+        // Search should now find it by AI tags
+        let results = service.search_text("cat", 10).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document.asset_id, asset_id);
+        
+        // Visual similarity search should work
+        let similar_results = service.search_visual_similar(&[0.1, 0.2, 0.3, 0.4], 5, None).await.unwrap();
+        assert_eq!(similar_results.len(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_search_text_stream() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        for name in ["harbor boats.jpg", "harbor.jpg", "boats.jpg", "boats at dusk.jpg", "forest.jpg"] {
+            service.index_asset(&create_test_asset(name)).await.unwrap();
+        }
+        
+        let collected = service.search_text("harbor boats", 10).await.unwrap();
+        let streamed: Vec<SearchResult> = service.search_text_stream("harbor boats", 10).await.unwrap().collect().await;
+        assert_eq!(streamed.len(), 4);
+        let scores = |results: &[SearchResult]| results.iter().map(|r| r.score).collect::<Vec<_>>();
+        assert_eq!(scores(&streamed), scores(&collected));
+        assert_eq!(streamed[0].document.id, collected[0].document.id);
+        assert_eq!(streamed[0].match_reason, collected[0].match_reason);
+        
+        // The best result arrives first, and the limit bounds the stream
+        let mut stream = service.search_text_stream("harbor boats", 2).await.unwrap();
+        assert_eq!(stream.next().await.unwrap().document.id, collected[0].document.id);
+        // The stream does not borrow the service, so writes can go on meanwhile
+        service.index_asset(&create_test_asset("quay.jpg")).await.unwrap();
+        assert_eq!(stream.next().await.unwrap().document.id, collected[1].document.id);
+        assert!(stream.next().await.is_none());
+        
+        assert_eq!(service.search_text_stream("nothing", 10).await.unwrap().count().await, 0);
+    }
+    
+    /// Encoder that knows one word, standing in for a CLIP text tower
+    struct CarEncoder;
+    
+    impl CrossModalEncoder for CarEncoder {
+        fn encode_text(&self, text: &str) -> DamResult<Vec<f32>> {
+            Ok(if text.contains("car") { vec![1.0, 0.0, 0.0] } else { vec![0.0, 0.0, 1.0] })
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_search_cross_modal() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        // Untagged files whose names say nothing about their content
+        let car = create_test_asset("IMG_0001.jpg");
+        let beach = create_test_asset("IMG_0002.jpg");
+        for (asset, embedding) in [(&car, vec![0.9, 0.3, 0.0]), (&beach, vec![0.1, 1.0, 0.2])] {
+            service.index_asset(asset).await.unwrap();
+            service.update_with_ai_results(asset.id, None, None, None, Some(embedding), None).await.unwrap();
+        }
+        assert!(service.search_cross_modal("a red sports car", 10, None).await.is_err());
+        
+        service.set_cross_modal_encoder(Some(Arc::new(CarEncoder)));
+        assert!(service.search_text("red sports car", 10).await.unwrap().is_empty());
+        let results = service.search_cross_modal("a red sports car at sunset", 10, None).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document.asset_id, car.id);
+        assert_eq!(results[0].match_reason, "Semantic visual match");
+        
+        // A per-query threshold admits weaker matches
+        assert_eq!(service.search_cross_modal("a red sports car", 10, Some(0.0)).await.unwrap().len(), 2);
+        assert!(service.search_cross_modal("  ", 10, None).await.unwrap().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_per_query_min_similarity() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        let close = create_test_asset("close.jpg");
+        let loose = create_test_asset("loose.jpg");
+        service.index_asset(&close).await.unwrap();
+        service.index_asset(&loose).await.unwrap();
+        service.update_with_ai_results(close.id, None, None, None, Some(vec![1.0, 0.1, 0.0]), None).await.unwrap();
+        service.update_with_ai_results(loose.id, None, None, None, Some(vec![1.0, 2.0, 0.0]), None).await.unwrap();
+        
+        // The configured 0.7 only admits the close match
+        let query = [1.0, 0.0, 0.0];
+        assert_eq!(service.search_visual_similar(&query, 10, None).await.unwrap().len(), 1);
+        assert_eq!(service.search_visual_similar(&query, 10, Some(0.4)).await.unwrap().len(), 2);
+        assert!(service.search_visual_similar(&query, 10, Some(0.999)).await.unwrap().is_empty());
+        
+        let similar = service.find_similar(close.id, EmbeddingType::Visual, 10, Some(0.4)).await.unwrap();
+        assert_eq!(similar.len(), 1);
+        assert_eq!(similar[0].document.asset_id, loose.id);
+        assert!(service.find_similar(close.id, EmbeddingType::Visual, 10, None).await.unwrap().is_empty());
+        
+        assert!(service.search_visual_similar(&query, 10, Some(1.5)).await.is_err());
+        assert!(service.search_text_similar(&query, 10, Some(-0.1)).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_diverse_similar_results() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        let embeddings = [
+            ("source.jpg", vec![1.0, 1.0, 0.0, 0.0]),
+            ("take1.jpg", vec![1.0, 0.25, 0.0, 0.0]),
+            ("take2.jpg", vec![1.0, 0.2, 0.0, 0.0]),
+            ("other.jpg", vec![0.15, 1.0, 0.0, 0.0]),
+        ];
+        let mut ids = Vec::new();
+        for (name, embedding) in embeddings {
+            let asset = create_test_asset(name);
+            service.index_asset(&asset).await.unwrap();
+            service.update_with_ai_results(asset.id, None, None, None, Some(embedding), None).await.unwrap();
+            ids.push(asset.id);
+        }
+        let asset_ids = |results: Vec<SearchResult>| results.into_iter().map(|r| r.document.asset_id).collect::<Vec<_>>();
+        
+        // Off by default: the two takes of one shot fill the results
+        let similar = service.find_similar(ids[0], EmbeddingType::Visual, 2, Some(0.0)).await.unwrap();
+        assert_eq!(asset_ids(similar), vec![ids[1], ids[2]]);
+        
+        let similar = service.find_similar_with_diversity(ids[0], EmbeddingType::Visual, 2, Some(0.0), 0.5).await.unwrap();
+        assert_eq!(similar[0].vector_score, similar[0].score);
+        assert_eq!(asset_ids(similar), vec![ids[1], ids[3]]);
+        
+        // The configured diversity applies to query embeddings too: the
+        // take least like the top result moves up
+        let query = [1.0, 0.6, 0.0, 0.0];
+        let similar = service.search_visual_similar(&query, 3, Some(0.0)).await.unwrap();
+        assert_eq!(asset_ids(similar), vec![ids[0], ids[1], ids[2]]);
+        service.set_config(IndexConfig { similarity_diversity: 0.5, ..IndexConfig::default() }).unwrap();
+        let similar = service.search_visual_similar(&query, 3, Some(0.0)).await.unwrap();
+        assert_eq!(asset_ids(similar), vec![ids[0], ids[2], ids[1]]);
+        
+        assert!(service.find_similar_with_diversity(ids[0], EmbeddingType::Visual, 2, None, 1.5).await.is_err());
+        assert!(service.set_config(IndexConfig { similarity_diversity: -0.1, ..IndexConfig::default() }).is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_reindex_reuses_document() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        let mut asset = create_test_asset("repeat.jpg");
+        service.index_asset(&asset).await.unwrap();
+        service.update_with_ai_results(
+            asset.id,
+            Some(vec!["harbor".to_string()]),
+            None,
+            None,
+            Some(vec![0.1, 0.2, 0.3, 0.4]),
+            None
+        ).await.unwrap();
+        
+        // Indexing the unchanged asset again keeps one document and its AI results
+        service.index_asset(&asset).await.unwrap();
+        let stats = service.get_stats();
+        assert_eq!(stats.total_documents, 1);
+        assert_eq!(stats.visual_embeddings, 1);
+        assert_eq!(service.search_text("harbor", 10).await.unwrap().len(), 1);
+        
+        // Changed content drops the stale embedding
+        asset.file_size += 1;
+        service.index_asset(&asset).await.unwrap();
+        let stats = service.get_stats();
+        assert_eq!(stats.total_documents, 1);
+        assert_eq!(stats.visual_embeddings, 0);
+        assert!(service.get_asset_document(asset.id).unwrap().unwrap().visual_embedding.is_none());
+    }
+    
+    #[tokio::test]
+    async fn test_type_boosts() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        for (name, asset_type, tag) in [
+            ("notes.txt", AssetType::Document, "interview"),
+            ("clip.mp4", AssetType::Video, "interview"),
+            ("beach.jpg", AssetType::Image, "beach"),
+            ("forest.jpg", AssetType::Image, "forest"),
+            ("city.jpg", AssetType::Image, "city"),
+        ] {
+            let mut asset = create_test_asset(name);
+            asset.asset_type = asset_type;
+            asset.tags = vec![tag.to_string()];
+            service.index_asset(&asset).await.unwrap();
+        }
+        
+        let top_type = |results: &[SearchResult]| results[0].document.asset_type.clone();
+        
+        let prefer_videos = TypeBoosts::default().with(AssetType::Video, 3.0);
+        let results = service.search_hybrid_with_boosts("interview", None, 10, &prefer_videos, false).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(top_type(&results), AssetType::Video);
+        
+        let prefer_documents = TypeBoosts::default().with(AssetType::Document, 3.0);
+        let results = service.search_hybrid_with_boosts("interview", None, 10, &prefer_documents, false).await.unwrap();
+        assert_eq!(top_type(&results), AssetType::Document);
+        
+        // Configured boosts also apply to plain text search
+        let mut config = service.config().clone();
+        config.type_boosts = prefer_videos;
+        service.set_config(config).unwrap();
+        let results = service.search_text("interview", 1).await.unwrap();
+        assert_eq!(top_type(&results), AssetType::Video);
+        
+        let invalid = TypeBoosts::default().with(AssetType::Video, -1.0);
+        assert!(service.search_hybrid_with_boosts("interview", None, 10, &invalid, false).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_max_results_cap() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        let mut config = service.config().clone();
+        config.max_results = 3;
+        service.set_config(config).unwrap();
+        
+        for i in 0..5 {
+            service.index_asset(&create_test_asset(&format!("capped_{}.jpg", i))).await.unwrap();
+        }
+        
+        let results = service.search_text("image", 1_000_000).await.unwrap();
+        assert_eq!(results.len(), 3);
+        
+        let results = service.search_hybrid("image", None, usize::MAX).await.unwrap();
+        assert_eq!(results.len(), 3);
+        
+        // Smaller requests are honored as-is
+        assert_eq!(service.search_text("image", 2).await.unwrap().len(), 2);
+    }
+    
+    #[tokio::test]
+    async fn test_update_preview() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        let asset = create_test_asset("thumb.jpg");
+        service.index_asset(&asset).await.unwrap();
+        assert_eq!(service.asset_ids().unwrap(), vec![asset.id]);
+        
+        let preview = PreviewInfo {
+            thumbnail_path: PathBuf::from("/previews/regenerated.jpg"),
+            thumbnail_size: (256, 128),
+            rendered_preview: None,
+            generated_at: Utc::now(),
+            extension: "jpg".to_string(),
+            waveform: None,
+            contact_sheet_grid: None,
+        };
+        service.update_preview(asset.id, &preview).await.unwrap();
+        
+        let document = service.get_asset_document(asset.id).unwrap().unwrap();
+        assert_eq!(document.thumbnail_path, Some(preview.thumbnail_path.clone()));
+        assert!(service.update_preview(Uuid::new_v4(), &preview).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_recent_documents() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        let mut assets = Vec::new();
+        for (i, name) in ["old.jpg", "middle.jpg", "new.jpg"].iter().enumerate() {
+            let mut asset = create_test_asset(name);
+            asset.modified_at = Utc::now() - chrono::Duration::days(10 - i as i64);
+            service.index_asset(&asset).await.unwrap();
+            assets.push(asset);
+        }
+        
+        let filenames = |documents: Vec<AssetDocument>| -> Vec<String> {
+            documents.into_iter().map(|d| d.filename).collect()
+        };
+        
+        let recent = service.recent(RecencyField::Modified, 2, 0).unwrap();
+        assert_eq!(filenames(recent), vec!["new.jpg", "middle.jpg"]);
+        
+        let recent = service.recent(RecencyField::Modified, 2, 2).unwrap();
+        assert_eq!(filenames(recent), vec!["old.jpg"]);
+        
+        // Re-indexing keeps the original indexed time
+        let first_indexed = service.recent(RecencyField::Indexed, 1, 2).unwrap();
+        service.index_asset(&assets[0]).await.unwrap();
+        assert_eq!(service.recent(RecencyField::Indexed, 1, 2).unwrap()[0].id, first_indexed[0].id);
+        
+        service.remove_asset(assets[2].id).await.unwrap();
+        let recent = service.recent(RecencyField::Modified, 10, 0).unwrap();
+        assert_eq!(filenames(recent), vec!["middle.jpg", "old.jpg"]);
+        
+        // The ordering is rebuilt when reopening the index
+        drop(service);
+        let service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        assert_eq!(service.recent(RecencyField::Modified, 1, 0).unwrap()[0].filename, "middle.jpg");
+    }
+    
+    #[tokio::test]
+    async fn test_outdated_documents_are_migrated() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        let mut asset = create_test_asset("sunset.jpg");
+        asset.tags = vec!["beach".to_string()];
+        service.index_asset(&asset).await.unwrap();
+        
+        // Rewrite the document as an unversioned one with stale derived fields
+        let document = service.get_asset_document(asset.id).unwrap().unwrap();
+        assert_eq!((document.detection_method, document.format_confidence), (DetectionMethod::MagicBytes, 1.0));
+        let mut value = serde_json::to_value(&document).unwrap();
+        let fields = value.as_object_mut().unwrap();
+        fields.remove("schema_version");
+        fields.insert("search_text".to_string(), serde_json::json!(""));
+        service.doc_store.insert(document.id.as_bytes(), serde_json::to_vec(&value).unwrap()).unwrap();
+        drop(service);
+        
+        let service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        let document = service.get_asset_document(asset.id).unwrap().unwrap();
+        assert_eq!(document.schema_version, DOCUMENT_SCHEMA_VERSION);
+        assert!(document.search_text.contains("beach"));
+        assert_eq!(document.tags, asset.tags);
+    }
+    
+    #[tokio::test]
+    async fn test_update_asset_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        let asset = create_test_asset("draft.jpg");
+        service.index_asset(&asset).await.unwrap();
+        service.update_with_ai_results(
+            asset.id,
+            Some(vec!["sunset".to_string()]),
+            None,
+            None,
+            None,
+            None
+        ).await.unwrap();
+        
+        service.update_asset_path(asset.id, "archive/final.jpg").await.unwrap();
+        
+        let results = service.search_text("final.jpg", 10).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document.file_path, PathBuf::from("archive/final.jpg"));
+        assert_eq!(results[0].document.ai_tags, vec!["sunset".to_string()]);
+    }
+    
+    #[tokio::test]
+    async fn test_iter_documents() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        let first = create_test_asset("first.jpg");
+        let second = create_test_asset("second.jpg");
+        service.index_asset(&first).await.unwrap();
+        service.index_asset(&second).await.unwrap();
+        
+        // A corrupt record is reported without ending the iteration
+        service.doc_store.insert(Uuid::nil().as_bytes(), b"not json".to_vec()).unwrap();
+        
+        let items: Vec<_> = service.iter_documents().collect();
+        assert_eq!(items.len(), 3);
+        assert_eq!(items.iter().filter(|item| item.is_err()).count(), 1);
+        
+        let mut asset_ids: Vec<Uuid> = items.into_iter().filter_map(Result::ok).map(|d| d.asset_id).collect();
+        let mut expected = vec![first.id, second.id];
+        asset_ids.sort();
+        expected.sort();
+        assert_eq!(asset_ids, expected);
+    }
+    
+    #[tokio::test]
+    async fn test_catalog_export_import() {
+        let source_dir = TempDir::new().unwrap();
+        let mut source = IndexService::with_storage_dir(source_dir.path()).unwrap();
+        
+        let asset = create_test_asset("harbor.jpg");
+        source.index_asset(&asset).await.unwrap();
+        source.update_with_ai_results(
+            asset.id,
+            Some(vec!["boats".to_string()]),
+            Some("Boats in a harbor, at dusk".to_string()),
+            None,
+            Some(vec![0.1, 0.2, 0.3]),
+            None
+        ).await.unwrap();
+        
+        let mut buffer = Vec::new();
+        assert_eq!(source.export_catalog(ExportFormat::Csv, &mut buffer).unwrap(), 1);
+        
+        let target_dir = TempDir::new().unwrap();
+        let mut target = IndexService::with_storage_dir(target_dir.path()).unwrap();
+        assert_eq!(target.import_catalog(ExportFormat::Csv, buffer.as_slice()).await.unwrap(), 1);
+        
+        let document = target.get_asset_document(asset.id).unwrap().unwrap();
+        assert_eq!(document.ai_tags, vec!["boats".to_string()]);
+        assert_eq!(document.ai_caption.as_deref(), Some("Boats in a harbor, at dusk"));
+        assert!(document.visual_embedding.is_none());
+        
+        let results = target.search_text("boats", 10).await.unwrap();
+        assert_eq!(results.len(), 1);
+        
+        // Importing over the same asset keeps what the catalog does not carry
+        source.update_with_ai_results(asset.id, None, None, Some("gulls and bells".to_string()), None, None).await.unwrap();
+        assert_eq!(source.import_catalog(ExportFormat::Csv, buffer.as_slice()).await.unwrap(), 1);
+        let document = source.get_asset_document(asset.id).unwrap().unwrap();
+        assert_eq!(document.transcription.as_deref(), Some("gulls and bells"));
+        assert!(document.visual_embedding.is_some());
+        assert_eq!(source.search_text("gulls", 10).await.unwrap().len(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_iter_unprocessed() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        let first = create_test_asset("first.jpg");
+        let second = create_test_asset("second.jpg");
+        service.index_asset(&first).await.unwrap();
+        service.index_asset(&second).await.unwrap();
+        
+        assert_eq!(service.iter_unprocessed(AiKind::VisualEmbedding).count(), 2);
+        
+        // Images never need transcription
+        assert_eq!(service.iter_unprocessed(AiKind::Transcription).count(), 0);
+        
+        service.update_with_ai_results(
+            first.id,
+            None,
+            None,
+            None,
+            Some(vec![0.1, 0.2, 0.3, 0.4]),
+            None
+        ).await.unwrap();
+        
+        let remaining: Vec<(Uuid, PathBuf)> = service.iter_unprocessed(AiKind::VisualEmbedding).collect();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].0, second.id);
+        assert_eq!(remaining[0].1, PathBuf::from("second.jpg"));
+    }
+    
+    #[tokio::test]
+    async fn test_rating_and_favorite_filters() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        let mut assets = Vec::new();
+        for name in ["a.jpg", "b.jpg", "c.jpg", "d.jpg"] {
+            let mut asset = create_test_asset(name);
+            asset.tags = vec!["harbor".to_string()];
+            service.index_asset(&asset).await.unwrap();
+            assets.push(asset);
+        }
+        
+        service.set_rating(assets[0].id, Some(2)).await.unwrap();
+        service.set_rating(assets[1].id, Some(5)).await.unwrap();
+        service.set_rating(assets[2].id, Some(4)).await.unwrap();
+        service.set_favorite(assets[2].id, true).await.unwrap();
+        assert!(service.set_rating(assets[3].id, Some(6)).await.is_err());
+        
+        let ids = |results: Vec<SearchResult>| results.into_iter().map(|r| r.document.asset_id).collect::<Vec<_>>();
+        
+        let query = SearchQuery::text_search("harbor").with_min_rating(4);
+        let results = service.search(&query).await.unwrap();
+        assert_eq!(results.len(), 2);
+        
+        let query = SearchQuery::default().favorites_only();
+        assert_eq!(ids(service.search(&query).await.unwrap()), vec![assets[2].id]);
+        
+        // Unrated assets sort last
+        let query = SearchQuery::default().sorted_by(SortCriteria::Rating { ascending: false });
+        assert_eq!(
+            ids(service.search(&query).await.unwrap()),
+            vec![assets[1].id, assets[2].id, assets[0].id, assets[3].id]
+        );
+        
+        // Curation survives re-ingestion and reopening the index
+        service.index_asset(&assets[2]).await.unwrap();
+        drop(service);
+        let service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        let document = service.get_asset_document(assets[2].id).unwrap().unwrap();
+        assert_eq!(document.rating, Some(4));
+        assert!(document.favorite);
+    }
+    
+    #[tokio::test]
+    async fn test_chunked_text_embeddings() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        let interview = create_test_asset("interview.wav");
+        let other = create_test_asset("other.wav");
+        service.index_asset(&interview).await.unwrap();
+        service.index_asset(&other).await.unwrap();
+        
+        // Only the last chunk of the transcript is about the query topic
+        let chunks = vec![vec![1.0, 0.0, 0.0], vec![0.9, 0.1, 0.0], vec![0.0, 0.0, 1.0]];
+        service.update_text_embedding_chunks(interview.id, chunks).await.unwrap();
+        service.update_with_ai_results(other.id, None, None, None, None, Some(vec![0.6, 0.0, 0.8])).await.unwrap();
+        
+        let results = service.search_text_similar(&[0.0, 0.0, 1.0], 10, None).await.unwrap();
+        assert_eq!(results[0].document.asset_id, interview.id);
+        assert!((results[0].vector_score - 1.0).abs() < 1e-6);
+        
+        // Chunks are persisted alongside their mean
+        drop(service);
+        let service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        let document = service.get_asset_document(interview.id).unwrap().unwrap();
+        assert_eq!(document.text_embedding_chunks.len(), 3);
+        assert_eq!(document.text_embedding.unwrap().len(), 3);
+        let results = service.search_text_similar(&[0.0, 0.0, 1.0], 1, None).await.unwrap();
+        assert_eq!(results[0].document.asset_id, interview.id);
+    }
+    
+    #[tokio::test]
+    async fn test_compact() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        let mut assets = Vec::new();
+        for i in 0..200 {
+            let mut asset = create_test_asset(&format!("shot_{}.jpg", i));
+            asset.tags = vec!["harbor".to_string(), "x".repeat(2000)];
+            service.index_asset(&asset).await.unwrap();
+            assets.push(asset);
+        }
+        for asset in &assets[1..] {
+            service.remove_asset(asset.id).await.unwrap();
+        }
+        
+        let mut events = service.events().subscribe();
+        let stats = service.compact().await.unwrap();
+        assert_eq!(stats.documents, 1);
+        assert!(stats.bytes_after < stats.bytes_before);
+        assert_eq!(stats.reclaimed_bytes(), stats.bytes_before - stats.bytes_after);
+        
+        // Progress is shown while compacting, followed by a notification
+        assert!(matches!(events.try_recv().unwrap(), schema::UiMessage::ShowProgress { .. }));
+        assert!(matches!(events.try_recv().unwrap(), schema::UiMessage::HideProgress));
+        assert!(matches!(
+            events.try_recv().unwrap(),
+            schema::UiMessage::Notification { level: NotificationLevel::Success, .. }
+        ));
+        
+        // The service keeps working on the compacted database
+        let results = service.search_text("harbor", 10).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document.asset_id, assets[0].id);
+        service.index_asset(&create_test_asset("after.jpg")).await.unwrap();
+        
+        drop(service);
+        let service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        assert_eq!(service.get_stats().total_documents, 2);
+        assert!(!temp_dir.path().join("documents.db.old").exists());
+    }
+    
+    #[test]
+    fn test_failed_swap_keeps_original() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("documents.db");
+        let old_path = temp_dir.path().join("documents.db.old");
+        std::fs::create_dir(&db_path).unwrap();
+        std::fs::write(db_path.join("conf"), "original").unwrap();
+        
+        // No compacted database to move in
+        assert!(swap_database(&db_path, &temp_dir.path().join("documents.db.compact"), &old_path).is_err());
+        assert_eq!(std::fs::read_to_string(db_path.join("conf")).unwrap(), "original");
+        assert!(!old_path.exists());
+    }
+    
+    #[test]
+    fn test_failed_reopen_restores_original() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("documents.db");
+        let compact_path = temp_dir.path().join("documents.db.compact");
+        let old_path = temp_dir.path().join("documents.db.old");
+        {
+            let original = sled::open(&db_path).unwrap();
+            original.insert("kept", "original").unwrap();
+            original.flush().unwrap();
+            let compacted = sled::open(&compact_path).unwrap();
+            compacted.insert("kept", "compacted").unwrap();
+            compacted.flush().unwrap();
+        }
+        
+        // The first open, of the swapped in compacted database, fails
+        let opens = std::cell::Cell::new(0);
+        let (db, swapped) = swap_and_reopen(&db_path, &compact_path, &old_path, |path| {
+            opens.set(opens.get() + 1);
+            if opens.get() == 1 {
+                Err(sled::Error::Unsupported("forced failure".to_string()))
+            } else {
+                sled::open(path)
+            }
+        }).unwrap();
+        assert!(swapped.is_err());
+        assert_eq!(opens.get(), 2);
+        
+        // The original is back in place, readable and writable
+        assert_eq!(db.get("kept").unwrap().unwrap(), "original");
+        db.insert("added", "after").unwrap();
+        db.flush().unwrap();
+        drop(db);
+        let reopened = sled::open(&db_path).unwrap();
+        assert_eq!(reopened.get("kept").unwrap().unwrap(), "original");
+        assert_eq!(reopened.get("added").unwrap().unwrap(), "after");
+        assert!(compact_path.exists());
+        assert!(!old_path.exists());
+    }
+    
+    #[tokio::test]
+    async fn test_batch_index_and_remove() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        let existing = create_test_asset("existing.jpg");
+        service.index_asset(&existing).await.unwrap();
+        let existing_doc = service.get_asset_document(existing.id).unwrap().unwrap();
+        
+        let assets = vec![existing.clone(), create_test_asset("first.jpg"), create_test_asset("second.jpg")];
+        let results = service.index_assets(&assets).await.unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|result| result.is_ok()));
+        assert_eq!(service.get_stats().total_documents, 3);
+        
+        // Re-indexing in a batch reuses the existing document
+        assert_eq!(service.get_asset_document(existing.id).unwrap().unwrap().id, existing_doc.id);
+        assert_eq!(service.search_text("second", 10).await.unwrap().len(), 1);
+        
+        let unknown = Uuid::new_v4();
+        let results = service.remove_assets(&[assets[1].id, unknown, assets[2].id]).await.unwrap();
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+        assert!(results[2].is_ok());
+        assert!(service.search_text("second", 10).await.unwrap().is_empty());
+        
+        drop(service);
+        let service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        assert_eq!(service.get_stats().total_documents, 1);
+        assert!(service.get_asset_document(existing.id).unwrap().is_some());
+        assert!(service.get_asset_document(assets[1].id).unwrap().is_none());
+    }
+    
+    #[tokio::test]
+    async fn test_rejected_update_leaves_index_unchanged() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        let first = create_test_asset("first.jpg");
+        let second = create_test_asset("second.jpg");
+        service.index_asset(&first).await.unwrap();
+        service.index_asset(&second).await.unwrap();
+        service.update_with_ai_results(first.id, None, None, None, Some(vec![1.0, 0.0, 0.0]), None).await.unwrap();
+        
+        // An embedding of another dimension is refused before anything is written
+        let before = serde_json::to_value(service.get_asset_document(second.id).unwrap().unwrap()).unwrap();
+        let tags = Some(vec!["harbor".to_string()]);
+        assert!(service.update_with_ai_results(second.id, tags, None, None, Some(vec![1.0, 0.0]), None).await.is_err());
+        let after = serde_json::to_value(service.get_asset_document(second.id).unwrap().unwrap()).unwrap();
+        assert_eq!(before, after);
+        assert!(service.search_text("harbor", 10).await.unwrap().is_empty());
+        assert_eq!(service.embedding_dimension(EmbeddingType::Visual), Some(3));
+    }
+    
+    #[tokio::test]
+    async fn test_group_by_date() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        let date = |value: &str| chrono::DateTime::parse_from_rfc3339(value).unwrap().with_timezone(&Utc);
+        
+        // The capture date wins over the ingestion date
+        let mut captured = create_test_asset("captured.jpg");
+        captured.created_at = date("2024-06-01T10:00:00Z");
+        captured.metadata.custom.insert("DateTimeOriginal".to_string(), "2021:03:05 14:30:00".to_string());
+        let mut march = create_test_asset("march.jpg");
+        march.created_at = date("2021-03-20T09:00:00Z");
+        let mut june = create_test_asset("june.jpg");
+        june.created_at = date("2024-06-01T10:00:00Z");
+        let mut unset = create_test_asset("unset.jpg");
+        unset.created_at = chrono::DateTime::<Utc>::UNIX_EPOCH;
+        
+        for asset in [&captured, &march, &june, &unset] {
+            service.index_asset(asset).await.unwrap();
+        }
+        
+        let months = service.group_by_date(DateGranularity::Month).unwrap();
+        let labels: Vec<String> = months.iter().map(|(bucket, _)| bucket.label()).collect();
+        assert_eq!(labels, vec!["2024-06", "2021-03", "Undated"]);
+        assert_eq!(months[0].1, vec![june.id]);
+        assert_eq!(months[1].1, vec![march.id, captured.id]);
+        assert_eq!(months[2], (DateBucket::Undated, vec![unset.id]));
+        
+        let years = service.group_by_date(DateGranularity::Year).unwrap();
+        assert_eq!(years.len(), 3);
+        assert_eq!(years[1].0, DateBucket::Year(2021));
+    }
+    
+    #[tokio::test]
+    async fn test_get_waveform() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        let mut clip = create_test_asset("clip.wav");
+        clip.asset_type = AssetType::Audio;
+        clip.preview = Some(PreviewInfo {
+            thumbnail_path: PathBuf::from("/previews/clip.jpg"),
+            thumbnail_size: (256, 64),
+            rendered_preview: None,
+            generated_at: Utc::now(),
+            extension: "jpg".to_string(),
+            waveform: Some(schema::Waveform {
+                peaks: vec![(-0.1, 0.2), (-0.5, 0.1), (-0.2, 0.9), (0.0, 0.3)],
+            }),
+            contact_sheet_grid: None,
+        });
+        let photo = create_test_asset("photo.jpg");
+        service.index_asset(&clip).await.unwrap();
+        service.index_asset(&photo).await.unwrap();
+        
+        assert_eq!(service.get_waveform(clip.id, 2).unwrap(), vec![(-0.5, 0.2), (-0.2, 0.9)]);
+        assert_eq!(service.get_waveform(clip.id, 100).unwrap().len(), 4);
+        assert!(service.get_waveform(photo.id, 100).unwrap().is_empty());
+        assert!(service.get_waveform(Uuid::new_v4(), 100).is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_query_expansion() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        let ai_tagged = [
+            ("first.jpg", vec!["dog", "puppy"]),
+            ("second.jpg", vec!["dog", "puppy", "grass"]),
+            ("third.jpg", vec!["puppy"]),
+            ("fourth.jpg", vec!["forest"]),
+            ("fifth.jpg", vec!["forest"]),
+        ];
+        let mut ids = Vec::new();
+        for (filename, tags) in ai_tagged {
+            let asset = create_test_asset(filename);
+            service.index_asset(&asset).await.unwrap();
+            let tags = tags.into_iter().map(String::from).collect();
+            service.update_with_ai_results(asset.id, Some(tags), None, None, None, None).await.unwrap();
+            ids.push(asset.id);
+        }
+        
+        assert_eq!(service.search_text("dog", 10).await.unwrap().len(), 2);
+        
+        let config = IndexConfig { query_expansion: true, ..IndexConfig::default() };
+        service.set_config(config).unwrap();
+        
+        // `puppy` always accompanies `dog`; `grass` only once, which is noise
+        let results = service.search_text("dog", 10).await.unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[2].document.asset_id, ids[2]);
+        assert!(results[2].score < results[1].score);
+        
+        let invalid = IndexConfig { expansion_weight: 1.5, ..IndexConfig::default() };
+        assert!(service.set_config(invalid).is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_get_asset_details() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        let embeddings = [vec![1.0, 0.0, 0.0], vec![0.9, 0.1, 0.0], vec![0.0, 0.0, 1.0]];
+        let mut ids = Vec::new();
+        for (index, embedding) in embeddings.into_iter().enumerate() {
+            let asset = create_test_asset(&format!("photo_{}.jpg", index));
+            service.index_asset(&asset).await.unwrap();
+            service.update_with_ai_results(asset.id, Some(vec!["beach".to_string()]), None, None, Some(embedding), None).await.unwrap();
+            ids.push(asset.id);
+        }
+        let plain = create_test_asset("notes.txt");
+        service.index_asset(&plain).await.unwrap();
+        
+        let details = service.get_asset_details(ids[0]).unwrap();
+        assert_eq!(details.document.asset_id, ids[0]);
+        assert_eq!(details.document.ai_tags, vec!["beach"]);
+        assert_eq!(details.similar.len(), 1);
+        assert_eq!(details.similar[0].0, ids[1]);
+        
+        assert!(service.get_asset_details(plain.id).unwrap().similar.is_empty());
+        assert!(service.get_asset_details(Uuid::new_v4()).is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_score_explanation() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        let beach = create_test_asset("beach_vacation.jpg");
+        service.index_asset(&beach).await.unwrap();
+        service.update_with_ai_results(beach.id, None, None, None, Some(vec![1.0, 0.0]), None).await.unwrap();
+        for filler in ["forest.jpg", "city.jpg"] {
+            service.index_asset(&create_test_asset(filler)).await.unwrap();
+        }
+        
+        let results = service.search_text("vacation", 10).await.unwrap();
+        assert!(results[0].explanation.is_none());
+        
+        let weights = FieldWeights::default();
+        let results = service.search_text_weighted("vacation", 10, &weights, true).await.unwrap();
+        let explanation = results[0].explanation.as_ref().unwrap();
+        let term = explanation.terms.iter().find(|term| term.field == "filename").unwrap();
+        assert_eq!(term.term, TextLanguage::English.stemmer().unwrap().stem("vacation"));
+        assert_eq!(term.term_frequency, 1);
+        assert_eq!(term.boost, weights.get("filename"));
+        assert!((term.score - term.idf * term.boost).abs() < 1e-6);
+        assert_eq!(explanation.score, results[0].score);
+        
+        // Hybrid scores add the vector component and quality multiplier
+        let results = service.search_hybrid_with_boosts("vacation", Some(&[1.0, 0.0]), 10, &TypeBoosts::default(), true).await.unwrap();
+        let explanation = results[0].explanation.as_ref().unwrap();
+        assert!((explanation.vector_score - 1.0).abs() < 1e-6);
+        assert_eq!(explanation.quality_multiplier, results[0].document.quality_score);
+        assert_eq!(explanation.score, results[0].score);
+        
+        let query = SearchQuery::text_search("vacation").explained();
+        assert!(service.search(&query).await.unwrap()[0].explanation.is_some());
+    }
+    
+    #[tokio::test]
+    async fn test_text_language() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        service.set_config(IndexConfig { language: TextLanguage::French, ..IndexConfig::default() }).unwrap();
+        
+        let captions = [
+            ("salon.jpg", "Les chats noirs dorment sur le canapé"),
+            ("plage.jpg", "Une photo de la plage avec des enfants"),
+        ];
+        let mut ids = Vec::new();
+        for (filename, caption) in captions {
+            let asset = create_test_asset(filename);
+            service.index_asset(&asset).await.unwrap();
+            service.update_with_ai_results(asset.id, None, Some(caption.to_string()), None, None, None).await.unwrap();
+            ids.push(asset.id);
+        }
+        
+        // Inflected forms match and stop words are not indexed
+        let results = service.search_text("chat noir", 10).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document.asset_id, ids[0]);
+        assert!(service.search_text("les", 10).await.unwrap().is_empty());
+        
+        // Auto settles on the language of the captions
+        let documents: Vec<AssetDocument> = service.iter_documents().map(Result::unwrap).collect();
+        let mut index = TextIndex::new(IndexConfig { language: TextLanguage::Auto, ..IndexConfig::default() });
+        assert_eq!(index.language(), TextLanguage::English);
+        assert_eq!(index.detect_language(&documents), TextLanguage::French);
+    }
+    
+    #[tokio::test]
+    async fn test_processing_status() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        let asset = create_test_asset("interview.jpg");
+        service.index_asset(&asset).await.unwrap();
+        let status = |service: &IndexService| service.get_asset_document(asset.id).unwrap().unwrap().processing_status;
+        assert_eq!(status(&service), ProcessingStatus::default());
+        
+        let task_id = Uuid::new_v4();
+        service.apply_processing_event(&ProcessMessage::Started {
+            task_id,
+            asset_id: asset.id,
+            task_type: ProcessingTaskType::ImageTagging,
+        }).await.unwrap();
+        assert_eq!(status(&service).tagging, StepStatus::InProgress);
+        
+        service.apply_processing_event(&ProcessMessage::Failed {
+            task_id,
+            asset_id: asset.id,
+            task_type: ProcessingTaskType::ImageTagging,
+            error: "model not loaded".to_string(),
+        }).await.unwrap();
+        assert_eq!(status(&service).tagging, StepStatus::Failed { reason: "model not loaded".to_string() });
+        assert_eq!(status(&service).embedding, StepStatus::NotStarted);
+        
+        let failed: Vec<_> = service.iter_failed_processing().collect();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].0, asset.id);
+        assert_eq!(failed[0].2, ProcessingTaskType::ImageTagging);
+        
+        // A successful retry stores the result and clears the failure
+        service.apply_processing_event(&ProcessMessage::Completed {
+            task_id: Uuid::new_v4(),
+            asset_id: asset.id,
+            result: ProcessingResult::Tags { tags: vec!["microphone".to_string()] },
+        }).await.unwrap();
+        let document = service.get_asset_document(asset.id).unwrap().unwrap();
+        assert_eq!(document.processing_status.tagging, StepStatus::Done);
+        assert_eq!(document.ai_tags, vec!["microphone"]);
+        assert_eq!(service.iter_failed_processing().count(), 0);
+    }
+    
+    #[tokio::test]
+    async fn test_prefix_search() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        for filename in ["architecture.jpg", "archive.zip", "archer.png", "arch.jpg", "bridge.psd"] {
+            service.index_asset(&create_test_asset(filename)).await.unwrap();
+        }
+        
+        let results = service.search_text("arch*", 10).await.unwrap();
+        assert_eq!(results.len(), 4);
+        // The exact term outranks terms that only share the prefix
+        assert_eq!(results[0].document.filename, "arch.jpg");
+        
+        let expanded = service.text_index.expand_prefix("ARCH");
+        assert_eq!(expanded.len(), 4);
+        assert!(expanded.iter().all(|(term, _)| term.starts_with("arch")));
+        
+        // Leading wildcards act as a plain extension search
+        let results = service.search_text("*.psd", 10).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document.filename, "bridge.psd");
+        
+        // Too-short prefixes do not expand to the whole index
+        assert!(service.text_index.expand_prefix("a").is_empty());
+        assert!(service.text_index.expand_prefix("").is_empty());
+        assert!(service.search_text("a*", 10).await.unwrap().is_empty());
+        
+        // The prefix is stemmed like the indexed words
+        service.index_asset(&create_test_asset("happy.jpg")).await.unwrap();
+        assert_eq!(service.search_text("happy*", 10).await.unwrap().len(), 1);
+        
+        // After the exact term, the most frequent expansions come first
+        let mut tagged = create_test_asset("vault.png");
+        tagged.tags = vec!["archive".to_string()];
+        service.index_asset(&tagged).await.unwrap();
+        let expanded = service.text_index.expand_prefix("arch");
+        assert_eq!(expanded[0], ("arch".to_string(), 1.0));
+        assert!(expanded[1].0.starts_with("archiv"));
+        assert!(expanded[1].1 < 1.0);
+    }
+    
+    #[tokio::test]
+    async fn test_processing_stats() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        let done = create_test_asset("done.jpg");
+        let failed = create_test_asset("failed.jpg");
+        let pending = create_test_asset("pending.jpg");
+        for asset in [&done, &failed, &pending] {
+            service.index_asset(asset).await.unwrap();
+        }
+        service.update_with_ai_results(done.id, Some(vec!["tree".to_string()]), None, None, Some(vec![1.0, 0.0]), None).await.unwrap();
+        let failure = StepStatus::Failed { reason: "out of memory".to_string() };
+        service.set_processing_step(failed.id, &ProcessingTaskType::ImageTagging, failure).await.unwrap();
+        
+        let stats = service.get_stats().processing;
+        assert_eq!(stats.total_assets, 3);
+        assert_eq!((stats.fully_processed, stats.failed, stats.pending), (1, 1, 1));
+        assert_eq!(stats.tagging.applicable, 3);
+        assert_eq!((stats.tagging.done, stats.tagging.failed, stats.tagging.not_started()), (1, 1, 1));
+        assert_eq!(stats.embedding.done, 1);
+        // Transcription does not apply to images
+        assert_eq!(stats.transcription.applicable, 0);
+        
+        service.remove_asset(done.id).await.unwrap();
+        let stats = service.get_stats().processing;
+        assert_eq!((stats.total_assets, stats.fully_processed), (2, 0));
+        
+        // Counts are rebuilt from storage
+        drop(service);
+        let service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        let stats = service.get_stats().processing;
+        assert_eq!((stats.total_assets, stats.failed, stats.pending), (2, 1, 1));
+    }
+    
+    #[tokio::test]
+    async fn test_resident_embedding_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = IndexConfig { max_resident_embeddings: Some(2), ..IndexConfig::default() };
+        let mut service = IndexService::open(temp_dir.path().to_path_buf(), config).unwrap();
+        
+        let assets: Vec<Asset> = (0..4).map(|i| create_test_asset(&format!("frame{}.jpg", i))).collect();
+        for (i, asset) in assets.iter().enumerate() {
+            service.index_asset(asset).await.unwrap();
+            let mut embedding = vec![0.1; 4];
+            embedding[i] = 1.0;
+            service.update_with_ai_results(asset.id, None, None, None, Some(embedding), None).await.unwrap();
+        }
+        
+        let stats = service.get_stats();
+        assert_eq!((stats.visual_embeddings, stats.resident_embeddings, stats.spilled_embeddings), (4, 2, 2));
+        
+        // Spilled embeddings are kept as raw floats beside the documents
+        let spilled = service.doc_store.open_tree(SPILLED_EMBEDDINGS_TREE).unwrap();
+        assert_eq!(spilled.len(), 2);
+        let chunks = vec![vec![1.0, -0.5], vec![0.25, 2.0]];
+        assert_eq!(decode_embeddings(&encode_embeddings(&chunks)), Some(chunks));
+        assert_eq!(decode_embeddings(&[2, 0, 0, 0, 1]), None);
+        
+        // The first asset was spilled but is still found, read back from storage
+        let results = service.search_visual_similar(&[1.0, 0.1, 0.1, 0.1], 1, Some(0.0)).await.unwrap();
+        assert_eq!(results[0].document.asset_id, assets[0].id);
+        let similar = service.find_similar(assets[1].id, EmbeddingType::Visual, 3, Some(0.0)).await.unwrap();
+        assert_eq!(similar.len(), 3);
+        
+        // Lifting the limit brings everything back into memory
+        service.set_config(IndexConfig::default()).unwrap();
+        let stats = service.get_stats();
+        assert_eq!((stats.resident_embeddings, stats.spilled_embeddings), (4, 0));
+        
+        // Reopening with a limit spills while loading
+        drop(service);
+        let config = IndexConfig { max_resident_embeddings: Some(3), ..IndexConfig::default() };
+        let service = IndexService::open(temp_dir.path().to_path_buf(), config).unwrap();
+        let stats = service.get_stats();
+        assert_eq!((stats.resident_embeddings, stats.spilled_embeddings), (3, 1));
+    }
+    
+    #[tokio::test]
+    async fn test_storage_stats() {
+        let temp_dir = TempDir::new().unwrap();
+        let preview_dir = temp_dir.path().join("previews");
+        std::fs::create_dir_all(preview_dir.join("ab")).unwrap();
+        std::fs::write(preview_dir.join("ab").join("thumb.jpg"), vec![0u8; 300]).unwrap();
+        std::fs::write(preview_dir.join("preview.jpg"), vec![0u8; 200]).unwrap();
+        
+        let service = IndexService::with_storage_dir(temp_dir.path().join("index")).unwrap();
+        let stats = service.get_stats();
+        assert_eq!(stats.preview_bytes, None);
+        assert_eq!(stats.vector_memory_bytes, 0);
+        assert!(stats.last_indexed_at.is_none());
+        
+        let mut service = service.with_preview_dir(&preview_dir);
+        let first = create_test_asset("first.jpg");
+        let second = create_test_asset("second.jpg");
+        service.index_asset(&first).await.unwrap();
+        service.index_asset(&second).await.unwrap();
+        service.update_with_ai_results(first.id, None, None, None, Some(vec![1.0; 4]), None).await.unwrap();
+        service.doc_store.flush().unwrap();
+        
+        let stats = service.get_stats();
+        assert_eq!(stats.preview_bytes, Some(500));
+        assert!(stats.database_bytes > 0);
+        match schema::IndexMessage::from(&stats) {
+            schema::IndexMessage::Stats { document_count, index_size_bytes, .. } => {
+                assert_eq!((document_count, index_size_bytes), (2, stats.database_bytes));
             }
+            other => panic!("unexpected message {:?}", other),
         }
-        Ok(None)
+        
+        // The measured size is reused until it is due again
+        std::fs::write(preview_dir.join("extra.jpg"), vec![0u8; 100]).unwrap();
+        assert_eq!(service.get_stats().preview_bytes, Some(500));
+        *service.preview_size.lock().unwrap() = None;
+        assert_eq!(service.get_stats().preview_bytes, Some(600));
+        assert!(stats.vector_memory_bytes >= 16);
+        let indexed_at = |asset: &Asset| service.find_document_by_asset_id(&asset.id).unwrap().unwrap().indexed_at;
+        assert_eq!(stats.last_indexed_at, Some(indexed_at(&first).max(indexed_at(&second))));
     }
-}
-
-impl Default for IndexService {
-    fn default() -> Self {
-        Self::new().expect("Failed to create IndexService")
+    
+    #[tokio::test]
+    async fn test_get_embedding() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        let photo = create_test_asset("photo.jpg");
+        service.index_asset(&photo).await.unwrap();
+        assert_eq!(service.get_embedding(photo.id, EmbeddingType::Visual).unwrap(), None);
+        
+        service.update_with_ai_results(photo.id, None, None, None, Some(vec![3.0, 4.0]), None).await.unwrap();
+        service.update_text_embedding_chunks(photo.id, vec![vec![1.0, 0.0], vec![0.0, 2.0]]).await.unwrap();
+        
+        // Vectors come back unit-length, as search compares them
+        assert_eq!(service.get_embedding(photo.id, EmbeddingType::Visual).unwrap(), Some(vec![0.6, 0.8]));
+        let text = service.get_embedding(photo.id, EmbeddingType::Text).unwrap().unwrap();
+        assert!((text[0] - text[1]).abs() < 1e-6);
+        assert!((text.iter().map(|x| x * x).sum::<f32>() - 1.0).abs() < 1e-5);
+        
+        assert!(service.get_embedding(Uuid::new_v4(), EmbeddingType::Visual).is_err());
     }
-}
-
-/// Index statistics
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct IndexStats {
-    pub total_documents: usize,
-    pub total_terms: usize,
-    pub avg_terms_per_doc: f32,
-    pub visual_embeddings: usize,
-    pub text_embeddings: usize,
-    pub visual_dimension: Option<usize>,
-    pub text_dimension: Option<usize>,
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use schema::{AssetType, FileFormat, AssetMetadata, VersionInfo};
-    use std::path::PathBuf;
-    use chrono::Utc;
-    use tempfile::TempDir;
     
-    fn create_test_asset(filename: &str) -> Asset {
-        let path = PathBuf::from(filename);
-        let now = Utc::now();
+    #[tokio::test]
+    async fn test_clear_embeddings() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
         
-        Asset {
-            id: Uuid::new_v4(),
-            original_path: path.clone(),
-            current_path: path,
-            asset_type: AssetType::Image,
-            file_size: 1024,
-            format: FileFormat {
-                extension: "jpg".to_string(),
-                mime_type: Some("image/jpeg".to_string()),
-                version: None,
-                supported: true,
-            },
-            created_at: now,
-            modified_at: now,
-            tags: Vec::new(),
-            metadata: AssetMetadata::default(),
-            preview: None,
-            embedding: None,
-            version_info: VersionInfo {
-                current_version: "v1".to_string(),
-                version_count: 1,
-                last_snapshot: now,
-                has_changes: false,
-            },
+        let photo = create_test_asset("photo.jpg");
+        let scan = create_test_asset("scan.jpg");
+        service.index_asset(&photo).await.unwrap();
+        service.index_asset(&scan).await.unwrap();
+        service.update_with_ai_results(photo.id, None, None, None, Some(vec![1.0, 0.0]), None).await.unwrap();
+        service.update_with_ai_results(scan.id, None, None, None, Some(vec![0.0, 1.0]), Some(vec![1.0, 0.0, 0.0])).await.unwrap();
+        
+        // A larger model's embeddings are rejected until the old ones are gone
+        assert!(service.update_with_ai_results(photo.id, None, None, None, Some(vec![1.0, 0.0, 0.0]), None).await.is_err());
+        assert_eq!(service.clear_embeddings(EmbeddingType::Visual).await.unwrap(), 2);
+        assert_eq!(service.embedding_dimension(EmbeddingType::Visual), None);
+        assert_eq!(service.embedding_dimension(EmbeddingType::Text), Some(3));
+        
+        let photo_doc = service.get_asset_document(photo.id).unwrap().unwrap();
+        assert!(photo_doc.visual_embedding.is_none());
+        assert_eq!(photo_doc.processing_status.embedding, StepStatus::NotStarted);
+        // The text embedding is kept, so the scan's embedding step stays done
+        let scan_doc = service.get_asset_document(scan.id).unwrap().unwrap();
+        assert_eq!(scan_doc.processing_status.embedding, StepStatus::Done);
+        
+        service.update_with_ai_results(photo.id, None, None, None, Some(vec![1.0, 0.0, 0.0]), None).await.unwrap();
+        assert_eq!(service.embedding_dimension(EmbeddingType::Visual), Some(3));
+        
+        // Swapping in an embedder of another size drops the old vectors
+        assert!(!service.ensure_embedding_dimension(EmbeddingType::Text, 3).await.unwrap());
+        assert!(service.ensure_embedding_dimension(EmbeddingType::Text, 4).await.unwrap());
+        assert_eq!(service.embedding_dimension(EmbeddingType::Text), Some(4));
+        assert!(service.update_with_ai_results(scan.id, None, None, None, None, Some(vec![1.0, 0.0, 0.0])).await.is_err());
+        
+        // Cleared embeddings stay gone after reopening
+        drop(service);
+        let service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        assert_eq!(service.get_stats().visual_embeddings, 1);
+    }
+    
+    #[tokio::test]
+    async fn test_audit_embeddings() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        let photo = create_test_asset("photo.jpg");
+        let scan = create_test_asset("scan.jpg");
+        let sketch = create_test_asset("sketch.jpg");
+        for asset in [&photo, &scan, &sketch] {
+            service.index_asset(asset).await.unwrap();
         }
+        service.update_with_ai_results(photo.id, None, None, None, Some(vec![1.0, 0.0]), Some(vec![1.0, 0.0, 0.0])).await.unwrap();
+        service.update_with_ai_results(scan.id, None, None, None, Some(vec![0.0, 1.0]), None).await.unwrap();
+        assert!(service.audit_embeddings().unwrap().is_consistent());
+        
+        // An embedding written by another model, bypassing the vector store
+        let mut document = service.get_asset_document(sketch.id).unwrap().unwrap();
+        document.visual_embedding = Some(vec![1.0, 0.0, 0.0, 0.0]);
+        document.processing_status.embedding = StepStatus::Done;
+        service.store_document(&document).await.unwrap();
+        
+        // Reopening loads the others instead of failing on the odd one
+        drop(service);
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        assert_eq!(service.get_stats().visual_embeddings, 2);
+        
+        let audit = service.audit_embeddings().unwrap();
+        assert!(!audit.is_consistent());
+        assert_eq!(audit.visual.expected, Some(2));
+        assert_eq!(audit.visual.dimensions, BTreeMap::from([(2, 2), (4, 1)]));
+        assert_eq!(audit.visual.mismatched, vec![sketch.id]);
+        assert_eq!(audit.text.dimensions, BTreeMap::from([(3, 1)]));
+        assert!(audit.text.mismatched.is_empty());
+        
+        // A dimension declared by the active embedder wins over the majority
+        let declared = DimensionAudit::new(Some(4), vec![(photo.id, vec![2]), (sketch.id, vec![4])]);
+        assert_eq!(declared.mismatched, vec![photo.id]);
+        
+        let repaired = service.repair_embeddings().await.unwrap();
+        assert_eq!(repaired.requeued, vec![sketch.id]);
+        let document = service.get_asset_document(sketch.id).unwrap().unwrap();
+        assert!(document.visual_embedding.is_none());
+        assert_eq!(document.processing_status.embedding, StepStatus::NotStarted);
+        assert!(service.audit_embeddings().unwrap().is_consistent());
+        assert_eq!(service.get_stats().visual_dimension, Some(2));
+        
+        // The repaired asset accepts an embedding of the library's dimension
+        service.update_with_ai_results(sketch.id, None, None, None, Some(vec![1.0, 1.0]), None).await.unwrap();
+        assert_eq!(service.get_stats().visual_embeddings, 3);
     }
     
     #[tokio::test]
-    async fn test_index_service_creation() {
+    async fn test_rebuild_vector_store() {
         let temp_dir = TempDir::new().unwrap();
-        let service = IndexService::with_storage_dir(temp_dir.path());
-        assert!(service.is_ok());
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        let photo = create_test_asset("photo.jpg");
+        let scan = create_test_asset("scan.jpg");
+        let odd = create_test_asset("odd.jpg");
+        for asset in [&photo, &scan, &odd] {
+            service.index_asset(asset).await.unwrap();
+        }
+        service.update_with_ai_results(photo.id, None, None, None, Some(vec![1.0, 0.0]), Some(vec![1.0, 0.0, 0.0])).await.unwrap();
+        service.update_with_ai_results(scan.id, None, None, None, Some(vec![0.0, 1.0]), None).await.unwrap();
+        let mut document = service.get_asset_document(odd.id).unwrap().unwrap();
+        document.visual_embedding = Some(vec![1.0, 0.0, 0.0, 0.0]);
+        service.store_document(&document).await.unwrap();
+        
+        // Lose the in-memory vectors, as after a crash mid-update
+        service.vector_store.clear();
+        assert!(service.search_visual_similar(&[1.0, 0.0], 10, None).await.unwrap().is_empty());
+        
+        let stats = service.rebuild_vector_store().unwrap();
+        assert_eq!(stats, EmbeddingLoadStats {
+            visual_loaded: 2,
+            visual_skipped: 1,
+            text_loaded: 1,
+            text_skipped: 0,
+            unreadable_documents: 0,
+        });
+        let results = service.search_visual_similar(&[1.0, 0.0], 10, None).await.unwrap();
+        assert_eq!(results[0].document.asset_id, photo.id);
+        
+        // The text index was not touched
+        assert_eq!(service.search_text("scan", 10).await.unwrap().len(), 1);
+        
+        // Documents that fail to read are counted rather than silently dropped
+        service.doc_store.insert(Uuid::new_v4().as_bytes(), b"not a document".to_vec()).unwrap();
+        assert_eq!(service.rebuild_vector_store().unwrap().unreadable_documents, 1);
     }
     
     #[tokio::test]
-    async fn test_asset_indexing_and_search() {
+    async fn test_dimension_filters() {
         let temp_dir = TempDir::new().unwrap();
         let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
         
-        // Create test asset
-        let asset = create_test_asset("vacation_photo.jpg");
-        let asset_id = asset.id;
+        let image = |filename: &str, width: u32, height: u32| {
+            let mut asset = create_test_asset(filename);
+            asset.metadata.image = Some(schema::ImageMetadata {
+                width,
+                height,
+                bit_depth: 8,
+                color_space: "RGB".to_string(),
+                has_alpha: false,
+                layers: None,
+                frame_count: None,
+                is_animated: false,
+                keywords: Vec::new(),
+                page_count: None,
+                pages: Vec::new(),
+            });
+            asset
+        };
+        let banner = image("banner.jpg", 1920, 1080);
+        let poster = image("poster.jpg", 1080, 1920);
+        let avatar = image("avatar.jpg", 512, 512);
+        let mut memo = create_test_asset("banner memo.txt");
+        memo.asset_type = AssetType::Document;
+        for asset in [&banner, &poster, &avatar, &memo] {
+            service.index_asset(asset).await.unwrap();
+        }
         
-        // Index asset
-        service.index_asset(&asset).await.unwrap();
+        let ids = |results: Vec<SearchResult>| {
+            let mut ids: Vec<Uuid> = results.into_iter().map(|r| r.document.asset_id).collect();
+            ids.sort();
+            ids
+        };
+        let sorted = |mut wanted: Vec<Uuid>| {
+            wanted.sort();
+            wanted
+        };
+        let search = |filter: DimensionFilter| SearchQuery::default().with_dimensions(filter);
         
-        // Search for it
-        let results = service.search_text("vacation", 10).await.unwrap();
+        assert_eq!(ids(service.search(&search(DimensionFilter::exact(1920, 1080))).await.unwrap()), vec![banner.id]);
+        assert_eq!(ids(service.search(&search(DimensionFilter::oriented(Orientation::Portrait))).await.unwrap()), vec![poster.id]);
+        assert_eq!(ids(service.search(&search(DimensionFilter::oriented(Orientation::Square))).await.unwrap()), vec![avatar.id]);
+        assert_eq!(
+            ids(service.search(&search(DimensionFilter::default().with_min_size(1000, 1000))).await.unwrap()),
+            sorted(vec![banner.id, poster.id])
+        );
+        assert_eq!(
+            ids(service.search(&search(DimensionFilter::default().with_aspect_ratio(Some(0.9), Some(1.8)))).await.unwrap()),
+            sorted(vec![banner.id, avatar.id])
+        );
+        
+        // Composes with text and type filters; the memo has no dimensions
+        let query = SearchQuery::text_search("banner").with_dimensions(DimensionFilter::default().with_max_size(4000, 4000));
+        assert_eq!(ids(service.search(&query).await.unwrap()), vec![banner.id]);
+        let query = search(DimensionFilter::default().with_min_size(1, 1)).with_asset_type(AssetType::Document);
+        assert!(service.search(&query).await.unwrap().is_empty());
+        assert_eq!(service.search(&SearchQuery::text_search("banner")).await.unwrap().len(), 2);
+    }
+    
+    #[tokio::test]
+    async fn test_custom_metadata() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        let poster = create_test_asset("poster.jpg");
+        let logo = create_test_asset("logo.jpg");
+        service.index_asset(&poster).await.unwrap();
+        service.index_asset(&logo).await.unwrap();
+        
+        service.set_custom_metadata(poster.id, "client", "Acme").await.unwrap();
+        service.set_custom_metadata(logo.id, "client", "Globex").await.unwrap();
+        service.set_custom_metadata(logo.id, "license", "CC-BY").await.unwrap();
+        assert!(service.set_custom_metadata(logo.id, "bad key", "x").await.is_err());
+        
+        // Values are searchable text
+        let results = service.search_text("acme", 10).await.unwrap();
         assert_eq!(results.len(), 1);
-        assert_eq!(results[0].document.asset_id, asset_id);
+        assert_eq!(results[0].document.asset_id, poster.id);
         
-        // Remove asset
-        service.remove_asset(asset_id).await.unwrap();
+        // Field filters, alone or with text
+        let ids = |results: Vec<SearchResult>| results.into_iter().map(|r| r.document.asset_id).collect::<Vec<_>>();
+        assert_eq!(ids(service.search_text("custom:client=globex", 10).await.unwrap()), vec![logo.id]);
+        assert_eq!(ids(service.search_text("poster custom:client=acme", 10).await.unwrap()), vec![poster.id]);
+        assert!(service.search_text("poster custom:client=globex", 10).await.unwrap().is_empty());
+        assert_eq!(ids(service.search(&SearchQuery::default().with_custom("license", "cc-by")).await.unwrap()), vec![logo.id]);
+        assert_eq!(ids(service.search_text("custom:license", 10).await.unwrap()), vec![logo.id]);
         
-        // Should not find it anymore
-        let results = service.search_text("vacation", 10).await.unwrap();
-        assert_eq!(results.len(), 0);
+        // Edits persist and survive re-indexing
+        assert!(service.remove_custom_metadata(logo.id, "license").await.unwrap());
+        assert!(!service.remove_custom_metadata(logo.id, "license").await.unwrap());
+        service.index_asset(&poster).await.unwrap();
+        drop(service);
+        let service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        let details = service.get_asset_details(poster.id).unwrap();
+        assert_eq!(details.document.custom_metadata().get("client").map(String::as_str), Some("Acme"));
+        assert!(service.search_text("custom:license", 10).await.unwrap().is_empty());
+        assert_eq!(service.search_text("globex", 10).await.unwrap().len(), 1);
     }
     
     #[tokio::test]
-    async fn test_ai_results_update() {
+    async fn test_custom_metadata_reindex() {
         let temp_dir = TempDir::new().unwrap();
         let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
         
-        // Create and index asset
-        let asset = create_test_asset("test.jpg");
-        let asset_id = asset.id;
-        service.index_asset(&asset).await.unwrap();
+        let mut photo = create_test_asset("photo.jpg");
+        photo.metadata.custom.insert("camera".to_string(), "X100".to_string());
+        photo.metadata.custom.insert("lens".to_string(), "23mm".to_string());
+        service.index_asset(&photo).await.unwrap();
+        service.set_custom_metadata(photo.id, "lens", "35mm").await.unwrap();
         
-        // Update with AI results
-        service.update_with_ai_results(
-            asset_id,
-            Some(vec!["cat".to_string(), "cute".to_string()]),
-            Some("A cute cat sitting on a chair".to_string()),
-            None,
-            Some(vec![0.1, 0.2, 0.3, 0.4]),
-            None
-        ).await.unwrap();
+        // File values are re-read, so a key the file dropped goes away; user values stay on top
+        photo.metadata.custom.remove("camera");
+        service.index_asset(&photo).await.unwrap();
+        let document = service.get_asset_details(photo.id).unwrap().document;
+        assert!(!document.metadata.contains_key("camera"));
+        assert_eq!(document.custom_metadata().get("lens").map(String::as_str), Some("35mm"));
+        assert!(service.search_text("x100", 10).await.unwrap().is_empty());
         
-        // Search should now find it by AI tags
-        let results = service.search_text("cat", 10).await.unwrap();
-        assert_eq!(results.len(), 1);
-        assert_eq!(results[0].document.asset_id, asset_id);
+        // Removing the user value shows the file value again
+        assert!(service.remove_custom_metadata(photo.id, "lens").await.unwrap());
+        let document = service.get_asset_details(photo.id).unwrap().document;
+        assert_eq!(document.custom_metadata().get("lens").map(String::as_str), Some("23mm"));
+    }
+    
+    #[tokio::test]
+    async fn test_speaker_search() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
         
-        // Visual similarity search should work
-        let similar_results = service.search_visual_similar(&[0.1, 0.2, 0.3, 0.4], 5).await.unwrap();
-        assert_eq!(similar_results.len(), 1);
+        let interview = create_test_asset("interview.wav");
+        let lecture = create_test_asset("lecture.wav");
+        service.index_asset(&interview).await.unwrap();
+        service.index_asset(&lecture).await.unwrap();
+        
+        let segment = |start_ms: u64, text: &str, speaker: &str| TranscriptionSegment {
+            start_ms,
+            end_ms: start_ms + 1000,
+            text: text.to_string(),
+            speaker: Some(speaker.to_string()),
+        };
+        service.update_transcription_segments(interview.id, vec![
+            segment(0, "What about the budget?", "1"),
+            segment(1000, "We cut the travel costs", "2"),
+        ]).await.unwrap();
+        // No diarization: speaker filters never match
+        service.update_with_ai_results(lecture.id, None, None, Some("travel budget".to_string()), None, None).await.unwrap();
+        
+        let ids = |results: Vec<SearchResult>| results.into_iter().map(|r| r.document.asset_id).collect::<Vec<_>>();
+        assert_eq!(service.search_text("travel", 10).await.unwrap().len(), 2);
+        assert_eq!(ids(service.search_text("speaker:2 travel", 10).await.unwrap()), vec![interview.id]);
+        assert!(service.search_text("speaker:2 budget", 10).await.unwrap().is_empty());
+        assert_eq!(ids(service.search_text("SPEAKER:1 budget", 10).await.unwrap()), vec![interview.id]);
+        assert!(service.search_text("speaker:3 travel", 10).await.unwrap().is_empty());
+        
+        // Without text, the filter lists every asset the speaker appears in
+        assert_eq!(ids(service.search(&SearchQuery::default().with_speaker("2")).await.unwrap()), vec![interview.id]);
+        
+        let document = service.get_asset_document(interview.id).unwrap().unwrap();
+        assert_eq!(document.transcription.as_deref(), Some("What about the budget? We cut the travel costs"));
+        assert_eq!(document.processing_status.transcription, StepStatus::Done);
+    }
+    
+    #[tokio::test]
+    async fn test_query_cache() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        let config = IndexConfig { query_cache: true, query_cache_size: 8, ..IndexConfig::default() };
+        service.set_config(config).unwrap();
+        
+        let harbor = create_test_asset("harbor.jpg");
+        service.index_asset(&harbor).await.unwrap();
+        assert_eq!(service.search_text("harbor", 10).await.unwrap().len(), 1);
+        assert_eq!(service.query_cache().len(), 1);
+        
+        // Same query with other spacing is a cache hit
+        assert_eq!(service.search_text("  harbor ", 10).await.unwrap().len(), 1);
+        assert_eq!(service.query_cache().len(), 1);
+        
+        // Writes invalidate cached results
+        service.index_asset(&create_test_asset("harbor_night.jpg")).await.unwrap();
+        assert!(service.query_cache().is_empty());
+        assert_eq!(service.search_text("harbor", 10).await.unwrap().len(), 2);
+        
+        let query = SearchQuery::text_search("harbor");
+        assert_eq!(service.search(&query).await.unwrap().len(), 2);
+        service.remove_asset(harbor.id).await.unwrap();
+        assert_eq!(service.search(&query).await.unwrap().len(), 1);
+        assert_eq!(service.search_text("harbor", 10).await.unwrap().len(), 1);
+        
+        // Disabled by default
+        service.set_config(IndexConfig::default()).unwrap();
+        service.search_text("harbor", 10).await.unwrap();
+        assert!(service.query_cache().is_empty());
+        
+        let invalid = IndexConfig { query_cache: true, query_cache_size: 0, ..IndexConfig::default() };
+        assert!(invalid.validate().is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_redetect_unknowns() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        let mut heic = create_test_asset("beach.heic");
+        heic.asset_type = AssetType::Unknown;
+        let mut blob = create_test_asset("blob.bin");
+        blob.asset_type = AssetType::Unknown;
+        let mut raw = create_test_asset("frame.raf");
+        raw.format.supported = false;
+        service.index_assets(&[heic.clone(), blob.clone(), raw, create_test_asset("harbor.jpg")]).await.unwrap();
+        
+        let report = service.redetect_unknowns(|document| {
+            let heic = heic.clone();
+            async move {
+                // Only the HEIC file is recognized by the newer detector
+                let mut upgraded = heic;
+                upgraded.asset_type = AssetType::Image;
+                Ok((document.filename == "beach.heic").then_some(upgraded))
+            }
+        }).await.unwrap();
+        
+        assert_eq!(report.checked, 3);
+        assert_eq!(report.upgraded, vec![heic.id]);
+        assert!(report.failed.is_empty());
+        assert_eq!(service.get_asset_document(heic.id).unwrap().unwrap().asset_type, AssetType::Image);
+        assert_eq!(service.get_asset_document(blob.id).unwrap().unwrap().asset_type, AssetType::Unknown);
+        assert_eq!(service.get_stats().total_documents, 4);
+    }
+    
+    #[tokio::test]
+    async fn test_find_similar_combined() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        let embeddings = [
+            ("source.jpg", Some(vec![1.0, 0.0, 0.0]), Some(vec![0.0, 1.0, 0.0])),
+            ("twin.jpg", Some(vec![1.0, 0.1, 0.0]), Some(vec![0.0, 1.0, 0.1])),
+            ("look.jpg", Some(vec![1.0, 0.2, 0.0]), Some(vec![1.0, 0.0, 0.0])),
+            ("topic.jpg", Some(vec![0.0, 0.0, 1.0]), Some(vec![0.0, 1.0, 0.2])),
+            ("memo.jpg", None, Some(vec![0.0, 1.0, 0.0])),
+            ("bare.jpg", None, None),
+        ];
+        let mut ids = HashMap::new();
+        for (name, visual, text) in embeddings {
+            let asset = create_test_asset(name);
+            service.index_asset(&asset).await.unwrap();
+            service.update_with_ai_results(asset.id, None, None, None, visual, text).await.unwrap();
+            ids.insert(name, asset.id);
+        }
+        let names = |results: &[SearchResult]| -> Vec<String> {
+            results.iter().map(|result| result.document.filename.clone()).collect()
+        };
+        
+        // Matches on both kinds rank first and each asset appears once
+        let results = service.find_similar_combined(ids["source.jpg"], 10, None).await.unwrap();
+        assert_eq!(names(&results), vec!["twin.jpg", "look.jpg", "memo.jpg", "topic.jpg"]);
+        assert!(results[0].match_reason.contains("visual + text"));
+        
+        let text_heavy = SimilarityWeights { visual: 0.1, text: 0.9 };
+        let results = service.find_similar_combined_with_weights(ids["source.jpg"], &text_heavy, 10, None).await.unwrap();
+        assert_eq!(names(&results)[..2], ["twin.jpg", "memo.jpg"]);
+        
+        // Without a visual embedding the text ranking is used alone
+        let results = service.find_similar_combined(ids["memo.jpg"], 10, None).await.unwrap();
+        assert_eq!(names(&results), vec!["source.jpg", "twin.jpg", "topic.jpg"]);
+        assert!(results[0].score > 0.99);
+        
+        assert!(service.find_similar_combined(ids["bare.jpg"], 10, None).await.is_err());
+        let invalid = SimilarityWeights { visual: 0.0, text: 0.0 };
+        assert!(service.find_similar_combined_with_weights(ids["source.jpg"], &invalid, 10, None).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_search_grouped() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = IndexService::with_storage_dir(temp_dir.path()).unwrap();
+        
+        for (name, asset_type, tag) in [
+            ("harbor1.jpg", AssetType::Image, "harbor"),
+            ("harbor2.jpg", AssetType::Image, "harbor"),
+            ("harbor3.jpg", AssetType::Image, "harbor"),
+            ("tour.mp4", AssetType::Video, "harbor"),
+            ("notes.txt", AssetType::Document, "forest"),
+        ] {
+            let mut asset = create_test_asset(name);
+            asset.asset_type = asset_type;
+            asset.tags = vec![tag.to_string()];
+            service.index_asset(&asset).await.unwrap();
+        }
+        
+        let groups = service.search_grouped("harbor", 2).await.unwrap();
+        assert_eq!(groups.len(), 2);
+        let group = |asset_type: AssetType| groups.iter().find(|group| group.asset_type == asset_type).unwrap();
+        
+        // Groups are capped independently but count every match
+        let images = group(AssetType::Image);
+        assert_eq!((images.total, images.results.len()), (3, 2));
+        assert!(images.results.iter().all(|result| result.document.asset_type == AssetType::Image));
+        assert!(images.results[0].score >= images.results[1].score);
+        let videos = group(AssetType::Video);
+        assert_eq!((videos.total, videos.results.len()), (1, 1));
+        
+        assert!(service.search_grouped("submarine", 2).await.unwrap().is_empty());
+        
+        // An empty query lists every asset
+        let groups = service.search_grouped("", 2).await.unwrap();
+        let totals: HashMap<AssetType, (usize, usize)> = groups.iter()
+            .map(|group| (group.asset_type.clone(), (group.total, group.results.len())))
+            .collect();
+        assert_eq!(totals[&AssetType::Image], (3, 2));
+        assert_eq!(totals[&AssetType::Video], (1, 1));
+        assert_eq!(totals[&AssetType::Document], (1, 1));
+        
+        // With a text encoder, images that match by content join the text matches
+        let car = create_test_asset("IMG_0001.jpg");
+        service.index_asset(&car).await.unwrap();
+        service.update_with_ai_results(car.id, None, None, None, Some(vec![0.9, 0.3, 0.0]), None).await.unwrap();
+        assert!(service.search_grouped("car", 2).await.unwrap().is_empty());
+        service.set_cross_modal_encoder(Some(Arc::new(CarEncoder)));
+        let groups = service.search_grouped("car", 2).await.unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].total, 1);
+        assert_eq!(groups[0].results[0].document.asset_id, car.id);
     }
 }
@@ -0,0 +1,142 @@
+//! AI processing progress of the library
+//!
+//! Keeps running counts of each document's processing status so dashboard
+//! stats ("1,240 of 5,000 tagged") are available without scanning every
+//! document on each refresh.
+
+use crate::document::{AiKind, AssetDocument};
+use schema::{AssetType, ProcessingStatus, StepStatus};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Counts for one processing step, over the assets it applies to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepCounts {
+    /// Assets whose type this step applies to
+    pub applicable: usize,
+    pub in_progress: usize,
+    pub done: usize,
+    pub failed: usize,
+}
+
+impl StepCounts {
+    /// Applicable assets the step has not run on yet
+    pub fn not_started(&self) -> usize {
+        self.applicable - self.in_progress - self.done - self.failed
+    }
+
+    fn adjust(&mut self, status: &StepStatus, add: bool) {
+        let counters = [
+            Some(&mut self.applicable),
+            match status {
+                StepStatus::NotStarted => None,
+                StepStatus::InProgress => Some(&mut self.in_progress),
+                StepStatus::Done => Some(&mut self.done),
+                StepStatus::Failed { .. } => Some(&mut self.failed),
+            },
+        ];
+        for counter in counters.into_iter().flatten() {
+            if add {
+                *counter += 1;
+            } else {
+                *counter -= 1;
+            }
+        }
+    }
+}
+
+/// Library-wide AI processing progress
+///
+/// An asset is fully processed when every step that applies to its type is
+/// done, failed when any of them failed, and pending otherwise.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessingStats {
+    pub total_assets: usize,
+    pub fully_processed: usize,
+    pub pending: usize,
+    pub failed: usize,
+    pub tagging: StepCounts,
+    pub transcription: StepCounts,
+    pub embedding: StepCounts,
+}
+
+/// Processing status of every indexed document, with running totals
+#[derive(Debug, Default)]
+pub struct ProcessingIndex {
+    documents: HashMap<Uuid, (AssetType, ProcessingStatus)>,
+    stats: ProcessingStats,
+}
+
+impl ProcessingIndex {
+    /// Create an empty processing index
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or update a document
+    pub fn insert(&mut self, document: &AssetDocument) {
+        self.remove(&document.id);
+
+        let entry = (document.asset_type.clone(), document.processing_status.clone());
+        self.adjust(&entry.0, &entry.1, true);
+        self.documents.insert(document.id, entry);
+    }
+
+    /// Remove a document
+    pub fn remove(&mut self, doc_id: &Uuid) {
+        if let Some((asset_type, status)) = self.documents.remove(doc_id) {
+            self.adjust(&asset_type, &status, false);
+        }
+    }
+
+    /// Asset type of an indexed document
+    pub fn asset_type(&self, doc_id: &Uuid) -> Option<&AssetType> {
+        self.documents.get(doc_id).map(|(asset_type, _)| asset_type)
+    }
+
+    /// Current totals
+    pub fn stats(&self) -> ProcessingStats {
+        self.stats
+    }
+
+    /// Remove all documents
+    pub fn clear(&mut self) {
+        self.documents.clear();
+        self.stats = ProcessingStats::default();
+    }
+
+    fn adjust(&mut self, asset_type: &AssetType, status: &ProcessingStatus, add: bool) {
+        let steps = [
+            (&mut self.stats.tagging, &status.tagging, AiKind::Tags),
+            (&mut self.stats.transcription, &status.transcription, AiKind::Transcription),
+            (&mut self.stats.embedding, &status.embedding, AiKind::TextEmbedding),
+        ];
+
+        let mut all_done = true;
+        let mut any_failed = false;
+        for (counts, step, kind) in steps {
+            if kind.applies_to(asset_type) {
+                counts.adjust(step, add);
+                all_done &= *step == StepStatus::Done;
+                any_failed |= step.is_failed();
+            }
+        }
+
+        let stats = &mut self.stats;
+        let outcome = if any_failed {
+            &mut stats.failed
+        } else if all_done {
+            &mut stats.fully_processed
+        } else {
+            &mut stats.pending
+        };
+        for counter in [outcome, &mut stats.total_assets] {
+            if add {
+                *counter += 1;
+            } else {
+                *counter -= 1;
+            }
+        }
+    }
+}
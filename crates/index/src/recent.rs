@@ -0,0 +1,115 @@
+//! Recency ordering of indexed documents
+//!
+//! Keeps documents sorted by their timestamps so "recently added" and
+//! "recently modified" views can be paged without sorting the whole index.
+
+use crate::document::AssetDocument;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use uuid::Uuid;
+
+/// Timestamp used to order documents by recency
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RecencyField {
+    /// When the asset was first added to the index
+    Indexed,
+    /// File creation time
+    Created,
+    /// File modification time
+    Modified,
+}
+
+/// Timestamps of one document, as stored in the ordered sets
+#[derive(Debug, Clone, Copy)]
+struct Timestamps {
+    indexed: DateTime<Utc>,
+    created: DateTime<Utc>,
+    modified: DateTime<Utc>,
+}
+
+impl Timestamps {
+    fn of(document: &AssetDocument) -> Self {
+        Self {
+            indexed: document.indexed_at,
+            created: document.created_at,
+            modified: document.modified_at,
+        }
+    }
+}
+
+/// Document IDs ordered by each recency field
+#[derive(Debug, Default)]
+pub struct RecencyIndex {
+    indexed: BTreeSet<(DateTime<Utc>, Uuid)>,
+    created: BTreeSet<(DateTime<Utc>, Uuid)>,
+    modified: BTreeSet<(DateTime<Utc>, Uuid)>,
+    /// Current timestamps per document, for removal
+    documents: HashMap<Uuid, Timestamps>,
+}
+
+impl RecencyIndex {
+    /// Create an empty recency index
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Add or update a document
+    pub fn insert(&mut self, document: &AssetDocument) {
+        self.remove(&document.id);
+        
+        let timestamps = Timestamps::of(document);
+        self.indexed.insert((timestamps.indexed, document.id));
+        self.created.insert((timestamps.created, document.id));
+        self.modified.insert((timestamps.modified, document.id));
+        self.documents.insert(document.id, timestamps);
+    }
+    
+    /// Remove a document
+    pub fn remove(&mut self, doc_id: &Uuid) {
+        if let Some(timestamps) = self.documents.remove(doc_id) {
+            self.indexed.remove(&(timestamps.indexed, *doc_id));
+            self.created.remove(&(timestamps.created, *doc_id));
+            self.modified.remove(&(timestamps.modified, *doc_id));
+        }
+    }
+    
+    /// Most recent `indexed_at` across all documents
+    pub fn latest_indexed(&self) -> Option<DateTime<Utc>> {
+        self.indexed.last().map(|(indexed, _)| *indexed)
+    }
+    
+    /// Document IDs ordered newest first, skipping `offset` and taking `limit`
+    pub fn newest(&self, by: RecencyField, limit: usize, offset: usize) -> Vec<Uuid> {
+        let set = match by {
+            RecencyField::Indexed => &self.indexed,
+            RecencyField::Created => &self.created,
+            RecencyField::Modified => &self.modified,
+        };
+        
+        set.iter()
+            .rev()
+            .skip(offset)
+            .take(limit)
+            .map(|(_, doc_id)| *doc_id)
+            .collect()
+    }
+    
+    /// Number of tracked documents
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+    
+    /// Whether no documents are tracked
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+    
+    /// Remove all documents
+    pub fn clear(&mut self) {
+        self.indexed.clear();
+        self.created.clear();
+        self.modified.clear();
+        self.documents.clear();
+    }
+}
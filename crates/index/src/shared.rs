@@ -0,0 +1,123 @@
+//! Sharing one index between threads and tasks
+//!
+//! `IndexService` follows Rust's usual split: everything that only reads
+//! (searches, lookups, stats) takes `&self`, everything that changes the
+//! index takes `&mut self`. `SharedIndex` maps that split onto a
+//! reader-writer lock, so any number of searches run at the same time and
+//! only writes wait for exclusive access. Writers are queued fairly, so a
+//! steady stream of searches cannot starve an import.
+
+use crate::{IndexService, IndexStats, SearchGroup, SearchResult};
+use futures::stream::BoxStream;
+use schema::{Asset, DamResult, SearchQuery};
+use std::sync::Arc;
+use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Cloneable handle to an `IndexService` shared across tasks
+///
+/// Clones refer to the same index. For several calls that must see a
+/// consistent index, hold one guard from `read` or `write` across them.
+#[derive(Clone)]
+pub struct SharedIndex {
+    inner: Arc<RwLock<IndexService>>,
+}
+
+impl SharedIndex {
+    /// Share an index service
+    pub fn new(service: IndexService) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(service)),
+        }
+    }
+
+    /// Shared access for reads; runs alongside other readers
+    pub async fn read(&self) -> RwLockReadGuard<'_, IndexService> {
+        self.inner.read().await
+    }
+
+    /// Exclusive access for writes; waits for current readers to finish
+    pub async fn write(&self) -> RwLockWriteGuard<'_, IndexService> {
+        self.inner.write().await
+    }
+
+    /// Text search under a read lock
+    pub async fn search_text(&self, query: &str, max_results: usize) -> DamResult<Vec<SearchResult>> {
+        self.read().await.search_text(query, max_results).await
+    }
+
+    /// Streamed text search; the read lock is released once the stream is
+    /// returned, before its results are read
+    pub async fn search_text_stream(&self, query: &str, max_results: usize) -> DamResult<BoxStream<'static, SearchResult>> {
+        self.read().await.search_text_stream(query, max_results).await
+    }
+
+    /// Structured search under a read lock
+    pub async fn search(&self, query: &SearchQuery) -> DamResult<Vec<SearchResult>> {
+        self.read().await.search(query).await
+    }
+
+    /// Search grouped by asset type under a read lock
+    pub async fn search_grouped(&self, query: &str, per_group_limit: usize) -> DamResult<Vec<SearchGroup>> {
+        self.read().await.search_grouped(query, per_group_limit).await
+    }
+
+    /// Index statistics under a read lock
+    pub async fn get_stats(&self) -> IndexStats {
+        self.read().await.get_stats()
+    }
+
+    /// Add or update an asset under the write lock
+    pub async fn index_asset(&self, asset: &Asset) -> DamResult<()> {
+        self.write().await.index_asset(asset).await
+    }
+}
+
+impl From<IndexService> for SharedIndex {
+    fn from(service: IndexService) -> Self {
+        Self::new(service)
+    }
+}
+
+// Sharing across threads relies on the service being Send + Sync; keep it so
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<IndexService>();
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_reads() {
+        let temp_dir = TempDir::new().unwrap();
+        let index = SharedIndex::new(IndexService::with_storage_dir(temp_dir.path()).unwrap());
+
+        let mut asset = Asset::new("/photos/harbor.png".into(), schema::AssetType::Image);
+        asset.file_size = 1024;
+        index.index_asset(&asset).await.unwrap();
+
+        // Readers do not block each other: a held read guard still lets searches through
+        let guard = index.read().await;
+        let searches: Vec<_> = (0..8)
+            .map(|_| {
+                let index = index.clone();
+                tokio::spawn(async move { index.search_text("harbor", 10).await.unwrap().len() })
+            })
+            .collect();
+        for search in searches {
+            assert_eq!(search.await.unwrap(), 1);
+        }
+        drop(guard);
+
+        // A streamed search does not keep writers waiting
+        let stream = index.search_text_stream("harbor", 10).await.unwrap();
+        let mut other = Asset::new("/photos/quay.png".into(), schema::AssetType::Image);
+        other.file_size = 1024;
+        index.index_asset(&other).await.unwrap();
+        drop(stream);
+
+        assert_eq!(index.get_stats().await.total_documents, 2);
+    }
+}
@@ -0,0 +1,171 @@
+//! Highlighted snippet extraction
+//!
+//! Builds short previews around text matches, e.g.
+//! `"...a cute <b>cat</b> sitting on..."`, from the word positions
+//! recorded by the text index and the raw field text of the document.
+
+use crate::document::AssetDocument;
+use crate::text_search::FieldMatch;
+use std::collections::BTreeMap;
+
+/// Words of context kept on each side of a match
+pub const SNIPPET_CONTEXT_WORDS: usize = 4;
+
+/// Maximum length of a single snippet in characters (excluding markup)
+pub const MAX_SNIPPET_CHARS: usize = 160;
+
+/// Maximum number of snippets returned per document
+pub const MAX_SNIPPETS: usize = 3;
+
+/// Build highlighted snippets for the matches of one document
+///
+/// Matches in the same field whose context windows overlap are merged into
+/// a single snippet. Fields are visited in order of their best match score.
+pub fn extract_snippets(document: &AssetDocument, matches: &[FieldMatch]) -> Vec<String> {
+    // Group match positions by field, remembering the best score per field
+    let mut fields: BTreeMap<&str, (f32, Vec<usize>)> = BTreeMap::new();
+    for field_match in matches {
+        let entry = fields.entry(field_match.field_name.as_str()).or_insert((0.0, Vec::new()));
+        entry.0 = entry.0.max(field_match.score);
+        entry.1.push(field_match.position);
+    }
+    
+    let mut fields: Vec<_> = fields.into_iter().collect();
+    fields.sort_by(|a, b| b.1.0.partial_cmp(&a.1.0).unwrap_or(std::cmp::Ordering::Equal));
+    
+    let mut snippets = Vec::new();
+    for (field, (_, positions)) in fields {
+        if let Some(text) = document.field_text(field) {
+            snippets.extend(field_snippets(&text, &positions));
+        }
+        if snippets.len() >= MAX_SNIPPETS {
+            break;
+        }
+    }
+    
+    snippets.truncate(MAX_SNIPPETS);
+    snippets
+}
+
+/// Build snippets for word `positions` within `text`
+fn field_snippets(text: &str, positions: &[usize]) -> Vec<String> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let mut positions: Vec<usize> = positions.iter().copied().filter(|&p| p < words.len()).collect();
+    positions.sort_unstable();
+    positions.dedup();
+    
+    // Merge overlapping or adjacent context windows
+    let mut windows: Vec<(usize, usize)> = Vec::new();
+    for &position in &positions {
+        let start = position.saturating_sub(SNIPPET_CONTEXT_WORDS);
+        let end = (position + SNIPPET_CONTEXT_WORDS + 1).min(words.len());
+        match windows.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => windows.push((start, end)),
+        }
+    }
+    
+    windows.into_iter()
+        .map(|(start, end)| render_window(&words, start, end, &positions))
+        .collect()
+}
+
+/// Render one window, bolding matched words and capping its length
+fn render_window(words: &[&str], mut start: usize, mut end: usize, positions: &[usize]) -> String {
+    let is_match = |index: &usize| positions.binary_search(index).is_ok();
+    let first = (start..end).find(is_match).unwrap_or(start);
+    let last = (start..end).rev().find(is_match).unwrap_or(end - 1);
+    let span_length = |start: usize, end: usize| {
+        words[start..end].iter().map(|w| w.chars().count()).sum::<usize>() + (end - start).saturating_sub(1)
+    };
+    
+    // Trim context from the longer side until the window fits
+    while span_length(start, end) > MAX_SNIPPET_CHARS && (start < first || end - 1 > last) {
+        if first - start >= end - 1 - last {
+            start += 1;
+        } else {
+            end -= 1;
+        }
+    }
+    
+    let mut snippet = String::new();
+    let mut length = 0;
+    let mut truncated = false;
+    
+    if start > 0 {
+        snippet.push_str("...");
+    }
+    
+    for (index, word) in words.iter().enumerate().take(end).skip(start) {
+        // Matches alone may still exceed the cap; cut at a word boundary
+        let word_length = word.chars().count() + usize::from(length > 0);
+        if length > 0 && length + word_length > MAX_SNIPPET_CHARS {
+            truncated = true;
+            break;
+        }
+        
+        if length > 0 {
+            snippet.push(' ');
+        }
+        
+        let escaped = escape_html(word);
+        if is_match(&index) {
+            snippet.push_str("<b>");
+            snippet.push_str(&escaped);
+            snippet.push_str("</b>");
+        } else {
+            snippet.push_str(&escaped);
+        }
+        length += word_length;
+    }
+    
+    if truncated || end < words.len() {
+        snippet.push_str("...");
+    }
+    
+    snippet
+}
+
+/// Escape text so the snippet markup is the only HTML in the output
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_snippet_context_and_highlight() {
+        let text = "here we see a cute cat sitting on the warm windowsill today";
+        let snippets = field_snippets(text, &[5]);
+        assert_eq!(snippets, vec!["...we see a cute <b>cat</b> sitting on the warm...".to_string()]);
+    }
+    
+    #[test]
+    fn test_overlapping_windows_are_merged() {
+        let text = "one two three four five six seven eight nine ten eleven twelve thirteen fourteen fifteen sixteen seventeen eighteen nineteen twenty";
+        
+        // Close matches share a window
+        let snippets = field_snippets(text, &[2, 5]);
+        assert_eq!(snippets.len(), 1);
+        assert!(snippets[0].contains("<b>three</b>") && snippets[0].contains("<b>six</b>"));
+        
+        // Distant matches get separate windows
+        let snippets = field_snippets(text, &[0, 19]);
+        assert_eq!(snippets.len(), 2);
+        assert!(snippets[0].starts_with("<b>one</b>"));
+        assert!(snippets[1].ends_with("<b>twenty</b>"));
+    }
+    
+    #[test]
+    fn test_snippet_length_is_capped() {
+        let long_word = "x".repeat(100);
+        let text = format!("{} {} match {} {}", long_word, long_word, long_word, long_word);
+        let snippet = &field_snippets(&text, &[2])[0];
+        
+        assert!(snippet.contains("<b>match</b>"));
+        assert!(snippet.starts_with("...") && snippet.ends_with("..."));
+        assert!(snippet.len() <= MAX_SNIPPET_CHARS + "<b></b>......".len());
+    }
+}
@@ -4,10 +4,27 @@
 //! but functional text search using string matching and scoring.
 
 use crate::error::IndexError;
-use crate::document::{AssetDocument, IndexConfig};
+use crate::document::{AssetDocument, FieldWeights, IndexConfig, DEFAULT_FIELD_WEIGHTS};
+use crate::language::TextLanguage;
+use crate::top_k::TopK;
+use rust_stemmers::Stemmer;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Documents a query term and an AI tag must share before the tag is used
+/// to expand the query
+const MIN_EXPANSION_COOCCURRENCE: usize = 2;
+
+/// Shortest prefix a `term*` query expands, so `*` or `a*` cannot match
+/// most of the index
+const MIN_PREFIX_LENGTH: usize = 2;
+
+/// Most indexed terms a single `term*` query expands to
+const MAX_PREFIX_EXPANSIONS: usize = 64;
+
+/// Score multiplier for terms matched by prefix rather than exactly
+const PREFIX_MATCH_WEIGHT: f32 = 0.8;
 
 /// Text search result with scoring
 #[derive(Debug, Clone)]
@@ -29,32 +46,71 @@ pub struct FieldMatch {
 /// Simple inverted index for text search
 #[derive(Debug, Clone)]
 pub struct TextIndex {
-    /// Term to document mapping with positions
-    term_index: HashMap<String, HashMap<Uuid, Vec<TermOccurrence>>>,
+    /// Term to document mapping with positions, sorted for prefix scans
+    term_index: BTreeMap<String, HashMap<Uuid, Vec<TermOccurrence>>>,
     /// Document to terms mapping for updates
     document_terms: HashMap<Uuid, HashSet<String>>,
     /// Search configuration
     config: IndexConfig,
+    /// Language terms are stemmed in; the configured one, or the detected
+    /// one for `Auto`
+    language: TextLanguage,
 }
 
 /// Term occurrence in a document
+/// 
+/// `position` is the index of the whitespace-separated word in the raw
+/// field text, so snippets can be cut from `AssetDocument::field_text`.
+/// Field boosts are not stored here; they are looked up from
+/// `FieldWeights` at query time so weights can change without reindexing.
 #[derive(Debug, Clone)]
 pub struct TermOccurrence {
     pub field: String,
     pub position: usize,
-    pub score_boost: f32,
 }
 
 impl TextIndex {
     /// Create a new text index
     pub fn new(config: IndexConfig) -> Self {
         Self {
-            term_index: HashMap::new(),
+            term_index: BTreeMap::new(),
             document_terms: HashMap::new(),
+            language: resolved_language(config.language, TextLanguage::default()),
             config,
         }
     }
     
+    /// Replace the search configuration
+    /// 
+    /// A detected `Auto` language is kept; a different explicit language
+    /// only matches documents indexed after it was set.
+    pub fn set_config(&mut self, config: IndexConfig) {
+        self.language = resolved_language(config.language, self.language);
+        self.config = config;
+    }
+    
+    /// Language terms are currently stemmed in
+    pub fn language(&self) -> TextLanguage {
+        self.language
+    }
+    
+    /// Settle an `Auto` language on the dominant language of the documents
+    /// 
+    /// Only has an effect with `Auto` configured, and should run before the
+    /// documents are added so index and queries agree. English is used when
+    /// the text gives no clear answer.
+    pub fn detect_language(&mut self, documents: &[AssetDocument]) -> TextLanguage {
+        if self.config.language == TextLanguage::Auto {
+            let texts: Vec<String> = documents.iter()
+                .flat_map(|document| ["description", "ai_caption", "transcription", "extracted_text"]
+                    .into_iter()
+                    .filter_map(|field| document.field_text(field)))
+                .collect();
+            self.language = TextLanguage::detect(texts.iter().map(String::as_str)).unwrap_or_default();
+        }
+        self.language
+    }
+    
     /// Add or update a document in the index
     pub fn add_document(&mut self, document: &AssetDocument) -> Result<(), IndexError> {
         // Remove existing document if present
@@ -62,42 +118,13 @@ impl TextIndex {
         
         let mut doc_terms = HashSet::new();
         
-        // Index different fields with different boost scores
-        self.index_field(&document.id, "filename", &document.filename, 2.0, &mut doc_terms);
-        self.index_field(&document.id, "title", &document.title, 1.8, &mut doc_terms);
-        
-        // Index tags with high boost
-        let tags_text = document.tags.join(" ");
-        self.index_field(&document.id, "tags", &tags_text, 2.5, &mut doc_terms);
-        
-        // Index AI tags
-        let ai_tags_text = document.ai_tags.join(" ");
-        self.index_field(&document.id, "ai_tags", &ai_tags_text, 2.0, &mut doc_terms);
-        
-        // Index description if present
-        if let Some(ref desc) = document.description {
-            self.index_field(&document.id, "description", desc, 1.5, &mut doc_terms);
-        }
-        
-        // Index transcription if present
-        if let Some(ref transcript) = document.transcription {
-            self.index_field(&document.id, "transcription", transcript, 1.8, &mut doc_terms);
-        }
-        
-        // Index AI caption if present
-        if let Some(ref caption) = document.ai_caption {
-            self.index_field(&document.id, "ai_caption", caption, 1.6, &mut doc_terms);
-        }
-        
-        // Index extracted text if present
-        if let Some(ref text) = document.extracted_text {
-            self.index_field(&document.id, "extracted_text", text, 1.4, &mut doc_terms);
+        // Index each field separately; boosts are applied at query time
+        for (field, _) in DEFAULT_FIELD_WEIGHTS {
+            if let Some(text) = document.field_text(field) {
+                self.index_field(&document.id, field, &text, &mut doc_terms);
+            }
         }
         
-        // Index asset type
-        let asset_type_text = format!("{:?}", document.asset_type).to_lowercase();
-        self.index_field(&document.id, "asset_type", &asset_type_text, 1.2, &mut doc_terms);
-        
         // Store document terms for later removal
         self.document_terms.insert(document.id, doc_terms);
         
@@ -122,35 +149,77 @@ impl TextIndex {
     
     /// Search for documents matching the query
     pub fn search(&self, query: &str, max_results: usize) -> Result<Vec<TextMatch>, IndexError> {
+        self.search_with_weights(query, max_results, &self.config.field_weights)
+    }
+    
+    /// Search using explicit per-field weights instead of the configured ones
+    /// 
+    /// Fields weighted 0 are ignored entirely, so documents that only match
+    /// in those fields are not returned.
+    pub fn search_with_weights(&self, query: &str, max_results: usize, weights: &FieldWeights) -> Result<Vec<TextMatch>, IndexError> {
+        Ok(self.top_matches(query, max_results, weights)?.into_best_first().collect())
+    }
+    
+    /// The best `max_results` matches of a query, not yet sorted
+    /// 
+    /// Matches are ranked in a bounded heap and come out best first from
+    /// `TopK::into_best_first`, so a caller can start on the top ones
+    /// without ordering the rest.
+    pub fn top_matches(&self, query: &str, max_results: usize, weights: &FieldWeights) -> Result<TopK<TextMatch>, IndexError> {
+        let mut top = TopK::new(max_results);
         if query.len() < self.config.min_query_length {
-            return Ok(Vec::new());
+            return Ok(top);
         }
         
-        let terms = self.tokenize(query);
-        if terms.is_empty() {
-            return Ok(Vec::new());
+        // `term*` words match by prefix; everything else is tokenized as usual
+        let (prefixes, plain): (Vec<&str>, Vec<&str>) = query.split_whitespace()
+            .partition(|word| word.len() > 1 && word.ends_with('*'));
+        let terms = self.tokenize(&plain.join(" "));
+        
+        // Query terms count fully, related AI tags and prefix matches at a reduced weight
+        let mut weighted_terms: Vec<(String, f32)> = terms.iter().map(|term| (term.clone(), 1.0)).collect();
+        if self.config.query_expansion {
+            weighted_terms.extend(self.expand_terms(&terms));
+        }
+        for prefix in prefixes {
+            weighted_terms.extend(self.expand_prefix(prefix.trim_end_matches('*')));
+        }
+        if weighted_terms.is_empty() {
+            return Ok(top);
         }
         
         // Find documents containing any of the terms
         let mut doc_scores: HashMap<Uuid, f32> = HashMap::new();
         let mut doc_matches: HashMap<Uuid, Vec<FieldMatch>> = HashMap::new();
         
-        for term in &terms {
+        for (term, term_weight) in &weighted_terms {
             if let Some(doc_map) = self.term_index.get(term) {
+                let idf = self.inverse_document_frequency(doc_map.len()) * term_weight;
+                
                 for (doc_id, occurrences) in doc_map {
-                    let term_score = self.calculate_term_score(term, occurrences, doc_map.len());
+                    let weighted: Vec<(&TermOccurrence, f32)> = occurrences.iter()
+                        .map(|occurrence| (occurrence, weights.get(&occurrence.field)))
+                        .filter(|(_, weight)| *weight > 0.0)
+                        .collect();
+                    
+                    if weighted.is_empty() {
+                        continue;
+                    }
+                    
+                    // Sum of field weights equals term frequency times mean boost
+                    let term_score = idf * weighted.iter().map(|(_, weight)| weight).sum::<f32>();
                     
                     // Add to document score
                     *doc_scores.entry(*doc_id).or_insert(0.0) += term_score;
                     
                     // Create field matches
                     let matches = doc_matches.entry(*doc_id).or_insert_with(Vec::new);
-                    for occurrence in occurrences {
+                    for (occurrence, weight) in weighted {
                         matches.push(FieldMatch {
                             field_name: occurrence.field.clone(),
                             match_text: term.clone(),
                             position: occurrence.position,
-                            score: term_score * occurrence.score_boost,
+                            score: idf * weight,
                         });
                     }
                 }
@@ -159,26 +228,132 @@ impl TextIndex {
         
         // Handle phrase matching for multi-term queries
         if terms.len() > 1 {
-            self.boost_phrase_matches(query, &terms, &mut doc_scores);
+            self.boost_phrase_matches(query, &terms, &mut doc_scores, &doc_matches);
         }
         
-        // Convert to results and sort
-        let mut results: Vec<TextMatch> = doc_scores
-            .into_iter()
-            .map(|(doc_id, score)| TextMatch {
+        // Keep the best matches
+        for (doc_id, score) in doc_scores {
+            top.push(score, TextMatch {
                 document_id: doc_id,
                 score,
                 matches: doc_matches.remove(&doc_id).unwrap_or_default(),
-            })
-            .collect();
+            });
+        }
         
-        // Sort by score (descending)
-        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+        Ok(top)
+    }
+    
+    /// Indexed terms starting with a prefix, with their score multipliers
+    /// 
+    /// The prefix is lowercased and stemmed like indexed words, since a stem
+    /// may end in a letter its word does not ("happy" is indexed as
+    /// "happi"); terms starting with either the prefix or its stem match.
+    /// Prefixes shorter than `MIN_PREFIX_LENGTH` expand to nothing. At most
+    /// `MAX_PREFIX_EXPANSIONS` terms are returned: the term equal to the
+    /// prefix, which matches fully, then the terms found in the most
+    /// documents, which match at `PREFIX_MATCH_WEIGHT`.
+    pub fn expand_prefix(&self, prefix: &str) -> Vec<(String, f32)> {
+        let prefix = prefix.to_lowercase();
+        if prefix.chars().count() < MIN_PREFIX_LENGTH {
+            return Vec::new();
+        }
+        let stemmed = match self.language.stemmer() {
+            Some(stemmer) if prefix.chars().all(char::is_alphabetic) => stemmer.stem(&prefix).into_owned(),
+            _ => prefix.clone(),
+        };
+        
+        let mut candidates: HashMap<&str, usize> = HashMap::new();
+        for start in [&prefix, &stemmed] {
+            let matching = self.term_index.range(start.clone()..)
+                .take_while(|(term, _)| term.starts_with(start.as_str()));
+            for (term, doc_map) in matching {
+                candidates.insert(term, doc_map.len());
+            }
+        }
         
-        // Limit results
-        results.truncate(max_results);
+        let exact = |term: &str| term == prefix || term == stemmed;
+        let mut candidates: Vec<(&str, usize)> = candidates.into_iter().collect();
+        candidates.sort_by(|a, b| {
+            exact(b.0).cmp(&exact(a.0))
+                .then(b.1.cmp(&a.1))
+                .then(a.0.cmp(b.0))
+        });
+        candidates.into_iter()
+            .take(MAX_PREFIX_EXPANSIONS)
+            .map(|(term, _)| {
+                let weight = if exact(term) { 1.0 } else { PREFIX_MATCH_WEIGHT };
+                (term.to_string(), weight)
+            })
+            .collect()
+    }
+    
+    /// AI tags related to the query terms, with their score multipliers
+    /// 
+    /// A tag is related to a term when both occur in the same documents;
+    /// relatedness is the Jaccard index of the two document sets, and pairs
+    /// sharing fewer than `MIN_EXPANSION_COOCCURRENCE` documents are
+    /// ignored. Each term adds at most `expansion_terms` tags, weighted by
+    /// `expansion_weight` times their relatedness.
+    pub fn expand_terms(&self, terms: &[String]) -> Vec<(String, f32)> {
+        let mut expanded: HashMap<String, f32> = HashMap::new();
+        
+        for term in terms {
+            let Some(doc_map) = self.term_index.get(term) else {
+                continue;
+            };
+            
+            let mut cooccurrences: HashMap<&str, usize> = HashMap::new();
+            for doc_id in doc_map.keys() {
+                for related in self.document_terms.get(doc_id).into_iter().flatten() {
+                    if !terms.contains(related) && self.is_ai_tag_in(related, doc_id) {
+                        *cooccurrences.entry(related.as_str()).or_insert(0) += 1;
+                    }
+                }
+            }
+            
+            let mut related: Vec<(&str, f32)> = cooccurrences.into_iter()
+                .filter(|(_, shared)| *shared >= MIN_EXPANSION_COOCCURRENCE)
+                .map(|(related, shared)| {
+                    let union = doc_map.len() + self.ai_tag_document_count(related) - shared;
+                    (related, shared as f32 / union as f32)
+                })
+                .collect();
+            related.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal).then(a.0.cmp(b.0)));
+            related.truncate(self.config.expansion_terms);
+            
+            for (related, relatedness) in related {
+                let weight = self.config.expansion_weight * relatedness;
+                let entry = expanded.entry(related.to_string()).or_insert(0.0);
+                *entry = entry.max(weight);
+            }
+        }
         
-        Ok(results)
+        let mut expanded: Vec<(String, f32)> = expanded.into_iter().collect();
+        expanded.sort_by(|a, b| a.0.cmp(&b.0));
+        expanded
+    }
+    
+    /// Whether a term occurs in the AI tags of a document
+    fn is_ai_tag_in(&self, term: &str, doc_id: &Uuid) -> bool {
+        self.term_index.get(term)
+            .and_then(|doc_map| doc_map.get(doc_id))
+            .is_some_and(|occurrences| occurrences.iter().any(|o| o.field == "ai_tags"))
+    }
+    
+    /// Number of documents with a term in their AI tags
+    fn ai_tag_document_count(&self, term: &str) -> usize {
+        self.term_index.get(term)
+            .map(|doc_map| doc_map.values()
+                .filter(|occurrences| occurrences.iter().any(|o| o.field == "ai_tags"))
+                .count())
+            .unwrap_or(0)
+    }
+    
+    /// Inverse document frequency of a term, 0 if it is not indexed
+    pub fn term_idf(&self, term: &str) -> f32 {
+        self.term_index.get(term)
+            .map(|doc_map| self.inverse_document_frequency(doc_map.len()))
+            .unwrap_or(0.0)
     }
     
     /// Get statistics about the index
@@ -207,10 +382,8 @@ impl TextIndex {
     }
     
     /// Index a specific field of a document
-    fn index_field(&mut self, doc_id: &Uuid, field: &str, text: &str, boost: f32, doc_terms: &mut HashSet<String>) {
-        let terms = self.tokenize(text);
-        
-        for (position, term) in terms.iter().enumerate() {
+    fn index_field(&mut self, doc_id: &Uuid, field: &str, text: &str, doc_terms: &mut HashSet<String>) {
+        for (position, term) in self.tokenize_with_positions(text, true) {
             doc_terms.insert(term.clone());
             
             let doc_map = self.term_index.entry(term.clone()).or_insert_with(HashMap::new);
@@ -219,46 +392,102 @@ impl TextIndex {
             occurrences.push(TermOccurrence {
                 field: field.to_string(),
                 position,
-                score_boost: boost,
             });
         }
     }
     
-    /// Tokenize text into searchable terms
+    /// Tokenize a query into searchable terms
     fn tokenize(&self, text: &str) -> Vec<String> {
-        text.to_lowercase()
-            .split_whitespace()
-            .map(|word| {
-                // Remove punctuation and special characters
-                word.chars()
-                    .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
-                    .collect::<String>()
-            })
-            .filter(|term| term.len() >= 2) // Minimum term length
+        self.tokenize_with_positions(text, false)
+            .into_iter()
+            .map(|(_, term)| term)
             .collect()
     }
     
-    /// Calculate TF-IDF style score for a term
-    fn calculate_term_score(&self, term: &str, occurrences: &[TermOccurrence], doc_freq: usize) -> f32 {
-        let tf = occurrences.len() as f32; // Term frequency in document
-        let idf = ((self.document_terms.len() as f32) / (doc_freq as f32 + 1.0)).ln(); // Inverse document frequency
-        let boost = occurrences.iter().map(|o| o.score_boost).sum::<f32>() / occurrences.len() as f32;
+    /// Tokenize text, keeping the index of the source word for each term
+    /// 
+    /// Latin-style text is split on whitespace. Words containing scripts
+    /// written without spaces (CJK, Thai, ...) are additionally split into
+    /// character bigrams so substrings of a sentence can match. Indexed text
+    /// also gets unigrams so single-character queries find it; queries only
+    /// use unigrams for lone characters to avoid matching on every shared
+    /// character.
+    /// 
+    /// Other punctuation separates terms within a word, so `photo.jpg`
+    /// yields `photo` and `jpg`. Compound terms such as `img_2024` or
+    /// `4096x2160` are indexed whole and also by their parts, so numbers
+    /// (years, dimensions, serials) can be searched on their own.
+    fn tokenize_with_positions(&self, text: &str, unigrams: bool) -> Vec<(usize, String)> {
+        let min_length = self.config.min_term_length;
+        let stemmer = self.language.stemmer();
+        let mut terms = Vec::new();
+        
+        for (position, word) in text.to_lowercase().split_whitespace().enumerate() {
+            let segments = word.split(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'));
+            
+            for segment in segments {
+                let segment = segment.trim_matches(|c| c == '-' || c == '_');
+                if segment.is_empty() {
+                    continue;
+                }
+                
+                if self.config.cjk_bigrams && segment.chars().any(is_unsegmented_script) {
+                    terms.extend(segment_unspaced_word(segment, unigrams, min_length).into_iter().map(|term| (position, term)));
+                    continue;
+                }
+                
+                if is_valid_term(segment, min_length) {
+                    terms.extend(self.normalize_term(segment, stemmer.as_ref()).map(|term| (position, term)));
+                }
+                
+                let parts = compound_parts(segment);
+                if parts.len() > 1 {
+                    terms.extend(parts.into_iter()
+                        .filter(|part| is_valid_term(part, min_length))
+                        .filter_map(|part| self.normalize_term(part, stemmer.as_ref()))
+                        .map(|term| (position, term)));
+                }
+            }
+        }
         
-        tf * idf * boost
+        terms
+    }
+    
+    /// Drop stop words and stem plain words in the index language
+    /// 
+    /// Terms with digits or joiners (`img_2024`, `test-file`) are kept
+    /// as-is, since they are usually identifiers rather than words.
+    fn normalize_term(&self, term: &str, stemmer: Option<&Stemmer>) -> Option<String> {
+        if self.language.is_stop_word(term) {
+            return None;
+        }
+        match stemmer {
+            Some(stemmer) if term.chars().all(char::is_alphabetic) => Some(stemmer.stem(term).into_owned()),
+            _ => Some(term.to_string()),
+        }
+    }
+    
+    /// Inverse document frequency for a term found in `doc_freq` documents
+    fn inverse_document_frequency(&self, doc_freq: usize) -> f32 {
+        ((self.document_terms.len() as f32) / (doc_freq as f32 + 1.0)).ln()
     }
     
     /// Boost scores for phrase matches
-    fn boost_phrase_matches(&self, query: &str, terms: &[String], doc_scores: &mut HashMap<Uuid, f32>) {
+    fn boost_phrase_matches(
+        &self,
+        query: &str,
+        terms: &[String],
+        doc_scores: &mut HashMap<Uuid, f32>,
+        doc_matches: &HashMap<Uuid, Vec<FieldMatch>>,
+    ) {
         // Simple phrase matching - boost documents that contain terms in sequence
         let query_lower = query.to_lowercase();
         
         for (doc_id, score) in doc_scores.iter_mut() {
-            // Check if all terms appear in the same document
-            let has_all_terms = terms.iter().all(|term| {
-                self.term_index.get(term)
-                    .map(|doc_map| doc_map.contains_key(doc_id))
-                    .unwrap_or(false)
-            });
+            // Check if all terms appear in the same document (in weighted fields)
+            let has_all_terms = doc_matches.get(doc_id)
+                .map(|matches| terms.iter().all(|term| matches.iter().any(|m| &m.match_text == term)))
+                .unwrap_or(false);
             
             if has_all_terms {
                 // Boost for having all terms
@@ -273,6 +502,100 @@ impl TextIndex {
     }
 }
 
+/// The language to stem in: the configured one, or `current` for `Auto`
+fn resolved_language(configured: TextLanguage, current: TextLanguage) -> TextLanguage {
+    match configured {
+        TextLanguage::Auto => current,
+        language => language,
+    }
+}
+
+/// Whether a character belongs to a script that is written without spaces
+fn is_unsegmented_script(c: char) -> bool {
+    matches!(c as u32,
+        0x0E00..=0x0EFF     // Thai, Lao
+        | 0x1000..=0x109F   // Myanmar
+        | 0x1100..=0x11FF   // Hangul Jamo
+        | 0x1780..=0x17FF   // Khmer
+        | 0x3040..=0x30FF   // Hiragana, Katakana
+        | 0x3130..=0x318F   // Hangul Compatibility Jamo
+        | 0x31F0..=0x31FF   // Katakana Phonetic Extensions
+        | 0x3400..=0x4DBF   // CJK Extension A
+        | 0x4E00..=0x9FFF   // CJK Unified Ideographs
+        | 0xAC00..=0xD7AF   // Hangul Syllables
+        | 0xF900..=0xFAFF   // CJK Compatibility Ideographs
+        | 0x20000..=0x2FA1F // CJK Extensions B-F, Compatibility Supplement
+    )
+}
+
+/// Whether a term is long enough to index
+/// 
+/// Numbers are always kept so short values like `3` in "take 3" or `42`
+/// stay searchable regardless of the configured minimum length.
+fn is_valid_term(term: &str, min_length: usize) -> bool {
+    term.chars().all(|c| c.is_ascii_digit()) || term.chars().count() >= min_length
+}
+
+/// Split a compound term at `-`/`_` and at letter/digit boundaries
+/// 
+/// `img_2024` gives `img`, `2024`; `4096x2160` gives `4096`, `x`, `2160`.
+fn compound_parts(term: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    
+    for piece in term.split(['-', '_']).filter(|piece| !piece.is_empty()) {
+        let mut start = 0;
+        let mut previous_digit = None;
+        for (index, c) in piece.char_indices() {
+            let is_digit = c.is_ascii_digit();
+            if previous_digit.is_some_and(|previous| previous != is_digit) {
+                parts.push(&piece[start..index]);
+                start = index;
+            }
+            previous_digit = Some(is_digit);
+        }
+        parts.push(&piece[start..]);
+    }
+    
+    parts
+}
+
+/// Split a word mixing unspaced scripts and other text into terms
+/// 
+/// Runs of unspaced-script characters become overlapping bigrams, plus
+/// unigrams when `unigrams` is set (a lone character is always kept);
+/// other runs are kept whole, subject to the usual minimum length.
+fn segment_unspaced_word(word: &str, unigrams: bool, min_length: usize) -> Vec<String> {
+    let mut terms = Vec::new();
+    let chars: Vec<char> = word.chars().collect();
+    let mut start = 0;
+    
+    while start < chars.len() {
+        let unspaced = is_unsegmented_script(chars[start]);
+        let end = chars[start..].iter()
+            .position(|c| is_unsegmented_script(*c) != unspaced)
+            .map(|offset| start + offset)
+            .unwrap_or(chars.len());
+        let run = &chars[start..end];
+        
+        if unspaced {
+            if unigrams || run.len() == 1 {
+                terms.extend(run.iter().map(|c| c.to_string()));
+            }
+            terms.extend(run.windows(2).map(|pair| pair.iter().collect::<String>()));
+        } else {
+            let term: String = run.iter().collect();
+            let term = term.trim_matches(|c| c == '-' || c == '_');
+            if !term.is_empty() && is_valid_term(term, min_length) {
+                terms.push(term.to_string());
+            }
+        }
+        
+        start = end;
+    }
+    
+    terms
+}
+
 /// Statistics about the text index
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TextIndexStats {
@@ -352,6 +675,96 @@ mod tests {
         assert_eq!(results.len(), 0);
     }
     
+    #[test]
+    fn test_query_time_field_weights() {
+        let mut index = TextIndex::new(IndexConfig::default());
+        
+        let tagged = create_test_document("first.jpg", vec!["ocean".to_string()]);
+        let mut ai_tagged = create_test_document("second.jpg", vec![]);
+        ai_tagged.ai_tags = vec!["ocean".to_string()];
+        
+        index.add_document(&tagged).unwrap();
+        index.add_document(&ai_tagged).unwrap();
+        for filler in ["third.jpg", "fourth.jpg"] {
+            index.add_document(&create_test_document(filler, vec!["forest".to_string()])).unwrap();
+        }
+        
+        // Default weights favour user tags over AI tags
+        let results = index.search("ocean", 10).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].document_id, tagged.id);
+        
+        // Re-weighting flips the order without reindexing
+        let weights = FieldWeights::default().with("ai_tags", 5.0);
+        let results = index.search_with_weights("ocean", 10, &weights).unwrap();
+        assert_eq!(results[0].document_id, ai_tagged.id);
+        
+        // Zero-weighted fields are excluded from matching
+        let results = index.search_with_weights("ocean", 10, &FieldWeights::only(&["tags"])).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document_id, tagged.id);
+    }
+    
+    #[test]
+    fn test_cjk_tokenization() {
+        let mut index = TextIndex::new(IndexConfig::default());
+        
+        let tokens = index.tokenize("東京タワー photo");
+        assert_eq!(tokens, vec!["東京", "京タ", "タワ", "ワー", "photo"]);
+        assert_eq!(index.tokenize("猫_2024"), vec!["猫", "2024"]);
+        
+        // Substrings of an unspaced sentence are searchable
+        let mut doc = create_test_document("travel.jpg", vec![]);
+        doc.ai_caption = Some("夕暮れの東京タワーと猫".to_string());
+        index.add_document(&doc).unwrap();
+        
+        assert_eq!(index.search("東京タワー", 10).unwrap().len(), 1);
+        assert_eq!(index.search("猫", 10).unwrap().len(), 1);
+        assert!(index.search("大阪", 10).unwrap().is_empty());
+        
+        // Disabling bigrams keeps the whole run as one term
+        let config = IndexConfig { cjk_bigrams: false, ..IndexConfig::default() };
+        let plain = TextIndex::new(config);
+        assert_eq!(plain.tokenize("東京タワー"), vec!["東京タワー"]);
+    }
+    
+    #[test]
+    fn test_numeric_tokens() {
+        let mut index = TextIndex::new(IndexConfig::default());
+        
+        assert_eq!(index.tokenize("IMG_2024.jpg"), vec!["img_2024", "img", "2024", "jpg"]);
+        assert_eq!(index.tokenize("4096x2160"), vec!["4096x2160", "4096", "2160"]);
+        assert_eq!(index.tokenize("take 3"), vec!["take", "3"]);
+        
+        let mut year = create_test_document("IMG_2024.jpg", vec![]);
+        year.description = Some("Summer holiday, 2024".to_string());
+        let mut texture = create_test_document("brick_4096x4096.png", vec![]);
+        texture.description = Some("Tileable wall texture".to_string());
+        index.add_document(&year).unwrap();
+        index.add_document(&texture).unwrap();
+        for filler in ["a.jpg", "b.jpg", "c.jpg"] {
+            index.add_document(&create_test_document(filler, vec!["forest".to_string()])).unwrap();
+        }
+        
+        let results = index.search("2024", 10).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document_id, year.id);
+        
+        let results = index.search("4096", 10).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document_id, texture.id);
+    }
+    
+    #[test]
+    fn test_min_term_length() {
+        let config = IndexConfig { min_term_length: 4, ..IndexConfig::default() };
+        let index = TextIndex::new(config);
+        assert_eq!(index.tokenize("a red car parked 12"), vec!["park", "12"]);
+        
+        let invalid = IndexConfig { min_term_length: 0, ..IndexConfig::default() };
+        assert!(invalid.validate().is_err());
+    }
+    
     #[test]
     fn test_tokenization() {
         let config = IndexConfig::default();
@@ -0,0 +1,164 @@
+//! Chronological grouping of indexed documents
+//!
+//! Buckets documents by their best known date for timeline browsing. The
+//! capture date (EXIF `DateTimeOriginal`) is preferred; documents without
+//! one fall back to `created_at`. This is read-only grouping, files are not
+//! moved.
+
+use crate::document::AssetDocument;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Custom metadata keys that carry the capture date, as written by
+/// extractors and sidecars
+pub const CAPTURE_DATE_KEYS: &[&str] = &[
+    "DateTimeOriginal",
+    "exif.DateTimeOriginal",
+    "exif:DateTimeOriginal",
+    "date_taken",
+];
+
+/// Size of timeline buckets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DateGranularity {
+    Day,
+    Month,
+    Year,
+}
+
+/// One bucket of a timeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DateBucket {
+    Day(NaiveDate),
+    Month { year: i32, month: u32 },
+    Year(i32),
+    /// Documents without a plausible date
+    Undated,
+}
+
+impl DateBucket {
+    /// Bucket of a date at the given granularity
+    pub fn of(date: DateTime<Utc>, granularity: DateGranularity) -> Self {
+        match granularity {
+            DateGranularity::Day => Self::Day(date.date_naive()),
+            DateGranularity::Month => Self::Month { year: date.year(), month: date.month() },
+            DateGranularity::Year => Self::Year(date.year()),
+        }
+    }
+    
+    /// First day of the bucket, `None` for `Undated`
+    pub fn start(&self) -> Option<NaiveDate> {
+        match *self {
+            Self::Day(date) => Some(date),
+            Self::Month { year, month } => NaiveDate::from_ymd_opt(year, month, 1),
+            Self::Year(year) => NaiveDate::from_ymd_opt(year, 1, 1),
+            Self::Undated => None,
+        }
+    }
+    
+    /// Display label such as "2024", "2024-03" or "2024-03-05"
+    pub fn label(&self) -> String {
+        match *self {
+            Self::Day(date) => date.format("%Y-%m-%d").to_string(),
+            Self::Month { year, month } => format!("{:04}-{:02}", year, month),
+            Self::Year(year) => format!("{:04}", year),
+            Self::Undated => "Undated".to_string(),
+        }
+    }
+}
+
+/// Parse a capture date as written by EXIF (`2024:03:05 14:30:00`) or as
+/// RFC 3339
+///
+/// EXIF dates carry no time zone and are taken as UTC.
+pub fn parse_capture_date(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date.with_timezone(&Utc));
+    }
+    
+    ["%Y:%m:%d %H:%M:%S", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .map(|date| date.and_utc())
+}
+
+/// Capture date from an asset's custom metadata
+pub fn capture_date(custom: &HashMap<String, String>) -> Option<DateTime<Utc>> {
+    CAPTURE_DATE_KEYS
+        .iter()
+        .filter_map(|key| custom.get(*key))
+        .find_map(|value| parse_capture_date(value))
+}
+
+/// Best available date of a document, `None` if it has no plausible one
+///
+/// Dates at or before the Unix epoch or more than a day in the future come
+/// from unset or wrong clocks and are not trusted.
+pub fn best_date(document: &AssetDocument) -> Option<DateTime<Utc>> {
+    let latest = Utc::now() + Duration::days(1);
+    let plausible = |date: &DateTime<Utc>| date.timestamp() > 0 && *date <= latest;
+    
+    document.captured_at
+        .filter(plausible)
+        .or_else(|| Some(document.created_at).filter(plausible))
+}
+
+/// Group documents into buckets, newest bucket first and `Undated` last
+///
+/// Documents within a bucket are ordered newest first.
+pub fn group_documents<'a, I>(documents: I, granularity: DateGranularity) -> Vec<(DateBucket, Vec<&'a AssetDocument>)>
+where
+    I: IntoIterator<Item = &'a AssetDocument>,
+{
+    let mut dated: HashMap<DateBucket, Vec<(DateTime<Utc>, &AssetDocument)>> = HashMap::new();
+    let mut undated = Vec::new();
+    
+    for document in documents {
+        match best_date(document) {
+            Some(date) => dated.entry(DateBucket::of(date, granularity)).or_default().push((date, document)),
+            None => undated.push(document),
+        }
+    }
+    
+    let mut buckets: Vec<(DateBucket, Vec<&AssetDocument>)> = dated
+        .into_iter()
+        .map(|(bucket, mut documents)| {
+            documents.sort_by_key(|(date, _)| std::cmp::Reverse(*date));
+            (bucket, documents.into_iter().map(|(_, document)| document).collect())
+        })
+        .collect();
+    buckets.sort_by_key(|(bucket, _)| std::cmp::Reverse(bucket.start()));
+    
+    if !undated.is_empty() {
+        buckets.push((DateBucket::Undated, undated));
+    }
+    buckets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_parse_capture_date() {
+        let expected = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap().and_hms_opt(14, 30, 0).unwrap().and_utc();
+        assert_eq!(parse_capture_date("2024:03:05 14:30:00"), Some(expected));
+        assert_eq!(parse_capture_date("2024-03-05T15:30:00+01:00"), Some(expected));
+        assert_eq!(parse_capture_date("0000:00:00 00:00:00"), None);
+        assert_eq!(parse_capture_date("yesterday"), None);
+        
+        let custom = HashMap::from([("exif.DateTimeOriginal".to_string(), "2024:03:05 14:30:00".to_string())]);
+        assert_eq!(capture_date(&custom), Some(expected));
+    }
+    
+    #[test]
+    fn test_bucket_labels() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap().and_hms_opt(0, 0, 0).unwrap().and_utc();
+        assert_eq!(DateBucket::of(date, DateGranularity::Day).label(), "2024-03-05");
+        assert_eq!(DateBucket::of(date, DateGranularity::Month).label(), "2024-03");
+        assert_eq!(DateBucket::of(date, DateGranularity::Year).label(), "2024");
+        assert_eq!(DateBucket::Undated.start(), None);
+    }
+}
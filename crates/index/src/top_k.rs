@@ -0,0 +1,143 @@
+//! Bounded selection of the best scored items
+//!
+//! A broad query can match most of a library, but only the first results
+//! are shown right away. `TopK` keeps the best `k` items seen so far in a
+//! min-heap, evicting the weakest as better ones arrive, so ranking costs
+//! `O(n log k)` instead of sorting every match. The kept items are handed
+//! out best first, one at a time, so a caller can start on the top result
+//! before the rest is ordered.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+/// An item ordered by score, then by arrival (earlier is better)
+#[derive(Debug)]
+struct Scored<T> {
+    score: f32,
+    seq: usize,
+    item: T,
+}
+
+impl<T> PartialEq for Scored<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for Scored<T> {}
+
+impl<T> PartialOrd for Scored<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Scored<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score.total_cmp(&other.score).then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+/// The `k` highest scoring items pushed so far
+#[derive(Debug)]
+pub struct TopK<T> {
+    k: usize,
+    seen: usize,
+    /// Weakest kept item on top
+    heap: BinaryHeap<Reverse<Scored<T>>>,
+}
+
+impl<T> TopK<T> {
+    /// Keep at most `k` items
+    pub fn new(k: usize) -> Self {
+        Self {
+            k,
+            seen: 0,
+            heap: BinaryHeap::with_capacity(k.min(1024)),
+        }
+    }
+
+    /// Offer an item; it is kept if it beats the weakest kept one
+    ///
+    /// Of items with equal scores, the ones pushed first are kept.
+    pub fn push(&mut self, score: f32, item: T) {
+        let scored = Scored { score, seq: self.seen, item };
+        self.seen += 1;
+
+        if self.heap.len() < self.k {
+            self.heap.push(Reverse(scored));
+        } else if let Some(mut weakest) = self.heap.peek_mut() {
+            if scored > weakest.0 {
+                *weakest = Reverse(scored);
+            }
+        }
+    }
+
+    /// Items kept
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Items pushed, kept or not
+    pub fn seen(&self) -> usize {
+        self.seen
+    }
+
+    /// Kept items, best first, each ordered only when it is taken
+    pub fn into_best_first(self) -> BestFirst<T> {
+        let items: Vec<Scored<T>> = self.heap.into_iter().map(|Reverse(scored)| scored).collect();
+        BestFirst { heap: BinaryHeap::from(items) }
+    }
+}
+
+/// Iterator over the items of a `TopK`, best first
+#[derive(Debug)]
+pub struct BestFirst<T> {
+    heap: BinaryHeap<Scored<T>>,
+}
+
+impl<T> Iterator for BestFirst<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.heap.pop().map(|scored| scored.item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.heap.len(), Some(self.heap.len()))
+    }
+}
+
+impl<T> ExactSizeIterator for BestFirst<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keeps_best_k() {
+        let mut top = TopK::new(3);
+        for (score, item) in [(0.5, "c"), (0.9, "a"), (0.1, "e"), (0.7, "b"), (0.3, "d")] {
+            top.push(score, item);
+        }
+        assert_eq!((top.len(), top.seen()), (3, 5));
+        assert_eq!(top.into_best_first().collect::<Vec<_>>(), vec!["a", "b", "c"]);
+
+        let mut none = TopK::new(0);
+        none.push(1.0, "a");
+        assert!(none.is_empty());
+    }
+
+    #[test]
+    fn test_ties_keep_arrival_order() {
+        let mut top = TopK::new(2);
+        for item in ["first", "second", "third"] {
+            top.push(1.0, item);
+        }
+        assert_eq!(top.into_best_first().collect::<Vec<_>>(), vec!["first", "second"]);
+    }
+}
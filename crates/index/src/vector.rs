@@ -1,10 +1,22 @@
 //! Vector similarity search for embeddings
 
-use crate::error::IndexError;
+use crate::error::VectorError;
 use crate::document::AssetDocument;
+use schema::DamResult;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::{Arc, Mutex, PoisonError};
+use tracing::warn;
+
+/// Embeddings with a smaller norm than this are treated as all-zero
+/// 
+/// A failed inference typically yields an all-zero vector; storing it
+/// would make it equally (dis)similar to everything.
+pub const MIN_EMBEDDING_MAGNITUDE: f32 = 1e-6;
+
+/// Candidates considered per result when similarity results are diversified
+pub const DIVERSITY_CANDIDATE_FACTOR: usize = 4;
 
 /// Vector similarity search result
 #[derive(Debug, Clone)]
@@ -15,73 +27,286 @@ pub struct VectorMatch {
 }
 
 /// Type of embedding used for search
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum EmbeddingType {
     Visual,
     Text,
 }
 
+/// How embeddings are compared
+/// 
+/// Use the metric the embedding model was trained with:
+/// - `Cosine` for CLIP and sentence-transformer style models, which are
+///   trained on normalized vectors (the default)
+/// - `Euclidean` for models trained with an L2 or triplet loss, such as
+///   FaceNet-style face and many image retrieval embeddings
+/// - `Manhattan` for sparse or histogram-like features, where L1 is more
+///   robust to a few large differences
+/// 
+/// Scores are always "higher is more similar": cosine similarity in
+/// [-1, 1], or the negated distance for the other metrics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DistanceMetric {
+    #[default]
+    Cosine,
+    Euclidean,
+    Manhattan,
+}
+
+impl DistanceMetric {
+    /// Whether scores are distances (negated) rather than similarities
+    pub fn is_distance(&self) -> bool {
+        !matches!(self, DistanceMetric::Cosine)
+    }
+    
+    /// Score two prepared vectors, higher meaning more similar
+    pub fn score(&self, a: &[f32], b: &[f32]) -> f32 {
+        match self {
+            DistanceMetric::Cosine => cosine_similarity(a, b),
+            DistanceMetric::Euclidean => -euclidean_distance(a, b),
+            DistanceMetric::Manhattan => -manhattan_distance(a, b),
+        }
+    }
+    
+    /// Bring a vector into the form it is stored and compared in
+    /// 
+    /// Cosine compares unit vectors; distance metrics keep the magnitude
+    /// the model produced.
+    fn prepare(&self, vector: &[f32]) -> Vec<f32> {
+        match self {
+            DistanceMetric::Cosine => normalize_vector(vector),
+            DistanceMetric::Euclidean | DistanceMetric::Manhattan => vector.to_vec(),
+        }
+    }
+}
+
+/// Which embeddings leave memory once the resident limit is reached
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvictionPolicy {
+    /// Spill the documents added, searched or looked up longest ago
+    #[default]
+    LeastRecentlyUsed,
+    /// Spill the documents added longest ago, regardless of searches
+    KeepNewest,
+}
+
+/// Storage that spilled embeddings are read back from
+pub trait EmbeddingSource: std::fmt::Debug + Send + Sync {
+    /// Embeddings of a document as the model produced them, or as they
+    /// were handed to `spill`; text embeddings may have several chunks
+    fn load(&self, doc_id: &Uuid, embedding_type: &EmbeddingType) -> Option<Vec<Vec<f32>>>;
+    
+    /// Keep a copy of embeddings leaving memory, for `load` to read back
+    /// cheaply; sources that already hold them need not do anything
+    fn spill(&self, _doc_id: &Uuid, _embedding_type: &EmbeddingType, _chunks: &[Vec<f32>]) {}
+    
+    /// Drop the copies `spill` kept of a document
+    fn forget(&self, _doc_id: &Uuid) {}
+}
+
+/// Model that embeds text queries into the visual embedding space
+/// 
+/// CLIP-style models train an image and a text tower into one space, so
+/// a written description compares directly with stored visual embeddings.
+/// Encoding runs a forward pass and may block.
+pub trait CrossModalEncoder: Send + Sync {
+    /// Embedding of `text` with the dimension of the visual embeddings
+    fn encode_text(&self, text: &str) -> DamResult<Vec<f32>>;
+}
+
+/// Cap on the documents whose embeddings stay in memory
+#[derive(Debug)]
+struct Residency {
+    max_resident: usize,
+    policy: EvictionPolicy,
+    source: Arc<dyn EmbeddingSource>,
+}
+
+/// Resident documents ordered by last use
+#[derive(Debug, Default)]
+struct Usage {
+    clock: u64,
+    ticks: HashMap<Uuid, u64>,
+    order: BTreeSet<(u64, Uuid)>,
+}
+
+impl Usage {
+    fn touch(&mut self, doc_id: Uuid) {
+        self.clock += 1;
+        if let Some(previous) = self.ticks.insert(doc_id, self.clock) {
+            self.order.remove(&(previous, doc_id));
+        }
+        self.order.insert((self.clock, doc_id));
+    }
+    
+    fn remove(&mut self, doc_id: &Uuid) {
+        if let Some(tick) = self.ticks.remove(doc_id) {
+            self.order.remove(&(tick, *doc_id));
+        }
+    }
+    
+    fn pop_oldest(&mut self) -> Option<Uuid> {
+        let (_, doc_id) = self.order.pop_first()?;
+        self.ticks.remove(&doc_id);
+        Some(doc_id)
+    }
+    
+    fn clear(&mut self) {
+        self.ticks.clear();
+        self.order.clear();
+    }
+}
+
 /// In-memory vector store for similarity search
-#[derive(Debug, Clone)]
+/// 
+/// With a resident limit set, embeddings beyond it are spilled: dropped
+/// from memory and read back from their `EmbeddingSource` whenever a
+/// search needs them. Spilled documents are still found, just more slowly,
+/// and stay spilled until memory frees up.
+#[derive(Debug)]
 pub struct VectorStore {
     /// Visual embeddings indexed by document ID
     visual_embeddings: HashMap<Uuid, Vec<f32>>,
-    /// Text embeddings indexed by document ID
-    text_embeddings: HashMap<Uuid, Vec<f32>>,
+    /// Text embeddings indexed by document ID, one per chunk of long text
+    text_embeddings: HashMap<Uuid, Vec<Vec<f32>>>,
     /// Dimension of visual embeddings
     visual_dim: Option<usize>,
     /// Dimension of text embeddings
     text_dim: Option<usize>,
+    /// Metric used to compare embeddings
+    metric: DistanceMetric,
+    /// Limit on resident documents; unlimited when unset
+    residency: Option<Residency>,
+    /// Documents whose visual embedding was spilled
+    spilled_visual: HashSet<Uuid>,
+    /// Documents whose text embeddings were spilled
+    spilled_text: HashSet<Uuid>,
+    /// Use order of resident documents, tracked only with a limit
+    usage: Mutex<Usage>,
 }
 
 impl VectorStore {
     /// Create a new vector store
     pub fn new() -> Self {
+        Self::with_metric(DistanceMetric::default())
+    }
+    
+    /// Create a vector store comparing embeddings with the given metric
+    /// 
+    /// Scores passed as `min_similarity` to the search methods are on the
+    /// metric's scale, i.e. `-max_distance` for distance metrics.
+    pub fn with_metric(metric: DistanceMetric) -> Self {
         Self {
             visual_embeddings: HashMap::new(),
             text_embeddings: HashMap::new(),
             visual_dim: None,
             text_dim: None,
+            metric,
+            residency: None,
+            spilled_visual: HashSet::new(),
+            spilled_text: HashSet::new(),
+            usage: Mutex::new(Usage::default()),
         }
     }
     
-    /// Add or update visual embedding for a document
-    pub fn add_visual_embedding(&mut self, doc_id: Uuid, embedding: Vec<f32>) -> Result<(), IndexError> {
-        // Validate dimension consistency
-        if let Some(expected_dim) = self.visual_dim {
-            if embedding.len() != expected_dim {
-                return Err(IndexError::VectorError(format!(
-                    "Visual embedding dimension mismatch: expected {}, got {}",
-                    expected_dim, embedding.len()
-                )));
+    /// Keep at most `max_resident` documents' embeddings in memory
+    pub fn with_residency_limit(mut self, max_resident: usize, policy: EvictionPolicy, source: Arc<dyn EmbeddingSource>) -> Self {
+        self.set_residency_limit(Some(max_resident), policy, source);
+        self
+    }
+    
+    /// Change the resident limit, or remove it with `None`
+    /// 
+    /// Spilled embeddings are read back from `source` while there is room
+    /// under the new limit; resident ones beyond it are spilled right away.
+    /// A limit of zero is treated as one.
+    pub fn set_residency_limit(&mut self, max_resident: Option<usize>, policy: EvictionPolicy, source: Arc<dyn EmbeddingSource>) {
+        // Seed the use order in arbitrary order if tracking starts now
+        if self.residency.is_none() && max_resident.is_some() {
+            let usage = self.usage.get_mut().unwrap_or_else(PoisonError::into_inner);
+            for doc_id in self.visual_embeddings.keys().chain(self.text_embeddings.keys()) {
+                usage.touch(*doc_id);
             }
+        }
+        
+        self.residency = Some(Residency {
+            max_resident: max_resident.unwrap_or(usize::MAX).max(1),
+            policy,
+            source,
+        });
+        self.restore_spilled();
+        
+        if max_resident.is_none() {
+            self.residency = None;
+            self.usage.get_mut().unwrap_or_else(PoisonError::into_inner).clear();
         } else {
-            self.visual_dim = Some(embedding.len());
+            self.evict();
         }
+    }
+    
+    /// Most documents kept in memory, if limited
+    pub fn max_resident(&self) -> Option<usize> {
+        self.residency.as_ref().map(|residency| residency.max_resident)
+    }
+    
+    /// Metric used to compare embeddings
+    pub fn metric(&self) -> DistanceMetric {
+        self.metric
+    }
+    
+    /// Add or update visual embedding for a document
+    pub fn add_visual_embedding(&mut self, doc_id: Uuid, embedding: Vec<f32>) -> Result<(), VectorError> {
+        self.check_visual_embedding(&embedding)?;
+        self.visual_dim = Some(embedding.len());
         
         // Normalize the embedding
-        let normalized = normalize_vector(&embedding);
+        let normalized = self.metric.prepare(&embedding);
         self.visual_embeddings.insert(doc_id, normalized);
+        self.spilled_visual.remove(&doc_id);
+        self.admit(doc_id);
         Ok(())
     }
     
     /// Add or update text embedding for a document
-    pub fn add_text_embedding(&mut self, doc_id: Uuid, embedding: Vec<f32>) -> Result<(), IndexError> {
-        // Validate dimension consistency
-        if let Some(expected_dim) = self.text_dim {
-            if embedding.len() != expected_dim {
-                return Err(IndexError::VectorError(format!(
-                    "Text embedding dimension mismatch: expected {}, got {}",
-                    expected_dim, embedding.len()
-                )));
-            }
-        } else {
-            self.text_dim = Some(embedding.len());
-        }
+    pub fn add_text_embedding(&mut self, doc_id: Uuid, embedding: Vec<f32>) -> Result<(), VectorError> {
+        self.add_text_embeddings(doc_id, vec![embedding])
+    }
+    
+    /// Add or update the chunk embeddings of a long text
+    /// 
+    /// A document matches a query as well as its best matching chunk.
+    pub fn add_text_embeddings(&mut self, doc_id: Uuid, embeddings: Vec<Vec<f32>>) -> Result<(), VectorError> {
+        self.check_text_embeddings(&embeddings)?;
+        self.text_dim = Some(embeddings[0].len());
         
-        // Normalize the embedding
-        let normalized = normalize_vector(&embedding);
+        // Normalize the embeddings
+        let normalized = embeddings.iter().map(|embedding| self.metric.prepare(embedding)).collect();
         self.text_embeddings.insert(doc_id, normalized);
+        self.spilled_text.remove(&doc_id);
+        self.admit(doc_id);
+        Ok(())
+    }
+    
+    /// Check a visual embedding as `add_visual_embedding` does, without adding it
+    pub fn check_visual_embedding(&self, embedding: &[f32]) -> Result<(), VectorError> {
+        check_dimension(self.visual_dim, embedding)?;
+        check_magnitude(embedding)
+    }
+    
+    /// Check chunk embeddings as `add_text_embeddings` does, without adding them
+    pub fn check_text_embeddings(&self, embeddings: &[Vec<f32>]) -> Result<(), VectorError> {
+        let Some(first) = embeddings.first() else {
+            return Err(VectorError::Other("No text embeddings given".to_string()));
+        };
+        
+        // Validate dimension consistency
+        let expected_dim = self.text_dim.unwrap_or(first.len());
+        for embedding in embeddings {
+            check_dimension(Some(expected_dim), embedding)?;
+            check_magnitude(embedding)?;
+        }
         Ok(())
     }
     
@@ -89,27 +314,164 @@ impl VectorStore {
     pub fn remove_document(&mut self, doc_id: &Uuid) {
         self.visual_embeddings.remove(doc_id);
         self.text_embeddings.remove(doc_id);
+        let visual = self.spilled_visual.remove(doc_id);
+        let text = self.spilled_text.remove(doc_id);
+        if let Some(residency) = self.residency.as_ref().filter(|_| visual || text) {
+            residency.source.forget(doc_id);
+        }
+        self.usage.get_mut().unwrap_or_else(PoisonError::into_inner).remove(doc_id);
+    }
+    
+    /// Record a newly stored embedding and spill others if over the limit
+    fn admit(&mut self, doc_id: Uuid) {
+        if self.residency.is_some() {
+            self.usage.get_mut().unwrap_or_else(PoisonError::into_inner).touch(doc_id);
+            self.evict();
+        }
+    }
+    
+    /// Record a search or lookup hit, which protects resident documents
+    /// from eviction under `LeastRecentlyUsed`
+    fn record_use(&self, doc_ids: impl IntoIterator<Item = Uuid>) {
+        let Some(residency) = &self.residency else {
+            return;
+        };
+        if residency.policy != EvictionPolicy::LeastRecentlyUsed {
+            return;
+        }
+        let mut usage = self.usage.lock().unwrap_or_else(PoisonError::into_inner);
+        for doc_id in doc_ids {
+            if usage.ticks.contains_key(&doc_id) {
+                usage.touch(doc_id);
+            }
+        }
+    }
+    
+    /// Documents with embeddings in memory
+    fn resident_count(&self) -> usize {
+        let text_only = self.text_embeddings.keys()
+            .filter(|doc_id| !self.visual_embeddings.contains_key(doc_id))
+            .count();
+        self.visual_embeddings.len() + text_only
+    }
+    
+    /// Spill the least valuable documents until the limit holds
+    /// 
+    /// Spilled embeddings are handed to the source already prepared.
+    fn evict(&mut self) {
+        let Some(residency) = &self.residency else {
+            return;
+        };
+        let max_resident = residency.max_resident;
+        let source = residency.source.clone();
+        let mut resident = self.resident_count();
+        let usage = self.usage.get_mut().unwrap_or_else(PoisonError::into_inner);
+        while resident > max_resident {
+            let Some(doc_id) = usage.pop_oldest() else {
+                break;
+            };
+            let visual = self.visual_embeddings.remove(&doc_id);
+            let text = self.text_embeddings.remove(&doc_id);
+            let spilled = visual.is_some() || text.is_some();
+            if let Some(embedding) = visual {
+                source.spill(&doc_id, &EmbeddingType::Visual, &[embedding]);
+                self.spilled_visual.insert(doc_id);
+            }
+            if let Some(chunks) = text {
+                source.spill(&doc_id, &EmbeddingType::Text, &chunks);
+                self.spilled_text.insert(doc_id);
+            }
+            if spilled {
+                resident -= 1;
+            }
+        }
+    }
+    
+    /// Read spilled documents back into memory while there is room
+    fn restore_spilled(&mut self) {
+        let Some(residency) = &self.residency else {
+            return;
+        };
+        let source = residency.source.clone();
+        let max_resident = residency.max_resident;
+        
+        let mut spilled: Vec<Uuid> = self.spilled_visual.union(&self.spilled_text).copied().collect();
+        spilled.sort();
+        for doc_id in spilled {
+            if self.resident_count() >= max_resident {
+                break;
+            }
+            if self.spilled_visual.remove(&doc_id) {
+                match source.load(&doc_id, &EmbeddingType::Visual).and_then(|mut v| v.pop()) {
+                    Some(embedding) => {
+                        self.visual_embeddings.insert(doc_id, self.metric.prepare(&embedding));
+                    }
+                    None => warn!("Spilled visual embedding of {} is no longer stored", doc_id),
+                }
+            }
+            if self.spilled_text.remove(&doc_id) {
+                match source.load(&doc_id, &EmbeddingType::Text) {
+                    Some(chunks) if !chunks.is_empty() => {
+                        let prepared = chunks.iter().map(|chunk| self.metric.prepare(chunk)).collect();
+                        self.text_embeddings.insert(doc_id, prepared);
+                    }
+                    _ => warn!("Spilled text embeddings of {} are no longer stored", doc_id),
+                }
+            }
+            self.usage.get_mut().unwrap_or_else(PoisonError::into_inner).touch(doc_id);
+        }
+    }
+    
+    /// Prepared embeddings of a spilled document, read from the source
+    fn load_spilled(&self, doc_id: &Uuid, embedding_type: &EmbeddingType) -> Option<Vec<Vec<f32>>> {
+        let residency = self.residency.as_ref()?;
+        match residency.source.load(doc_id, embedding_type) {
+            Some(chunks) if !chunks.is_empty() => {
+                Some(chunks.iter().map(|chunk| self.metric.prepare(chunk)).collect())
+            }
+            _ => {
+                warn!("Spilled embedding of document {} could not be read back", doc_id);
+                None
+            }
+        }
+    }
+    
+    /// Visual embeddings stored, resident or spilled
+    fn visual_count(&self) -> usize {
+        self.visual_embeddings.len() + self.spilled_visual.len()
+    }
+    
+    /// Documents with text embeddings stored, resident or spilled
+    fn text_count(&self) -> usize {
+        self.text_embeddings.len() + self.spilled_text.len()
     }
     
     /// Find similar documents using visual embedding
-    pub fn find_visual_similar(&self, query_embedding: &[f32], top_k: usize, min_similarity: f32) -> Result<Vec<VectorMatch>, IndexError> {
-        if self.visual_embeddings.is_empty() {
+    pub fn find_visual_similar(&self, query_embedding: &[f32], top_k: usize, min_similarity: f32) -> Result<Vec<VectorMatch>, VectorError> {
+        if self.visual_count() == 0 {
             return Ok(Vec::new());
         }
+        check_dimension(self.visual_dim, query_embedding)?;
         
         // Normalize query embedding
-        let normalized_query = normalize_vector(query_embedding);
+        let normalized_query = self.metric.prepare(query_embedding);
         
-        // Calculate similarities
-        let mut similarities: Vec<VectorMatch> = self.visual_embeddings
+        // Calculate similarities, reading spilled embeddings back as needed
+        let resident = self.visual_embeddings
             .iter()
-            .map(|(doc_id, embedding)| {
-                let similarity = cosine_similarity(&normalized_query, embedding);
-                VectorMatch {
-                    document_id: *doc_id,
-                    similarity,
-                    embedding_type: EmbeddingType::Visual,
-                }
+            .map(|(doc_id, embedding)| (*doc_id, self.metric.score(&normalized_query, embedding)));
+        let spilled = self.spilled_visual
+            .iter()
+            .filter_map(|doc_id| {
+                let chunks = self.load_spilled(doc_id, &EmbeddingType::Visual)?;
+                Some((*doc_id, best_similarity(self.metric, &normalized_query, &chunks)))
+            });
+        let mut similarities: Vec<VectorMatch> = resident
+            .chain(spilled)
+            .map(|(document_id, similarity)| VectorMatch {
+                document_id,
+                similarity,
+                embedding_type: EmbeddingType::Visual,
             })
             .filter(|m| m.similarity >= min_similarity)
             .collect();
@@ -119,29 +481,37 @@ impl VectorStore {
         
         // Take top k
         similarities.truncate(top_k);
+        self.record_use(similarities.iter().map(|m| m.document_id));
         
         Ok(similarities)
     }
     
     /// Find similar documents using text embedding
-    pub fn find_text_similar(&self, query_embedding: &[f32], top_k: usize, min_similarity: f32) -> Result<Vec<VectorMatch>, IndexError> {
-        if self.text_embeddings.is_empty() {
+    pub fn find_text_similar(&self, query_embedding: &[f32], top_k: usize, min_similarity: f32) -> Result<Vec<VectorMatch>, VectorError> {
+        if self.text_count() == 0 {
             return Ok(Vec::new());
         }
+        check_dimension(self.text_dim, query_embedding)?;
         
         // Normalize query embedding
-        let normalized_query = normalize_vector(query_embedding);
+        let normalized_query = self.metric.prepare(query_embedding);
         
-        // Calculate similarities
-        let mut similarities: Vec<VectorMatch> = self.text_embeddings
+        // Calculate similarities against each document's best chunk
+        let resident = self.text_embeddings
             .iter()
-            .map(|(doc_id, embedding)| {
-                let similarity = cosine_similarity(&normalized_query, embedding);
-                VectorMatch {
-                    document_id: *doc_id,
-                    similarity,
-                    embedding_type: EmbeddingType::Text,
-                }
+            .map(|(doc_id, chunks)| (*doc_id, best_similarity(self.metric, &normalized_query, chunks)));
+        let spilled = self.spilled_text
+            .iter()
+            .filter_map(|doc_id| {
+                let chunks = self.load_spilled(doc_id, &EmbeddingType::Text)?;
+                Some((*doc_id, best_similarity(self.metric, &normalized_query, &chunks)))
+            });
+        let mut similarities: Vec<VectorMatch> = resident
+            .chain(spilled)
+            .map(|(document_id, similarity)| VectorMatch {
+                document_id,
+                similarity,
+                embedding_type: EmbeddingType::Text,
             })
             .filter(|m| m.similarity >= min_similarity)
             .collect();
@@ -151,33 +521,142 @@ impl VectorStore {
         
         // Take top k
         similarities.truncate(top_k);
+        self.record_use(similarities.iter().map(|m| m.document_id));
         
         Ok(similarities)
     }
     
     /// Find similar documents to a given document
-    pub fn find_similar_to_document(&self, doc_id: &Uuid, embedding_type: EmbeddingType, top_k: usize, min_similarity: f32) -> Result<Vec<VectorMatch>, IndexError> {
+    pub fn find_similar_to_document(&self, doc_id: &Uuid, embedding_type: EmbeddingType, top_k: usize, min_similarity: f32) -> Result<Vec<VectorMatch>, VectorError> {
         match embedding_type {
             EmbeddingType::Visual => {
-                if let Some(query_embedding) = self.visual_embeddings.get(doc_id) {
-                    let mut results = self.find_visual_similar(query_embedding, top_k + 1, min_similarity)?;
+                if self.visual_count() == 0 {
+                    return Err(VectorError::EmptyStore);
+                }
+                if let Some(query_embedding) = self.embedding(doc_id, EmbeddingType::Visual) {
+                    let mut results = self.find_visual_similar(&query_embedding, top_k + 1, min_similarity)?;
                     // Remove the query document itself
                     results.retain(|m| m.document_id != *doc_id);
                     results.truncate(top_k);
                     Ok(results)
                 } else {
-                    Err(IndexError::DocumentNotFound(format!("No visual embedding found for document: {}", doc_id)))
+                    Err(VectorError::DocumentMissing(*doc_id))
                 }
             }
             EmbeddingType::Text => {
-                if let Some(query_embedding) = self.text_embeddings.get(doc_id) {
-                    let mut results = self.find_text_similar(query_embedding, top_k + 1, min_similarity)?;
+                if self.text_count() == 0 {
+                    return Err(VectorError::EmptyStore);
+                }
+                let query_chunks = match self.text_embeddings.get(doc_id) {
+                    Some(chunks) => Some(chunks.clone()),
+                    None if self.spilled_text.contains(doc_id) => self.load_spilled(doc_id, &EmbeddingType::Text),
+                    None => None,
+                };
+                if let Some(query_chunks) = query_chunks {
+                    // Best match over all pairs of chunks
+                    let mut best: HashMap<Uuid, VectorMatch> = HashMap::new();
+                    for query_embedding in &query_chunks {
+                        for result in self.find_text_similar(query_embedding, self.text_count(), min_similarity)? {
+                            match best.get(&result.document_id) {
+                                Some(existing) if existing.similarity >= result.similarity => {}
+                                _ => {
+                                    best.insert(result.document_id, result);
+                                }
+                            }
+                        }
+                    }
+                    
                     // Remove the query document itself
-                    results.retain(|m| m.document_id != *doc_id);
+                    best.remove(doc_id);
+                    let mut results: Vec<VectorMatch> = best.into_values().collect();
+                    results.sort_by(|a, b| b.similarity.partial_cmp(&a.similarity).unwrap());
                     results.truncate(top_k);
                     Ok(results)
                 } else {
-                    Err(IndexError::DocumentNotFound(format!("No text embedding found for document: {}", doc_id)))
+                    Err(VectorError::DocumentMissing(*doc_id))
+                }
+            }
+        }
+    }
+    
+    /// Re-rank matches by Maximal Marginal Relevance and keep `top_k`
+    ///
+    /// Matches are picked one at a time, each maximizing
+    /// `(1 - diversity) * similarity - diversity * redundancy`, where
+    /// redundancy is its highest score against a match already picked,
+    /// compared by their stored embeddings. A `diversity` of 0 keeps the
+    /// relevance order; the first pick is always the most relevant match.
+    /// `similarity` stays the relevance to the query.
+    pub fn diversify(&self, mut matches: Vec<VectorMatch>, top_k: usize, diversity: f32) -> Vec<VectorMatch> {
+        matches.sort_by(|a, b| b.similarity.partial_cmp(&a.similarity).unwrap_or(std::cmp::Ordering::Equal));
+        if diversity <= 0.0 || matches.len() <= 1 || top_k <= 1 {
+            matches.truncate(top_k);
+            return matches;
+        }
+
+        let embeddings: Vec<Option<Vec<f32>>> = matches.iter()
+            .map(|m| self.embedding(&m.document_id, m.embedding_type))
+            .collect();
+        // Highest score of each candidate against the picked matches; a
+        // candidate without an embedding is never penalized
+        let mut redundancy = vec![f32::NEG_INFINITY; matches.len()];
+        let mut remaining: Vec<usize> = (0..matches.len()).collect();
+        let mut picked = Vec::with_capacity(top_k.min(matches.len()));
+
+        while picked.len() < top_k && !remaining.is_empty() {
+            let marginal = |i: usize| {
+                let penalty = if redundancy[i].is_finite() { redundancy[i] } else { 0.0 };
+                (1.0 - diversity) * matches[i].similarity - diversity * penalty
+            };
+            // Ties go to the more relevant candidate
+            let mut best = 0;
+            for position in 1..remaining.len() {
+                if marginal(remaining[position]) > marginal(remaining[best]) {
+                    best = position;
+                }
+            }
+            let chosen = remaining.remove(best);
+
+            if let Some(chosen_embedding) = &embeddings[chosen] {
+                for &i in &remaining {
+                    if let Some(embedding) = &embeddings[i] {
+                        redundancy[i] = redundancy[i].max(self.metric.score(chosen_embedding, embedding));
+                    }
+                }
+            }
+            picked.push(chosen);
+        }
+
+        let mut slots: Vec<Option<VectorMatch>> = matches.into_iter().map(Some).collect();
+        picked.into_iter().filter_map(|i| slots[i].take()).collect()
+    }
+
+    /// Stored embedding of a document, as search compares it
+    /// 
+    /// Under the cosine metric vectors are stored unit-length; distance
+    /// metrics keep the model's magnitude. A chunked text embedding is
+    /// returned as the mean of its chunks, prepared the same way.
+    pub fn embedding(&self, doc_id: &Uuid, embedding_type: EmbeddingType) -> Option<Vec<f32>> {
+        self.record_use([*doc_id]);
+        match embedding_type {
+            EmbeddingType::Visual => match self.visual_embeddings.get(doc_id) {
+                Some(embedding) => Some(embedding.clone()),
+                None if self.spilled_visual.contains(doc_id) => self.load_spilled(doc_id, &embedding_type)?.pop(),
+                None => None,
+            },
+            EmbeddingType::Text => {
+                let loaded;
+                let chunks = match self.text_embeddings.get(doc_id) {
+                    Some(chunks) => chunks,
+                    None if self.spilled_text.contains(doc_id) => {
+                        loaded = self.load_spilled(doc_id, &embedding_type)?;
+                        &loaded
+                    }
+                    None => return None,
+                };
+                match chunks.as_slice() {
+                    [single] => Some(single.clone()),
+                    chunks => mean_pool(chunks).map(|mean| self.metric.prepare(&mean)),
                 }
             }
         }
@@ -186,10 +665,76 @@ impl VectorStore {
     /// Get statistics about the vector store
     pub fn get_stats(&self) -> VectorStoreStats {
         VectorStoreStats {
-            visual_embeddings_count: self.visual_embeddings.len(),
-            text_embeddings_count: self.text_embeddings.len(),
+            visual_embeddings_count: self.visual_count(),
+            text_embeddings_count: self.text_count(),
             visual_dimension: self.visual_dim,
             text_dimension: self.text_dim,
+            resident_documents: self.resident_count(),
+            spilled_documents: self.spilled_visual.union(&self.spilled_text).count(),
+            max_resident_documents: self.max_resident(),
+        }
+    }
+    
+    /// Estimated heap memory held by the stored embeddings in bytes
+    /// 
+    /// Counts vector data plus per-entry key and allocation overhead;
+    /// unused capacity is ignored.
+    pub fn memory_bytes(&self) -> u64 {
+        const ENTRY: usize = std::mem::size_of::<Uuid>() + std::mem::size_of::<Vec<f32>>();
+        let floats = |vector: &Vec<f32>| vector.len() * std::mem::size_of::<f32>();
+        
+        let visual: usize = self.visual_embeddings.values().map(|v| ENTRY + floats(v)).sum();
+        let text: usize = self.text_embeddings.values()
+            .map(|chunks| ENTRY + chunks.iter().map(|v| std::mem::size_of::<Vec<f32>>() + floats(v)).sum::<usize>())
+            .sum();
+        (visual + text) as u64
+    }
+    
+    /// Dimension of the stored embeddings of a type, if any are stored
+    pub fn dimension(&self, embedding_type: EmbeddingType) -> Option<usize> {
+        match embedding_type {
+            EmbeddingType::Visual => self.visual_dim,
+            EmbeddingType::Text => self.text_dim,
+        }
+    }
+    
+    /// Fix the dimension of a type before any embedding of it is stored
+    /// 
+    /// Lets the store reject vectors from the wrong embedder right away
+    /// instead of adopting the first dimension it sees. Has no effect once
+    /// embeddings of the type are stored.
+    pub fn expect_dimension(&mut self, embedding_type: EmbeddingType, dimension: usize) {
+        match embedding_type {
+            EmbeddingType::Visual if self.visual_count() == 0 => self.visual_dim = Some(dimension),
+            EmbeddingType::Text if self.text_count() == 0 => self.text_dim = Some(dimension),
+            _ => {}
+        }
+    }
+    
+    /// Clear all embeddings of one type, so the next one may have any dimension
+    pub fn clear_type(&mut self, embedding_type: EmbeddingType) {
+        match embedding_type {
+            EmbeddingType::Visual => {
+                self.visual_embeddings.clear();
+                self.spilled_visual.clear();
+                self.visual_dim = None;
+            }
+            EmbeddingType::Text => {
+                self.text_embeddings.clear();
+                self.spilled_text.clear();
+                self.text_dim = None;
+            }
+        }
+        
+        // Forget documents with nothing left in memory
+        let (visual, text) = (&self.visual_embeddings, &self.text_embeddings);
+        let usage = self.usage.get_mut().unwrap_or_else(PoisonError::into_inner);
+        let unused: Vec<Uuid> = usage.ticks.keys()
+            .filter(|doc_id| !visual.contains_key(doc_id) && !text.contains_key(doc_id))
+            .copied()
+            .collect();
+        for doc_id in unused {
+            usage.remove(&doc_id);
         }
     }
     
@@ -197,21 +742,34 @@ impl VectorStore {
     pub fn clear(&mut self) {
         self.visual_embeddings.clear();
         self.text_embeddings.clear();
+        self.spilled_visual.clear();
+        self.spilled_text.clear();
+        self.usage.get_mut().unwrap_or_else(PoisonError::into_inner).clear();
         self.visual_dim = None;
         self.text_dim = None;
     }
     
     /// Load embeddings from documents
-    pub fn load_from_documents(&mut self, documents: &[AssetDocument]) -> Result<(), IndexError> {
+    /// 
+    /// Degenerate embeddings stored before they were rejected, and ones of
+    /// another dimension than the expected or first loaded one, are skipped
+    /// with a warning instead of failing the whole load.
+    pub fn load_from_documents(&mut self, documents: &[AssetDocument]) -> Result<EmbeddingLoadStats, VectorError> {
+        let mut stats = EmbeddingLoadStats::default();
         for doc in documents {
             if let Some(ref visual_emb) = doc.visual_embedding {
-                self.add_visual_embedding(doc.id, visual_emb.clone())?;
+                let loaded = skip_unloadable(doc.id, self.add_visual_embedding(doc.id, visual_emb.clone()))?;
+                stats.count_visual(loaded);
             }
-            if let Some(ref text_emb) = doc.text_embedding {
-                self.add_text_embedding(doc.id, text_emb.clone())?;
+            if !doc.text_embedding_chunks.is_empty() {
+                let loaded = skip_unloadable(doc.id, self.add_text_embeddings(doc.id, doc.text_embedding_chunks.clone()))?;
+                stats.count_text(loaded);
+            } else if let Some(ref text_emb) = doc.text_embedding {
+                let loaded = skip_unloadable(doc.id, self.add_text_embedding(doc.id, text_emb.clone()))?;
+                stats.count_text(loaded);
             }
         }
-        Ok(())
+        Ok(stats)
     }
 }
 
@@ -228,6 +786,95 @@ pub struct VectorStoreStats {
     pub text_embeddings_count: usize,
     pub visual_dimension: Option<usize>,
     pub text_dimension: Option<usize>,
+    /// Documents whose embeddings are held in memory
+    pub resident_documents: usize,
+    /// Documents whose embeddings were spilled and are read back on demand
+    pub spilled_documents: usize,
+    /// Resident limit, if one is set
+    pub max_resident_documents: Option<usize>,
+}
+
+/// Embeddings loaded by `VectorStore::load_from_documents`, per document
+/// 
+/// Skipped embeddings were degenerate or of another dimension; they stay
+/// in their documents for `IndexService::repair_embeddings` to clear.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmbeddingLoadStats {
+    pub visual_loaded: usize,
+    pub visual_skipped: usize,
+    pub text_loaded: usize,
+    pub text_skipped: usize,
+    /// Stored documents that could not be read, so none of their
+    /// embeddings were loaded
+    #[serde(default)]
+    pub unreadable_documents: usize,
+}
+
+impl EmbeddingLoadStats {
+    fn count_visual(&mut self, loaded: bool) {
+        if loaded {
+            self.visual_loaded += 1;
+        } else {
+            self.visual_skipped += 1;
+        }
+    }
+    
+    fn count_text(&mut self, loaded: bool) {
+        if loaded {
+            self.text_loaded += 1;
+        } else {
+            self.text_skipped += 1;
+        }
+    }
+    
+    /// Embeddings skipped of either type
+    pub fn skipped(&self) -> usize {
+        self.visual_skipped + self.text_skipped
+    }
+}
+
+/// Check a vector against the dimension the store expects, if one is set yet
+fn check_dimension(expected: Option<usize>, vector: &[f32]) -> Result<(), VectorError> {
+    match expected {
+        Some(expected) if vector.len() != expected => Err(VectorError::DimensionMismatch {
+            expected,
+            got: vector.len(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Reject embeddings that carry no direction: all zeros or non-finite values
+fn check_magnitude(vector: &[f32]) -> Result<(), VectorError> {
+    if vector.iter().any(|x| !x.is_finite()) {
+        return Err(VectorError::NonFinite);
+    }
+    let magnitude = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if magnitude < MIN_EMBEDDING_MAGNITUDE {
+        return Err(VectorError::ZeroMagnitude { magnitude });
+    }
+    Ok(())
+}
+
+/// Turn a degenerate-embedding or dimension error into a warning while loading
+/// 
+/// One embedding from another model must not keep the rest of the library
+/// out of the store; `IndexService::audit_embeddings` finds such documents.
+/// 
+/// Returns whether the embedding was loaded.
+fn skip_unloadable(doc_id: Uuid, result: Result<(), VectorError>) -> Result<bool, VectorError> {
+    match result {
+        Ok(()) => Ok(true),
+        Err(e @ (VectorError::ZeroMagnitude { .. } | VectorError::NonFinite)) => {
+            warn!("Skipping stored embedding of document {}: {}", doc_id, e);
+            Ok(false)
+        }
+        Err(e @ VectorError::DimensionMismatch { .. }) => {
+            warn!("Skipping stored embedding of document {} from another model: {}", doc_id, e);
+            Ok(false)
+        }
+        Err(e) => Err(e),
+    }
 }
 
 /// Calculate cosine similarity between two normalized vectors
@@ -238,6 +885,33 @@ fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
     a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
 }
 
+/// Highest similarity between a prepared query and any of the chunks
+fn best_similarity(metric: DistanceMetric, query: &[f32], chunks: &[Vec<f32>]) -> f32 {
+    chunks.iter()
+        .map(|chunk| metric.score(query, chunk))
+        .fold(f32::NEG_INFINITY, f32::max)
+}
+
+/// Mean of several embeddings, each normalized first
+/// 
+/// Gives a single representative vector for a chunked text. Returns `None`
+/// for an empty list or mismatched dimensions.
+pub fn mean_pool(embeddings: &[Vec<f32>]) -> Option<Vec<f32>> {
+    let dimension = embeddings.first()?.len();
+    if embeddings.iter().any(|embedding| embedding.len() != dimension) {
+        return None;
+    }
+    
+    let mut mean = vec![0.0; dimension];
+    for embedding in embeddings {
+        for (sum, value) in mean.iter_mut().zip(normalize_vector(embedding)) {
+            *sum += value;
+        }
+    }
+    let count = embeddings.len() as f32;
+    Some(mean.into_iter().map(|sum| sum / count).collect())
+}
+
 /// Normalize a vector to unit length
 fn normalize_vector(vector: &[f32]) -> Vec<f32> {
     let magnitude: f32 = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
@@ -337,6 +1011,213 @@ mod tests {
         
         // Try to add embedding with different dimension
         let result = store.add_visual_embedding(doc_id2, vec![0.1, 0.2]);
-        assert!(result.is_err());
+        assert_eq!(result, Err(VectorError::DimensionMismatch { expected: 3, got: 2 }));
+        
+        // Queries of the wrong dimension are rejected instead of panicking
+        let result = store.find_visual_similar(&[0.1, 0.2], 5, 0.0);
+        assert!(matches!(result, Err(VectorError::DimensionMismatch { expected: 3, got: 2 })));
+        
+        // Clearing a type lets a model with another dimension take over
+        store.add_text_embedding(doc_id1, vec![1.0, 0.0]).unwrap();
+        store.clear_type(EmbeddingType::Visual);
+        assert_eq!(store.dimension(EmbeddingType::Visual), None);
+        assert_eq!(store.dimension(EmbeddingType::Text), Some(2));
+        store.add_visual_embedding(doc_id2, vec![0.1, 0.2]).unwrap();
+        assert_eq!(store.get_stats().visual_embeddings_count, 1);
+        
+        // An expected dimension applies before the first embedding arrives
+        store.clear_type(EmbeddingType::Text);
+        store.expect_dimension(EmbeddingType::Text, 4);
+        assert!(store.add_text_embedding(doc_id1, vec![1.0, 0.0]).is_err());
+        store.expect_dimension(EmbeddingType::Visual, 4);
+        assert_eq!(store.dimension(EmbeddingType::Visual), Some(2));
+    }
+    
+    #[test]
+    fn test_zero_magnitude_rejected() {
+        let mut store = VectorStore::new();
+        let doc_id = Uuid::new_v4();
+        
+        let result = store.add_visual_embedding(doc_id, vec![0.0; 4]);
+        assert!(matches!(result, Err(VectorError::ZeroMagnitude { .. })));
+        assert!(store.add_text_embedding(doc_id, vec![1e-9, 0.0]).is_err());
+        assert!(matches!(store.add_text_embeddings(doc_id, vec![vec![1.0, 0.0], vec![0.0, 0.0]]), Err(VectorError::ZeroMagnitude { .. })));
+        assert_eq!(store.add_visual_embedding(doc_id, vec![f32::NAN, 1.0, 0.0, 0.0]), Err(VectorError::NonFinite));
+        
+        // Nothing was stored, so a proper embedding of any dimension is still accepted
+        assert_eq!(store.get_stats().visual_embeddings_count, 0);
+        assert_eq!(store.dimension(EmbeddingType::Visual), None);
+        store.add_visual_embedding(doc_id, vec![0.0, 1.0]).unwrap();
+        
+        // Stored zero vectors from older versions are skipped, not fatal
+        let mut broken = AssetDocument::from_asset(&schema::Asset::new("/broken.png".into(), schema::AssetType::Image));
+        broken.visual_embedding = Some(vec![0.0, 0.0]);
+        let mut reloaded = VectorStore::new();
+        let stats = reloaded.load_from_documents(&[broken]).unwrap();
+        assert_eq!((stats.visual_loaded, stats.visual_skipped), (0, 1));
+        assert_eq!(reloaded.get_stats().visual_embeddings_count, 0);
+    }
+    
+    #[test]
+    fn test_similar_to_document_errors() {
+        let mut store = VectorStore::new();
+        let doc_id = Uuid::new_v4();
+        let missing = Uuid::new_v4();
+        
+        let result = store.find_similar_to_document(&doc_id, EmbeddingType::Visual, 5, 0.0);
+        assert!(matches!(result, Err(VectorError::EmptyStore)));
+        
+        store.add_visual_embedding(doc_id, vec![1.0, 0.0]).unwrap();
+        let result = store.find_similar_to_document(&missing, EmbeddingType::Visual, 5, 0.0);
+        assert!(matches!(result, Err(VectorError::DocumentMissing(id)) if id == missing));
+        
+        // Distinct cases surface as distinct application errors
+        let err: schema::DamError = VectorError::DimensionMismatch { expected: 3, got: 2 }.into();
+        assert!(!err.is_recoverable());
+        let err: schema::DamError = VectorError::DocumentMissing(missing).into();
+        assert!(!err.is_recoverable());
+    }
+    
+    #[test]
+    fn test_text_chunks() {
+        let mut store = VectorStore::new();
+        let transcript = Uuid::new_v4();
+        let other = Uuid::new_v4();
+        
+        store.add_text_embeddings(transcript, vec![vec![1.0, 0.0], vec![0.0, 1.0]]).unwrap();
+        store.add_text_embedding(other, vec![0.7, 0.7]).unwrap();
+        assert!(store.add_text_embeddings(other, vec![vec![1.0, 0.0], vec![1.0]]).is_err());
+        
+        // A query matching the second chunk finds the whole transcript
+        let results = store.find_text_similar(&[0.0, 1.0], 5, 0.0).unwrap();
+        assert_eq!(results[0].document_id, transcript);
+        assert!((results[0].similarity - 1.0).abs() < 1e-6);
+        
+        let results = store.find_similar_to_document(&transcript, EmbeddingType::Text, 5, 0.0).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].document_id, other);
+        
+        let mean = mean_pool(&[vec![2.0, 0.0], vec![0.0, 1.0]]).unwrap();
+        assert_eq!(mean, vec![0.5, 0.5]);
+        assert!(mean_pool(&[]).is_none());
+    }
+    
+    /// Embeddings as the database would hold them
+    #[derive(Debug, Default)]
+    struct MapSource(Mutex<HashMap<Uuid, Vec<f32>>>);
+    
+    impl EmbeddingSource for MapSource {
+        fn load(&self, doc_id: &Uuid, _embedding_type: &EmbeddingType) -> Option<Vec<Vec<f32>>> {
+            self.0.lock().unwrap().get(doc_id).map(|embedding| vec![embedding.clone()])
+        }
+    }
+    
+    fn limited_store(policy: EvictionPolicy) -> (VectorStore, Vec<Uuid>) {
+        let source = Arc::new(MapSource::default());
+        let mut store = VectorStore::new().with_residency_limit(2, policy, source.clone());
+        let ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+        for (i, doc_id) in ids.iter().enumerate() {
+            let mut embedding = vec![0.1; 3];
+            embedding[i] = 1.0;
+            source.0.lock().unwrap().insert(*doc_id, embedding.clone());
+            store.add_visual_embedding(*doc_id, embedding).unwrap();
+        }
+        (store, ids)
+    }
+    
+    #[test]
+    fn test_residency_limit() {
+        let (mut store, ids) = limited_store(EvictionPolicy::LeastRecentlyUsed);
+        let stats = store.get_stats();
+        assert_eq!((stats.visual_embeddings_count, stats.resident_documents, stats.spilled_documents), (3, 2, 1));
+        assert!(!store.visual_embeddings.contains_key(&ids[0]));
+        
+        // Spilled embeddings are still searched and looked up
+        let results = store.find_visual_similar(&[1.0, 0.0, 0.0], 1, 0.0).unwrap();
+        assert_eq!(results[0].document_id, ids[0]);
+        assert!(store.embedding(&ids[0], EmbeddingType::Visual).is_some());
+        assert_eq!(store.find_similar_to_document(&ids[0], EmbeddingType::Visual, 5, -1.0).unwrap().len(), 2);
+        
+        // A search hit protects the older resident document from eviction
+        store.find_visual_similar(&[0.0, 1.0, 0.0], 1, 0.0).unwrap();
+        store.add_visual_embedding(Uuid::new_v4(), vec![0.0, 0.0, 1.0]).unwrap();
+        assert!(store.visual_embeddings.contains_key(&ids[1]));
+        assert!(!store.visual_embeddings.contains_key(&ids[2]));
+        
+        store.remove_document(&ids[0]);
+        assert_eq!(store.get_stats().visual_embeddings_count, 3);
+    }
+    
+    #[test]
+    fn test_residency_keep_newest() {
+        let (mut store, ids) = limited_store(EvictionPolicy::KeepNewest);
+        
+        // Searches do not change what stays in memory
+        store.find_visual_similar(&[0.0, 1.0, 0.0], 1, 0.0).unwrap();
+        store.add_visual_embedding(Uuid::new_v4(), vec![0.0, 0.0, 1.0]).unwrap();
+        assert!(!store.visual_embeddings.contains_key(&ids[1]));
+        assert!(store.visual_embeddings.contains_key(&ids[2]));
+        
+        // Removing the limit reads spilled embeddings back
+        let source = store.residency.as_ref().unwrap().source.clone();
+        store.set_residency_limit(None, EvictionPolicy::KeepNewest, source);
+        let stats = store.get_stats();
+        assert_eq!((stats.resident_documents, stats.spilled_documents, stats.max_resident_documents), (4, 0, None));
+    }
+    
+    #[test]
+    fn test_distance_metrics() {
+        let near = Uuid::new_v4();
+        let far = Uuid::new_v4();
+        
+        // Same direction as the query but far away: only L2/L1 notice
+        let mut euclidean = VectorStore::with_metric(DistanceMetric::Euclidean);
+        let mut cosine = VectorStore::new();
+        for store in [&mut euclidean, &mut cosine] {
+            store.add_visual_embedding(near, vec![0.9, 0.3]).unwrap();
+            store.add_visual_embedding(far, vec![10.0, 0.0]).unwrap();
+        }
+        
+        let results = cosine.find_visual_similar(&[1.0, 0.0], 2, 0.0).unwrap();
+        assert_eq!(results[0].document_id, far);
+        
+        let results = euclidean.find_visual_similar(&[1.0, 0.0], 2, f32::NEG_INFINITY).unwrap();
+        assert_eq!(results[0].document_id, near);
+        assert!((results[0].similarity + euclidean_distance(&[1.0, 0.0], &[0.9, 0.3])).abs() < 1e-6);
+        assert!((results[1].similarity + 9.0).abs() < 1e-6);
+        
+        // A threshold of -max_distance drops distant matches
+        let results = euclidean.find_visual_similar(&[1.0, 0.0], 2, -1.0).unwrap();
+        assert_eq!(results.len(), 1);
+        
+        let mut manhattan = VectorStore::with_metric(DistanceMetric::Manhattan);
+        manhattan.add_visual_embedding(near, vec![0.9, 0.3]).unwrap();
+        let results = manhattan.find_visual_similar(&[1.0, 0.0], 1, -0.5).unwrap();
+        assert!((results[0].similarity + 0.4).abs() < 1e-6);
+    }
+    
+    #[test]
+    fn test_diversify() {
+        let (a, near_duplicate, other) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let mut store = VectorStore::new();
+        store.add_visual_embedding(a, vec![1.0, 0.2, 0.0, 0.0]).unwrap();
+        store.add_visual_embedding(near_duplicate, vec![1.0, 0.25, 0.0, 0.0]).unwrap();
+        store.add_visual_embedding(other, vec![0.15, 1.0, 0.0, 0.0]).unwrap();
+        
+        let query = [1.0, 1.0, 0.0, 0.0];
+        let matches = store.find_visual_similar(&query, 3, 0.0).unwrap();
+        let ids = |matches: &[VectorMatch]| matches.iter().map(|m| m.document_id).collect::<Vec<_>>();
+        assert_eq!(ids(&matches), vec![near_duplicate, a, other]);
+        
+        // No diversity keeps the relevance order
+        assert_eq!(ids(&store.diversify(matches.clone(), 2, 0.0)), vec![near_duplicate, a]);
+        
+        // The near-duplicate of the top result gives way to the other one
+        let diverse = store.diversify(matches.clone(), 2, 0.5);
+        assert_eq!(ids(&diverse), vec![near_duplicate, other]);
+        assert_eq!(diverse[1].similarity, matches[2].similarity);
+        
+        // Even at full diversity the most relevant match comes first
+        assert_eq!(store.diversify(matches, 3, 1.0)[0].document_id, near_duplicate);
     }
 }
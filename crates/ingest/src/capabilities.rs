@@ -0,0 +1,104 @@
+//! What the DAM can do with each file format
+//!
+//! Format support is spread over detection, the metadata parsers and the
+//! preview generators. This module keeps one table of it so frontends can
+//! tell users up front when a format will only be partially handled. The
+//! table is derived from the extension groups in [`crate::formats`] that
+//! the parsers and preview generators route on.
+
+use crate::detector::SUPPORTED_EXTENSIONS;
+use crate::formats::{is_in_any, is_undecodable_image, METADATA_PARSER_GROUPS, PREVIEW_GROUPS};
+use schema::AssetType;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// What is available for one file extension
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FormatCapability {
+    /// Asset type files with this extension are ingested as
+    pub asset_type: AssetType,
+    /// Recognized by format detection
+    pub supported: bool,
+    /// Format-specific metadata (dimensions, duration, ...) is extracted
+    pub has_metadata_parser: bool,
+    /// A rendered thumbnail is generated instead of a generic icon
+    pub has_preview: bool,
+    /// AI tagging or transcription can run on it
+    pub ai_processable: bool,
+}
+
+/// Capabilities of every known extension
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FormatCapabilities {
+    /// Capabilities by lowercase extension, without the dot
+    pub formats: BTreeMap<String, FormatCapability>,
+}
+
+impl FormatCapabilities {
+    /// Build the table for all supported extensions
+    pub fn new() -> Self {
+        let formats = SUPPORTED_EXTENSIONS.iter()
+            .map(|extension| (extension.to_string(), FormatCapability::of(extension)))
+            .collect();
+        Self { formats }
+    }
+
+    /// Capabilities of an extension; unknown extensions have none
+    pub fn get(&self, extension: &str) -> FormatCapability {
+        let extension = extension.trim_start_matches('.').to_lowercase();
+        self.formats.get(&extension)
+            .cloned()
+            .unwrap_or_else(|| FormatCapability::of(&extension))
+    }
+}
+
+impl FormatCapability {
+    fn of(extension: &str) -> Self {
+        let asset_type = AssetType::from_extension(extension);
+        let supported = SUPPORTED_EXTENSIONS.contains(&extension);
+        Self {
+            ai_processable: supported
+                && !is_undecodable_image(extension)
+                && matches!(asset_type, AssetType::Image | AssetType::Audio | AssetType::Video),
+            has_metadata_parser: is_in_any(METADATA_PARSER_GROUPS, extension),
+            has_preview: is_in_any(PREVIEW_GROUPS, extension),
+            asset_type,
+            supported,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capabilities() {
+        let capabilities = FormatCapabilities::new();
+
+        let png = capabilities.get("PNG");
+        assert!(png.supported && png.has_metadata_parser && png.has_preview && png.ai_processable);
+        assert_eq!(png.asset_type, AssetType::Image);
+
+        // Partially handled formats
+        let psd = capabilities.get(".psd");
+        assert!(psd.has_metadata_parser && !psd.has_preview);
+        let mp4 = capabilities.get("mp4");
+        assert!(mp4.ai_processable && !mp4.has_metadata_parser && !mp4.has_preview);
+        assert!(!capabilities.get("zip").ai_processable);
+        let heic = capabilities.get("heic");
+        assert!(heic.supported && !heic.has_preview && !heic.ai_processable);
+
+        let unknown = capabilities.get("xyz");
+        assert!(!unknown.supported && !unknown.has_preview && !unknown.ai_processable);
+        assert!(!capabilities.formats.contains_key("xyz"));
+
+        // Every listed parser or preview belongs to a supported format
+        for extension in METADATA_PARSER_GROUPS.iter().chain(PREVIEW_GROUPS).flat_map(|group| group.iter()) {
+            assert!(capabilities.get(extension).supported, "{} is not supported", extension);
+        }
+        let tif = capabilities.get("tif");
+        assert!(tif.has_metadata_parser && tif.has_preview);
+        assert_eq!(tif.asset_type, AssetType::Image);
+    }
+}
@@ -0,0 +1,159 @@
+//! Capture date extraction
+//!
+//! Reads when a photo was taken from its EXIF `DateTimeOriginal` tag
+//! (JPEG APP1 segment or the TIFF structure of TIFF and camera RAW files),
+//! falling back to the equivalent XMP properties. The value is stored in
+//! the asset's custom metadata under [`CAPTURE_DATE_KEY`], in the form it
+//! was written, for the index to parse.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Custom metadata key the capture date is stored under
+pub const CAPTURE_DATE_KEY: &str = "DateTimeOriginal";
+
+/// Bytes read from the head of a file when looking for its capture date
+///
+/// EXIF blocks and XMP packets sit before the image data in the formats
+/// written by cameras and photo tools, so the rest is never read.
+const CAPTURE_DATE_SCAN_BYTES: u64 = 256 * 1024;
+
+/// Pointer from IFD0 to the EXIF IFD
+const TAG_EXIF_IFD: u16 = 0x8769;
+const TAG_DATE_TIME_ORIGINAL: u16 = 0x9003;
+const TYPE_ASCII: u16 = 2;
+
+/// XMP properties holding the capture date, most specific first
+const XMP_DATE_PROPERTIES: &[&str] = &["exif:DateTimeOriginal", "photoshop:DateCreated", "xmp:CreateDate"];
+
+/// Read the capture date of an image file
+///
+/// Blocking; only the head of the file is read.
+pub fn read_capture_date(path: &Path) -> std::io::Result<Option<String>> {
+    let mut head = Vec::new();
+    File::open(path)?.take(CAPTURE_DATE_SCAN_BYTES).read_to_end(&mut head)?;
+    Ok(capture_date(&head))
+}
+
+/// Find the capture date in the head of an image
+pub fn capture_date(data: &[u8]) -> Option<String> {
+    let exif = if data.starts_with(&[0xFF, 0xD8]) {
+        crate::embedded::jpeg_exif_segment(data).and_then(exif_date_time_original)
+    } else {
+        exif_date_time_original(data)
+    };
+    exif.or_else(|| {
+        let xmp = crate::keywords::find_xmp_packet(data)?;
+        let xmp = String::from_utf8_lossy(xmp);
+        XMP_DATE_PROPERTIES.iter().find_map(|property| xmp_property(&xmp, property))
+    })
+}
+
+/// Read `DateTimeOriginal` from the EXIF IFD of a TIFF structure
+fn exif_date_time_original(tiff: &[u8]) -> Option<String> {
+    let little_endian = match tiff.get(..4)? {
+        [b'I', b'I', 0x2A, 0x00] => true,
+        [b'M', b'M', 0x00, 0x2A] => false,
+        _ => return None,
+    };
+    let u16_at = |offset: usize| -> Option<u16> {
+        let bytes: [u8; 2] = tiff.get(offset..offset.checked_add(2)?)?.try_into().ok()?;
+        Some(if little_endian { u16::from_le_bytes(bytes) } else { u16::from_be_bytes(bytes) })
+    };
+    let u32_at = |offset: usize| -> Option<u32> {
+        let bytes: [u8; 4] = tiff.get(offset..offset.checked_add(4)?)?.try_into().ok()?;
+        Some(if little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) })
+    };
+    let find_entry = |ifd: usize, tag: u16| -> Option<usize> {
+        let count = u16_at(ifd)? as usize;
+        (0..count)
+            .map(|i| ifd + 2 + i * 12)
+            .find(|&entry| u16_at(entry) == Some(tag))
+    };
+
+    let ifd0 = u32_at(4)? as usize;
+    let exif_ifd = u32_at(find_entry(ifd0, TAG_EXIF_IFD)? + 8)? as usize;
+    let entry = find_entry(exif_ifd, TAG_DATE_TIME_ORIGINAL)?;
+    if u16_at(entry + 2)? != TYPE_ASCII {
+        return None;
+    }
+    let count = u32_at(entry + 4)? as usize;
+    let start = if count <= 4 { entry + 8 } else { u32_at(entry + 8)? as usize };
+    let value = tiff.get(start..start.checked_add(count)?)?;
+
+    let value = String::from_utf8_lossy(value);
+    let value = value.trim_end_matches('\0').trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// Value of an XMP property written as an attribute or as an element
+fn xmp_property(xmp: &str, property: &str) -> Option<String> {
+    let attribute = format!("{}=\"", property);
+    let value = if let Some(start) = xmp.find(&attribute) {
+        let rest = &xmp[start + attribute.len()..];
+        &rest[..rest.find('"')?]
+    } else {
+        let open = format!("<{}>", property);
+        let rest = &xmp[xmp.find(&open)? + open.len()..];
+        &rest[..rest.find(&format!("</{}>", property))?]
+    };
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a little-endian TIFF whose EXIF IFD holds a `DateTimeOriginal`
+    fn tiff_with_date(date: &str) -> Vec<u8> {
+        let mut value = date.as_bytes().to_vec();
+        value.push(0);
+
+        let mut data = b"II\x2A\x00".to_vec();
+        data.extend(8u32.to_le_bytes());
+        // IFD0 with one entry pointing at the EXIF IFD right after it
+        let exif_ifd = 8 + 2 + 12 + 4;
+        data.extend(1u16.to_le_bytes());
+        data.extend(TAG_EXIF_IFD.to_le_bytes());
+        data.extend(4u16.to_le_bytes());
+        data.extend(1u32.to_le_bytes());
+        data.extend((exif_ifd as u32).to_le_bytes());
+        data.extend(0u32.to_le_bytes());
+        // EXIF IFD with the date stored after it
+        let value_offset = exif_ifd + 2 + 12 + 4;
+        data.extend(1u16.to_le_bytes());
+        data.extend(TAG_DATE_TIME_ORIGINAL.to_le_bytes());
+        data.extend(TYPE_ASCII.to_le_bytes());
+        data.extend((value.len() as u32).to_le_bytes());
+        data.extend((value_offset as u32).to_le_bytes());
+        data.extend(0u32.to_le_bytes());
+        data.extend(value);
+        data
+    }
+
+    #[test]
+    fn test_exif_capture_date() {
+        let tiff = tiff_with_date("2024:03:05 14:30:00");
+        assert_eq!(capture_date(&tiff).as_deref(), Some("2024:03:05 14:30:00"));
+
+        let payload = [b"Exif\0\0".as_slice(), &tiff].concat();
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE1];
+        jpeg.extend(((payload.len() + 2) as u16).to_be_bytes());
+        jpeg.extend(payload);
+        jpeg.extend([0xFF, 0xDA]);
+        assert_eq!(capture_date(&jpeg).as_deref(), Some("2024:03:05 14:30:00"));
+    }
+
+    #[test]
+    fn test_xmp_capture_date() {
+        let attribute = b"....<x:xmpmeta><rdf:Description exif:DateTimeOriginal=\"2023-07-01T09:15:00\"/></x:xmpmeta>";
+        assert_eq!(capture_date(attribute).as_deref(), Some("2023-07-01T09:15:00"));
+
+        let element = b"<x:xmpmeta><photoshop:DateCreated>2022-01-02T03:04:05+01:00</photoshop:DateCreated></x:xmpmeta>";
+        assert_eq!(capture_date(element).as_deref(), Some("2022-01-02T03:04:05+01:00"));
+
+        assert_eq!(capture_date(b"\x89PNG\r\n\x1a\n"), None);
+    }
+}
@@ -6,14 +6,62 @@
 //! - Content analysis
 //! - MIME type detection
 
-use schema::{FileFormat, DamResult};
+use schema::{DetectionMethod, FileFormat, DamResult};
 use std::path::Path;
 use tokio::fs;
 use tokio::io::AsyncReadExt;
-use tracing::{debug, warn};
+use tracing::warn;
 use crate::error::IngestError;
 
+/// Confidence when the content signature and the extension agree
+pub const CONFIDENCE_CONFIRMED: f32 = 1.0;
+
+/// Confidence of a magic byte match that overrides the extension
+pub const CONFIDENCE_MAGIC: f32 = 0.9;
+
+/// Confidence of a text content match (e.g. SVG) that overrides the extension
+pub const CONFIDENCE_SNIFFED: f32 = 0.7;
+
+/// Confidence of a known extension whose content was not recognized
+pub const CONFIDENCE_EXTENSION: f32 = 0.5;
+
+/// Confidence of an unknown extension whose content was not recognized
+pub const CONFIDENCE_UNKNOWN: f32 = 0.1;
+
+/// Extensions the DAM recognizes and ingests
+pub const SUPPORTED_EXTENSIONS: &[&str] = &[
+    // Images
+    "png", "jpg", "jpeg", "gif", "bmp", "tiff", "tif", "tga", "webp", "psd", "psb", "svg", "exr", "hdr",
+    "heic", "heif", "avif", "cr2", "cr3", "nef", "nrw", "arw", "dng", "orf", "rw2", "raf", "pef", "srw",
+    // 3D formats
+    "blend", "fbx", "obj", "gltf", "glb", "dae", "3ds", "ply", "stl",
+    // Audio
+    "wav", "mp3", "flac", "ogg", "aac", "m4a",
+    // Video
+    "mp4", "mov", "avi", "mkv", "wmv", "webm",
+    // Documents
+    "txt", "md", "csv", "tsv", "srt", "vtt", "log", "pdf", "doc", "docx",
+    // Archives
+    "zip", "rar", "tar", "gz", "7z",
+];
+
+/// Extensions that share a signature with a detected format
+/// 
+/// Container formats (RIFF, ISO media, ZIP, TIFF) are recognized by one
+/// signature but stored under more specific extensions; a match within the
+/// family confirms the extension instead of overriding it.
+const SIGNATURE_FAMILIES: &[(&str, &[&str])] = &[
+    ("jpg", &["jpeg", "jpe"]),
+    ("tiff", &["tif", "dng", "cr2", "nef", "nrw", "arw", "pef", "srw"]),
+    ("psd", &["psb"]),
+    ("wav", &["avi", "wave"]),
+    ("mp4", &["m4a", "m4v", "mov", "3gp", "heic", "heif", "avif", "cr3"]),
+    ("zip", &["docx", "xlsx", "pptx", "kra"]),
+    ("gz", &["tgz"]),
+];
+
 /// Service for detecting file formats
+#[derive(Clone)]
 pub struct FormatDetector {
     /// Magic byte patterns for format detection
     magic_patterns: Vec<MagicPattern>,
@@ -50,6 +98,11 @@ impl FormatDetector {
     }
     
     /// Detect file format from path and content
+    /// 
+    /// The content signature wins over the extension. `detection_method` and
+    /// `confidence` record which one decided: a signature confirming the
+    /// extension is certain, one contradicting it (a mislabeled file) is
+    /// slightly less so, and an extension alone is a guess.
     pub async fn detect_format<P: AsRef<Path>>(&self, path: P) -> DamResult<FileFormat> {
         let path = path.as_ref();
         
@@ -57,16 +110,20 @@ impl FormatDetector {
         let mut format = self.detect_from_extension(path);
         
         // Then try magic byte detection for more accurate results
-        if let Ok(magic_format) = self.detect_from_magic_bytes(path).await {
-            // If magic bytes give us a different result, prefer that
-            if magic_format.extension != format.extension {
-                debug!(
-                    "Magic byte detection overrides extension: {} -> {} for {}",
-                    format.extension,
-                    magic_format.extension,
-                    path.display()
+        if let Ok(content_format) = self.detect_from_magic_bytes(path).await {
+            if same_format(&content_format.extension, &format.extension) {
+                // Keep the more specific extension (e.g. "mov" over "mp4")
+                format.detection_method = content_format.detection_method;
+                format.confidence = CONFIDENCE_CONFIRMED;
+                format.mime_type = format.mime_type.or(content_format.mime_type);
+            } else {
+                warn!(
+                    "File content does not match its extension: {} is {}, not {}",
+                    path.display(),
+                    content_format.extension,
+                    format.extension
                 );
-                format = magic_format;
+                format = content_format;
             }
         }
         
@@ -99,6 +156,8 @@ impl FormatDetector {
             mime_type,
             version: None,
             supported,
+            detection_method: DetectionMethod::Extension,
+            confidence: if supported { CONFIDENCE_EXTENSION } else { CONFIDENCE_UNKNOWN },
         }
     }
     
@@ -120,10 +179,24 @@ impl FormatDetector {
                     mime_type: Some(pattern.mime_type.clone()),
                     version: None,
                     supported: pattern.supported,
+                    detection_method: DetectionMethod::MagicBytes,
+                    confidence: CONFIDENCE_MAGIC,
                 });
             }
         }
         
+        // SVG is text-based, so sniff for the root element instead
+        if crate::svg::is_svg_content(&buffer) {
+            return Ok(FileFormat {
+                extension: "svg".to_string(),
+                mime_type: Some("image/svg+xml".to_string()),
+                version: None,
+                supported: true,
+                detection_method: DetectionMethod::ContentSniffing,
+                confidence: CONFIDENCE_SNIFFED,
+            });
+        }
+        
         Err(IngestError::UnknownFormat {
             path: path.to_path_buf(),
         }.into())
@@ -162,27 +235,7 @@ impl FormatDetector {
     
     /// Check if an extension is supported
     fn is_extension_supported(&self, extension: &str) -> bool {
-        match extension {
-            // Images
-            "png" | "jpg" | "jpeg" | "gif" | "bmp" | "tiff" | "tga" | "webp" | "psd" | "psb" => true,
-            
-            // 3D formats
-            "blend" | "fbx" | "obj" | "gltf" | "glb" | "dae" | "3ds" | "ply" | "stl" => true,
-            
-            // Audio
-            "wav" | "mp3" | "flac" | "ogg" | "aac" | "m4a" => true,
-            
-            // Video
-            "mp4" | "mov" | "avi" | "mkv" | "wmv" | "webm" => true,
-            
-            // Documents
-            "txt" | "md" | "pdf" | "doc" | "docx" => true,
-            
-            // Archives
-            "zip" | "rar" | "tar" | "gz" | "7z" => true,
-            
-            _ => false,
-        }
+        SUPPORTED_EXTENSIONS.contains(&extension)
     }
     
     /// Convert extension to MIME type
@@ -193,9 +246,26 @@ impl FormatDetector {
             "jpg" | "jpeg" => "image/jpeg",
             "gif" => "image/gif",
             "bmp" => "image/bmp",
-            "tiff" => "image/tiff",
+            "tiff" | "tif" => "image/tiff",
             "webp" => "image/webp",
             "psd" => "image/vnd.adobe.photoshop",
+            "svg" => "image/svg+xml",
+            "exr" => "image/x-exr",
+            "hdr" => "image/vnd.radiance",
+            "cr2" => "image/x-canon-cr2",
+            "nef" => "image/x-nikon-nef",
+            "arw" => "image/x-sony-arw",
+            "dng" => "image/x-adobe-dng",
+            "heic" => "image/heic",
+            "heif" => "image/heif",
+            "avif" => "image/avif",
+            "cr3" => "image/x-canon-cr3",
+            "nrw" => "image/x-nikon-nrw",
+            "orf" => "image/x-olympus-orf",
+            "rw2" => "image/x-panasonic-rw2",
+            "raf" => "image/x-fuji-raf",
+            "pef" => "image/x-pentax-pef",
+            "srw" => "image/x-samsung-srw",
             
             // 3D formats
             "gltf" => "model/gltf+json",
@@ -220,6 +290,11 @@ impl FormatDetector {
             // Documents
             "txt" => "text/plain",
             "md" => "text/markdown",
+            "csv" => "text/csv",
+            "tsv" => "text/tab-separated-values",
+            "srt" => "application/x-subrip",
+            "vtt" => "text/vtt",
+            "log" => "text/plain",
             "pdf" => "application/pdf",
             
             // Archives
@@ -247,6 +322,9 @@ impl FormatDetector {
         // BMP
         self.add_pattern("bmp", vec![0x42, 0x4D], 0, "image/bmp", true);
         
+        // Canon CR2 (a TIFF with a "CR" marker after the header); checked before plain TIFF
+        self.add_pattern("cr2", vec![0x43, 0x52, 0x02, 0x00], 8, "image/x-canon-cr2", true);
+        
         // TIFF (little endian)
         self.add_pattern("tiff", vec![0x49, 0x49, 0x2A, 0x00], 0, "image/tiff", true);
         
@@ -256,6 +334,13 @@ impl FormatDetector {
         // WebP
         self.add_pattern("webp", vec![0x57, 0x45, 0x42, 0x50], 8, "image/webp", true);
         
+        // OpenEXR
+        self.add_pattern("exr", vec![0x76, 0x2F, 0x31, 0x01], 0, "image/x-exr", true);
+        
+        // Radiance HDR
+        self.add_pattern("hdr", b"#?RADIANCE".to_vec(), 0, "image/vnd.radiance", true);
+        self.add_pattern("hdr", b"#?RGBE".to_vec(), 0, "image/vnd.radiance", true);
+        
         // Photoshop PSD
         self.add_pattern("psd", vec![0x38, 0x42, 0x50, 0x53], 0, "image/vnd.adobe.photoshop", true);
         
@@ -313,6 +398,13 @@ impl FormatDetector {
     }
 }
 
+/// Whether a detected format and a file extension name the same format
+fn same_format(detected: &str, extension: &str) -> bool {
+    detected == extension || SIGNATURE_FAMILIES.iter().any(|(signature, members)| {
+        *signature == detected && members.contains(&extension)
+    })
+}
+
 impl Default for FormatDetector {
     fn default() -> Self {
         Self::new().expect("Failed to create FormatDetector")
@@ -362,6 +454,89 @@ mod tests {
         assert_eq!(format.extension, "png");
         assert_eq!(format.mime_type, Some("image/png".to_string()));
         assert!(format.supported);
+        
+        // OpenEXR magic number
+        let exr_path = dir.path().join("render.bin");
+        let mut file = File::create(&exr_path).await.unwrap();
+        file.write_all(&[0x76, 0x2F, 0x31, 0x01, 0x02, 0x00, 0x00, 0x00]).await.unwrap();
+        file.flush().await.unwrap();
+        
+        let format = detector.detect_from_magic_bytes(&exr_path).await.unwrap();
+        assert_eq!(format.extension, "exr");
+        assert_eq!(format.mime_type, Some("image/x-exr".to_string()));
+    }
+    
+    #[tokio::test]
+    async fn test_svg_content_detection() {
+        let detector = FormatDetector::new().unwrap();
+        let dir = tempdir().unwrap();
+        
+        let svg_path = dir.path().join("icon.txt");
+        let mut file = File::create(&svg_path).await.unwrap();
+        file.write_all(b"<?xml version=\"1.0\"?>\n<svg xmlns=\"http://www.w3.org/2000/svg\"/>").await.unwrap();
+        file.flush().await.unwrap();
+        
+        let format = detector.detect_from_magic_bytes(&svg_path).await.unwrap();
+        assert_eq!(format.extension, "svg");
+        assert_eq!(format.mime_type, Some("image/svg+xml".to_string()));
+    }
+    
+    #[tokio::test]
+    async fn test_detection_method_and_confidence() {
+        let detector = FormatDetector::new().unwrap();
+        let dir = tempdir().unwrap();
+        let png_signature = [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+        
+        // Content and extension agree
+        let png_path = dir.path().join("logo.png");
+        std::fs::write(&png_path, png_signature).unwrap();
+        let format = detector.detect_format(&png_path).await.unwrap();
+        assert_eq!(format.detection_method, DetectionMethod::MagicBytes);
+        assert_eq!(format.confidence, CONFIDENCE_CONFIRMED);
+        assert!(!format.contradicts_extension(&png_path));
+        
+        // A PNG saved as .jpg is reported as such
+        let mislabeled = dir.path().join("photo.jpg");
+        std::fs::write(&mislabeled, png_signature).unwrap();
+        let format = detector.detect_format(&mislabeled).await.unwrap();
+        assert_eq!(format.extension, "png");
+        assert_eq!(format.confidence, CONFIDENCE_MAGIC);
+        assert!(format.contradicts_extension(&mislabeled));
+        
+        // Container signatures confirm the more specific extension
+        let movie = dir.path().join("clip.MOV");
+        std::fs::write(&movie, [0, 0, 0, 0x14, b'f', b't', b'y', b'p', b'q', b't']).unwrap();
+        let format = detector.detect_format(&movie).await.unwrap();
+        assert_eq!(format.extension, "mov");
+        assert_eq!(format.confidence, CONFIDENCE_CONFIRMED);
+        assert!(!format.contradicts_extension(&movie));
+        
+        // HEIC photos share the ISO media signature but stay images
+        let photo = dir.path().join("IMG_0002.HEIC");
+        std::fs::write(&photo, [0, 0, 0, 0x18, b'f', b't', b'y', b'p', b'h', b'e', b'i', b'c']).unwrap();
+        let format = detector.detect_format(&photo).await.unwrap();
+        assert_eq!(format.extension, "heic");
+        assert_eq!(format.mime_type, Some("image/heic".to_string()));
+        
+        // Plain TIFF-based RAW files keep their extension; CR2 is recognized by content
+        let nef = dir.path().join("DSC_0001.NEF");
+        std::fs::write(&nef, [0x49, 0x49, 0x2A, 0x00, 8, 0, 0, 0]).unwrap();
+        let format = detector.detect_format(&nef).await.unwrap();
+        assert_eq!(format.extension, "nef");
+        assert_eq!(format.confidence, CONFIDENCE_CONFIRMED);
+        
+        let cr2 = dir.path().join("IMG_0001.tif");
+        std::fs::write(&cr2, [0x49, 0x49, 0x2A, 0x00, 0x10, 0, 0, 0, b'C', b'R', 2, 0]).unwrap();
+        let format = detector.detect_format(&cr2).await.unwrap();
+        assert_eq!(format.extension, "cr2");
+        assert_eq!(format.mime_type, Some("image/x-canon-cr2".to_string()));
+        
+        // Unrecognized content leaves only the extension
+        let text = dir.path().join("notes.md");
+        std::fs::write(&text, "# notes").unwrap();
+        let format = detector.detect_format(&text).await.unwrap();
+        assert_eq!(format.detection_method, DetectionMethod::Extension);
+        assert_eq!(format.confidence, CONFIDENCE_EXTENSION);
     }
     
     #[test]
@@ -371,6 +546,7 @@ mod tests {
         assert!(detector.is_extension_supported("png"));
         assert!(detector.is_extension_supported("blend"));
         assert!(detector.is_extension_supported("wav"));
+        assert!(detector.is_extension_supported("dng"));
         assert!(!detector.is_extension_supported("xyz"));
     }
     
@@ -0,0 +1,349 @@
+//! Embedded preview extraction
+//!
+//! Camera RAW files (CR2, NEF, ARW, DNG) are TIFF containers that carry one
+//! or more ready-made JPEG previews, and most camera JPEGs store a small
+//! thumbnail in their EXIF block. Pulling those out is far cheaper than
+//! decoding the full image. Anything malformed is treated as "no preview".
+//!
+//! RAW files run to tens of megabytes, so they are read piecewise: only the
+//! IFDs and the chosen preview are loaded.
+
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+pub use crate::formats::RAW_EXTENSIONS;
+
+/// Bytes read from the head of a JPEG when looking for its EXIF thumbnail
+const JPEG_EXIF_SCAN_BYTES: u64 = 128 * 1024;
+
+/// Upper bound on IFDs visited, guarding against offset loops
+const MAX_IFDS: usize = 32;
+
+/// TIFF tags used to locate previews
+const TAG_SUB_IFDS: u16 = 0x014A;
+const TAG_EXIF_IFD: u16 = 0x8769;
+const TAG_COMPRESSION: u16 = 0x0103;
+const TAG_STRIP_OFFSETS: u16 = 0x0111;
+const TAG_STRIP_BYTE_COUNTS: u16 = 0x0117;
+const TAG_JPEG_OFFSET: u16 = 0x0201;
+const TAG_JPEG_LENGTH: u16 = 0x0202;
+
+/// Compression values that mean "the strip is a JPEG stream"
+const COMPRESSION_OLD_JPEG: u32 = 6;
+const COMPRESSION_JPEG: u32 = 7;
+
+/// Check whether an extension belongs to a supported camera RAW format
+pub fn is_raw_extension(extension: &str) -> bool {
+    crate::formats::is_in(RAW_EXTENSIONS, extension)
+}
+
+/// Extract the largest embedded JPEG preview from a TIFF/RAW or JPEG file
+///
+/// Returns the raw JPEG bytes, or `None` when the file carries no preview.
+pub fn extract_embedded_preview(data: &[u8]) -> Option<Vec<u8>> {
+    if data.starts_with(&[0xFF, 0xD8]) {
+        return jpeg_exif_segment(data).and_then(largest_preview);
+    }
+    largest_preview(data)
+}
+
+/// Read the largest embedded JPEG preview of a TIFF/RAW or JPEG file on disk
+///
+/// Blocking; only the parts of the file needed to find the preview are read.
+pub fn read_embedded_preview(path: &Path) -> std::io::Result<Option<Vec<u8>>> {
+    let mut file = File::open(path)?;
+    let size = file.metadata()?.len();
+    let mut magic = [0u8; 2];
+    if file.read_exact(&mut magic).is_err() {
+        return Ok(None);
+    }
+    file.seek(SeekFrom::Start(0))?;
+
+    if magic == [0xFF, 0xD8] {
+        // EXIF data sits in the first APP1 segment, so JPEGs only need their head read
+        let mut head = Vec::new();
+        file.take(JPEG_EXIF_SCAN_BYTES).read_to_end(&mut head)?;
+        return Ok(extract_embedded_preview(&head));
+    }
+    Ok(largest_preview(&FileSource { file: RefCell::new(file), size }))
+}
+
+/// Find the TIFF payload of a JPEG's APP1 `Exif` segment
+pub(crate) fn jpeg_exif_segment(data: &[u8]) -> Option<&[u8]> {
+    let mut pos = 2;
+    while pos + 4 <= data.len() {
+        if data[pos] != 0xFF {
+            return None;
+        }
+        let marker = data[pos + 1];
+        // Start of scan: no more metadata segments follow
+        if marker == 0xDA || marker == 0xD9 {
+            return None;
+        }
+        let length = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        let body = data.get(pos + 4..pos + 2 + length)?;
+        if marker == 0xE1 && body.starts_with(b"Exif\0\0") {
+            return Some(&body[6..]);
+        }
+        pos += 2 + length;
+    }
+    None
+}
+
+/// Walk every IFD in a TIFF structure and return the biggest JPEG found
+fn largest_preview<S: Source + ?Sized>(tiff: &S) -> Option<Vec<u8>> {
+    let reader = TiffReader::new(tiff)?;
+    let mut pending = vec![reader.u32(4)? as usize];
+    let mut visited = Vec::new();
+    let mut best: Option<(usize, usize)> = None;
+
+    while let Some(offset) = pending.pop() {
+        if offset == 0 || visited.contains(&offset) || visited.len() >= MAX_IFDS {
+            continue;
+        }
+        visited.push(offset);
+
+        let Some(ifd) = reader.ifd(offset) else { continue };
+        pending.extend(ifd.sub_ifds.iter().map(|&o| o as usize));
+        if let Some(next) = ifd.next {
+            pending.push(next as usize);
+        }
+
+        if let Some((start, len)) = ifd.jpeg_range() {
+            let in_bounds = start.checked_add(len).map_or(false, |end| end as u64 <= tiff.size());
+            let is_jpeg = in_bounds && len >= 2 && reader.bytes(start, 2).as_deref() == Some(&[0xFF, 0xD8][..]);
+            if is_jpeg && best.map_or(true, |(_, best_len)| len > best_len) {
+                best = Some((start, len));
+            }
+        }
+    }
+
+    best.and_then(|(start, len)| reader.bytes(start, len))
+}
+
+/// The preview-related fields of a single IFD
+#[derive(Debug, Default)]
+struct Ifd {
+    compression: Option<u32>,
+    strip_offset: Option<u32>,
+    strip_length: Option<u32>,
+    jpeg_offset: Option<u32>,
+    jpeg_length: Option<u32>,
+    sub_ifds: Vec<u32>,
+    next: Option<u32>,
+}
+
+impl Ifd {
+    /// Byte range of the JPEG stream this IFD points at, if any
+    fn jpeg_range(&self) -> Option<(usize, usize)> {
+        if let (Some(offset), Some(length)) = (self.jpeg_offset, self.jpeg_length) {
+            return Some((offset as usize, length as usize));
+        }
+        match self.compression {
+            Some(COMPRESSION_OLD_JPEG) | Some(COMPRESSION_JPEG) => {
+                Some((self.strip_offset? as usize, self.strip_length? as usize))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Random access to the bytes of a TIFF structure
+trait Source {
+    /// Total length in bytes
+    fn size(&self) -> u64;
+
+    /// Fill `buf` from `offset`; `None` if the range is out of bounds or unreadable
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Option<()>;
+}
+
+impl Source for [u8] {
+    fn size(&self) -> u64 {
+        self.len() as u64
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Option<()> {
+        let start = usize::try_from(offset).ok()?;
+        buf.copy_from_slice(self.get(start..start.checked_add(buf.len())?)?);
+        Some(())
+    }
+}
+
+/// A file read one range at a time
+struct FileSource {
+    file: RefCell<File>,
+    size: u64,
+}
+
+impl Source for FileSource {
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Option<()> {
+        if offset.checked_add(buf.len() as u64)? > self.size {
+            return None;
+        }
+        let mut file = self.file.borrow_mut();
+        file.seek(SeekFrom::Start(offset)).ok()?;
+        file.read_exact(buf).ok()
+    }
+}
+
+/// Endian-aware reads over a TIFF structure
+struct TiffReader<'a, S: Source + ?Sized> {
+    source: &'a S,
+    little_endian: bool,
+}
+
+impl<'a, S: Source + ?Sized> TiffReader<'a, S> {
+    fn new(source: &'a S) -> Option<Self> {
+        let mut magic = [0u8; 4];
+        source.read_at(0, &mut magic)?;
+        let little_endian = match magic {
+            [b'I', b'I', 0x2A, 0x00] => true,
+            [b'M', b'M', 0x00, 0x2A] => false,
+            _ => return None,
+        };
+        Some(Self { source, little_endian })
+    }
+
+    /// Copy `len` bytes starting at `offset`
+    fn bytes(&self, offset: usize, len: usize) -> Option<Vec<u8>> {
+        let mut buf = vec![0u8; len];
+        self.source.read_at(offset as u64, &mut buf)?;
+        Some(buf)
+    }
+
+    fn u16(&self, offset: usize) -> Option<u16> {
+        let mut bytes = [0u8; 2];
+        self.source.read_at(offset as u64, &mut bytes)?;
+        Some(if self.little_endian { u16::from_le_bytes(bytes) } else { u16::from_be_bytes(bytes) })
+    }
+
+    fn u32(&self, offset: usize) -> Option<u32> {
+        let mut bytes = [0u8; 4];
+        self.source.read_at(offset as u64, &mut bytes)?;
+        Some(if self.little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) })
+    }
+
+    /// Read the values of an entry as integers (SHORT or LONG types only)
+    fn values(&self, entry: usize) -> Option<Vec<u32>> {
+        let field_type = self.u16(entry + 2)?;
+        let count = self.u32(entry + 4)? as usize;
+        let size: usize = match field_type {
+            3 => 2,
+            4 | 13 => 4,
+            _ => return None,
+        };
+        let total = size.checked_mul(count)?;
+        let start = if total <= 4 { entry + 8 } else { self.u32(entry + 8)? as usize };
+        if start.checked_add(total)? as u64 > self.source.size() {
+            return None;
+        }
+        (0..count)
+            .map(|i| match size {
+                2 => self.u16(start + i * 2).map(u32::from),
+                _ => self.u32(start + i * 4),
+            })
+            .collect()
+    }
+
+    fn ifd(&self, offset: usize) -> Option<Ifd> {
+        let count = self.u16(offset)? as usize;
+        let mut ifd = Ifd::default();
+
+        for i in 0..count {
+            let entry = offset + 2 + i * 12;
+            let tag = self.u16(entry)?;
+            let first = || self.values(entry).and_then(|v| v.first().copied());
+            match tag {
+                TAG_COMPRESSION => ifd.compression = first(),
+                TAG_STRIP_OFFSETS => ifd.strip_offset = first(),
+                TAG_STRIP_BYTE_COUNTS => ifd.strip_length = first(),
+                TAG_JPEG_OFFSET => ifd.jpeg_offset = first(),
+                TAG_JPEG_LENGTH => ifd.jpeg_length = first(),
+                TAG_SUB_IFDS | TAG_EXIF_IFD => ifd.sub_ifds.extend(self.values(entry).unwrap_or_default()),
+                _ => {}
+            }
+        }
+
+        ifd.next = self.u32(offset + 2 + count * 12).filter(|&next| next != 0);
+        Some(ifd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a little-endian TIFF whose first IFD points at the given JPEG
+    fn tiff_with_jpeg(jpeg: &[u8]) -> Vec<u8> {
+        let mut data = b"II\x2A\x00".to_vec();
+        data.extend(8u32.to_le_bytes());
+        let jpeg_start = 8 + 2 + 2 * 12 + 4;
+        data.extend(2u16.to_le_bytes());
+        for (tag, value) in [(TAG_JPEG_OFFSET, jpeg_start as u32), (TAG_JPEG_LENGTH, jpeg.len() as u32)] {
+            data.extend(tag.to_le_bytes());
+            data.extend(4u16.to_le_bytes());
+            data.extend(1u32.to_le_bytes());
+            data.extend(value.to_le_bytes());
+        }
+        data.extend(0u32.to_le_bytes());
+        data.extend_from_slice(jpeg);
+        data
+    }
+
+    #[test]
+    fn test_extract_from_tiff() {
+        let jpeg = [0xFF, 0xD8, 0x01, 0x02, 0xFF, 0xD9];
+        let tiff = tiff_with_jpeg(&jpeg);
+        assert_eq!(extract_embedded_preview(&tiff), Some(jpeg.to_vec()));
+    }
+
+    #[test]
+    fn test_read_from_file() {
+        let jpeg = [0xFF, 0xD8, 0x01, 0x02, 0xFF, 0xD9];
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("photo.dng");
+        std::fs::write(&path, tiff_with_jpeg(&jpeg)).unwrap();
+        assert_eq!(read_embedded_preview(&path).unwrap(), Some(jpeg.to_vec()));
+
+        std::fs::write(&path, b"x").unwrap();
+        assert_eq!(read_embedded_preview(&path).unwrap(), None);
+    }
+
+    #[test]
+    fn test_extract_from_jpeg_exif() {
+        let thumbnail = [0xFF, 0xD8, 0xAA, 0xFF, 0xD9];
+        let tiff = tiff_with_jpeg(&thumbnail);
+
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE1];
+        jpeg.extend(((tiff.len() + 8) as u16).to_be_bytes());
+        jpeg.extend_from_slice(b"Exif\0\0");
+        jpeg.extend(&tiff);
+        jpeg.extend([0xFF, 0xDA, 0x00, 0x02]);
+
+        assert_eq!(extract_embedded_preview(&jpeg), Some(thumbnail.to_vec()));
+    }
+
+    #[test]
+    fn test_no_preview() {
+        assert_eq!(extract_embedded_preview(b"not an image"), None);
+        assert_eq!(extract_embedded_preview(&[0xFF, 0xD8, 0xFF, 0xDA, 0, 2]), None);
+
+        // Offsets pointing outside the file are ignored
+        let mut tiff = tiff_with_jpeg(&[0xFF, 0xD8]);
+        tiff.truncate(tiff.len() - 2);
+        assert_eq!(extract_embedded_preview(&tiff), None);
+    }
+
+    #[test]
+    fn test_raw_extensions() {
+        assert!(is_raw_extension("CR2"));
+        assert!(is_raw_extension("dng"));
+        assert!(!is_raw_extension("jpg"));
+    }
+}
@@ -0,0 +1,139 @@
+//! Text encoding detection for extracted document text
+//!
+//! Plain-text files carry no declared encoding, and decoding everything as
+//! UTF-8 turns Latin-1, Windows-1252, Shift-JIS or UTF-16 files into
+//! replacement characters in the search index. A byte order mark decides
+//! the encoding outright; otherwise UTF-16 without a BOM is recognized by
+//! its zero bytes, valid UTF-8 is kept as is, and anything else is guessed
+//! by `chardetng` and decoded with `encoding_rs`.
+
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+
+/// Share of zero bytes in one byte position above which BOM-less text is
+/// taken to be UTF-16
+const UTF16_ZERO_SHARE: f64 = 0.3;
+
+/// Text decoded to UTF-8, with the encoding it was read as
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedText {
+    pub text: String,
+    /// WHATWG name of the source encoding, e.g. `UTF-8` or `Shift_JIS`
+    pub encoding: &'static str,
+    /// Whether the encoding came from a byte order mark
+    pub had_bom: bool,
+}
+
+/// Detect the encoding of `data` and decode it
+///
+/// `truncated` tells that `data` was cut off, so a character split by the
+/// cut does not count against UTF-8 and the detector does not treat the
+/// end as the end of the file. The BOM is not part of the returned text.
+pub fn decode_text(data: &[u8], truncated: bool) -> DecodedText {
+    if let Some((encoding, bom_length)) = Encoding::for_bom(data) {
+        return decode_with(encoding, &data[bom_length..], true);
+    }
+
+    // Zero bytes are valid UTF-8, so UTF-16 has to be ruled out first
+    if let Some(encoding) = guess_utf16(data) {
+        return decode_with(encoding, data, false);
+    }
+
+    if is_utf8(data, truncated) {
+        return decode_with(UTF_8, data, false);
+    }
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(data, !truncated);
+    decode_with(detector.guess(None, true), data, false)
+}
+
+fn decode_with(encoding: &'static Encoding, data: &[u8], had_bom: bool) -> DecodedText {
+    let (text, _) = encoding.decode_without_bom_handling(data);
+    DecodedText {
+        text: text.into_owned(),
+        encoding: encoding.name(),
+        had_bom,
+    }
+}
+
+/// Whether the data is UTF-8, allowing a character cut off at the end of
+/// truncated data
+fn is_utf8(data: &[u8], truncated: bool) -> bool {
+    match std::str::from_utf8(data) {
+        Ok(_) => true,
+        // No error length means the input ended mid-character
+        Err(e) => truncated && e.error_len().is_none(),
+    }
+}
+
+/// UTF-16 byte order of BOM-less text, judged by where its zero bytes are
+///
+/// Text in Latin scripts encoded as UTF-16 has a zero in every other byte;
+/// 8-bit encodings practically never contain zeros.
+fn guess_utf16(data: &[u8]) -> Option<&'static Encoding> {
+    let pairs = data.len() / 2;
+    if pairs == 0 {
+        return None;
+    }
+    let zero_share = |offset: usize| {
+        data.chunks_exact(2).filter(|pair| pair[offset] == 0).count() as f64 / pairs as f64
+    };
+    let (even, odd) = (zero_share(0), zero_share(1));
+
+    if odd > UTF16_ZERO_SHARE && even < UTF16_ZERO_SHARE / 3.0 {
+        Some(UTF_16LE)
+    } else if even > UTF16_ZERO_SHARE && odd < UTF16_ZERO_SHARE / 3.0 {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    #[test]
+    fn test_detects_encodings() {
+        let decoded = decode_text("naïve café".as_bytes(), false);
+        assert_eq!((decoded.text.as_str(), decoded.encoding, decoded.had_bom), ("naïve café", "UTF-8", false));
+
+        // Latin-1 text is read as Windows-1252, its WHATWG superset
+        let decoded = decode_text(b"Le caf\xe9 est \xe0 c\xf4t\xe9 de la gare, pr\xe8s du th\xe9\xe2tre", false);
+        assert_eq!(decoded.encoding, "windows-1252");
+        assert_eq!(decoded.text, "Le café est à côté de la gare, près du théâtre");
+
+        let (shift_jis, _, _) = encoding_rs::SHIFT_JIS.encode("東京の夜景と港の写真です。撮影のメモはこちらにあります。");
+        let decoded = decode_text(&shift_jis, false);
+        assert_eq!(decoded.encoding, "Shift_JIS");
+        assert_eq!(decoded.text, "東京の夜景と港の写真です。撮影のメモはこちらにあります。");
+
+        // UTF-16 without a BOM is recognized by its zero bytes
+        let decoded = decode_text(&utf16le("Storyboard notes"), false);
+        assert_eq!((decoded.text.as_str(), decoded.encoding), ("Storyboard notes", "UTF-16LE"));
+    }
+
+    #[test]
+    fn test_byte_order_marks() {
+        let mut data = vec![0xFF, 0xFE];
+        data.extend(utf16le("Größe"));
+        let decoded = decode_text(&data, false);
+        assert_eq!((decoded.text.as_str(), decoded.encoding, decoded.had_bom), ("Größe", "UTF-16LE", true));
+
+        let decoded = decode_text(b"\xEF\xBB\xBFplain", false);
+        assert_eq!((decoded.text.as_str(), decoded.encoding, decoded.had_bom), ("plain", "UTF-8", true));
+    }
+
+    #[test]
+    fn test_truncated_utf8() {
+        // "é" cut after its first byte still counts as UTF-8 when truncated
+        let data = &"café".as_bytes()[..4];
+        assert_eq!(decode_text(data, true).encoding, "UTF-8");
+        assert_ne!(decode_text(data, false).encoding, "UTF-8");
+    }
+}
@@ -47,9 +47,13 @@ pub enum IngestError {
     #[error("File too large: {path} ({size} bytes)")]
     FileTooLarge { path: PathBuf, size: u64 },
     
+    /// Target path for a move already exists
+    #[error("Target already exists: {path}")]
+    TargetExists { path: PathBuf },
+    
     /// File is corrupted or invalid
-    #[error("Corrupted file: {path}")]
-    CorruptedFile { path: PathBuf },
+    #[error("Corrupted file {path}: {reason}")]
+    CorruptedFile { path: PathBuf, reason: String },
     
     /// External tool dependency error
     #[error("External tool error: {tool} - {reason}")]
@@ -89,8 +93,11 @@ impl From<IngestError> for DamError {
             IngestError::FileTooLarge { path, size } => {
                 DamError::ingestion(format!("File too large: {} ({} bytes)", path.display(), size))
             }
-            IngestError::CorruptedFile { path } => {
-                DamError::ingestion(format!("Corrupted file: {}", path.display()))
+            IngestError::TargetExists { path } => {
+                DamError::invalid_operation(format!("Target already exists: {}", path.display()))
+            }
+            IngestError::CorruptedFile { path, reason } => {
+                DamError::invalid_asset_data(format!("Corrupted file {}: {}", path.display(), reason))
             }
             IngestError::ExternalToolError { tool, reason } => {
                 DamError::external_dependency(tool, reason)
@@ -164,9 +171,17 @@ impl IngestError {
         Self::FileTooLarge { path, size }
     }
     
+    /// Create a target exists error
+    pub fn target_exists(path: PathBuf) -> Self {
+        Self::TargetExists { path }
+    }
+    
     /// Create a corrupted file error
-    pub fn corrupted_file(path: PathBuf) -> Self {
-        Self::CorruptedFile { path }
+    pub fn corrupted_file<S: Into<String>>(path: PathBuf, reason: S) -> Self {
+        Self::CorruptedFile {
+            path,
+            reason: reason.into(),
+        }
     }
     
     /// Create an external tool error
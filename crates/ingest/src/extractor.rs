@@ -0,0 +1,86 @@
+//! Pluggable metadata extraction
+//!
+//! Integrators register `MetadataExtractor`s on the `AssetParser` to pull
+//! studio-specific fields (e.g. from a proprietary sidecar file next to each
+//! render) without forking the crate. Extractors run after the built-in
+//! format parsers and their fields are merged into `AssetMetadata::custom`.
+
+use async_trait::async_trait;
+use schema::{Asset, DamResult};
+use std::collections::HashMap;
+
+/// Custom metadata source for some kinds of assets
+#[async_trait]
+pub trait MetadataExtractor: Send + Sync {
+    /// Whether this extractor handles the asset
+    fn supports(&self, asset: &Asset) -> bool;
+    
+    /// Extract fields to merge into the asset's custom metadata
+    async fn extract(&self, asset: &Asset) -> DamResult<HashMap<String, String>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AssetParser;
+    use schema::{AssetType, DamError};
+    use std::sync::Arc;
+    
+    /// Reads `key=value` lines from a `<file>.meta` sidecar
+    struct SidecarExtractor;
+    
+    #[async_trait]
+    impl MetadataExtractor for SidecarExtractor {
+        fn supports(&self, asset: &Asset) -> bool {
+            asset.asset_type == AssetType::Image
+        }
+        
+        async fn extract(&self, asset: &Asset) -> DamResult<HashMap<String, String>> {
+            let sidecar = asset.current_path.with_extension("meta");
+            let content = tokio::fs::read_to_string(sidecar).await?;
+            Ok(content.lines()
+                .filter_map(|line| line.split_once('='))
+                .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+                .collect())
+        }
+    }
+    
+    struct FailingExtractor;
+    
+    #[async_trait]
+    impl MetadataExtractor for FailingExtractor {
+        fn supports(&self, _asset: &Asset) -> bool {
+            true
+        }
+        
+        async fn extract(&self, _asset: &Asset) -> DamResult<HashMap<String, String>> {
+            Err(DamError::processing("extractor failed"))
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_registered_extractors_augment_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let image_path = dir.path().join("render.png");
+        image::RgbImage::new(4, 2).save(&image_path).unwrap();
+        std::fs::write(dir.path().join("render.meta"), "shot = sh010\nartist=kim\n").unwrap();
+        
+        let parser = AssetParser::new().unwrap()
+            .with_extractor(Arc::new(FailingExtractor))
+            .with_extractor(Arc::new(SidecarExtractor));
+        
+        let mut asset = Asset::new(image_path, AssetType::Image);
+        asset.file_size = std::fs::metadata(&asset.current_path).unwrap().len();
+        let metadata = parser.parse_metadata(&asset).await.unwrap();
+        
+        // Built-in parsing still ran, failing extractors are skipped
+        assert_eq!(metadata.image.unwrap().width, 4);
+        assert_eq!(metadata.custom.get("shot").map(String::as_str), Some("sh010"));
+        assert_eq!(metadata.custom.get("artist").map(String::as_str), Some("kim"));
+        
+        // Unsupported asset types are not passed to the extractor
+        let audio = Asset::new(dir.path().join("render.wav"), AssetType::Audio);
+        let parser = AssetParser::new().unwrap().with_extractor(Arc::new(SidecarExtractor));
+        assert!(parser.parse_metadata(&audio).await.unwrap().custom.is_empty());
+    }
+}
@@ -0,0 +1,728 @@
+//! Best-effort FBX metadata extraction
+//!
+//! Reads the node tree of binary (`Kaydara FBX Binary`) and ASCII FBX files
+//! and extracts geometry counts, bounds, material and texture references and
+//! animation takes. Anything that cannot be read is left empty rather than
+//! failing the whole parse.
+
+use flate2::read::ZlibDecoder;
+use schema::{AnimationInfo, BoundingBox, ThreeDMetadata};
+use std::io::Read;
+
+/// Magic header of binary FBX files
+const BINARY_MAGIC: &[u8] = b"Kaydara FBX Binary";
+
+/// FBX time units per second
+const KTIME_PER_SECOND: f64 = 46_186_158_000.0;
+
+/// Frame rate assumed when the file does not specify one
+const DEFAULT_FRAME_RATE: f64 = 30.0;
+
+/// Largest decoded array accepted, so a declared length cannot force a
+/// huge allocation or decompression
+const MAX_ARRAY_BYTES: usize = 256 * 1024 * 1024;
+
+/// Deepest node nesting accepted; real files stay in the single digits
+const MAX_NODE_DEPTH: usize = 64;
+
+/// A property value attached to an FBX node
+#[derive(Debug, Clone)]
+pub enum FbxProperty {
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+    String(String),
+    IntArray(Vec<i64>),
+    FloatArray(Vec<f64>),
+    Raw(Vec<u8>),
+}
+
+/// A node in the FBX document tree
+#[derive(Debug, Clone, Default)]
+pub struct FbxNode {
+    pub name: String,
+    pub properties: Vec<FbxProperty>,
+    pub children: Vec<FbxNode>,
+}
+
+impl FbxNode {
+    /// Find the first direct child with the given name
+    pub fn child(&self, name: &str) -> Option<&FbxNode> {
+        self.children.iter().find(|c| c.name == name)
+    }
+    
+    /// Iterate over direct children with the given name
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a FbxNode> + 'a {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+    
+    /// Get a string property by index
+    pub fn string_property(&self, index: usize) -> Option<&str> {
+        match self.properties.get(index) {
+            Some(FbxProperty::String(s)) => Some(s.as_str()),
+            _ => None,
+        }
+    }
+    
+    /// Flatten all numeric properties (scalars and arrays) into one list
+    pub fn numbers(&self) -> Vec<f64> {
+        let mut values = Vec::new();
+        for property in &self.properties {
+            match property {
+                FbxProperty::Integer(v) => values.push(*v as f64),
+                FbxProperty::Float(v) => values.push(*v),
+                FbxProperty::IntArray(a) => values.extend(a.iter().map(|v| *v as f64)),
+                FbxProperty::FloatArray(a) => values.extend(a.iter().copied()),
+                _ => {}
+            }
+        }
+        values
+    }
+}
+
+/// Parse FBX data (binary or ASCII) into 3D metadata
+pub fn parse_fbx(data: &[u8]) -> Result<ThreeDMetadata, String> {
+    let nodes = if data.starts_with(BINARY_MAGIC) {
+        parse_binary(data)?
+    } else {
+        let text = String::from_utf8_lossy(data);
+        parse_ascii(&text)?
+    };
+    
+    Ok(extract_metadata(&nodes))
+}
+
+/// Build metadata from the top-level node list
+fn extract_metadata(nodes: &[FbxNode]) -> ThreeDMetadata {
+    let objects = nodes.iter().find(|n| n.name == "Objects");
+    
+    let mut vertex_count = None;
+    let mut face_count = None;
+    let mut min_bounds = [f64::INFINITY; 3];
+    let mut max_bounds = [f64::NEG_INFINITY; 3];
+    let mut material_count = None;
+    let mut textures: Vec<String> = Vec::new();
+    
+    if let Some(objects) = objects {
+        // FBX 7 stores meshes in Geometry nodes, FBX 6 directly in Model nodes
+        for mesh in objects.children.iter().filter(|n| n.name == "Geometry" || n.name == "Model") {
+            if let Some(vertices) = mesh.child("Vertices") {
+                let positions = vertices.numbers();
+                *vertex_count.get_or_insert(0u32) += (positions.len() / 3) as u32;
+                
+                for point in positions.chunks_exact(3) {
+                    for axis in 0..3 {
+                        min_bounds[axis] = min_bounds[axis].min(point[axis]);
+                        max_bounds[axis] = max_bounds[axis].max(point[axis]);
+                    }
+                }
+            }
+            
+            if let Some(indices) = mesh.child("PolygonVertexIndex") {
+                // Negative indices mark the last vertex of each polygon
+                let polygons = indices.numbers().iter().filter(|i| **i < 0.0).count();
+                *face_count.get_or_insert(0u32) += polygons as u32;
+            }
+        }
+        
+        material_count = Some(objects.children_named("Material").count() as u32);
+        
+        for texture in objects.children.iter().filter(|n| n.name == "Texture" || n.name == "Video") {
+            let filename = texture.child("RelativeFilename")
+                .or_else(|| texture.child("FileName"))
+                .or_else(|| texture.child("Filename"))
+                .and_then(|n| n.string_property(0));
+            
+            if let Some(filename) = filename {
+                if !filename.is_empty() && !textures.iter().any(|t| t == filename) {
+                    textures.push(filename.to_string());
+                }
+            }
+        }
+    }
+    
+    let bounds = if min_bounds[0].is_finite() {
+        Some(BoundingBox {
+            min: (min_bounds[0] as f32, min_bounds[1] as f32, min_bounds[2] as f32),
+            max: (max_bounds[0] as f32, max_bounds[1] as f32, max_bounds[2] as f32),
+        })
+    } else {
+        None
+    };
+    
+    let frame_rate = frame_rate(nodes);
+    let mut animations = take_animations(nodes, frame_rate);
+    if animations.is_empty() {
+        if let Some(objects) = objects {
+            animations = stack_animations(objects, frame_rate);
+        }
+    }
+    
+    ThreeDMetadata {
+        vertex_count,
+        face_count,
+        material_count,
+        bounds,
+        animations,
+        textures,
+    }
+}
+
+/// Read animations from the `Takes` section
+fn take_animations(nodes: &[FbxNode], frame_rate: f64) -> Vec<AnimationInfo> {
+    let Some(takes) = nodes.iter().find(|n| n.name == "Takes") else {
+        return Vec::new();
+    };
+    
+    takes.children_named("Take")
+        .map(|take| {
+            let name = take.string_property(0).map(clean_name).unwrap_or_else(|| "Unnamed".to_string());
+            let duration = take.child("LocalTime")
+                .map(|t| t.numbers())
+                .filter(|times| times.len() >= 2)
+                .map(|times| ((times[1] - times[0]) / KTIME_PER_SECOND).max(0.0))
+                .unwrap_or(0.0);
+            animation_info(name, duration, frame_rate)
+        })
+        .collect()
+}
+
+/// Read animations from `AnimationStack` objects (FBX 7)
+fn stack_animations(objects: &FbxNode, frame_rate: f64) -> Vec<AnimationInfo> {
+    objects.children_named("AnimationStack")
+        .map(|stack| {
+            let name = stack.properties.iter()
+                .find_map(|p| match p {
+                    FbxProperty::String(s) if !s.is_empty() => Some(clean_name(s)),
+                    _ => None,
+                })
+                .unwrap_or_else(|| "Unnamed".to_string());
+            
+            let start = property70(stack, "LocalStart").unwrap_or(0.0);
+            let stop = property70(stack, "LocalStop").unwrap_or(start);
+            animation_info(name, ((stop - start) / KTIME_PER_SECOND).max(0.0), frame_rate)
+        })
+        .collect()
+}
+
+/// Build an animation entry from a duration in seconds
+fn animation_info(name: String, duration: f64, frame_rate: f64) -> AnimationInfo {
+    AnimationInfo {
+        name,
+        duration: duration as f32,
+        frame_count: (duration * frame_rate).round() as u32,
+    }
+}
+
+/// Read the frame rate from `GlobalSettings`, if set
+fn frame_rate(nodes: &[FbxNode]) -> f64 {
+    nodes.iter()
+        .find(|n| n.name == "GlobalSettings")
+        .and_then(|settings| property70(settings, "CustomFrameRate"))
+        .filter(|rate| *rate > 0.0)
+        .unwrap_or(DEFAULT_FRAME_RATE)
+}
+
+/// Look up a numeric value in a `Properties70` block
+fn property70(node: &FbxNode, key: &str) -> Option<f64> {
+    node.child("Properties70")?
+        .children_named("P")
+        .find(|p| p.string_property(0) == Some(key))
+        .and_then(|p| p.numbers().last().copied())
+}
+
+/// Strip class suffixes (binary) and prefixes (ASCII) from object names
+fn clean_name(raw: &str) -> String {
+    let name = raw.split("\u{0}\u{1}").next().unwrap_or(raw);
+    let name = name.rsplit("::").next().unwrap_or(name);
+    name.to_string()
+}
+
+/// Parse the node tree of a binary FBX file
+fn parse_binary(data: &[u8]) -> Result<Vec<FbxNode>, String> {
+    let mut reader = BinaryReader { data, pos: 23 };
+    let version = reader.u32()?;
+    let wide = version >= 7500;
+    
+    let mut nodes = Vec::new();
+    while reader.pos < data.len() {
+        match reader.node(wide, 0)? {
+            Some(node) => nodes.push(node),
+            None => break,
+        }
+    }
+    
+    Ok(nodes)
+}
+
+/// Cursor over binary FBX data with bounds-checked reads
+struct BinaryReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BinaryReader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| format!("Unexpected end of FBX data at offset {}", self.pos))?;
+        let slice = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+    
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+    
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+    
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+    
+    fn offset(&mut self, wide: bool) -> Result<u64, String> {
+        if wide { self.u64() } else { self.u32().map(u64::from) }
+    }
+    
+    /// Read one node record, returning `None` for the null terminator
+    fn node(&mut self, wide: bool, depth: usize) -> Result<Option<FbxNode>, String> {
+        if depth > MAX_NODE_DEPTH {
+            return Err(format!("FBX nodes nested deeper than {}", MAX_NODE_DEPTH));
+        }
+        let end_offset = self.offset(wide)? as usize;
+        let num_properties = self.offset(wide)?;
+        let _property_list_len = self.offset(wide)?;
+        let name_len = self.u8()? as usize;
+        
+        if end_offset == 0 {
+            return Ok(None);
+        }
+        if end_offset > self.data.len() || end_offset < self.pos {
+            return Err(format!("Invalid FBX node end offset {}", end_offset));
+        }
+        
+        let name = String::from_utf8_lossy(self.bytes(name_len)?).to_string();
+        
+        let mut properties = Vec::new();
+        for _ in 0..num_properties {
+            properties.push(self.property()?);
+        }
+        
+        let mut children = Vec::new();
+        while self.pos < end_offset {
+            match self.node(wide, depth + 1)? {
+                Some(child) => children.push(child),
+                None => break,
+            }
+        }
+        
+        self.pos = end_offset;
+        Ok(Some(FbxNode { name, properties, children }))
+    }
+    
+    fn property(&mut self) -> Result<FbxProperty, String> {
+        let type_code = self.u8()?;
+        let property = match type_code {
+            b'Y' => FbxProperty::Integer(i16::from_le_bytes(self.bytes(2)?.try_into().unwrap()) as i64),
+            b'C' => FbxProperty::Bool(self.u8()? != 0),
+            b'I' => FbxProperty::Integer(i32::from_le_bytes(self.bytes(4)?.try_into().unwrap()) as i64),
+            b'L' => FbxProperty::Integer(i64::from_le_bytes(self.bytes(8)?.try_into().unwrap())),
+            b'F' => FbxProperty::Float(f32::from_le_bytes(self.bytes(4)?.try_into().unwrap()) as f64),
+            b'D' => FbxProperty::Float(f64::from_le_bytes(self.bytes(8)?.try_into().unwrap())),
+            b'S' => {
+                let len = self.u32()? as usize;
+                FbxProperty::String(String::from_utf8_lossy(self.bytes(len)?).to_string())
+            }
+            b'R' => {
+                let len = self.u32()? as usize;
+                FbxProperty::Raw(self.bytes(len)?.to_vec())
+            }
+            b'f' | b'd' | b'l' | b'i' | b'b' => self.array(type_code)?,
+            other => return Err(format!("Unknown FBX property type '{}'", other as char)),
+        };
+        Ok(property)
+    }
+    
+    fn array(&mut self, type_code: u8) -> Result<FbxProperty, String> {
+        let length = self.u32()? as usize;
+        let encoding = self.u32()?;
+        let compressed_len = self.u32()? as usize;
+        let raw = self.bytes(compressed_len)?;
+        
+        let element_size = match type_code {
+            b'f' | b'i' => 4,
+            b'd' | b'l' => 8,
+            _ => 1,
+        };
+        let size = length.checked_mul(element_size)
+            .filter(|size| *size <= MAX_ARRAY_BYTES)
+            .ok_or_else(|| format!("FBX array of {} elements is too large", length))?;
+        
+        let decoded;
+        let bytes = if encoding == 1 {
+            // Never inflate past the declared size
+            let mut buffer = Vec::with_capacity(size);
+            ZlibDecoder::new(raw).take(size as u64).read_to_end(&mut buffer)
+                .map_err(|e| format!("Failed to decompress FBX array: {}", e))?;
+            decoded = buffer;
+            decoded.as_slice()
+        } else {
+            raw
+        };
+        
+        if bytes.len() < size {
+            return Err("FBX array shorter than declared length".to_string());
+        }
+        
+        let elements = bytes[..size].chunks_exact(element_size);
+        let property = match type_code {
+            b'f' => FbxProperty::FloatArray(elements.map(|c| f32::from_le_bytes(c.try_into().unwrap()) as f64).collect()),
+            b'd' => FbxProperty::FloatArray(elements.map(|c| f64::from_le_bytes(c.try_into().unwrap())).collect()),
+            b'i' => FbxProperty::IntArray(elements.map(|c| i32::from_le_bytes(c.try_into().unwrap()) as i64).collect()),
+            b'l' => FbxProperty::IntArray(elements.map(|c| i64::from_le_bytes(c.try_into().unwrap())).collect()),
+            _ => FbxProperty::IntArray(elements.map(|c| c[0] as i64).collect()),
+        };
+        Ok(property)
+    }
+}
+
+/// Token in an ASCII FBX document
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Key(String),
+    Str(String),
+    Number(f64),
+    Word(String),
+    ArrayLen(usize),
+    Open,
+    Close,
+    Comma,
+}
+
+/// Parse the node tree of an ASCII FBX file
+fn parse_ascii(text: &str) -> Result<Vec<FbxNode>, String> {
+    let tokens = tokenize_ascii(text);
+    let mut pos = 0;
+    let nodes = parse_ascii_nodes(&tokens, &mut pos, 0)?;
+    
+    if nodes.is_empty() {
+        return Err("No FBX nodes found".to_string());
+    }
+    Ok(nodes)
+}
+
+fn tokenize_ascii(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = text.chars().collect();
+    let mut i = 0;
+    
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            ';' => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '{' => { tokens.push(Token::Open); i += 1; }
+            '}' => { tokens.push(Token::Close); i += 1; }
+            ',' => { tokens.push(Token::Comma); i += 1; }
+            '"' => {
+                let start = i + 1;
+                i = start;
+                while i < chars.len() && chars[i] != '"' {
+                    i += 1;
+                }
+                tokens.push(Token::Str(chars[start..i.min(chars.len())].iter().collect()));
+                i += 1;
+            }
+            '*' => {
+                let start = i + 1;
+                i = start;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+                let len: String = chars[start..i].iter().collect();
+                tokens.push(Token::ArrayLen(len.parse().unwrap_or(0)));
+            }
+            c if c.is_whitespace() => i += 1,
+            _ => {
+                let start = i;
+                while i < chars.len() && !chars[i].is_whitespace() && !matches!(chars[i], ',' | '{' | '}' | ':' | '"' | ';') {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                
+                if i < chars.len() && chars[i] == ':' {
+                    tokens.push(Token::Key(word));
+                    i += 1;
+                } else if let Ok(number) = word.parse::<f64>() {
+                    tokens.push(Token::Number(number));
+                } else if !word.is_empty() {
+                    tokens.push(Token::Word(word));
+                } else {
+                    i += 1;
+                }
+            }
+        }
+    }
+    
+    tokens
+}
+
+fn parse_ascii_nodes(tokens: &[Token], pos: &mut usize, depth: usize) -> Result<Vec<FbxNode>, String> {
+    if depth > MAX_NODE_DEPTH {
+        return Err(format!("FBX nodes nested deeper than {}", MAX_NODE_DEPTH));
+    }
+    let mut nodes = Vec::new();
+    
+    while *pos < tokens.len() {
+        let name = match &tokens[*pos] {
+            Token::Close => {
+                *pos += 1;
+                break;
+            }
+            Token::Key(name) => name.clone(),
+            _ => {
+                // Skip stray values
+                *pos += 1;
+                continue;
+            }
+        };
+        *pos += 1;
+        
+        let mut node = FbxNode { name, ..Default::default() };
+        let mut numbers = Vec::new();
+        
+        while *pos < tokens.len() {
+            match &tokens[*pos] {
+                Token::Str(s) => node.properties.push(FbxProperty::String(s.clone())),
+                Token::Number(n) => numbers.push(*n),
+                Token::Word(w) => node.properties.push(FbxProperty::String(w.clone())),
+                Token::Comma | Token::ArrayLen(_) => {}
+                Token::Key(_) | Token::Close => break,
+                Token::Open => {
+                    *pos += 1;
+                    node.children = parse_ascii_nodes(tokens, pos, depth + 1)?;
+                    break;
+                }
+            }
+            *pos += 1;
+        }
+        
+        // FBX 7 arrays: `Vertices: *N { a: ... }`
+        if let Some(index) = node.children.iter().position(|c| c.name == "a") {
+            let array = node.children.remove(index);
+            numbers.extend(array.numbers());
+        }
+        
+        if !numbers.is_empty() {
+            node.properties.push(FbxProperty::FloatArray(numbers));
+        }
+        
+        nodes.push(node);
+    }
+    
+    Ok(nodes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const ASCII_FBX: &str = r#"; FBX 7.4.0 project file
+FBXHeaderExtension:  {
+	FBXVersion: 7400
+}
+Objects:  {
+	Geometry: 1001, "Geometry::Cube", "Mesh" {
+		Vertices: *12 {
+			a: -1,-1,0,1,-1,0,1,1,0,-1,1,2
+		}
+		PolygonVertexIndex: *4 {
+			a: 0,1,2,-4
+		}
+	}
+	Material: 2001, "Material::Red", "" {
+	}
+	Texture: 3001, "Texture::Diffuse", "" {
+		FileName: "C:/textures/diffuse.png"
+		RelativeFilename: "textures/diffuse.png"
+	}
+}
+Takes:  {
+	Current: "Take 001"
+	Take: "Take 001" {
+		LocalTime: 0,92372316000
+	}
+}
+"#;
+    
+    #[test]
+    fn test_ascii_fbx() {
+        let metadata = parse_fbx(ASCII_FBX.as_bytes()).unwrap();
+        
+        assert_eq!(metadata.vertex_count, Some(4));
+        assert_eq!(metadata.face_count, Some(1));
+        assert_eq!(metadata.material_count, Some(1));
+        assert_eq!(metadata.textures, vec!["textures/diffuse.png".to_string()]);
+        
+        let bounds = metadata.bounds.unwrap();
+        assert_eq!(bounds.min, (-1.0, -1.0, 0.0));
+        assert_eq!(bounds.max, (1.0, 1.0, 2.0));
+        
+        assert_eq!(metadata.animations.len(), 1);
+        assert_eq!(metadata.animations[0].name, "Take 001");
+        assert!((metadata.animations[0].duration - 2.0).abs() < 1e-6);
+        assert_eq!(metadata.animations[0].frame_count, 60);
+    }
+    
+    /// Encode a node as a binary FBX 7.4 record starting at `start`
+    fn encode(node: &FbxNode, start: usize) -> Vec<u8> {
+        let properties: Vec<u8> = node.properties.iter().flat_map(encode_property).collect();
+        
+        let mut child_bytes = Vec::new();
+        let children_start = start + 13 + node.name.len() + properties.len();
+        for child in &node.children {
+            let encoded = encode(child, children_start + child_bytes.len());
+            child_bytes.extend(encoded);
+        }
+        if !node.children.is_empty() {
+            child_bytes.extend_from_slice(&[0u8; 13]);
+        }
+        
+        let end = children_start + child_bytes.len();
+        let mut record = Vec::new();
+        record.extend_from_slice(&(end as u32).to_le_bytes());
+        record.extend_from_slice(&(node.properties.len() as u32).to_le_bytes());
+        record.extend_from_slice(&(properties.len() as u32).to_le_bytes());
+        record.push(node.name.len() as u8);
+        record.extend_from_slice(node.name.as_bytes());
+        record.extend(properties);
+        record.extend(child_bytes);
+        record
+    }
+    
+    fn encode_property(property: &FbxProperty) -> Vec<u8> {
+        let mut out = Vec::new();
+        match property {
+            FbxProperty::String(s) => {
+                out.push(b'S');
+                out.extend_from_slice(&(s.len() as u32).to_le_bytes());
+                out.extend_from_slice(s.as_bytes());
+            }
+            FbxProperty::Integer(v) => {
+                out.push(b'L');
+                out.extend_from_slice(&v.to_le_bytes());
+            }
+            FbxProperty::Float(v) => {
+                out.push(b'D');
+                out.extend_from_slice(&v.to_le_bytes());
+            }
+            FbxProperty::Bool(v) => {
+                out.push(b'C');
+                out.push(*v as u8);
+            }
+            FbxProperty::Raw(bytes) => {
+                out.push(b'R');
+                out.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+                out.extend_from_slice(bytes);
+            }
+            FbxProperty::FloatArray(values) => {
+                out.push(b'd');
+                out.extend_from_slice(&(values.len() as u32).to_le_bytes());
+                out.extend_from_slice(&0u32.to_le_bytes());
+                out.extend_from_slice(&((values.len() * 8) as u32).to_le_bytes());
+                for v in values {
+                    out.extend_from_slice(&v.to_le_bytes());
+                }
+            }
+            FbxProperty::IntArray(values) => {
+                out.push(b'i');
+                out.extend_from_slice(&(values.len() as u32).to_le_bytes());
+                out.extend_from_slice(&0u32.to_le_bytes());
+                out.extend_from_slice(&((values.len() * 4) as u32).to_le_bytes());
+                for v in values {
+                    out.extend_from_slice(&(*v as i32).to_le_bytes());
+                }
+            }
+        }
+        out
+    }
+    
+    /// Binary FBX 7.4 file holding `nodes`
+    fn binary_file(nodes: &[FbxNode]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(b"Kaydara FBX Binary  \x00\x1a\x00");
+        data.extend_from_slice(&7400u32.to_le_bytes());
+        for node in nodes {
+            let start = data.len();
+            data.extend(encode(node, start));
+        }
+        data.extend_from_slice(&[0u8; 13]);
+        data
+    }
+    
+    #[test]
+    fn test_binary_fbx() {
+        let geometry = FbxNode {
+            name: "Geometry".to_string(),
+            properties: vec![
+                FbxProperty::Integer(1001),
+                FbxProperty::String("Cube\u{0}\u{1}Geometry".to_string()),
+            ],
+            children: vec![
+                FbxNode {
+                    name: "Vertices".to_string(),
+                    properties: vec![FbxProperty::FloatArray(vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0])],
+                    children: vec![],
+                },
+                FbxNode {
+                    name: "PolygonVertexIndex".to_string(),
+                    properties: vec![FbxProperty::IntArray(vec![0, 1, -3])],
+                    children: vec![],
+                },
+            ],
+        };
+        let objects = FbxNode {
+            name: "Objects".to_string(),
+            properties: vec![],
+            children: vec![geometry],
+        };
+        let settings = FbxNode {
+            name: "Creator".to_string(),
+            properties: vec![FbxProperty::Float(30.0), FbxProperty::Bool(true), FbxProperty::Raw(vec![1, 2, 3])],
+            children: vec![],
+        };
+        
+        let metadata = parse_fbx(&binary_file(&[objects, settings])).unwrap();
+        assert_eq!(metadata.vertex_count, Some(3));
+        assert_eq!(metadata.face_count, Some(1));
+        assert_eq!(metadata.material_count, Some(0));
+    }
+    
+    #[test]
+    fn test_binary_fbx_limits() {
+        // An array declaring more elements than any file would hold
+        let mut data = binary_file(&[FbxNode {
+            name: "Vertices".to_string(),
+            properties: vec![FbxProperty::FloatArray(vec![0.0; 3])],
+            children: vec![],
+        }]);
+        let length_at = 27 + 13 + "Vertices".len() + 1;
+        data[length_at..length_at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(parse_fbx(&data).unwrap_err().contains("too large"));
+        
+        let mut nested = FbxNode { name: "Leaf".to_string(), ..Default::default() };
+        for _ in 0..=MAX_NODE_DEPTH {
+            nested = FbxNode { name: "Node".to_string(), properties: vec![], children: vec![nested] };
+        }
+        assert!(parse_fbx(&binary_file(&[nested])).unwrap_err().contains("nested"));
+        
+        let deep_ascii = format!("{}{}", "Node: {\n".repeat(MAX_NODE_DEPTH + 2), "}\n".repeat(MAX_NODE_DEPTH + 2));
+        assert!(parse_fbx(deep_ascii.as_bytes()).unwrap_err().contains("nested"));
+    }
+}
@@ -0,0 +1,108 @@
+//! Extensions grouped by how they are handled
+//!
+//! The metadata parsers, preview generators and integrity checks route on
+//! these groups, and the capability table is derived from them, so adding
+//! an extension to a group changes all of them together. Extensions are
+//! lowercase and without the leading dot.
+
+/// Raster images the image crate decodes
+pub const DECODABLE_IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "bmp", "tiff", "tif", "tga", "webp"];
+
+/// TIFF images, which may hold further pages
+pub const TIFF_EXTENSIONS: &[&str] = &["tiff", "tif"];
+
+/// High dynamic range images, decoded to linear floating point
+pub const HDR_IMAGE_EXTENSIONS: &[&str] = &["exr", "hdr"];
+
+/// Photoshop documents; dimensions come from the header
+pub const PSD_EXTENSIONS: &[&str] = &["psd", "psb"];
+
+/// Vector images, rasterized for their preview
+pub const SVG_EXTENSIONS: &[&str] = &["svg"];
+
+/// TIFF-based camera RAW formats, previewed from their embedded JPEG
+pub const RAW_EXTENSIONS: &[&str] = &["cr2", "nef", "arw", "dng"];
+
+/// Image formats ingested without a decoder
+///
+/// The image crate is built without HEIF and AVIF decoders, so these get a
+/// placeholder preview and no pixel-based metadata or AI tagging.
+pub const UNDECODABLE_IMAGE_EXTENSIONS: &[&str] = &["heic", "heif", "avif"];
+
+/// glTF scenes, parsed and rendered
+pub const GLTF_EXTENSIONS: &[&str] = &["gltf", "glb"];
+
+/// Plain meshes, parsed and rendered
+pub const MESH_EXTENSIONS: &[&str] = &["obj", "stl"];
+
+/// Blender files; only the header is parsed
+pub const BLEND_EXTENSIONS: &[&str] = &["blend"];
+
+/// FBX scenes; parsed but not rendered
+pub const FBX_EXTENSIONS: &[&str] = &["fbx"];
+
+/// Audio decoded for metadata and waveform previews
+pub const AUDIO_EXTENSIONS: &[&str] = &["wav", "mp3", "flac", "ogg", "aac", "m4a"];
+
+/// Text documents whose content is extracted
+pub const TEXT_EXTENSIONS: &[&str] = &["txt", "md", "csv", "tsv", "srt", "vtt", "log"];
+
+/// Archives whose entries are listed
+pub const ARCHIVE_EXTENSIONS: &[&str] = &["zip", "tar", "gz"];
+
+/// Groups with a format-specific metadata parser
+pub const METADATA_PARSER_GROUPS: &[&[&str]] = &[
+    DECODABLE_IMAGE_EXTENSIONS,
+    HDR_IMAGE_EXTENSIONS,
+    PSD_EXTENSIONS,
+    SVG_EXTENSIONS,
+    GLTF_EXTENSIONS,
+    MESH_EXTENSIONS,
+    BLEND_EXTENSIONS,
+    FBX_EXTENSIONS,
+    AUDIO_EXTENSIONS,
+    TEXT_EXTENSIONS,
+    ARCHIVE_EXTENSIONS,
+];
+
+/// Groups rendered to a real preview rather than a placeholder
+pub const PREVIEW_GROUPS: &[&[&str]] = &[
+    DECODABLE_IMAGE_EXTENSIONS,
+    HDR_IMAGE_EXTENSIONS,
+    SVG_EXTENSIONS,
+    RAW_EXTENSIONS,
+    GLTF_EXTENSIONS,
+    MESH_EXTENSIONS,
+    AUDIO_EXTENSIONS,
+];
+
+/// Whether an extension belongs to a group, ignoring case and a leading dot
+pub fn is_in(group: &[&str], extension: &str) -> bool {
+    let extension = extension.trim_start_matches('.');
+    group.iter().any(|member| member.eq_ignore_ascii_case(extension))
+}
+
+/// Whether an extension belongs to any of the groups
+pub fn is_in_any(groups: &[&[&str]], extension: &str) -> bool {
+    groups.iter().any(|group| is_in(group, extension))
+}
+
+/// Whether an image extension has no decoder
+pub fn is_undecodable_image(extension: &str) -> bool {
+    is_in(UNDECODABLE_IMAGE_EXTENSIONS, extension)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_groups() {
+        assert!(is_in(TIFF_EXTENSIONS, ".TIF"));
+        assert!(is_in(DECODABLE_IMAGE_EXTENSIONS, "tif"));
+        assert!(is_undecodable_image("HEIC"));
+        assert!(!is_undecodable_image("png"));
+        assert!(is_in_any(PREVIEW_GROUPS, "dng"));
+        assert!(!is_in_any(METADATA_PARSER_GROUPS, "dng"));
+    }
+}
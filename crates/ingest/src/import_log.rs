@@ -0,0 +1,187 @@
+//! Append-only log of import events
+//!
+//! Records what was imported, when, from where and with which result, so a
+//! library can be audited and reconciled against an external source of
+//! truth. This is provenance of the import events themselves, separate
+//! from version history. Entries are JSON lines appended to one file and
+//! are never rewritten.
+
+use chrono::{DateTime, Utc};
+use schema::DamResult;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tracing::warn;
+use uuid::Uuid;
+
+/// Default location for the import log
+///
+/// In the platform data directory next to the previews, e.g.
+/// `~/.local/share/dam/imports.jsonl` on Linux.
+pub fn default_import_log_path() -> PathBuf {
+    dirs::data_dir()
+        .map(|dir| dir.join("dam").join("imports.jsonl"))
+        .unwrap_or_else(|| PathBuf::from("imports.jsonl"))
+}
+
+/// Ingest call that produced an entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportOperation {
+    /// `ingest_file`, including imports triggered by the folder monitor
+    File,
+    /// `ingest_batch`
+    Batch,
+    /// `ingest_directory`
+    Directory,
+}
+
+/// What happened to an imported file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum ImportOutcome {
+    /// A new asset was created
+    Imported,
+    /// The file was deliberately not imported
+    Skipped { reason: String },
+    /// The content already exists in the library as another asset
+    Duplicate { existing: Uuid },
+    /// Ingestion failed
+    Failed { reason: String },
+}
+
+/// One import event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportLogEntry {
+    pub timestamp: DateTime<Utc>,
+    pub operation: ImportOperation,
+    pub source_path: PathBuf,
+    /// Asset created by the import, if any
+    pub asset_id: Option<Uuid>,
+    /// Detected file extension, if detection got that far
+    pub format: Option<String>,
+    #[serde(flatten)]
+    pub outcome: ImportOutcome,
+}
+
+impl ImportLogEntry {
+    /// Entry stamped with the current time
+    pub fn new(operation: ImportOperation, source_path: PathBuf, outcome: ImportOutcome) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            operation,
+            source_path,
+            asset_id: None,
+            format: None,
+            outcome,
+        }
+    }
+
+    /// Record the asset and format the import produced
+    pub fn with_asset(mut self, asset_id: Uuid, format: impl Into<String>) -> Self {
+        self.asset_id = Some(asset_id);
+        self.format = Some(format.into());
+        self
+    }
+}
+
+/// Import log stored as a JSON-lines file
+#[derive(Debug)]
+pub struct ImportLog {
+    path: PathBuf,
+    /// Serializes appends from concurrent imports
+    write_lock: Mutex<()>,
+}
+
+impl ImportLog {
+    /// Use the log at `path`; the file is created on the first entry
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    /// Location of the log file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append an entry
+    pub async fn append(&self, entry: &ImportLogEntry) -> DamResult<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+
+        let _guard = self.write_lock.lock().await;
+        if let Some(parent) = self.path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path).await?;
+        file.write_all(&line).await?;
+        file.flush().await?;
+        Ok(())
+    }
+
+    /// Entries recorded at or after `since`, oldest first
+    ///
+    /// Unreadable lines, e.g. a line cut off by a crash, are skipped with a
+    /// warning. A log that does not exist yet has no entries.
+    pub async fn entries_since(&self, since: DateTime<Utc>) -> DamResult<Vec<ImportLogEntry>> {
+        let contents = match tokio::fs::read_to_string(&self.path).await {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .filter_map(|(number, line)| match serde_json::from_str::<ImportLogEntry>(line) {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    warn!("Skipping unreadable line {} of import log {}: {}", number + 1, self.path.display(), e);
+                    None
+                }
+            })
+            .filter(|entry| entry.timestamp >= since)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_append_and_query() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = ImportLog::new(dir.path().join("logs").join("imports.jsonl"));
+        assert!(log.entries_since(DateTime::<Utc>::MIN_UTC).await.unwrap().is_empty());
+
+        let asset_id = Uuid::new_v4();
+        let mut old = ImportLogEntry::new(ImportOperation::File, "/in/old.png".into(), ImportOutcome::Imported);
+        old.timestamp = Utc::now() - chrono::Duration::days(2);
+        log.append(&old).await.unwrap();
+        log.append(&ImportLogEntry::new(ImportOperation::Directory, "/in/a.png".into(), ImportOutcome::Imported)
+            .with_asset(asset_id, "png")).await.unwrap();
+        log.append(&ImportLogEntry::new(
+            ImportOperation::Directory,
+            "/in/b.png".into(),
+            ImportOutcome::Duplicate { existing: asset_id },
+        )).await.unwrap();
+
+        // A torn final line does not hide the entries before it
+        let mut file = OpenOptions::new().append(true).open(log.path()).await.unwrap();
+        file.write_all(b"{\"timestamp\":").await.unwrap();
+
+        let recent = log.entries_since(Utc::now() - chrono::Duration::days(1)).await.unwrap();
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].asset_id, Some(asset_id));
+        assert_eq!(recent[0].format.as_deref(), Some("png"));
+        assert_eq!(recent[1].outcome, ImportOutcome::Duplicate { existing: asset_id });
+        assert_eq!(log.entries_since(DateTime::<Utc>::MIN_UTC).await.unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,435 @@
+//! Detection of truncated and corrupt media
+//!
+//! Partially downloaded or damaged files pass format detection, which only
+//! looks at the header, and then fail later during preview or AI processing.
+//! These checks look past the header: end-of-file markers for JPEG/PNG/GIF,
+//! a full decode for other images, and structural size checks for RIFF,
+//! MP4/MOV and ZIP containers. Containers only have their box headers and
+//! trailers read, and images are streamed through the decoder under
+//! allocation limits, but decoding every image is still expensive, so
+//! ingest only runs them when asked to.
+//!
+//! PNG and JPEG also have a lightweight structural check, which preview
+//! generation always runs before decoding: the decoders may hand back a
+//! partial image for damaged data, which would become a broken thumbnail.
+//! It verifies every PNG chunk's CRC while streaming the file, and walks a
+//! JPEG's markers from SOI to the first EOI. Data after that EOI, such as
+//! the video of a motion photo, is not part of the image and is ignored.
+
+use crate::formats::{self, DECODABLE_IMAGE_EXTENSIONS};
+use schema::IntegrityStatus;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Cursor, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Signature every PNG starts with
+const PNG_SIGNATURE: &[u8] = &[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+/// JPEG start-of-image marker
+const JPEG_SOI: &[u8] = &[0xFF, 0xD8];
+
+/// JPEG end-of-image marker
+const JPEG_EOI: &[u8] = &[0xFF, 0xD9];
+
+/// Signature of the ZIP end-of-central-directory record
+const ZIP_EOCD: &[u8] = &[0x50, 0x4B, 0x05, 0x06];
+
+/// The EOCD record sits within this many bytes of the end (22 + max comment)
+const ZIP_EOCD_SEARCH: usize = 22 + u16::MAX as usize;
+
+/// Most memory a check decode may allocate; larger images stay `Unchecked`
+const MAX_DECODE_ALLOC: u64 = 512 * 1024 * 1024;
+
+/// Check a file's integrity based on its detected extension
+///
+/// A file that cannot be read comes back as `Unreadable` rather than
+/// failing, so the asset is flagged and the import goes on.
+pub async fn check_file<P: AsRef<Path>>(path: P, extension: &str) -> IntegrityStatus {
+    let path = path.as_ref().to_path_buf();
+    let extension = extension.to_lowercase();
+    let checked = tokio::task::spawn_blocking(move || {
+        let file = File::open(&path)?;
+        let length = file.metadata()?.len();
+        check_reader(BufReader::new(file), length, &extension)
+    })
+    .await
+    .unwrap_or_else(|e| Err(io::Error::other(e)));
+    checked.unwrap_or_else(|e| unreadable(&e))
+}
+
+/// Check the integrity of file content
+///
+/// Formats without a check come back as `Unchecked`.
+pub fn check_bytes(data: &[u8], extension: &str) -> IntegrityStatus {
+    check_reader(Cursor::new(data), data.len() as u64, extension).unwrap_or_else(|e| unreadable(&e))
+}
+
+/// Check content of `length` bytes, reading only what the format's check needs
+fn check_reader<R: BufRead + Seek>(mut reader: R, length: u64, extension: &str) -> io::Result<IntegrityStatus> {
+    let structure = match extension {
+        "jpg" | "jpeg" => Some(check_jpeg(&mut reader)),
+        "png" => Some(check_png(&mut reader)),
+        "gif" => Some(check_trailer(&mut reader, length, &[0x3B], "GIF is missing its trailer")?),
+        "wav" | "avi" | "webp" => check_riff(&mut reader, length)?,
+        "mp4" | "m4a" | "m4v" | "mov" | "3gp" => Some(check_mp4(&mut reader, length)?),
+        "zip" | "docx" | "xlsx" | "pptx" | "kra" => Some(check_zip(&mut reader, length)?),
+        _ => None,
+    };
+
+    match structure {
+        Some(IntegrityStatus::Intact) | None if formats::is_in(DECODABLE_IMAGE_EXTENSIONS, &extension) => {
+            reader.rewind()?;
+            check_decode(reader)
+        }
+        Some(status) => Ok(status),
+        None => Ok(IntegrityStatus::Unchecked),
+    }
+}
+
+fn truncated(reason: &str) -> IntegrityStatus {
+    IntegrityStatus::Truncated { reason: reason.to_string() }
+}
+
+fn corrupt(reason: impl Into<String>) -> IntegrityStatus {
+    IntegrityStatus::Corrupt { reason: reason.into() }
+}
+
+fn unreadable(error: &io::Error) -> IntegrityStatus {
+    IntegrityStatus::Unreadable { reason: error.to_string() }
+}
+
+/// Structural check of a PNG or JPEG file, without decoding it
+///
+/// Other extensions, and files that cannot be read, come back as
+/// `Unchecked`.
+pub fn check_image_file(path: &Path, extension: &str) -> IntegrityStatus {
+    let checked = match extension.to_lowercase().as_str() {
+        "png" => File::open(path).map(|file| check_png(BufReader::new(file))),
+        "jpg" | "jpeg" => File::open(path).map(|file| check_jpeg(BufReader::new(file))),
+        _ => return IntegrityStatus::Unchecked,
+    };
+    checked.unwrap_or(IntegrityStatus::Unchecked)
+}
+
+/// `check_image_file` off the async runtime
+pub async fn check_image_structure<P: AsRef<Path>>(path: P, extension: &str) -> IntegrityStatus {
+    let path = path.as_ref().to_path_buf();
+    let extension = extension.to_string();
+    tokio::task::spawn_blocking(move || check_image_file(&path, &extension))
+        .await
+        .unwrap_or(IntegrityStatus::Unchecked)
+}
+
+/// Walk the PNG chunks up to IEND, verifying each CRC
+///
+/// Chunk data is streamed through the checksum, so memory use does not
+/// depend on the image size.
+fn check_png(mut reader: impl Read) -> IntegrityStatus {
+    let mut signature = [0u8; 8];
+    if reader.read_exact(&mut signature).is_err() || signature != PNG_SIGNATURE {
+        return corrupt("PNG signature is missing");
+    }
+
+    let mut buffer = [0u8; 8192];
+    loop {
+        let mut header = [0u8; 8];
+        if reader.read_exact(&mut header).is_err() {
+            return truncated("PNG is missing its IEND chunk");
+        }
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as u64;
+        let kind = String::from_utf8_lossy(&header[4..8]).into_owned();
+
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(&header[4..8]);
+        let mut remaining = length;
+        while remaining > 0 {
+            let chunk = remaining.min(buffer.len() as u64) as usize;
+            if reader.read_exact(&mut buffer[..chunk]).is_err() {
+                return truncated(&format!("PNG {} chunk is cut off", kind));
+            }
+            hasher.update(&buffer[..chunk]);
+            remaining -= chunk as u64;
+        }
+
+        let mut crc = [0u8; 4];
+        if reader.read_exact(&mut crc).is_err() {
+            return truncated(&format!("PNG {} chunk is cut off", kind));
+        }
+        if hasher.finalize() != u32::from_be_bytes(crc) {
+            return corrupt(format!("PNG {} chunk fails its CRC check", kind));
+        }
+        if kind == "IEND" {
+            return IntegrityStatus::Intact;
+        }
+    }
+}
+
+/// Walk the JPEG markers from SOI up to the first EOI
+///
+/// Segments are skipped by their declared length, so thumbnails inside
+/// EXIF data do not end the walk early, and entropy-coded scan data is
+/// scanned for the next marker. Nothing after the EOI is read.
+fn check_jpeg(mut reader: impl Read) -> IntegrityStatus {
+    let mut soi = [0u8; 2];
+    if reader.read_exact(&mut soi).is_err() || soi != JPEG_SOI {
+        return corrupt("JPEG is missing its start-of-image marker");
+    }
+
+    let missing_eoi = || truncated("JPEG is missing its end-of-image marker");
+    loop {
+        // Scan data may precede the next marker
+        match read_byte(&mut reader) {
+            Some(0xFF) => {}
+            Some(_) => continue,
+            None => return missing_eoi(),
+        }
+        let mut marker = 0xFF;
+        while marker == 0xFF {
+            let Some(byte) = read_byte(&mut reader) else { return missing_eoi() };
+            marker = byte;
+        }
+
+        match marker {
+            // Stuffed zero, restart and TEM markers carry no length
+            0x00 | 0x01 | 0xD0..=0xD7 => {}
+            0xD9 => return IntegrityStatus::Intact,
+            _ => {
+                let mut length = [0u8; 2];
+                if reader.read_exact(&mut length).is_err() {
+                    return missing_eoi();
+                }
+                let length = u16::from_be_bytes(length);
+                if length < 2 {
+                    return corrupt(format!("JPEG segment {:#04X} has invalid length {}", marker, length));
+                }
+                let payload = u64::from(length - 2);
+                let skipped = std::io::copy(&mut reader.by_ref().take(payload), &mut std::io::sink());
+                if skipped.ok() != Some(payload) {
+                    return missing_eoi();
+                }
+            }
+        }
+    }
+}
+
+fn read_byte(reader: &mut impl Read) -> Option<u8> {
+    let mut byte = [0u8; 1];
+    reader.read_exact(&mut byte).ok().map(|_| byte[0])
+}
+
+/// The last bytes must be `trailer`
+fn check_trailer(reader: &mut (impl Read + Seek), length: u64, trailer: &[u8], reason: &str) -> io::Result<IntegrityStatus> {
+    let tail = read_at(reader, length.saturating_sub(trailer.len() as u64), trailer.len())?;
+    Ok(if tail.ends_with(trailer) { IntegrityStatus::Intact } else { truncated(reason) })
+}
+
+/// The RIFF header records the size of everything after it
+fn check_riff(reader: &mut (impl Read + Seek), length: u64) -> io::Result<Option<IntegrityStatus>> {
+    let header = read_at(reader, 0, 8)?;
+    if !header.starts_with(b"RIFF") || header.len() < 8 {
+        return Ok(None);
+    }
+    let declared = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as u64;
+    Ok(Some(if length >= declared + 8 {
+        IntegrityStatus::Intact
+    } else {
+        truncated("RIFF data ends before its declared size")
+    }))
+}
+
+/// Top-level MP4/MOV boxes must tile the file and include the `moov` index
+///
+/// Only the box headers are read; the walk seeks over box contents.
+fn check_mp4(reader: &mut (impl Read + Seek), length: u64) -> io::Result<IntegrityStatus> {
+    let mut pos = 0u64;
+    let mut has_moov = false;
+
+    while pos < length {
+        let header = read_at(reader, pos, 16)?;
+        if header.len() < 8 {
+            return Ok(truncated("MP4 box header is cut off"));
+        }
+        let mut size = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as u64;
+        let kind = &header[4..8];
+        if size == 1 {
+            let Some(large) = header.get(8..16) else {
+                return Ok(truncated("MP4 box header is cut off"));
+            };
+            size = u64::from_be_bytes(large.try_into().expect("eight bytes"));
+        } else if size == 0 {
+            // Box extends to the end of the file
+            size = length - pos;
+        }
+        if size < 8 {
+            return Ok(corrupt(format!("MP4 box has invalid size {}", size)));
+        }
+
+        has_moov |= kind == b"moov";
+        let end = pos.saturating_add(size);
+        if end > length {
+            return Ok(truncated("MP4 box extends past the end of the file"));
+        }
+        pos = end;
+    }
+
+    Ok(if has_moov { IntegrityStatus::Intact } else { truncated("MP4 has no moov box") })
+}
+
+/// ZIP archives keep their directory at the end, so a cut-off file loses it
+fn check_zip(reader: &mut (impl Read + Seek), length: u64) -> io::Result<IntegrityStatus> {
+    let tail = read_at(reader, length.saturating_sub(ZIP_EOCD_SEARCH as u64), ZIP_EOCD_SEARCH)?;
+    Ok(if tail.windows(ZIP_EOCD.len()).any(|window| window == ZIP_EOCD) {
+        IntegrityStatus::Intact
+    } else {
+        truncated("ZIP is missing its central directory")
+    })
+}
+
+/// Up to `len` bytes starting at `offset`; fewer at the end of the data
+fn read_at(reader: &mut (impl Read + Seek), offset: u64, len: usize) -> io::Result<Vec<u8>> {
+    reader.seek(SeekFrom::Start(offset))?;
+    let mut bytes = Vec::with_capacity(len);
+    reader.take(len as u64).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Decode the whole image; any decoder error marks it corrupt
+///
+/// Images too large for `MAX_DECODE_ALLOC`, or in a format the decoder was
+/// built without, stay `Unchecked`.
+fn check_decode(reader: impl BufRead + Seek) -> io::Result<IntegrityStatus> {
+    let mut decoder = image::io::Reader::new(reader).with_guessed_format()?;
+    let mut limits = image::io::Limits::default();
+    limits.max_alloc = Some(MAX_DECODE_ALLOC);
+    decoder.limits(limits);
+
+    Ok(match decoder.decode() {
+        Ok(_) => IntegrityStatus::Intact,
+        Err(image::ImageError::Limits(_) | image::ImageError::Unsupported(_)) => IntegrityStatus::Unchecked,
+        Err(image::ImageError::IoError(e)) => unreadable(&e),
+        Err(e) => corrupt(e.to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn encode(format: image::ImageFormat) -> Vec<u8> {
+        let img = image::DynamicImage::ImageRgb8(image::RgbImage::new(8, 8));
+        let mut data = Vec::new();
+        img.write_to(&mut Cursor::new(&mut data), format).unwrap();
+        data
+    }
+
+    #[test]
+    fn test_images() {
+        for (format, extension) in [(image::ImageFormat::Png, "png"), (image::ImageFormat::Jpeg, "jpg")] {
+            let data = encode(format);
+            assert_eq!(check_bytes(&data, extension), IntegrityStatus::Intact);
+
+            let cut = &data[..data.len() / 2];
+            assert!(matches!(check_bytes(cut, extension), IntegrityStatus::Truncated { .. }));
+        }
+
+        // Right trailer, damaged content
+        let mut png = encode(image::ImageFormat::Png);
+        png[16] ^= 0xFF;
+        assert!(matches!(check_bytes(&png, "png"), IntegrityStatus::Corrupt { .. }));
+    }
+
+    #[test]
+    fn test_image_structure() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, data: &[u8]| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, data).unwrap();
+            path
+        };
+
+        let png = encode(image::ImageFormat::Png);
+        assert_eq!(check_image_file(&write("ok.png", &png), "png"), IntegrityStatus::Intact);
+
+        // A flipped byte inside IDAT fails that chunk's CRC
+        let idat = png.windows(4).position(|window| window == b"IDAT").unwrap();
+        let mut damaged = png.clone();
+        damaged[idat + 6] ^= 0xFF;
+        match check_image_file(&write("bad.png", &damaged), "png") {
+            IntegrityStatus::Corrupt { reason } => assert!(reason.contains("IDAT")),
+            other => panic!("expected a CRC failure, got {:?}", other),
+        }
+        let cut = write("cut.png", &png[..png.len() - 20]);
+        assert!(matches!(check_image_file(&cut, "png"), IntegrityStatus::Truncated { .. }));
+
+        let mut jpeg = encode(image::ImageFormat::Jpeg);
+        jpeg.extend([0; 16]);
+        assert_eq!(check_image_file(&write("ok.jpg", &jpeg), "JPG"), IntegrityStatus::Intact);
+        let cut = write("cut.jpg", &jpeg[..jpeg.len() / 2]);
+        assert!(matches!(check_image_file(&cut, "jpg"), IntegrityStatus::Truncated { .. }));
+        let headless = write("headless.jpg", &jpeg[2..]);
+        assert!(matches!(check_image_file(&headless, "jpg"), IntegrityStatus::Corrupt { .. }));
+
+        // Motion photos append their video after the image's EOI
+        let mut motion = encode(image::ImageFormat::Jpeg);
+        motion.extend(b"\0\0\0\x18ftypmp42");
+        motion.extend([0xFF, 0xD8, 0x12, 0x34]);
+        assert_eq!(check_image_file(&write("motion.jpg", &motion), "jpg"), IntegrityStatus::Intact);
+        assert_eq!(check_bytes(&motion, "jpg"), IntegrityStatus::Intact);
+
+        assert_eq!(check_image_file(&write("a.gif", b"GIF89a"), "gif"), IntegrityStatus::Unchecked);
+        assert_eq!(check_image_file(&dir.path().join("missing.png"), "png"), IntegrityStatus::Unchecked);
+    }
+
+    #[test]
+    fn test_containers() {
+        let mut mp4 = Vec::new();
+        for (kind, size) in [(b"ftyp", 16u32), (b"moov", 12)] {
+            mp4.extend(size.to_be_bytes());
+            mp4.extend(kind);
+            mp4.resize(mp4.len() + size as usize - 8, 0);
+        }
+        assert_eq!(check_bytes(&mp4, "mp4"), IntegrityStatus::Intact);
+        assert!(matches!(check_bytes(&mp4[..20], "mp4"), IntegrityStatus::Truncated { .. }));
+
+        let mut wav = b"RIFF".to_vec();
+        wav.extend(12u32.to_le_bytes());
+        wav.extend(b"WAVEdata");
+        assert!(matches!(check_bytes(&wav, "wav"), IntegrityStatus::Truncated { .. }));
+        wav.extend([0; 4]);
+        assert_eq!(check_bytes(&wav, "wav"), IntegrityStatus::Intact);
+
+        let mut zip = b"PK\x03\x04 local entries".to_vec();
+        assert!(matches!(check_bytes(&zip, "zip"), IntegrityStatus::Truncated { .. }));
+        zip.extend(ZIP_EOCD);
+        zip.extend([0; 18]);
+        assert_eq!(check_bytes(&zip, "zip"), IntegrityStatus::Intact);
+
+        assert_eq!(check_bytes(b"solid 3d", "stl"), IntegrityStatus::Unchecked);
+    }
+
+    #[tokio::test]
+    async fn test_check_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("clip.mp4");
+        let mut mp4 = Vec::new();
+        for (kind, size) in [(b"ftyp", 16u32), (b"mdat", 4096), (b"moov", 12)] {
+            mp4.extend(size.to_be_bytes());
+            mp4.extend(kind);
+            mp4.resize(mp4.len() + size as usize - 8, 0);
+        }
+        std::fs::write(&path, &mp4).unwrap();
+        assert_eq!(check_file(&path, "MP4").await, IntegrityStatus::Intact);
+
+        std::fs::write(&path, &mp4[..100]).unwrap();
+        assert!(matches!(check_file(&path, "mp4").await, IntegrityStatus::Truncated { .. }));
+
+        let png = dir.path().join("image.png");
+        std::fs::write(&png, encode(image::ImageFormat::Png)).unwrap();
+        assert_eq!(check_file(&png, "png").await, IntegrityStatus::Intact);
+
+        let missing = check_file(dir.path().join("missing.png"), "png").await;
+        assert!(matches!(missing, IntegrityStatus::Unreadable { .. }));
+        assert!(missing.is_suspect());
+    }
+}
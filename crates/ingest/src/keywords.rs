@@ -0,0 +1,294 @@
+//! Embedded keyword extraction
+//!
+//! Reads the keywords that photo tools like Lightroom embed in images: the
+//! XMP `dc:subject` list (JPEG APP1) and IPTC `Keywords` (dataset 2:25 in
+//! the Photoshop APP13 block). Only segment headers are walked; no image
+//! data is decoded.
+
+/// Signature of an XMP packet in a JPEG APP1 segment
+const XMP_SIGNATURE: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+
+/// Signature of Photoshop image resources in a JPEG APP13 segment
+const PHOTOSHOP_SIGNATURE: &[u8] = b"Photoshop 3.0\0";
+
+/// Image resource ID of the IPTC-NAA record
+const IPTC_RESOURCE_ID: u16 = 0x0404;
+
+/// IPTC application record and its keywords dataset
+const IPTC_APPLICATION_RECORD: u8 = 2;
+const IPTC_KEYWORDS_DATASET: u8 = 25;
+
+/// Collect the embedded keywords of an image
+///
+/// JPEG files are read segment by segment and the XMP and IPTC keyword
+/// sets are unioned. For other formats a raw XMP packet is searched for.
+pub fn read_embedded_keywords(data: &[u8]) -> Vec<String> {
+    let mut keywords = Vec::new();
+    
+    if data.starts_with(&[0xFF, 0xD8]) {
+        for (marker, payload) in jpeg_segments(data) {
+            match marker {
+                0xE1 if payload.starts_with(XMP_SIGNATURE) => {
+                    let xmp = String::from_utf8_lossy(&payload[XMP_SIGNATURE.len()..]);
+                    keywords.extend(parse_xmp_subjects(&xmp));
+                }
+                0xED if payload.starts_with(PHOTOSHOP_SIGNATURE) => {
+                    keywords.extend(parse_photoshop_iptc(&payload[PHOTOSHOP_SIGNATURE.len()..]));
+                }
+                _ => {}
+            }
+        }
+    } else if let Some(xmp) = find_xmp_packet(data) {
+        keywords.extend(parse_xmp_subjects(&String::from_utf8_lossy(xmp)));
+    }
+    
+    merge_keywords(Vec::new(), keywords)
+}
+
+/// Union two keyword lists, keeping first-seen order and ignoring case
+pub fn merge_keywords(mut existing: Vec<String>, additional: Vec<String>) -> Vec<String> {
+    let mut seen: std::collections::HashSet<String> = existing.iter().map(|k| k.to_lowercase()).collect();
+    for keyword in additional {
+        let keyword = keyword.trim();
+        if !keyword.is_empty() && seen.insert(keyword.to_lowercase()) {
+            existing.push(keyword.to_string());
+        }
+    }
+    existing
+}
+
+/// Iterate over `(marker, payload)` of the JPEG segments before the image data
+fn jpeg_segments(data: &[u8]) -> impl Iterator<Item = (u8, &[u8])> {
+    let mut pos = 2;
+    std::iter::from_fn(move || {
+        loop {
+            if data.get(pos) != Some(&0xFF) {
+                return None;
+            }
+            let marker = *data.get(pos + 1)?;
+            match marker {
+                // Fill byte
+                0xFF => pos += 1,
+                // Markers without a length
+                0x01 | 0xD0..=0xD7 => pos += 2,
+                // Start of scan or end of image: no more metadata
+                0xDA | 0xD9 => return None,
+                _ => {
+                    let length = u16::from_be_bytes([*data.get(pos + 2)?, *data.get(pos + 3)?]) as usize;
+                    let payload = data.get(pos + 4..pos + 2 + length.max(2))?;
+                    pos += 2 + length;
+                    return Some((marker, payload));
+                }
+            }
+        }
+    })
+}
+
+/// Find a raw `<x:xmpmeta>` packet anywhere in the data
+pub(crate) fn find_xmp_packet(data: &[u8]) -> Option<&[u8]> {
+    let start = find(data, b"<x:xmpmeta")?;
+    let end = find(&data[start..], b"</x:xmpmeta>")? + start;
+    Some(&data[start..end])
+}
+
+/// Extract the `rdf:li` items of the `dc:subject` property
+pub(crate) fn parse_xmp_subjects(xmp: &str) -> Vec<String> {
+    let Some(start) = xmp.find("<dc:subject") else {
+        return Vec::new();
+    };
+    let subject = &xmp[start..];
+    let subject = &subject[..subject.find("</dc:subject>").unwrap_or(subject.len())];
+    
+    let mut subjects = Vec::new();
+    let mut rest = subject;
+    while let Some(item_start) = rest.find("<rdf:li") {
+        rest = &rest[item_start..];
+        let Some(content_start) = rest.find('>') else {
+            break;
+        };
+        let Some(content_end) = rest.find("</rdf:li>") else {
+            break;
+        };
+        if content_start < content_end {
+            subjects.push(unescape_xml(&rest[content_start + 1..content_end]));
+        }
+        rest = &rest[content_end..];
+    }
+    subjects
+}
+
+/// Find the IPTC record among Photoshop image resources and read its keywords
+fn parse_photoshop_iptc(mut resources: &[u8]) -> Vec<String> {
+    let mut keywords = Vec::new();
+    
+    while resources.len() >= 12 && resources.starts_with(b"8BIM") {
+        let id = u16::from_be_bytes([resources[4], resources[5]]);
+        
+        // Pascal-string name, padded so length byte + name is even
+        let name_length = resources[6] as usize;
+        let name_end = 7 + name_length + (name_length + 1) % 2;
+        let Some(size_bytes) = resources.get(name_end..name_end + 4) else {
+            break;
+        };
+        let size = u32::from_be_bytes([size_bytes[0], size_bytes[1], size_bytes[2], size_bytes[3]]) as usize;
+        let data_start = name_end + 4;
+        let Some(data) = resources.get(data_start..data_start + size) else {
+            break;
+        };
+        
+        if id == IPTC_RESOURCE_ID {
+            keywords.extend(parse_iptc_keywords(data));
+        }
+        
+        let next = data_start + size + size % 2;
+        resources = resources.get(next..).unwrap_or_default();
+    }
+    
+    keywords
+}
+
+/// Read all 2:25 Keywords datasets from an IPTC-NAA record
+fn parse_iptc_keywords(mut data: &[u8]) -> Vec<String> {
+    let mut keywords = Vec::new();
+    
+    while data.len() >= 5 && data[0] == 0x1C {
+        let (record, dataset) = (data[1], data[2]);
+        let length = u16::from_be_bytes([data[3], data[4]]) as usize;
+        
+        // Extended datasets (length with the high bit set) are not used for keywords
+        if length & 0x8000 != 0 {
+            break;
+        }
+        let Some(value) = data.get(5..5 + length) else {
+            break;
+        };
+        
+        if record == IPTC_APPLICATION_RECORD && dataset == IPTC_KEYWORDS_DATASET {
+            keywords.push(decode_iptc_string(value));
+        }
+        data = &data[5 + length..];
+    }
+    
+    keywords
+}
+
+/// Decode an IPTC string as UTF-8, falling back to Latin-1
+fn decode_iptc_string(value: &[u8]) -> String {
+    match std::str::from_utf8(value) {
+        Ok(text) => text.to_string(),
+        Err(_) => value.iter().map(|&byte| byte as char).collect(),
+    }
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.trim()
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// Build a JPEG APP segment with the given marker and payload
+    fn app_segment(marker: u8, payload: &[u8]) -> Vec<u8> {
+        let mut segment = vec![0xFF, marker];
+        segment.extend(((payload.len() + 2) as u16).to_be_bytes());
+        segment.extend(payload);
+        segment
+    }
+    
+    fn xmp_segment(subjects: &[&str]) -> Vec<u8> {
+        let items: String = subjects.iter().map(|s| format!("<rdf:li>{}</rdf:li>", s)).collect();
+        let xmp = format!(
+            "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"><rdf:RDF><rdf:Description>\
+             <dc:subject><rdf:Bag>{}</rdf:Bag></dc:subject>\
+             </rdf:Description></rdf:RDF></x:xmpmeta>",
+            items
+        );
+        app_segment(0xE1, &[XMP_SIGNATURE, xmp.as_bytes()].concat())
+    }
+    
+    fn iptc_segment(keywords: &[&[u8]]) -> Vec<u8> {
+        let mut iptc = vec![0x1C, 0x01, 0x5A, 0x00, 0x03, 0x1B, 0x25, 0x47];
+        for keyword in keywords {
+            iptc.extend([0x1C, IPTC_APPLICATION_RECORD, IPTC_KEYWORDS_DATASET]);
+            iptc.extend((keyword.len() as u16).to_be_bytes());
+            iptc.extend(*keyword);
+        }
+        
+        let mut payload = PHOTOSHOP_SIGNATURE.to_vec();
+        payload.extend(b"8BIM");
+        payload.extend(IPTC_RESOURCE_ID.to_be_bytes());
+        payload.extend([0, 0]);
+        payload.extend((iptc.len() as u32).to_be_bytes());
+        payload.extend(&iptc);
+        if iptc.len() % 2 == 1 {
+            payload.push(0);
+        }
+        app_segment(0xED, &payload)
+    }
+    
+    #[test]
+    fn test_jpeg_keywords_are_unioned() {
+        let mut jpeg = vec![0xFF, 0xD8];
+        jpeg.extend(app_segment(0xE0, b"JFIF\0\x01\x02"));
+        jpeg.extend(xmp_segment(&["Beach", "Sunset &amp; Sea"]));
+        jpeg.extend(iptc_segment(&[b"sunset & sea", b"Caf\xe9", b"Holiday"]));
+        jpeg.extend([0xFF, 0xDA, 0x00, 0x02, 0xFF, 0xD9]);
+        
+        let keywords = read_embedded_keywords(&jpeg);
+        assert_eq!(keywords, vec!["Beach", "Sunset & Sea", "Café", "Holiday"]);
+    }
+    
+    #[test]
+    fn test_raw_xmp_packet() {
+        let mut png = b"\x89PNG\r\n\x1a\n....iTXtXML:com.adobe.xmp\0\0\0\0\0".to_vec();
+        png.extend(&xmp_segment(&["studio"])[4 + XMP_SIGNATURE.len()..]);
+        assert_eq!(read_embedded_keywords(&png), vec!["studio"]);
+    }
+    
+    #[test]
+    fn test_malformed_segments() {
+        assert!(read_embedded_keywords(&[0xFF, 0xD8, 0xFF, 0xE1, 0xFF, 0xFF]).is_empty());
+        assert!(read_embedded_keywords(&[]).is_empty());
+        
+        // Truncated IPTC dataset
+        let mut jpeg = vec![0xFF, 0xD8];
+        let mut segment = iptc_segment(&[b"complete"]);
+        segment.truncate(segment.len() - 3);
+        let length = (segment.len() - 2) as u16;
+        segment[2..4].copy_from_slice(&length.to_be_bytes());
+        jpeg.extend(segment);
+        assert!(read_embedded_keywords(&jpeg).is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_keywords_in_image_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tagged.jpg");
+        
+        let mut encoded = Vec::new();
+        image::DynamicImage::new_rgb8(8, 8)
+            .write_to(&mut std::io::Cursor::new(&mut encoded), image::ImageOutputFormat::Jpeg(80))
+            .unwrap();
+        let mut jpeg = encoded[..2].to_vec();
+        jpeg.extend(xmp_segment(&["portrait", "Studio"]));
+        jpeg.extend(iptc_segment(&[b"studio", b"client-x"]));
+        jpeg.extend(&encoded[2..]);
+        std::fs::write(&path, jpeg).unwrap();
+        
+        let parser = crate::AssetParser::new().unwrap();
+        let mut asset = schema::Asset::new(path, schema::AssetType::Image);
+        asset.file_size = std::fs::metadata(&asset.current_path).unwrap().len();
+        let metadata = parser.parse_metadata(&asset).await.unwrap();
+        assert_eq!(metadata.image.unwrap().keywords, vec!["portrait", "Studio", "client-x"]);
+    }
+}
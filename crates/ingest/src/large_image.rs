@@ -0,0 +1,238 @@
+//! Thumbnails of very large images
+//!
+//! `image::open` decodes the whole image, so a 100-megapixel panorama needs
+//! hundreds of megabytes before it can be shrunk to a thumbnail. Images
+//! above a pixel threshold take this path instead: JPEGs are decoded at a
+//! reduced scale by the decoder's DCT scaling, PNG rows are streamed into a
+//! box-filtered downscale, and interlaced PNGs only decode their first
+//! Adam7 pass, which already is a 1/8 scale image. The full-resolution
+//! buffer is never held. Dimensions come from the file header without
+//! decoding.
+
+use crate::error::IngestError;
+use image::codecs::jpeg::JpegDecoder;
+use image::{DynamicImage, ImageBuffer, ImageDecoder, ImageFormat};
+use schema::DamResult;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// Pixel count above which previews use the downscaling decoders
+pub const DEFAULT_LARGE_IMAGE_PIXELS: u64 = 40_000_000;
+
+/// Width and height from the image header, without decoding pixels
+pub fn header_dimensions(path: &Path) -> DamResult<(u32, u32)> {
+    image::io::Reader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|e| decode_error(path, e))?
+        .into_dimensions()
+        .map_err(|e| decode_error(path, e).into())
+}
+
+/// Decode an image reduced to fit within `max_size`, never upscaling
+///
+/// Returns None for formats without a reduced decode, which callers have
+/// to decode fully.
+pub fn load_downscaled(path: &Path, max_size: (u32, u32)) -> DamResult<Option<DynamicImage>> {
+    let format = image::io::Reader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|e| decode_error(path, e))?
+        .format();
+
+    match format {
+        Some(ImageFormat::Jpeg) => load_jpeg_scaled(path, max_size).map(Some),
+        Some(ImageFormat::Png) => load_png_streamed(path, max_size).map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// Decode a JPEG at the smallest DCT scale (1/2, 1/4 or 1/8) that still
+/// covers `max_size`
+fn load_jpeg_scaled(path: &Path, max_size: (u32, u32)) -> DamResult<DynamicImage> {
+    let file = BufReader::new(File::open(path)?);
+    let mut decoder = JpegDecoder::new(file).map_err(|e| decode_error(path, e))?;
+    let (width, height) = fit_within(decoder.dimensions(), max_size);
+    decoder.scale(to_u16(width), to_u16(height)).map_err(|e| decode_error(path, e))?;
+    let image = DynamicImage::from_decoder(decoder).map_err(|e| decode_error(path, e))?;
+
+    // DCT scaling stops at 1/8, so very large JPEGs may still need shrinking
+    let (width, height) = fit_within((image.width(), image.height()), max_size);
+    if (width, height) == (image.width(), image.height()) {
+        Ok(image)
+    } else {
+        Ok(image.resize_exact(width, height, image::imageops::FilterType::Triangle))
+    }
+}
+
+/// Stream PNG rows into a downscaled image
+///
+/// Output is expanded to 8-bit gray, gray-alpha, RGB or RGBA. Interlaced
+/// images stop after the first Adam7 pass, which holds every 8th pixel of
+/// every 8th row.
+fn load_png_streamed(path: &Path, max_size: (u32, u32)) -> DamResult<DynamicImage> {
+    let mut decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info().map_err(|e| decode_error(path, e))?;
+
+    let (width, height) = (reader.info().width, reader.info().height);
+    let interlaced = reader.info().interlaced;
+    let (color_type, _) = reader.output_color_type();
+    let channels = color_type.samples();
+
+    // The first Adam7 pass starts at pixel 0 of every 8th row and column
+    let source = if interlaced {
+        (width.div_ceil(8), height.div_ceil(8))
+    } else {
+        (width, height)
+    };
+    let mut downscaler = Downscaler::new(source, fit_within(source, max_size), channels);
+
+    // Rows arrive in pass order, so the first pass is exactly the first rows
+    for _ in 0..source.1 {
+        let Some(row) = reader.next_row().map_err(|e| decode_error(path, e))? else {
+            break;
+        };
+        downscaler.push_row(row.data());
+    }
+
+    downscaler.finish().ok_or_else(|| IngestError::preview_generation_failed(
+        path.to_path_buf(),
+        format!("Unexpected layout for {:?} PNG", color_type),
+    ).into())
+}
+
+/// Size of `size` scaled down to fit within `max_size`, keeping the aspect ratio
+fn fit_within((width, height): (u32, u32), (max_width, max_height): (u32, u32)) -> (u32, u32) {
+    let scale = (max_width as f64 / width.max(1) as f64)
+        .min(max_height as f64 / height.max(1) as f64)
+        .min(1.0);
+    (
+        ((width as f64 * scale).round() as u32).max(1),
+        ((height as f64 * scale).round() as u32).max(1),
+    )
+}
+
+fn to_u16(value: u32) -> u16 {
+    value.min(u16::MAX as u32) as u16
+}
+
+/// Box-filter downscale fed one 8-bit source row at a time
+///
+/// Each target pixel is the mean of the source pixels mapping onto it, so
+/// only one row of running sums is kept besides the output.
+struct Downscaler {
+    source_height: u64,
+    target: (u32, u32),
+    channels: usize,
+    /// Target column of each source column
+    columns: Vec<usize>,
+    sums: Vec<u64>,
+    counts: Vec<u64>,
+    output: Vec<u8>,
+    rows_seen: u64,
+    current_row: u64,
+}
+
+impl Downscaler {
+    fn new((width, height): (u32, u32), target: (u32, u32), channels: usize) -> Self {
+        let (target_width, target_height) = target;
+        Self {
+            source_height: height.max(1) as u64,
+            target,
+            channels,
+            columns: (0..width as u64)
+                .map(|x| (x * target_width as u64 / width.max(1) as u64) as usize)
+                .collect(),
+            sums: vec![0; target_width as usize * channels],
+            counts: vec![0; target_width as usize],
+            output: Vec::with_capacity(target_width as usize * target_height as usize * channels),
+            rows_seen: 0,
+            current_row: 0,
+        }
+    }
+
+    fn push_row(&mut self, row: &[u8]) {
+        let target_row = self.rows_seen * self.target.1 as u64 / self.source_height;
+        if target_row != self.current_row {
+            self.flush_row();
+            self.current_row = target_row;
+        }
+        self.rows_seen += 1;
+
+        for (pixel, column) in row.chunks_exact(self.channels).zip(&self.columns) {
+            self.counts[*column] += 1;
+            for (channel, value) in pixel.iter().enumerate() {
+                self.sums[column * self.channels + channel] += *value as u64;
+            }
+        }
+    }
+
+    /// Append the averages of the current target row and reset the sums
+    fn flush_row(&mut self) {
+        for (column, count) in self.counts.iter_mut().enumerate() {
+            for sum in &mut self.sums[column * self.channels..(column + 1) * self.channels] {
+                self.output.push((*sum / (*count).max(1)) as u8);
+                *sum = 0;
+            }
+            *count = 0;
+        }
+    }
+
+    /// The downscaled image; None if the rows did not match the layout
+    fn finish(mut self) -> Option<DynamicImage> {
+        self.flush_row();
+        let (width, height) = self.target;
+        match self.channels {
+            1 => ImageBuffer::from_raw(width, height, self.output).map(DynamicImage::ImageLuma8),
+            2 => ImageBuffer::from_raw(width, height, self.output).map(DynamicImage::ImageLumaA8),
+            3 => ImageBuffer::from_raw(width, height, self.output).map(DynamicImage::ImageRgb8),
+            4 => ImageBuffer::from_raw(width, height, self.output).map(DynamicImage::ImageRgba8),
+            _ => None,
+        }
+    }
+}
+
+fn decode_error(path: &Path, e: impl std::fmt::Display) -> IngestError {
+    IngestError::preview_generation_failed(path.to_path_buf(), format!("Failed to decode large image: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::GenericImageView;
+
+    fn two_tone(width: u32, height: u32) -> image::RgbImage {
+        image::RgbImage::from_fn(width, height, |x, _| {
+            if x < width / 2 { image::Rgb([255, 0, 0]) } else { image::Rgb([0, 0, 255]) }
+        })
+    }
+
+    #[test]
+    fn test_downscaled_decodes() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = two_tone(400, 200);
+
+        let png = dir.path().join("panorama.png");
+        source.save(&png).unwrap();
+        assert_eq!(header_dimensions(&png).unwrap(), (400, 200));
+
+        let thumbnail = load_downscaled(&png, (100, 100)).unwrap().unwrap().to_rgb8();
+        assert_eq!(thumbnail.dimensions(), (100, 50));
+        assert_eq!(thumbnail.get_pixel(10, 25), &image::Rgb([255, 0, 0]));
+        assert_eq!(thumbnail.get_pixel(90, 25), &image::Rgb([0, 0, 255]));
+
+        // JPEG scaling stops at a DCT scale at least as large as requested
+        let jpeg = dir.path().join("panorama.jpg");
+        source.save(&jpeg).unwrap();
+        let thumbnail = load_downscaled(&jpeg, (100, 100)).unwrap().unwrap();
+        assert_eq!(thumbnail.dimensions(), (100, 50));
+
+        // Images are never upscaled
+        let small = load_downscaled(&png, (1000, 1000)).unwrap().unwrap();
+        assert_eq!(small.dimensions(), (400, 200));
+
+        let bmp = dir.path().join("panorama.bmp");
+        source.save(&bmp).unwrap();
+        assert!(load_downscaled(&bmp, (100, 100)).unwrap().is_none());
+    }
+}
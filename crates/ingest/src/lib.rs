@@ -7,29 +7,83 @@
 //! - File system monitoring for automatic import
 
 pub mod detector;
+pub mod formats;
 pub mod parser;
 pub mod preview;
 pub mod monitor;
 pub mod error;
+pub mod fbx;
+pub mod svg;
+pub mod mesh;
+pub mod policy;
+pub mod keywords;
+pub mod capture_date;
+pub mod extractor;
+pub mod sidecar;
+pub mod paths;
+pub mod plan;
+pub mod waveform;
+pub mod video;
+pub mod embedded;
+pub mod integrity;
+pub mod capabilities;
+pub mod import_log;
+pub mod scan;
+pub mod sequence;
+pub mod large_image;
+pub mod type_overrides;
+pub mod tiff;
+pub mod encoding;
+pub mod tag_rules;
+pub mod report;
 
-use schema::{Asset, AssetType, DamResult};
-use std::path::Path;
+use schema::{Asset, AssetType, DamResult, FileFormat, NotificationLevel, PreviewInfo, UiEvents};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use tokio::fs;
 use tracing::{info, warn, error};
 use uuid::Uuid;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 
 pub use detector::*;
-pub use parser::AssetParser;
+pub use parser::{AssetParser, ExtractionCaps, DEFAULT_EXTRACTION_CAP};
+pub use extractor::MetadataExtractor;
 pub use preview::*;
 pub use monitor::*;
 pub use error::*;
+pub use policy::*;
+pub use paths::*;
+pub use plan::*;
+pub use capabilities::{FormatCapabilities, FormatCapability};
+pub use import_log::{default_import_log_path, ImportLog, ImportLogEntry, ImportOperation, ImportOutcome};
+pub use scan::{default_scan_state_dir, FileStamp, ScanReport, ScanState};
+pub use sequence::{FrameSequence, SequenceDetection};
+pub use type_overrides::AssetTypeOverrides;
+pub use tag_rules::{FilenameTagRule, FilenameTagRules, RuleTarget};
+pub use report::{AccessError, AccessErrorKind, BatchIngestReport, FileFailure};
+
+/// Files ingested concurrently when importing a directory
+const DIRECTORY_BATCH_SIZE: usize = 10;
 
 /// Main ingestion service
+#[derive(Clone)]
 pub struct IngestService {
     detector: FormatDetector,
     parser: AssetParser,
     preview_generator: PreviewGenerator,
+    symlink_policy: SymlinkPolicy,
+    follow_symlinks: bool,
+    hidden_files: HiddenFilePolicy,
+    integrity_check: bool,
+    import_log: Option<Arc<ImportLog>>,
+    scan_state_dir: PathBuf,
+    sequence_detection: Option<SequenceDetection>,
+    type_overrides: AssetTypeOverrides,
+    tag_rules: FilenameTagRules,
+    mode: IngestMode,
+    events: UiEvents,
 }
 
 impl IngestService {
@@ -39,12 +93,289 @@ impl IngestService {
             detector: FormatDetector::new()?,
             parser: AssetParser::new()?,
             preview_generator: PreviewGenerator::new()?,
+            symlink_policy: SymlinkPolicy::default(),
+            follow_symlinks: false,
+            hidden_files: HiddenFilePolicy::default(),
+            integrity_check: false,
+            import_log: None,
+            scan_state_dir: default_scan_state_dir(),
+            sequence_detection: None,
+            type_overrides: AssetTypeOverrides::new(),
+            tag_rules: FilenameTagRules::new(),
+            mode: IngestMode::default(),
+            events: UiEvents::new(),
         })
     }
     
+    /// Choose whether symlinks in asset paths are resolved
+    pub fn with_symlink_policy(mut self, policy: SymlinkPolicy) -> Self {
+        self.symlink_policy = policy;
+        self
+    }
+    
+    /// Symlink policy used for canonical paths
+    pub fn symlink_policy(&self) -> SymlinkPolicy {
+        self.symlink_policy
+    }
+    
+    /// Choose whether directory imports walk into symlinked folders
+    /// 
+    /// Off by default: symlinked files are imported but symlinked folders
+    /// are not entered, so a link to a folder elsewhere cannot pull in a
+    /// whole other tree. When on, links are followed and a link back to a
+    /// folder already walked (a cycle, or a second link to the same folder)
+    /// is skipped. Either way a file reached through several links is
+    /// imported once. The monitor applies the same rule to watch events.
+    pub fn with_follow_symlinks(mut self, follow: bool) -> Self {
+        self.follow_symlinks = follow;
+        self
+    }
+    
+    /// Whether directory imports walk into symlinked folders
+    pub fn follows_symlinks(&self) -> bool {
+        self.follow_symlinks
+    }
+    
+    /// Choose which hidden (dot-prefixed) files and folders are ingested
+    pub fn with_hidden_files(mut self, policy: HiddenFilePolicy) -> Self {
+        self.hidden_files = policy;
+        self
+    }
+    
+    pub fn set_hidden_files(&mut self, policy: HiddenFilePolicy) {
+        self.hidden_files = policy;
+    }
+    
+    /// Treat files with `extension` as `asset_type`, overriding the built-in type
+    pub fn with_type_override(mut self, extension: &str, asset_type: AssetType) -> Self {
+        self.type_overrides.insert(extension, asset_type);
+        self
+    }
+    
+    /// Replace all extension to asset type overrides
+    pub fn with_type_overrides(mut self, overrides: AssetTypeOverrides) -> Self {
+        self.type_overrides = overrides;
+        self
+    }
+    
+    pub fn set_type_overrides(&mut self, overrides: AssetTypeOverrides) {
+        self.type_overrides = overrides;
+    }
+    
+    pub fn type_overrides(&self) -> &AssetTypeOverrides {
+        &self.type_overrides
+    }
+    
+    /// Choose between full and fast (preview-only) ingest
+    pub fn with_mode(mut self, mode: IngestMode) -> Self {
+        self.mode = mode;
+        self
+    }
+    
+    pub fn set_mode(&mut self, mode: IngestMode) {
+        self.mode = mode;
+    }
+    
+    pub fn mode(&self) -> IngestMode {
+        self.mode
+    }
+    
+    /// Add a rule deriving tags from file names, after the existing rules
+    pub fn with_tag_rule(mut self, rule: FilenameTagRule) -> Self {
+        self.tag_rules.push(rule);
+        self
+    }
+    
+    /// Replace all filename tag rules
+    pub fn with_tag_rules(mut self, rules: FilenameTagRules) -> Self {
+        self.tag_rules = rules;
+        self
+    }
+    
+    pub fn set_tag_rules(&mut self, rules: FilenameTagRules) {
+        self.tag_rules = rules;
+    }
+    
+    /// Rules deriving tags from file names during ingest
+    pub fn tag_rules(&self) -> &FilenameTagRules {
+        &self.tag_rules
+    }
+    
+    /// Asset type files with an extension are ingested as
+    pub fn asset_type_for(&self, extension: &str) -> AssetType {
+        self.type_overrides.asset_type(extension)
+    }
+    
+    /// Canonical absolute form of a path, as stored on assets
+    pub fn canonical_path<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        canonicalize_path(path, self.symlink_policy)
+    }
+    
+    /// Check every ingested file for truncation or corruption
+    /// 
+    /// Reads and, for images, fully decodes each file, so it is off by
+    /// default. Problems are recorded on `Asset::integrity`; ingest itself
+    /// still succeeds.
+    pub fn with_integrity_check(mut self, enabled: bool) -> Self {
+        self.integrity_check = enabled;
+        self
+    }
+    
+    /// What can be done with each supported file extension
+    pub fn capabilities(&self) -> FormatCapabilities {
+        FormatCapabilities::new()
+    }
+    
+    /// Report directory import progress on a shared event channel
+    pub fn with_events(mut self, events: UiEvents) -> Self {
+        self.events = events;
+        self
+    }
+    
+    /// Channel on which import progress and notifications are emitted
+    pub fn events(&self) -> &UiEvents {
+        &self.events
+    }
+    
+    /// Make previews of images above `pixels` from a reduced decode
+    /// 
+    /// Defaults to `large_image::DEFAULT_LARGE_IMAGE_PIXELS`.
+    pub fn with_large_image_threshold(mut self, pixels: u64) -> Self {
+        self.preview_generator.set_large_image_threshold(pixels);
+        self
+    }
+    
+    /// Choose what transparent areas of previews are shown over
+    pub fn with_preview_background(mut self, background: PreviewBackground) -> Self {
+        self.preview_generator.set_background(background);
+        self
+    }
+    
+    /// Preview videos with contact sheets of up to `(rows, columns)` frames
+    /// 
+    /// None, the default, gives videos a placeholder preview. Contact sheets
+    /// need ffmpeg on the `PATH`; without it videos fall back to the placeholder.
+    pub fn with_video_contact_sheet(mut self, grid: Option<(u32, u32)>) -> Self {
+        self.preview_generator.set_contact_sheet(grid);
+        self
+    }
+    
+    pub fn set_video_contact_sheet(&mut self, grid: Option<(u32, u32)>) {
+        self.preview_generator.set_contact_sheet(grid);
+    }
+    
+    /// Use per-asset-type caps for content extraction
+    pub fn with_extraction_caps(mut self, caps: ExtractionCaps) -> Self {
+        self.parser.set_extraction_caps(caps);
+        self
+    }
+    
+    /// Add a custom metadata extractor to the parser
+    pub fn with_extractor(mut self, extractor: Arc<dyn MetadataExtractor>) -> Self {
+        self.parser.register_extractor(extractor);
+        self
+    }
+    
+    /// Register a custom metadata extractor on the parser
+    pub fn register_extractor(&mut self, extractor: Arc<dyn MetadataExtractor>) {
+        self.parser.register_extractor(extractor);
+    }
+    
+    /// Record every import in an append-only log
+    /// 
+    /// Share the log between services importing into the same library.
+    pub fn with_import_log(mut self, log: Arc<ImportLog>) -> Self {
+        self.import_log = Some(log);
+        self
+    }
+    
+    /// Keep the progress of resumable directory scans in `dir`
+    pub fn with_scan_state_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.scan_state_dir = dir.as_ref().to_path_buf();
+        self
+    }
+    
+    /// Group numbered image frames into one asset per sequence
+    /// 
+    /// Applies to `ingest_directory` only and is off by default; frames
+    /// that do not form a sequence are ingested as single files.
+    pub fn with_sequence_detection(mut self, detection: SequenceDetection) -> Self {
+        self.sequence_detection = Some(detection);
+        self
+    }
+    
+    /// Import events recorded at or after `since`, oldest first
+    /// 
+    /// Empty if no import log is configured.
+    pub async fn import_log(&self, since: DateTime<Utc>) -> DamResult<Vec<ImportLogEntry>> {
+        match &self.import_log {
+            Some(log) => log.entries_since(since).await,
+            None => Ok(Vec::new()),
+        }
+    }
+    
+    /// Add an entry to the import log, if one is configured
+    /// 
+    /// Ingest logs skipped and failed files itself; callers record outcomes
+    /// only they know about, such as whether an asset was stored. A failed
+    /// write is logged but does not fail the import.
+    pub async fn record_import(&self, entry: ImportLogEntry) {
+        if let Some(log) = &self.import_log {
+            if let Err(e) = log.append(&entry).await {
+                warn!("Failed to write import log {}: {}", log.path().display(), e);
+            }
+        }
+    }
+    
+    /// Log whether an ingested asset made it into the library
+    /// 
+    /// An ingested asset is not imported until the caller has stored it,
+    /// so callers report the outcome of storing each asset here.
+    pub async fn record_stored<E: std::fmt::Display>(&self, operation: ImportOperation, asset: &Asset, stored: &Result<(), E>) {
+        if self.import_log.is_none() {
+            return;
+        }
+        let outcome = match stored {
+            Ok(()) => ImportOutcome::Imported,
+            Err(e) => ImportOutcome::Failed { reason: e.to_string() },
+        };
+        let entry = ImportLogEntry::new(operation, asset.original_path.clone(), outcome)
+            .with_asset(asset.id, asset.format.extension.clone());
+        self.record_import(entry).await;
+    }
+    
+    /// Log files that were deliberately not ingested
+    async fn record_skipped(&self, operation: ImportOperation, paths: impl IntoIterator<Item = (PathBuf, String)>) {
+        if self.import_log.is_none() {
+            return;
+        }
+        for (path, reason) in paths {
+            self.record_import(ImportLogEntry::new(operation, path, ImportOutcome::Skipped { reason })).await;
+        }
+    }
+    
     /// Ingest a single file
+    /// 
+    /// The asset stores the canonical absolute path, so the same file
+    /// reached through different relative paths yields the same path.
+    /// Failures are recorded in the import log; report the outcome of
+    /// storing the asset with `record_stored`.
     pub async fn ingest_file<P: AsRef<Path>>(&self, path: P) -> DamResult<Asset> {
-        let path = path.as_ref();
+        self.ingest_logged(path.as_ref(), ImportOperation::File).await
+    }
+    
+    /// Ingest a file, recording a failure in the import log
+    async fn ingest_logged(&self, path: &Path, operation: ImportOperation) -> DamResult<Asset> {
+        let result = self.ingest_one(path).await;
+        if let Err(e) = &result {
+            let outcome = ImportOutcome::Failed { reason: e.to_string() };
+            self.record_import(ImportLogEntry::new(operation, self.canonical_path(path), outcome)).await;
+        }
+        result
+    }
+    
+    async fn ingest_one(&self, path: &Path) -> DamResult<Asset> {
+        let path = &self.canonical_path(path);
         info!("Ingesting file: {}", path.display());
         
         // Check if file exists and is readable
@@ -66,7 +397,7 @@ impl IngestService {
         let modified = metadata.modified()?;
         
         // Detect file format
-        let format_info = self.detector.detect_format(path).await?;
+        let format_info = self.detect_format(path).await?;
         info!("Detected format: {} for {}", format_info.extension, path.display());
         
         if !format_info.supported {
@@ -74,7 +405,7 @@ impl IngestService {
         }
         
         // Determine asset type
-        let asset_type = AssetType::from_extension(&format_info.extension);
+        let asset_type = self.asset_type_for(&format_info.extension);
         
         // Create base asset
         let mut asset = Asset::new(path.to_path_buf(), asset_type);
@@ -82,15 +413,15 @@ impl IngestService {
         asset.format = format_info;
         asset.modified_at = modified.into();
         
-        // Parse file-specific metadata
-        match self.parser.parse_metadata(&asset).await {
-            Ok(metadata) => {
-                asset.metadata = metadata;
-                info!("Extracted metadata for {}", path.display());
-            }
-            Err(e) => {
-                warn!("Failed to extract metadata for {}: {}", path.display(), e);
-            }
+        match self.mode {
+            IngestMode::Full => self.read_details(&mut asset).await?,
+            IngestMode::Fast => asset.needs_deep_processing = true,
+        }
+        
+        // Naming conventions become tags without any model
+        let rule_tags = self.tag_rules.tags_for(path);
+        if !rule_tags.is_empty() {
+            asset.tags = keywords::merge_keywords(std::mem::take(&mut asset.tags), rule_tags);
         }
         
         // Generate preview/thumbnail
@@ -108,14 +439,199 @@ impl IngestService {
         Ok(asset)
     }
     
+    /// Finish an asset from a fast import
+    /// 
+    /// Runs what `IngestMode::Fast` skipped: the integrity check, metadata
+    /// parsing and sidecars. The format is detected again, since an asset
+    /// rebuilt from its index document only knows its extension. ID, paths,
+    /// tags and preview are kept and `needs_deep_processing` is cleared.
+    pub async fn complete_asset(&self, asset: &Asset) -> DamResult<Asset> {
+        let path = &asset.current_path;
+        info!("Completing metadata of {}", path.display());
+        if !path.is_file() {
+            return Err(IngestError::file_not_found(path.clone()).into());
+        }
+        
+        let mut completed = asset.clone();
+        completed.format = self.detect_format(path).await?;
+        self.read_details(&mut completed).await?;
+        completed.needs_deep_processing = false;
+        Ok(completed)
+    }
+    
+    /// Integrity check, metadata and sidecars: the slow part of ingest
+    async fn read_details(&self, asset: &mut Asset) -> DamResult<()> {
+        let path = asset.current_path.clone();
+        let path = path.as_path();
+        if self.integrity_check {
+            asset.integrity = integrity::check_file(path, &asset.format.extension).await;
+        } else if asset.asset_type == AssetType::Image {
+            // The cheap structural check still flags damaged PNGs and JPEGs,
+            // which then get no preview rather than a broken one
+            asset.integrity = integrity::check_image_structure(path, &asset.format.extension).await;
+        }
+        if asset.integrity.is_suspect() {
+            warn!("Integrity check failed for {}: {:?}", path.display(), asset.integrity);
+        }
+        
+        // Parse file-specific metadata
+        match self.parser.parse_metadata(asset).await {
+            Ok(metadata) => {
+                asset.metadata = metadata;
+                info!("Extracted metadata for {}", path.display());
+                
+                // Embedded XMP/IPTC keywords arrive as regular tags
+                if let Some(image) = &asset.metadata.image {
+                    asset.tags = keywords::merge_keywords(std::mem::take(&mut asset.tags), image.keywords.clone());
+                }
+            }
+            Err(e) => {
+                warn!("Failed to extract metadata for {}: {}", path.display(), e);
+            }
+        }
+        
+        // Sidecar files next to the asset override embedded metadata
+        self.refresh_sidecars(asset).await?;
+        Ok(())
+    }
+    
+    /// Detect a file's format, treating overridden extensions as supported
+    async fn detect_format(&self, path: &Path) -> DamResult<FileFormat> {
+        let mut format = self.detector.detect_format(path).await?;
+        if self.type_overrides.recognizes(&format.extension) {
+            format.supported = true;
+        }
+        Ok(format)
+    }
+    
+    /// Format a current detector finds for an asset, if it is an upgrade
+    /// 
+    /// Only assets detected as `Unknown` or an unsupported format are
+    /// checked. Returns the new format when it maps to a known asset type
+    /// or is now supported, e.g. after a detector learned a new signature.
+    pub async fn redetect(&self, asset: &Asset) -> DamResult<Option<FileFormat>> {
+        let unknown = asset.asset_type == AssetType::Unknown;
+        if !unknown && asset.format.supported {
+            return Ok(None);
+        }
+        
+        let format = self.detect_format(&asset.current_path).await?;
+        let asset_type = self.asset_type_for(&format.extension);
+        let upgraded = (unknown && asset_type != AssetType::Unknown) || (!asset.format.supported && format.supported);
+        Ok(upgraded.then_some(format))
+    }
+    
+    /// Re-ingest an asset whose format `redetect` upgrades
+    /// 
+    /// Metadata and preview are regenerated for the new format. The asset
+    /// keeps its ID, original path, creation time and tags. Returns None if
+    /// detection finds nothing better.
+    pub async fn upgrade_asset(&self, asset: &Asset) -> DamResult<Option<Asset>> {
+        let Some(format) = self.redetect(asset).await? else {
+            return Ok(None);
+        };
+        info!(
+            "Upgrading {} from {} ({:?}) to {}",
+            asset.current_path.display(), asset.format.extension, asset.asset_type, format.extension
+        );
+        
+        let mut upgraded = self.ingest_one(&asset.current_path).await?;
+        upgraded.id = asset.id;
+        upgraded.original_path = asset.original_path.clone();
+        upgraded.created_at = asset.created_at;
+        upgraded.tags = keywords::merge_keywords(asset.tags.clone(), std::mem::take(&mut upgraded.tags));
+        Ok(Some(upgraded))
+    }
+    
+    /// Re-read the sidecar files of an asset and merge them into it
+    /// 
+    /// Used when a sidecar is added or edited after the asset was ingested,
+    /// so the existing asset is updated instead of re-ingested under a new
+    /// ID. Keywords are only ever added. Returns whether a sidecar was found;
+    /// unreadable sidecars are skipped with a warning.
+    pub async fn refresh_sidecars(&self, asset: &mut Asset) -> DamResult<bool> {
+        let sidecars = sidecar::find_sidecars(&asset.current_path);
+        
+        for path in &sidecars {
+            match sidecar::read_sidecar(path).await {
+                Ok(metadata) => {
+                    sidecar::apply_sidecar(asset, metadata);
+                    info!("Merged sidecar {} into {}", path.display(), asset.current_path.display());
+                }
+                Err(e) => warn!("Failed to read sidecar {}: {}", path.display(), e),
+            }
+        }
+        
+        Ok(!sidecars.is_empty())
+    }
+    
+    /// Generate a contact sheet of `rows * cols` frames for a video asset
+    pub async fn generate_contact_sheet(&self, asset: &Asset, rows: u32, cols: u32) -> DamResult<PreviewInfo> {
+        self.preview_generator.generate_contact_sheet(asset, rows, cols).await
+    }
+    
+    /// Regenerate the preview of an already ingested asset
+    /// 
+    /// Uses the current preview settings, overwriting the previous thumbnail.
+    pub async fn regenerate_preview(&self, asset: &Asset) -> DamResult<PreviewInfo> {
+        self.preview_generator.generate_preview(asset).await
+    }
+    
+    /// Move an asset's file to a new location
+    /// 
+    /// Updates `current_path` only; `original_path` is kept for provenance
+    /// and the preview stays valid since it is keyed by asset ID.
+    pub async fn move_asset<P: AsRef<Path>>(&self, asset: &mut Asset, new_path: P) -> DamResult<()> {
+        let new_path = new_path.as_ref();
+        info!("Moving asset {} to {}", asset.id, new_path.display());
+        
+        move_file(&asset.current_path, new_path).await?;
+        asset.current_path = self.canonical_path(new_path);
+        
+        Ok(())
+    }
+    
+    /// Apply an import policy to a freshly ingested asset
+    /// 
+    /// Returns `true` if the file was relocated. Name collisions in the
+    /// library are resolved by appending a counter to the filename. Store
+    /// the asset afterwards so it is indexed at its new path.
+    pub async fn apply_import_policy(&self, asset: &mut Asset, policy: &ImportPolicy) -> DamResult<bool> {
+        let Some(target) = policy.target_path(asset) else {
+            return Ok(false);
+        };
+        
+        if self.canonical_path(&target) == asset.current_path {
+            return Ok(false);
+        }
+        
+        // Another file may take a free name at any moment, so names are
+        // claimed by moving rather than checked beforehand
+        for candidate in collision_candidates(&target) {
+            if move_file_if_absent(&asset.current_path, &candidate).await? {
+                info!("Relocated asset {} to {}", asset.id, candidate.display());
+                asset.current_path = self.canonical_path(&candidate);
+                return Ok(true);
+            }
+        }
+        
+        Err(schema::DamError::invalid_operation(format!(
+            "Could not find a free name for {}", target.display()
+        )))
+    }
+    
     /// Ingest multiple files in parallel
     pub async fn ingest_batch<P: AsRef<Path>>(&self, paths: Vec<P>) -> Vec<DamResult<Asset>> {
+        self.ingest_paths(paths, ImportOperation::Batch).await
+    }
+    
+    async fn ingest_paths<P: AsRef<Path>>(&self, paths: Vec<P>, operation: ImportOperation) -> Vec<DamResult<Asset>> {
         info!("Ingesting batch of {} files", paths.len());
         
         let tasks = paths.into_iter().map(|path| {
             let service = self;
             async move {
-                service.ingest_file(path).await
+                service.ingest_logged(path.as_ref(), operation).await
             }
         });
         
@@ -123,7 +639,20 @@ impl IngestService {
     }
     
     /// Ingest all files in a directory recursively
+    /// 
+    /// Hidden, temporary and sidecar files are skipped. Failed files and
+    /// unreadable folders are logged and skipped; use
+    /// `ingest_directory_report` to find out which.
     pub async fn ingest_directory<P: AsRef<Path>>(&self, dir_path: P) -> DamResult<Vec<Asset>> {
+        Ok(self.ingest_directory_report(dir_path).await?.assets)
+    }
+    
+    /// `ingest_directory` that reports what could not be imported
+    /// 
+    /// Folders and files the walk or ingest could not read (permission
+    /// denied, I/O errors) are listed apart from files that were read but
+    /// failed, e.g. on unsupported content. Neither stops the import.
+    pub async fn ingest_directory_report<P: AsRef<Path>>(&self, dir_path: P) -> DamResult<BatchIngestReport> {
         let dir_path = dir_path.as_ref();
         info!("Ingesting directory: {}", dir_path.display());
         
@@ -139,68 +668,495 @@ impl IngestService {
             }.into());
         }
         
-        // Collect all files recursively
+        // Collect all files recursively, pruning skipped hidden folders
+        let (walked, inaccessible) = self.walk_files(dir_path, false);
+        
+        info!("Found {} files in directory", walked.len());
+        let mut report = BatchIngestReport { inaccessible, ..BatchIngestReport::default() };
+        let mut skipped = Vec::new();
         let mut file_paths = Vec::new();
-        let mut walker = walkdir::WalkDir::new(dir_path).into_iter();
+        for path in walked {
+            match self.ignore_reason(&path) {
+                Some(reason) => skipped.push((self.canonical_path(&path), reason.to_string())),
+                None => file_paths.push(path),
+            }
+        }
+        skipped.extend(report.inaccessible.iter().map(|location| (location.path.clone(), location.message.clone())));
+        report.skipped = skipped.iter().map(|(path, _)| path.clone()).collect();
+        self.record_skipped(ImportOperation::Directory, skipped).await;
         
-        while let Some(entry) = walker.next() {
-            match entry {
-                Ok(entry) => {
-                    if entry.file_type().is_file() {
-                        file_paths.push(entry.path().to_path_buf());
-                    }
+        let (sequences, file_paths) = match &self.sequence_detection {
+            Some(detection) => detection.group(file_paths),
+            None => (Vec::new(), file_paths),
+        };
+        
+        // Process files in batches to avoid overwhelming the system
+        let total = file_paths.len() + sequences.len();
+        let title = format!("Importing {}", dir_path.display());
+        self.events.progress(&title, 0, total, None);
+        
+        for (position, chunk) in file_paths.chunks(DIRECTORY_BATCH_SIZE).enumerate() {
+            let results = self.ingest_paths(chunk.to_vec(), ImportOperation::Directory).await;
+            
+            for (path, result) in chunk.iter().zip(results) {
+                match result {
+                    Ok(asset) => report.assets.push(asset),
+                    Err(e) => record_failure(&mut report, path, e),
                 }
-                Err(e) => {
-                    warn!("Error walking directory: {}", e);
+            }
+            
+            let completed = (position * DIRECTORY_BATCH_SIZE + chunk.len()).min(total);
+            self.events.progress(&title, completed, total, Some(format!("{} of {} files", completed, total)));
+        }
+        
+        for (position, sequence) in sequences.iter().enumerate() {
+            match self.ingest_sequence(sequence).await {
+                Ok(asset) => report.assets.push(asset),
+                Err(e) => record_failure(&mut report, &sequence.directory.join(sequence.pattern()), e),
+            }
+            
+            let completed = file_paths.len() + position + 1;
+            self.events.progress(&title, completed, total, Some(sequence.pattern()));
+        }
+        
+        info!(
+            "Successfully ingested {} assets from directory ({} skipped, {} failed, {} locations inaccessible)",
+            report.assets.len(), report.skipped.len(), report.failures.len(), report.inaccessible.len()
+        );
+        self.events.hide_progress();
+        self.notify_import(dir_path, report.assets.len(), report.failures.len(), report.inaccessible.len());
+        Ok(report)
+    }
+    
+    /// Ingest a frame sequence as one asset
+    /// 
+    /// Metadata and preview come from the middle frame, which shows the
+    /// content better than a fade-in first frame. The asset points at the
+    /// first frame, its size is the total of all frames and the frame range
+    /// is stored in `metadata.sequence`.
+    pub async fn ingest_sequence(&self, sequence: &FrameSequence) -> DamResult<Asset> {
+        info!("Ingesting sequence {} ({} frames)", sequence.pattern(), sequence.frames.len());
+        let source_path = self.canonical_path(sequence.directory.join(sequence.pattern()));
+        
+        let result = async {
+            let mut asset = self.ingest_one(sequence.middle_frame()).await?;
+            let first_frame = self.canonical_path(sequence.first_frame());
+            asset.original_path = first_frame.clone();
+            asset.current_path = first_frame;
+            
+            let mut total_size = 0;
+            for (_, frame) in &sequence.frames {
+                total_size += fs::metadata(frame).await?.len();
+            }
+            asset.file_size = total_size;
+            asset.metadata.sequence = Some(sequence.metadata());
+            Ok::<_, schema::DamError>(asset)
+        }.await;
+        
+        if let Err(e) = &result {
+            let outcome = ImportOutcome::Failed { reason: e.to_string() };
+            self.record_import(ImportLogEntry::new(ImportOperation::Directory, source_path, outcome)).await;
+        }
+        result
+    }
+    
+    /// Ingest a directory, continuing an earlier interrupted run
+    /// 
+    /// Unlike `ingest_directory`, files are handed to `store` a batch at a
+    /// time, and the files of the assets it returns as stored are recorded
+    /// in the scan state of the root directory. Those are skipped by later
+    /// runs as long as their size and modification time are unchanged; new
+    /// and edited files are ingested, and files that failed or were not
+    /// stored are retried. The scan stops between batches once `cancel` is
+    /// set, keeping its progress. Only newly stored assets are returned.
+    /// Skipped, imported and failed files are all recorded in the import log.
+    pub async fn resume_directory<P, F, Fut>(&self, dir_path: P, cancel: &AtomicBool, mut store: F) -> DamResult<ScanReport>
+    where
+        P: AsRef<Path>,
+        F: FnMut(Vec<Asset>) -> Fut,
+        Fut: std::future::Future<Output = Vec<Asset>>,
+    {
+        let dir_path = dir_path.as_ref();
+        info!("Resuming import of directory: {}", dir_path.display());
+        
+        if !dir_path.exists() {
+            return Err(IngestError::file_not_found(dir_path.to_path_buf()).into());
+        }
+        
+        if !dir_path.is_dir() {
+            return Err(IngestError::not_a_directory(dir_path.to_path_buf()).into());
+        }
+        
+        let root = self.canonical_path(dir_path);
+        let mut state = ScanState::load(&self.scan_state_dir, &root).await?;
+        let (files, inaccessible) = self.walk_files(dir_path, true);
+        let mut report = ScanReport { inaccessible, ..ScanReport::default() };
+        
+        // Walk in a stable order so progress reads naturally across runs
+        let mut present = HashSet::new();
+        let mut pending = Vec::new();
+        let mut skipped = Vec::new();
+        for file in files {
+            let path = self.canonical_path(&file);
+            present.insert(path.clone());
+            if let Some(reason) = self.ignore_reason(&path) {
+                skipped.push((path, reason.to_string()));
+                continue;
+            }
+            let stamp = std::fs::metadata(&file).ok().and_then(|metadata| FileStamp::from_metadata(&metadata));
+            match stamp {
+                Some(stamp) if state.is_done(&path, &stamp) => {
+                    report.skipped += 1;
+                    skipped.push((path, "unchanged since it was imported".to_string()));
                 }
+                stamp => pending.push((path, stamp)),
             }
         }
+        skipped.extend(report.inaccessible.iter().map(|location| (location.path.clone(), location.message.clone())));
+        self.record_skipped(ImportOperation::Directory, skipped).await;
         
-        info!("Found {} files in directory", file_paths.len());
+        info!("{} files to ingest, {} already done", pending.len(), report.skipped);
         
-        // Process files in batches to avoid overwhelming the system
-        const BATCH_SIZE: usize = 10;
-        let mut all_assets = Vec::new();
+        let title = format!("Importing {}", dir_path.display());
+        self.events.progress(&title, 0, pending.len(), None);
         
-        for chunk in file_paths.chunks(BATCH_SIZE) {
-            let results = self.ingest_batch(chunk.to_vec()).await;
+        for (position, chunk) in pending.chunks(DIRECTORY_BATCH_SIZE).enumerate() {
+            if cancel.load(Ordering::Relaxed) {
+                info!("Import of {} cancelled, progress saved", root.display());
+                report.cancelled = true;
+                break;
+            }
             
-            for result in results {
+            let paths: Vec<&PathBuf> = chunk.iter().map(|(path, _)| path).collect();
+            let results = self.ingest_paths(paths, ImportOperation::Directory).await;
+            
+            let mut ingested = Vec::new();
+            let mut stamps = HashMap::new();
+            for ((path, stamp), result) in chunk.iter().zip(results) {
                 match result {
-                    Ok(asset) => all_assets.push(asset),
-                    Err(e) => error!("Failed to ingest file: {}", e),
+                    Ok(asset) => {
+                        stamps.insert(asset.id, (path.clone(), *stamp));
+                        ingested.push(asset);
+                    }
+                    Err(e) => match AccessError::from_ingest(path, &e) {
+                        Some(access) => {
+                            warn!("Cannot read {}: {}", path.display(), e);
+                            report.inaccessible.push(access);
+                        }
+                        None => {
+                            error!("Failed to ingest file: {}", e);
+                            report.failed += 1;
+                        }
+                    },
+                }
+            }
+            
+            let attempted: Vec<(Uuid, PathBuf, String)> = ingested.iter()
+                .map(|asset| (asset.id, asset.original_path.clone(), asset.format.extension.clone()))
+                .collect();
+            let stored = store(ingested).await;
+            report.failed += attempted.len().saturating_sub(stored.len());
+            if self.import_log.is_some() {
+                let stored_ids: HashSet<Uuid> = stored.iter().map(|asset| asset.id).collect();
+                for (asset_id, path, format) in attempted {
+                    let outcome = if stored_ids.contains(&asset_id) {
+                        ImportOutcome::Imported
+                    } else {
+                        ImportOutcome::Failed { reason: "the asset was not stored".to_string() }
+                    };
+                    let entry = ImportLogEntry::new(ImportOperation::Directory, path, outcome).with_asset(asset_id, format);
+                    self.record_import(entry).await;
                 }
             }
+            // Files without a readable mtime are simply redone next time
+            state.mark_done(stored.iter().filter_map(|asset| {
+                let (path, stamp) = stamps.remove(&asset.id)?;
+                Some((path, stamp?))
+            })).await?;
+            report.assets.extend(stored);
+            
+            let completed = position * DIRECTORY_BATCH_SIZE + chunk.len();
+            self.events.progress(&title, completed, pending.len(), Some(format!("{} of {} files", completed, pending.len())));
+        }
+        self.events.hide_progress();
+        
+        if !report.cancelled {
+            // Files below folders that could not be read were not seen, not removed
+            let unreadable: Vec<PathBuf> = report.inaccessible.iter()
+                .map(|location| self.canonical_path(&location.path))
+                .collect();
+            report.removed = state.finish(&present, &unreadable).await?;
         }
         
-        info!("Successfully ingested {} assets from directory", all_assets.len());
-        Ok(all_assets)
+        info!(
+            "Ingested {} assets from {} ({} skipped, {} failed, {} inaccessible, {} removed)",
+            report.assets.len(),
+            root.display(),
+            report.skipped,
+            report.failed,
+            report.inaccessible.len(),
+            report.removed.len()
+        );
+        if report.cancelled {
+            self.events.notify(
+                NotificationLevel::Info,
+                "Import paused",
+                format!("Import of {} stopped after {} files; progress is saved", dir_path.display(), report.assets.len()),
+            );
+        } else {
+            self.notify_import(dir_path, report.assets.len(), report.failed, report.inaccessible.len());
+        }
+        Ok(report)
     }
     
-    /// Check if a file should be ingested (based on extension and other criteria)
-    pub fn should_ingest<P: AsRef<Path>>(&self, path: P) -> bool {
-        let path = path.as_ref();
+    /// Notify the outcome of a directory import
+    fn notify_import(&self, dir_path: &Path, imported: usize, failed: usize, inaccessible: usize) {
+        if failed == 0 && inaccessible == 0 {
+            self.events.notify(
+                NotificationLevel::Success,
+                "Import finished",
+                format!("Imported {} assets from {}", imported, dir_path.display()),
+            );
+            return;
+        }
         
-        // Skip hidden files and directories
-        if let Some(filename) = path.file_name() {
-            if filename.to_string_lossy().starts_with('.') {
+        let mut message = format!("Imported {} assets from {}", imported, dir_path.display());
+        if failed > 0 {
+            message.push_str(&format!("; {} files failed", failed));
+        }
+        if inaccessible > 0 {
+            message.push_str(&format!("; {} locations could not be read", inaccessible));
+        }
+        self.events.notify(NotificationLevel::Warning, "Import finished with errors", message);
+    }
+    
+    /// Preview what `ingest_directory` would do, without side effects
+    /// 
+    /// Every file is classified as imported, ignored, unsupported or a
+    /// sequence frame, grouped into sequences as the import would. Imported
+    /// files with the same content as an earlier one are flagged, though
+    /// the import creates an asset for each. Files are only read for format
+    /// detection and hashing; no metadata is parsed, no previews are
+    /// written and no assets are created. Skipped hidden folders are not
+    /// walked, so their files are not listed.
+    pub async fn ingest_directory_dry_run<P: AsRef<Path>>(&self, dir_path: P) -> DamResult<IngestPlan> {
+        let dir_path = dir_path.as_ref();
+        info!("Planning import of directory: {}", dir_path.display());
+        
+        if !dir_path.exists() {
+            return Err(IngestError::file_not_found(dir_path.to_path_buf()).into());
+        }
+        
+        if !dir_path.is_dir() {
+            return Err(IngestError::not_a_directory(dir_path.to_path_buf()).into());
+        }
+        
+        let mut plan = IngestPlan::new(self.canonical_path(dir_path));
+        let mut seen: HashMap<String, PathBuf> = HashMap::new();
+        
+        let (files, inaccessible) = self.walk_files(dir_path, false);
+        plan.inaccessible = inaccessible;
+        let planned = |path: &Path, action: PlannedAction| PlannedFile {
+            path: self.canonical_path(path),
+            action,
+            asset_type: AssetType::Unknown,
+            file_size: std::fs::metadata(path).map(|metadata| metadata.len()).unwrap_or(0),
+            duplicate_of: None,
+            sequence: None,
+        };
+        
+        let mut candidates = Vec::new();
+        for walked in files {
+            if self.is_ignored(&walked) {
+                plan.record(planned(&walked, PlannedAction::Ignored));
+            } else {
+                candidates.push(walked);
+            }
+        }
+        
+        // Grouped before format detection, as `ingest_directory_report` does
+        let (sequences, singles) = match &self.sequence_detection {
+            Some(detection) => detection.group(candidates),
+            None => (Vec::new(), candidates),
+        };
+        
+        for walked in singles {
+            let mut file = planned(&walked, PlannedAction::Unsupported);
+            if let Ok(format) = self.detect_format(&walked).await {
+                if format.supported {
+                    file.asset_type = self.asset_type_for(&format.extension);
+                    file.action = PlannedAction::Import;
+                    
+                    match compute_file_hash(&walked).await {
+                        Ok(hash) => match seen.get(&hash) {
+                            Some(original) => file.duplicate_of = Some(original.clone()),
+                            None => {
+                                seen.insert(hash, file.path.clone());
+                            }
+                        },
+                        Err(e) => warn!("Failed to hash {}: {}", walked.display(), e),
+                    }
+                }
+            }
+            plan.record(file);
+        }
+        
+        // A sequence becomes one asset of its middle frame's type
+        for sequence in &sequences {
+            let pattern = sequence.pattern();
+            let extension = sequence.middle_frame().extension()
+                .map(|extension| extension.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            let asset_type = self.asset_type_for(&extension);
+            for (_, frame) in &sequence.frames {
+                let mut file = planned(frame, PlannedAction::SequenceFrame);
+                file.asset_type = asset_type.clone();
+                file.sequence = Some(pattern.clone());
+                plan.record(file);
+            }
+            plan.record_sequence(&asset_type);
+        }
+        
+        info!(
+            "Import plan for {}: {} files, {} to import, {} ignored, {} unsupported, {} duplicates, {} sequences",
+            dir_path.display(),
+            plan.total_files,
+            plan.to_import,
+            plan.ignored,
+            plan.unsupported,
+            plan.duplicates,
+            plan.sequences
+        );
+        Ok(plan)
+    }
+    
+    /// Files below a directory, each file once
+    /// 
+    /// Symlinked files are always collected; symlinked folders are only
+    /// entered with `follow_symlinks`. Folders are tracked by canonical
+    /// path, so a link back to a folder already walked is pruned instead of
+    /// looping, and files by their resolved target, so a file reached
+    /// directly and through a link is collected once. The direct path is
+    /// the one kept: linked folders that point inside `dir_path` are left
+    /// to the direct walk, and a linked file gives way to its target. Folders
+    /// and entries the walk could not read are returned alongside.
+    fn walk_files(&self, dir_path: &Path, sorted: bool) -> (Vec<PathBuf>, Vec<AccessError>) {
+        let mut walker = walkdir::WalkDir::new(dir_path).follow_links(self.follow_symlinks);
+        if sorted {
+            walker = walker.sort_by_file_name();
+        }
+        let canonical_root = std::fs::canonicalize(dir_path).unwrap_or_else(|_| dir_path.to_path_buf());
+        
+        let mut visited_dirs = HashSet::new();
+        let walker = walker.into_iter().filter_entry(|entry| {
+            if self.is_hidden_entry(entry) {
+                return false;
+            }
+            if !entry.file_type().is_dir() {
+                return true;
+            }
+            let canonical = std::fs::canonicalize(entry.path()).unwrap_or_else(|_| entry.path().to_path_buf());
+            if entry.depth() > 0 && entry.path_is_symlink() && canonical.starts_with(&canonical_root) {
+                info!("Skipping {}, its folder is walked directly", entry.path().display());
                 return false;
             }
+            let first_visit = visited_dirs.insert(canonical);
+            if !first_visit {
+                info!("Skipping {}, its folder was already walked", entry.path().display());
+            }
+            first_visit
+        });
+        
+        // Resolved target to its position in `files` and whether it was reached through a link
+        let mut targets: HashMap<PathBuf, (usize, bool)> = HashMap::new();
+        let mut files = Vec::new();
+        let mut inaccessible = Vec::new();
+        for entry in walker {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    warn!("Error walking directory: {}", e);
+                    inaccessible.push(AccessError::from_walk(&e, dir_path));
+                    continue;
+                }
+            };
+            
+            // Unfollowed links report their own type; dangling ones are skipped
+            let is_file = if entry.path_is_symlink() {
+                entry.path().is_file()
+            } else {
+                entry.file_type().is_file()
+            };
+            if !is_file {
+                continue;
+            }
+            
+            let target = std::fs::canonicalize(entry.path()).unwrap_or_else(|_| entry.path().to_path_buf());
+            let linked = entry.path_is_symlink();
+            match targets.get_mut(&target) {
+                None => {
+                    targets.insert(target, (files.len(), linked));
+                    files.push(entry.path().to_path_buf());
+                }
+                Some((position, kept_linked)) if *kept_linked && !linked => {
+                    files[*position] = entry.path().to_path_buf();
+                    *kept_linked = false;
+                }
+                Some(_) => {}
+            }
+        }
+        (files, inaccessible)
+    }
+    
+    /// Whether a walked folder below the root is hidden and not walked into
+    fn is_hidden_entry(&self, entry: &walkdir::DirEntry) -> bool {
+        entry.depth() > 0
+            && entry.file_type().is_dir()
+            && self.hidden_files.skips(&entry.file_name().to_string_lossy())
+    }
+    
+    /// Files skipped regardless of format: hidden, temporary and sidecar files
+    fn is_ignored(&self, path: &Path) -> bool {
+        self.ignore_reason(path).is_some()
+    }
+    
+    /// Why a file is skipped regardless of format, if it is
+    fn ignore_reason(&self, path: &Path) -> Option<&'static str> {
+        // Skip hidden files unless allowlisted
+        if let Some(filename) = path.file_name() {
+            if self.hidden_files.skips(&filename.to_string_lossy()) {
+                return Some("hidden file");
+            }
+        }
+        
+        // Sidecars are merged into their asset instead of imported
+        if sidecar::is_sidecar(path) {
+            return Some("sidecar of another file");
         }
         
         // Skip common non-asset files
         if let Some(extension) = path.extension() {
             let ext = extension.to_string_lossy().to_lowercase();
-            match ext.as_str() {
-                "tmp" | "temp" | "log" | "bak" | "cache" => return false,
-                _ => {}
+            if matches!(ext.as_str(), "tmp" | "temp" | "bak" | "cache") {
+                return Some("temporary file");
             }
         }
         
+        None
+    }
+    
+    /// Check if a file should be ingested (based on extension and other criteria)
+    pub fn should_ingest<P: AsRef<Path>>(&self, path: P) -> bool {
+        let path = path.as_ref();
+        
+        if self.is_ignored(path) {
+            return false;
+        }
+        
         // Check if we support this format
         if let Ok(format_info) = tokio::task::block_in_place(|| {
             tokio::runtime::Handle::current().block_on(async {
-                self.detector.detect_format(path).await
+                self.detect_format(path).await
             })
         }) {
             format_info.supported
@@ -216,6 +1172,23 @@ impl Default for IngestService {
     }
 }
 
+/// File a failed directory import entry under access errors or failures
+fn record_failure(report: &mut BatchIngestReport, path: &Path, error: schema::DamError) {
+    match AccessError::from_ingest(path, &error) {
+        Some(access) => {
+            warn!("Cannot read {}: {}", path.display(), error);
+            report.inaccessible.push(access);
+        }
+        None => {
+            error!("Failed to ingest {}: {}", path.display(), error);
+            report.failures.push(FileFailure {
+                path: path.to_path_buf(),
+                error: error.to_string(),
+            });
+        }
+    }
+}
+
 /// Utility function to compute file hash for deduplication
 pub async fn compute_file_hash<P: AsRef<Path>>(path: P) -> DamResult<String> {
     use sha2::{Sha256, Digest};
@@ -236,6 +1209,59 @@ pub async fn compute_file_hash<P: AsRef<Path>>(path: P) -> DamResult<String> {
     Ok(format!("{:x}", hasher.finalize()))
 }
 
+/// Move a file to a new location without overwriting
+/// 
+/// Fails with `IngestError::TargetExists` if the target is already present,
+/// leaving the source untouched. Falls back to copy-and-delete when a plain
+/// rename is not possible (e.g. across filesystems).
+pub async fn move_file<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> DamResult<()> {
+    let to = to.as_ref();
+    if !move_file_if_absent(from.as_ref(), to).await? {
+        return Err(IngestError::target_exists(to.to_path_buf()).into());
+    }
+    Ok(())
+}
+
+/// Move a file unless the target exists, as `move_file`
+/// 
+/// Returns `false`, leaving both paths untouched, if the target is
+/// already present.
+async fn move_file_if_absent(from: &Path, to: &Path) -> DamResult<bool> {
+    if !fs::try_exists(from).await? {
+        return Err(IngestError::file_not_found(from.to_path_buf()).into());
+    }
+    
+    if let Some(parent) = to.parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent).await?;
+        }
+    }
+    
+    // Claim the target atomically, so a file appearing there concurrently
+    // is never replaced; the rename below swaps the placeholder for the file
+    match fs::OpenOptions::new().write(true).create_new(true).open(to).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => return Ok(false),
+        Err(e) => return Err(e.into()),
+    }
+    
+    if let Err(e) = fs::rename(from, to).await {
+        warn!("Rename failed ({}), falling back to copy for {}", e, from.display());
+        
+        if let Err(e) = fs::copy(from, to).await {
+            let _ = fs::remove_file(to).await;
+            return Err(e.into());
+        }
+        
+        if let Err(e) = fs::remove_file(from).await {
+            let _ = fs::remove_file(to).await;
+            return Err(e.into());
+        }
+    }
+    
+    Ok(true)
+}
+
 /// Check if a path represents a supported asset type
 pub fn is_supported_asset<P: AsRef<Path>>(path: P) -> bool {
     let path = path.as_ref();
@@ -275,6 +1301,48 @@ mod tests {
         assert_eq!(hash.len(), 64); // SHA256 produces 64 hex characters
     }
     
+    #[tokio::test]
+    async fn test_move_file_refuses_existing_target() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("source.png");
+        let target = dir.path().join("nested").join("target.png");
+        let existing = dir.path().join("existing.png");
+        
+        fs::write(&source, b"source").await.unwrap();
+        fs::write(&existing, b"existing").await.unwrap();
+        
+        assert!(move_file(&source, &existing).await.is_err());
+        assert_eq!(fs::read(&existing).await.unwrap(), b"existing");
+        
+        move_file(&source, &target).await.unwrap();
+        assert!(!source.exists());
+        assert_eq!(fs::read(&target).await.unwrap(), b"source");
+    }
+    
+    #[tokio::test]
+    async fn test_apply_import_policy_relocates() {
+        let dir = tempdir().unwrap();
+        let library = dir.path().join("library");
+        let source = dir.path().join("inbox").join("clip.wav");
+        fs::create_dir_all(source.parent().unwrap()).await.unwrap();
+        fs::write(&source, b"audio").await.unwrap();
+        
+        let service = IngestService::new().unwrap();
+        let mut asset = Asset::new(source.clone(), AssetType::Audio);
+        
+        assert!(!service.apply_import_policy(&mut asset, &ImportPolicy::InPlace).await.unwrap());
+        assert_eq!(asset.current_path, source);
+        
+        let policy = ImportPolicy::relocate_with_template(&library, "{type}/{filename}").unwrap();
+        fs::create_dir_all(library.join("audio")).await.unwrap();
+        fs::write(library.join("audio").join("clip.wav"), b"other").await.unwrap();
+        
+        assert!(service.apply_import_policy(&mut asset, &policy).await.unwrap());
+        assert_eq!(asset.current_path, library.join("audio").join("clip (1).wav"));
+        assert_eq!(asset.original_path, source);
+        assert!(!source.exists());
+    }
+    
     #[tokio::test]
     async fn test_is_supported_asset() {
         assert!(is_supported_asset("test.png"));
@@ -283,4 +1351,460 @@ mod tests {
         assert!(!is_supported_asset("document.xyz"));
         assert!(!is_supported_asset("file_without_extension"));
     }
+    
+    #[tokio::test]
+    async fn test_upgrade_unknown_asset() {
+        let dir = tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        let path = root.join("render.dat");
+        image::RgbImage::new(3, 2).save_with_format(&path, image::ImageFormat::Png).unwrap();
+        
+        // As recorded by an older import that did not recognize the file
+        let mut asset = Asset::new(path.clone(), AssetType::Unknown);
+        asset.tags = vec!["client-x".to_string()];
+        
+        let service = IngestService::new().unwrap();
+        assert_eq!(service.redetect(&asset).await.unwrap().unwrap().extension, "png");
+        
+        let upgraded = service.upgrade_asset(&asset).await.unwrap().unwrap();
+        assert_eq!(upgraded.id, asset.id);
+        assert_eq!(upgraded.asset_type, AssetType::Image);
+        assert_eq!(upgraded.format.extension, "png");
+        assert!(upgraded.tags.contains(&"client-x".to_string()));
+        
+        // Recognized assets are left alone
+        assert!(service.upgrade_asset(&upgraded).await.unwrap().is_none());
+    }
+    
+    #[tokio::test]
+    async fn test_ingest_directory_dry_run() {
+        let dir = tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        std::fs::create_dir(root.join("shots")).unwrap();
+        image::RgbImage::new(4, 4).save(root.join("shots/a.png")).unwrap();
+        std::fs::copy(root.join("shots/a.png"), root.join("copy.png")).unwrap();
+        std::fs::write(root.join("clip.wav"), b"RIFF....WAVEfmt ").unwrap();
+        std::fs::write(root.join(".hidden.png"), b"hidden").unwrap();
+        std::fs::write(root.join("clip.xmp"), b"<x:xmpmeta/>").unwrap();
+        std::fs::write(root.join("notes.xyz"), b"notes").unwrap();
+        
+        let service = IngestService::new().unwrap();
+        let plan = service.ingest_directory_dry_run(&root).await.unwrap();
+        
+        assert_eq!(plan.total_files, 6);
+        assert_eq!(plan.to_import, 3);
+        assert_eq!(plan.duplicates, 1);
+        assert_eq!(plan.ignored, 2);
+        assert_eq!(plan.unsupported, 1);
+        assert_eq!(plan.by_type.get(&AssetType::Image), Some(&2));
+        assert_eq!(plan.by_type.get(&AssetType::Audio), Some(&1));
+        
+        // Repeated content is imported too, only flagged
+        let duplicate = plan.duplicate_samples().next().unwrap();
+        assert_eq!(duplicate.action, PlannedAction::Import);
+        let original = duplicate.duplicate_of.clone().unwrap();
+        assert_ne!(duplicate.path, original);
+        assert!(original.ends_with("a.png") || original.ends_with("copy.png"));
+        
+        // Nothing was written next to the files
+        assert_eq!(std::fs::read_dir(&root).unwrap().count(), 6);
+        assert!(service.ingest_directory_dry_run(root.join("copy.png")).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_dry_run_matches_ingest() {
+        let dir = tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        for frame in [1, 2, 4] {
+            image::RgbImage::new(2, 2).save(root.join(format!("shot_{:04}.png", frame))).unwrap();
+        }
+        image::RgbImage::new(2, 2).save(root.join("cover.png")).unwrap();
+        std::fs::copy(root.join("cover.png"), root.join("cover copy.png")).unwrap();
+        
+        let service = IngestService::new().unwrap().with_sequence_detection(SequenceDetection::new());
+        let plan = service.ingest_directory_dry_run(&root).await.unwrap();
+        assert_eq!((plan.sequences, plan.sequence_frames), (1, 3));
+        assert_eq!(plan.duplicates, 1);
+        let frame = plan.samples_of(PlannedAction::SequenceFrame).next().unwrap();
+        assert_eq!(frame.sequence.as_deref(), Some("shot_####.png"));
+        
+        // The import creates exactly the planned assets
+        let report = service.ingest_directory_report(&root).await.unwrap();
+        assert_eq!(report.assets.len(), plan.to_import);
+        assert_eq!(plan.to_import, 3);
+        let imported_bytes: u64 = report.assets.iter().map(|asset| asset.file_size).sum();
+        assert_eq!(imported_bytes, plan.import_bytes);
+    }
+    
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_hidden_files() {
+        let dir = tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        for folder in [".cache", ".assets"] {
+            std::fs::create_dir(root.join(folder)).unwrap();
+            image::RgbImage::new(4, 4).save(root.join(folder).join("a.png")).unwrap();
+        }
+        image::RgbImage::new(4, 4).save(root.join(".cover.png")).unwrap();
+        image::RgbImage::new(2, 2).save(root.join("visible.png")).unwrap();
+        
+        // Hidden files are ignored and hidden folders are not walked
+        let service = IngestService::new().unwrap();
+        let plan = service.ingest_directory_dry_run(&root).await.unwrap();
+        assert_eq!(plan.total_files, 2);
+        assert_eq!(plan.to_import, 1);
+        assert!(!service.should_ingest(root.join(".cover.png")));
+        
+        let service = IngestService::new().unwrap()
+            .with_hidden_files(HiddenFilePolicy::default().with_include(".assets").with_include(".cover*"));
+        let plan = service.ingest_directory_dry_run(&root).await.unwrap();
+        assert_eq!(plan.total_files, 3);
+        assert_eq!(plan.to_import, 2);
+        assert!(service.should_ingest(root.join(".cover.png")));
+        assert!(service.should_ingest(root.join(".assets/a.png")));
+    }
+    
+    #[tokio::test]
+    async fn test_resume_directory_cancelled() {
+        let dir = tempdir().unwrap();
+        let state_dir = tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        image::RgbImage::new(2, 2).save(root.join("a.png")).unwrap();
+        
+        // Pretend an earlier run finished the file
+        let stamp = FileStamp::from_metadata(&std::fs::metadata(root.join("a.png")).unwrap()).unwrap();
+        let mut state = ScanState::load(state_dir.path(), &root).await.unwrap();
+        state.mark_done([(root.join("a.png"), stamp)]).await.unwrap();
+        image::RgbImage::new(2, 2).save(root.join("b.png")).unwrap();
+        
+        let service = IngestService::new().unwrap().with_scan_state_dir(state_dir.path());
+        let report = service.resume_directory(&root, &AtomicBool::new(true), |assets| async { assets }).await.unwrap();
+        assert!(report.cancelled);
+        assert_eq!(report.skipped, 1);
+        assert!(report.assets.is_empty());
+        assert!(report.removed.is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_resume_directory_marks_stored_files() {
+        let dir = tempdir().unwrap();
+        let state_dir = tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        image::RgbImage::new(2, 2).save(root.join("a.png")).unwrap();
+        image::RgbImage::new(2, 2).save(root.join("b.png")).unwrap();
+        let service = IngestService::new().unwrap().with_scan_state_dir(state_dir.path());
+        
+        // Only a.png makes it into storage, so b.png is retried
+        let cancel = AtomicBool::new(false);
+        let report = service.resume_directory(&root, &cancel, |assets| async {
+            assets.into_iter().filter(|asset| asset.current_path.ends_with("a.png")).collect()
+        }).await.unwrap();
+        assert_eq!((report.assets.len(), report.failed), (1, 1));
+        
+        let report = service.resume_directory(&root, &cancel, |assets| async { assets }).await.unwrap();
+        assert_eq!(report.skipped, 1);
+        assert_eq!(report.assets.len(), 1);
+        assert!(report.assets[0].current_path.ends_with("b.png"));
+    }
+    
+    #[tokio::test]
+    async fn test_import_log() {
+        let dir = tempdir().unwrap();
+        let since = Utc::now();
+        
+        assert!(IngestService::new().unwrap().import_log(since).await.unwrap().is_empty());
+        
+        let log = Arc::new(ImportLog::new(dir.path().join("imports.jsonl")));
+        let service = IngestService::new().unwrap().with_import_log(log.clone());
+        let missing = dir.path().join("missing.png");
+        assert!(service.ingest_file(&missing).await.is_err());
+        
+        let existing = Uuid::new_v4();
+        service.record_import(ImportLogEntry::new(
+            ImportOperation::Directory,
+            dir.path().join("copy.png"),
+            ImportOutcome::Duplicate { existing },
+        )).await;
+        
+        let entries = service.import_log(since).await.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].operation, ImportOperation::File);
+        assert!(entries[0].source_path.ends_with("missing.png"));
+        assert!(matches!(entries[0].outcome, ImportOutcome::Failed { .. }));
+        assert_eq!(entries[0].asset_id, None);
+        assert_eq!(entries[1].outcome, ImportOutcome::Duplicate { existing });
+        
+        // Later queries only see later events
+        assert!(service.import_log(Utc::now()).await.unwrap().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_import_log_directory() {
+        let dir = tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        image::RgbImage::new(2, 2).save(root.join("a.png")).unwrap();
+        image::RgbImage::new(2, 2).save(root.join(".hidden.png")).unwrap();
+        std::fs::write(root.join("a.xmp"), b"<x:xmpmeta/>").unwrap();
+        
+        let since = Utc::now();
+        let log_dir = tempdir().unwrap();
+        let log = Arc::new(ImportLog::new(log_dir.path().join("imports.jsonl")));
+        let service = IngestService::new().unwrap().with_import_log(log);
+        let report = service.ingest_directory_report(&root).await.unwrap();
+        assert_eq!(report.assets.len(), 1);
+        assert_eq!(report.skipped.len(), 2);
+        
+        // Skips are logged right away, the import only once it is stored
+        let entries = service.import_log(since).await.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|entry| matches!(entry.outcome, ImportOutcome::Skipped { .. })));
+        
+        let asset = &report.assets[0];
+        service.record_stored(ImportOperation::Directory, asset, &Ok::<(), String>(())).await;
+        let entries = service.import_log(since).await.unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[2].outcome, ImportOutcome::Imported);
+        assert_eq!(entries[2].asset_id, Some(asset.id));
+    }
+    
+    #[tokio::test]
+    async fn test_ingest_directory_sequences() {
+        let dir = tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        for frame in [1, 2, 4] {
+            image::RgbImage::new(2, 2).save(root.join(format!("shot_{:04}.png", frame))).unwrap();
+        }
+        image::RgbImage::new(2, 2).save(root.join("cover.png")).unwrap();
+        
+        // Off by default: every frame is its own asset
+        let assets = IngestService::new().unwrap().ingest_directory(&root).await.unwrap();
+        assert_eq!(assets.len(), 4);
+        
+        let service = IngestService::new().unwrap().with_sequence_detection(SequenceDetection::new());
+        let assets = service.ingest_directory(&root).await.unwrap();
+        assert_eq!(assets.len(), 2);
+        
+        let sequence = assets.iter().find(|asset| asset.metadata.sequence.is_some()).unwrap();
+        assert_eq!(sequence.current_path, root.join("shot_0001.png"));
+        let metadata = sequence.metadata.sequence.as_ref().unwrap();
+        assert_eq!(metadata.pattern, "shot_####.png");
+        assert_eq!(metadata.frame_count, 3);
+        assert_eq!((metadata.first_frame, metadata.last_frame), (1, 4));
+        assert_eq!(metadata.missing_frames, vec![3]);
+        
+        let frame_size = std::fs::metadata(root.join("shot_0001.png")).unwrap().len();
+        assert_eq!(sequence.file_size, frame_size * 3);
+    }
+    
+    #[tokio::test]
+    async fn test_ingest_directory_emits_events() {
+        let dir = tempdir().unwrap();
+        for name in ["a.png", "b.png"] {
+            image::RgbImage::new(2, 2).save(dir.path().join(name)).unwrap();
+        }
+        
+        let events = schema::UiEvents::new();
+        let mut receiver = events.subscribe();
+        let service = IngestService::new().unwrap().with_events(events);
+        service.ingest_directory(dir.path()).await.unwrap();
+        
+        let mut messages = Vec::new();
+        while let Ok(message) = receiver.try_recv() {
+            messages.push(message);
+        }
+        
+        match messages.iter().rev().nth(2) {
+            Some(schema::UiMessage::ShowProgress { progress, .. }) => assert_eq!(*progress, 1.0),
+            other => panic!("expected final progress, got {:?}", other),
+        }
+        assert!(matches!(messages[messages.len() - 2], schema::UiMessage::HideProgress));
+        assert!(matches!(
+            messages[messages.len() - 1],
+            schema::UiMessage::Notification { level: NotificationLevel::Success, .. }
+        ));
+    }
+    
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_ingest_directory_reports_inaccessible() {
+        use std::os::unix::fs::PermissionsExt;
+        
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("notes.txt"), b"shared drive notes").unwrap();
+        let locked = dir.path().join("locked");
+        std::fs::create_dir(&locked).unwrap();
+        std::fs::write(locked.join("secret.txt"), b"hidden").unwrap();
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o000)).unwrap();
+        
+        // Permissions do not apply to root, so there is nothing to report
+        let denied = std::fs::read_dir(&locked).is_err();
+        let report = IngestService::new().unwrap().ingest_directory_report(dir.path()).await.unwrap();
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o755)).unwrap();
+        if !denied {
+            return;
+        }
+        
+        assert_eq!(report.assets.len(), 1);
+        assert!(report.failures.is_empty());
+        assert_eq!(report.inaccessible.len(), 1);
+        assert_eq!(report.inaccessible[0].path, locked);
+        assert_eq!(report.inaccessible[0].kind, AccessErrorKind::PermissionDenied);
+    }
+    
+    #[test]
+    fn test_access_error_classification() {
+        let path = Path::new("/mnt/share/a.png");
+        let denied = schema::DamError::from(std::io::Error::from(std::io::ErrorKind::PermissionDenied));
+        assert_eq!(AccessError::from_ingest(path, &denied).unwrap().kind, AccessErrorKind::PermissionDenied);
+        let unsupported = schema::DamError::ingestion("Unsupported content");
+        assert!(AccessError::from_ingest(path, &unsupported).is_none());
+        
+        // Other I/O errors on a file that opens fine happened after reading it
+        let dir = tempdir().unwrap();
+        let readable = dir.path().join("a.png");
+        std::fs::write(&readable, b"data").unwrap();
+        let disk_full = schema::DamError::from(std::io::Error::other("no space left for the preview"));
+        assert!(AccessError::from_ingest(&readable, &disk_full).is_none());
+        assert_eq!(AccessError::from_ingest(path, &disk_full).unwrap().kind, AccessErrorKind::NotFound);
+    }
+    
+    #[tokio::test]
+    async fn test_type_overrides() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("layout.xyz");
+        std::fs::write(&path, b"studio layout data").unwrap();
+        
+        let asset = IngestService::new().unwrap().ingest_file(&path).await.unwrap();
+        assert_eq!(asset.asset_type, AssetType::Unknown);
+        assert!(!asset.format.supported);
+        
+        let service = IngestService::new().unwrap()
+            .with_type_override(".XYZ", AssetType::Document)
+            .with_type_override("dae", AssetType::Document);
+        let asset = service.ingest_file(&path).await.unwrap();
+        assert_eq!(asset.asset_type, AssetType::Document);
+        assert!(asset.format.supported);
+        
+        // Built-in types can be corrected too
+        assert_eq!(service.asset_type_for("dae"), AssetType::Document);
+        assert_eq!(service.asset_type_for("exr"), AssetType::Image);
+    }
+    
+    #[tokio::test]
+    async fn test_filename_tag_rules() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("ACME_poster_v03.txt");
+        std::fs::write(&path, b"poster copy").unwrap();
+        
+        let service = IngestService::new().unwrap()
+            .with_tag_rule(FilenameTagRule::new(r"^(?P<client>[A-Z]+)_", "client:{client}").unwrap())
+            .with_tag_rule(FilenameTagRule::new(r"_v(\d+)", "version:{1}").unwrap());
+        let asset = service.ingest_file(&path).await.unwrap();
+        assert_eq!(asset.tags, vec!["client:ACME", "version:03"]);
+        
+        let asset = IngestService::new().unwrap().ingest_file(&path).await.unwrap();
+        assert!(asset.tags.is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_fast_mode_defers_metadata() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("brief.txt");
+        std::fs::write(&path, b"Campaign brief for the spring launch").unwrap();
+        
+        let service = IngestService::new().unwrap().with_mode(IngestMode::Fast);
+        let asset = service.ingest_file(&path).await.unwrap();
+        assert!(asset.needs_deep_processing);
+        assert_eq!(asset.asset_type, AssetType::Document);
+        assert!(asset.file_size > 0);
+        assert!(asset.metadata.document.is_none());
+        
+        let completed = service.complete_asset(&asset).await.unwrap();
+        assert!(!completed.needs_deep_processing);
+        assert_eq!(completed.id, asset.id);
+        let document = completed.metadata.document.unwrap();
+        assert!(document.extracted_text.contains("spring launch"));
+        
+        std::fs::remove_file(&path).unwrap();
+        assert!(service.complete_asset(&asset).await.is_err());
+    }
+    
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_plain_text_formats() {
+        let dir = tempdir().unwrap();
+        let service = IngestService::new().unwrap();
+        for (name, content, expected) in [
+            ("shots.csv", "shot,lens\nharbor_wide,24mm\n", "harbor_wide"),
+            ("shots.tsv", "shot\tlens\nharbor_close\t85mm\n", "harbor_close"),
+            ("episode.srt", "1\n00:00:01,000 --> 00:00:02,000\nThe lighthouse keeper\n", "lighthouse keeper"),
+            ("episode.vtt", "WEBVTT\n\n00:01.000 --> 00:02.000\nFog over the bay\n", "Fog over the bay"),
+            ("render.log", "frame 12 rendered in 4.2s\n", "rendered"),
+        ] {
+            let path = dir.path().join(name);
+            std::fs::write(&path, content).unwrap();
+            
+            assert!(service.should_ingest(&path), "{}", name);
+            let asset = service.ingest_file(&path).await.unwrap();
+            assert_eq!(asset.asset_type, AssetType::Document, "{}", name);
+            let document = asset.metadata.document.unwrap_or_else(|| panic!("no text extracted from {}", name));
+            assert!(document.extracted_text.contains(expected), "{}", name);
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_corrupt_image_flagged() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("damaged.png");
+        let mut data = std::io::Cursor::new(Vec::new());
+        image::RgbImage::new(8, 8).write_to(&mut data, image::ImageOutputFormat::Png).unwrap();
+        let mut data = data.into_inner();
+        data[16] ^= 0xFF;
+        std::fs::write(&path, &data).unwrap();
+        
+        // No preview is written, so the default preview directory is untouched
+        let asset = IngestService::new().unwrap().ingest_file(&path).await.unwrap();
+        assert!(matches!(asset.integrity, schema::IntegrityStatus::Corrupt { .. }));
+        assert!(asset.preview.is_none());
+    }
+    
+    #[cfg(unix)]
+    #[test]
+    fn test_walk_symlinks_and_cycles() {
+        use std::os::unix::fs::symlink;
+        
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("library");
+        let other = dir.path().join("elsewhere");
+        std::fs::create_dir_all(root.join("shots")).unwrap();
+        std::fs::create_dir(&other).unwrap();
+        std::fs::write(root.join("shots/a.png"), b"a").unwrap();
+        std::fs::write(other.join("b.png"), b"b").unwrap();
+        symlink(root.join("shots/a.png"), root.join("a_link.png")).unwrap();
+        symlink(&other, root.join("elsewhere")).unwrap();
+        symlink(&other, root.join("elsewhere_again")).unwrap();
+        // Cycle back to the root
+        symlink(&root, root.join("shots/up")).unwrap();
+        
+        let names = |files: Vec<PathBuf>| {
+            let mut names: Vec<String> = files.iter()
+                .map(|file| file.file_name().unwrap().to_string_lossy().into_owned())
+                .collect();
+            names.sort();
+            names
+        };
+        
+        // Symlinked files count once, under their target's own path; linked folders are not entered
+        let service = IngestService::new().unwrap();
+        assert!(!service.follows_symlinks());
+        assert_eq!(service.walk_files(&root, true).0, vec![root.join("shots/a.png")]);
+        
+        // Followed links are walked once each and the cycle is pruned
+        let service = IngestService::new().unwrap().with_follow_symlinks(true);
+        assert_eq!(names(service.walk_files(&root, true).0), vec!["a.png", "b.png"]);
+        
+        // A file only reachable through a link is still collected
+        std::fs::remove_file(root.join("shots/a.png")).unwrap();
+        symlink(other.join("b.png"), root.join("b_link.png")).unwrap();
+        let service = IngestService::new().unwrap();
+        assert_eq!(service.walk_files(&root, true).0, vec![root.join("b_link.png")]);
+    }
 }
@@ -0,0 +1,566 @@
+//! Mesh loading and software rendering
+//!
+//! Loads triangle meshes from glTF/GLB, OBJ and STL files and renders a
+//! flat-shaded thumbnail from a 3/4 view with a small z-buffered rasterizer.
+//! No GPU is needed, so previews also work on headless machines.
+//!
+//! Loading is blocking and bounded by [`MAX_PREVIEW_VERTICES`] and
+//! [`MAX_PREVIEW_FACES`]; async callers run it on the blocking pool.
+
+use schema::BoundingBox;
+use std::path::Path;
+
+/// Maximum number of vertices loaded for a preview
+///
+/// Very dense models are truncated; the silhouette is still recognizable
+/// and rendering time stays bounded.
+pub const MAX_PREVIEW_VERTICES: usize = 1_000_000;
+
+/// Maximum number of triangles loaded for a preview
+pub const MAX_PREVIEW_FACES: usize = 2_000_000;
+
+/// Rotation around the vertical axis for the default view, in degrees
+const VIEW_YAW_DEGREES: f32 = 45.0;
+
+/// Downward tilt of the default view, in degrees
+const VIEW_PITCH_DEGREES: f32 = 30.0;
+
+/// Fraction of the image covered by the model along its larger side
+const FRAME_FILL: f32 = 0.85;
+
+const BACKGROUND: [u8; 3] = [48, 48, 56];
+const BASE_COLOR: [f32; 3] = [190.0, 196.0, 210.0];
+const AMBIENT: f32 = 0.25;
+
+/// Triangle mesh in model space
+#[derive(Debug, Clone, Default)]
+pub struct Mesh {
+    pub positions: Vec<[f32; 3]>,
+    pub triangles: Vec<[u32; 3]>,
+    /// Vertices in the file, including those past the preview limit
+    pub vertex_count: usize,
+    /// Triangles in the file, including those past the preview limit
+    pub face_count: usize,
+    /// Whether loading stopped at `MAX_PREVIEW_VERTICES` or `MAX_PREVIEW_FACES`
+    pub truncated: bool,
+}
+
+impl Mesh {
+    /// Load a mesh, choosing the format from the file extension
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let extension = path.extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        
+        match extension.as_str() {
+            "gltf" | "glb" => Self::from_gltf(path),
+            "obj" => {
+                let data = std::fs::read(path).map_err(|e| e.to_string())?;
+                Ok(Self::from_obj(&String::from_utf8_lossy(&data)))
+            }
+            "stl" => {
+                let data = std::fs::read(path).map_err(|e| e.to_string())?;
+                Self::from_stl(&data)
+            }
+            _ => Err(format!("Mesh loading not supported for .{}", extension)),
+        }
+    }
+    
+    /// Load all meshes of the default scene with their node transforms applied
+    ///
+    /// Only the geometry buffers are read; textures are never decoded.
+    pub fn from_gltf(path: &Path) -> Result<Self, String> {
+        let data = std::fs::read(path).map_err(|e| e.to_string())?;
+        let gltf::Gltf { document, blob } = gltf::Gltf::from_slice(&data).map_err(|e| e.to_string())?;
+        drop(data);
+        let buffers = gltf::import_buffers(&document, path.parent(), blob).map_err(|e| e.to_string())?;
+        let mut mesh = Self::default();
+        
+        let scene = document.default_scene().or_else(|| document.scenes().next());
+        match scene {
+            Some(scene) => {
+                for node in scene.nodes() {
+                    mesh.add_gltf_node(&node, &buffers, IDENTITY);
+                }
+            }
+            None => {
+                for gltf_mesh in document.meshes() {
+                    mesh.add_gltf_mesh(&gltf_mesh, &buffers, IDENTITY);
+                }
+            }
+        }
+        
+        Ok(mesh)
+    }
+    
+    fn add_gltf_node(&mut self, node: &gltf::Node, buffers: &[gltf::buffer::Data], parent: Matrix) {
+        let transform = multiply(&parent, &node.transform().matrix());
+        if let Some(gltf_mesh) = node.mesh() {
+            self.add_gltf_mesh(&gltf_mesh, buffers, transform);
+        }
+        for child in node.children() {
+            self.add_gltf_node(&child, buffers, transform);
+        }
+    }
+    
+    fn add_gltf_mesh(&mut self, gltf_mesh: &gltf::Mesh, buffers: &[gltf::buffer::Data], transform: Matrix) {
+        for primitive in gltf_mesh.primitives() {
+            if primitive.mode() != gltf::mesh::Mode::Triangles {
+                continue;
+            }
+            
+            // Counts come from the accessors, so nothing is read past the limits
+            let Some(count) = primitive.get(&gltf::Semantic::Positions).map(|accessor| accessor.count()) else {
+                continue;
+            };
+            let index_count = primitive.indices().map_or(count, |accessor| accessor.count());
+            self.vertex_count = self.vertex_count.saturating_add(count);
+            self.face_count = self.face_count.saturating_add(index_count / 3);
+            
+            if self.truncated
+                || self.positions.len().saturating_add(count) > MAX_PREVIEW_VERTICES
+                || self.triangles.len().saturating_add(index_count / 3) > MAX_PREVIEW_FACES
+            {
+                self.truncated = true;
+                continue;
+            }
+            
+            let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|data| &data.0[..]));
+            let Some(positions) = reader.read_positions() else {
+                continue;
+            };
+            let base = self.positions.len() as u32;
+            self.positions.extend(positions.take(count).map(|p| transform_point(&transform, p)));
+            let count = self.positions.len() as u32 - base;
+            
+            let indices: Vec<u32> = match reader.read_indices() {
+                Some(indices) => indices.into_u32().take(index_count).collect(),
+                None => (0..count).collect(),
+            };
+            self.triangles.extend(
+                indices.chunks_exact(3)
+                    .filter(|t| t.iter().all(|&i| i < count))
+                    .map(|t| [base + t[0], base + t[1], base + t[2]])
+            );
+        }
+    }
+    
+    /// Parse the vertices and faces of a Wavefront OBJ file
+    ///
+    /// Polygons are fan-triangulated; texture/normal indices are ignored.
+    pub fn from_obj(text: &str) -> Self {
+        let mut mesh = Self::default();
+        
+        for line in text.lines() {
+            let mut parts = line.split_whitespace();
+            match parts.next() {
+                Some("v") => {
+                    let coords: Vec<f32> = parts.take(3).filter_map(|p| p.parse().ok()).collect();
+                    let [x, y, z] = coords[..] else {
+                        continue;
+                    };
+                    mesh.vertex_count += 1;
+                    if mesh.positions.len() >= MAX_PREVIEW_VERTICES {
+                        mesh.truncated = true;
+                        continue;
+                    }
+                    mesh.positions.push([x, y, z]);
+                }
+                Some("f") => {
+                    let vertex_count = mesh.positions.len() as i64;
+                    let face: Option<Vec<u32>> = parts
+                        .map(|part| {
+                            // `v`, `v/vt`, `v//vn` or `v/vt/vn`; negative indices count from the end
+                            let index: i64 = part.split('/').next()?.parse().ok()?;
+                            let index = if index < 0 { vertex_count + index } else { index - 1 };
+                            (0..vertex_count).contains(&index).then_some(index as u32)
+                        })
+                        .collect();
+                    
+                    if let Some(face) = face {
+                        for i in 1..face.len().saturating_sub(1) {
+                            mesh.face_count += 1;
+                            if mesh.triangles.len() >= MAX_PREVIEW_FACES {
+                                mesh.truncated = true;
+                                continue;
+                            }
+                            mesh.triangles.push([face[0], face[i], face[i + 1]]);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        
+        mesh
+    }
+    
+    /// Parse a binary or ASCII STL file
+    pub fn from_stl(data: &[u8]) -> Result<Self, String> {
+        // Binary files may also start with "solid", so check the size first
+        if data.len() >= 84 {
+            let count = u32::from_le_bytes([data[80], data[81], data[82], data[83]]) as usize;
+            if count.checked_mul(50).and_then(|size| size.checked_add(84)) == Some(data.len()) {
+                return Ok(Self::from_binary_stl(&data[84..], count));
+            }
+        }
+        
+        if data.starts_with(b"solid") {
+            return Ok(Self::from_ascii_stl(&String::from_utf8_lossy(data)));
+        }
+        
+        Err("Not a valid STL file".to_string())
+    }
+    
+    fn from_binary_stl(records: &[u8], count: usize) -> Self {
+        let mut mesh = Self {
+            vertex_count: count.saturating_mul(3),
+            face_count: count,
+            ..Self::default()
+        };
+        
+        for record in records.chunks_exact(50).take(count) {
+            if mesh.positions.len() + 3 > MAX_PREVIEW_VERTICES {
+                mesh.truncated = true;
+                break;
+            }
+            
+            // Skip the 12-byte facet normal; vertices follow
+            let float = |offset: usize| f32::from_le_bytes([
+                record[offset], record[offset + 1], record[offset + 2], record[offset + 3],
+            ]);
+            let base = mesh.positions.len() as u32;
+            for vertex in 0..3 {
+                let offset = 12 + vertex * 12;
+                mesh.positions.push([float(offset), float(offset + 4), float(offset + 8)]);
+            }
+            mesh.triangles.push([base, base + 1, base + 2]);
+        }
+        
+        mesh
+    }
+    
+    fn from_ascii_stl(text: &str) -> Self {
+        let mut mesh = Self::default();
+        let mut facet = Vec::with_capacity(3);
+        
+        for line in text.lines() {
+            let mut parts = line.split_whitespace();
+            match parts.next() {
+                Some("vertex") => {
+                    let coords: Vec<f32> = parts.take(3).filter_map(|p| p.parse().ok()).collect();
+                    if let [x, y, z] = coords[..] {
+                        facet.push([x, y, z]);
+                    }
+                }
+                Some("endloop") => {
+                    if facet.len() == 3 {
+                        mesh.vertex_count += 3;
+                        mesh.face_count += 1;
+                        if mesh.positions.len() + 3 > MAX_PREVIEW_VERTICES {
+                            mesh.truncated = true;
+                            facet.clear();
+                            continue;
+                        }
+                        let base = mesh.positions.len() as u32;
+                        mesh.positions.append(&mut facet);
+                        mesh.triangles.push([base, base + 1, base + 2]);
+                    }
+                    facet.clear();
+                }
+                _ => {}
+            }
+        }
+        
+        mesh
+    }
+    
+    /// Axis-aligned bounds of all vertices
+    pub fn bounds(&self) -> Option<BoundingBox> {
+        let mut min = [f32::INFINITY; 3];
+        let mut max = [f32::NEG_INFINITY; 3];
+        
+        for position in self.positions.iter().filter(|p| p.iter().all(|c| c.is_finite())) {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(position[axis]);
+                max[axis] = max[axis].max(position[axis]);
+            }
+        }
+        
+        min[0].is_finite().then(|| BoundingBox {
+            min: (min[0], min[1], min[2]),
+            max: (max[0], max[1], max[2]),
+        })
+    }
+}
+
+/// Render a flat-shaded view of `mesh` that fits within `size`
+///
+/// The model is viewed from above at a 3/4 angle and framed using its
+/// bounding box, so the whole model is visible regardless of its scale.
+pub fn render_mesh(mesh: &Mesh, size: (u32, u32)) -> Result<image::RgbImage, String> {
+    let bounds = mesh.bounds().ok_or("Mesh has no vertices")?;
+    if mesh.triangles.is_empty() {
+        return Err("Mesh has no faces".to_string());
+    }
+    
+    let (width, height) = (size.0.max(1), size.1.max(1));
+    let center = [
+        (bounds.min.0 + bounds.max.0) / 2.0,
+        (bounds.min.1 + bounds.max.1) / 2.0,
+        (bounds.min.2 + bounds.max.2) / 2.0,
+    ];
+    let view = ViewRotation::new(VIEW_YAW_DEGREES, VIEW_PITCH_DEGREES);
+    
+    // Frame the projected bounding box corners
+    let (mut min_x, mut max_x, mut min_y, mut max_y) = (f32::MAX, f32::MIN, f32::MAX, f32::MIN);
+    for corner in 0..8 {
+        let point = [
+            if corner & 1 == 0 { bounds.min.0 } else { bounds.max.0 },
+            if corner & 2 == 0 { bounds.min.1 } else { bounds.max.1 },
+            if corner & 4 == 0 { bounds.min.2 } else { bounds.max.2 },
+        ];
+        let [x, y, _] = view.apply(sub(point, center));
+        min_x = min_x.min(x);
+        max_x = max_x.max(x);
+        min_y = min_y.min(y);
+        max_y = max_y.max(y);
+    }
+    let extent = (max_x - min_x).max(max_y - min_y).max(f32::EPSILON);
+    let scale = FRAME_FILL * width.min(height) as f32 / extent;
+    let offset = ((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+    
+    // Project into screen space; z grows towards the viewer
+    let projected: Vec<[f32; 3]> = mesh.positions.iter()
+        .map(|&position| {
+            let [x, y, z] = view.apply(sub(position, center));
+            [
+                width as f32 / 2.0 + (x - offset.0) * scale,
+                height as f32 / 2.0 - (y - offset.1) * scale,
+                z,
+            ]
+        })
+        .collect();
+    
+    let light = normalize([0.4, 0.6, 1.0]);
+    let mut image = image::RgbImage::from_pixel(width, height, image::Rgb(BACKGROUND));
+    let mut depth = vec![f32::NEG_INFINITY; (width * height) as usize];
+    
+    for triangle in &mesh.triangles {
+        let [a, b, c] = triangle.map(|i| projected[i as usize]);
+        if [a, b, c].iter().flatten().any(|v| !v.is_finite()) {
+            continue;
+        }
+        
+        // Two-sided flat shading, since winding order is not reliable across formats
+        let view_normal = normalize(cross(
+            sub(view.apply(mesh.positions[triangle[1] as usize]), view.apply(mesh.positions[triangle[0] as usize])),
+            sub(view.apply(mesh.positions[triangle[2] as usize]), view.apply(mesh.positions[triangle[0] as usize])),
+        ));
+        let intensity = AMBIENT + (1.0 - AMBIENT) * dot(view_normal, light).abs();
+        let color = image::Rgb(BASE_COLOR.map(|channel| (channel * intensity).min(255.0) as u8));
+        
+        rasterize_triangle(a, b, c, |x, y, z| {
+            let index = (y * width + x) as usize;
+            if z > depth[index] {
+                depth[index] = z;
+                image.put_pixel(x, y, color);
+            }
+        }, width, height);
+    }
+    
+    Ok(image)
+}
+
+/// Visit the pixels covered by a screen-space triangle with interpolated depth
+fn rasterize_triangle<F: FnMut(u32, u32, f32)>(a: [f32; 3], b: [f32; 3], c: [f32; 3], mut plot: F, width: u32, height: u32) {
+    let area = edge(a, b, c);
+    if area.abs() < f32::EPSILON {
+        return;
+    }
+    
+    let min_x = a[0].min(b[0]).min(c[0]).floor().max(0.0) as u32;
+    let max_x = a[0].max(b[0]).max(c[0]).ceil().min(width as f32 - 1.0);
+    let min_y = a[1].min(b[1]).min(c[1]).floor().max(0.0) as u32;
+    let max_y = a[1].max(b[1]).max(c[1]).ceil().min(height as f32 - 1.0);
+    if max_x < 0.0 || max_y < 0.0 {
+        return;
+    }
+    
+    for y in min_y..=max_y as u32 {
+        for x in min_x..=max_x as u32 {
+            let p = [x as f32 + 0.5, y as f32 + 0.5, 0.0];
+            let w0 = edge(b, c, p) / area;
+            let w1 = edge(c, a, p) / area;
+            let w2 = edge(a, b, p) / area;
+            if w0 >= 0.0 && w1 >= 0.0 && w2 >= 0.0 {
+                plot(x, y, w0 * a[2] + w1 * b[2] + w2 * c[2]);
+            }
+        }
+    }
+}
+
+/// Signed doubled area of the triangle `a`, `b`, `p` in screen space
+fn edge(a: [f32; 3], b: [f32; 3], p: [f32; 3]) -> f32 {
+    (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+}
+
+/// Rotation from model space into view space
+struct ViewRotation {
+    yaw: (f32, f32),
+    pitch: (f32, f32),
+}
+
+impl ViewRotation {
+    fn new(yaw_degrees: f32, pitch_degrees: f32) -> Self {
+        Self {
+            yaw: yaw_degrees.to_radians().sin_cos(),
+            pitch: pitch_degrees.to_radians().sin_cos(),
+        }
+    }
+    
+    fn apply(&self, [x, y, z]: [f32; 3]) -> [f32; 3] {
+        let (yaw_sin, yaw_cos) = self.yaw;
+        let (pitch_sin, pitch_cos) = self.pitch;
+        
+        // Turn around the vertical axis, then tilt the top towards the viewer
+        let x1 = x * yaw_cos + z * yaw_sin;
+        let z1 = -x * yaw_sin + z * yaw_cos;
+        let y2 = y * pitch_cos - z1 * pitch_sin;
+        let z2 = y * pitch_sin + z1 * pitch_cos;
+        [x1, y2, z2]
+    }
+}
+
+/// Column-major 4x4 transform, as used by glTF
+type Matrix = [[f32; 4]; 4];
+
+const IDENTITY: Matrix = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    let mut result = [[0.0; 4]; 4];
+    for (column, result_column) in result.iter_mut().enumerate() {
+        for (row, value) in result_column.iter_mut().enumerate() {
+            *value = (0..4).map(|k| a[k][row] * b[column][k]).sum();
+        }
+    }
+    result
+}
+
+fn transform_point(m: &Matrix, [x, y, z]: [f32; 3]) -> [f32; 3] {
+    [
+        m[0][0] * x + m[1][0] * y + m[2][0] * z + m[3][0],
+        m[0][1] * x + m[1][1] * y + m[2][1] * z + m[3][1],
+        m[0][2] * x + m[1][2] * y + m[2][2] * z + m[3][2],
+    ]
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let length = dot(v, v).sqrt();
+    if length > 0.0 {
+        [v[0] / length, v[1] / length, v[2] / length]
+    } else {
+        v
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const CUBE_OBJ: &str = "\
+v -1 -1 -1
+v 1 -1 -1
+v 1 1 -1
+v -1 1 -1
+v -1 -1 1
+v 1 -1 1
+v 1 1 1
+v -1 1 1
+f 1 2 3 4
+f 5/1 6/1 7/1 8/1
+f 1//1 2//1 6//1 5//1
+f 4 3 7 8
+f -8 -4 -1 -5
+f 2 3 7 6
+";
+    
+    #[test]
+    fn test_obj_parsing() {
+        let mesh = Mesh::from_obj(CUBE_OBJ);
+        assert_eq!(mesh.positions.len(), 8);
+        // Six quads, two triangles each
+        assert_eq!(mesh.triangles.len(), 12);
+        assert_eq!((mesh.vertex_count, mesh.face_count), (8, 12));
+        assert!(!mesh.truncated);
+        // Negative indices count back from the last vertex
+        assert!(mesh.triangles.contains(&[0, 4, 7]));
+        
+        let bounds = mesh.bounds().unwrap();
+        assert_eq!(bounds.min, (-1.0, -1.0, -1.0));
+        assert_eq!(bounds.max, (1.0, 1.0, 1.0));
+    }
+    
+    #[test]
+    fn test_stl_parsing() {
+        let ascii = "solid tri\nfacet normal 0 0 1\nouter loop\nvertex 0 0 0\nvertex 1 0 0\nvertex 0 2 0\nendloop\nendfacet\nendsolid tri\n";
+        let mesh = Mesh::from_stl(ascii.as_bytes()).unwrap();
+        assert_eq!(mesh.triangles.len(), 1);
+        assert_eq!(mesh.bounds().unwrap().max, (1.0, 2.0, 0.0));
+        
+        // Binary header may start with "solid" as well
+        let mut binary = b"solid but binary".to_vec();
+        binary.resize(80, 0);
+        binary.extend(1u32.to_le_bytes());
+        for value in [0.0f32, 0.0, 1.0, 0.0, 0.0, 0.0, 3.0, 0.0, 0.0, 0.0, 3.0, 0.0] {
+            binary.extend(value.to_le_bytes());
+        }
+        binary.extend([0, 0]);
+        let mesh = Mesh::from_stl(&binary).unwrap();
+        assert_eq!(mesh.positions, vec![[0.0, 0.0, 0.0], [3.0, 0.0, 0.0], [0.0, 3.0, 0.0]]);
+        assert_eq!((mesh.vertex_count, mesh.face_count), (3, 1));
+        
+        assert!(Mesh::from_stl(b"not a mesh").is_err());
+    }
+    
+    #[test]
+    fn test_render_frames_model() {
+        // Scale should not matter thanks to bounding-box framing
+        for scale in [0.001, 1.0, 1000.0] {
+            let mut mesh = Mesh::from_obj(CUBE_OBJ);
+            for position in &mut mesh.positions {
+                *position = position.map(|c| c * scale + 50.0);
+            }
+            
+            let image = render_mesh(&mesh, (64, 48)).unwrap();
+            assert_eq!(image.dimensions(), (64, 48));
+            assert_ne!(image.get_pixel(32, 24).0, BACKGROUND, "model not centered at scale {}", scale);
+            assert_eq!(image.get_pixel(0, 0).0, BACKGROUND);
+            assert_eq!(image.get_pixel(63, 47).0, BACKGROUND);
+        }
+        
+        assert!(render_mesh(&Mesh::default(), (64, 64)).is_err());
+    }
+}
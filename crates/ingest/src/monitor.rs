@@ -1,15 +1,18 @@
 //! File system monitoring for automatic asset ingestion
 //! 
 //! This module watches directories for file changes and automatically
-//! triggers ingestion of new or modified assets.
+//! triggers ingestion of new or modified assets. Assets are kept through an
+//! `AssetStore`, usually the search index, which also supplies the assets
+//! to update when a sidecar next to their file changes.
 
-use schema::DamResult;
+use async_trait::async_trait;
+use schema::{Asset, DamResult};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::mpsc;
 use tracing::{debug, info, warn, error};
 use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
-use crate::{IngestService, error::IngestError};
+use crate::{ImportOperation, IngestService, ImportPolicy, SymlinkPolicy, canonicalize_path, passes_through_symlink, error::IngestError, sidecar};
 
 /// Events emitted by the file system monitor
 #[derive(Debug, Clone)]
@@ -26,10 +29,28 @@ pub enum MonitorEvent {
     /// A file was moved/renamed
     FileMoved { from: PathBuf, to: PathBuf },
     
+    /// A sidecar file was created or modified
+    /// 
+    /// `assets` lists the already present files it belongs to; their assets
+    /// should be updated with `IngestService::refresh_sidecars`. If it is
+    /// empty the asset has not arrived yet and will pick up the sidecar when
+    /// it is ingested.
+    SidecarChanged { sidecar: PathBuf, assets: Vec<PathBuf> },
+    
     /// Monitoring error occurred
     Error { message: String },
 }
 
+/// Where the monitor keeps the assets it ingests
+#[async_trait]
+pub trait AssetStore: Send + Sync {
+    /// Store a new or updated asset
+    async fn store(&self, asset: &Asset) -> DamResult<()>;
+    
+    /// The stored asset whose file is at `path`, if any
+    async fn find_by_path(&self, path: &Path) -> DamResult<Option<Asset>>;
+}
+
 /// File system monitor service
 pub struct FileSystemMonitor {
     /// The file system watcher
@@ -46,6 +67,12 @@ pub struct FileSystemMonitor {
     
     /// Whether to automatically ingest detected files
     auto_ingest: bool,
+    
+    /// What to do with files after they are auto-ingested
+    import_policy: ImportPolicy,
+    
+    /// Where ingested and updated assets are stored
+    asset_store: Option<Arc<dyn AssetStore>>,
 }
 
 impl FileSystemMonitor {
@@ -57,12 +84,27 @@ impl FileSystemMonitor {
             ingest_service,
             monitored_paths: Vec::new(),
             auto_ingest: true,
+            import_policy: ImportPolicy::default(),
+            asset_store: None,
         })
     }
+
     
     /// Start monitoring a directory
+    /// 
+    /// Event paths are canonicalized with the ingest service's symlink
+    /// policy, so they match the paths stored on ingested assets.
+    /// 
+    /// Like directory imports, events from inside symlinked folders are
+    /// dropped unless the ingest service follows symlinks, and a directory
+    /// that resolves to one already watched (or to a folder inside one,
+    /// e.g. through a symlink) is not watched a second time. Watching a
+    /// parent of watched folders takes them over, so they are not watched
+    /// twice either.
     pub async fn start_monitoring<P: AsRef<Path>>(&mut self, path: P) -> DamResult<()> {
-        let path = path.as_ref().to_path_buf();
+        let path = self.ingest_service.canonical_path(path);
+        let symlink_policy = self.ingest_service.symlink_policy();
+        let follow_symlinks = self.ingest_service.follows_symlinks();
         
         if !path.exists() {
             return Err(IngestError::file_not_found(path).into());
@@ -72,6 +114,21 @@ impl FileSystemMonitor {
             return Err(IngestError::not_a_directory(path).into());
         }
         
+        if let Some(watched) = self.watching(&path) {
+            info!("{} is already monitored through {}", path.display(), watched.display());
+            return Ok(());
+        }
+        
+        for child in self.watched_inside(&path) {
+            info!("{} is now monitored through {}", child.display(), path.display());
+            if let Some(watcher) = &mut self.watcher {
+                if let Err(e) = watcher.unwatch(&child) {
+                    debug!("Failed to unwatch {}: {}", child.display(), e);
+                }
+            }
+        }
+        
+        let root = path.clone();
         info!("Starting file system monitoring for: {}", path.display());
         
         // Create event channel
@@ -81,7 +138,11 @@ impl FileSystemMonitor {
         let mut watcher = notify::recommended_watcher(move |result: Result<Event, notify::Error>| {
             match result {
                 Ok(event) => {
-                    if let Some(monitor_event) = Self::convert_notify_event(event) {
+                    // The watcher itself always recurses into symlinked folders
+                    if !follow_symlinks && event.paths.iter().any(|path| passes_through_symlink(&root, path)) {
+                        return;
+                    }
+                    if let Some(monitor_event) = Self::convert_notify_event(event, symlink_policy) {
                         if let Err(e) = event_sender.try_send(monitor_event) {
                             warn!("Failed to send monitor event: {}", e);
                         }
@@ -110,6 +171,24 @@ impl FileSystemMonitor {
         Ok(())
     }
     
+    /// Monitored directory that already covers `path`, comparing resolved paths
+    fn watching(&self, path: &Path) -> Option<&PathBuf> {
+        let resolved = canonicalize_path(path, SymlinkPolicy::Resolve);
+        self.monitored_paths.iter().find(|watched| {
+            resolved.starts_with(canonicalize_path(watched, SymlinkPolicy::Resolve))
+        })
+    }
+    
+    /// Remove and return the monitored directories inside `path`
+    fn watched_inside(&mut self, path: &Path) -> Vec<PathBuf> {
+        let resolved = canonicalize_path(path, SymlinkPolicy::Resolve);
+        let (inside, outside): (Vec<PathBuf>, Vec<PathBuf>) = std::mem::take(&mut self.monitored_paths)
+            .into_iter()
+            .partition(|watched| canonicalize_path(watched, SymlinkPolicy::Resolve).starts_with(&resolved));
+        self.monitored_paths = outside;
+        inside
+    }
+    
     /// Stop monitoring all directories
     pub async fn stop_monitoring(&mut self) -> DamResult<()> {
         info!("Stopping file system monitoring");
@@ -183,6 +262,14 @@ impl FileSystemMonitor {
                     self.auto_ingest_file(to).await?;
                 }
             }
+            MonitorEvent::SidecarChanged { sidecar, assets } => {
+                debug!("Sidecar {} changed for {} existing assets", sidecar.display(), assets.len());
+                for path in assets {
+                    if let Err(e) = self.update_from_sidecar(path).await {
+                        warn!("Failed to apply sidecar {} to {}: {}", sidecar.display(), path.display(), e);
+                    }
+                }
+            }
             MonitorEvent::FileDeleted { path: _ } => {
                 // File deletion would be handled by the main asset management system
                 debug!("File deleted, asset cleanup should be handled externally");
@@ -200,9 +287,23 @@ impl FileSystemMonitor {
         info!("Auto-ingesting detected file: {}", path.display());
         
         match self.ingest_service.ingest_file(path).await {
-            Ok(asset) => {
+            Ok(mut asset) => {
                 info!("Successfully auto-ingested: {} (ID: {})", 
                       path.display(), asset.id);
+                
+                match self.ingest_service.apply_import_policy(&mut asset, &self.import_policy).await {
+                    Ok(true) => info!("Relocated {} to {}", path.display(), asset.current_path.display()),
+                    Ok(false) => {}
+                    Err(e) => warn!("Failed to relocate {}: {}", path.display(), e),
+                }
+                
+                if let Some(store) = &self.asset_store {
+                    let stored = store.store(&asset).await;
+                    self.ingest_service.record_stored(ImportOperation::File, &asset, &stored).await;
+                    if let Err(e) = stored {
+                        warn!("Failed to store auto-ingested {}: {}", path.display(), e);
+                    }
+                }
             }
             Err(e) => {
                 warn!("Failed to auto-ingest {}: {}", path.display(), e);
@@ -212,6 +313,26 @@ impl FileSystemMonitor {
         Ok(())
     }
     
+    /// Merge the sidecars of the stored asset at `path` into it and store it again
+    /// 
+    /// The asset's metadata is read again along with its sidecars, keeping
+    /// its ID, paths and preview. Files without a stored asset pick up their
+    /// sidecars when they are ingested.
+    async fn update_from_sidecar(&self, path: &Path) -> DamResult<()> {
+        let Some(store) = &self.asset_store else {
+            return Ok(());
+        };
+        let Some(asset) = store.find_by_path(path).await? else {
+            debug!("No stored asset for {} yet", path.display());
+            return Ok(());
+        };
+        
+        let updated = self.ingest_service.complete_asset(&asset).await?;
+        store.store(&updated).await?;
+        info!("Updated {} from its sidecar", path.display());
+        Ok(())
+    }
+    
     /// Check if a file should be automatically ingested
     fn should_ingest_file(&self, path: &Path) -> bool {
         // Skip directories
@@ -219,12 +340,30 @@ impl FileSystemMonitor {
             return false;
         }
         
+        // Files already in the library were placed there by the import policy
+        if self.import_policy.is_in_library(path) {
+            return false;
+        }
+        
         // Use the ingest service's filtering logic
         self.ingest_service.should_ingest(path)
     }
     
     /// Convert notify event to our monitor event
-    fn convert_notify_event(event: Event) -> Option<MonitorEvent> {
+    fn convert_notify_event(mut event: Event, symlink_policy: SymlinkPolicy) -> Option<MonitorEvent> {
+        for path in &mut event.paths {
+            *path = canonicalize_path(&*path, symlink_policy);
+        }
+        
+        if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+            if let Some(path) = event.paths.first().filter(|path| sidecar::is_sidecar(path)) {
+                return Some(MonitorEvent::SidecarChanged {
+                    sidecar: path.clone(),
+                    assets: sidecar::find_companion_assets(path),
+                });
+            }
+        }
+        
         match event.kind {
             EventKind::Create(_) => {
                 if let Some(path) = event.paths.first() {
@@ -268,6 +407,19 @@ impl FileSystemMonitor {
         info!("Auto-ingest set to: {}", auto_ingest);
     }
     
+    /// Set the policy applied to auto-ingested files
+    pub fn set_import_policy(&mut self, policy: ImportPolicy) {
+        info!("Import policy set to: {:?}", policy);
+        self.import_policy = policy;
+    }
+    
+    /// Store auto-ingested assets, and update them when their sidecars change
+    /// 
+    /// Without a store, detected files are ingested and then only logged.
+    pub fn set_asset_store(&mut self, store: Arc<dyn AssetStore>) {
+        self.asset_store = Some(store);
+    }
+    
     /// Get the list of monitored paths
     pub fn monitored_paths(&self) -> &[PathBuf] {
         &self.monitored_paths
@@ -293,6 +445,8 @@ pub struct MonitorBuilder {
     paths: Vec<PathBuf>,
     auto_ingest: bool,
     recursive: bool,
+    import_policy: ImportPolicy,
+    asset_store: Option<Arc<dyn AssetStore>>,
 }
 
 impl MonitorBuilder {
@@ -302,6 +456,8 @@ impl MonitorBuilder {
             paths: Vec::new(),
             auto_ingest: true,
             recursive: true,
+            import_policy: ImportPolicy::default(),
+            asset_store: None,
         }
     }
     
@@ -317,6 +473,18 @@ impl MonitorBuilder {
         self
     }
     
+    /// Set the policy applied to auto-ingested files
+    pub fn import_policy(mut self, policy: ImportPolicy) -> Self {
+        self.import_policy = policy;
+        self
+    }
+    
+    /// Set where auto-ingested assets are stored
+    pub fn asset_store(mut self, store: Arc<dyn AssetStore>) -> Self {
+        self.asset_store = Some(store);
+        self
+    }
+    
     /// Set whether to monitor recursively
     pub fn recursive(mut self, recursive: bool) -> Self {
         self.recursive = recursive;
@@ -327,6 +495,10 @@ impl MonitorBuilder {
     pub fn build(self, ingest_service: Arc<IngestService>) -> DamResult<FileSystemMonitor> {
         let mut monitor = FileSystemMonitor::new(ingest_service)?;
         monitor.set_auto_ingest(self.auto_ingest);
+        monitor.set_import_policy(self.import_policy);
+        if let Some(store) = self.asset_store {
+            monitor.set_asset_store(store);
+        }
         Ok(monitor)
     }
 }
@@ -386,6 +558,35 @@ mod tests {
         assert_eq!(monitor.monitored_paths().len(), 0);
     }
     
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_symlinked_directory_watched_once() {
+        let ingest_service = Arc::new(IngestService::new().unwrap().with_symlink_policy(SymlinkPolicy::Preserve));
+        let dir = tempdir().unwrap();
+        let shots = dir.path().join("shots");
+        std::fs::create_dir(&shots).unwrap();
+        std::os::unix::fs::symlink(&shots, dir.path().join("link")).unwrap();
+        let mut monitor = FileSystemMonitor::new(ingest_service).unwrap();
+        
+        monitor.start_monitoring(dir.path()).await.unwrap();
+        monitor.start_monitoring(dir.path().join("link")).await.unwrap();
+        monitor.start_monitoring(&shots).await.unwrap();
+        assert_eq!(monitor.monitored_paths().len(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_parent_takes_over_watched_child() {
+        let ingest_service = Arc::new(IngestService::new().unwrap());
+        let dir = tempdir().unwrap();
+        let shots = dir.path().join("shots");
+        std::fs::create_dir(&shots).unwrap();
+        let mut monitor = FileSystemMonitor::new(ingest_service.clone()).unwrap();
+        
+        monitor.start_monitoring(&shots).await.unwrap();
+        monitor.start_monitoring(dir.path()).await.unwrap();
+        assert_eq!(monitor.monitored_paths(), &[ingest_service.canonical_path(dir.path())]);
+    }
+    
     #[test]
     fn test_should_ingest_file() {
         let ingest_service = Arc::new(IngestService::new().unwrap());
@@ -407,7 +608,7 @@ mod tests {
             attrs: Default::default(),
         };
         
-        let monitor_event = FileSystemMonitor::convert_notify_event(create_event);
+        let monitor_event = FileSystemMonitor::convert_notify_event(create_event, SymlinkPolicy::Resolve);
         assert!(matches!(monitor_event, Some(MonitorEvent::FileCreated { .. })));
         
         let modify_event = Event {
@@ -418,7 +619,95 @@ mod tests {
             attrs: Default::default(),
         };
         
-        let monitor_event = FileSystemMonitor::convert_notify_event(modify_event);
+        let monitor_event = FileSystemMonitor::convert_notify_event(modify_event, SymlinkPolicy::Resolve);
         assert!(matches!(monitor_event, Some(MonitorEvent::FileModified { .. })));
     }
+    
+    #[test]
+    fn test_sidecar_events() {
+        use notify::{Event, EventKind};
+        
+        let dir = tempdir().unwrap();
+        let sidecar = dir.path().join("photo.xmp");
+        let create_event = |path: &Path| Event {
+            kind: EventKind::Create(notify::event::CreateKind::File),
+            paths: vec![path.to_path_buf()],
+            attrs: Default::default(),
+        };
+        
+        // Sidecar arriving before its asset
+        let event = FileSystemMonitor::convert_notify_event(create_event(&sidecar), SymlinkPolicy::Resolve);
+        assert!(matches!(event, Some(MonitorEvent::SidecarChanged { ref assets, .. }) if assets.is_empty()));
+        
+        // Sidecar arriving after its asset
+        let photo = dir.path().join("photo.jpg");
+        std::fs::write(&photo, b"jpeg").unwrap();
+        let event = FileSystemMonitor::convert_notify_event(create_event(&sidecar), SymlinkPolicy::Resolve);
+        assert!(matches!(event, Some(MonitorEvent::SidecarChanged { ref assets, .. }) if assets == &vec![photo.clone()]));
+        
+        let event = FileSystemMonitor::convert_notify_event(create_event(&photo), SymlinkPolicy::Resolve);
+        assert!(matches!(event, Some(MonitorEvent::FileCreated { .. })));
+    }
+    
+    /// Assets kept in memory, keyed by path
+    #[derive(Default)]
+    struct MemoryStore(std::sync::Mutex<std::collections::HashMap<PathBuf, Asset>>);
+    
+    #[async_trait]
+    impl AssetStore for MemoryStore {
+        async fn store(&self, asset: &Asset) -> DamResult<()> {
+            self.0.lock().unwrap().insert(asset.current_path.clone(), asset.clone());
+            Ok(())
+        }
+        
+        async fn find_by_path(&self, path: &Path) -> DamResult<Option<Asset>> {
+            Ok(self.0.lock().unwrap().get(path).cloned())
+        }
+    }
+    
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_sidecar_change_updates_asset() {
+        let dir = tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        let photo = root.join("photo.png");
+        image::RgbImage::new(4, 4).save(&photo).unwrap();
+        
+        let ingest_service = Arc::new(IngestService::new().unwrap());
+        let store = Arc::new(MemoryStore::default());
+        let mut monitor = FileSystemMonitor::new(ingest_service.clone()).unwrap();
+        monitor.set_asset_store(store.clone());
+        monitor.handle_event(&MonitorEvent::FileCreated { path: photo.clone() }).await.unwrap();
+        let stored = store.find_by_path(&photo).await.unwrap().unwrap();
+        assert!(stored.tags.is_empty());
+        
+        let sidecar = root.join("photo.xmp");
+        std::fs::write(&sidecar, r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF><rdf:Description>
+            <dc:subject><rdf:Bag><rdf:li>harbor</rdf:li></rdf:Bag></dc:subject>
+            </rdf:Description></rdf:RDF></x:xmpmeta>"#).unwrap();
+        monitor.handle_event(&MonitorEvent::SidecarChanged { sidecar, assets: vec![photo.clone()] }).await.unwrap();
+        
+        let updated = store.find_by_path(&photo).await.unwrap().unwrap();
+        assert_eq!(updated.id, stored.id);
+        assert_eq!(updated.tags, vec!["harbor".to_string()]);
+    }
+    
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_relocated_asset_stored_at_library_path() {
+        let dir = tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        let photo = root.join("inbox").join("photo.png");
+        std::fs::create_dir_all(photo.parent().unwrap()).unwrap();
+        image::RgbImage::new(4, 4).save(&photo).unwrap();
+        
+        let store = Arc::new(MemoryStore::default());
+        let mut monitor = FileSystemMonitor::new(Arc::new(IngestService::new().unwrap())).unwrap();
+        monitor.set_asset_store(store.clone());
+        monitor.set_import_policy(ImportPolicy::relocate_with_template(root.join("library"), "{type}/{filename}").unwrap());
+        monitor.handle_event(&MonitorEvent::FileCreated { path: photo.clone() }).await.unwrap();
+        
+        let relocated = root.join("library").join("images").join("photo.png");
+        assert!(relocated.exists());
+        assert!(store.find_by_path(&photo).await.unwrap().is_none());
+        assert_eq!(store.find_by_path(&relocated).await.unwrap().unwrap().original_path, photo);
+    }
 }
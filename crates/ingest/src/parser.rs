@@ -6,38 +6,138 @@
 use schema::{
     Asset, AssetMetadata, AssetType, DamResult,
     ImageMetadata, PsdLayer, ThreeDMetadata, BoundingBox, AnimationInfo,
-    AudioMetadata, VideoMetadata,
+    AudioMetadata, VideoMetadata, ArchiveMetadata, DocumentMetadata,
 };
+use std::collections::HashMap;
 use std::path::Path;
+use std::sync::Arc;
 use tokio::fs;
 use tracing::{debug, warn, error};
 use crate::error::IngestError;
+use crate::extractor::MetadataExtractor;
+use crate::formats::{self, is_in};
 use image::{io::Reader as ImageReader, GenericImageView};
 // use obj_rs as obj; // TODO: Fix obj-rs dependency issue
 
+/// Maximum number of archive entry names stored in metadata
+const MAX_ARCHIVE_ENTRIES: usize = 1000;
+
+/// Default number of bytes of a text document read for indexing
+const MAX_EXTRACTED_TEXT_BYTES: u64 = 1024 * 1024;
+
+/// Content extraction cap for asset types without their own cap (128MB)
+pub const DEFAULT_EXTRACTION_CAP: u64 = 128 * 1024 * 1024;
+
+/// Per-asset-type file size caps for content extraction
+/// 
+/// Assets over their cap are still ingested, with file size, format and
+/// timestamps, but their format-specific metadata is not parsed. Text
+/// documents are the exception: they are read up to the cap instead.
+/// Types without an entry use `DEFAULT_EXTRACTION_CAP`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtractionCaps {
+    caps: HashMap<AssetType, u64>,
+}
+
+impl ExtractionCaps {
+    /// Cap for an asset type in bytes
+    pub fn get(&self, asset_type: &AssetType) -> u64 {
+        self.caps.get(asset_type).copied().unwrap_or(DEFAULT_EXTRACTION_CAP)
+    }
+    
+    /// Set the cap of a single asset type
+    pub fn with(mut self, asset_type: AssetType, bytes: u64) -> Self {
+        self.caps.insert(asset_type, bytes);
+        self
+    }
+}
+
+impl Default for ExtractionCaps {
+    /// Audio and video parsing only reads headers and stream info, so they
+    /// get generous caps; documents are read up to 1MB
+    fn default() -> Self {
+        Self { caps: HashMap::new() }
+            .with(AssetType::Audio, 2 * 1024 * 1024 * 1024)
+            .with(AssetType::Video, 16 * 1024 * 1024 * 1024)
+            .with(AssetType::Document, MAX_EXTRACTED_TEXT_BYTES)
+    }
+}
+
 /// Service for parsing asset metadata
+#[derive(Clone)]
 pub struct AssetParser {
-    /// Maximum file size to read into memory for parsing (128MB)
-    max_file_size: u64,
+    /// Largest files whose content is extracted, per asset type
+    extraction_caps: ExtractionCaps,
+    /// Custom extractors run after the built-in parsers
+    extractors: Vec<Arc<dyn MetadataExtractor>>,
 }
 
 impl AssetParser {
     /// Create a new asset parser
     pub fn new() -> DamResult<Self> {
         Ok(Self {
-            max_file_size: 128 * 1024 * 1024, // 128MB
+            extraction_caps: ExtractionCaps::default(),
+            extractors: Vec::new(),
         })
     }
     
+    /// Use different content extraction caps
+    pub fn with_extraction_caps(mut self, caps: ExtractionCaps) -> Self {
+        self.set_extraction_caps(caps);
+        self
+    }
+    
+    /// Replace the content extraction caps
+    pub fn set_extraction_caps(&mut self, caps: ExtractionCaps) {
+        self.extraction_caps = caps;
+    }
+    
+    /// Content extraction caps in use
+    pub fn extraction_caps(&self) -> &ExtractionCaps {
+        &self.extraction_caps
+    }
+    
+    /// Add a custom metadata extractor
+    pub fn with_extractor(mut self, extractor: Arc<dyn MetadataExtractor>) -> Self {
+        self.register_extractor(extractor);
+        self
+    }
+    
+    /// Register a custom metadata extractor
+    /// 
+    /// Extractors run in registration order after the built-in parsers;
+    /// when two of them return the same key, the later one wins.
+    pub fn register_extractor(&mut self, extractor: Arc<dyn MetadataExtractor>) {
+        self.extractors.push(extractor);
+    }
+    
     /// Parse metadata from an asset
     pub async fn parse_metadata(&self, asset: &Asset) -> DamResult<AssetMetadata> {
+        let mut metadata = self.parse_builtin_metadata(asset).await;
+        
+        for extractor in &self.extractors {
+            if !extractor.supports(asset) {
+                continue;
+            }
+            match extractor.extract(asset).await {
+                Ok(fields) => metadata.custom.extend(fields),
+                Err(e) => warn!("Custom metadata extraction failed for {}: {}", asset.current_path.display(), e),
+            }
+        }
+        
+        Ok(metadata)
+    }
+    
+    /// Run the format-specific parser for the asset type
+    async fn parse_builtin_metadata(&self, asset: &Asset) -> AssetMetadata {
         let path = &asset.current_path;
         
-        // Check file size before attempting to parse
-        if asset.file_size > self.max_file_size {
-            warn!("File too large for metadata parsing: {} ({} bytes)", 
-                  path.display(), asset.file_size);
-            return Ok(AssetMetadata::default());
+        // Documents are read up to the cap; other types are skipped above it
+        let cap = self.extraction_caps.get(&asset.asset_type);
+        if asset.file_size > cap && asset.asset_type != AssetType::Document {
+            warn!("File too large for content extraction: {} ({} bytes, cap {} bytes)", 
+                  path.display(), asset.file_size, cap);
+            return AssetMetadata::default();
         }
         
         debug!("Parsing metadata for: {}", path.display());
@@ -47,6 +147,9 @@ impl AssetParser {
         match asset.asset_type {
             AssetType::Image => {
                 metadata.image = self.parse_image_metadata(path).await.ok();
+                if let Some(date) = self.parse_capture_date(path).await {
+                    metadata.custom.insert(crate::capture_date::CAPTURE_DATE_KEY.to_string(), date);
+                }
             }
             AssetType::ThreeD => {
                 metadata.three_d = self.parse_3d_metadata(path).await.ok();
@@ -57,12 +160,18 @@ impl AssetParser {
             AssetType::Video => {
                 metadata.video = self.parse_video_metadata(path).await.ok();
             }
+            AssetType::Archive => {
+                metadata.archive = self.parse_archive_metadata(path).await.ok();
+            }
+            AssetType::Document => {
+                metadata.document = self.parse_text_document(path, cap).await.ok();
+            }
             _ => {
                 debug!("No specific metadata parser for asset type: {:?}", asset.asset_type);
             }
         }
         
-        Ok(metadata)
+        metadata
     }
     
     /// Parse image metadata
@@ -74,11 +183,33 @@ impl AssetParser {
             .to_lowercase();
         
         match extension.as_str() {
-            "psd" | "psb" => self.parse_psd_metadata(path).await,
+            ext if is_in(formats::PSD_EXTENSIONS, ext) => self.parse_psd_metadata(path).await,
+            ext if is_in(formats::SVG_EXTENSIONS, ext) => self.parse_svg_metadata(path).await,
+            ext if formats::is_undecodable_image(ext) => Err(IngestError::metadata_extraction_failed(
+                path.to_path_buf(),
+                format!("No decoder for .{} images", ext),
+            ).into()),
             _ => self.parse_standard_image_metadata(path).await,
         }
     }
     
+    /// Read when an image was taken, from its EXIF or XMP data
+    async fn parse_capture_date(&self, path: &Path) -> Option<String> {
+        let path = path.to_path_buf();
+        let result = tokio::task::spawn_blocking(move || crate::capture_date::read_capture_date(&path)).await;
+        match result {
+            Ok(Ok(date)) => date,
+            Ok(Err(e)) => {
+                debug!("Could not read capture date: {}", e);
+                None
+            }
+            Err(e) => {
+                warn!("Capture date task failed: {}", e);
+                None
+            }
+        }
+    }
+    
     /// Parse standard image formats (PNG, JPEG, etc.)
     async fn parse_standard_image_metadata<P: AsRef<Path>>(&self, path: P) -> DamResult<ImageMetadata> {
         let path = path.as_ref();
@@ -103,6 +234,22 @@ impl AssetParser {
         // Try to determine color information from file format
         let (bit_depth, color_space, has_alpha) = self.detect_color_info(&extension);
         
+        let data = fs::read(path).await.unwrap_or_default();
+        
+        // Count frames for formats that can be animated
+        let frame_count = match extension.as_str() {
+            "gif" => count_gif_frames(&data),
+            "webp" => count_webp_frames(&data),
+            _ => None,
+        };
+        
+        // Scans and layered exports keep extra pages in further IFDs
+        let pages = if is_in(formats::TIFF_EXTENSIONS, &extension) {
+            crate::tiff::read_pages(&data).unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        
         Ok(ImageMetadata {
             width,
             height,
@@ -110,6 +257,36 @@ impl AssetParser {
             color_space,
             has_alpha,
             layers: None,
+            frame_count,
+            is_animated: frame_count.map(|count| count > 1).unwrap_or(false),
+            keywords: crate::keywords::read_embedded_keywords(&data),
+            page_count: (!pages.is_empty()).then_some(pages.len() as u32),
+            pages,
+        })
+    }
+    
+    /// Parse SVG dimensions from width/height or the viewBox
+    async fn parse_svg_metadata<P: AsRef<Path>>(&self, path: P) -> DamResult<ImageMetadata> {
+        let path = path.as_ref();
+        
+        let data = fs::read(path).await?;
+        let tree = crate::svg::parse_svg(&data)
+            .map_err(|_| IngestError::unsupported_format("svg", path.to_path_buf()))?;
+        
+        let (width, height) = crate::svg::svg_dimensions(&tree);
+        
+        Ok(ImageMetadata {
+            width,
+            height,
+            bit_depth: 8,
+            color_space: "RGB".to_string(),
+            has_alpha: true,
+            layers: None,
+            frame_count: None,
+            is_animated: false,
+            keywords: Vec::new(),
+            page_count: None,
+            pages: Vec::new(),
         })
     }
     
@@ -157,6 +334,11 @@ impl AssetParser {
             color_space,
             has_alpha,
             layers: if layers.is_empty() { None } else { Some(layers) },
+            frame_count: None,
+            is_animated: false,
+            keywords: crate::keywords::read_embedded_keywords(&psd_data),
+            page_count: None,
+            pages: Vec::new(),
         })
     }
     
@@ -169,9 +351,10 @@ impl AssetParser {
             .to_lowercase();
         
         match extension.as_str() {
-            "gltf" | "glb" => self.parse_gltf_metadata(path).await,
-            "obj" => self.parse_obj_metadata(path).await,
-            "blend" => self.parse_blend_metadata(path).await,
+            ext if is_in(formats::GLTF_EXTENSIONS, ext) => self.parse_gltf_metadata(path).await,
+            ext if is_in(formats::MESH_EXTENSIONS, ext) => self.parse_mesh_metadata(path).await,
+            ext if is_in(formats::BLEND_EXTENSIONS, ext) => self.parse_blend_metadata(path).await,
+            ext if is_in(formats::FBX_EXTENSIONS, ext) => self.parse_fbx_metadata(path).await,
             _ => {
                 // For unsupported 3D formats, return basic metadata
                 Ok(ThreeDMetadata {
@@ -187,10 +370,16 @@ impl AssetParser {
     }
     
     /// Parse glTF/GLB metadata
+    ///
+    /// Only the JSON document is parsed; buffers and textures are not loaded.
     async fn parse_gltf_metadata<P: AsRef<Path>>(&self, path: P) -> DamResult<ThreeDMetadata> {
         let path = path.as_ref();
         
-        let (gltf, _buffers, _images) = gltf::import(path)
+        let data = fs::read(path).await?;
+        let gltf = tokio::task::spawn_blocking(move || gltf::Gltf::from_slice(&data).map_err(|e| e.to_string()))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|parsed| parsed)
             .map_err(|e| IngestError::metadata_extraction_failed(
                 path.to_path_buf(),
                 format!("Failed to parse glTF: {}", e)
@@ -207,14 +396,14 @@ impl AssetParser {
         for mesh in gltf.meshes() {
             for primitive in mesh.primitives() {
                 if let Some(accessor) = primitive.get(&gltf::Semantic::Positions) {
-                    vertex_count += accessor.count() as u32;
+                    vertex_count = vertex_count.saturating_add(saturating_u32(accessor.count()));
                     
                     // Update bounding box - simplified without bounds check
                     // Note: accessor.bounds() may not be available in all gltf versions
                 }
                 
                 if let Some(indices) = primitive.indices() {
-                    face_count += (indices.count() / 3) as u32;
+                    face_count = face_count.saturating_add(saturating_u32(indices.count() / 3));
                 }
             }
         }
@@ -261,23 +450,47 @@ impl AssetParser {
         })
     }
     
-    /// Parse OBJ metadata
-    async fn parse_obj_metadata<P: AsRef<Path>>(&self, path: P) -> DamResult<ThreeDMetadata> {
+    /// Parse OBJ/STL metadata from the mesh used for previews
+    async fn parse_mesh_metadata<P: AsRef<Path>>(&self, path: P) -> DamResult<ThreeDMetadata> {
         let path = path.as_ref();
         
-        // TODO: Implement OBJ parsing once obj-rs dependency is fixed
-        warn!("OBJ parsing not fully implemented, returning basic metadata for: {}", path.display());
+        let owned = path.to_path_buf();
+        let mesh = tokio::task::spawn_blocking(move || crate::mesh::Mesh::load(&owned))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|loaded| loaded)
+            .map_err(|e| IngestError::metadata_extraction_failed(
+                path.to_path_buf(),
+                format!("Failed to parse mesh: {}", e)
+            ))?;
+        
+        if mesh.truncated {
+            debug!("Mesh {} exceeds the preview limits, bounds are partial", path.display());
+        }
         
         Ok(ThreeDMetadata {
-            vertex_count: None,
-            face_count: None,
+            vertex_count: Some(saturating_u32(mesh.vertex_count)),
+            face_count: Some(saturating_u32(mesh.face_count)),
             material_count: None,
-            bounds: None,
+            bounds: mesh.bounds(),
             animations: Vec::new(),
             textures: Vec::new(),
         })
     }
     
+    /// Parse FBX metadata (binary or ASCII)
+    async fn parse_fbx_metadata<P: AsRef<Path>>(&self, path: P) -> DamResult<ThreeDMetadata> {
+        let path = path.as_ref();
+        
+        let data = fs::read(path).await?;
+        
+        crate::fbx::parse_fbx(&data)
+            .map_err(|e| IngestError::metadata_extraction_failed(
+                path.to_path_buf(),
+                format!("Failed to parse FBX: {}", e)
+            ).into())
+    }
+    
     /// Parse Blender file metadata (basic)
     async fn parse_blend_metadata<P: AsRef<Path>>(&self, path: P) -> DamResult<ThreeDMetadata> {
         let _path = path.as_ref();
@@ -374,6 +587,71 @@ impl AssetParser {
         })
     }
     
+    /// List archive contents without extracting them
+    async fn parse_archive_metadata<P: AsRef<Path>>(&self, path: P) -> DamResult<ArchiveMetadata> {
+        let path = path.as_ref();
+        
+        // Archive reads block, so they run off the async runtime
+        let owned = path.to_path_buf();
+        let listing = tokio::task::spawn_blocking(move || list_archive(&owned))
+            .await
+            .unwrap_or_else(|e| Err(format!("Archive listing task failed: {}", e)));
+        
+        listing.map_err(|e| IngestError::metadata_extraction_failed(
+            path.to_path_buf(),
+            format!("Failed to list archive: {}", e)
+        ).into())
+    }
+    
+    /// Extract the text of plain-text and Markdown documents
+    /// 
+    /// Reads at most `max_bytes`. The encoding is taken from a byte order
+    /// mark or detected from the content, and recorded in the metadata.
+    async fn parse_text_document<P: AsRef<Path>>(&self, path: P, max_bytes: u64) -> DamResult<DocumentMetadata> {
+        use tokio::io::AsyncReadExt;
+        
+        let path = path.as_ref();
+        let extension = path.extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        
+        if !is_in(formats::TEXT_EXTENSIONS, &extension) {
+            return Err(IngestError::unsupported_format(extension, path.to_path_buf()).into());
+        }
+        let is_markdown = extension == "md";
+        
+        let file = fs::File::open(path).await?;
+        let mut data = Vec::new();
+        file.take(max_bytes.saturating_add(1)).read_to_end(&mut data).await?;
+        
+        let truncated = data.len() as u64 > max_bytes;
+        data.truncate(usize::try_from(max_bytes).unwrap_or(usize::MAX));
+        
+        let decoded = crate::encoding::decode_text(&data, truncated);
+        let mut text = decoded.text;
+        if truncated {
+            // Drop a multi-byte character cut in half by the limit
+            while text.ends_with(char::REPLACEMENT_CHARACTER) {
+                text.pop();
+            }
+        }
+        let text = text.trim_start_matches('\u{feff}');
+        
+        let extracted_text = if is_markdown {
+            strip_markdown(text)
+        } else {
+            text.to_string()
+        };
+        
+        Ok(DocumentMetadata {
+            word_count: extracted_text.split_whitespace().count(),
+            extracted_text,
+            truncated,
+            encoding: Some(decoded.encoding.to_string()),
+        })
+    }
+    
     /// Detect color information from file extension
     fn detect_color_info(&self, extension: &str) -> (u8, String, bool) {
         match extension {
@@ -381,13 +659,269 @@ impl AssetParser {
             "jpg" | "jpeg" => (8, "RGB".to_string(), false),
             "gif" => (8, "Indexed".to_string(), true),
             "bmp" => (8, "RGB".to_string(), false),
-            "tiff" => (8, "RGB".to_string(), true),
+            "tiff" | "tif" => (8, "RGB".to_string(), true),
             "webp" => (8, "RGB".to_string(), true),
+            // HDR formats store linear floating-point samples
+            "exr" => (32, "Linear RGB".to_string(), true),
+            "hdr" => (32, "Linear RGB".to_string(), false),
+            // Camera RAW sensor data is stored at up to 16 bits per sample
+            ext if is_in(formats::RAW_EXTENSIONS, ext) => (16, "RGB".to_string(), false),
             _ => (8, "RGB".to_string(), false),
         }
     }
 }
 
+/// Count image frames in a GIF by walking its block structure
+/// 
+/// Only block headers are read; no LZW data is decoded.
+pub fn count_gif_frames(data: &[u8]) -> Option<u32> {
+    if data.len() < 13 || !(data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a")) {
+        return None;
+    }
+    
+    // Skip header and logical screen descriptor (plus global color table)
+    let mut pos = 13;
+    let flags = data[10];
+    if flags & 0x80 != 0 {
+        pos += 3 * (1 << ((flags & 0x07) + 1));
+    }
+    
+    // Skip a chain of data sub-blocks, returning the position after the terminator
+    let skip_sub_blocks = |mut pos: usize| -> Option<usize> {
+        loop {
+            let len = *data.get(pos)? as usize;
+            pos += 1;
+            if len == 0 {
+                return Some(pos);
+            }
+            pos += len;
+        }
+    };
+    
+    let mut frames = 0u32;
+    loop {
+        match *data.get(pos)? {
+            // Extension: label byte followed by sub-blocks
+            0x21 => pos = skip_sub_blocks(pos + 2)?,
+            // Image descriptor
+            0x2C => {
+                frames += 1;
+                let local_flags = *data.get(pos + 9)?;
+                pos += 10;
+                if local_flags & 0x80 != 0 {
+                    pos += 3 * (1 << ((local_flags & 0x07) + 1));
+                }
+                // LZW minimum code size, then image data sub-blocks
+                pos = skip_sub_blocks(pos + 1)?;
+            }
+            // Trailer
+            0x3B => break,
+            _ => break,
+        }
+    }
+    
+    Some(frames)
+}
+
+/// Count frames in a WebP file from its RIFF chunks
+/// 
+/// Static WebP files report a single frame; animated ones count `ANMF` chunks.
+pub fn count_webp_frames(data: &[u8]) -> Option<u32> {
+    if data.len() < 12 || &data[0..4] != b"RIFF" || &data[8..12] != b"WEBP" {
+        return None;
+    }
+    
+    let mut pos = 12;
+    let mut animated = false;
+    let mut frames = 0u32;
+    
+    while pos + 8 <= data.len() {
+        let fourcc = &data[pos..pos + 4];
+        let size = u32::from_le_bytes(data[pos + 4..pos + 8].try_into().ok()?) as usize;
+        
+        match fourcc {
+            // VP8X flags: bit 1 marks animation
+            b"VP8X" => animated = data.get(pos + 8).map(|flags| flags & 0x02 != 0).unwrap_or(false),
+            b"ANMF" => frames += 1,
+            _ => {}
+        }
+        
+        // Chunks are padded to an even size
+        pos += 8 + size + (size & 1);
+    }
+    
+    Some(if animated { frames.max(1) } else { 1 })
+}
+
+/// Narrow a count read from a file to the `u32` metadata fields hold
+fn saturating_u32(count: usize) -> u32 {
+    u32::try_from(count).unwrap_or(u32::MAX)
+}
+
+/// List an archive's entries, choosing the reader by file name
+/// 
+/// Blocking; reads headers only, never entry contents.
+fn list_archive(path: &Path) -> Result<ArchiveMetadata, String> {
+    let filename = path.file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    
+    if filename.ends_with(".zip") {
+        list_zip_entries(path)
+    } else if filename.ends_with(".tar") {
+        std::fs::File::open(path)
+            .map_err(|e| e.to_string())
+            .and_then(list_tar_entries)
+    } else if filename.ends_with(".tar.gz") || filename.ends_with(".tgz") {
+        std::fs::File::open(path)
+            .map_err(|e| e.to_string())
+            .and_then(|file| list_tar_entries(flate2::read::GzDecoder::new(file)))
+    } else if filename.ends_with(".gz") {
+        list_gzip_entry(path)
+    } else {
+        Err(format!("Archive listing not supported for {}", filename))
+    }
+}
+
+/// List ZIP entries from the central directory
+fn list_zip_entries(path: &Path) -> Result<ArchiveMetadata, String> {
+    let file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| e.to_string())?;
+    
+    let mut metadata = ArchiveMetadata {
+        entry_count: archive.len(),
+        total_uncompressed_size: 0,
+        entries: Vec::new(),
+    };
+    
+    for index in 0..archive.len() {
+        // Raw access reads the header only, without decompressing
+        let entry = archive.by_index_raw(index).map_err(|e| e.to_string())?;
+        metadata.total_uncompressed_size += entry.size();
+        if metadata.entries.len() < MAX_ARCHIVE_ENTRIES {
+            metadata.entries.push(entry.name().to_string());
+        }
+    }
+    
+    Ok(metadata)
+}
+
+/// List TAR entries by walking the headers
+fn list_tar_entries<R: std::io::Read>(reader: R) -> Result<ArchiveMetadata, String> {
+    let mut archive = tar::Archive::new(reader);
+    let mut metadata = ArchiveMetadata {
+        entry_count: 0,
+        total_uncompressed_size: 0,
+        entries: Vec::new(),
+    };
+    
+    for entry in archive.entries().map_err(|e| e.to_string())? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        metadata.entry_count += 1;
+        metadata.total_uncompressed_size += entry.header().size().unwrap_or(0);
+        if metadata.entries.len() < MAX_ARCHIVE_ENTRIES {
+            let name = entry.path()
+                .map(|p| p.to_string_lossy().to_string())
+                .map_err(|e| e.to_string())?;
+            metadata.entries.push(name);
+        }
+    }
+    
+    Ok(metadata)
+}
+
+/// Reduce Markdown to its readable text
+/// 
+/// Drops headings markers, list and quote prefixes, code fences, rules and
+/// emphasis characters, and keeps only the text of links and images.
+pub fn strip_markdown(markdown: &str) -> String {
+    let mut lines = Vec::new();
+    
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+        
+        // Code fences and horizontal rules carry no text
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            continue;
+        }
+        if trimmed.len() >= 3
+            && trimmed.chars().all(|c| c == '-' || c == '*' || c == '_' || c == ' ')
+            && trimmed.chars().filter(|c| !c.is_whitespace()).count() >= 3
+        {
+            continue;
+        }
+        
+        let mut content = trimmed;
+        content = content.trim_start_matches('>').trim_start();
+        content = content.trim_start_matches('#').trim_start();
+        if let Some(rest) = content.strip_prefix("- ")
+            .or_else(|| content.strip_prefix("* "))
+            .or_else(|| content.strip_prefix("+ "))
+        {
+            content = rest;
+        } else if let Some((number, rest)) = content.split_once(". ") {
+            if !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()) {
+                content = rest;
+            }
+        }
+        
+        lines.push(strip_inline_markdown(content));
+    }
+    
+    lines.join("\n")
+}
+
+/// Strip inline Markdown: `[text](url)` and `![alt](url)` become their text,
+/// emphasis and code markers are removed
+fn strip_inline_markdown(line: &str) -> String {
+    let mut output = String::with_capacity(line.len());
+    let mut rest = line;
+    
+    while let Some(c) = rest.chars().next() {
+        if c == '!' && rest[1..].starts_with('[') {
+            rest = &rest[1..];
+            continue;
+        }
+        if c == '[' {
+            if let Some(close) = rest.find("](") {
+                if let Some(end) = rest[close..].find(')') {
+                    output.push_str(&rest[1..close]);
+                    rest = &rest[close + end + 1..];
+                    continue;
+                }
+            }
+        }
+        if !matches!(c, '*' | '`' | '~') {
+            output.push(c);
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    
+    output
+}
+
+/// Describe a plain gzip file as a single-entry archive
+fn list_gzip_entry(path: &Path) -> Result<ArchiveMetadata, String> {
+    use std::io::{Read, Seek, SeekFrom};
+    
+    let mut file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+    
+    // ISIZE footer: uncompressed size modulo 2^32
+    file.seek(SeekFrom::End(-4)).map_err(|e| e.to_string())?;
+    let mut footer = [0u8; 4];
+    file.read_exact(&mut footer).map_err(|e| e.to_string())?;
+    
+    let name = path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    
+    Ok(ArchiveMetadata {
+        entry_count: 1,
+        total_uncompressed_size: u32::from_le_bytes(footer) as u64,
+        entries: vec![name],
+    })
+}
+
 impl Default for AssetParser {
     fn default() -> Self {
         Self::new().expect("Failed to create AssetParser")
@@ -420,6 +954,178 @@ mod tests {
         assert_eq!(bit_depth, 8);
         assert_eq!(color_space, "RGB");
         assert!(!has_alpha);
+        
+        let (bit_depth, color_space, _) = parser.detect_color_info("exr");
+        assert_eq!(bit_depth, 32);
+        assert_eq!(color_space, "Linear RGB");
+    }
+    
+    #[tokio::test]
+    async fn test_tiff_pages() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("scan.tif");
+        image::RgbImage::new(8, 6).save(&path).unwrap();
+        
+        let parser = AssetParser::new().unwrap();
+        let metadata = parser.parse_image_metadata(&path).await.unwrap();
+        assert_eq!((metadata.width, metadata.height), (8, 6));
+        assert_eq!(metadata.page_count, Some(1));
+        assert_eq!(metadata.pages[0], schema::ImagePage { width: 8, height: 6, tiled: false });
+        
+        // Other formats have no pages
+        let path = dir.path().join("photo.png");
+        image::RgbImage::new(8, 6).save(&path).unwrap();
+        assert_eq!(parser.parse_image_metadata(&path).await.unwrap().page_count, None);
+    }
+    
+    #[tokio::test]
+    async fn test_tar_listing() {
+        let dir = tempdir().unwrap();
+        let archive_path = dir.path().join("bundle.tar");
+        
+        {
+            let file = std::fs::File::create(&archive_path).unwrap();
+            let mut builder = tar::Builder::new(file);
+            for name in ["textures/wood.png", "readme.txt"] {
+                let data = b"hello";
+                let mut header = tar::Header::new_gnu();
+                header.set_size(data.len() as u64);
+                header.set_mode(0o644);
+                header.set_cksum();
+                builder.append_data(&mut header, name, &data[..]).unwrap();
+            }
+            builder.finish().unwrap();
+        }
+        
+        let parser = AssetParser::new().unwrap();
+        let metadata = parser.parse_archive_metadata(&archive_path).await.unwrap();
+        
+        assert_eq!(metadata.entry_count, 2);
+        assert_eq!(metadata.total_uncompressed_size, 10);
+        assert_eq!(metadata.entries, vec!["textures/wood.png".to_string(), "readme.txt".to_string()]);
+    }
+    
+    #[test]
+    fn test_strip_markdown() {
+        let markdown = "# Project Notes\n\n> Quoted **bold** text\n\n- item with [a link](https://example.com)\n1. ![diagram](img.png) shows `code`\n---\n```rust\nlet snake_case = 1;\n```";
+        let text = strip_markdown(markdown);
+        
+        assert!(text.contains("Project Notes"));
+        assert!(text.contains("Quoted bold text"));
+        assert!(text.contains("item with a link"));
+        assert!(text.contains("diagram shows code"));
+        assert!(text.contains("let snake_case = 1;"));
+        assert!(!text.contains('#') && !text.contains("https") && !text.contains("```"));
+    }
+    
+    #[tokio::test]
+    async fn test_text_document_extraction() {
+        let dir = tempdir().unwrap();
+        let parser = AssetParser::new().unwrap();
+        
+        let notes = dir.path().join("notes.md");
+        tokio::fs::write(&notes, "## Harbor survey\nThe *lighthouse* needs paint.").await.unwrap();
+        let metadata = parser.parse_text_document(&notes, MAX_EXTRACTED_TEXT_BYTES).await.unwrap();
+        assert_eq!(metadata.extracted_text, "Harbor survey\nThe lighthouse needs paint.");
+        assert_eq!(metadata.word_count, 6);
+        assert!(!metadata.truncated);
+        
+        assert_eq!(metadata.encoding.as_deref(), Some("UTF-8"));
+        
+        // Invalid UTF-8 is decoded in the detected encoding instead of failing
+        let latin1 = dir.path().join("legacy.txt");
+        tokio::fs::write(&latin1, b"caf\xe9 menu").await.unwrap();
+        let metadata = parser.parse_text_document(&latin1, MAX_EXTRACTED_TEXT_BYTES).await.unwrap();
+        assert!(metadata.extracted_text.starts_with("caf"));
+        assert!(metadata.extracted_text.ends_with(" menu"));
+        
+        // Subtitles with a UTF-16 BOM
+        let subtitles = dir.path().join("episode.srt");
+        let mut data = vec![0xFF, 0xFE];
+        data.extend("1\n00:00:01,000 --> 00:00:02,000\nSchöne Grüße".encode_utf16().flat_map(u16::to_le_bytes));
+        tokio::fs::write(&subtitles, data).await.unwrap();
+        let metadata = parser.parse_text_document(&subtitles, MAX_EXTRACTED_TEXT_BYTES).await.unwrap();
+        assert!(metadata.extracted_text.ends_with("Schöne Grüße"));
+        assert_eq!(metadata.encoding.as_deref(), Some("UTF-16LE"));
+        
+        // Large files are capped
+        let large = dir.path().join("large.txt");
+        tokio::fs::write(&large, "word ".repeat(MAX_EXTRACTED_TEXT_BYTES as usize)).await.unwrap();
+        let metadata = parser.parse_text_document(&large, MAX_EXTRACTED_TEXT_BYTES).await.unwrap();
+        assert!(metadata.truncated);
+        assert!(metadata.extracted_text.len() as u64 <= MAX_EXTRACTED_TEXT_BYTES);
+    }
+    
+    #[tokio::test]
+    async fn test_extraction_caps() {
+        let dir = tempdir().unwrap();
+        let caps = ExtractionCaps::default()
+            .with(AssetType::Document, 10)
+            .with(AssetType::Archive, 10);
+        let parser = AssetParser::new().unwrap().with_extraction_caps(caps);
+        
+        // Over-cap documents are read up to the cap
+        let notes = dir.path().join("notes.txt");
+        tokio::fs::write(&notes, "harbor lighthouse survey").await.unwrap();
+        let mut asset = Asset::new(notes, AssetType::Document);
+        asset.file_size = 24;
+        let metadata = parser.parse_metadata(&asset).await.unwrap();
+        let document = metadata.document.unwrap();
+        assert_eq!(document.extracted_text, "harbor lig");
+        assert!(document.truncated);
+        
+        // Other over-cap types are not parsed at all
+        let archive_path = dir.path().join("bundle.tar");
+        {
+            let file = std::fs::File::create(&archive_path).unwrap();
+            let mut builder = tar::Builder::new(file);
+            let mut header = tar::Header::new_gnu();
+            header.set_size(5);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, "readme.txt", &b"hello"[..]).unwrap();
+            builder.finish().unwrap();
+        }
+        let mut asset = Asset::new(archive_path.clone(), AssetType::Archive);
+        asset.file_size = std::fs::metadata(&archive_path).unwrap().len();
+        assert!(parser.parse_metadata(&asset).await.unwrap().archive.is_none());
+        
+        let parser = AssetParser::new().unwrap();
+        assert!(parser.parse_metadata(&asset).await.unwrap().archive.is_some());
+        assert_eq!(parser.extraction_caps().get(&AssetType::Image), DEFAULT_EXTRACTION_CAP);
+    }
+    
+    #[test]
+    fn test_gif_frame_count() {
+        // 1x1 GIF89a without a global color table and two image descriptors
+        let mut gif = b"GIF89a".to_vec();
+        gif.extend_from_slice(&[1, 0, 1, 0, 0x00, 0, 0]);
+        for _ in 0..2 {
+            // Graphic control extension
+            gif.extend_from_slice(&[0x21, 0xF9, 4, 0, 10, 0, 0, 0]);
+            // Image descriptor, LZW code size and one data sub-block
+            gif.extend_from_slice(&[0x2C, 0, 0, 0, 0, 1, 0, 1, 0, 0x00]);
+            gif.extend_from_slice(&[2, 2, 0x4C, 0x01, 0]);
+        }
+        gif.push(0x3B);
+        
+        assert_eq!(count_gif_frames(&gif), Some(2));
+        assert_eq!(count_gif_frames(b"not a gif"), None);
+    }
+    
+    #[test]
+    fn test_webp_frame_count() {
+        let mut webp = b"RIFF\0\0\0\0WEBP".to_vec();
+        webp.extend_from_slice(b"VP8X");
+        webp.extend_from_slice(&10u32.to_le_bytes());
+        webp.extend_from_slice(&[0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        for _ in 0..3 {
+            webp.extend_from_slice(b"ANMF");
+            webp.extend_from_slice(&2u32.to_le_bytes());
+            webp.extend_from_slice(&[0, 0]);
+        }
+        
+        assert_eq!(count_webp_frames(&webp), Some(3));
     }
     
     #[tokio::test]
@@ -0,0 +1,133 @@
+//! Canonical asset paths
+//!
+//! The same file can be reached as `./shots/a.png`, `/abs/shots/a.png` or
+//! through a symlink. Ingestion, the monitor and duplicate checks all store
+//! and compare the canonical absolute form so one file maps to one asset.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+
+/// How symlinks in asset paths are treated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SymlinkPolicy {
+    /// Resolve symlinks to the file they point at, so a file reached
+    /// through several links is one asset
+    #[default]
+    Resolve,
+    /// Keep symlinks in the path, only making it absolute and normalized
+    Preserve,
+}
+
+/// Canonical absolute form of a path
+///
+/// Falls back to the lexically normalized absolute path when the file no
+/// longer exists (e.g. for deletion events) or cannot be resolved.
+pub fn canonicalize_path<P: AsRef<Path>>(path: P, policy: SymlinkPolicy) -> PathBuf {
+    let path = path.as_ref();
+    
+    if policy == SymlinkPolicy::Resolve {
+        if let Ok(canonical) = std::fs::canonicalize(path) {
+            return canonical;
+        }
+    }
+    
+    absolute_path(path)
+}
+
+/// Absolute path with `.` and `..` removed, without touching the file system
+///
+/// Same as `std::path::absolute`, which is newer than the workspace MSRV.
+pub fn absolute_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let path = path.as_ref();
+    let joined = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        match std::env::current_dir() {
+            Ok(cwd) => cwd.join(path),
+            Err(_) => path.to_path_buf(),
+        }
+    };
+    
+    let mut normalized = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Whether a path below `root` is reached through a symlinked folder
+///
+/// Only the folders between `root` and the file are checked, so a
+/// symlinked file directly in a folder does not count. Paths outside
+/// `root` are never below a link.
+pub fn passes_through_symlink(root: &Path, path: &Path) -> bool {
+    let Ok(relative) = path.strip_prefix(root) else {
+        return false;
+    };
+    let mut current = root.to_path_buf();
+    let mut components = relative.components().peekable();
+    while let Some(component) = components.next() {
+        current.push(component);
+        // The last component is the file itself
+        if components.peek().is_none() {
+            break;
+        }
+        if std::fs::symlink_metadata(&current).is_ok_and(|metadata| metadata.file_type().is_symlink()) {
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_canonicalize_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        std::fs::create_dir(root.join("shots")).unwrap();
+        let file = root.join("shots/a.png");
+        std::fs::write(&file, b"png").unwrap();
+        
+        // Different spellings of the same file agree
+        let dotted = root.join("shots/./../shots/a.png");
+        assert_eq!(canonicalize_path(&dotted, SymlinkPolicy::Resolve), file);
+        assert_eq!(canonicalize_path(&dotted, SymlinkPolicy::Preserve), file);
+        
+        // Deleted files still get an absolute, normalized path
+        std::fs::remove_file(&file).unwrap();
+        assert_eq!(canonicalize_path(&dotted, SymlinkPolicy::Resolve), file);
+        
+        let relative = absolute_path("shots/../a.png");
+        assert!(relative.is_absolute());
+        assert!(relative.ends_with("a.png") && !relative.ends_with("shots/a.png"));
+    }
+    
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_policy() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        let file = root.join("a.png");
+        let link = root.join("link.png");
+        std::fs::write(&file, b"png").unwrap();
+        std::os::unix::fs::symlink(&file, &link).unwrap();
+        
+        assert_eq!(canonicalize_path(&link, SymlinkPolicy::Resolve), file);
+        assert_eq!(canonicalize_path(&link, SymlinkPolicy::Preserve), link);
+        
+        std::fs::create_dir(root.join("shots")).unwrap();
+        std::os::unix::fs::symlink(root.join("shots"), root.join("linked")).unwrap();
+        assert!(passes_through_symlink(&root, &root.join("linked/b.png")));
+        assert!(!passes_through_symlink(&root, &root.join("shots/b.png")));
+        assert!(!passes_through_symlink(&root, &link));
+    }
+}
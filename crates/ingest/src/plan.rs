@@ -0,0 +1,129 @@
+//! Dry-run import plans
+//!
+//! `IngestService::ingest_directory_dry_run` classifies every file of a
+//! tree the way `ingest_directory` would treat it, without parsing
+//! metadata, generating previews or creating assets, so a large import can
+//! be reviewed before it is started. Like the import itself, the plan
+//! groups numbered frames into sequences when sequence detection is on and
+//! imports files with repeated content as assets of their own; those are
+//! only flagged.
+
+use crate::report::AccessError;
+use schema::AssetType;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Files kept as examples for each action
+pub const PLAN_SAMPLE_SIZE: usize = 20;
+
+/// What ingestion would do with a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PlannedAction {
+    /// Imported as a new asset
+    Import,
+    /// Skipped as hidden, temporary or a sidecar
+    Ignored,
+    /// Skipped because the format is not supported
+    Unsupported,
+    /// Imported as a frame of a sequence asset
+    SequenceFrame,
+}
+
+/// A file and what would happen to it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedFile {
+    pub path: PathBuf,
+    pub action: PlannedAction,
+    pub asset_type: AssetType,
+    pub file_size: u64,
+    /// An earlier imported file with the same content; both are imported
+    pub duplicate_of: Option<PathBuf>,
+    /// Pattern of the sequence a frame is imported with, e.g. `shot_####.png`
+    #[serde(default)]
+    pub sequence: Option<String>,
+}
+
+/// Result of a dry-run import
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IngestPlan {
+    pub root: PathBuf,
+    pub total_files: usize,
+    /// Assets that would be created: files imported alone and sequences
+    pub to_import: usize,
+    pub ignored: usize,
+    pub unsupported: usize,
+    /// Imported files whose content repeats an earlier imported file
+    pub duplicates: usize,
+    /// Sequence assets, each counted once in `to_import`
+    #[serde(default)]
+    pub sequences: usize,
+    /// Frames imported as part of a sequence
+    #[serde(default)]
+    pub sequence_frames: usize,
+    /// Bytes of the files that would be imported, sequence frames included
+    pub import_bytes: u64,
+    /// Assets that would be created, per asset type
+    pub by_type: HashMap<AssetType, usize>,
+    /// Up to `PLAN_SAMPLE_SIZE` files per action, and as many duplicates
+    pub samples: Vec<PlannedFile>,
+    /// Folders the import could not read, with everything below them
+    #[serde(default)]
+    pub inaccessible: Vec<AccessError>,
+}
+
+impl IngestPlan {
+    pub(crate) fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            ..Self::default()
+        }
+    }
+    
+    /// Count a classified file, keeping it as a sample while there is room
+    pub(crate) fn record(&mut self, file: PlannedFile) {
+        self.total_files += 1;
+        match file.action {
+            PlannedAction::Import => {
+                self.add_asset(&file.asset_type);
+                self.import_bytes += file.file_size;
+                if file.duplicate_of.is_some() {
+                    self.duplicates += 1;
+                }
+            }
+            PlannedAction::Ignored => self.ignored += 1,
+            PlannedAction::Unsupported => self.unsupported += 1,
+            PlannedAction::SequenceFrame => {
+                self.sequence_frames += 1;
+                self.import_bytes += file.file_size;
+            }
+        }
+        
+        let room = self.samples_of(file.action).count() < PLAN_SAMPLE_SIZE
+            || (file.duplicate_of.is_some() && self.duplicate_samples().count() < PLAN_SAMPLE_SIZE);
+        if room {
+            self.samples.push(file);
+        }
+    }
+    
+    /// Count a sequence asset; its frames are recorded one by one
+    pub(crate) fn record_sequence(&mut self, asset_type: &AssetType) {
+        self.sequences += 1;
+        self.add_asset(asset_type);
+    }
+    
+    fn add_asset(&mut self, asset_type: &AssetType) {
+        self.to_import += 1;
+        *self.by_type.entry(asset_type.clone()).or_insert(0) += 1;
+    }
+    
+    /// Sample files for one action
+    pub fn samples_of(&self, action: PlannedAction) -> impl Iterator<Item = &PlannedFile> {
+        self.samples.iter().filter(move |file| file.action == action)
+    }
+    
+    /// Sample imported files whose content repeats an earlier one
+    pub fn duplicate_samples(&self) -> impl Iterator<Item = &PlannedFile> {
+        self.samples.iter().filter(|file| file.duplicate_of.is_some())
+    }
+}
@@ -0,0 +1,289 @@
+//! Import policies for auto-ingested files
+//!
+//! Decides whether a newly ingested file stays where it was found or is
+//! relocated into an organized library tree.
+
+use chrono::Datelike;
+use schema::{Asset, AssetType, DamError, DamResult};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use crate::paths::{canonicalize_path, SymlinkPolicy};
+
+/// Default layout for relocated assets
+pub const DEFAULT_PATH_TEMPLATE: &str = "{type}/{year}/{month}/{filename}";
+
+/// Highest counter tried when resolving name collisions
+const MAX_COLLISION_COUNTER: u32 = 10_000;
+
+/// What happens to a file after it has been ingested
+#[derive(Debug, Clone, Default)]
+pub enum ImportPolicy {
+    /// Leave files where they are (the default)
+    #[default]
+    InPlace,
+    
+    /// Move files into `library_root` following `template`
+    ///
+    /// Supported placeholders: `{type}`, `{year}`, `{month}`, `{day}`,
+    /// `{filename}`, `{stem}` and `{ext}`.
+    Relocate {
+        library_root: PathBuf,
+        template: String,
+    },
+}
+
+impl ImportPolicy {
+    /// Relocate into a library root using the default template
+    pub fn relocate<P: Into<PathBuf>>(library_root: P) -> Self {
+        Self::Relocate {
+            library_root: library_root.into(),
+            template: DEFAULT_PATH_TEMPLATE.to_string(),
+        }
+    }
+    
+    /// Relocate into a library root using a custom template
+    pub fn relocate_with_template<P: Into<PathBuf>, S: Into<String>>(library_root: P, template: S) -> DamResult<Self> {
+        let template = template.into();
+        
+        if !template.contains("{filename}") && !template.contains("{stem}") {
+            return Err(DamError::configuration(format!(
+                "Import path template must contain {{filename}} or {{stem}}: {}", template
+            )));
+        }
+        
+        Ok(Self::Relocate {
+            library_root: library_root.into(),
+            template,
+        })
+    }
+    
+    /// Whether this policy moves files
+    pub fn relocates(&self) -> bool {
+        matches!(self, Self::Relocate { .. })
+    }
+    
+    /// Check if a path is already inside the library root
+    ///
+    /// Used to avoid re-processing files the policy itself moved. A
+    /// relative library root also matches the canonical paths reported by
+    /// the monitor.
+    pub fn is_in_library(&self, path: &Path) -> bool {
+        match self {
+            Self::InPlace => false,
+            Self::Relocate { library_root, .. } => {
+                path.starts_with(library_root)
+                    || path.starts_with(canonicalize_path(library_root, SymlinkPolicy::Resolve))
+            }
+        }
+    }
+    
+    /// Target path for an asset, before collision handling
+    ///
+    /// Returns `None` for in-place policies.
+    pub fn target_path(&self, asset: &Asset) -> Option<PathBuf> {
+        let Self::Relocate { library_root, template } = self else {
+            return None;
+        };
+        
+        let filename = asset.filename().unwrap_or("unnamed").to_string();
+        let stem = asset.current_path.file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| filename.clone());
+        let ext = asset.extension().unwrap_or("").to_string();
+        let date = asset.modified_at;
+        
+        let rendered = template
+            .replace("{type}", type_folder(&asset.asset_type))
+            .replace("{year}", &format!("{:04}", date.year()))
+            .replace("{month}", &format!("{:02}", date.month()))
+            .replace("{day}", &format!("{:02}", date.day()))
+            .replace("{filename}", &filename)
+            .replace("{stem}", &stem)
+            .replace("{ext}", &ext);
+        
+        // Drop empty, current and parent components so templates cannot escape the root
+        let relative: PathBuf = rendered
+            .split(['/', '\\'])
+            .filter(|part| !part.is_empty() && *part != "." && *part != "..")
+            .collect();
+        
+        Some(library_root.join(relative))
+    }
+}
+
+/// Folder name used for `{type}`
+fn type_folder(asset_type: &AssetType) -> &'static str {
+    match asset_type {
+        AssetType::Image => "images",
+        AssetType::ThreeD => "models",
+        AssetType::Audio => "audio",
+        AssetType::Video => "video",
+        AssetType::Document => "documents",
+        AssetType::Archive => "archives",
+        AssetType::Unknown => "other",
+    }
+}
+
+/// How much work ingest does per file
+///
+/// A fast import makes a large folder browsable quickly: files get a
+/// format, size and preview, and are marked `needs_deep_processing`.
+/// Metadata parsing, integrity checks and sidecars are left to
+/// `IngestService::complete_asset`, run later by a background pass.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum IngestMode {
+    /// Everything at import time (the default)
+    #[default]
+    Full,
+    /// Previews and basic file information only
+    Fast,
+}
+
+/// Which dot-prefixed files and folders are ingested
+///
+/// Hidden entries are skipped by default. Names matching an `include`
+/// pattern are ingested anyway; patterns are matched against a single file
+/// or folder name and may contain `*` wildcards, e.g. `.assets` or `.ref*`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HiddenFilePolicy {
+    /// Skip names starting with `.` unless they match `include`
+    pub skip_hidden: bool,
+    /// Dot-patterns ingested even when hidden files are skipped
+    pub include: Vec<String>,
+}
+
+impl Default for HiddenFilePolicy {
+    fn default() -> Self {
+        Self {
+            skip_hidden: true,
+            include: Vec::new(),
+        }
+    }
+}
+
+impl HiddenFilePolicy {
+    /// Ingest hidden files and folders like any other
+    pub fn include_all() -> Self {
+        Self {
+            skip_hidden: false,
+            include: Vec::new(),
+        }
+    }
+    
+    /// Also ingest names matching a dot-pattern
+    pub fn with_include<S: Into<String>>(mut self, pattern: S) -> Self {
+        self.include.push(pattern.into());
+        self
+    }
+    
+    /// Whether a file or folder name is skipped as hidden
+    pub fn skips(&self, name: &str) -> bool {
+        self.skip_hidden
+            && name.starts_with('.')
+            && !self.include.iter().any(|pattern| wildcard_match(pattern, name))
+    }
+}
+
+/// Match a name against a pattern where `*` stands for any run of characters
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No wildcard: the whole name must match
+        return rest.is_empty();
+    };
+    
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// Names to try for a file, appending ` (n)` to the stem after the first
+/// 
+/// Whether a name is free is only known when the file is created there, so
+/// callers try each in turn rather than checking first.
+pub fn collision_candidates(path: &Path) -> impl Iterator<Item = PathBuf> + '_ {
+    let stem = path.file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let ext = path.extension().map(|e| e.to_string_lossy().to_string());
+    
+    let numbered = (1..=MAX_COLLISION_COUNTER).map(move |counter| {
+        let name = match &ext {
+            Some(ext) => format!("{} ({}).{}", stem, counter, ext),
+            None => format!("{} ({})", stem, counter),
+        };
+        path.with_file_name(name)
+    });
+    std::iter::once(path.to_path_buf()).chain(numbered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use tempfile::tempdir;
+    
+    #[test]
+    fn test_template_rendering() {
+        let mut asset = Asset::new(PathBuf::from("/inbox/sunset.png"), AssetType::Image);
+        asset.modified_at = Utc.with_ymd_and_hms(2024, 3, 7, 12, 0, 0).unwrap();
+        
+        assert!(ImportPolicy::InPlace.target_path(&asset).is_none());
+        
+        let policy = ImportPolicy::relocate("/library");
+        assert_eq!(
+            policy.target_path(&asset).unwrap(),
+            PathBuf::from("/library/images/2024/03/sunset.png")
+        );
+        
+        let policy = ImportPolicy::relocate_with_template("/library", "../{year}/{stem}-{day}.{ext}").unwrap();
+        assert_eq!(policy.target_path(&asset).unwrap(), PathBuf::from("/library/2024/sunset-07.png"));
+        
+        assert!(ImportPolicy::relocate_with_template("/library", "{type}/{year}").is_err());
+    }
+    
+    #[test]
+    fn test_collision_counter() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("photo.jpg");
+        
+        let candidates: Vec<PathBuf> = collision_candidates(&path).take(3).collect();
+        assert_eq!(candidates, vec![
+            path.clone(),
+            dir.path().join("photo (1).jpg"),
+            dir.path().join("photo (2).jpg"),
+        ]);
+        assert_eq!(collision_candidates(&path).count(), MAX_COLLISION_COUNTER as usize + 1);
+    }
+    
+    #[test]
+    fn test_hidden_file_policy() {
+        let policy = HiddenFilePolicy::default();
+        assert!(policy.skips(".DS_Store"));
+        assert!(policy.skips(".git"));
+        assert!(!policy.skips("photo.png"));
+        
+        let policy = policy.with_include(".assets").with_include(".ref*.png");
+        assert!(!policy.skips(".assets"));
+        assert!(!policy.skips(".reference-01.png"));
+        assert!(policy.skips(".assets-old"));
+        assert!(policy.skips(".ref.jpg"));
+        
+        assert!(!HiddenFilePolicy::include_all().skips(".DS_Store"));
+        
+        // Missing fields keep their defaults when read from settings
+        let policy: HiddenFilePolicy = serde_json::from_str(r#"{"include": [".assets"]}"#).unwrap();
+        assert_eq!(policy, HiddenFilePolicy::default().with_include(".assets"));
+    }
+}
@@ -2,14 +2,143 @@
 //! 
 //! This module generates previews and thumbnails for various asset types.
 
-use schema::{Asset, AssetType, PreviewInfo, DamResult};
+use schema::{Asset, AssetType, IntegrityStatus, PreviewInfo, DamResult};
 use std::path::{Path, PathBuf};
 use chrono::Utc;
 use tracing::{debug, warn, error};
 use crate::error::IngestError;
-use image::GenericImageView;
+use crate::waveform;
+use crate::embedded;
+use crate::formats;
+use crate::integrity;
+use crate::video;
+use crate::large_image::{self, DEFAULT_LARGE_IMAGE_PIXELS};
+use image::{AnimationDecoder, GenericImageView};
+
+/// Which frame of an animated image to use for its thumbnail
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RepresentativeFrame {
+    /// The first frame, as a static decoder would show it
+    First,
+    
+    /// The middle frame, which is usually more representative of the content
+    #[default]
+    Middle,
+}
+
+/// Image format of generated thumbnails
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PreviewFormat {
+    /// JPEG for opaque images, PNG for images with transparency
+    #[default]
+    Auto,
+    
+    /// Smallest files; transparency is flattened onto the preview background
+    Jpeg,
+    
+    /// Lossless with transparency, best for logos, icons and line art
+    Png,
+    
+    /// Lossless WebP with transparency
+    WebP,
+}
+
+impl PreviewFormat {
+    /// Extensions of all formats a preview may be stored in
+    pub const EXTENSIONS: &'static [&'static str] = &["jpg", "png", "webp"];
+    
+    /// Concrete format for an image, resolving `Auto` by its transparency
+    pub fn resolve(self, has_transparency: bool) -> Self {
+        match self {
+            PreviewFormat::Auto if has_transparency => PreviewFormat::Png,
+            PreviewFormat::Auto => PreviewFormat::Jpeg,
+            format => format,
+        }
+    }
+    
+    /// File extension of previews in this format (`jpg` for `Auto`)
+    pub fn extension(self) -> &'static str {
+        match self {
+            PreviewFormat::Auto | PreviewFormat::Jpeg => "jpg",
+            PreviewFormat::Png => "png",
+            PreviewFormat::WebP => "webp",
+        }
+    }
+}
+
+/// What transparent areas of a preview are shown over
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PreviewBackground {
+    /// Keep transparency where the format allows it; JPEG previews use white
+    #[default]
+    Transparent,
+    
+    /// Flatten onto a flat RGB color
+    Color([u8; 3]),
+    
+    /// Flatten onto a light gray checkerboard, as image editors show alpha
+    Checkerboard,
+}
+
+impl PreviewBackground {
+    /// Plain white background
+    pub const WHITE: Self = PreviewBackground::Color([255, 255, 255]);
+    
+    /// Whether images are flattened even when the format could keep alpha
+    fn flattens(self) -> bool {
+        !matches!(self, PreviewBackground::Transparent)
+    }
+    
+    /// Background color at a pixel
+    fn color_at(self, x: u32, y: u32) -> [u8; 3] {
+        match self {
+            PreviewBackground::Transparent => [255, 255, 255],
+            PreviewBackground::Color(color) => color,
+            PreviewBackground::Checkerboard if (x / CHECKERBOARD_CELL + y / CHECKERBOARD_CELL) % 2 == 0 => [255, 255, 255],
+            PreviewBackground::Checkerboard => [204, 204, 204],
+        }
+    }
+    
+    /// Composite an RGBA image onto this background
+    pub fn composite(self, image: &image::RgbaImage) -> image::RgbImage {
+        image::RgbImage::from_fn(image.width(), image.height(), |x, y| {
+            let source = image.get_pixel(x, y);
+            let background = self.color_at(x, y);
+            let alpha = source[3] as u32;
+            let blend = |channel: usize| {
+                ((source[channel] as u32 * alpha + background[channel] as u32 * (255 - alpha)) / 255) as u8
+            };
+            image::Rgb([blend(0), blend(1), blend(2)])
+        })
+    }
+}
+
+/// Edge length in pixels of checkerboard background squares
+const CHECKERBOARD_CELL: u32 = 8;
+
+/// Separator between the asset ID and the size suffix of additional preview
+/// sizes, as in `<asset id>_1024.webp`
+pub const PREVIEW_SIZE_SEPARATOR: char = '_';
+
+/// Exposure scale applied before tone mapping HDR images
+const HDR_EXPOSURE: f32 = 1.0;
+
+/// Display gamma used when converting linear HDR data to 8-bit
+const DISPLAY_GAMMA: f32 = 2.2;
+
+/// Default location for generated previews
+/// 
+/// Uses the platform data directory (e.g. `~/.local/share/dam/previews` on
+/// Linux) so previews are found regardless of the working directory. Falls
+/// back to `./previews` only when no data directory can be determined.
+pub fn default_preview_dir() -> PathBuf {
+    dirs::data_dir()
+        .map(|dir| dir.join("dam").join("previews"))
+        .unwrap_or_else(|| PathBuf::from("previews"))
+}
 
 /// Service for generating asset previews
+#[derive(Clone)]
 pub struct PreviewGenerator {
     /// Directory where previews are stored
     preview_dir: PathBuf,
@@ -19,19 +148,37 @@ pub struct PreviewGenerator {
     
     /// JPEG quality for generated previews (0-100)
     jpeg_quality: u8,
+    
+    /// Frame used for animated GIF/WebP thumbnails
+    representative_frame: RepresentativeFrame,
+    
+    /// Format thumbnails are written in
+    format: PreviewFormat,
+    
+    /// Background transparent images are flattened onto
+    background: PreviewBackground,
+    
+    /// Pixel count above which images are decoded at reduced size
+    large_image_pixels: u64,
+    
+    /// Largest `(rows, columns)` of the contact sheets made for videos
+    contact_sheet: Option<(u32, u32)>,
 }
 
 impl PreviewGenerator {
-    /// Create a new preview generator
+    /// Create a new preview generator storing previews in `default_preview_dir()`
     pub fn new() -> DamResult<Self> {
-        let preview_dir = std::env::current_dir()
-            .unwrap_or_default()
-            .join("previews");
+        let preview_dir = default_preview_dir();
         
         Ok(Self {
             preview_dir,
             max_preview_size: (512, 512),
             jpeg_quality: 85,
+            representative_frame: RepresentativeFrame::default(),
+            format: PreviewFormat::default(),
+            background: PreviewBackground::default(),
+            large_image_pixels: DEFAULT_LARGE_IMAGE_PIXELS,
+            contact_sheet: None,
         })
     }
     
@@ -45,9 +192,69 @@ impl PreviewGenerator {
             preview_dir: preview_dir.into(),
             max_preview_size: max_size,
             jpeg_quality,
+            representative_frame: RepresentativeFrame::default(),
+            format: PreviewFormat::default(),
+            background: PreviewBackground::default(),
+            large_image_pixels: DEFAULT_LARGE_IMAGE_PIXELS,
+            contact_sheet: None,
         })
     }
     
+    /// Choose which frame of animated images is used for thumbnails
+    pub fn with_representative_frame(mut self, frame: RepresentativeFrame) -> Self {
+        self.representative_frame = frame;
+        self
+    }
+    
+    /// Choose the thumbnail format
+    pub fn with_format(mut self, format: PreviewFormat) -> Self {
+        self.format = format;
+        self
+    }
+    
+    /// Choose what transparent areas are shown over
+    /// 
+    /// With the default `Transparent`, `Auto` previews of transparent
+    /// images are PNGs keeping their alpha. A color or checkerboard makes
+    /// `Auto` write flattened JPEGs instead. PNG and WebP previews always
+    /// keep alpha; JPEG previews are always flattened.
+    pub fn with_background(mut self, background: PreviewBackground) -> Self {
+        self.set_background(background);
+        self
+    }
+    
+    /// Change what transparent areas are shown over
+    pub fn set_background(&mut self, background: PreviewBackground) {
+        self.background = background;
+    }
+    
+    /// Decode images with more pixels than this at reduced size
+    /// 
+    /// Keeps huge panoramas and scans from being decoded at full
+    /// resolution just to make a thumbnail.
+    pub fn with_large_image_threshold(mut self, pixels: u64) -> Self {
+        self.set_large_image_threshold(pixels);
+        self
+    }
+    
+    /// Change the pixel count above which images are decoded at reduced size
+    pub fn set_large_image_threshold(&mut self, pixels: u64) {
+        self.large_image_pixels = pixels;
+    }
+    
+    /// Preview videos with a contact sheet of up to `rows * cols` frames
+    /// 
+    /// Off by default, since it needs ffmpeg; videos then get a placeholder.
+    pub fn with_contact_sheet(mut self, rows: u32, cols: u32) -> Self {
+        self.set_contact_sheet(Some((rows, cols)));
+        self
+    }
+    
+    /// Change the contact sheet grid of video previews, or turn them off
+    pub fn set_contact_sheet(&mut self, grid: Option<(u32, u32)>) {
+        self.contact_sheet = grid;
+    }
+    
     /// Generate preview for an asset
     pub async fn generate_preview(&self, asset: &Asset) -> DamResult<PreviewInfo> {
         debug!("Generating preview for: {}", asset.current_path.display());
@@ -55,7 +262,7 @@ impl PreviewGenerator {
         // Ensure preview directory exists
         tokio::fs::create_dir_all(&self.preview_dir).await?;
         
-        match asset.asset_type {
+        let preview = match asset.asset_type {
             AssetType::Image => self.generate_image_preview(asset).await,
             AssetType::ThreeD => self.generate_3d_preview(asset).await,
             AssetType::Audio => self.generate_audio_preview(asset).await,
@@ -64,21 +271,69 @@ impl PreviewGenerator {
                 // For unsupported types, generate a generic icon
                 self.generate_generic_preview(asset).await
             }
-        }
+        }?;
+        
+        // A previous preview in another format would otherwise linger
+        self.remove_other_formats(&asset.id, &preview.thumbnail_path).await;
+        Ok(preview)
     }
     
     /// Generate preview for image assets
     async fn generate_image_preview(&self, asset: &Asset) -> DamResult<PreviewInfo> {
         let input_path = &asset.current_path;
-        let preview_filename = format!("{}.jpg", asset.id);
-        let preview_path = self.preview_dir.join(&preview_filename);
         
-        // Load and resize the image
-        let img = image::open(input_path)
-            .map_err(|e| IngestError::preview_generation_failed(
-                input_path.clone(),
-                format!("Failed to open image: {}", e)
-            ))?;
+        let is_svg = formats::is_in(formats::SVG_EXTENSIONS, &asset.format.extension)
+            || asset.extension().map(|ext| formats::is_in(formats::SVG_EXTENSIONS, ext)).unwrap_or(false);
+        if is_svg {
+            return self.generate_svg_preview(asset).await;
+        }
+        
+        // HEIC and AVIF have no decoder; label them instead of failing
+        let extension = asset.extension().unwrap_or(&asset.format.extension).to_string();
+        if formats::is_undecodable_image(&extension) {
+            return self.generate_labeled_placeholder(&asset.id, &extension.to_uppercase(), (90, 120, 160)).await;
+        }
+        
+        // Damaged PNGs and JPEGs may decode to a partial image; report them
+        // instead of writing a broken thumbnail. A JPEG cut off before its
+        // end marker still decodes most of the picture, so it keeps its preview.
+        let integrity = match &asset.integrity {
+            IntegrityStatus::Unchecked => integrity::check_image_structure(input_path, &asset.format.extension).await,
+            checked => checked.clone(),
+        };
+        let is_jpeg = matches!(asset.format.extension.to_lowercase().as_str(), "jpg" | "jpeg");
+        match integrity {
+            IntegrityStatus::Truncated { reason } if is_jpeg => {
+                warn!("Generating preview of truncated {}: {}", input_path.display(), reason);
+            }
+            IntegrityStatus::Truncated { reason } | IntegrityStatus::Corrupt { reason } => {
+                return Err(IngestError::corrupted_file(input_path.clone(), reason).into());
+            }
+            _ => {}
+        }
+        
+        let is_hdr = is_hdr_extension(&asset.format.extension)
+            || asset.extension().map(is_hdr_extension).unwrap_or(false);
+        
+        // Animated images use a representative frame; RAW and camera JPEGs use their
+        // embedded preview when it is usable; huge images decode at reduced size;
+        // everything else decodes normally
+        let img = if is_hdr {
+            self.load_hdr_image(input_path)?
+        } else if let Some(preview) = self.load_embedded_preview(asset).await {
+            preview
+        } else if let Some(reduced) = self.load_large_image(input_path)? {
+            reduced
+        } else {
+            match self.load_animation_frame(input_path)? {
+                Some(frame) => frame,
+                None => image::open(input_path)
+                    .map_err(|e| IngestError::preview_generation_failed(
+                        input_path.clone(),
+                        format!("Failed to open image: {}", e)
+                    ))?,
+            }
+        };
         
         let (width, height) = img.dimensions();
         let (thumb_width, thumb_height) = self.calculate_thumbnail_size(width, height);
@@ -86,77 +341,290 @@ impl PreviewGenerator {
         // Resize image maintaining aspect ratio
         let thumbnail = img.resize(thumb_width, thumb_height, image::imageops::FilterType::Lanczos3);
         
-        // Save as JPEG
-        thumbnail.save_with_format(&preview_path, image::ImageFormat::Jpeg)
-            .map_err(|e| IngestError::preview_generation_failed(
-                input_path.clone(),
-                format!("Failed to save thumbnail: {}", e)
-            ))?;
+        let format = self.output_format(has_transparency(&thumbnail));
+        let preview_path = self.write_preview(&thumbnail, asset, format)?;
+        
+        Ok(PreviewInfo {
+            thumbnail_path: preview_path,
+            thumbnail_size: (thumb_width, thumb_height),
+            rendered_preview: None,
+            generated_at: Utc::now(),
+            extension: format.extension().to_string(),
+            waveform: None,
+            contact_sheet_grid: None,
+        })
+    }
+    
+    /// Decode an image above the large-image threshold at reduced size
+    /// 
+    /// Returns None for images below the threshold, and for large images in
+    /// formats without a reduced decode, which then decode fully.
+    fn load_large_image(&self, path: &Path) -> DamResult<Option<image::DynamicImage>> {
+        let Ok((width, height)) = large_image::header_dimensions(path) else {
+            return Ok(None);
+        };
+        if width as u64 * height as u64 <= self.large_image_pixels {
+            return Ok(None);
+        }
+        
+        // Twice the thumbnail size leaves the final resize something to filter
+        let (max_width, max_height) = self.max_preview_size;
+        let reduced = large_image::load_downscaled(path, (max_width.saturating_mul(2), max_height.saturating_mul(2)))?;
+        match &reduced {
+            Some(_) => debug!("Decoded {}x{} image {} at reduced size", width, height, path.display()),
+            None => warn!("No reduced decode for {}x{} image {}, decoding fully", width, height, path.display()),
+        }
+        Ok(reduced)
+    }
+    
+    /// Decode an EXR/HDR image and tone-map it to 8-bit
+    /// 
+    /// The format is taken from the file contents, so misnamed files work too.
+    fn load_hdr_image(&self, path: &Path) -> DamResult<image::DynamicImage> {
+        let decode_error = |e: String| IngestError::preview_generation_failed(
+            path.to_path_buf(),
+            format!("Failed to open HDR image: {}", e)
+        );
+        
+        let img = image::io::Reader::open(path)
+            .and_then(|reader| reader.with_guessed_format())
+            .map_err(|e| decode_error(e.to_string()))?
+            .decode()
+            .map_err(|e| decode_error(e.to_string()))?;
+        
+        Ok(tone_map(&img))
+    }
+    
+    /// Decode the JPEG preview embedded in a camera RAW or JPEG file
+    /// 
+    /// RAW previews are always preferred over a full decode. EXIF thumbnails in
+    /// JPEGs are only used when they are at least as large as the thumbnail we
+    /// would produce, so small previews never get upscaled.
+    async fn load_embedded_preview(&self, asset: &Asset) -> Option<image::DynamicImage> {
+        let extension = asset.extension().unwrap_or(&asset.format.extension).to_ascii_lowercase();
+        let is_raw = embedded::is_raw_extension(&extension);
+        if !is_raw && extension != "jpg" && extension != "jpeg" {
+            return None;
+        }
+        
+        let path = asset.current_path.clone();
+        let preview = match tokio::task::spawn_blocking(move || embedded::read_embedded_preview(&path)).await {
+            Ok(Ok(preview)) => preview?,
+            Ok(Err(e)) => {
+                debug!("Could not read embedded preview of {:?}: {}", asset.current_path, e);
+                return None;
+            }
+            Err(e) => {
+                warn!("Embedded preview task failed for {:?}: {}", asset.current_path, e);
+                return None;
+            }
+        };
+        let img = match image::load_from_memory_with_format(&preview, image::ImageFormat::Jpeg) {
+            Ok(img) => img,
+            Err(e) => {
+                debug!("Ignoring unreadable embedded preview in {:?}: {}", asset.current_path, e);
+                return None;
+            }
+        };
+        
+        let (max_width, max_height) = self.max_preview_size;
+        if !is_raw && img.width() < max_width && img.height() < max_height {
+            return None;
+        }
+        
+        debug!("Using embedded {}x{} preview for {:?}", img.width(), img.height(), asset.current_path);
+        Some(img)
+    }
+    
+    /// Decode the representative frame of an animated GIF/WebP
+    /// 
+    /// Returns `None` for other formats and for single-frame files.
+    fn load_animation_frame(&self, path: &Path) -> DamResult<Option<image::DynamicImage>> {
+        let extension = path.extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase())
+            .unwrap_or_default();
+        
+        // Frames are counted from the container so only the chosen one is decoded
+        let (data, frame_count) = match extension.as_str() {
+            "gif" | "webp" => {
+                let data = std::fs::read(path)?;
+                let count = match extension.as_str() {
+                    "gif" => crate::parser::count_gif_frames(&data),
+                    _ => crate::parser::count_webp_frames(&data),
+                };
+                (data, count.unwrap_or(0) as usize)
+            }
+            _ => return Ok(None),
+        };
+        if frame_count <= 1 {
+            return Ok(None);
+        }
+        
+        let index = match self.representative_frame {
+            RepresentativeFrame::First => 0,
+            RepresentativeFrame::Middle => frame_count / 2,
+        };
+        debug!("Using frame {} of {} for {}", index, frame_count, path.display());
+        
+        let reader = std::io::Cursor::new(data);
+        let frame = match extension.as_str() {
+            "gif" => image::codecs::gif::GifDecoder::new(reader)
+                .map(|decoder| decoder.into_frames().nth(index)),
+            _ => image::codecs::webp::WebPDecoder::new(reader)
+                .map(|decoder| decoder.into_frames().nth(index)),
+        };
+        
+        let frame = match frame {
+            Ok(Some(Ok(frame))) => frame,
+            Ok(None) => return Ok(None),
+            Ok(Some(Err(e))) | Err(e) => {
+                warn!("Failed to decode animation frame for {}: {}", path.display(), e);
+                return Ok(None);
+            }
+        };
+        Ok(Some(image::DynamicImage::ImageRgba8(frame.into_buffer())))
+    }
+    
+    /// Rasterize an SVG asset into a thumbnail
+    async fn generate_svg_preview(&self, asset: &Asset) -> DamResult<PreviewInfo> {
+        let input_path = &asset.current_path;
+        
+        let data = tokio::fs::read(input_path).await?;
+        let tree = crate::svg::parse_svg(&data)
+            .map_err(|_| IngestError::unsupported_format("svg", input_path.clone()))?;
+        
+        let rendered = crate::svg::render_svg(&tree, self.max_preview_size)
+            .map_err(|e| IngestError::preview_generation_failed(input_path.clone(), e))?;
+        let (thumb_width, thumb_height) = rendered.dimensions();
+        
+        let rendered = image::DynamicImage::ImageRgba8(rendered);
+        let format = self.output_format(has_transparency(&rendered));
+        let preview_path = self.write_preview(&rendered, asset, format)?;
         
         Ok(PreviewInfo {
             thumbnail_path: preview_path,
             thumbnail_size: (thumb_width, thumb_height),
             rendered_preview: None,
             generated_at: Utc::now(),
+            extension: format.extension().to_string(),
+            waveform: None,
+            contact_sheet_grid: None,
         })
     }
     
     /// Generate preview for 3D assets
     async fn generate_3d_preview(&self, asset: &Asset) -> DamResult<PreviewInfo> {
         let input_path = &asset.current_path;
-        let preview_filename = format!("{}.jpg", asset.id);
-        let preview_path = self.preview_dir.join(&preview_filename);
+        let format = self.format.resolve(false);
         
-        // For now, generate a placeholder 3D preview
-        // In a full implementation, this would:
-        // 1. Load the 3D model
-        // 2. Render it from multiple angles
-        // 3. Create a composite preview image
+        let owned = input_path.clone();
+        let size = self.max_preview_size;
+        let rendered = tokio::task::spawn_blocking(move || {
+            crate::mesh::Mesh::load(&owned).and_then(|mesh| {
+                if mesh.truncated {
+                    warn!("Model {} exceeds the preview limits, rendering a partial preview", owned.display());
+                }
+                crate::mesh::render_mesh(&mesh, size)
+            })
+        })
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
         
-        warn!("3D preview generation not fully implemented, creating placeholder for: {}", 
-              input_path.display());
+        let rendered = match rendered {
+            Ok(rendered) => rendered,
+            Err(e) => {
+                // Formats without a mesh loader (FBX, Blender, ...) end up here too
+                warn!("Could not render 3D preview for {}: {}, creating placeholder",
+                      input_path.display(), e);
+                
+                let preview_path = self.preview_file(&asset.id, format);
+                self.create_placeholder_preview(&preview_path, "3D", (128, 128, 200)).await?;
+                
+                return Ok(PreviewInfo {
+                    thumbnail_path: preview_path.clone(),
+                    thumbnail_size: self.max_preview_size,
+                    rendered_preview: Some(preview_path),
+                    generated_at: Utc::now(),
+                    extension: format.extension().to_string(),
+                    waveform: None,
+                    contact_sheet_grid: None,
+                });
+            }
+        };
         
-        self.create_placeholder_preview(&preview_path, "3D", (128, 128, 200)).await?;
+        let thumbnail_size = rendered.dimensions();
+        let preview_path = self.write_preview(&image::DynamicImage::ImageRgb8(rendered), asset, format)?;
         
         Ok(PreviewInfo {
             thumbnail_path: preview_path.clone(),
-            thumbnail_size: self.max_preview_size,
+            thumbnail_size,
             rendered_preview: Some(preview_path),
             generated_at: Utc::now(),
+            extension: format.extension().to_string(),
+            waveform: None,
+            contact_sheet_grid: None,
         })
     }
     
     /// Generate preview for audio assets
     async fn generate_audio_preview(&self, asset: &Asset) -> DamResult<PreviewInfo> {
         let input_path = &asset.current_path;
-        let preview_filename = format!("{}.jpg", asset.id);
-        let preview_path = self.preview_dir.join(&preview_filename);
-        
-        // For audio files, we could generate a waveform visualization
-        // For now, create a placeholder with audio icon
+        let format = self.format.resolve(false);
         
         debug!("Generating audio waveform preview for: {}", input_path.display());
         
-        self.create_placeholder_preview(&preview_path, "♪", (100, 150, 255)).await?;
+        // One decode yields the peaks for both the image and interactive waveforms
+        let owned = input_path.clone();
+        let decoded = tokio::task::spawn_blocking(move || waveform::decode_waveform(&owned, waveform::WAVEFORM_RESOLUTION))
+            .await
+            .unwrap_or_else(|e| Err(IngestError::preview_generation_failed(input_path.clone(), e.to_string()).into()));
+        let waveform = match decoded {
+            Ok(waveform) => waveform,
+            Err(e) => {
+                warn!("Failed to decode waveform for {}: {}", input_path.display(), e);
+                let preview_path = self.preview_file(&asset.id, format);
+                self.create_placeholder_preview(&preview_path, "♪", (100, 150, 255)).await?;
+                
+                return Ok(PreviewInfo {
+                    thumbnail_path: preview_path,
+                    thumbnail_size: self.max_preview_size,
+                    rendered_preview: None,
+                    generated_at: Utc::now(),
+                    extension: format.extension().to_string(),
+                    waveform: None,
+                    contact_sheet_grid: None,
+                });
+            }
+        };
+        
+        let rendered = waveform::render_waveform(&waveform, self.max_preview_size);
+        let preview_path = self.write_preview(&image::DynamicImage::ImageRgb8(rendered), asset, format)?;
         
         Ok(PreviewInfo {
             thumbnail_path: preview_path,
             thumbnail_size: self.max_preview_size,
             rendered_preview: None,
             generated_at: Utc::now(),
+            extension: format.extension().to_string(),
+            waveform: Some(waveform),
+            contact_sheet_grid: None,
         })
     }
     
     /// Generate preview for video assets
     async fn generate_video_preview(&self, asset: &Asset) -> DamResult<PreviewInfo> {
         let input_path = &asset.current_path;
-        let preview_filename = format!("{}.jpg", asset.id);
-        let preview_path = self.preview_dir.join(&preview_filename);
-        
-        // For video files, we would extract a frame from the middle of the video
-        // For now, create a placeholder
+        if let Some((rows, cols)) = self.contact_sheet {
+            match self.generate_contact_sheet(asset, rows, cols).await {
+                Ok(preview) => return Ok(preview),
+                Err(e) => warn!("No contact sheet for {}, using a placeholder: {}", input_path.display(), e),
+            }
+        }
         
-        debug!("Generating video frame preview for: {}", input_path.display());
+        let format = self.format.resolve(false);
+        let preview_path = self.preview_file(&asset.id, format);
+        debug!("Generating placeholder video preview for: {}", input_path.display());
         
         self.create_placeholder_preview(&preview_path, "▶", (255, 100, 100)).await?;
         
@@ -165,21 +633,110 @@ impl PreviewGenerator {
             thumbnail_size: self.max_preview_size,
             rendered_preview: None,
             generated_at: Utc::now(),
+            extension: format.extension().to_string(),
+            waveform: None,
+            contact_sheet_grid: None,
+        })
+    }
+    
+    /// Generate a contact sheet of evenly spaced frames for a video
+    /// 
+    /// Extracts up to `rows * cols` frames with ffmpeg and saves them as a
+    /// grid in `rendered_preview`, next to a single-frame thumbnail from the
+    /// middle of the video. Each side is capped at `video::MAX_SHEET_SIDE`,
+    /// and short videos get a smaller grid, so every frame covers at least a
+    /// second; `contact_sheet_grid` holds the grid used.
+    pub async fn generate_contact_sheet(&self, asset: &Asset, rows: u32, cols: u32) -> DamResult<PreviewInfo> {
+        let input_path = &asset.current_path;
+        if asset.asset_type != AssetType::Video {
+            return Err(IngestError::preview_generation_failed(
+                input_path.clone(),
+                "Contact sheets are only generated for videos".to_string()
+            ).into());
+        }
+        
+        let duration = match asset.metadata.video.as_ref().map(|video| video.duration as f64) {
+            Some(duration) if duration > 0.0 => duration,
+            _ => video::probe_duration(input_path).await?,
+        };
+        let (rows, cols) = video::fit_grid(rows, cols, duration);
+        debug!("Generating {}x{} contact sheet for: {}", rows, cols, input_path.display());
+        
+        let (cell_width, _) = self.max_preview_size;
+        let mut frames = Vec::new();
+        for time in video::frame_times(rows * cols, duration) {
+            match video::extract_frame(input_path, time, cell_width).await {
+                Ok(frame) => frames.push(frame),
+                Err(e) => warn!("Skipping contact sheet frame of {}: {}", input_path.display(), e),
+            }
+        }
+        let Some(middle) = frames.get(frames.len() / 2) else {
+            return Err(IngestError::preview_generation_failed(
+                input_path.clone(),
+                "No frames could be extracted".to_string()
+            ).into());
+        };
+        
+        let (thumb_width, thumb_height) = self.calculate_thumbnail_size(middle.width(), middle.height());
+        tokio::fs::create_dir_all(&self.preview_dir).await?;
+        
+        // Resizing, compositing and encoding are CPU-bound
+        let generator = self.clone();
+        let owned = asset.clone();
+        let format = self.format.resolve(false);
+        let (thumbnail_path, sheet_path) = tokio::task::spawn_blocking(move || -> DamResult<(PathBuf, PathBuf)> {
+            let failed = |message: String| IngestError::preview_generation_failed(owned.current_path.clone(), message);
+            let middle = &frames[frames.len() / 2];
+            let thumbnail = middle.resize(thumb_width, thumb_height, image::imageops::FilterType::Lanczos3);
+            let thumbnail_path = generator.write_preview(&thumbnail, &owned, format)?;
+            
+            // Cells take the first frame's aspect ratio
+            let cell_height = (cell_width as u64 * frames[0].height() as u64 / frames[0].width().max(1) as u64)
+                .clamp(1, u32::MAX as u64) as u32;
+            let sheet = video::composite_grid(&frames, rows, cols, (cell_width, cell_height))
+                .ok_or_else(|| failed(format!("A {}x{} contact sheet is too large", rows, cols)))?;
+            let sheet_path = generator.preview_dir.join(format!("{}{}sheet.jpg", owned.id, PREVIEW_SIZE_SEPARATOR));
+            let file = std::fs::File::create(&sheet_path)?;
+            image::codecs::jpeg::JpegEncoder::new_with_quality(std::io::BufWriter::new(file), generator.jpeg_quality)
+                .encode_image(&sheet)
+                .map_err(|e| failed(format!("Failed to save contact sheet: {}", e)))?;
+            Ok((thumbnail_path, sheet_path))
+        })
+        .await
+        .map_err(|e| IngestError::preview_generation_failed(input_path.clone(), format!("Contact sheet task failed: {}", e)))??;
+        self.remove_other_formats(&asset.id, &thumbnail_path).await;
+        
+        Ok(PreviewInfo {
+            thumbnail_path,
+            thumbnail_size: (thumb_width, thumb_height),
+            rendered_preview: Some(sheet_path),
+            generated_at: Utc::now(),
+            extension: format.extension().to_string(),
+            waveform: None,
+            contact_sheet_grid: Some((rows, cols)),
         })
     }
     
     /// Generate generic preview for unsupported asset types
     async fn generate_generic_preview(&self, asset: &Asset) -> DamResult<PreviewInfo> {
-        let preview_filename = format!("{}.jpg", asset.id);
-        let preview_path = self.preview_dir.join(&preview_filename);
+        self.generate_labeled_placeholder(&asset.id, "?", (128, 128, 128)).await
+    }
+    
+    /// Write a placeholder preview with a text label
+    async fn generate_labeled_placeholder(&self, id: &uuid::Uuid, label: &str, color: (u8, u8, u8)) -> DamResult<PreviewInfo> {
+        let format = self.format.resolve(false);
+        let preview_path = self.preview_file(id, format);
         
-        self.create_placeholder_preview(&preview_path, "?", (128, 128, 128)).await?;
+        self.create_placeholder_preview(&preview_path, label, color).await?;
         
         Ok(PreviewInfo {
             thumbnail_path: preview_path,
             thumbnail_size: self.max_preview_size,
             rendered_preview: None,
             generated_at: Utc::now(),
+            extension: format.extension().to_string(),
+            waveform: None,
+            contact_sheet_grid: None,
         })
     }
     
@@ -201,8 +758,8 @@ impl PreviewGenerator {
             *pixel = image::Rgb([color.0, color.1, color.2]);
         }
         
-        // Save the placeholder
-        img.save_with_format(output_path, image::ImageFormat::Jpeg)
+        // Save the placeholder, in the format named by its extension
+        img.save(output_path)
             .map_err(|e| IngestError::preview_generation_failed(
                 output_path.to_path_buf(),
                 format!("Failed to save placeholder: {}", e)
@@ -211,6 +768,77 @@ impl PreviewGenerator {
         Ok(())
     }
     
+    /// Path of an asset's preview in the given format
+    fn preview_file(&self, asset_id: &uuid::Uuid, format: PreviewFormat) -> PathBuf {
+        self.preview_dir.join(format!("{}.{}", asset_id, format.extension()))
+    }
+    
+    /// Existing preview files of an asset, in any format
+    fn existing_previews(&self, asset_id: &uuid::Uuid) -> Vec<PathBuf> {
+        PreviewFormat::EXTENSIONS.iter()
+            .map(|extension| self.preview_dir.join(format!("{}.{}", asset_id, extension)))
+            .filter(|path| path.exists())
+            .collect()
+    }
+    
+    /// Encode a thumbnail in the given format
+    /// 
+    /// JPEG has no alpha channel, so transparent areas are flattened onto
+    /// the background and `jpeg_quality` applies.
+    fn write_preview(&self, thumbnail: &image::DynamicImage, asset: &Asset, format: PreviewFormat) -> DamResult<PathBuf> {
+        let preview_path = self.preview_file(&asset.id, format);
+        let save_error = |e: image::ImageError| IngestError::preview_generation_failed(
+            asset.current_path.clone(),
+            format!("Failed to save thumbnail: {}", e)
+        );
+        
+        match format {
+            PreviewFormat::Auto | PreviewFormat::Jpeg => {
+                let flattened = self.background.composite(&thumbnail.to_rgba8());
+                let file = std::fs::File::create(&preview_path)?;
+                let mut writer = std::io::BufWriter::new(file);
+                image::codecs::jpeg::JpegEncoder::new_with_quality(&mut writer, self.jpeg_quality)
+                    .encode_image(&flattened)
+                    .map_err(save_error)?;
+            }
+            PreviewFormat::Png => {
+                thumbnail.save_with_format(&preview_path, image::ImageFormat::Png)
+                    .map_err(save_error)?;
+            }
+            PreviewFormat::WebP => {
+                // The WebP encoder only takes 8-bit RGB(A)
+                let thumbnail = if thumbnail.color().has_alpha() {
+                    image::DynamicImage::ImageRgba8(thumbnail.to_rgba8())
+                } else {
+                    image::DynamicImage::ImageRgb8(thumbnail.to_rgb8())
+                };
+                thumbnail.save_with_format(&preview_path, image::ImageFormat::WebP)
+                    .map_err(save_error)?;
+            }
+        }
+        
+        Ok(preview_path)
+    }
+    
+    /// Concrete format for a thumbnail, flattening `Auto` onto an opaque background
+    fn output_format(&self, has_transparency: bool) -> PreviewFormat {
+        match self.format {
+            PreviewFormat::Auto if self.background.flattens() => PreviewFormat::Jpeg,
+            format => format.resolve(has_transparency),
+        }
+    }
+    
+    /// Delete an asset's previews other than the one just written
+    async fn remove_other_formats(&self, asset_id: &uuid::Uuid, keep: &Path) {
+        for path in self.existing_previews(asset_id) {
+            if path != keep {
+                if let Err(e) = tokio::fs::remove_file(&path).await {
+                    warn!("Failed to delete outdated preview {}: {}", path.display(), e);
+                }
+            }
+        }
+    }
+    
     /// Calculate thumbnail dimensions maintaining aspect ratio
     fn calculate_thumbnail_size(&self, original_width: u32, original_height: u32) -> (u32, u32) {
         let (max_width, max_height) = self.max_preview_size;
@@ -229,19 +857,14 @@ impl PreviewGenerator {
         (new_width.max(1), new_height.max(1))
     }
     
-    /// Check if a preview already exists for an asset
+    /// Check if a preview already exists for an asset, in any format
     pub async fn preview_exists(&self, asset_id: &uuid::Uuid) -> bool {
-        let preview_filename = format!("{}.jpg", asset_id);
-        let preview_path = self.preview_dir.join(preview_filename);
-        preview_path.exists()
+        !self.existing_previews(asset_id).is_empty()
     }
     
-    /// Delete preview for an asset
+    /// Delete preview for an asset, whichever format it was written in
     pub async fn delete_preview(&self, asset_id: &uuid::Uuid) -> DamResult<()> {
-        let preview_filename = format!("{}.jpg", asset_id);
-        let preview_path = self.preview_dir.join(preview_filename);
-        
-        if preview_path.exists() {
+        for preview_path in self.existing_previews(asset_id) {
             tokio::fs::remove_file(&preview_path).await?;
             debug!("Deleted preview: {}", preview_path.display());
         }
@@ -249,14 +872,23 @@ impl PreviewGenerator {
         Ok(())
     }
     
-    /// Get the path where a preview would be stored
+    /// Get the path where a preview is or would be stored
+    /// 
+    /// Returns the existing preview if there is one; otherwise the path in
+    /// the configured format for an opaque image.
     pub fn get_preview_path(&self, asset_id: &uuid::Uuid) -> PathBuf {
-        let preview_filename = format!("{}.jpg", asset_id);
-        self.preview_dir.join(preview_filename)
+        self.existing_previews(asset_id)
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| self.preview_file(asset_id, self.format.resolve(false)))
     }
     
     /// Clean up old previews that no longer have corresponding assets
+    /// 
+    /// Removes every variant (format and size) of the previews of assets not
+    /// in `valid_asset_ids`. Files not named like previews are left alone.
     pub async fn cleanup_orphaned_previews(&self, valid_asset_ids: &[uuid::Uuid]) -> DamResult<usize> {
+        let valid: std::collections::HashSet<&uuid::Uuid> = valid_asset_ids.iter().collect();
         let mut cleaned_count = 0;
         
         let mut dir_entries = tokio::fs::read_dir(&self.preview_dir).await?;
@@ -264,19 +896,18 @@ impl PreviewGenerator {
         while let Some(entry) = dir_entries.next_entry().await? {
             let path = entry.path();
             
-            if path.extension().and_then(|s| s.to_str()) == Some("jpg") {
-                if let Some(filename) = path.file_stem().and_then(|s| s.to_str()) {
-                    if let Ok(asset_id) = uuid::Uuid::parse_str(filename) {
-                        if !valid_asset_ids.contains(&asset_id) {
-                            if let Err(e) = tokio::fs::remove_file(&path).await {
-                                warn!("Failed to delete orphaned preview {}: {}", path.display(), e);
-                            } else {
-                                cleaned_count += 1;
-                                debug!("Cleaned up orphaned preview: {}", path.display());
-                            }
-                        }
-                    }
-                }
+            let Some(asset_id) = preview_asset_id(&path) else {
+                continue;
+            };
+            if valid.contains(&asset_id) {
+                continue;
+            }
+            
+            if let Err(e) = tokio::fs::remove_file(&path).await {
+                warn!("Failed to delete orphaned preview {}: {}", path.display(), e);
+            } else {
+                cleaned_count += 1;
+                debug!("Cleaned up orphaned preview: {}", path.display());
             }
         }
         
@@ -290,6 +921,53 @@ impl Default for PreviewGenerator {
     }
 }
 
+/// Asset ID a preview file belongs to
+/// 
+/// Preview files are named `<asset id>.<ext>`, or `<asset id>_<suffix>.<ext>`
+/// for additional sizes (e.g. `_1024`), with any preview format extension.
+/// Returns `None` for files not named like a preview.
+pub fn preview_asset_id(path: &Path) -> Option<uuid::Uuid> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    if !PreviewFormat::EXTENSIONS.contains(&extension.as_str()) {
+        return None;
+    }
+    
+    let stem = path.file_stem()?.to_str()?;
+    let id = match stem.split_once(PREVIEW_SIZE_SEPARATOR) {
+        Some((id, suffix)) if !suffix.is_empty() => id,
+        Some(_) => return None,
+        None => stem,
+    };
+    uuid::Uuid::parse_str(id).ok()
+}
+
+/// Whether any pixel of an image is not fully opaque
+fn has_transparency(image: &image::DynamicImage) -> bool {
+    image.color().has_alpha() && image.to_rgba8().pixels().any(|pixel| pixel[3] < 255)
+}
+
+/// Whether an extension names a high-dynamic-range image format
+fn is_hdr_extension(extension: &str) -> bool {
+    formats::is_in(formats::HDR_IMAGE_EXTENSIONS, extension)
+}
+
+/// Map linear HDR pixels into displayable 8-bit RGB
+/// 
+/// Applies Reinhard tone mapping (`c / (1 + c)`) followed by display gamma,
+/// so bright highlights compress smoothly instead of clipping to white.
+pub fn tone_map(img: &image::DynamicImage) -> image::DynamicImage {
+    let linear = img.to_rgb32f();
+    let mapped = image::RgbImage::from_fn(linear.width(), linear.height(), |x, y| {
+        let pixel = linear.get_pixel(x, y);
+        image::Rgb(pixel.0.map(|channel| {
+            let exposed = (channel * HDR_EXPOSURE).max(0.0);
+            let compressed = exposed / (1.0 + exposed);
+            (compressed.powf(1.0 / DISPLAY_GAMMA) * 255.0).round() as u8
+        }))
+    });
+    image::DynamicImage::ImageRgb8(mapped)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -300,6 +978,13 @@ mod tests {
     fn test_preview_generator_creation() {
         let generator = PreviewGenerator::new();
         assert!(generator.is_ok());
+        
+        // The default location must not depend on the working directory
+        let preview_dir = generator.unwrap().preview_dir;
+        assert_eq!(preview_dir, default_preview_dir());
+        if dirs::data_dir().is_some() {
+            assert!(preview_dir.is_absolute());
+        }
     }
     
     #[test]
@@ -370,4 +1055,211 @@ mod tests {
         let img = img.unwrap();
         assert_eq!(img.dimensions(), (128, 128));
     }
+    
+    #[tokio::test]
+    async fn test_preview_formats() {
+        let dir = tempdir().unwrap();
+        let logo_path = dir.path().join("logo.png");
+        let photo_path = dir.path().join("photo.png");
+        image::RgbaImage::from_pixel(16, 16, image::Rgba([255, 0, 0, 0])).save(&logo_path).unwrap();
+        image::RgbImage::from_pixel(16, 16, image::Rgb([0, 0, 255])).save(&photo_path).unwrap();
+        
+        // Transparent images keep their alpha channel by default
+        let generator = PreviewGenerator::with_settings(dir.path().join("previews"), (8, 8), 80).unwrap();
+        let logo = Asset::new(logo_path, AssetType::Image);
+        let preview = generator.generate_preview(&logo).await.unwrap();
+        assert_eq!(preview.extension, "png");
+        assert_eq!(image::open(&preview.thumbnail_path).unwrap().to_rgba8().get_pixel(0, 0)[3], 0);
+        
+        let photo = Asset::new(photo_path, AssetType::Image);
+        let preview = generator.generate_preview(&photo).await.unwrap();
+        assert_eq!(preview.extension, "jpg");
+        
+        // Switching formats replaces the old preview
+        let generator = generator.with_format(PreviewFormat::WebP);
+        let preview = generator.generate_preview(&photo).await.unwrap();
+        assert_eq!(preview.thumbnail_path.extension().unwrap(), "webp");
+        assert_eq!(generator.get_preview_path(&photo.id), preview.thumbnail_path);
+        assert!(image::open(&preview.thumbnail_path).is_ok());
+        
+        // Cleanup and deletion find previews in every format
+        assert_eq!(generator.cleanup_orphaned_previews(&[photo.id]).await.unwrap(), 1);
+        generator.delete_preview(&photo.id).await.unwrap();
+        assert!(!generator.preview_exists(&photo.id).await);
+    }
+    
+    #[tokio::test]
+    async fn test_preview_background() {
+        let dir = tempdir().unwrap();
+        let logo_path = dir.path().join("logo.png");
+        image::RgbaImage::from_pixel(16, 16, image::Rgba([255, 0, 0, 0])).save(&logo_path).unwrap();
+        let logo = Asset::new(logo_path, AssetType::Image);
+        let previews = dir.path().join("previews");
+        
+        // JPEG previews default to white rather than black
+        let generator = PreviewGenerator::with_settings(&previews, (16, 16), 100).unwrap()
+            .with_format(PreviewFormat::Jpeg);
+        let preview = generator.generate_preview(&logo).await.unwrap();
+        let pixel = *image::open(&preview.thumbnail_path).unwrap().to_rgb8().get_pixel(4, 4);
+        assert!(pixel.0.iter().all(|channel| *channel > 245));
+        
+        // A flat color makes Auto flatten too
+        let generator = PreviewGenerator::with_settings(&previews, (16, 16), 100).unwrap()
+            .with_background(PreviewBackground::Color([0, 0, 255]));
+        let preview = generator.generate_preview(&logo).await.unwrap();
+        assert_eq!(preview.extension, "jpg");
+        let pixel = *image::open(&preview.thumbnail_path).unwrap().to_rgb8().get_pixel(4, 4);
+        assert!(pixel[2] > 240 && pixel[0] < 15);
+        
+        // Formats with alpha keep it whatever the background
+        let generator = generator.with_format(PreviewFormat::Png);
+        let preview = generator.generate_preview(&logo).await.unwrap();
+        assert_eq!(image::open(&preview.thumbnail_path).unwrap().to_rgba8().get_pixel(4, 4)[3], 0);
+        
+        let checkerboard = PreviewBackground::Checkerboard.composite(&image::RgbaImage::new(16, 16));
+        assert_eq!(checkerboard.get_pixel(0, 0), &image::Rgb([255, 255, 255]));
+        assert_eq!(checkerboard.get_pixel(8, 0), &image::Rgb([204, 204, 204]));
+    }
+    
+    #[tokio::test]
+    async fn test_large_image_preview() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("panorama.png");
+        image::RgbImage::from_pixel(300, 100, image::Rgb([0, 128, 0])).save(&path).unwrap();
+        
+        // Every image counts as large, so the streaming decode makes the thumbnail
+        let generator = PreviewGenerator::with_settings(dir.path().join("previews"), (30, 30), 80).unwrap()
+            .with_large_image_threshold(0);
+        let asset = Asset::new(path, AssetType::Image);
+        let preview = generator.generate_preview(&asset).await.unwrap();
+        assert_eq!(preview.thumbnail_size, (30, 10));
+        
+        let thumbnail = image::open(&preview.thumbnail_path).unwrap().to_rgb8();
+        assert_eq!(thumbnail.dimensions(), (30, 10));
+        assert!(thumbnail.get_pixel(15, 5)[1] > 100);
+    }
+    
+    #[tokio::test]
+    async fn test_undecodable_image_preview() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("photo.HEIC");
+        std::fs::write(&path, b"\0\0\0\x18ftypheic").unwrap();
+        
+        // No decoder, so a placeholder is written instead of an error
+        let generator = PreviewGenerator::with_settings(dir.path().join("previews"), (40, 40), 80).unwrap();
+        let asset = Asset::new(path, AssetType::Image);
+        let preview = generator.generate_preview(&asset).await.unwrap();
+        assert_eq!(preview.thumbnail_size, (40, 40));
+        assert!(preview.thumbnail_path.exists());
+    }
+    
+    #[tokio::test]
+    async fn test_cleanup_orphaned_preview_variants() {
+        let dir = tempdir().unwrap();
+        let generator = PreviewGenerator::with_settings(dir.path(), (64, 64), 80).unwrap();
+        let kept = Uuid::new_v4();
+        let orphan = Uuid::new_v4();
+        
+        let kept_files = [
+            format!("{}.jpg", kept),
+            format!("{}_1024.webp", kept),
+            format!("{}.txt", orphan),
+            format!("{}_.png", orphan),
+            "notes.jpg".to_string(),
+        ];
+        let orphan_files = [
+            format!("{}.png", orphan),
+            format!("{}_256.jpg", orphan),
+            format!("{}_1024.WEBP", orphan),
+        ];
+        for name in kept_files.iter().chain(&orphan_files) {
+            std::fs::write(dir.path().join(name), b"preview").unwrap();
+        }
+        
+        assert_eq!(preview_asset_id(&dir.path().join(&orphan_files[1])), Some(orphan));
+        assert_eq!(generator.cleanup_orphaned_previews(&[kept]).await.unwrap(), 3);
+        
+        for name in &kept_files {
+            assert!(dir.path().join(name).exists(), "{} was removed", name);
+        }
+        for name in &orphan_files {
+            assert!(!dir.path().join(name).exists(), "{} was kept", name);
+        }
+    }
+    
+    #[test]
+    fn test_tone_map() {
+        let hdr = image::Rgb32FImage::from_fn(3, 1, |x, _| match x {
+            0 => image::Rgb([0.0, 0.0, 0.0]),
+            1 => image::Rgb([1.0, 1.0, 1.0]),
+            _ => image::Rgb([100.0, -1.0, f32::NAN]),
+        });
+        let mapped = tone_map(&image::DynamicImage::ImageRgb32F(hdr)).to_rgb8();
+        
+        assert_eq!(mapped.get_pixel(0, 0).0, [0, 0, 0]);
+        // Linear 1.0 maps to 0.5 before gamma: 0.5^(1/2.2) * 255
+        assert_eq!(mapped.get_pixel(1, 0).0, [186, 186, 186]);
+        // Very bright values stay below white; negative and NaN values are black
+        assert_eq!(mapped.get_pixel(2, 0).0, [254, 0, 0]);
+    }
+    
+    #[tokio::test]
+    async fn test_exr_preview() {
+        let dir = tempdir().unwrap();
+        let exr_path = dir.path().join("render.exr");
+        let hdr = image::Rgb32FImage::from_pixel(64, 32, image::Rgb([4.0, 0.5, 0.0]));
+        image::DynamicImage::ImageRgb32F(hdr).save(&exr_path).unwrap();
+        
+        let generator = PreviewGenerator::with_settings(dir.path(), (32, 32), 80).unwrap();
+        let asset = Asset::new(exr_path, AssetType::Image);
+        let preview = generator.generate_preview(&asset).await.unwrap();
+        assert_eq!(preview.thumbnail_size, (32, 16));
+        
+        let thumbnail = image::open(&preview.thumbnail_path).unwrap().to_rgb8();
+        let pixel = thumbnail.get_pixel(16, 8);
+        assert!(pixel[0] > 200 && pixel[0] < 255, "highlight clipped or black: {:?}", pixel);
+        assert!(pixel[1] > 120 && pixel[1] < 160, "unexpected midtone: {:?}", pixel);
+    }
+    
+    #[tokio::test]
+    async fn test_3d_preview_rendering() {
+        let dir = tempdir().unwrap();
+        let obj_path = dir.path().join("triangle.obj");
+        std::fs::write(&obj_path, "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n").unwrap();
+        
+        let generator = PreviewGenerator::with_settings(dir.path(), (64, 64), 80).unwrap();
+        let preview = generator.generate_preview(&Asset::new(obj_path, AssetType::ThreeD)).await.unwrap();
+        assert_eq!(preview.thumbnail_size, (64, 64));
+        
+        // Unloadable models fall back to the placeholder
+        let broken_path = dir.path().join("broken.stl");
+        std::fs::write(&broken_path, b"garbage").unwrap();
+        let preview = generator.generate_preview(&Asset::new(broken_path, AssetType::ThreeD)).await.unwrap();
+        assert!(preview.thumbnail_path.exists());
+    }
+    
+    #[test]
+    fn test_animated_gif_middle_frame() {
+        let dir = tempdir().unwrap();
+        let gif_path = dir.path().join("anim.gif");
+        
+        // Three solid frames: red, green, blue
+        {
+            let file = std::fs::File::create(&gif_path).unwrap();
+            let mut encoder = image::codecs::gif::GifEncoder::new(file);
+            for color in [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255]] {
+                let buffer = image::RgbaImage::from_pixel(8, 8, image::Rgba(color));
+                encoder.encode_frame(image::Frame::new(buffer)).unwrap();
+            }
+        }
+        
+        let generator = PreviewGenerator::with_settings(dir.path(), (64, 64), 80).unwrap();
+        let frame = generator.load_animation_frame(&gif_path).unwrap().unwrap();
+        let pixel = frame.get_pixel(4, 4);
+        assert!(pixel[1] > 200 && pixel[0] < 50, "expected green middle frame, got {:?}", pixel);
+        
+        let generator = generator.with_representative_frame(RepresentativeFrame::First);
+        let frame = generator.load_animation_frame(&gif_path).unwrap().unwrap();
+        assert!(frame.get_pixel(4, 4)[0] > 200);
+    }
 }
@@ -0,0 +1,97 @@
+//! Outcomes of directory imports
+//!
+//! On shared drives and NAS mounts parts of a tree are often unreadable to
+//! the importing user. Such locations do not stop a directory import, but
+//! they are reported separately from files that were read and failed to
+//! parse, so the caller can tell "fix the permissions on these folders"
+//! apart from "these files are broken".
+
+use schema::{Asset, DamError};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Why a location could not be read
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AccessErrorKind {
+    PermissionDenied,
+    /// Removed while the import was running
+    NotFound,
+    /// A followed symlink leads back into one of its parent folders
+    SymlinkLoop,
+    /// Any other I/O error, e.g. a dropped network mount
+    Io,
+}
+
+impl AccessErrorKind {
+    fn of(kind: io::ErrorKind) -> Self {
+        match kind {
+            io::ErrorKind::PermissionDenied => AccessErrorKind::PermissionDenied,
+            io::ErrorKind::NotFound => AccessErrorKind::NotFound,
+            _ => AccessErrorKind::Io,
+        }
+    }
+}
+
+/// A folder or file an import could not read
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccessError {
+    pub path: PathBuf,
+    pub kind: AccessErrorKind,
+    pub message: String,
+}
+
+impl AccessError {
+    /// Access error of a failed directory walk step under `root`
+    pub(crate) fn from_walk(error: &walkdir::Error, root: &Path) -> Self {
+        let path = error.path().unwrap_or(root).to_path_buf();
+        let kind = if error.loop_ancestor().is_some() {
+            AccessErrorKind::SymlinkLoop
+        } else {
+            error.io_error().map_or(AccessErrorKind::Io, |e| AccessErrorKind::of(e.kind()))
+        };
+        Self { path, kind, message: error.to_string() }
+    }
+
+    /// Access error of a file whose ingest failed because it could not be read
+    ///
+    /// Denied and vanished files count, as do other I/O errors while the
+    /// file cannot even be opened. Returns None for errors that happened
+    /// after the file was read, such as unsupported or corrupt content or
+    /// failing to write its preview.
+    pub(crate) fn from_ingest(path: &Path, error: &DamError) -> Option<Self> {
+        let kind = match error {
+            DamError::PermissionDenied { .. } => AccessErrorKind::PermissionDenied,
+            DamError::FileSystem(e) => match e.kind() {
+                io::ErrorKind::PermissionDenied | io::ErrorKind::NotFound => AccessErrorKind::of(e.kind()),
+                _ => match std::fs::File::open(path) {
+                    Ok(_) => return None,
+                    Err(open) => AccessErrorKind::of(open.kind()),
+                },
+            },
+            _ => return None,
+        };
+        Some(Self { path: path.to_path_buf(), kind, message: error.to_string() })
+    }
+}
+
+/// A file that was read but could not be ingested
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileFailure {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+/// Outcome of `IngestService::ingest_directory_report`
+#[derive(Debug, Default)]
+pub struct BatchIngestReport {
+    pub assets: Vec<Asset>,
+    /// Files that failed for reasons other than access, such as
+    /// unparseable content
+    pub failures: Vec<FileFailure>,
+    /// Folders and files that could not be read; folders listed here were
+    /// skipped with everything below them
+    pub inaccessible: Vec<AccessError>,
+    /// Hidden, temporary and sidecar files that were not imported
+    pub skipped: Vec<PathBuf>,
+}
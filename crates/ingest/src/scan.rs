@@ -0,0 +1,271 @@
+//! Resumable directory scans
+//!
+//! `IngestService::resume_directory` remembers every file it ingested and
+//! the caller stored, so an interrupted import of a large archive continues
+//! where it stopped instead of starting over. State is kept per root
+//! directory as a journal of completed files with their size and
+//! modification time: a file is skipped on the next run only if both still
+//! match, so edited and newly added files are picked up. Files that
+//! disappeared are dropped from the state when a scan completes.
+
+use crate::report::AccessError;
+use chrono::{DateTime, Utc};
+use schema::DamResult;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tokio::fs::{File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tracing::{info, warn};
+
+/// Bump when the journal format changes; older journals are discarded
+const SCAN_STATE_VERSION: u32 = 1;
+
+/// Default location for scan state
+///
+/// Next to the previews in the platform data directory, e.g.
+/// `~/.local/share/dam/scans` on Linux.
+pub fn default_scan_state_dir() -> PathBuf {
+    dirs::data_dir()
+        .map(|dir| dir.join("dam").join("scans"))
+        .unwrap_or_else(|| PathBuf::from("scans"))
+}
+
+/// Size and modification time of a file when it was ingested
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileStamp {
+    pub size: u64,
+    /// Modification time in milliseconds since the Unix epoch
+    pub modified_ms: i64,
+}
+
+impl FileStamp {
+    /// Stamp of a file from its metadata
+    pub fn from_metadata(metadata: &std::fs::Metadata) -> Option<Self> {
+        let modified: DateTime<Utc> = metadata.modified().ok()?.into();
+        Some(Self {
+            size: metadata.len(),
+            modified_ms: modified.timestamp_millis(),
+        })
+    }
+}
+
+/// First journal line, identifying the scanned root
+#[derive(Debug, Serialize, Deserialize)]
+struct ScanHeader {
+    version: u32,
+    root: PathBuf,
+}
+
+/// One completed file
+#[derive(Debug, Serialize, Deserialize)]
+struct ScanRecord {
+    path: PathBuf,
+    #[serde(flatten)]
+    stamp: FileStamp,
+}
+
+/// Outcome of `IngestService::resume_directory`
+#[derive(Debug, Default)]
+pub struct ScanReport {
+    /// Assets ingested in this run
+    pub assets: Vec<schema::Asset>,
+    /// Files skipped because an earlier run already ingested them unchanged
+    pub skipped: usize,
+    /// Files that failed and will be retried on the next run
+    pub failed: usize,
+    /// Folders and files that could not be read; unreadable files are
+    /// retried on the next run but not counted in `failed`
+    pub inaccessible: Vec<AccessError>,
+    /// Previously ingested files that no longer exist
+    pub removed: Vec<PathBuf>,
+    /// Whether the scan stopped early; run it again to continue
+    pub cancelled: bool,
+}
+
+/// Files completed so far under one root directory
+pub struct ScanState {
+    root: PathBuf,
+    journal_path: PathBuf,
+    completed: HashMap<PathBuf, FileStamp>,
+    journal: Option<File>,
+}
+
+impl ScanState {
+    /// Load the state of `root` from `state_dir`, or start empty
+    ///
+    /// A journal written for another root or by another format version is
+    /// discarded rather than trusted.
+    pub async fn load(state_dir: &Path, root: &Path) -> DamResult<Self> {
+        let journal_path = Self::journal_path(state_dir, root);
+        let mut state = Self {
+            root: root.to_path_buf(),
+            journal_path,
+            completed: HashMap::new(),
+            journal: None,
+        };
+
+        let contents = match tokio::fs::read_to_string(&state.journal_path).await {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(state),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut lines = contents.lines();
+        let header = lines.next().and_then(|line| serde_json::from_str::<ScanHeader>(line).ok());
+        match header {
+            Some(header) if header.version == SCAN_STATE_VERSION && header.root == root => {}
+            _ => {
+                warn!("Discarding scan state {} written for another root or version", state.journal_path.display());
+                return Ok(state);
+            }
+        }
+
+        // A line cut off by a crash is simply not counted as completed
+        for record in lines.filter_map(|line| serde_json::from_str::<ScanRecord>(line).ok()) {
+            state.completed.insert(record.path, record.stamp);
+        }
+        info!("Resuming scan of {}: {} files already done", root.display(), state.completed.len());
+        Ok(state)
+    }
+
+    /// Journal file of a root, named by a hash of its path
+    fn journal_path(state_dir: &Path, root: &Path) -> PathBuf {
+        let hash = blake3::hash(root.to_string_lossy().as_bytes()).to_hex();
+        state_dir.join(format!("{}.jsonl", &hash[..16]))
+    }
+
+    /// Number of files recorded as completed
+    pub fn completed_count(&self) -> usize {
+        self.completed.len()
+    }
+
+    /// Whether a file was completed and has not changed since
+    pub fn is_done(&self, path: &Path, stamp: &FileStamp) -> bool {
+        self.completed.get(path) == Some(stamp)
+    }
+
+    /// Record completed files, synced to disk before returning
+    pub async fn mark_done(&mut self, files: impl IntoIterator<Item = (PathBuf, FileStamp)>) -> DamResult<()> {
+        let mut lines = Vec::new();
+        let mut done = Vec::new();
+        for (path, stamp) in files {
+            serde_json::to_writer(&mut lines, &ScanRecord { path: path.clone(), stamp })?;
+            lines.push(b'\n');
+            done.push((path, stamp));
+        }
+        if done.is_empty() {
+            return Ok(());
+        }
+
+        if self.journal.is_none() {
+            let fresh = self.completed.is_empty();
+            self.journal = Some(self.open_journal(fresh).await?);
+        }
+        let journal = self.journal.as_mut().expect("journal opened above");
+        journal.write_all(&lines).await?;
+        journal.flush().await?;
+        journal.sync_data().await?;
+        self.completed.extend(done);
+        Ok(())
+    }
+
+    /// Finish a complete scan: forget files that are no longer present
+    ///
+    /// Files at or below an `unreadable` path were not seen by the scan but
+    /// may still exist, so they are kept. Rewrites the journal with only
+    /// the remaining files, keeping it from growing across runs. Returns
+    /// the removed paths.
+    pub async fn finish(&mut self, present: &HashSet<PathBuf>, unreadable: &[PathBuf]) -> DamResult<Vec<PathBuf>> {
+        let mut removed: Vec<PathBuf> = self.completed.keys()
+            .filter(|path| !present.contains(*path))
+            .filter(|path| !unreadable.iter().any(|location| path.starts_with(location)))
+            .cloned()
+            .collect();
+        removed.sort();
+        for path in &removed {
+            self.completed.remove(path);
+        }
+
+        self.journal = None;
+        let mut journal = self.open_journal(true).await?;
+        for (path, stamp) in &self.completed {
+            let mut line = serde_json::to_vec(&ScanRecord { path: path.clone(), stamp: *stamp })?;
+            line.push(b'\n');
+            journal.write_all(&line).await?;
+        }
+        journal.flush().await?;
+        journal.sync_data().await?;
+        Ok(removed)
+    }
+
+    /// Forget all progress for a root
+    pub async fn clear(state_dir: &Path, root: &Path) -> DamResult<()> {
+        match tokio::fs::remove_file(Self::journal_path(state_dir, root)).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Open the journal for appending, starting a new one with a header if `fresh`
+    async fn open_journal(&self, fresh: bool) -> DamResult<File> {
+        if let Some(parent) = self.journal_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        if !fresh {
+            return Ok(OpenOptions::new().append(true).open(&self.journal_path).await?);
+        }
+
+        let mut journal = File::create(&self.journal_path).await?;
+        let mut header = serde_json::to_vec(&ScanHeader { version: SCAN_STATE_VERSION, root: self.root.clone() })?;
+        header.push(b'\n');
+        journal.write_all(&header).await?;
+        Ok(journal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_state_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = PathBuf::from("/archive");
+        let stamp = FileStamp { size: 10, modified_ms: 1_000 };
+
+        let mut state = ScanState::load(dir.path(), &root).await.unwrap();
+        state.mark_done([(root.join("a.png"), stamp), (root.join("b.png"), stamp)]).await.unwrap();
+        drop(state);
+
+        let mut state = ScanState::load(dir.path(), &root).await.unwrap();
+        assert_eq!(state.completed_count(), 2);
+        assert!(state.is_done(&root.join("a.png"), &stamp));
+        // Edited files are not done
+        assert!(!state.is_done(&root.join("a.png"), &FileStamp { size: 12, modified_ms: 2_000 }));
+
+        let present: HashSet<PathBuf> = [root.join("a.png"), root.join("c.png")].into_iter().collect();
+        assert_eq!(state.finish(&present, &[]).await.unwrap(), vec![root.join("b.png")]);
+        let state = ScanState::load(dir.path(), &root).await.unwrap();
+        assert_eq!(state.completed_count(), 1);
+
+        // Other roots have their own state
+        assert_eq!(ScanState::load(dir.path(), Path::new("/other")).await.unwrap().completed_count(), 0);
+
+        ScanState::clear(dir.path(), &root).await.unwrap();
+        assert_eq!(ScanState::load(dir.path(), &root).await.unwrap().completed_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_unreadable_folders_are_not_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = PathBuf::from("/share");
+        let stamp = FileStamp { size: 10, modified_ms: 1_000 };
+
+        let mut state = ScanState::load(dir.path(), &root).await.unwrap();
+        state.mark_done([(root.join("locked/a.png"), stamp), (root.join("gone.png"), stamp)]).await.unwrap();
+        let removed = state.finish(&HashSet::new(), &[root.join("locked")]).await.unwrap();
+        assert_eq!(removed, vec![root.join("gone.png")]);
+        assert!(state.is_done(&root.join("locked/a.png"), &stamp));
+    }
+}
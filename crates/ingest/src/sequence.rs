@@ -0,0 +1,269 @@
+//! Numbered image sequences
+//!
+//! Renders and animation exports arrive as folders of numbered frames
+//! (`shot_0001.png`, `shot_0002.png`, ...). Ingesting each frame as its own
+//! asset buries the library in near-identical images, so a directory import
+//! can optionally group such frames into one sequence asset. Frames belong
+//! to the same sequence when they sit in the same folder and share the text
+//! before and after the frame number; several sequences can be interleaved
+//! in one folder, and small gaps in the numbering are recorded rather than
+//! splitting the sequence.
+
+use schema::{AssetType, DamError, DamResult, SequenceMetadata};
+use regex::Regex;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Default frame naming: any prefix, the frame number, then the extension
+pub const DEFAULT_SEQUENCE_PATTERN: &str = r"^(.*?)(?P<frame>\d+)(\.[^.]+)$";
+
+/// Name of the capture group holding the frame number
+const FRAME_GROUP: &str = "frame";
+
+/// How numbered frames are recognized
+#[derive(Debug, Clone)]
+pub struct SequenceDetection {
+    pattern: Regex,
+    min_frames: usize,
+    max_gap: u64,
+}
+
+impl SequenceDetection {
+    /// Detection with the default naming pattern, at least 3 frames and
+    /// gaps of up to 100 missing frames
+    pub fn new() -> Self {
+        Self {
+            pattern: Regex::new(DEFAULT_SEQUENCE_PATTERN).expect("default sequence pattern is valid"),
+            min_frames: 3,
+            max_gap: 100,
+        }
+    }
+
+    /// Recognize frames by a custom regex on the file name
+    ///
+    /// The pattern must have a named group `frame` matching the frame
+    /// number; the text around it identifies the sequence.
+    pub fn with_pattern(mut self, pattern: &str) -> DamResult<Self> {
+        let pattern = Regex::new(pattern)
+            .map_err(|e| DamError::configuration(format!("Invalid sequence pattern: {}", e)))?;
+        if !pattern.capture_names().any(|name| name == Some(FRAME_GROUP)) {
+            return Err(DamError::configuration(format!(
+                "Sequence pattern needs a named group (?P<{}>...)", FRAME_GROUP
+            )));
+        }
+        self.pattern = pattern;
+        Ok(self)
+    }
+
+    /// Fewest frames that make a sequence; smaller groups stay single files
+    pub fn with_min_frames(mut self, min_frames: usize) -> Self {
+        self.min_frames = min_frames.max(2);
+        self
+    }
+
+    /// Largest run of missing frames that does not split a sequence
+    ///
+    /// Keeps unrelated numbered files, such as camera photos named by
+    /// counter or time, from being joined across large jumps.
+    pub fn with_max_gap(mut self, max_gap: u64) -> Self {
+        self.max_gap = max_gap;
+        self
+    }
+
+    /// Split image files into sequences and files that are not part of one
+    pub fn group(&self, paths: Vec<PathBuf>) -> (Vec<FrameSequence>, Vec<PathBuf>) {
+        let mut groups: BTreeMap<(PathBuf, String, String), BTreeMap<u64, Vec<PathBuf>>> = BTreeMap::new();
+        let mut singles = Vec::new();
+
+        for path in paths {
+            match self.frame_key(&path) {
+                Some((key, frame)) => groups.entry(key).or_default().entry(frame).or_default().push(path),
+                None => singles.push(path),
+            }
+        }
+
+        let mut sequences = Vec::new();
+        for ((directory, prefix, suffix), mut by_frame) in groups {
+            // The same frame number twice (e.g. `1` and `001`) is ambiguous
+            let ambiguous: Vec<u64> = by_frame.iter()
+                .filter(|(_, paths)| paths.len() > 1)
+                .map(|(frame, _)| *frame)
+                .collect();
+            for frame in ambiguous {
+                singles.extend(by_frame.remove(&frame).unwrap_or_default());
+            }
+
+            let frames = by_frame.into_iter()
+                .filter_map(|(frame, mut paths)| paths.pop().map(|path| (frame, path)));
+            let mut runs: Vec<Vec<(u64, PathBuf)>> = Vec::new();
+            for (frame, path) in frames {
+                match runs.last_mut() {
+                    Some(run) if frame - run[run.len() - 1].0 <= self.max_gap.saturating_add(1) => run.push((frame, path)),
+                    _ => runs.push(vec![(frame, path)]),
+                }
+            }
+
+            for frames in runs {
+                if frames.len() < self.min_frames {
+                    singles.extend(frames.into_iter().map(|(_, path)| path));
+                    continue;
+                }
+                sequences.push(FrameSequence {
+                    directory: directory.clone(),
+                    prefix: prefix.clone(),
+                    suffix: suffix.clone(),
+                    frames,
+                });
+            }
+        }
+
+        (sequences, singles)
+    }
+
+    /// Sequence key and frame number of an image file, if it is numbered
+    fn frame_key(&self, path: &Path) -> Option<((PathBuf, String, String), u64)> {
+        let extension = path.extension()?.to_str()?;
+        if AssetType::from_extension(extension) != AssetType::Image {
+            return None;
+        }
+
+        let name = path.file_name()?.to_str()?;
+        let captures = self.pattern.captures(name)?;
+        let frame = captures.name(FRAME_GROUP)?;
+        let number = frame.as_str().parse().ok()?;
+        let key = (
+            path.parent().map(Path::to_path_buf).unwrap_or_default(),
+            name[..frame.start()].to_string(),
+            name[frame.end()..].to_string(),
+        );
+        Some((key, number))
+    }
+}
+
+impl Default for SequenceDetection {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Frames of one sequence, sorted by frame number
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameSequence {
+    pub directory: PathBuf,
+    /// File name text before the frame number
+    pub prefix: String,
+    /// File name text after the frame number, including the extension
+    pub suffix: String,
+    pub frames: Vec<(u64, PathBuf)>,
+}
+
+impl FrameSequence {
+    /// Path of the lowest-numbered frame
+    pub fn first_frame(&self) -> &Path {
+        &self.frames[0].1
+    }
+
+    /// Path of the frame in the middle of the sequence
+    pub fn middle_frame(&self) -> &Path {
+        &self.frames[self.frames.len() / 2].1
+    }
+
+    /// Frame numbers missing between the first and last frame
+    pub fn missing_frames(&self) -> Vec<u64> {
+        let present: HashSet<u64> = self.frames.iter().map(|(frame, _)| *frame).collect();
+        let first = self.frames[0].0;
+        let last = self.frames[self.frames.len() - 1].0;
+        (first..=last).filter(|frame| !present.contains(frame)).collect()
+    }
+
+    /// File name pattern with one `#` per digit, e.g. `shot_####.png`
+    ///
+    /// Sequences whose frame numbers vary in width use a single `#`.
+    pub fn pattern(&self) -> String {
+        let widths: HashSet<usize> = self.frames.iter()
+            .filter_map(|(_, path)| path.file_name()?.to_str().map(str::len))
+            .map(|len| len.saturating_sub(self.prefix.len() + self.suffix.len()))
+            .collect();
+        let width = match widths.len() {
+            1 => widths.into_iter().next().unwrap_or(1).max(1),
+            _ => 1,
+        };
+        format!("{}{}{}", self.prefix, "#".repeat(width), self.suffix)
+    }
+
+    /// Metadata stored on the sequence asset
+    pub fn metadata(&self) -> SequenceMetadata {
+        SequenceMetadata {
+            pattern: self.pattern(),
+            frame_count: self.frames.len(),
+            first_frame: self.frames[0].0,
+            last_frame: self.frames[self.frames.len() - 1].0,
+            missing_frames: self.missing_frames(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(names: &[&str]) -> Vec<PathBuf> {
+        names.iter().map(|name| PathBuf::from("/renders").join(name)).collect()
+    }
+
+    #[test]
+    fn test_groups_interleaved_sequences_with_gaps() {
+        let detection = SequenceDetection::new();
+        let (sequences, mut singles) = detection.group(paths(&[
+            "shot_0001.png", "bg_0001.png", "shot_0002.png", "bg_0002.png",
+            "shot_0005.png", "bg_0003.png", "notes.txt", "cover.png", "take_1.png",
+        ]));
+        singles.sort();
+
+        assert_eq!(sequences.len(), 2);
+        let bg = &sequences[0];
+        assert_eq!(bg.pattern(), "bg_####.png");
+        assert_eq!(bg.frames.len(), 3);
+        assert!(bg.missing_frames().is_empty());
+
+        let shot = &sequences[1];
+        let metadata = shot.metadata();
+        assert_eq!(metadata.pattern, "shot_####.png");
+        assert_eq!((metadata.first_frame, metadata.last_frame), (1, 5));
+        assert_eq!(metadata.frame_count, 3);
+        assert_eq!(metadata.missing_frames, vec![3, 4]);
+        assert_eq!(shot.first_frame(), Path::new("/renders/shot_0001.png"));
+        assert_eq!(shot.middle_frame(), Path::new("/renders/shot_0002.png"));
+
+        // Too few frames, non-images and unnumbered files stay single
+        assert_eq!(singles, paths(&["cover.png", "notes.txt", "take_1.png"]));
+    }
+
+    #[test]
+    fn test_large_gap_splits_sequence() {
+        let detection = SequenceDetection::new().with_max_gap(10);
+        let (sequences, singles) = detection.group(paths(&[
+            "IMG_1.jpg", "IMG_2.jpg", "IMG_3.jpg", "IMG_500.jpg", "IMG_501.jpg",
+        ]));
+
+        assert_eq!(sequences.len(), 1);
+        assert_eq!(sequences[0].pattern(), "IMG_#.jpg");
+        assert_eq!(singles, paths(&["IMG_500.jpg", "IMG_501.jpg"]));
+    }
+
+    #[test]
+    fn test_custom_pattern() {
+        let detection = SequenceDetection::new()
+            .with_pattern(r"^(?P<frame>\d+)_.*\.exr$")
+            .unwrap()
+            .with_min_frames(2);
+        let (sequences, singles) = detection.group(paths(&["10_beauty.exr", "11_beauty.exr", "shot_0001.exr"]));
+
+        assert_eq!(sequences.len(), 1);
+        assert_eq!(sequences[0].pattern(), "##_beauty.exr");
+        assert_eq!(singles, paths(&["shot_0001.exr"]));
+
+        assert!(SequenceDetection::new().with_pattern(r"(\d+").is_err());
+        assert!(SequenceDetection::new().with_pattern(r"^(\d+)\.png$").is_err());
+    }
+}
@@ -0,0 +1,284 @@
+//! Sidecar metadata files
+//!
+//! Photo and render tools often keep metadata in a file next to the asset
+//! instead of inside it: `photo.jpg` + `photo.xmp` (Lightroom) or
+//! `photo.jpg.xmp` (darktable), `render.png` + `render.json`. XMP sidecars
+//! contribute keywords and the star rating; JSON sidecars are flattened into
+//! custom metadata fields.
+
+use crate::error::IngestError;
+use crate::keywords::{merge_keywords, parse_xmp_subjects};
+use schema::{Asset, DamResult, MAX_RATING};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Extensions of recognized sidecar files
+pub const SIDECAR_EXTENSIONS: &[&str] = &["xmp", "json"];
+
+/// Metadata read from a sidecar file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Sidecar {
+    /// Keywords to merge into the asset's tags
+    pub keywords: Vec<String>,
+    /// Star rating (0-5, -1 for rejected)
+    pub rating: Option<i8>,
+    /// Fields to merge into the asset's custom metadata
+    pub custom: HashMap<String, String>,
+}
+
+/// Whether a path looks like a sidecar file
+pub fn is_sidecar<P: AsRef<Path>>(path: P) -> bool {
+    path.as_ref()
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| SIDECAR_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Existing sidecar files belonging to an asset
+///
+/// Both naming conventions are checked: `photo.xmp` and `photo.jpg.xmp`.
+pub fn find_sidecars<P: AsRef<Path>>(asset_path: P) -> Vec<PathBuf> {
+    let asset_path = asset_path.as_ref();
+    let Some(filename) = asset_path.file_name() else {
+        return Vec::new();
+    };
+    
+    let mut sidecars = Vec::new();
+    for extension in SIDECAR_EXTENSIONS {
+        let replaced = asset_path.with_extension(extension);
+        let mut appended = filename.to_os_string();
+        appended.push(".");
+        appended.push(extension);
+        let appended = asset_path.with_file_name(appended);
+        
+        for candidate in [replaced, appended] {
+            if candidate != asset_path && candidate.is_file() && !sidecars.contains(&candidate) {
+                sidecars.push(candidate);
+            }
+        }
+    }
+    sidecars
+}
+
+/// Existing asset files a sidecar belongs to
+///
+/// `photo.jpg.xmp` belongs to `photo.jpg`; `photo.xmp` belongs to every
+/// non-sidecar file named `photo.*` in the same directory (e.g. a RAW and
+/// its JPEG). Returns an empty list if the asset has not arrived yet.
+pub fn find_companion_assets<P: AsRef<Path>>(sidecar_path: P) -> Vec<PathBuf> {
+    let sidecar_path = sidecar_path.as_ref();
+    let Some(stem) = sidecar_path.file_stem() else {
+        return Vec::new();
+    };
+    
+    let direct = sidecar_path.with_file_name(stem);
+    if direct.extension().is_some() && direct.is_file() && !is_sidecar(&direct) {
+        return vec![direct];
+    }
+    
+    let directory = match sidecar_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let Ok(entries) = std::fs::read_dir(directory) else {
+        return Vec::new();
+    };
+    
+    let mut companions: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.file_stem() == Some(stem) && !is_sidecar(path) && path.is_file())
+        .map(|path| sidecar_path.with_file_name(path.file_name().unwrap_or_default()))
+        .collect();
+    companions.sort();
+    companions
+}
+
+/// Read and parse a sidecar file
+pub async fn read_sidecar<P: AsRef<Path>>(path: P) -> DamResult<Sidecar> {
+    let path = path.as_ref();
+    let data = tokio::fs::read(path).await?;
+    
+    let extension = path.extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    
+    match extension.as_str() {
+        "xmp" => Ok(parse_xmp_sidecar(&String::from_utf8_lossy(&data))),
+        "json" => parse_json_sidecar(&data).map_err(|reason| {
+            IngestError::metadata_extraction_failed(path.to_path_buf(), reason).into()
+        }),
+        _ => Err(IngestError::unsupported_format(extension, path.to_path_buf()).into()),
+    }
+}
+
+/// Merge sidecar metadata into an asset
+///
+/// Keywords are added to the tags and custom fields overwrite fields of
+/// the same name, so sidecar values take precedence over embedded ones.
+/// XMP ratings of 1-5 stars set the asset rating; 0 (unrated) and -1
+/// (rejected) leave it unset.
+pub fn apply_sidecar(asset: &mut Asset, sidecar: Sidecar) {
+    asset.tags = merge_keywords(std::mem::take(&mut asset.tags), sidecar.keywords);
+    if let Some(rating) = sidecar.rating.filter(|rating| *rating > 0) {
+        asset.rating = Some((rating as u8).min(MAX_RATING));
+    }
+    asset.metadata.custom.extend(sidecar.custom);
+}
+
+/// Read keywords and rating from an XMP sidecar
+fn parse_xmp_sidecar(xmp: &str) -> Sidecar {
+    Sidecar {
+        keywords: parse_xmp_subjects(xmp),
+        rating: parse_xmp_rating(xmp),
+        custom: HashMap::new(),
+    }
+}
+
+/// Read `xmp:Rating`, written either as an attribute or as an element
+fn parse_xmp_rating(xmp: &str) -> Option<i8> {
+    let value = if let Some(start) = xmp.find("xmp:Rating=\"") {
+        let rest = &xmp[start + "xmp:Rating=\"".len()..];
+        &rest[..rest.find('"')?]
+    } else {
+        let start = xmp.find("<xmp:Rating>")? + "<xmp:Rating>".len();
+        let rest = &xmp[start..];
+        &rest[..rest.find("</xmp:Rating>")?]
+    };
+    
+    // Some tools write fractional ratings
+    let rating = value.trim().parse::<f32>().ok()?.round();
+    (-1.0..=5.0).contains(&rating).then_some(rating as i8)
+}
+
+/// Flatten a JSON object into custom metadata fields
+fn parse_json_sidecar(data: &[u8]) -> Result<Sidecar, String> {
+    let value: serde_json::Value = serde_json::from_slice(data)
+        .map_err(|e| format!("Invalid JSON sidecar: {}", e))?;
+    
+    if !value.is_object() {
+        return Err("JSON sidecar must contain an object".to_string());
+    }
+    
+    let mut custom = HashMap::new();
+    flatten_json("", &value, &mut custom);
+    
+    Ok(Sidecar { custom, ..Sidecar::default() })
+}
+
+/// Flatten nested objects into dotted keys; arrays of scalars are joined
+fn flatten_json(prefix: &str, value: &serde_json::Value, out: &mut HashMap<String, String>) {
+    use serde_json::Value;
+    
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let key = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+                flatten_json(&key, value, out);
+            }
+        }
+        Value::Array(items) if items.iter().all(|item| !item.is_object() && !item.is_array()) => {
+            let joined: Vec<String> = items.iter().filter_map(json_scalar).collect();
+            out.insert(prefix.to_string(), joined.join(", "));
+        }
+        Value::Array(_) => {
+            out.insert(prefix.to_string(), value.to_string());
+        }
+        _ => {
+            if let Some(scalar) = json_scalar(value) {
+                out.insert(prefix.to_string(), scalar);
+            }
+        }
+    }
+}
+
+fn json_scalar(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::Null => None,
+        serde_json::Value::String(text) => Some(text.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::AssetType;
+    
+    const XMP: &str = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF>
+        <rdf:Description xmp:Rating="4">
+        <dc:subject><rdf:Bag><rdf:li>harbor</rdf:li><rdf:li>Boats</rdf:li></rdf:Bag></dc:subject>
+        </rdf:Description></rdf:RDF></x:xmpmeta>"#;
+    
+    #[test]
+    fn test_sidecar_lookup() {
+        let dir = tempfile::tempdir().unwrap();
+        let photo = dir.path().join("photo.jpg");
+        let raw = dir.path().join("photo.cr2");
+        std::fs::write(&photo, b"jpeg").unwrap();
+        std::fs::write(&raw, b"raw").unwrap();
+        
+        assert!(find_sidecars(&photo).is_empty());
+        
+        let xmp = dir.path().join("photo.xmp");
+        let json = dir.path().join("photo.jpg.json");
+        std::fs::write(&xmp, XMP).unwrap();
+        std::fs::write(&json, "{}").unwrap();
+        assert_eq!(find_sidecars(&photo), vec![xmp.clone(), json.clone()]);
+        
+        assert_eq!(find_companion_assets(&xmp), vec![raw, photo.clone()]);
+        assert_eq!(find_companion_assets(&json), vec![photo]);
+        assert!(find_companion_assets(dir.path().join("other.xmp")).is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_sidecar_parsing() {
+        let dir = tempfile::tempdir().unwrap();
+        
+        let xmp = dir.path().join("photo.xmp");
+        std::fs::write(&xmp, XMP).unwrap();
+        let sidecar = read_sidecar(&xmp).await.unwrap();
+        assert_eq!(sidecar.keywords, vec!["harbor", "Boats"]);
+        assert_eq!(sidecar.rating, Some(4));
+        assert_eq!(parse_xmp_rating("<xmp:Rating>-1</xmp:Rating>"), Some(-1));
+        
+        let json = dir.path().join("render.json");
+        std::fs::write(&json, r#"{"shot": "sh010", "frames": 240, "camera": {"lens": 35}, "passes": ["beauty", "depth"], "note": null}"#).unwrap();
+        let sidecar = read_sidecar(&json).await.unwrap();
+        assert_eq!(sidecar.custom.get("shot").map(String::as_str), Some("sh010"));
+        assert_eq!(sidecar.custom.get("frames").map(String::as_str), Some("240"));
+        assert_eq!(sidecar.custom.get("camera.lens").map(String::as_str), Some("35"));
+        assert_eq!(sidecar.custom.get("passes").map(String::as_str), Some("beauty, depth"));
+        assert!(!sidecar.custom.contains_key("note"));
+        
+        std::fs::write(&json, "[1, 2]").unwrap();
+        assert!(read_sidecar(&json).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_refresh_sidecars() {
+        let dir = tempfile::tempdir().unwrap();
+        let photo = dir.path().join("photo.png");
+        std::fs::write(dir.path().join("photo.xmp"), XMP).unwrap();
+        std::fs::write(dir.path().join("photo.json"), r#"{"client": "acme"}"#).unwrap();
+        
+        let service = crate::IngestService::new().unwrap();
+        let mut asset = Asset::new(photo, AssetType::Image);
+        asset.tags = vec!["boats".to_string()];
+        assert!(service.refresh_sidecars(&mut asset).await.unwrap());
+        assert_eq!(asset.tags, vec!["boats", "harbor"]);
+        assert_eq!(asset.rating, Some(4));
+        assert_eq!(asset.metadata.custom.get("client").map(String::as_str), Some("acme"));
+        
+        // An edited sidecar updates the existing asset in place
+        let id = asset.id;
+        std::fs::write(dir.path().join("photo.json"), r#"{"client": "globex"}"#).unwrap();
+        assert!(service.refresh_sidecars(&mut asset).await.unwrap());
+        assert_eq!(asset.id, id);
+        assert_eq!(asset.metadata.custom.get("client").map(String::as_str), Some("globex"));
+        
+        let mut other = Asset::new(dir.path().join("other.png"), AssetType::Image);
+        assert!(!service.refresh_sidecars(&mut other).await.unwrap());
+    }
+}
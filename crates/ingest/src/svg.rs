@@ -0,0 +1,92 @@
+//! SVG parsing and rasterization
+//!
+//! Uses `usvg` to resolve document size (explicit `width`/`height` or the
+//! `viewBox`) and `resvg` to render raster thumbnails.
+
+use resvg::tiny_skia;
+use resvg::usvg;
+
+/// Check whether the start of a file looks like an SVG document
+pub fn is_svg_content(buffer: &[u8]) -> bool {
+    let text = String::from_utf8_lossy(buffer);
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+
+    if text.starts_with("<svg") {
+        return true;
+    }
+
+    // XML prolog, doctype or comments may precede the root element
+    (text.starts_with("<?xml") || text.starts_with("<!--") || text.starts_with("<!DOCTYPE"))
+        && (text.contains("<svg") || text.contains("<!DOCTYPE svg"))
+}
+
+/// Parse SVG data into a render tree
+pub fn parse_svg(data: &[u8]) -> Result<usvg::Tree, String> {
+    usvg::Tree::from_data(data, &usvg::Options::default())
+        .map_err(|e| e.to_string())
+}
+
+/// Intrinsic size of an SVG in pixels, rounded up
+pub fn svg_dimensions(tree: &usvg::Tree) -> (u32, u32) {
+    let size = tree.size();
+    (size.width().ceil().max(1.0) as u32, size.height().ceil().max(1.0) as u32)
+}
+
+/// Render an SVG to fit within `max_size`, keeping its aspect ratio
+///
+/// Vector content is scaled up as well as down, so small icons still get
+/// a full-size thumbnail. Transparent areas are returned as alpha.
+pub fn render_svg(tree: &usvg::Tree, max_size: (u32, u32)) -> Result<image::RgbaImage, String> {
+    let size = tree.size();
+    let scale = (max_size.0 as f32 / size.width()).min(max_size.1 as f32 / size.height());
+
+    let width = (size.width() * scale).round().max(1.0) as u32;
+    let height = (size.height() * scale).round().max(1.0) as u32;
+
+    let mut pixmap = tiny_skia::Pixmap::new(width, height)
+        .ok_or_else(|| format!("Invalid SVG render size {}x{}", width, height))?;
+
+    resvg::render(tree, tiny_skia::Transform::from_scale(scale, scale), &mut pixmap.as_mut());
+
+    // tiny-skia stores premultiplied alpha; convert back to straight RGBA
+    let mut rgba = image::RgbaImage::new(width, height);
+    for (pixel, source) in rgba.pixels_mut().zip(pixmap.pixels()) {
+        let color = source.demultiply();
+        *pixel = image::Rgba([color.red(), color.green(), color.blue(), color.alpha()]);
+    }
+
+    Ok(rgba)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VIEWBOX_ONLY: &str = r#"<?xml version="1.0"?>
+<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 200 100">
+  <rect x="0" y="0" width="200" height="100" fill="red"/>
+</svg>"#;
+
+    #[test]
+    fn test_svg_sniffing() {
+        assert!(is_svg_content(VIEWBOX_ONLY.as_bytes()));
+        assert!(is_svg_content(b"<svg xmlns=\"http://www.w3.org/2000/svg\"/>"));
+        assert!(!is_svg_content(b"<?xml version=\"1.0\"?><note/>"));
+        assert!(!is_svg_content(&[0x89, 0x50, 0x4E, 0x47]));
+    }
+
+    #[test]
+    fn test_viewbox_dimensions_and_render() {
+        let tree = parse_svg(VIEWBOX_ONLY.as_bytes()).unwrap();
+        assert_eq!(svg_dimensions(&tree), (200, 100));
+
+        let image = render_svg(&tree, (512, 512)).unwrap();
+        assert_eq!(image.dimensions(), (512, 256));
+        assert_eq!(image.get_pixel(10, 10).0, [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_malformed_svg() {
+        assert!(parse_svg(b"<svg><rect").is_err());
+    }
+}
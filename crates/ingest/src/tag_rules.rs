@@ -0,0 +1,317 @@
+//! Tags from file naming conventions
+//!
+//! Studios often encode client, project or version in file names
+//! (`ACME_poster_v03.psd`) long before any tagging model runs. A rule pairs
+//! a regex with capture groups with a tag template such as
+//! `client:{client}`; every match of the regex in the file name (or the
+//! whole path) yields a tag with the captured text filled in. Rules are
+//! evaluated in the order they were added and their tags are merged with
+//! the asset's existing tags, ignoring case.
+//!
+//! Rules serialize as their pattern, template and target, and are
+//! validated again when deserialized, so they can live in a settings file.
+
+use crate::keywords::merge_keywords;
+use schema::{DamError, DamResult};
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Part of a file path a rule is matched against
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleTarget {
+    /// The file name with extension
+    #[default]
+    FileName,
+    /// The full path, so folder names can be matched too
+    Path,
+}
+
+/// Piece of a parsed tag template
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TemplatePart {
+    Literal(String),
+    Named(String),
+    Numbered(usize),
+}
+
+/// One regex-to-tag rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "TagRuleConfig", into = "TagRuleConfig")]
+pub struct FilenameTagRule {
+    pattern: Regex,
+    template: String,
+    parts: Vec<TemplatePart>,
+    target: RuleTarget,
+}
+
+impl FilenameTagRule {
+    /// Rule producing `template` for every match of `pattern` in the file name
+    ///
+    /// The template refers to capture groups by name (`{client}`) or number
+    /// (`{1}`); `{{` and `}}` are literal braces. Every referenced group has
+    /// to exist in the pattern.
+    pub fn new(pattern: &str, template: &str) -> DamResult<Self> {
+        let pattern = Regex::new(pattern)
+            .map_err(|e| DamError::configuration(format!("Invalid tag rule pattern: {}", e)))?;
+        let parts = parse_template(template)?;
+
+        for part in &parts {
+            let known = match part {
+                TemplatePart::Named(name) => pattern.capture_names().any(|group| group == Some(name.as_str())),
+                TemplatePart::Numbered(index) => *index < pattern.captures_len(),
+                TemplatePart::Literal(_) => true,
+            };
+            if !known {
+                return Err(DamError::configuration(format!(
+                    "Tag template {:?} refers to a group the pattern does not have", template
+                )));
+            }
+        }
+
+        Ok(Self {
+            pattern,
+            template: template.to_string(),
+            parts,
+            target: RuleTarget::default(),
+        })
+    }
+
+    /// Match against another part of the path
+    pub fn with_target(mut self, target: RuleTarget) -> Self {
+        self.target = target;
+        self
+    }
+
+    pub fn pattern(&self) -> &str {
+        self.pattern.as_str()
+    }
+
+    pub fn template(&self) -> &str {
+        &self.template
+    }
+
+    pub fn target(&self) -> RuleTarget {
+        self.target
+    }
+
+    /// Tags this rule produces for a path, in match order
+    ///
+    /// A match where a referenced group captured nothing yields no tag.
+    pub fn tags(&self, path: &Path) -> Vec<String> {
+        let subject = match self.target {
+            RuleTarget::FileName => match path.file_name() {
+                Some(name) => name.to_string_lossy(),
+                None => return Vec::new(),
+            },
+            RuleTarget::Path => path.to_string_lossy(),
+        };
+
+        self.pattern.captures_iter(&subject)
+            .filter_map(|captures| self.fill(&captures))
+            .collect()
+    }
+
+    fn fill(&self, captures: &Captures) -> Option<String> {
+        let mut tag = String::new();
+        for part in &self.parts {
+            let value = match part {
+                TemplatePart::Literal(text) => {
+                    tag.push_str(text);
+                    continue;
+                }
+                TemplatePart::Named(name) => captures.name(name),
+                TemplatePart::Numbered(index) => captures.get(*index),
+            };
+            match value.map(|m| m.as_str()) {
+                Some(text) if !text.is_empty() => tag.push_str(text),
+                _ => return None,
+            }
+        }
+        let tag = tag.trim();
+        (!tag.is_empty()).then(|| tag.to_string())
+    }
+}
+
+/// Serialized form of a rule
+#[derive(Serialize, Deserialize)]
+struct TagRuleConfig {
+    pattern: String,
+    template: String,
+    #[serde(default)]
+    target: RuleTarget,
+}
+
+impl TryFrom<TagRuleConfig> for FilenameTagRule {
+    type Error = DamError;
+
+    fn try_from(config: TagRuleConfig) -> DamResult<Self> {
+        Ok(Self::new(&config.pattern, &config.template)?.with_target(config.target))
+    }
+}
+
+impl From<FilenameTagRule> for TagRuleConfig {
+    fn from(rule: FilenameTagRule) -> Self {
+        Self {
+            pattern: rule.pattern.as_str().to_string(),
+            template: rule.template,
+            target: rule.target,
+        }
+    }
+}
+
+/// Ordered list of filename tag rules
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct FilenameTagRules {
+    rules: Vec<FilenameTagRule>,
+}
+
+impl FilenameTagRules {
+    /// No rules; file names add no tags
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a rule after the existing ones
+    pub fn with(mut self, rule: FilenameTagRule) -> Self {
+        self.push(rule);
+        self
+    }
+
+    pub fn push(&mut self, rule: FilenameTagRule) {
+        self.rules.push(rule);
+    }
+
+    /// Remove every rule
+    pub fn clear(&mut self) {
+        self.rules.clear();
+    }
+
+    pub fn rules(&self) -> &[FilenameTagRule] {
+        &self.rules
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Tags of all rules for a path, in rule order without duplicates
+    pub fn tags_for(&self, path: &Path) -> Vec<String> {
+        self.rules.iter().fold(Vec::new(), |tags, rule| merge_keywords(tags, rule.tags(path)))
+    }
+}
+
+fn parse_template(template: &str) -> DamResult<Vec<TemplatePart>> {
+    let invalid = |reason: &str| DamError::configuration(format!("Invalid tag template {:?}: {}", template, reason));
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                literal.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                literal.push('}');
+            }
+            '{' => {
+                let mut group = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => group.push(c),
+                        None => return Err(invalid("unclosed '{'")),
+                    }
+                }
+                if group.is_empty() {
+                    return Err(invalid("empty group reference"));
+                }
+                if !literal.is_empty() {
+                    parts.push(TemplatePart::Literal(std::mem::take(&mut literal)));
+                }
+                parts.push(match group.parse::<usize>() {
+                    Ok(index) => TemplatePart::Numbered(index),
+                    Err(_) => TemplatePart::Named(group),
+                });
+            }
+            '}' => return Err(invalid("unmatched '}'")),
+            c => literal.push(c),
+        }
+    }
+    if !literal.is_empty() {
+        parts.push(TemplatePart::Literal(literal));
+    }
+    Ok(parts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rule_tags() {
+        let rule = FilenameTagRule::new(r"^(?P<client>[A-Z]+)_", "client:{client}").unwrap();
+        assert_eq!(rule.tags(Path::new("/work/ACME_poster_v03.psd")), vec!["client:ACME"]);
+        assert!(rule.tags(Path::new("/work/poster.psd")).is_empty());
+
+        // Every match counts, and numbered groups work too
+        let rule = FilenameTagRule::new(r"#(\w+)", "{1}").unwrap();
+        assert_eq!(rule.tags(Path::new("shot #forest #night.png")), vec!["forest", "night"]);
+
+        // Path rules see folder names
+        let rule = FilenameTagRule::new(r"/projects/([^/]+)/", "project:{1}").unwrap()
+            .with_target(RuleTarget::Path);
+        assert_eq!(rule.tags(Path::new("/projects/apollo/renders/a.png")), vec!["project:apollo"]);
+
+        // An optional group that did not participate yields no tag
+        let rule = FilenameTagRule::new(r"_v(\d+)?", "version:{1}").unwrap();
+        assert!(rule.tags(Path::new("poster_v.psd")).is_empty());
+        assert_eq!(rule.tags(Path::new("poster_v03.psd")), vec!["version:03"]);
+    }
+
+    #[test]
+    fn test_rules_in_order() {
+        let rules = FilenameTagRules::new()
+            .with(FilenameTagRule::new(r"^([A-Za-z]+)_", "client:{1}").unwrap())
+            .with(FilenameTagRule::new(r"_v(\d+)", "version:{1}").unwrap())
+            .with(FilenameTagRule::new(r"^([a-z]+)_", "CLIENT:{1}").unwrap());
+        // The third rule repeats the first one's tag in another case
+        assert_eq!(rules.tags_for(Path::new("acme_poster_v03.psd")), vec!["client:acme", "version:03"]);
+    }
+
+    #[test]
+    fn test_rules_serde() {
+        let json = serde_json::json!([
+            { "pattern": r"^([A-Za-z]+)_", "template": "client:{1}" },
+            { "pattern": r"/projects/([^/]+)/", "template": "project:{1}", "target": "path" },
+        ]);
+        let rules: FilenameTagRules = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(rules.rules()[1].target(), RuleTarget::Path);
+        assert_eq!(rules.tags_for(Path::new("/projects/apollo/acme_a.png")), vec!["client:acme", "project:apollo"]);
+
+        let round_trip: FilenameTagRules = serde_json::from_value(serde_json::to_value(&rules).unwrap()).unwrap();
+        assert_eq!(round_trip.rules().len(), 2);
+        assert_eq!(round_trip.rules()[0].template(), "client:{1}");
+
+        // Rules are validated when read back
+        let invalid = serde_json::json!([{ "pattern": r"(\w+)", "template": "{2}" }]);
+        assert!(serde_json::from_value::<FilenameTagRules>(invalid).is_err());
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        assert!(FilenameTagRule::new(r"(", "x").is_err());
+        assert!(FilenameTagRule::new(r"(?P<client>\w+)", "client:{project}").is_err());
+        assert!(FilenameTagRule::new(r"(\w+)", "{2}").is_err());
+        assert!(FilenameTagRule::new(r"(\w+)", "{1").is_err());
+        assert!(FilenameTagRule::new(r"(\w+)", "a}b").is_err());
+
+        let rule = FilenameTagRule::new(r"(\w+)\.txt", "{{{1}}}").unwrap();
+        assert_eq!(rule.tags(Path::new("notes.txt")), vec!["{notes}"]);
+    }
+}
@@ -0,0 +1,247 @@
+//! Page structure of TIFF files
+//!
+//! A TIFF holds a chain of image file directories (IFDs), one per page.
+//! Scanners write multi-page documents this way and some exporters add
+//! reduced-resolution copies as extra IFDs. The image decoder only reads
+//! the first IFD, so the chain is walked here directly, without decoding
+//! pixels, to count pages and read each page's size. Classic and BigTIFF
+//! files in either byte order are handled; stripped and tiled pages only
+//! differ in which tags describe their data.
+
+use schema::ImagePage;
+use std::collections::HashSet;
+
+/// Most IFDs read before giving up on a file
+const MAX_TIFF_PAGES: usize = 4096;
+
+const TAG_NEW_SUBFILE_TYPE: u16 = 254;
+const TAG_SUBFILE_TYPE: u16 = 255;
+const TAG_IMAGE_WIDTH: u16 = 256;
+const TAG_IMAGE_LENGTH: u16 = 257;
+const TAG_TILE_WIDTH: u16 = 322;
+
+/// NewSubfileType bit marking a reduced-resolution copy of another page
+const REDUCED_RESOLUTION: u64 = 1;
+
+/// Old-style SubfileType value of a reduced-resolution image
+const SUBFILE_REDUCED: u64 = 2;
+
+const TYPE_SHORT: u16 = 3;
+const TYPE_LONG: u16 = 4;
+const TYPE_LONG8: u16 = 16;
+
+/// Pages of a TIFF, in file order
+///
+/// Reduced-resolution copies are not pages and are skipped. Returns None
+/// if the data is not a TIFF; a chain that breaks off partway returns the
+/// pages read up to that point.
+pub fn read_pages(data: &[u8]) -> Option<Vec<ImagePage>> {
+    let reader = Reader::new(data)?;
+    let mut pages = Vec::new();
+    let mut visited = HashSet::new();
+    let mut offset = reader.first_ifd()?;
+
+    // A zero offset ends the chain; revisiting one means a corrupt loop
+    while offset != 0 && visited.insert(offset) && visited.len() <= MAX_TIFF_PAGES {
+        let Some(ifd) = reader.ifd(offset) else {
+            break;
+        };
+        if !ifd.reduced && ifd.width > 0 && ifd.height > 0 {
+            pages.push(ImagePage {
+                width: ifd.width,
+                height: ifd.height,
+                tiled: ifd.tiled,
+            });
+        }
+        offset = ifd.next;
+    }
+
+    Some(pages)
+}
+
+/// Fields of one IFD that matter for page listing
+struct Ifd {
+    width: u32,
+    height: u32,
+    tiled: bool,
+    reduced: bool,
+    next: u64,
+}
+
+/// Byte-order and offset-size aware view of TIFF data
+struct Reader<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+    big_tiff: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Option<Self> {
+        let little_endian = match data.get(0..2)? {
+            b"II" => true,
+            b"MM" => false,
+            _ => return None,
+        };
+        let mut reader = Self { data, little_endian, big_tiff: false };
+        match reader.u16_at(2)? {
+            42 => {}
+            // BigTIFF: 8-byte offsets, declared in the header
+            43 if reader.u16_at(4)? == 8 => reader.big_tiff = true,
+            _ => return None,
+        }
+        Some(reader)
+    }
+
+    fn first_ifd(&self) -> Option<u64> {
+        if self.big_tiff {
+            self.u64_at(8)
+        } else {
+            self.u32_at(4).map(u64::from)
+        }
+    }
+
+    fn ifd(&self, offset: u64) -> Option<Ifd> {
+        let offset = usize::try_from(offset).ok()?;
+        let (count, entries, entry_size) = if self.big_tiff {
+            (self.u64_at(offset)?, offset + 8, 20)
+        } else {
+            (u64::from(self.u16_at(offset)?), offset + 2, 12)
+        };
+        let count = usize::try_from(count).ok()?;
+        // Reject counts the data cannot hold before looping over them
+        let end = entries.checked_add(count.checked_mul(entry_size)?)?;
+        if end > self.data.len() {
+            return None;
+        }
+
+        let mut ifd = Ifd { width: 0, height: 0, tiled: false, reduced: false, next: 0 };
+        for entry in (entries..end).step_by(entry_size) {
+            let tag = self.u16_at(entry)?;
+            let value = || self.entry_value(entry);
+            match tag {
+                TAG_IMAGE_WIDTH => ifd.width = value().and_then(|v| u32::try_from(v).ok()).unwrap_or(0),
+                TAG_IMAGE_LENGTH => ifd.height = value().and_then(|v| u32::try_from(v).ok()).unwrap_or(0),
+                TAG_TILE_WIDTH => ifd.tiled = true,
+                TAG_NEW_SUBFILE_TYPE => ifd.reduced |= value().is_some_and(|v| v & REDUCED_RESOLUTION != 0),
+                TAG_SUBFILE_TYPE => ifd.reduced |= value() == Some(SUBFILE_REDUCED),
+                _ => {}
+            }
+        }
+
+        ifd.next = if self.big_tiff {
+            self.u64_at(end).unwrap_or(0)
+        } else {
+            self.u32_at(end).map(u64::from).unwrap_or(0)
+        };
+        Some(ifd)
+    }
+
+    /// First value of an entry stored inline in its value field
+    fn entry_value(&self, entry: usize) -> Option<u64> {
+        let field_type = self.u16_at(entry + 2)?;
+        let value_at = entry + if self.big_tiff { 12 } else { 8 };
+        match field_type {
+            TYPE_SHORT => self.u16_at(value_at).map(u64::from),
+            TYPE_LONG => self.u32_at(value_at).map(u64::from),
+            TYPE_LONG8 if self.big_tiff => self.u64_at(value_at),
+            _ => None,
+        }
+    }
+
+    fn bytes<const N: usize>(&self, offset: usize) -> Option<[u8; N]> {
+        self.data.get(offset..offset.checked_add(N)?)?.try_into().ok()
+    }
+
+    fn u16_at(&self, offset: usize) -> Option<u16> {
+        let bytes = self.bytes(offset)?;
+        Some(if self.little_endian { u16::from_le_bytes(bytes) } else { u16::from_be_bytes(bytes) })
+    }
+
+    fn u32_at(&self, offset: usize) -> Option<u32> {
+        let bytes = self.bytes(offset)?;
+        Some(if self.little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) })
+    }
+
+    fn u64_at(&self, offset: usize) -> Option<u64> {
+        let bytes = self.bytes(offset)?;
+        Some(if self.little_endian { u64::from_le_bytes(bytes) } else { u64::from_be_bytes(bytes) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal classic TIFF with one IFD per `(width, height, tags)` page
+    fn build_tiff(little_endian: bool, pages: &[(u32, u32, &[(u16, u16, u32)])]) -> Vec<u8> {
+        let u16b = |v: u16| if little_endian { v.to_le_bytes() } else { v.to_be_bytes() };
+        let u32b = |v: u32| if little_endian { v.to_le_bytes() } else { v.to_be_bytes() };
+
+        let mut data = Vec::new();
+        data.extend_from_slice(if little_endian { b"II" } else { b"MM" });
+        data.extend_from_slice(&u16b(42));
+        data.extend_from_slice(&u32b(8));
+
+        for (index, (width, height, extra)) in pages.iter().enumerate() {
+            let mut entries = vec![(TAG_IMAGE_WIDTH, TYPE_LONG, *width), (TAG_IMAGE_LENGTH, TYPE_SHORT, *height)];
+            entries.extend_from_slice(extra);
+            data.extend_from_slice(&u16b(entries.len() as u16));
+            for (tag, field_type, value) in entries {
+                data.extend_from_slice(&u16b(tag));
+                data.extend_from_slice(&u16b(field_type));
+                data.extend_from_slice(&u32b(1));
+                // Inline values are left-justified in the 4-byte field
+                if field_type == TYPE_SHORT {
+                    data.extend_from_slice(&u16b(value as u16));
+                    data.extend_from_slice(&[0, 0]);
+                } else {
+                    data.extend_from_slice(&u32b(value));
+                }
+            }
+            let next = if index + 1 < pages.len() { data.len() as u32 + 4 } else { 0 };
+            data.extend_from_slice(&u32b(next));
+        }
+        data
+    }
+
+    #[test]
+    fn test_pages_in_both_byte_orders() {
+        for little_endian in [true, false] {
+            let data = build_tiff(little_endian, &[
+                (2480, 3508, &[]),
+                (3508, 2480, &[(TAG_TILE_WIDTH, TYPE_SHORT, 256)]),
+                // Reduced-resolution preview of the first page
+                (248, 350, &[(TAG_NEW_SUBFILE_TYPE, TYPE_LONG, 1)]),
+            ]);
+            let pages = read_pages(&data).unwrap();
+            assert_eq!(pages.len(), 2);
+            assert_eq!((pages[0].width, pages[0].height, pages[0].tiled), (2480, 3508, false));
+            assert_eq!((pages[1].width, pages[1].height, pages[1].tiled), (3508, 2480, true));
+        }
+    }
+
+    #[test]
+    fn test_broken_chains() {
+        assert!(read_pages(b"not a tiff").is_none());
+
+        // An IFD pointing back at itself ends the walk
+        let mut data = build_tiff(true, &[(10, 10, &[])]);
+        let len = data.len();
+        data[len - 4..].copy_from_slice(&8u32.to_le_bytes());
+        assert_eq!(read_pages(&data).unwrap().len(), 1);
+
+        // A truncated second IFD keeps the pages read so far
+        let mut data = build_tiff(true, &[(10, 10, &[]), (20, 20, &[])]);
+        data.truncate(data.len() - 10);
+        assert_eq!(read_pages(&data).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_single_page_from_encoder() {
+        let mut data = std::io::Cursor::new(Vec::new());
+        image::RgbImage::new(6, 4).write_to(&mut data, image::ImageOutputFormat::Tiff).unwrap();
+        let pages = read_pages(data.get_ref()).unwrap();
+        assert_eq!(pages.len(), 1);
+        assert_eq!((pages[0].width, pages[0].height), (6, 4));
+    }
+}
@@ -0,0 +1,124 @@
+//! User-defined extension to asset type mappings
+//!
+//! `AssetType::from_extension` only knows the built-in extensions, so a
+//! studio's in-house formats come in as `Unknown`, and an ambiguous
+//! extension always gets the built-in type. Overrides are consulted before
+//! the built-in match; since metadata parsing and preview generation both
+//! route on the asset type, an override changes how the file is handled
+//! everywhere, not just how it is labeled.
+
+use schema::AssetType;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Extension to asset type overrides, consulted before the built-in match
+///
+/// Serializes as a plain map from extension to asset type.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "HashMap<String, AssetType>", into = "HashMap<String, AssetType>")]
+pub struct AssetTypeOverrides {
+    /// Lowercase extensions without the leading dot
+    overrides: HashMap<String, AssetType>,
+}
+
+impl AssetTypeOverrides {
+    /// No overrides; every extension uses the built-in type
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Treat files with `extension` as `asset_type`
+    ///
+    /// The extension is case-insensitive and may include a leading dot.
+    pub fn with(mut self, extension: &str, asset_type: AssetType) -> Self {
+        self.insert(extension, asset_type);
+        self
+    }
+
+    /// Add or replace an override
+    pub fn insert(&mut self, extension: &str, asset_type: AssetType) {
+        self.overrides.insert(normalize(extension), asset_type);
+    }
+
+    /// Remove an override, restoring the built-in type
+    pub fn remove(&mut self, extension: &str) -> Option<AssetType> {
+        self.overrides.remove(&normalize(extension))
+    }
+
+    /// Overridden type of an extension, if any
+    pub fn get(&self, extension: &str) -> Option<&AssetType> {
+        self.overrides.get(&normalize(extension))
+    }
+
+    /// Whether no extension is overridden
+    pub fn is_empty(&self) -> bool {
+        self.overrides.is_empty()
+    }
+
+    /// Asset type of an extension: the override, else the built-in type
+    pub fn asset_type(&self, extension: &str) -> AssetType {
+        self.get(extension)
+            .cloned()
+            .unwrap_or_else(|| AssetType::from_extension(extension))
+    }
+
+    /// Whether an override makes an extension a known asset type
+    pub fn recognizes(&self, extension: &str) -> bool {
+        self.get(extension).is_some_and(|asset_type| *asset_type != AssetType::Unknown)
+    }
+}
+
+impl From<HashMap<String, AssetType>> for AssetTypeOverrides {
+    fn from(overrides: HashMap<String, AssetType>) -> Self {
+        let mut result = Self::new();
+        for (extension, asset_type) in overrides {
+            result.insert(&extension, asset_type);
+        }
+        result
+    }
+}
+
+impl From<AssetTypeOverrides> for HashMap<String, AssetType> {
+    fn from(overrides: AssetTypeOverrides) -> Self {
+        overrides.overrides
+    }
+}
+
+fn normalize(extension: &str) -> String {
+    extension.trim_start_matches('.').to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overrides_take_precedence() {
+        let mut overrides = AssetTypeOverrides::new()
+            .with(".XYZ", AssetType::Image)
+            .with("dae", AssetType::Document);
+
+        assert_eq!(overrides.asset_type("xyz"), AssetType::Image);
+        assert!(overrides.recognizes("Xyz"));
+        assert_eq!(overrides.asset_type("dae"), AssetType::Document);
+
+        // Extensions without an override use the built-in match
+        assert_eq!(overrides.asset_type("exr"), AssetType::Image);
+        assert_eq!(overrides.asset_type("heic"), AssetType::Image);
+        assert_eq!(overrides.asset_type("abc"), AssetType::Unknown);
+        assert!(!overrides.recognizes("png"));
+
+        assert_eq!(overrides.remove("DAE"), Some(AssetType::Document));
+        assert_eq!(overrides.asset_type("dae"), AssetType::ThreeD);
+    }
+
+    #[test]
+    fn test_serde_normalizes_extensions() {
+        let json = serde_json::json!({ ".XYZ": AssetType::Image });
+        let overrides: AssetTypeOverrides = serde_json::from_value(json).unwrap();
+        assert_eq!(overrides.asset_type("xyz"), AssetType::Image);
+
+        let round_trip: AssetTypeOverrides = serde_json::from_value(serde_json::to_value(&overrides).unwrap()).unwrap();
+        assert_eq!(round_trip, overrides);
+    }
+}
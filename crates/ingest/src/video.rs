@@ -0,0 +1,158 @@
+//! Video frame extraction and contact sheets
+//!
+//! Frames are grabbed with the `ffmpeg` command-line tool, which must be on
+//! the `PATH`; `ffprobe` supplies the duration when metadata lacks it. A
+//! contact sheet lays evenly spaced frames out in a grid so editors can
+//! judge a clip at a glance.
+
+use crate::error::IngestError;
+use schema::DamResult;
+use std::path::Path;
+use tokio::process::Command;
+
+/// Shortest stretch of video each contact sheet frame must cover, in seconds
+const MIN_SECONDS_PER_FRAME: f64 = 1.0;
+
+/// Most rows, and most columns, a contact sheet may have
+pub const MAX_SHEET_SIDE: u32 = 12;
+
+/// Pixels between and around contact sheet frames
+pub const SHEET_GAP: u32 = 4;
+
+/// Background of contact sheets
+const SHEET_BACKGROUND: image::Rgb<u8> = image::Rgb([16, 16, 16]);
+
+/// Duration of a video in seconds, read with `ffprobe`
+pub async fn probe_duration(path: &Path) -> DamResult<f64> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-show_entries", "format=duration", "-of", "default=noprint_wrappers=1:nokey=1"])
+        .arg(path)
+        .output()
+        .await
+        .map_err(|e| IngestError::external_tool_error("ffprobe", e.to_string()))?;
+
+    if !output.status.success() {
+        return Err(IngestError::external_tool_error("ffprobe", String::from_utf8_lossy(&output.stderr)).into());
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|duration| duration.is_finite() && *duration > 0.0)
+        .ok_or_else(|| IngestError::external_tool_error("ffprobe", format!("No duration for {}", path.display())).into())
+}
+
+/// Decode the frame shown at `seconds`, scaled to at most `max_width` wide
+pub async fn extract_frame(path: &Path, seconds: f64, max_width: u32) -> DamResult<image::DynamicImage> {
+    let seek = format!("{:.3}", seconds);
+    let scale = format!("scale='min({},iw)':-2", max_width);
+    let output = Command::new("ffmpeg")
+        .args(["-v", "error", "-ss", seek.as_str(), "-i"])
+        .arg(path)
+        .args(["-frames:v", "1", "-vf", scale.as_str(), "-f", "image2pipe", "-vcodec", "png", "-"])
+        .output()
+        .await
+        .map_err(|e| IngestError::external_tool_error("ffmpeg", e.to_string()))?;
+
+    if !output.status.success() || output.stdout.is_empty() {
+        return Err(IngestError::external_tool_error(
+            "ffmpeg",
+            format!("No frame at {:.3}s of {}: {}", seconds, path.display(), String::from_utf8_lossy(&output.stderr).trim()),
+        ).into());
+    }
+
+    tokio::task::spawn_blocking(move || image::load_from_memory_with_format(&output.stdout, image::ImageFormat::Png))
+        .await
+        .map_err(|e| IngestError::external_tool_error("ffmpeg", format!("Frame decoding stopped: {}", e)))?
+        .map_err(|e| IngestError::external_tool_error("ffmpeg", format!("Unreadable frame: {}", e)).into())
+}
+
+/// Shrink a `rows` x `cols` grid until every frame covers enough video
+///
+/// Each side is first limited to `MAX_SHEET_SIDE`. The longer side loses a
+/// row or column first, so the sheet keeps its shape. Always leaves at
+/// least a 1x1 grid.
+pub fn fit_grid(rows: u32, cols: u32, duration: f64) -> (u32, u32) {
+    let (mut rows, mut cols) = (rows.clamp(1, MAX_SHEET_SIDE), cols.clamp(1, MAX_SHEET_SIDE));
+    let max_frames = (duration / MIN_SECONDS_PER_FRAME)
+        .floor()
+        .min((MAX_SHEET_SIDE * MAX_SHEET_SIDE) as f64)
+        .max(1.0) as u32;
+
+    while rows * cols > max_frames {
+        if cols >= rows {
+            cols -= 1;
+        } else {
+            rows -= 1;
+        }
+    }
+    (rows, cols)
+}
+
+/// Timestamps of `count` frames spread evenly over `duration`
+///
+/// Each frame sits in the middle of its share of the video, which skips
+/// the black first and last frames most clips have.
+pub fn frame_times(count: u32, duration: f64) -> Vec<f64> {
+    (0..count)
+        .map(|i| duration * (i as f64 + 0.5) / count as f64)
+        .collect()
+}
+
+/// Lay frames out row by row in a `rows` x `cols` grid of `cell`-sized slots
+///
+/// Frames are centered in their slot; missing frames leave the slot empty.
+/// Returns None if the sheet would be too large to address.
+pub fn composite_grid(frames: &[image::DynamicImage], rows: u32, cols: u32, cell: (u32, u32)) -> Option<image::RgbImage> {
+    let (cell_width, cell_height) = cell;
+    let side = |count: u32, cell: u32| count.checked_mul(cell)?.checked_add(count.checked_add(1)?.checked_mul(SHEET_GAP)?);
+    let (width, height) = (side(cols, cell_width)?, side(rows, cell_height)?);
+    (width as u64).checked_mul(height as u64)?.checked_mul(3).filter(|&bytes| bytes <= isize::MAX as u64)?;
+    let mut sheet = image::RgbImage::from_pixel(width, height, SHEET_BACKGROUND);
+
+    for (index, frame) in frames.iter().take(rows.saturating_mul(cols) as usize).enumerate() {
+        let (row, col) = (index as u32 / cols, index as u32 % cols);
+        let frame = frame.resize(cell_width, cell_height, image::imageops::FilterType::Triangle).to_rgb8();
+        let x = SHEET_GAP + col * (cell_width + SHEET_GAP) + (cell_width - frame.width()) / 2;
+        let y = SHEET_GAP + row * (cell_height + SHEET_GAP) + (cell_height - frame.height()) / 2;
+        image::imageops::replace(&mut sheet, &frame, x as i64, y as i64);
+    }
+
+    Some(sheet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_grid() {
+        assert_eq!(fit_grid(3, 4, 600.0), (3, 4));
+        assert_eq!(fit_grid(3, 4, 6.5), (3, 2));
+        assert_eq!(fit_grid(3, 4, 0.2), (1, 1));
+        assert_eq!(fit_grid(0, 0, 60.0), (1, 1));
+        assert_eq!(fit_grid(u32::MAX, u32::MAX, f64::INFINITY), (MAX_SHEET_SIDE, MAX_SHEET_SIDE));
+        assert_eq!(fit_grid(u32::MAX, 2, f64::NAN), (MAX_SHEET_SIDE, 2));
+    }
+
+    #[test]
+    fn test_frame_times() {
+        assert_eq!(frame_times(4, 8.0), vec![1.0, 3.0, 5.0, 7.0]);
+        assert!(frame_times(0, 8.0).is_empty());
+    }
+
+    #[test]
+    fn test_composite_grid() {
+        let red = image::DynamicImage::ImageRgb8(image::RgbImage::from_pixel(20, 10, image::Rgb([255, 0, 0])));
+        let sheet = composite_grid(&[red.clone(), red.clone(), red], 2, 2, (20, 10)).unwrap();
+
+        assert_eq!(sheet.dimensions(), (2 * 20 + 3 * SHEET_GAP, 2 * 10 + 3 * SHEET_GAP));
+        assert_eq!(*sheet.get_pixel(SHEET_GAP, SHEET_GAP), image::Rgb([255, 0, 0]));
+        assert_eq!(*sheet.get_pixel(0, 0), SHEET_BACKGROUND);
+        // Only three frames: the last slot stays empty
+        assert_eq!(*sheet.get_pixel(2 * SHEET_GAP + 20, 2 * SHEET_GAP + 10), SHEET_BACKGROUND);
+
+        assert!(composite_grid(&[], 2, 2, (u32::MAX, 10)).is_none());
+    }
+}
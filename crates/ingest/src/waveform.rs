@@ -0,0 +1,198 @@
+//! Audio waveforms
+//!
+//! An audio file is decoded once into min/max peaks. The peaks are drawn
+//! into the audio preview and stored with it, so interactive waveforms in
+//! the UI never decode the file again.
+
+use crate::error::IngestError;
+use schema::{DamResult, Waveform};
+use std::path::Path;
+use symphonia::core::audio::SampleBuffer;
+use tracing::warn;
+
+/// Peaks stored per audio asset; requests for fewer buckets are combined
+/// from these
+pub const WAVEFORM_RESOLUTION: usize = 1024;
+
+/// Frames combined into one peak while decoding
+const FRAMES_PER_BLOCK: usize = 256;
+
+/// Background of waveform previews
+const BACKGROUND: image::Rgb<u8> = image::Rgb([24, 28, 40]);
+
+/// Color of the waveform in previews
+const FOREGROUND: image::Rgb<u8> = image::Rgb([100, 150, 255]);
+
+/// Decode an audio file into `resolution` min/max peaks
+///
+/// All channels are combined: each peak covers the lowest and highest
+/// sample of any channel in its time span. Decodes the whole file; run it
+/// on the blocking pool from async code.
+pub fn decode_waveform<P: AsRef<Path>>(path: P, resolution: usize) -> DamResult<Waveform> {
+    let path = path.as_ref();
+    let failed = |reason: String| IngestError::preview_generation_failed(path.to_path_buf(), reason);
+    
+    let file = std::fs::File::open(path)
+        .map_err(|e| failed(format!("Failed to open audio file: {}", e)))?;
+    let mss = symphonia::core::io::MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = symphonia::core::probe::Hint::new();
+    if let Some(extension) = path.extension() {
+        hint.with_extension(&extension.to_string_lossy());
+    }
+    
+    let probed = symphonia::default::get_probe()
+        .format(&hint, mss, &Default::default(), &Default::default())
+        .map_err(|e| failed(format!("Failed to probe audio format: {}", e)))?;
+    let mut format = probed.format;
+    
+    let track = format.tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != symphonia::core::codecs::CODEC_TYPE_NULL)
+        .ok_or_else(|| failed("No audio tracks found".to_string()))?;
+    let track_id = track.id;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &Default::default())
+        .map_err(|e| failed(format!("Failed to create decoder: {}", e)))?;
+    
+    let mut blocks = Vec::new();
+    let mut block = (f32::MAX, f32::MIN);
+    let mut block_frames = 0;
+    let mut samples: Option<SampleBuffer<f32>> = None;
+    
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(symphonia::core::errors::Error::ResetRequired) => {
+                decoder.reset();
+                continue;
+            }
+            Err(symphonia::core::errors::Error::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => {
+                warn!("Error reading packet of {}: {}", path.display(), e);
+                break;
+            }
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            Err(symphonia::core::errors::Error::DecodeError(e)) => {
+                warn!("Skipping undecodable packet of {}: {}", path.display(), e);
+                continue;
+            }
+            Err(e) => return Err(failed(format!("Failed to decode audio: {}", e)).into()),
+        };
+        
+        let channels = decoded.spec().channels.count().max(1);
+        let (frames, spec) = (decoded.capacity(), *decoded.spec());
+        if samples.as_ref().is_some_and(|buffer| buffer.capacity() < frames * channels) {
+            samples = None;
+        }
+        let buffer = samples.get_or_insert_with(|| SampleBuffer::new(frames as u64, spec));
+        buffer.copy_interleaved_ref(decoded);
+        
+        for frame in buffer.samples().chunks(channels) {
+            for &sample in frame {
+                block = (block.0.min(sample), block.1.max(sample));
+            }
+            block_frames += 1;
+            if block_frames == FRAMES_PER_BLOCK {
+                blocks.push(block);
+                block = (f32::MAX, f32::MIN);
+                block_frames = 0;
+            }
+        }
+    }
+    
+    if block_frames > 0 {
+        blocks.push(block);
+    }
+    if blocks.is_empty() {
+        return Err(failed("No audio samples decoded".to_string()).into());
+    }
+    
+    Ok(Waveform {
+        peaks: Waveform { peaks: blocks }.resample(resolution),
+    })
+}
+
+/// Draw a waveform centered in an image of the given size
+pub fn render_waveform(waveform: &Waveform, (width, height): (u32, u32)) -> image::RgbImage {
+    let mut img = image::RgbImage::from_pixel(width, height, BACKGROUND);
+    let peaks = waveform.resample(width as usize);
+    if peaks.is_empty() || height == 0 {
+        return img;
+    }
+    
+    let bottom = (height - 1) as f32;
+    let to_row = |sample: f32| ((1.0 - sample.clamp(-1.0, 1.0)) / 2.0 * bottom).round() as u32;
+    
+    // Fewer peaks than columns (very short audio) are stretched across the image
+    for x in 0..width {
+        let (min, max) = peaks[x as usize * peaks.len() / width as usize];
+        for y in to_row(max)..=to_row(min) {
+            img.put_pixel(x, y, FOREGROUND);
+        }
+    }
+    img
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// 16-bit PCM WAV with the given interleaved samples
+    fn write_wav(path: &Path, channels: u16, samples: &[i16]) {
+        let sample_rate: u32 = 8000;
+        let data_len = (samples.len() * 2) as u32;
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&channels.to_le_bytes());
+        bytes.extend_from_slice(&sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&(sample_rate * channels as u32 * 2).to_le_bytes());
+        bytes.extend_from_slice(&(channels * 2).to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_len.to_le_bytes());
+        for sample in samples {
+            bytes.extend_from_slice(&sample.to_le_bytes());
+        }
+        std::fs::write(path, bytes).unwrap();
+    }
+    
+    #[test]
+    fn test_decode_waveform_combines_channels() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stereo.wav");
+        
+        // Left channel is loud in the first half, right channel in the second
+        let mut samples = Vec::new();
+        for frame in 0..8000 {
+            let (left, right) = if frame < 4000 { (16384, -4096) } else { (0, -16384) };
+            samples.extend_from_slice(&[left, right]);
+        }
+        write_wav(&path, 2, &samples);
+        
+        let waveform = decode_waveform(&path, 4).unwrap();
+        assert_eq!(waveform.peaks.len(), 4);
+        let (min, max) = waveform.peaks[0];
+        assert!((max - 0.5).abs() < 0.01 && (min + 0.125).abs() < 0.01);
+        let (min, max) = waveform.peaks[3];
+        assert!(max.abs() < 0.01 && (min + 0.5).abs() < 0.01);
+        
+        assert_eq!(waveform.resample(2).len(), 2);
+        assert_eq!(waveform.resample(10).len(), 4);
+        
+        let img = render_waveform(&waveform, (8, 9));
+        assert_eq!(*img.get_pixel(0, 4), FOREGROUND);
+        assert_eq!(*img.get_pixel(0, 0), BACKGROUND);
+        
+        assert!(decode_waveform(dir.path().join("missing.wav"), 4).is_err());
+    }
+}
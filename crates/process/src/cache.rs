@@ -0,0 +1,248 @@
+//! Persistent cache of image inference results
+//!
+//! Re-importing an unchanged image should not re-run CLIP/BLIP. Results are
+//! stored in a sled database keyed by the file's content hash and the model
+//! tier that produced them, and the least recently used entries are evicted
+//! once the cache holds more than `max_entries`. Text embeddings share the
+//! cache, keyed by a hash of the text and the embedder that produced them.
+
+use crate::error::ProcessError;
+use schema::{DamResult, ModelTier};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use tracing::{debug, info};
+
+/// Default number of cached images
+pub const DEFAULT_CACHE_ENTRIES: usize = 50_000;
+
+/// Cached inference outputs for one image
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedInference {
+    /// Visual embedding (CLIP features)
+    pub embedding: Vec<f32>,
+    /// BLIP caption, if the tier has a captioning model
+    pub caption: Option<String>,
+}
+
+/// Stored entry with its position in the LRU order
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    last_used: u64,
+    inference: CachedInference,
+}
+
+/// Size-bounded, disk-backed LRU cache of image embeddings
+pub struct EmbeddingCache {
+    /// Cache key -> `CacheEntry`
+    entries: sled::Tree,
+    /// Big-endian access counter -> cache key, oldest first
+    access_order: sled::Tree,
+    /// Next access counter value
+    clock: AtomicU64,
+    /// Entries held; sled only counts a tree by walking it
+    count: AtomicUsize,
+    max_entries: usize,
+    /// Serializes updates so the two trees stay consistent
+    write_lock: Mutex<()>,
+}
+
+impl EmbeddingCache {
+    /// Open (or create) a cache in `dir` holding at most `max_entries` images
+    pub fn open<P: AsRef<Path>>(dir: P, max_entries: usize) -> DamResult<Self> {
+        let dir = dir.as_ref();
+        if max_entries == 0 {
+            return Err(ProcessError::CacheError("Cache must hold at least one entry".to_string()).into());
+        }
+        
+        std::fs::create_dir_all(dir)?;
+        let db = sled::open(dir.join("embeddings.db")).map_err(cache_error)?;
+        let entries = db.open_tree("entries").map_err(cache_error)?;
+        let access_order = db.open_tree("access_order").map_err(cache_error)?;
+        
+        let next_tick = access_order.last().map_err(cache_error)?
+            .map(|(key, _)| decode_tick(&key) + 1)
+            .unwrap_or(0);
+        
+        let count = entries.len();
+        info!("Opened embedding cache at {} ({} entries)", dir.display(), count);
+        
+        Ok(Self {
+            entries,
+            access_order,
+            clock: AtomicU64::new(next_tick),
+            count: AtomicUsize::new(count),
+            max_entries,
+            write_lock: Mutex::new(()),
+        })
+    }
+    
+    /// Look up the cached result for a content hash and tier
+    pub fn get(&self, content_hash: &str, tier: &ModelTier) -> DamResult<Option<CachedInference>> {
+        let found = self.get_entry(&cache_key(content_hash, tier))?;
+        if found.is_some() {
+            debug!("Embedding cache hit for {}", content_hash);
+        }
+        Ok(found)
+    }
+    
+    /// Store a result, evicting the least recently used entries if full
+    pub fn insert(&self, content_hash: &str, tier: &ModelTier, inference: CachedInference) -> DamResult<()> {
+        self.insert_entry(&cache_key(content_hash, tier), inference)
+    }
+    
+    /// Look up the embedding an embedder made of a text
+    pub fn get_text(&self, embedder: &str, text: &str) -> DamResult<Option<Vec<f32>>> {
+        Ok(self.get_entry(&text_key(embedder, text))?.map(|inference| inference.embedding))
+    }
+    
+    /// Store the embedding an embedder made of a text
+    pub fn insert_text(&self, embedder: &str, text: &str, embedding: Vec<f32>) -> DamResult<()> {
+        self.insert_entry(&text_key(embedder, text), CachedInference { embedding, caption: None })
+    }
+    
+    fn get_entry(&self, key: &str) -> DamResult<Option<CachedInference>> {
+        let _guard = self.write_lock.lock().unwrap();
+        
+        let Some(value) = self.entries.get(key).map_err(cache_error)? else {
+            return Ok(None);
+        };
+        let mut entry: CacheEntry = serde_json::from_slice(&value)
+            .map_err(|e| ProcessError::CacheError(format!("Corrupt cache entry: {}", e)))?;
+        
+        // Move the entry to the most recently used position
+        self.access_order.remove(entry.last_used.to_be_bytes()).map_err(cache_error)?;
+        entry.last_used = self.tick();
+        self.store(key, &entry)?;
+        
+        Ok(Some(entry.inference))
+    }
+    
+    fn insert_entry(&self, key: &str, inference: CachedInference) -> DamResult<()> {
+        let _guard = self.write_lock.lock().unwrap();
+        
+        if let Some(previous) = self.entries.get(key).map_err(cache_error)? {
+            if let Ok(previous) = serde_json::from_slice::<CacheEntry>(&previous) {
+                self.access_order.remove(previous.last_used.to_be_bytes()).map_err(cache_error)?;
+            }
+        }
+        
+        let entry = CacheEntry { last_used: self.tick(), inference };
+        self.store(key, &entry)?;
+        
+        while self.count.load(Ordering::Relaxed) > self.max_entries {
+            let Some((_, oldest)) = self.access_order.pop_min().map_err(cache_error)? else {
+                break;
+            };
+            if self.entries.remove(&oldest).map_err(cache_error)?.is_some() {
+                self.count.fetch_sub(1, Ordering::Relaxed);
+            }
+            debug!("Evicted {} from embedding cache", String::from_utf8_lossy(&oldest));
+        }
+        
+        Ok(())
+    }
+    
+    /// Remove all cached results
+    pub fn clear(&self) -> DamResult<()> {
+        let _guard = self.write_lock.lock().unwrap();
+        self.entries.clear().map_err(cache_error)?;
+        self.access_order.clear().map_err(cache_error)?;
+        self.count.store(0, Ordering::Relaxed);
+        info!("Cleared embedding cache");
+        Ok(())
+    }
+    
+    /// Number of cached images
+    pub fn len(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+    
+    /// Whether the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    
+    /// Write an entry and its access record
+    fn store(&self, key: &str, entry: &CacheEntry) -> DamResult<()> {
+        let value = serde_json::to_vec(entry)?;
+        if self.entries.insert(key, value).map_err(cache_error)?.is_none() {
+            self.count.fetch_add(1, Ordering::Relaxed);
+        }
+        self.access_order.insert(entry.last_used.to_be_bytes(), key.as_bytes()).map_err(cache_error)?;
+        Ok(())
+    }
+    
+    /// Next value of the access counter
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed)
+    }
+}
+
+/// Key for a content hash under a given tier's models
+fn cache_key(content_hash: &str, tier: &ModelTier) -> String {
+    format!("{:?}:{}", tier, content_hash)
+}
+
+/// Key for a text embedded by a given embedder
+fn text_key(embedder: &str, text: &str) -> String {
+    format!("text:{}:{}", embedder, blake3::hash(text.as_bytes()).to_hex())
+}
+
+/// Decode a big-endian access counter key
+fn decode_tick(key: &[u8]) -> u64 {
+    key.try_into().map(u64::from_be_bytes).unwrap_or(0)
+}
+
+fn cache_error(err: sled::Error) -> ProcessError {
+    ProcessError::CacheError(err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn inference(value: f32) -> CachedInference {
+        CachedInference { embedding: vec![value; 4], caption: None }
+    }
+    
+    #[test]
+    fn test_lru_eviction_and_persistence() {
+        let dir = std::env::temp_dir().join(format!("dam-embedding-cache-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        
+        {
+            let cache = EmbeddingCache::open(&dir, 2).unwrap();
+            cache.insert("a", &ModelTier::Low, inference(1.0)).unwrap();
+            cache.insert("b", &ModelTier::Low, inference(2.0)).unwrap();
+            
+            // Touch "a" so "b" becomes the eviction candidate
+            assert_eq!(cache.get("a", &ModelTier::Low).unwrap(), Some(inference(1.0)));
+            cache.insert("c", &ModelTier::Low, inference(3.0)).unwrap();
+            
+            assert_eq!(cache.len(), 2);
+            assert!(cache.get("b", &ModelTier::Low).unwrap().is_none());
+            
+            // Entries are per tier
+            assert!(cache.get("a", &ModelTier::High).unwrap().is_none());
+        }
+        
+        // Entries survive reopening, and the LRU order continues
+        let cache = EmbeddingCache::open(&dir, 2).unwrap();
+        assert_eq!(cache.get("c", &ModelTier::Low).unwrap(), Some(inference(3.0)));
+        cache.insert("d", &ModelTier::Low, inference(4.0)).unwrap();
+        assert!(cache.get("a", &ModelTier::Low).unwrap().is_none());
+        assert_eq!(cache.len(), 2);
+        
+        // Replacing an entry does not grow the cache
+        cache.insert("d", &ModelTier::Low, inference(5.0)).unwrap();
+        assert_eq!(cache.len(), 2);
+        
+        cache.clear().unwrap();
+        assert!(cache.is_empty());
+        
+        drop(cache);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
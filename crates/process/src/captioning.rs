@@ -0,0 +1,246 @@
+//! Caption decoding
+//!
+//! BLIP writes a caption one token at a time: its text decoder scores every
+//! vocabulary token given the image and the tokens so far, and a decoding
+//! strategy picks the continuation. Greedy decoding takes the best token at
+//! each step. Beam search keeps the `beam_size` best partial captions and
+//! returns the one with the best score per token, which avoids early
+//! choices that leave no good continuation. Both are deterministic; ties go
+//! to the lower token id.
+//!
+//! The scorer is meant to be BLIP's text decoder conditioned on the image
+//! features. Until its weights are wired up, `TaggingService` decodes the
+//! fixed placeholder caption of each tier through `FixedCaptionScorer`, so
+//! the length and decoding options already apply.
+
+use schema::ModelTier;
+use serde::{Deserialize, Serialize};
+
+/// Default most tokens in a caption
+pub const DEFAULT_CAPTION_LENGTH: usize = 20;
+
+/// Default number of partial captions kept by beam search
+pub const DEFAULT_BEAM_SIZE: usize = 3;
+
+/// Token that ends a caption
+pub const END_TOKEN: usize = 0;
+
+/// Length and decoding strategy of generated captions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CaptionOptions {
+    /// Most tokens in a caption; longer captions are cut off
+    pub max_length: usize,
+    /// Partial captions kept per step; 1 decodes greedily
+    pub beam_size: usize,
+}
+
+impl Default for CaptionOptions {
+    fn default() -> Self {
+        Self {
+            max_length: DEFAULT_CAPTION_LENGTH,
+            beam_size: DEFAULT_BEAM_SIZE,
+        }
+    }
+}
+
+impl CaptionOptions {
+    /// Greedy decoding of up to `max_length` tokens
+    pub fn greedy(max_length: usize) -> Self {
+        Self { max_length, beam_size: 1 }
+    }
+
+    /// Beam search over `beam_size` partial captions
+    pub fn beam(max_length: usize, beam_size: usize) -> Self {
+        Self { max_length, beam_size }
+    }
+
+    /// Whether the best token is taken at every step
+    pub fn is_greedy(&self) -> bool {
+        self.beam_size <= 1
+    }
+}
+
+/// Scores the next token of a caption
+pub trait TokenScorer {
+    /// Text of a token
+    fn token(&self, id: usize) -> &str;
+
+    /// Log-probability of every token following `prefix`
+    ///
+    /// Tokens that cannot follow are `f32::NEG_INFINITY`.
+    fn next_token_scores(&self, prefix: &[usize]) -> Vec<f32>;
+}
+
+/// Placeholder caption of a tier, until BLIP's text decoder is wired up
+pub fn placeholder_caption(tier: &ModelTier) -> &'static str {
+    match tier {
+        ModelTier::Low => "An image",
+        ModelTier::Medium => "A digital artwork with various elements",
+        ModelTier::High => "A detailed digital artwork featuring intricate design elements with vibrant colors and professional composition",
+    }
+}
+
+/// Scores a fixed caption, one word per step
+///
+/// Token 0 is `END_TOKEN` and token `i` the caption's `i`th word; only the
+/// next word of the caption, or the end after the last, can follow.
+pub struct FixedCaptionScorer {
+    words: Vec<&'static str>,
+}
+
+impl FixedCaptionScorer {
+    pub fn new(caption: &'static str) -> Self {
+        Self { words: caption.split_whitespace().collect() }
+    }
+}
+
+impl TokenScorer for FixedCaptionScorer {
+    fn token(&self, id: usize) -> &str {
+        match id {
+            END_TOKEN => "",
+            id => self.words[id - 1],
+        }
+    }
+
+    fn next_token_scores(&self, prefix: &[usize]) -> Vec<f32> {
+        let mut scores = vec![f32::NEG_INFINITY; self.words.len() + 1];
+        let next = if prefix.len() < self.words.len() { prefix.len() + 1 } else { END_TOKEN };
+        scores[next] = 0.0;
+        scores
+    }
+}
+
+/// Decode a caption with the strategy of `options`
+pub fn decode(scorer: &impl TokenScorer, options: &CaptionOptions) -> String {
+    let tokens = if options.is_greedy() {
+        decode_greedy(scorer, options.max_length)
+    } else {
+        decode_beam(scorer, options.max_length, options.beam_size)
+    };
+    tokens.iter().map(|&id| scorer.token(id)).collect::<Vec<_>>().join(" ")
+}
+
+fn decode_greedy(scorer: &impl TokenScorer, max_length: usize) -> Vec<usize> {
+    let mut tokens = Vec::new();
+    while tokens.len() < max_length {
+        match best_token(&scorer.next_token_scores(&tokens)) {
+            Some(token) if token != END_TOKEN => tokens.push(token),
+            _ => break,
+        }
+    }
+    tokens
+}
+
+/// Highest scoring token, the lowest id among equals
+fn best_token(scores: &[f32]) -> Option<usize> {
+    let mut best: Option<(usize, f32)> = None;
+    for (token, &score) in scores.iter().enumerate() {
+        if score.is_finite() && best.map_or(true, |(_, best_score)| score > best_score) {
+            best = Some((token, score));
+        }
+    }
+    best.map(|(token, _)| token)
+}
+
+struct Beam {
+    tokens: Vec<usize>,
+    score: f32,
+}
+
+impl Beam {
+    /// Score per token, so long captions are not penalized for their length
+    fn normalized_score(&self) -> f32 {
+        self.score / self.tokens.len().max(1) as f32
+    }
+}
+
+fn decode_beam(scorer: &impl TokenScorer, max_length: usize, beam_size: usize) -> Vec<usize> {
+    let mut beams = vec![Beam { tokens: Vec::new(), score: 0.0 }];
+    let mut finished = Vec::new();
+
+    for _ in 0..max_length {
+        let mut candidates = Vec::new();
+        for beam in &beams {
+            for (token, score) in scorer.next_token_scores(&beam.tokens).into_iter().enumerate() {
+                if !score.is_finite() {
+                    continue;
+                }
+                let score = beam.score + score;
+                if token == END_TOKEN {
+                    finished.push(Beam { tokens: beam.tokens.clone(), score });
+                } else {
+                    let mut tokens = beam.tokens.clone();
+                    tokens.push(token);
+                    candidates.push(Beam { tokens, score });
+                }
+            }
+        }
+
+        // Stable sort keeps generation order, and so lower token ids, among ties
+        candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+        candidates.truncate(beam_size);
+        beams = candidates;
+        if beams.is_empty() {
+            break;
+        }
+    }
+
+    // Captions cut off at the length limit compete with finished ones
+    finished.extend(beams);
+    let mut best: Option<Beam> = None;
+    for beam in finished {
+        if best.as_ref().map_or(true, |best| beam.normalized_score() > best.normalized_score()) {
+            best = Some(beam);
+        }
+    }
+    best.map(|beam| beam.tokens).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A tiny language model where the likelier first word leads nowhere good
+    struct ArticleScorer;
+
+    const VOCABULARY: [&str; 5] = ["<end>", "a", "an", "cat", "orange"];
+
+    impl TokenScorer for ArticleScorer {
+        fn token(&self, id: usize) -> &str {
+            VOCABULARY[id]
+        }
+
+        fn next_token_scores(&self, prefix: &[usize]) -> Vec<f32> {
+            let probabilities: [f32; 5] = match prefix {
+                [] => [0.0, 0.6, 0.4, 0.0, 0.0],
+                [1] => [0.25, 0.0, 0.0, 0.4, 0.35],
+                [2] => [0.05, 0.0, 0.0, 0.0, 0.95],
+                _ => [1.0, 0.0, 0.0, 0.0, 0.0],
+            };
+            probabilities.into_iter().map(f32::ln).collect()
+        }
+    }
+
+    #[test]
+    fn test_greedy_and_beam_decoding() {
+        // Greedy commits to the likelier article and cannot recover
+        assert_eq!(decode(&ArticleScorer, &CaptionOptions::greedy(20)), "a cat");
+        assert_eq!(decode(&ArticleScorer, &CaptionOptions::beam(20, 3)), "an orange");
+
+        // The same scorer and options always give the same caption
+        let options = CaptionOptions::beam(20, 2);
+        assert_eq!(decode(&ArticleScorer, &options), decode(&ArticleScorer, &options));
+
+        // Captions are cut off at the length limit
+        assert_eq!(decode(&ArticleScorer, &CaptionOptions::greedy(1)), "a");
+        assert_eq!(decode(&ArticleScorer, &CaptionOptions::beam(0, 3)), "");
+    }
+
+    #[test]
+    fn test_fixed_caption() {
+        let scorer = FixedCaptionScorer::new(placeholder_caption(&ModelTier::Medium));
+        assert_eq!(decode(&scorer, &CaptionOptions::greedy(20)), "A digital artwork with various elements");
+        assert_eq!(decode(&scorer, &CaptionOptions::beam(20, 3)), "A digital artwork with various elements");
+        assert_eq!(decode(&scorer, &CaptionOptions::greedy(2)), "A digital");
+    }
+}
@@ -1,17 +1,343 @@
 //! Vector embedding service for semantic search
+//!
+//! `EmbeddingService` handles chunking and validation and delegates the
+//! actual embedding to an `Embedder`. The built-in model is the default;
+//! integrators can inject their own backend (a local embedding server, a
+//! different model) without touching the model tiers.
 
+use async_trait::async_trait;
+use image::DynamicImage;
 use schema::DamResult;
+use std::sync::Arc;
+use tracing::{info, warn};
+use crate::cache::EmbeddingCache;
 use crate::error::ProcessError;
 
-pub struct EmbeddingService;
+/// Default input window of the text embedding model, in tokens
+pub const DEFAULT_MAX_TEXT_LENGTH: usize = 512;
+
+/// Output size of the built-in embedding model
+pub const BUILTIN_EMBEDDING_DIMENSION: usize = 384;
+
+/// Words per chunk for a model window, at ~0.75 words per token so chunks
+/// are not truncated by the tokenizer
+fn words_per_window(max_text_length: usize) -> usize {
+    (max_text_length * 3 / 4).max(1)
+}
+
+/// Backend that turns text and images into embedding vectors
+/// 
+/// Every vector an embedder returns must have `dimension()` entries.
+/// Vectors from different embedders are not comparable, so the index has
+/// to be re-embedded when the active embedder changes.
+#[async_trait]
+pub trait Embedder: Send + Sync {
+    /// Identifies the backend in logs
+    fn name(&self) -> &str;
+    
+    /// Length of every returned vector
+    fn dimension(&self) -> usize;
+    
+    /// Embed one piece of text that fits the model window
+    async fn embed_text(&self, text: &str) -> DamResult<Vec<f32>>;
+    
+    /// Embed an image into the same space as `embed_text`, if supported
+    async fn embed_image(&self, image: &DynamicImage) -> DamResult<Vec<f32>>;
+}
+
+/// The built-in candle embedding model
+/// 
+/// Model weights are not wired up yet. Until they are, text is embedded
+/// as hashed word counts and images as a coarse colour layout, so vectors
+/// are never all zeros and texts sharing words still land near each
+/// other. Text and image vectors are not comparable with each other.
+#[derive(Debug, Default)]
+pub struct CandleEmbedder;
+
+#[async_trait]
+impl Embedder for CandleEmbedder {
+    fn name(&self) -> &str {
+        "candle"
+    }
+    
+    fn dimension(&self) -> usize {
+        BUILTIN_EMBEDDING_DIMENSION
+    }
+    
+    async fn embed_text(&self, text: &str) -> DamResult<Vec<f32>> {
+        // Placeholder implementation: one hashed feature per word, plus a
+        // constant one so empty text still has a direction
+        let mut features = vec![0.0; BUILTIN_EMBEDDING_DIMENSION];
+        features[0] = 1.0;
+        for word in text.split_whitespace() {
+            let hash = word.to_lowercase().bytes()
+                .fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3));
+            features[(hash % BUILTIN_EMBEDDING_DIMENSION as u64) as usize] += 1.0;
+        }
+        Ok(normalize(features))
+    }
+    
+    async fn embed_image(&self, image: &DynamicImage) -> DamResult<Vec<f32>> {
+        // Placeholder implementation: a 16x8 RGB thumbnail is exactly 384
+        // values; the offset keeps black images off the zero vector
+        let thumbnail = image.resize_exact(16, 8, image::imageops::FilterType::Triangle).to_rgb8();
+        let features = thumbnail.into_raw().into_iter()
+            .map(|value| (value as f32 + 1.0) / 256.0)
+            .collect();
+        Ok(normalize(features))
+    }
+}
+
+/// Scale a vector to unit length
+fn normalize(mut vector: Vec<f32>) -> Vec<f32> {
+    let magnitude = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if magnitude > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= magnitude);
+    }
+    vector
+}
+
+pub struct EmbeddingService {
+    /// Backend producing the vectors
+    embedder: Arc<dyn Embedder>,
+    /// Model input window in tokens; longer text is chunked
+    max_text_length: usize,
+    /// Optional cache of text embeddings, shared with image tagging
+    embedding_cache: Option<Arc<EmbeddingCache>>,
+}
 
 impl EmbeddingService {
     pub fn new() -> DamResult<Self> {
-        Ok(Self)
+        Ok(Self {
+            embedder: Arc::new(CandleEmbedder),
+            max_text_length: DEFAULT_MAX_TEXT_LENGTH,
+            embedding_cache: None,
+        })
+    }
+    
+    /// Reuse embeddings of texts the active embedder has seen before
+    pub fn with_embedding_cache(mut self, cache: Arc<EmbeddingCache>) -> Self {
+        self.embedding_cache = Some(cache);
+        self
+    }
+    
+    /// Use a custom embedding backend instead of the built-in model
+    pub fn with_embedder(mut self, embedder: Arc<dyn Embedder>) -> Self {
+        self.set_embedder(embedder);
+        self
+    }
+    
+    /// Swap the embedding backend
+    /// 
+    /// Embeddings stored by the previous backend are not comparable with
+    /// new ones; re-embed the library, letting the index drop the old
+    /// vectors with `IndexService::ensure_embedding_dimension`.
+    pub fn set_embedder(&mut self, embedder: Arc<dyn Embedder>) {
+        info!("Using {} embedder ({} dimensions)", embedder.name(), embedder.dimension());
+        self.embedder = embedder;
+    }
+    
+    /// The active embedding backend
+    pub fn embedder(&self) -> &Arc<dyn Embedder> {
+        &self.embedder
+    }
+    
+    /// Length of the vectors the active embedder produces
+    pub fn dimension(&self) -> usize {
+        self.embedder.dimension()
+    }
+    
+    /// Use the input window of a specific model (`ModelConfig::max_text_length`)
+    pub fn with_max_text_length(mut self, max_text_length: usize) -> Self {
+        self.max_text_length = max_text_length.max(1);
+        self
+    }
+    
+    /// Model input window in tokens
+    pub fn max_text_length(&self) -> usize {
+        self.max_text_length
+    }
+    
+    /// Embed text that fits the model window
+    pub async fn generate_embedding(&self, text: &str) -> DamResult<Vec<f32>> {
+        let name = self.embedder.name();
+        if let Some(cache) = &self.embedding_cache {
+            // Cache failures only cost a recomputation
+            match cache.get_text(name, text) {
+                Ok(Some(embedding)) if embedding.len() == self.embedder.dimension() => return Ok(embedding),
+                Ok(_) => {}
+                Err(e) => warn!("Embedding cache lookup failed: {}", e),
+            }
+        }
+        
+        let embedding = self.embedder.embed_text(text).await?;
+        let embedding = self.check_dimension(embedding)?;
+        
+        if let Some(cache) = &self.embedding_cache {
+            if let Err(e) = cache.insert_text(name, text, embedding.clone()) {
+                warn!("Failed to cache text embedding: {}", e);
+            }
+        }
+        Ok(embedding)
+    }
+    
+    /// Embed an image with the active embedder
+    pub async fn embed_image(&self, image: &DynamicImage) -> DamResult<Vec<f32>> {
+        let embedding = self.embedder.embed_image(image).await?;
+        self.check_dimension(embedding)
+    }
+    
+    /// Reject vectors that do not match the embedder's declared dimension
+    fn check_dimension(&self, embedding: Vec<f32>) -> DamResult<Vec<f32>> {
+        if embedding.len() != self.embedder.dimension() {
+            return Err(ProcessError::EmbeddingFailed(format!(
+                "{} embedder returned {} dimensions, expected {}",
+                self.embedder.name(),
+                embedding.len(),
+                self.embedder.dimension()
+            )).into());
+        }
+        Ok(embedding)
+    }
+    
+    /// Embed text of any length as overlapping windows
+    ///
+    /// Text that fits the model window yields a single embedding. Longer
+    /// text (transcripts, documents) is split into windows overlapping by a
+    /// quarter, so every passage is represented by at least one vector
+    /// instead of being cut off at the window size.
+    pub async fn embed_chunks(&self, text: &str) -> DamResult<Vec<Vec<f32>>> {
+        let window = words_per_window(self.max_text_length);
+        
+        let mut embeddings = Vec::new();
+        for chunk in chunk_text(text, window, window / 4) {
+            embeddings.push(self.generate_embedding(&chunk).await?);
+        }
+        Ok(embeddings)
+    }
+}
+
+/// Split text into windows of `window` words, consecutive windows sharing
+/// `overlap` words
+///
+/// Empty text yields no chunks; text shorter than a window yields one.
+pub fn chunk_text(text: &str, window: usize, overlap: usize) -> Vec<String> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let window = window.max(1);
+    let step = window.saturating_sub(overlap).max(1);
+    
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < words.len() {
+        let end = (start + window).min(words.len());
+        chunks.push(words[start..end].join(" "));
+        if end == words.len() {
+            break;
+        }
+        start += step;
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_chunk_text() {
+        assert!(chunk_text("", 4, 1).is_empty());
+        assert_eq!(chunk_text("one two three", 4, 1), vec!["one two three"]);
+        
+        let text = (1..=10).map(|n| n.to_string()).collect::<Vec<_>>().join(" ");
+        let chunks = chunk_text(&text, 4, 1);
+        assert_eq!(chunks, vec!["1 2 3 4", "4 5 6 7", "7 8 9 10"]);
+        
+        // Overlap as large as the window still advances
+        assert_eq!(chunk_text("a b c", 2, 2), vec!["a b", "b c"]);
+    }
+    
+    #[tokio::test]
+    async fn test_embed_chunks() {
+        let service = EmbeddingService::new().unwrap().with_max_text_length(8);
+        
+        // 6 words per window with an overlap of 1
+        let transcript = vec!["word"; 20].join(" ");
+        let embeddings = service.embed_chunks(&transcript).await.unwrap();
+        assert_eq!(embeddings.len(), 4);
+        
+        assert_eq!(service.embed_chunks("short query").await.unwrap().len(), 1);
+    }
+    
+    /// Embeds text as its length, mimicking an external server
+    struct LengthEmbedder {
+        dimension: usize,
+    }
+    
+    #[async_trait]
+    impl Embedder for LengthEmbedder {
+        fn name(&self) -> &str {
+            "length"
+        }
+        
+        fn dimension(&self) -> usize {
+            self.dimension
+        }
+        
+        async fn embed_text(&self, text: &str) -> DamResult<Vec<f32>> {
+            Ok(vec![text.len() as f32, 1.0])
+        }
+        
+        async fn embed_image(&self, image: &DynamicImage) -> DamResult<Vec<f32>> {
+            Ok(vec![image.width() as f32, image.height() as f32])
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_custom_embedder() {
+        let service = EmbeddingService::new().unwrap();
+        assert_eq!(service.dimension(), BUILTIN_EMBEDDING_DIMENSION);
+        assert_eq!(service.embedder().name(), "candle");
+        
+        // The placeholder vectors have a direction the vector store accepts
+        let text = service.generate_embedding("").await.unwrap();
+        let image = service.embed_image(&DynamicImage::new_rgb8(4, 3)).await.unwrap();
+        for vector in [text, image] {
+            let magnitude = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+            assert!((magnitude - 1.0).abs() < 1e-4);
+        }
+        let sunset = service.generate_embedding("beach sunset").await.unwrap();
+        assert_ne!(sunset, service.generate_embedding("city skyline").await.unwrap());
+        
+        let service = service.with_embedder(Arc::new(LengthEmbedder { dimension: 2 }));
+        assert_eq!(service.dimension(), 2);
+        assert_eq!(service.generate_embedding("abc").await.unwrap(), vec![3.0, 1.0]);
+        let image = DynamicImage::new_rgb8(4, 3);
+        assert_eq!(service.embed_image(&image).await.unwrap(), vec![4.0, 3.0]);
+        
+        // Vectors that contradict the declared dimension are rejected
+        let service = service.with_embedder(Arc::new(LengthEmbedder { dimension: 3 }));
+        assert!(service.generate_embedding("abc").await.is_err());
     }
     
-    pub async fn generate_embedding(&self, _text: &str) -> Result<Vec<f32>, ProcessError> {
-        // Placeholder implementation
-        Ok(vec![0.0; 384]) // Typical embedding size
+    #[tokio::test]
+    async fn test_text_embedding_cache() {
+        let dir = std::env::temp_dir().join(format!("dam-text-embedding-cache-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache = Arc::new(EmbeddingCache::open(&dir, 10).unwrap());
+        let service = EmbeddingService::new().unwrap().with_embedding_cache(cache.clone());
+        
+        let first = service.generate_embedding("harbor at dusk").await.unwrap();
+        assert_eq!(service.generate_embedding("harbor at dusk").await.unwrap(), first);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get_text("candle", "harbor at dusk").unwrap(), Some(first));
+        
+        // Another embedder does not see the cached vector
+        let service = service.with_embedder(Arc::new(LengthEmbedder { dimension: 2 }));
+        assert_eq!(service.generate_embedding("harbor at dusk").await.unwrap(), vec![14.0, 1.0]);
+        assert_eq!(cache.len(), 2);
+        
+        drop(service);
+        drop(cache);
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 }
@@ -40,10 +40,23 @@ pub enum ProcessError {
     
     #[error("Inference failed: {0}")]
     InferenceFailed(String),
+    
+    #[error("Embedding cache error: {0}")]
+    CacheError(String),
 }
 
 impl From<ProcessError> for DamError {
     fn from(err: ProcessError) -> Self {
-        DamError::processing(err.to_string())
+        match err {
+            // Retrying cannot fix an unreadable input or a missing model
+            ProcessError::ImageLoadFailed(_) | ProcessError::AudioLoadFailed(_) => {
+                DamError::invalid_asset_data(err.to_string())
+            }
+            // A model is only loaded on request, so waiting does not help either
+            ProcessError::ModelNotFound(_) | ProcessError::ModelNotLoaded(_) | ProcessError::InvalidTier(_) => {
+                DamError::configuration(err.to_string())
+            }
+            _ => DamError::processing(err.to_string()),
+        }
     }
 }
@@ -0,0 +1,167 @@
+//! Health and status reporting
+//!
+//! Cheap snapshots of the processing service for UI status indicators and
+//! the server health endpoint. Reports only read in-memory state; nothing
+//! here touches model files.
+
+use schema::{ModelStatus, ModelTier, ProcessingTaskType, SystemMessage};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Health of a single AI sub-service
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceHealth {
+    /// Sub-service name (`transcription`, `tagging`, `embedding`, `generation`)
+    pub service: String,
+    /// Current model tier, for services with tiered models
+    pub tier: Option<ModelTier>,
+    /// Status of the models for the current tier
+    pub status: ModelStatus,
+    /// Memory held by all loaded tiers of this service
+    pub memory_usage_mb: u32,
+    /// Whether the service can accept work right now
+    pub ready: bool,
+}
+
+/// Health summary of the processing service
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthReport {
+    /// False if any sub-service failed to load its models
+    pub healthy: bool,
+    pub services: Vec<ServiceHealth>,
+    pub total_memory_mb: u32,
+}
+
+impl HealthReport {
+    /// Health of a sub-service by name
+    pub fn service(&self, name: &str) -> Option<&ServiceHealth> {
+        self.services.iter().find(|s| s.service == name)
+    }
+    
+    /// Whether at least one sub-service can accept work
+    pub fn is_ready(&self) -> bool {
+        self.services.iter().any(|s| s.ready)
+    }
+    
+    /// Answer for `SystemMessage::HealthCheck`
+    pub fn to_message(&self) -> SystemMessage {
+        SystemMessage::HealthCheckResponse { healthy: self.healthy }
+    }
+}
+
+/// Number of running tasks per sub-service
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActiveTasks {
+    pub transcription: usize,
+    pub tagging: usize,
+    pub embedding: usize,
+    pub generation: usize,
+}
+
+impl ActiveTasks {
+    /// Total running tasks
+    pub fn total(&self) -> usize {
+        self.transcription + self.tagging + self.embedding + self.generation
+    }
+}
+
+/// Runtime status of the processing service
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceStatus {
+    pub uptime_ms: u64,
+    pub active_tasks: ActiveTasks,
+    pub memory_usage_mb: u32,
+}
+
+impl ServiceStatus {
+    /// Answer for `SystemMessage::StatusRequest`
+    ///
+    /// The asset count lives in the index, so callers supply it.
+    pub fn to_message(&self, indexed_assets: usize) -> SystemMessage {
+        SystemMessage::Status {
+            uptime_ms: self.uptime_ms,
+            memory_usage_mb: self.memory_usage_mb as u64,
+            active_tasks: self.active_tasks.total(),
+            indexed_assets,
+        }
+    }
+}
+
+/// Lock-free counters of running tasks
+#[derive(Debug, Default)]
+pub(crate) struct TaskCounters {
+    transcription: AtomicUsize,
+    tagging: AtomicUsize,
+    embedding: AtomicUsize,
+    generation: AtomicUsize,
+}
+
+impl TaskCounters {
+    /// Count a task as running until the returned guard is dropped
+    pub(crate) fn begin(&self, task_type: &ProcessingTaskType) -> ActiveTaskGuard<'_> {
+        let counter = match task_type {
+            ProcessingTaskType::Transcription => &self.transcription,
+            ProcessingTaskType::ImageTagging | ProcessingTaskType::VideoAnalysis => &self.tagging,
+            ProcessingTaskType::EmbeddingGeneration => &self.embedding,
+            ProcessingTaskType::ImageEditing => &self.generation,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        ActiveTaskGuard { counter }
+    }
+    
+    /// Current counts
+    pub(crate) fn snapshot(&self) -> ActiveTasks {
+        ActiveTasks {
+            transcription: self.transcription.load(Ordering::Relaxed),
+            tagging: self.tagging.load(Ordering::Relaxed),
+            embedding: self.embedding.load(Ordering::Relaxed),
+            generation: self.generation.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Decrements its task counter when dropped
+pub(crate) struct ActiveTaskGuard<'a> {
+    counter: &'a AtomicUsize,
+}
+
+impl Drop for ActiveTaskGuard<'_> {
+    fn drop(&mut self) {
+        self.counter.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProcessingService;
+    
+    #[test]
+    fn test_health_without_models() {
+        let service = ProcessingService::new().unwrap();
+        let report = service.health();
+        
+        assert!(report.healthy);
+        assert_eq!(report.total_memory_mb, 0);
+        assert!(!report.service("tagging").unwrap().ready);
+        assert!(!report.service("transcription").unwrap().ready);
+        assert!(report.service("embedding").unwrap().ready);
+        assert!(matches!(report.to_message(), SystemMessage::HealthCheckResponse { healthy: true }));
+    }
+    
+    #[test]
+    fn test_active_task_counts() {
+        let service = ProcessingService::new().unwrap();
+        
+        {
+            let _tagging = service.begin_task(&ProcessingTaskType::ImageTagging);
+            let _embedding = service.begin_task(&ProcessingTaskType::EmbeddingGeneration);
+            let status = service.status();
+            assert_eq!(status.active_tasks.tagging, 1);
+            assert_eq!(status.active_tasks.embedding, 1);
+            assert_eq!(status.active_tasks.total(), 2);
+        }
+        
+        assert_eq!(service.status().active_tasks.total(), 0);
+    }
+}
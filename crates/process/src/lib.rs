@@ -3,8 +3,11 @@
 //! This crate handles all AI-powered processing including:
 //! - Audio transcription via whisper.cpp
 //! - Image tagging via CLIP/BLIP
+//! - Image captions with configurable length and beam search
 //! - Generative image editing via Stable Diffusion
 //! - Vector embedding generation for semantic search
+//! - Bulk reprocessing of indexed assets at a new model tier
+//! - Importing files through ingest, indexing and AI in one call
 
 pub mod transcription;
 pub mod tagging;
@@ -12,16 +15,34 @@ pub mod generation;
 pub mod embedding;
 pub mod error;
 pub mod whisper_ffi;
+pub mod queue;
+pub mod health;
+pub mod cache;
+pub mod reprocess;
+pub mod captioning;
+pub mod pipeline;
 
-use schema::DamResult;
+use index::SharedIndex;
+use schema::{ComputeDevice, DamResult, ModelManager, ModelStatus, ProcessingTaskType, UiEvents};
+use cache::EmbeddingCache;
 use std::path::Path;
+use std::sync::Arc;
+use std::time::Instant;
 use tracing::info;
+use uuid::Uuid;
 
 pub use transcription::*;
 pub use tagging::*;
 pub use generation::*;
 pub use embedding::*;
 pub use error::*;
+pub use queue::*;
+pub use health::*;
+pub use cache::*;
+pub use reprocess::*;
+pub use pipeline::*;
+pub use captioning::CaptionOptions;
+pub use whisper_ffi::{ResampleQuality, TokenTiming};
 
 /// Main AI processing service
 pub struct ProcessingService {
@@ -29,6 +50,9 @@ pub struct ProcessingService {
     tagging: TaggingService,
     generation: GenerationService,
     embedding: EmbeddingService,
+    started_at: Instant,
+    tasks: TaskCounters,
+    events: UiEvents,
 }
 
 impl ProcessingService {
@@ -41,9 +65,52 @@ impl ProcessingService {
             tagging: TaggingService::new()?,
             generation: GenerationService::new()?,
             embedding: EmbeddingService::new()?,
+            started_at: Instant::now(),
+            tasks: TaskCounters::default(),
+            events: UiEvents::new(),
         })
     }
     
+    /// Report reprocessing progress on a shared event channel
+    pub fn with_events(mut self, events: UiEvents) -> Self {
+        self.events = events;
+        self
+    }
+    
+    /// Run vision model inference on the given device
+    pub fn with_device(mut self, device: ComputeDevice) -> Self {
+        self.tagging = self.tagging.with_device(device);
+        self
+    }
+    
+    /// Resample audio for transcription with another quality/speed trade-off
+    pub fn with_resample_quality(mut self, quality: ResampleQuality) -> Self {
+        self.transcription = self.transcription.with_resample_quality(quality);
+        self
+    }
+    
+    /// Reuse image and text embeddings of content seen before
+    pub fn with_embedding_cache(mut self, cache: Arc<EmbeddingCache>) -> Self {
+        self.tagging = self.tagging.with_embedding_cache(cache.clone());
+        self.embedding = self.embedding.with_embedding_cache(cache);
+        self
+    }
+    
+    /// Switch the inference device of already loaded models
+    pub async fn set_device(&self, device: ComputeDevice) -> DamResult<()> {
+        self.tagging.set_device(device).await
+    }
+    
+    /// Change the resample quality of later transcriptions
+    pub fn set_resample_quality(&self, quality: ResampleQuality) {
+        self.transcription.set_resample_quality(quality);
+    }
+    
+    /// Channel on which progress and notifications are emitted
+    pub fn events(&self) -> &UiEvents {
+        &self.events
+    }
+    
     /// Get reference to transcription service
     pub fn transcription(&self) -> &TranscriptionService {
         &self.transcription
@@ -63,6 +130,124 @@ impl ProcessingService {
     pub fn embedding(&self) -> &EmbeddingService {
         &self.embedding
     }
+    
+    /// Compute a fresh visual embedding for an image file
+    /// 
+    /// Runs the current tier's CLIP model (or reuses the embedding cache)
+    /// without touching the index. The vector is the raw model output; the
+    /// index normalizes it to unit length when storing it, as returned by
+    /// `IndexService::get_embedding`.
+    pub async fn compute_embedding<P: AsRef<Path>>(&self, path: P) -> DamResult<Vec<f32>> {
+        let _active = self.begin_task(&ProcessingTaskType::EmbeddingGeneration);
+        let result = self.tagging.tag_image(path.as_ref()).await?;
+        if result.embedding.is_empty() {
+            return Err(ProcessError::ModelNotLoaded(format!(
+                "No CLIP model loaded for tier {:?}; visual embeddings unavailable", result.tier
+            )).into());
+        }
+        Ok(result.embedding)
+    }
+    
+    /// Caption an image asset and store the caption in the index
+    /// 
+    /// The caption replaces the document's `ai_caption`, which is part of
+    /// the text index, so the asset is found by the caption's words.
+    /// Returns None and leaves the document alone when the current tier
+    /// has no BLIP model loaded.
+    pub async fn caption_asset<P: AsRef<Path>>(&self, index: &SharedIndex, asset_id: Uuid, path: P, options: CaptionOptions) -> DamResult<Option<String>> {
+        let _active = self.begin_task(&ProcessingTaskType::ImageTagging);
+        let caption = self.tagging.caption_image(path.as_ref(), options).await?;
+        if let Some(caption) = &caption {
+            let mut index = index.write().await;
+            index.update_with_ai_results(asset_id, None, Some(caption.clone()), None, None, None).await?;
+        }
+        Ok(caption)
+    }
+    
+    /// Snapshot of model status and memory use
+    /// 
+    /// Statuses are reported for the current tier of each service, while
+    /// `total_vram_used_mb` covers every loaded tier so stale tiers that are
+    /// still resident are accounted for.
+    pub fn model_manager(&self) -> ModelManager {
+        let mut manager = ModelManager::new();
+        
+        manager.audio_status = self.transcription.model_status(&self.transcription.current_tier());
+        manager.vision_status = self.tagging.model_status(&self.tagging.current_tier());
+        manager.registry.current_tier = self.tagging.current_tier();
+        
+        // Generation and embedding do not hold model weights yet
+        manager.generation_status = ModelStatus::NotLoaded;
+        manager.embedding_status = ModelStatus::NotLoaded;
+        
+        manager.total_vram_used_mb = self.transcription.memory_usage_mb() + self.tagging.memory_usage_mb();
+        manager
+    }
+    
+    /// Summarize model status and readiness of every sub-service
+    /// 
+    /// Only reads in-memory state, so it is cheap enough to poll.
+    pub fn health(&self) -> HealthReport {
+        let transcription_tier = self.transcription.current_tier();
+        let tagging_tier = self.tagging.current_tier();
+        
+        let services = vec![
+            ServiceHealth {
+                service: "transcription".to_string(),
+                ready: self.transcription.is_model_loaded(&transcription_tier),
+                status: self.transcription.model_status(&transcription_tier),
+                memory_usage_mb: self.transcription.memory_usage_mb(),
+                tier: Some(transcription_tier),
+            },
+            ServiceHealth {
+                service: "tagging".to_string(),
+                ready: self.tagging.are_models_loaded(&tagging_tier),
+                status: self.tagging.model_status(&tagging_tier),
+                memory_usage_mb: self.tagging.memory_usage_mb(),
+                tier: Some(tagging_tier),
+            },
+            // The embedding placeholder needs no weights; generation is not implemented
+            ServiceHealth {
+                service: "embedding".to_string(),
+                tier: None,
+                status: ModelStatus::NotLoaded,
+                memory_usage_mb: 0,
+                ready: true,
+            },
+            ServiceHealth {
+                service: "generation".to_string(),
+                tier: None,
+                status: ModelStatus::NotLoaded,
+                memory_usage_mb: 0,
+                ready: false,
+            },
+        ];
+        
+        HealthReport {
+            healthy: !services.iter().any(|s| matches!(s.status, ModelStatus::Failed { .. })),
+            total_memory_mb: services.iter().map(|s| s.memory_usage_mb).sum(),
+            services,
+        }
+    }
+    
+    /// Uptime, running task counts and loaded model memory
+    pub fn status(&self) -> ServiceStatus {
+        ServiceStatus {
+            uptime_ms: self.started_at.elapsed().as_millis() as u64,
+            active_tasks: self.tasks.snapshot(),
+            memory_usage_mb: self.transcription.memory_usage_mb() + self.tagging.memory_usage_mb(),
+        }
+    }
+    
+    /// Count a task as running for `status()` until the guard is dropped
+    pub(crate) fn begin_task(&self, task_type: &ProcessingTaskType) -> ActiveTaskGuard<'_> {
+        self.tasks.begin(task_type)
+    }
+}
+
+/// Convert a byte count to whole megabytes, rounding up so loaded models never report 0
+pub(crate) fn bytes_to_mb(bytes: u64) -> u32 {
+    bytes.div_ceil(1024 * 1024).min(u32::MAX as u64) as u32
 }
 
 impl Default for ProcessingService {
@@ -0,0 +1,538 @@
+//! Import and process files in one call
+//!
+//! Adding files to the library takes three stages: ingesting them
+//! (metadata, hash, preview), indexing the resulting assets, and running
+//! the AI steps on them. `ProcessingService::import_and_process` runs the
+//! stages in that order for a list of files and directories. A failure
+//! only affects the asset and stage it happened in: an asset whose AI step
+//! failed stays indexed, and the report says which stage each failed asset
+//! reached.
+//!
+//! With an `IngestService` in `IngestMode::Fast`, the import stops after
+//! indexing and leaves metadata and AI steps to
+//! `ProcessingService::complete_deep_processing`, which can run later in
+//! the background and picks up where an interrupted run stopped.
+
+use crate::reprocess::AiStep;
+use crate::ProcessingService;
+use index::{AssetDocument, SharedIndex};
+use async_trait::async_trait;
+use ingest::{AssetStore, ImportOperation, IngestService};
+use schema::{Asset, DamResult, NotificationLevel, StepStatus};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// What `import_and_process` runs after indexing
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportOptions {
+    /// AI steps run on each imported asset they apply to, in order
+    pub ai_steps: Vec<AiStep>,
+}
+
+impl ImportOptions {
+    /// Ingest and index only
+    pub fn without_ai() -> Self {
+        Self { ai_steps: Vec::new() }
+    }
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            ai_steps: vec![AiStep::Tagging, AiStep::Transcription, AiStep::TextEmbedding],
+        }
+    }
+}
+
+/// Stage of the import pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ImportStage {
+    /// Reading the file into an asset
+    Ingest,
+    /// Reading the metadata a fast import skipped
+    Metadata,
+    /// Adding the asset to the search index
+    Index,
+    /// Running an AI step on the indexed asset
+    Ai(AiStep),
+}
+
+/// Progress of a running `import_and_process`, reported after every stage
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportProgress {
+    /// Stage that just finished
+    pub stage: ImportStage,
+    /// File or directory being handled
+    pub path: PathBuf,
+    /// Items of this stage handled so far: input paths while ingesting,
+    /// assets afterwards
+    pub completed: usize,
+    /// Items of this stage in total
+    pub total: usize,
+}
+
+/// A file that did not make it through every stage
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportFailure {
+    pub path: PathBuf,
+    /// Set once the file was ingested
+    pub asset_id: Option<Uuid>,
+    /// Stage that failed; earlier stages succeeded
+    pub stage: ImportStage,
+    pub error: String,
+}
+
+/// Outcome of an `import_and_process` run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportReport {
+    /// Assets that were ingested and indexed, including those whose AI
+    /// steps failed
+    pub indexed: Vec<Uuid>,
+    /// AI steps that ran successfully
+    pub steps_run: usize,
+    /// AI steps that did not apply or had nothing to process
+    pub steps_skipped: usize,
+    /// Failures by file and stage
+    pub failures: Vec<ImportFailure>,
+    /// Whether the run stopped early because it was cancelled
+    pub cancelled: bool,
+}
+
+impl ImportReport {
+    /// Imported assets with at least one failed AI step
+    pub fn partially_processed(&self) -> Vec<Uuid> {
+        let mut ids: Vec<Uuid> = self.failures.iter()
+            .filter(|failure| matches!(failure.stage, ImportStage::Ai(_)))
+            .filter_map(|failure| failure.asset_id)
+            .collect();
+        ids.dedup();
+        ids
+    }
+}
+
+impl ProcessingService {
+    /// Ingest files and directories, index the assets and run AI steps on them
+    ///
+    /// Directories are ingested recursively. Assets from a fast ingest are
+    /// only indexed; their AI steps are left to the deep pass. AI steps run with the
+    /// services' current tiers, one asset at a time, without holding the
+    /// index lock while models run. Failures are collected per file and
+    /// stage; a failed AI step is also recorded on the asset's step
+    /// status. `progress` is called after each stage; setting `cancel`
+    /// stops the run before the next asset, keeping everything done so far.
+    pub async fn import_and_process(
+        &self,
+        ingest: &IngestService,
+        index: &SharedIndex,
+        paths: &[PathBuf],
+        options: &ImportOptions,
+        mut progress: impl FnMut(ImportProgress),
+        cancel: &AtomicBool,
+    ) -> DamResult<ImportReport> {
+        info!("Importing {} paths with AI steps {:?}", paths.len(), options.ai_steps);
+        let mut report = ImportReport::default();
+
+        // Ingest everything first so the later stages know the total
+        let title = "Importing files";
+        self.events.progress(title, 0, paths.len(), None);
+        let mut assets = Vec::new();
+        for (position, path) in paths.iter().enumerate() {
+            if cancel.load(Ordering::Relaxed) {
+                report.cancelled = true;
+                break;
+            }
+            let operation = if path.is_dir() { ImportOperation::Directory } else { ImportOperation::File };
+            match ingest_path(ingest, path).await {
+                Ok((ingested, failures)) => {
+                    assets.extend(ingested.into_iter().map(|asset| (asset, operation)));
+                    report.failures.extend(failures);
+                }
+                Err(e) => {
+                    warn!("Failed to ingest {}: {}", path.display(), e);
+                    report.failures.push(ImportFailure {
+                        path: path.clone(),
+                        asset_id: None,
+                        stage: ImportStage::Ingest,
+                        error: e.to_string(),
+                    });
+                }
+            }
+            progress(ImportProgress { stage: ImportStage::Ingest, path: path.clone(), completed: position + 1, total: paths.len() });
+            self.events.progress(title, position + 1, paths.len(), Some(path.display().to_string()));
+        }
+
+        let total = assets.len();
+        let title = "Indexing and processing imported assets";
+        self.events.progress(title, 0, total, None);
+        for (position, (asset, operation)) in assets.iter().enumerate() {
+            if report.cancelled || cancel.load(Ordering::Relaxed) {
+                info!("Import cancelled after {} of {} assets", position, total);
+                report.cancelled = true;
+                break;
+            }
+            let completed = position + 1;
+            let path = &asset.current_path;
+
+            let stored = index.index_asset(asset).await;
+            ingest.record_stored(*operation, asset, &stored).await;
+            if let Err(e) = stored {
+                warn!("Failed to index {}: {}", path.display(), e);
+                report.failures.push(ImportFailure {
+                    path: path.clone(),
+                    asset_id: Some(asset.id),
+                    stage: ImportStage::Index,
+                    error: e.to_string(),
+                });
+                progress(ImportProgress { stage: ImportStage::Index, path: path.clone(), completed, total });
+                continue;
+            }
+            report.indexed.push(asset.id);
+            progress(ImportProgress { stage: ImportStage::Index, path: path.clone(), completed, total });
+
+            if !asset.needs_deep_processing {
+                for step in &options.ai_steps {
+                    self.import_step(index, asset, *step, &mut report).await?;
+                    progress(ImportProgress { stage: ImportStage::Ai(*step), path: path.clone(), completed, total });
+                }
+            }
+            self.events.progress(title, completed, total, Some(format!("{} of {} assets", completed, total)));
+        }
+        self.events.hide_progress();
+
+        info!(
+            "Import finished: {} assets indexed, {} AI steps run, {} failures",
+            report.indexed.len(), report.steps_run, report.failures.len()
+        );
+        let (level, title) = match (report.cancelled, report.failures.is_empty()) {
+            (true, _) => (NotificationLevel::Info, "Import cancelled"),
+            (false, true) => (NotificationLevel::Success, "Import finished"),
+            (false, false) => (NotificationLevel::Warning, "Import finished with errors"),
+        };
+        self.events.notify(
+            level,
+            title,
+            format!("{} assets imported, {} failures", report.indexed.len(), report.failures.len()),
+        );
+        Ok(report)
+    }
+
+    /// Finish fast-imported assets: metadata first, then the AI steps
+    ///
+    /// Every indexed asset still marked `needs_deep_processing` gets its
+    /// metadata read and is indexed again, keeping its preview, and then
+    /// runs `options.ai_steps`. The mark is only cleared once an asset is
+    /// through all steps, so a cancelled or interrupted pass resumes with
+    /// the assets it did not finish; assets whose metadata could not be read
+    /// stay marked too. At most `concurrency` assets are handled at a time;
+    /// pass the processing queue's `concurrency()` so the pass does not load
+    /// more models at once than the queue would. The report lists finished
+    /// assets under `indexed`.
+    pub async fn complete_deep_processing(
+        &self,
+        ingest: &IngestService,
+        index: &SharedIndex,
+        options: &ImportOptions,
+        concurrency: usize,
+        mut progress: impl FnMut(ImportProgress),
+        cancel: &AtomicBool,
+    ) -> DamResult<ImportReport> {
+        let pending = index.read().await.pending_deep_processing()?;
+        let total = pending.len();
+        info!("Deep processing {} fast-imported assets", total);
+        let mut report = ImportReport::default();
+        
+        let title = "Completing imported assets";
+        self.events.progress(title, 0, total, None);
+        let mut completed = 0;
+        for chunk in pending.chunks(concurrency.max(1)) {
+            if cancel.load(Ordering::Relaxed) {
+                info!("Deep processing cancelled after {} of {} assets", completed, total);
+                report.cancelled = true;
+                break;
+            }
+            
+            let tasks = chunk.iter().map(|document| self.deepen(ingest, index, document, options));
+            for (document, result) in chunk.iter().zip(futures::future::join_all(tasks).await) {
+                let (stage, asset_report) = result?;
+                report.indexed.extend(asset_report.indexed);
+                report.steps_run += asset_report.steps_run;
+                report.steps_skipped += asset_report.steps_skipped;
+                report.failures.extend(asset_report.failures);
+                
+                completed += 1;
+                progress(ImportProgress { stage, path: document.file_path.clone(), completed, total });
+            }
+            self.events.progress(title, completed, total, Some(format!("{} of {} assets", completed, total)));
+        }
+        self.events.hide_progress();
+        
+        info!(
+            "Deep processing finished: {} assets completed, {} failures",
+            report.indexed.len(), report.failures.len()
+        );
+        if total > 0 && !report.cancelled {
+            let level = if report.failures.is_empty() { NotificationLevel::Success } else { NotificationLevel::Warning };
+            self.events.notify(
+                level,
+                "Imported assets completed",
+                format!("{} assets completed, {} failures", report.indexed.len(), report.failures.len()),
+            );
+        }
+        Ok(report)
+    }
+    
+    /// Deep pass of one asset, returning the last stage it reached
+    async fn deepen(
+        &self,
+        ingest: &IngestService,
+        index: &SharedIndex,
+        document: &AssetDocument,
+        options: &ImportOptions,
+    ) -> DamResult<(ImportStage, ImportReport)> {
+        let mut report = ImportReport::default();
+        let fail = |report: &mut ImportReport, stage: ImportStage, error: String| {
+            warn!("Deep processing of {} failed at {:?}: {}", document.file_path.display(), stage, error);
+            report.failures.push(ImportFailure {
+                path: document.file_path.clone(),
+                asset_id: Some(document.asset_id),
+                stage,
+                error,
+            });
+        };
+        
+        let mut asset = match ingest.complete_asset(&asset_from_document(document)).await {
+            Ok(asset) => asset,
+            Err(e) => {
+                fail(&mut report, ImportStage::Metadata, e.to_string());
+                return Ok((ImportStage::Metadata, report));
+            }
+        };
+        // Still marked until the AI steps are through
+        asset.needs_deep_processing = true;
+        if let Err(e) = index.index_asset(&asset).await {
+            fail(&mut report, ImportStage::Index, e.to_string());
+            return Ok((ImportStage::Index, report));
+        }
+        
+        let mut stage = ImportStage::Index;
+        for step in &options.ai_steps {
+            self.import_step(index, &asset, *step, &mut report).await?;
+            stage = ImportStage::Ai(*step);
+        }
+        
+        index.write().await.set_needs_deep_processing(asset.id, false).await?;
+        report.indexed.push(asset.id);
+        Ok((stage, report))
+    }
+    
+    /// Run one AI step on a freshly indexed asset, recording the outcome
+    ///
+    /// Only index errors while recording a failure are returned.
+    async fn import_step(&self, index: &SharedIndex, asset: &Asset, step: AiStep, report: &mut ImportReport) -> DamResult<()> {
+        // Re-read so later steps see earlier results
+        let Some(document) = index.read().await.get_asset_document(asset.id)? else {
+            report.steps_skipped += 1;
+            return Ok(());
+        };
+        if !step.applies_to(&document.asset_type) {
+            report.steps_skipped += 1;
+            return Ok(());
+        }
+
+        let task_type = step.task_type();
+        let _active = self.begin_task(&task_type);
+        match self.run_step(index, step, &document).await {
+            Ok(true) => report.steps_run += 1,
+            Ok(false) => report.steps_skipped += 1,
+            Err(e) => {
+                warn!("{:?} failed for imported asset {}: {}", step, asset.id, e);
+                let error = e.to_string();
+                index.write().await
+                    .set_processing_step(asset.id, &task_type, StepStatus::Failed { reason: error.clone() }).await?;
+                report.failures.push(ImportFailure {
+                    path: asset.current_path.clone(),
+                    asset_id: Some(asset.id),
+                    stage: ImportStage::Ai(step),
+                    error,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The search index as the folder monitor's `AssetStore`
+///
+/// Assets the monitor ingests are indexed, and an asset whose sidecar
+/// changes is looked up by path, re-read and indexed again.
+#[derive(Clone)]
+pub struct IndexAssetStore {
+    index: SharedIndex,
+}
+
+impl IndexAssetStore {
+    pub fn new(index: SharedIndex) -> Self {
+        Self { index }
+    }
+}
+
+#[async_trait]
+impl AssetStore for IndexAssetStore {
+    async fn store(&self, asset: &Asset) -> DamResult<()> {
+        self.index.index_asset(asset).await
+    }
+
+    async fn find_by_path(&self, path: &Path) -> DamResult<Option<Asset>> {
+        let document = self.index.read().await.find_document_by_path(path)?;
+        Ok(document.as_ref().map(asset_from_document))
+    }
+}
+
+/// Asset rebuilt from what its document knows, for `IngestService::complete_asset`
+/// to read the rest
+fn asset_from_document(document: &AssetDocument) -> Asset {
+    let mut asset = Asset::new(document.file_path.clone(), document.asset_type.clone());
+    asset.id = document.asset_id;
+    if let Some(original_path) = &document.original_path {
+        asset.original_path = original_path.clone();
+    }
+    asset.file_size = document.file_size;
+    asset.created_at = document.created_at;
+    asset.modified_at = document.modified_at;
+    asset.tags = document.tags.clone();
+    asset.rating = document.rating;
+    asset.favorite = document.favorite;
+    asset.format.extension = asset.extension().unwrap_or_default().to_lowercase();
+    asset.format.supported = document.format_supported;
+    asset.format.detection_method = document.detection_method;
+    asset.format.confidence = document.format_confidence;
+    asset.needs_deep_processing = true;
+    asset
+}
+
+/// Ingest a file, or every file below a directory
+///
+/// Files and folders inside a directory that could not be read are
+/// returned as ingest failures next to the assets.
+async fn ingest_path(ingest: &IngestService, path: &Path) -> DamResult<(Vec<Asset>, Vec<ImportFailure>)> {
+    if !path.is_dir() {
+        return ingest.ingest_file(path).await.map(|asset| (vec![asset], Vec::new()));
+    }
+
+    let report = ingest.ingest_directory_report(path).await?;
+    let failure = |path: PathBuf, error: String| {
+        warn!("Failed to ingest {}: {}", path.display(), error);
+        ImportFailure { path, asset_id: None, stage: ImportStage::Ingest, error }
+    };
+    let failures = report.failures.into_iter()
+        .map(|file| failure(file.path, file.error))
+        .chain(report.inaccessible.into_iter().map(|location| failure(location.path, location.message)))
+        .collect();
+    Ok((report.assets, failures))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use index::IndexService;
+
+    #[tokio::test]
+    async fn test_import_isolates_failures() {
+        let dir = std::env::temp_dir().join(format!("dam-import-pipeline-{}", std::process::id()));
+        let files = dir.join("files");
+        std::fs::create_dir_all(&files).unwrap();
+        std::fs::write(files.join("notes.txt"), "Storyboard notes for the forest scene").unwrap();
+        image::RgbImage::new(8, 8).save(files.join("frame.png")).unwrap();
+
+        let index = SharedIndex::new(IndexService::with_storage_dir(dir.join("index")).unwrap());
+        let ingest = IngestService::new().unwrap();
+        let service = ProcessingService::new().unwrap();
+        let options = ImportOptions { ai_steps: vec![AiStep::Tagging] };
+        let paths = vec![files.clone(), dir.join("missing.png")];
+
+        let mut stages = Vec::new();
+        let report = service
+            .import_and_process(&ingest, &index, &paths, &options, |p| stages.push(p.stage), &AtomicBool::new(false))
+            .await
+            .unwrap();
+
+        // Both files are indexed even though tagging has no models loaded
+        assert_eq!(report.indexed.len(), 2);
+        assert_eq!(index.get_stats().await.total_documents, 2);
+        assert_eq!(report.steps_skipped, 1);
+        assert_eq!(report.failures.len(), 2);
+        assert!(report.failures.iter().any(|f| f.stage == ImportStage::Ingest && f.asset_id.is_none()));
+
+        let tagging_failure = report.failures.iter().find(|f| f.stage == ImportStage::Ai(AiStep::Tagging)).unwrap();
+        assert!(tagging_failure.path.ends_with("frame.png"));
+        assert_eq!(report.partially_processed(), vec![tagging_failure.asset_id.unwrap()]);
+        let document = index.read().await.get_asset_document(tagging_failure.asset_id.unwrap()).unwrap().unwrap();
+        assert!(matches!(document.processing_status.tagging, StepStatus::Failed { .. }));
+
+        assert_eq!(stages.iter().filter(|stage| **stage == ImportStage::Ingest).count(), 2);
+        assert_eq!(stages.iter().filter(|stage| **stage == ImportStage::Index).count(), 2);
+
+        // Cancelling before the start imports nothing
+        let report = service
+            .import_and_process(&ingest, &index, &paths, &options, |_| {}, &AtomicBool::new(true))
+            .await
+            .unwrap();
+        assert!(report.cancelled && report.indexed.is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_fast_import_then_deep_pass() {
+        let dir = std::env::temp_dir().join(format!("dam-fast-import-{}", std::process::id()));
+        let files = dir.join("files");
+        std::fs::create_dir_all(&files).unwrap();
+        std::fs::write(files.join("brief.txt"), "Campaign brief for the spring launch").unwrap();
+        std::fs::write(files.join("notes.txt"), "Location scouting notes").unwrap();
+
+        let index = SharedIndex::new(IndexService::with_storage_dir(dir.join("index")).unwrap());
+        let ingest = IngestService::new().unwrap().with_mode(ingest::IngestMode::Fast);
+        let service = ProcessingService::new().unwrap();
+        let options = ImportOptions { ai_steps: vec![AiStep::Tagging] };
+
+        // The fast import only indexes; tagging would not apply to text anyway
+        let report = service
+            .import_and_process(&ingest, &index, &[files.clone()], &options, |_| {}, &AtomicBool::new(false))
+            .await
+            .unwrap();
+        assert_eq!(report.indexed.len(), 2);
+        assert_eq!(report.steps_run + report.steps_skipped, 0);
+        let pending = index.read().await.pending_deep_processing().unwrap();
+        assert_eq!(pending.len(), 2);
+        assert!(pending.iter().all(|document| document.extracted_text.is_none()));
+
+        // A cancelled pass leaves everything to resume later
+        let report = service
+            .complete_deep_processing(&ingest, &index, &options, 2, |_| {}, &AtomicBool::new(true))
+            .await
+            .unwrap();
+        assert!(report.cancelled && report.indexed.is_empty());
+        assert_eq!(index.read().await.pending_deep_processing().unwrap().len(), 2);
+
+        let mut stages = Vec::new();
+        let report = service
+            .complete_deep_processing(&ingest, &index, &options, 2, |p| stages.push(p.stage), &AtomicBool::new(false))
+            .await
+            .unwrap();
+        assert_eq!(report.indexed.len(), 2);
+        assert_eq!(report.steps_skipped, 2);
+        assert!(report.failures.is_empty());
+        assert_eq!(stages, vec![ImportStage::Ai(AiStep::Tagging); 2]);
+        assert!(index.read().await.pending_deep_processing().unwrap().is_empty());
+
+        let results = index.read().await.search_text("scouting", 10).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(!results[0].document.needs_deep_processing);
+        assert!(results[0].document.extracted_text.is_some());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,352 @@
+//! Background processing queue with bounded concurrency
+//!
+//! Accepts `ProcessMessage` task requests, runs a limited number of them
+//! concurrently on the tokio runtime and reports each task through
+//! `ProcessMessage::Started`/`Completed`/`Failed` events.
+
+use crate::error::ProcessError;
+use crate::ProcessingService;
+use schema::{
+    retry_recoverable_async, DamError, DamResult, ModelTier, ProcessMessage, ProcessingResult,
+    ProcessingTaskType, DEFAULT_RETRY_ATTEMPTS, DEFAULT_RETRY_DELAY,
+};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, mpsc, Semaphore};
+use tokio::task::{JoinHandle, JoinSet};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Upper bound on concurrent workers regardless of available VRAM
+const MAX_WORKERS: usize = 4;
+
+/// Capacity of the event broadcast channel
+const EVENT_CAPACITY: usize = 256;
+
+/// State of a queued task
+#[derive(Debug, Clone)]
+pub enum TaskState {
+    /// Waiting for a free worker
+    Pending,
+    /// Currently being processed
+    Running,
+    /// Finished successfully
+    Completed(ProcessingResult),
+    /// Finished with an error
+    Failed(String),
+}
+
+/// A task waiting to be dispatched
+struct QueuedTask {
+    task_id: Uuid,
+    message: ProcessMessage,
+}
+
+/// Background queue that runs AI tasks with a bounded worker pool
+/// 
+/// Dropping the queue stops accepting tasks; queued and running ones still
+/// finish in the background. Use `shutdown` to wait for them.
+pub struct ProcessingQueue {
+    /// Channel used to submit tasks to the dispatcher
+    sender: mpsc::UnboundedSender<QueuedTask>,
+    /// Broadcast channel for task events
+    events: broadcast::Sender<ProcessMessage>,
+    /// Last known state of every submitted task
+    states: Arc<Mutex<HashMap<Uuid, TaskState>>>,
+    /// Dispatcher task owning all running workers
+    dispatcher: JoinHandle<()>,
+    /// Maximum number of concurrently running tasks
+    concurrency: usize,
+}
+
+impl ProcessingQueue {
+    /// Create a queue sized for the given tier and available VRAM
+    pub fn new(service: Arc<ProcessingService>, tier: &ModelTier, available_vram_mb: u32) -> Self {
+        Self::with_concurrency(service, Self::concurrency_for(tier, available_vram_mb))
+    }
+    
+    /// Create a queue with an explicit worker count
+    ///
+    /// Must be called from within a tokio runtime.
+    pub fn with_concurrency(service: Arc<ProcessingService>, concurrency: usize) -> Self {
+        let concurrency = concurrency.max(1);
+        info!("Starting processing queue with {} workers", concurrency);
+        
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+        let states = Arc::new(Mutex::new(HashMap::new()));
+        
+        let dispatcher = tokio::spawn(run_dispatcher(
+            service,
+            receiver,
+            Arc::new(Semaphore::new(concurrency)),
+            events.clone(),
+            states.clone(),
+        ));
+        
+        Self {
+            sender,
+            events,
+            states,
+            dispatcher,
+            concurrency,
+        }
+    }
+    
+    /// Number of workers appropriate for a tier
+    ///
+    /// One worker per `min_vram_mb` of the tier that fits in the available
+    /// VRAM, so several models of the same size can be resident at once.
+    /// CPU-only systems (or too little VRAM) get a single worker.
+    pub fn concurrency_for(tier: &ModelTier, available_vram_mb: u32) -> usize {
+        let per_worker = tier.min_vram_mb().max(1);
+        ((available_vram_mb / per_worker) as usize).clamp(1, MAX_WORKERS)
+    }
+    
+    /// Maximum number of concurrently running tasks
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+    
+    /// Submit a task request and return its task ID
+    ///
+    /// Only concrete work requests (`TranscribeAudio`, `TagImage`,
+    /// `GenerateEmbedding`, `EditImage`) are accepted.
+    pub fn enqueue(&self, message: ProcessMessage) -> DamResult<Uuid> {
+        if task_info(&message).is_none() {
+            return Err(DamError::invalid_operation(format!(
+                "Message cannot be queued as a processing task: {:?}", message
+            )));
+        }
+        
+        let task_id = Uuid::new_v4();
+        self.states.lock().unwrap().insert(task_id, TaskState::Pending);
+        
+        self.sender.send(QueuedTask { task_id, message })
+            .map_err(|_| DamError::processing("Processing queue has shut down"))?;
+        
+        debug!("Queued processing task {}", task_id);
+        Ok(task_id)
+    }
+    
+    /// Subscribe to task events
+    pub fn subscribe(&self) -> broadcast::Receiver<ProcessMessage> {
+        self.events.subscribe()
+    }
+    
+    /// Poll the state of a task
+    pub fn status(&self, task_id: &Uuid) -> Option<TaskState> {
+        self.states.lock().unwrap().get(task_id).cloned()
+    }
+    
+    /// Number of tasks that have not started yet
+    pub fn pending_count(&self) -> usize {
+        self.states.lock().unwrap()
+            .values()
+            .filter(|state| matches!(state, TaskState::Pending))
+            .count()
+    }
+    
+    /// Forget the states of finished tasks
+    pub fn clear_finished(&self) {
+        self.states.lock().unwrap()
+            .retain(|_, state| matches!(state, TaskState::Pending | TaskState::Running));
+    }
+    
+    /// Stop accepting tasks and wait for queued and running ones to finish
+    pub async fn shutdown(self) {
+        let Self { sender, dispatcher, .. } = self;
+        drop(sender);
+        if let Err(e) = dispatcher.await {
+            warn!("Processing queue dispatcher ended abnormally: {}", e);
+        }
+    }
+}
+
+/// Receive tasks and run them as permits become available
+async fn run_dispatcher(
+    service: Arc<ProcessingService>,
+    mut receiver: mpsc::UnboundedReceiver<QueuedTask>,
+    semaphore: Arc<Semaphore>,
+    events: broadcast::Sender<ProcessMessage>,
+    states: Arc<Mutex<HashMap<Uuid, TaskState>>>,
+) {
+    let mut workers = JoinSet::new();
+    
+    while let Some(task) = receiver.recv().await {
+        let permit = match semaphore.clone().acquire_owned().await {
+            Ok(permit) => permit,
+            Err(_) => break,
+        };
+        
+        // Reap finished workers so the set does not grow unbounded
+        while workers.try_join_next().is_some() {}
+        
+        let service = service.clone();
+        let events = events.clone();
+        let states = states.clone();
+        
+        workers.spawn(async move {
+            let _permit = permit;
+            run_task(&service, task, &events, &states).await;
+        });
+    }
+    
+    // Sender dropped and queue drained: let running workers finish
+    while workers.join_next().await.is_some() {}
+}
+
+/// Execute a single task and publish its events
+async fn run_task(
+    service: &ProcessingService,
+    task: QueuedTask,
+    events: &broadcast::Sender<ProcessMessage>,
+    states: &Mutex<HashMap<Uuid, TaskState>>,
+) {
+    let QueuedTask { task_id, message } = task;
+    let Some((asset_id, task_type)) = task_info(&message) else {
+        return;
+    };
+    
+    states.lock().unwrap().insert(task_id, TaskState::Running);
+    let _active = service.begin_task(&task_type);
+    
+    // Send errors only mean nobody is subscribed
+    let _ = events.send(ProcessMessage::Started { task_id, asset_id, task_type: task_type.clone() });
+    
+    match execute(service, message).await {
+        Ok(result) => {
+            debug!("Processing task {} completed", task_id);
+            states.lock().unwrap().insert(task_id, TaskState::Completed(result.clone()));
+            let _ = events.send(ProcessMessage::Completed { task_id, asset_id, result });
+        }
+        Err(e) => {
+            warn!("Processing task {} failed: {}", task_id, e);
+            let error = e.to_string();
+            states.lock().unwrap().insert(task_id, TaskState::Failed(error.clone()));
+            let _ = events.send(ProcessMessage::Failed { task_id, asset_id, task_type, error });
+        }
+    }
+}
+
+/// Run the processing service call matching a task request
+/// 
+/// Transcription and tagging are retried when they fail with a recoverable
+/// error, such as a failed inference on a busy device.
+async fn execute(service: &ProcessingService, message: ProcessMessage) -> DamResult<ProcessingResult> {
+    match message {
+        ProcessMessage::TranscribeAudio { audio_path, .. } => {
+            let transcript = retry_recoverable_async(DEFAULT_RETRY_ATTEMPTS, DEFAULT_RETRY_DELAY, || {
+                service.transcription().transcribe_file(&audio_path, None)
+            }).await?;
+            Ok(ProcessingResult::Transcription { text: transcript.full_text })
+        }
+        ProcessMessage::TagImage { image_path, .. } => {
+            let tagging = retry_recoverable_async(DEFAULT_RETRY_ATTEMPTS, DEFAULT_RETRY_DELAY, || {
+                service.tagging().tag_image(&image_path)
+            }).await?;
+            Ok(ProcessingResult::Tags {
+                tags: tagging.tags.into_iter().map(|(tag, _)| tag).collect(),
+            })
+        }
+        ProcessMessage::GenerateEmbedding { content, .. } => {
+            let vector = service.embedding().generate_embedding(&content).await?;
+            Ok(ProcessingResult::Embedding { vector })
+        }
+        ProcessMessage::EditImage { image_path, prompt, .. } => {
+            let image_data = service.generation().generate_image(&prompt).await?;
+            let output_path = edited_output_path(&image_path);
+            tokio::fs::write(&output_path, image_data).await
+                .map_err(|e| ProcessError::GenerationFailed(format!("Failed to write edited image: {}", e)))?;
+            Ok(ProcessingResult::EditedImage { output_path })
+        }
+        other => Err(DamError::invalid_operation(format!(
+            "Message cannot be processed as a task: {:?}", other
+        ))),
+    }
+}
+
+/// Asset ID and task type for queueable messages
+fn task_info(message: &ProcessMessage) -> Option<(Uuid, ProcessingTaskType)> {
+    match message {
+        ProcessMessage::TranscribeAudio { asset_id, .. } => Some((*asset_id, ProcessingTaskType::Transcription)),
+        ProcessMessage::TagImage { asset_id, .. } => Some((*asset_id, ProcessingTaskType::ImageTagging)),
+        ProcessMessage::GenerateEmbedding { asset_id, .. } => Some((*asset_id, ProcessingTaskType::EmbeddingGeneration)),
+        ProcessMessage::EditImage { asset_id, .. } => Some((*asset_id, ProcessingTaskType::ImageEditing)),
+        _ => None,
+    }
+}
+
+/// Output path for an edited image, next to the source
+fn edited_output_path(image_path: &std::path::Path) -> PathBuf {
+    let stem = image_path.file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "image".to_string());
+    image_path.with_file_name(format!("{}_edited.png", stem))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_concurrency_for_tier() {
+        // CPU-only systems get a single worker
+        assert_eq!(ProcessingQueue::concurrency_for(&ModelTier::Medium, 0), 1);
+        assert_eq!(ProcessingQueue::concurrency_for(&ModelTier::Low, 4096), 2);
+        assert_eq!(ProcessingQueue::concurrency_for(&ModelTier::Low, 65536), MAX_WORKERS);
+    }
+    
+    #[tokio::test]
+    async fn test_embedding_task_completes() {
+        let service = Arc::new(ProcessingService::new().unwrap());
+        let queue = ProcessingQueue::with_concurrency(service, 2);
+        let mut events = queue.subscribe();
+        
+        let task_id = queue.enqueue(ProcessMessage::GenerateEmbedding {
+            asset_id: Uuid::new_v4(),
+            content: "a red car".to_string(),
+        }).unwrap();
+        
+        loop {
+            match events.recv().await.unwrap() {
+                ProcessMessage::Completed { task_id: id, .. } if id == task_id => break,
+                ProcessMessage::Failed { task_id: id, error, .. } if id == task_id => panic!("{}", error),
+                _ => {}
+            }
+        }
+        
+        assert!(matches!(queue.status(&task_id), Some(TaskState::Completed(_))));
+    }
+    
+    #[tokio::test]
+    async fn test_shutdown_finishes_queued_tasks() {
+        let service = Arc::new(ProcessingService::new().unwrap());
+        let queue = ProcessingQueue::with_concurrency(service, 1);
+        let states = queue.states.clone();
+        
+        let task_ids: Vec<Uuid> = (0..3)
+            .map(|i| queue.enqueue(ProcessMessage::GenerateEmbedding {
+                asset_id: Uuid::new_v4(),
+                content: format!("asset {}", i),
+            }).unwrap())
+            .collect();
+        
+        queue.shutdown().await;
+        
+        let states = states.lock().unwrap();
+        for task_id in &task_ids {
+            assert!(matches!(states.get(task_id), Some(TaskState::Completed(_))));
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_rejects_non_task_messages() {
+        let service = Arc::new(ProcessingService::new().unwrap());
+        let queue = ProcessingQueue::with_concurrency(service, 1);
+        
+        let result = queue.enqueue(ProcessMessage::ProcessBatch { asset_ids: vec![] });
+        assert!(result.is_err());
+    }
+}
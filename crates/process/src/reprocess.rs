@@ -0,0 +1,273 @@
+//! Bulk reprocessing of the whole library
+//!
+//! After switching to a larger model tier, existing AI results are still
+//! the ones the old models produced. `ProcessingService::reprocess_all`
+//! walks every indexed asset, runs the requested steps at the new tier and
+//! writes the results back. Models run without holding the index lock, so
+//! searches keep working while a library is reprocessed.
+
+use crate::ProcessingService;
+use index::{AiKind, AssetDocument, EmbeddingType, SharedIndex, TranscriptionSegment};
+use schema::{AssetType, DamResult, ModelTier, NotificationLevel, ProcessingTaskType, StepStatus};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// An AI processing step that can be rerun over the library
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AiStep {
+    /// Tags, caption and visual embedding of images
+    Tagging,
+    /// Transcript of audio and video
+    Transcription,
+    /// Text embedding of transcripts, captions and extracted text
+    TextEmbedding,
+}
+
+impl AiStep {
+    /// Task type whose step status this step updates
+    pub fn task_type(&self) -> ProcessingTaskType {
+        match self {
+            AiStep::Tagging => ProcessingTaskType::ImageTagging,
+            AiStep::Transcription => ProcessingTaskType::Transcription,
+            AiStep::TextEmbedding => ProcessingTaskType::EmbeddingGeneration,
+        }
+    }
+
+    /// Whether the step can run on an asset type
+    pub fn applies_to(&self, asset_type: &AssetType) -> bool {
+        match self {
+            AiStep::Tagging => matches!(asset_type, AssetType::Image),
+            AiStep::Transcription => AiKind::Transcription.applies_to(asset_type),
+            AiStep::TextEmbedding => AiKind::TextEmbedding.applies_to(asset_type),
+        }
+    }
+
+    /// Whether the step already completed on a document
+    pub fn is_done(&self, document: &AssetDocument) -> bool {
+        match self {
+            // Cleared embeddings (e.g. after a model change) need tagging again
+            AiStep::Tagging => {
+                document.processing_status.tagging == StepStatus::Done
+                    && document.has_ai_result(AiKind::VisualEmbedding)
+            }
+            AiStep::Transcription => document.processing_status.transcription == StepStatus::Done,
+            // Tagging also marks the embedding step done, so look at the result itself
+            AiStep::TextEmbedding => document.has_ai_result(AiKind::TextEmbedding),
+        }
+    }
+}
+
+/// Progress of a running `reprocess_all`, reported after every asset
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReprocessProgress {
+    /// Asset that was just handled
+    pub asset_id: Uuid,
+    /// Assets handled so far, including this one
+    pub completed: usize,
+    /// Assets in the library when the run started
+    pub total: usize,
+}
+
+/// Outcome of a `reprocess_all` run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReprocessReport {
+    /// Steps that ran successfully
+    pub steps_run: usize,
+    /// Steps skipped because they were already done or do not apply
+    pub steps_skipped: usize,
+    /// Steps that failed, with the asset and reason
+    pub failures: Vec<(Uuid, ProcessingTaskType, String)>,
+    /// Whether the run stopped early because it was cancelled
+    pub cancelled: bool,
+}
+
+impl ProcessingService {
+    /// Rerun AI steps on every indexed asset at the given tier
+    ///
+    /// Steps that already completed are skipped unless `force` is set.
+    /// Failures are recorded on the asset's step status and in the report
+    /// without stopping the run. `progress` is called after each asset;
+    /// setting `cancel` stops the run before the next asset, leaving the
+    /// assets done so far updated.
+    ///
+    /// If the new models produce embeddings of another dimension, the old
+    /// embeddings of that type are cleared from the index before the first
+    /// new one is stored, so the vector store never mixes the two.
+    pub async fn reprocess_all(
+        &self,
+        index: &SharedIndex,
+        tier: ModelTier,
+        steps: &[AiStep],
+        force: bool,
+        mut progress: impl FnMut(ReprocessProgress),
+        cancel: &AtomicBool,
+    ) -> DamResult<ReprocessReport> {
+        info!("Reprocessing library at {} tier: {:?} (force: {})", tier.display_name(), steps, force);
+
+        if steps.contains(&AiStep::Tagging) {
+            self.tagging.set_tier(tier.clone()).await?;
+        }
+        if steps.contains(&AiStep::Transcription) {
+            self.transcription.set_tier(tier.clone()).await?;
+        }
+        if steps.contains(&AiStep::TextEmbedding) {
+            // Vectors of a swapped-out embedder must not mix with new ones
+            index.write().await.ensure_embedding_dimension(EmbeddingType::Text, self.embedding.dimension()).await?;
+        }
+
+        // Snapshot the asset list so the lock is not held while models run
+        let asset_ids: Vec<Uuid> = index.read().await
+            .iter_documents()
+            .filter_map(|document| match document {
+                Ok(document) => Some(document.asset_id),
+                Err(e) => {
+                    warn!("Skipping unreadable document during reprocessing: {}", e);
+                    None
+                }
+            })
+            .collect();
+
+        let total = asset_ids.len();
+        let mut report = ReprocessReport::default();
+        let title = format!("Reprocessing library at {} tier", tier.display_name());
+        self.events.progress(&title, 0, total, None);
+
+        for (position, asset_id) in asset_ids.into_iter().enumerate() {
+            if cancel.load(Ordering::Relaxed) {
+                info!("Reprocessing cancelled after {} of {} assets", position, total);
+                report.cancelled = true;
+                break;
+            }
+
+            for step in steps {
+                // Re-read per step so later steps see earlier results
+                let Some(document) = index.read().await.get_asset_document(asset_id)? else {
+                    break;
+                };
+                if !step.applies_to(&document.asset_type) || (!force && step.is_done(&document)) {
+                    report.steps_skipped += 1;
+                    continue;
+                }
+
+                let task_type = step.task_type();
+                let _active = self.begin_task(&task_type);
+                match self.run_step(index, *step, &document).await {
+                    Ok(true) => report.steps_run += 1,
+                    Ok(false) => report.steps_skipped += 1,
+                    Err(e) => {
+                        warn!("Reprocessing {:?} failed for {}: {}", step, asset_id, e);
+                        let reason = e.to_string();
+                        index.write().await
+                            .set_processing_step(asset_id, &task_type, StepStatus::Failed { reason: reason.clone() }).await?;
+                        report.failures.push((asset_id, task_type, reason));
+                    }
+                }
+            }
+
+            progress(ReprocessProgress { asset_id, completed: position + 1, total });
+            self.events.progress(&title, position + 1, total, Some(format!("{} of {} assets", position + 1, total)));
+        }
+        self.events.hide_progress();
+
+        info!(
+            "Reprocessing finished: {} steps run, {} skipped, {} failed",
+            report.steps_run, report.steps_skipped, report.failures.len()
+        );
+        let (level, title) = match (report.cancelled, report.failures.is_empty()) {
+            (true, _) => (NotificationLevel::Info, "Reprocessing cancelled"),
+            (false, true) => (NotificationLevel::Success, "Reprocessing finished"),
+            (false, false) => (NotificationLevel::Warning, "Reprocessing finished with errors"),
+        };
+        self.events.notify(
+            level,
+            title,
+            format!("{} steps run, {} skipped, {} failed", report.steps_run, report.steps_skipped, report.failures.len()),
+        );
+        Ok(report)
+    }
+
+    /// Run one step on a document and store its result
+    ///
+    /// Returns false if there was nothing to process, e.g. no text to embed.
+    pub(crate) async fn run_step(&self, index: &SharedIndex, step: AiStep, document: &AssetDocument) -> DamResult<bool> {
+        let asset_id = document.asset_id;
+        match step {
+            AiStep::Tagging => {
+                let result = self.tagging.tag_image(&document.file_path).await?;
+                let tags = result.tags.into_iter().map(|(tag, _)| tag).collect();
+
+                let mut index = index.write().await;
+                index.ensure_embedding_dimension(EmbeddingType::Visual, result.embedding.len()).await?;
+                index.update_with_ai_results(asset_id, Some(tags), result.caption, None, Some(result.embedding), None).await?;
+            }
+            AiStep::Transcription => {
+                let transcript = self.transcription.transcribe_file(&document.file_path, None).await?;
+                let mut index = index.write().await;
+                if transcript.segments.is_empty() {
+                    index.update_with_ai_results(asset_id, None, None, Some(transcript.full_text), None, None).await?;
+                } else {
+                    // Keep the timing so diarization can label the segments later
+                    let segments = transcript.segments.into_iter()
+                        .map(|segment| TranscriptionSegment {
+                            start_ms: segment.start_time_ms.max(0) as u64,
+                            end_ms: segment.end_time_ms.max(0) as u64,
+                            text: segment.text,
+                            speaker: None,
+                        })
+                        .collect();
+                    index.update_transcription_segments(asset_id, segments).await?;
+                }
+            }
+            AiStep::TextEmbedding => {
+                let text = [&document.transcription, &document.extracted_text, &document.ai_caption]
+                    .into_iter()
+                    .flatten()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                if text.trim().is_empty() {
+                    return Ok(false);
+                }
+
+                let chunks = self.embedding.embed_chunks(&text).await?;
+                if chunks.is_empty() {
+                    return Ok(false);
+                }
+                index.write().await.update_text_embedding_chunks(asset_id, chunks).await?;
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_steps_apply_to_asset_types() {
+        assert!(AiStep::Tagging.applies_to(&AssetType::Image));
+        assert!(!AiStep::Tagging.applies_to(&AssetType::Audio));
+        assert!(AiStep::Transcription.applies_to(&AssetType::Video));
+        assert!(!AiStep::Transcription.applies_to(&AssetType::Image));
+        assert!(AiStep::TextEmbedding.applies_to(&AssetType::Document));
+
+        assert_eq!(AiStep::Tagging.task_type(), ProcessingTaskType::ImageTagging);
+        assert_eq!(AiStep::TextEmbedding.task_type(), ProcessingTaskType::EmbeddingGeneration);
+    }
+
+    #[test]
+    fn test_tagging_done_needs_embedding() {
+        let asset = schema::Asset::new("/photos/a.png".into(), AssetType::Image);
+        let mut document = AssetDocument::from_asset(&asset);
+        document.processing_status.tagging = StepStatus::Done;
+        document.visual_embedding = Some(vec![1.0, 0.0]);
+        assert!(AiStep::Tagging.is_done(&document));
+
+        // A cleared embedding sends the asset back through tagging
+        document.visual_embedding = None;
+        assert!(!AiStep::Tagging.is_done(&document));
+    }
+}
@@ -6,8 +6,11 @@
 //! - Visual feature extraction for search
 //! - Tiered quality levels for different hardware
 
-use schema::{DamResult, ModelTier, ModelRegistry, ModelStatus};
+use schema::{ComputeDevice, DamResult, ModelTier, ModelRegistry, ModelStatus};
 use crate::error::ProcessError;
+use crate::bytes_to_mb;
+use crate::cache::{CachedInference, EmbeddingCache};
+use crate::captioning::{self, CaptionOptions, FixedCaptionScorer};
 use std::path::{Path, PathBuf};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
@@ -16,6 +19,12 @@ use image::{DynamicImage, ImageBuffer, Rgb};
 use candle_core::{Device, Tensor, DType};
 use candle_nn::VarBuilder;
 
+/// Largest accepted query image for similarity search (bytes)
+pub const MAX_QUERY_IMAGE_BYTES: usize = 20 * 1024 * 1024;
+
+/// Largest width or height of an image decoded for the models
+const MAX_DECODE_DIMENSION: u32 = 16384;
+
 /// Image tagging result with confidence scores
 #[derive(Debug, Clone)]
 pub struct TaggingResult {
@@ -80,6 +89,8 @@ pub struct VisionModel {
     preprocess_config: ImagePreprocessConfig,
     /// Placeholder for actual model (would be candle model in real implementation)
     _model_data: Vec<u8>,
+    /// Device that input tensors are built and run on
+    device: Device,
 }
 
 impl VisionModel {
@@ -106,9 +117,26 @@ impl VisionModel {
             model_type,
             preprocess_config,
             _model_data: model_data,
+            device: Device::Cpu,
         })
     }
     
+    /// Run the model on another device
+    pub fn with_device(mut self, device: Device) -> Self {
+        self.device = device;
+        self
+    }
+    
+    /// Device the model runs on
+    pub fn compute_device(&self) -> ComputeDevice {
+        device_kind(&self.device)
+    }
+    
+    /// Memory held by the model weights in bytes
+    pub fn memory_usage_bytes(&self) -> u64 {
+        self._model_data.len() as u64
+    }
+    
     /// Preprocess image for model input
     pub fn preprocess_image(&self, image: &DynamicImage) -> Result<Tensor, String> {
         let config = &self.preprocess_config;
@@ -139,9 +167,7 @@ impl VisionModel {
             }
         }
         
-        // Create tensor (placeholder - in real implementation would use candle)
-        // For now, return a dummy tensor structure
-        Tensor::from_vec(tensor_data, (1, 3, height as usize, width as usize), &Device::Cpu)
+        Tensor::from_vec(tensor_data, (1, 3, height as usize, width as usize), &self.device)
             .map_err(|e| format!("Failed to create tensor: {}", e))
     }
     
@@ -179,16 +205,128 @@ impl VisionModel {
     }
 }
 
+/// Pick the candle device for a device preference
+/// 
+/// `Auto` only tries accelerators when `gpu_permitted`. An accelerator that
+/// is unavailable or fails to open falls back to the CPU.
+pub fn select_device(preference: ComputeDevice, gpu_permitted: bool) -> Device {
+    let accelerator = match preference {
+        ComputeDevice::Cpu => None,
+        ComputeDevice::Cuda | ComputeDevice::Metal => {
+            let device = open_accelerator(preference);
+            if device.is_none() {
+                warn!("{} inference requested but not available, using CPU", preference.display_name());
+            }
+            device
+        }
+        ComputeDevice::Auto if gpu_permitted => {
+            open_accelerator(ComputeDevice::Cuda).or_else(|| open_accelerator(ComputeDevice::Metal))
+        }
+        ComputeDevice::Auto => None,
+    };
+    accelerator.unwrap_or(Device::Cpu)
+}
+
+/// Preprocess an image and run one model on it
+/// 
+/// Blocks for the whole forward pass; async callers go through
+/// `spawn_blocking`.
+fn run_inference(model: &VisionModel, image: &DynamicImage) -> Result<Vec<f32>, ProcessError> {
+    let tensor = model.preprocess_image(image)
+        .map_err(ProcessError::ImageProcessingFailed)?;
+    model.inference(&tensor)
+        .map_err(ProcessError::InferenceFailed)
+}
+
+/// Caption an image with a BLIP model
+/// 
+/// BLIP's text decoder weights are not wired up yet, so after running the
+/// model this decodes the tier's placeholder caption, cut to the length of
+/// `options`.
+fn caption_with(model: &VisionModel, image: &DynamicImage, tier: &ModelTier, options: &CaptionOptions) -> Result<String, ProcessError> {
+    run_inference(model, image)?;
+    let scorer = FixedCaptionScorer::new(captioning::placeholder_caption(tier));
+    Ok(captioning::decode(&scorer, options))
+}
+
+/// Decode an image file for the models
+/// 
+/// The format is taken from the contents, and decoding stops at
+/// `MAX_DECODE_DIMENSION` and the default allocation limit, so a crafted
+/// file cannot exhaust memory.
+fn open_image(path: &Path) -> Result<DynamicImage, ProcessError> {
+    let load_error = |e: String| ProcessError::ImageLoadFailed(format!("Failed to load image: {}", e));
+    let mut reader = image::io::Reader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|e| load_error(e.to_string()))?;
+    reader.limits(decode_limits());
+    reader.decode().map_err(|e| load_error(e.to_string()))
+}
+
+/// Decode image bytes for the models, with the limits of `open_image`
+fn decode_image(data: &[u8]) -> Result<DynamicImage, ProcessError> {
+    let load_error = |e: String| ProcessError::ImageLoadFailed(format!("Query is not a decodable image: {}", e));
+    let mut reader = image::io::Reader::new(std::io::Cursor::new(data))
+        .with_guessed_format()
+        .map_err(|e| load_error(e.to_string()))?;
+    reader.limits(decode_limits());
+    reader.decode().map_err(|e| load_error(e.to_string()))
+}
+
+/// Limits applied to every image decoded for the models
+fn decode_limits() -> image::io::Limits {
+    let mut limits = image::io::Limits::default();
+    limits.max_image_width = Some(MAX_DECODE_DIMENSION);
+    limits.max_image_height = Some(MAX_DECODE_DIMENSION);
+    limits
+}
+
+/// Which kind of hardware a candle device is
+fn device_kind(device: &Device) -> ComputeDevice {
+    if device.is_cuda() {
+        ComputeDevice::Cuda
+    } else if device.is_metal() {
+        ComputeDevice::Metal
+    } else {
+        ComputeDevice::Cpu
+    }
+}
+
+/// Open the first CUDA or Metal device, if this build and machine have one
+fn open_accelerator(device: ComputeDevice) -> Option<Device> {
+    let opened = match device {
+        ComputeDevice::Cuda if candle_core::utils::cuda_is_available() => Device::new_cuda(0),
+        ComputeDevice::Metal if candle_core::utils::metal_is_available() => Device::new_metal(0),
+        _ => return None,
+    };
+    
+    match opened {
+        Ok(device) => Some(device),
+        Err(e) => {
+            warn!("Failed to open {} device: {}", device.display_name(), e);
+            None
+        }
+    }
+}
+
 /// Image tagging service with model management
 pub struct TaggingService {
     /// Model registry for tier management
     registry: Arc<Mutex<ModelRegistry>>,
     /// Loaded vision models per tier
     models: Arc<Mutex<HashMap<ModelTier, HashMap<String, VisionModel>>>>,
+    /// Models a tier is configured with but that were not found on disk
+    missing_models: Arc<Mutex<HashMap<ModelTier, Vec<String>>>>,
     /// Model storage directory
     models_dir: PathBuf,
     /// Pre-defined tag vocabulary for zero-shot classification
     tag_vocabulary: Vec<String>,
+    /// Optional cache of inference results keyed by file content hash
+    embedding_cache: Option<Arc<EmbeddingCache>>,
+    /// Preferred inference device, applied when a tier's models load
+    device: Mutex<ComputeDevice>,
+    /// Length and decoding of captions made while tagging
+    caption_options: CaptionOptions,
 }
 
 impl TaggingService {
@@ -202,8 +340,12 @@ impl TaggingService {
         Ok(Self {
             registry: Arc::new(Mutex::new(ModelRegistry::new())),
             models: Arc::new(Mutex::new(HashMap::new())),
+            missing_models: Arc::new(Mutex::new(HashMap::new())),
             models_dir,
             tag_vocabulary,
+            embedding_cache: None,
+            device: Mutex::new(ComputeDevice::Auto),
+            caption_options: CaptionOptions::default(),
         })
     }
     
@@ -217,23 +359,88 @@ impl TaggingService {
         Ok(Self {
             registry: Arc::new(Mutex::new(ModelRegistry::new())),
             models: Arc::new(Mutex::new(HashMap::new())),
+            missing_models: Arc::new(Mutex::new(HashMap::new())),
             models_dir,
             tag_vocabulary,
+            embedding_cache: None,
+            device: Mutex::new(ComputeDevice::Auto),
+            caption_options: CaptionOptions::default(),
         })
     }
     
+    /// Reuse inference results for images whose content was seen before
+    pub fn with_embedding_cache(mut self, cache: Arc<EmbeddingCache>) -> Self {
+        self.embedding_cache = Some(cache);
+        self
+    }
+    
+    /// Run inference on the given device
+    /// 
+    /// With `Auto`, CUDA or Metal is used when the system info reports
+    /// enough VRAM for the tier's vision models.
+    pub fn with_device(mut self, device: ComputeDevice) -> Self {
+        *self.device.get_mut().unwrap() = device;
+        self
+    }
+    
+    /// Switch the inference device, reloading the current tier's models on it
+    pub async fn set_device(&self, device: ComputeDevice) -> DamResult<()> {
+        *self.device.lock().unwrap() = device;
+        
+        let tier = self.current_tier();
+        if self.are_models_loaded(&tier) {
+            self.load_models(tier).await?;
+        }
+        Ok(())
+    }
+    
+    /// Preferred inference device
+    pub fn device(&self) -> ComputeDevice {
+        *self.device.lock().unwrap()
+    }
+    
+    /// Caption length and decoding used by `tag_image`
+    /// 
+    /// Cached captions were made with the options in effect when they were
+    /// cached; `caption_image` always decodes afresh.
+    pub fn with_caption_options(mut self, options: CaptionOptions) -> Self {
+        self.caption_options = options;
+        self
+    }
+    
+    /// Caption options used by `tag_image`
+    pub fn caption_options(&self) -> CaptionOptions {
+        self.caption_options
+    }
+    
+    /// Drop all cached inference results
+    pub fn clear_embedding_cache(&self) -> DamResult<()> {
+        match &self.embedding_cache {
+            Some(cache) => cache.clear(),
+            None => Ok(()),
+        }
+    }
+    
     /// Load models for specific tier
+    /// 
+    /// Missing model files are not an error: the tier runs with whatever
+    /// loaded (e.g. tags without captions when BLIP is absent) and
+    /// `model_status` reports it as degraded.
     pub async fn load_models(&self, tier: ModelTier) -> DamResult<()> {
-        let config = {
+        let (config, gpu_permitted) = {
             let registry = self.registry.lock().unwrap();
-            registry.get_config(&tier)
+            let config = registry.get_config(&tier)
                 .ok_or_else(|| ProcessError::ModelNotFound(format!("No config for tier: {:?}", tier)))?
-                .clone()
+                .clone();
+            let gpu_permitted = registry.available_vram_mb >= config.vision.model_size_mb;
+            (config, gpu_permitted)
         };
         
-        info!("Loading vision models for tier {:?}", tier);
+        let device = select_device(self.device(), gpu_permitted);
+        info!("Loading vision models for tier {:?} on {}", tier, device_kind(&device).display_name());
         
         let mut tier_models = HashMap::new();
+        let mut missing = Vec::new();
         
         // Load CLIP model
         let clip_filename = format!("{}.safetensors", config.vision.clip_model);
@@ -241,10 +448,12 @@ impl TaggingService {
         
         if clip_path.exists() {
             let clip_model = VisionModel::load_from_file(&clip_path, config.vision.clip_model.clone())
-                .map_err(|e| ProcessError::ModelLoadFailed(e))?;
+                .map_err(|e| ProcessError::ModelLoadFailed(e))?
+                .with_device(device.clone());
             tier_models.insert("clip".to_string(), clip_model);
         } else {
             warn!("CLIP model not found: {}", clip_path.display());
+            missing.push(config.vision.clip_model.clone());
         }
         
         // Load BLIP model if specified
@@ -254,39 +463,106 @@ impl TaggingService {
             
             if blip_path.exists() {
                 let blip_model = VisionModel::load_from_file(&blip_path, blip_model_name.clone())
-                    .map_err(|e| ProcessError::ModelLoadFailed(e))?;
+                    .map_err(|e| ProcessError::ModelLoadFailed(e))?
+                    .with_device(device.clone());
                 tier_models.insert("blip".to_string(), blip_model);
             } else {
                 warn!("BLIP model not found: {}", blip_path.display());
+                missing.push(blip_model_name.clone());
             }
         }
         
+        if tier_models.is_empty() {
+            warn!("No vision models available for tier {:?}; images cannot be tagged", tier);
+        } else if !missing.is_empty() {
+            warn!("Vision models for tier {:?} partially loaded, missing: {}", tier, missing.join(", "));
+        } else {
+            info!("Successfully loaded vision models for tier {:?}", tier);
+        }
+        
         // Store models
         {
             let mut models = self.models.lock().unwrap();
             models.insert(tier.clone(), tier_models);
         }
+        self.missing_models.lock().unwrap().insert(tier, missing);
         
-        info!("Successfully loaded vision models for tier {:?}", tier);
         Ok(())
     }
     
     /// Tag image with current tier models
+    /// 
+    /// With an embedding cache configured, images whose content hash was
+    /// already processed by the current tier skip inference entirely.
     pub async fn tag_image<P: AsRef<Path>>(&self, image_path: P) -> DamResult<TaggingResult> {
         let path = image_path.as_ref();
         debug!("Tagging image: {}", path.display());
         
         let start_time = std::time::Instant::now();
         
-        // Load image
-        let image = image::open(path)
-            .map_err(|e| ProcessError::ImageLoadFailed(format!("Failed to load image: {}", e)))?;
+        let cache_entry = match &self.embedding_cache {
+            Some(cache) => {
+                let content_hash = ingest::compute_file_hash(path).await?;
+                let tier = self.current_tier();
+                
+                // Cache failures only cost a recomputation
+                match cache.get(&content_hash, &tier) {
+                    Ok(Some(cached)) => return self.result_from_cache(cached, tier, start_time),
+                    Ok(None) => {}
+                    Err(e) => warn!("Embedding cache lookup failed for {}: {}", path.display(), e),
+                }
+                Some((cache, content_hash))
+            }
+            None => None,
+        };
+        
+        // Decoding is CPU-bound too, so it stays off the async runtime
+        let owned_path = path.to_path_buf();
+        let image = tokio::task::spawn_blocking(move || open_image(&owned_path))
+            .await
+            .map_err(|e| ProcessError::ImageLoadFailed(format!("Image decode task failed: {}", e)))??;
         
         // Tag the image
-        self.tag_image_data(&image).await
+        let result = self.tag_image_data(&image).await?;
+        
+        if let Some((cache, content_hash)) = cache_entry {
+            if !result.embedding.is_empty() {
+                let inference = CachedInference {
+                    embedding: result.embedding.clone(),
+                    caption: result.caption.clone(),
+                };
+                if let Err(e) = cache.insert(&content_hash, &result.tier, inference) {
+                    warn!("Failed to cache embedding for {}: {}", path.display(), e);
+                }
+            }
+        }
+        
+        Ok(result)
+    }
+    
+    /// Rebuild a tagging result from cached inference outputs
+    fn result_from_cache(&self, cached: CachedInference, tier: ModelTier, start_time: std::time::Instant) -> DamResult<TaggingResult> {
+        let config = {
+            let registry = self.registry.lock().unwrap();
+            registry.get_config(&tier)
+                .ok_or_else(|| ProcessError::ModelNotFound(format!("No config for tier: {:?}", tier)))?
+                .clone()
+        };
+        
+        Ok(TaggingResult {
+            tags: self.generate_tags_from_features(&cached.embedding, &config),
+            caption: cached.caption,
+            embedding: cached.embedding,
+            processing_time_ms: start_time.elapsed().as_millis() as u64,
+            tier,
+        })
     }
     
     /// Tag image from loaded image data
+    /// 
+    /// Runs whichever of the tier's models are loaded: without CLIP there
+    /// are no tags or embedding, without BLIP no caption. Fails only when
+    /// no vision model is loaded at all.
     pub async fn tag_image_data(&self, image: &DynamicImage) -> DamResult<TaggingResult> {
         let start_time = std::time::Instant::now();
         
@@ -303,47 +579,35 @@ impl TaggingService {
                 .clone()
         };
         
-        // Get models for current tier - check if models are loaded first
-        let has_models = {
-            let models_guard = self.models.lock().unwrap();
-            models_guard.contains_key(&tier)
-        };
-        
-        if !has_models {
-            return Err(ProcessError::ModelNotLoaded(format!("Models not loaded for tier: {:?}", tier)).into());
+        // Look up and clone under one lock; the tier may be unloaded meanwhile
+        let models = self.models.lock().unwrap().get(&tier).cloned()
+            .ok_or_else(|| ProcessError::ModelNotLoaded(format!("Models not loaded for tier: {:?}", tier)))?;
+        if models.is_empty() {
+            return Err(ProcessError::ModelNotLoaded(format!("No vision models available for tier: {:?}", tier)).into());
         }
         
-        let models = self.models.lock().unwrap().get(&tier).unwrap().clone();
-
+        // Preprocessing, forward passes and decoding are CPU/GPU-bound, so
+        // they run on the blocking pool instead of stalling the async runtime
+        let image = image.clone();
+        let options = self.caption_options;
+        let caption_tier = tier.clone();
+        let (clip_features, caption) = tokio::task::spawn_blocking(move || {
+            let clip_features = models.get("clip").map(|model| run_inference(model, &image)).transpose()?;
+            let caption = models.get("blip")
+                .map(|model| caption_with(model, &image, &caption_tier, &options))
+                .transpose()?;
+            Ok::<_, ProcessError>((clip_features, caption))
+        })
+        .await
+        .map_err(|e| ProcessError::InferenceFailed(format!("Inference task failed: {}", e)))??;
+        
         let mut tags = Vec::new();
-        let mut caption = None;
         let mut embedding = Vec::new();
         
-        // Run CLIP inference for tagging and embeddings
-        if let Some(clip_model) = models.get("clip") {
-            let tensor = clip_model.preprocess_image(image)
-                .map_err(|e| ProcessError::ImageProcessingFailed(e))?;
-            
-            let features = clip_model.inference(&tensor)
-                .map_err(|e| ProcessError::InferenceFailed(e))?;
-            
-            // Use features as embedding
-            embedding = features.clone();
-            
-            // Generate tags using zero-shot classification
+        // CLIP features are the embedding and drive zero-shot tagging
+        if let Some(features) = clip_features {
             tags = self.generate_tags_from_features(&features, &config);
-        }
-        
-        // Run BLIP inference for captioning
-        if let Some(blip_model) = models.get("blip") {
-            let tensor = blip_model.preprocess_image(image)
-                .map_err(|e| ProcessError::ImageProcessingFailed(e))?;
-            
-            let _features = blip_model.inference(&tensor)
-                .map_err(|e| ProcessError::InferenceFailed(e))?;
-            
-            // Generate caption (placeholder implementation)
-            caption = Some(self.generate_caption_from_features(&_features, &config));
+            embedding = features;
         }
         
         let processing_time = start_time.elapsed().as_millis() as u64;
@@ -357,23 +621,155 @@ impl TaggingService {
         })
     }
     
+    /// Caption an image with the current tier's BLIP model
+    /// 
+    /// Returns None when the tier has no BLIP model loaded, e.g. only CLIP
+    /// was found on disk, and fails like `tag_image_data` when no vision
+    /// model is loaded at all. Until BLIP's text decoder is wired up the
+    /// caption is the tier's placeholder caption. Greedy decoding gives the
+    /// same caption for the same image and options every time.
+    pub async fn caption_image<P: AsRef<Path>>(&self, image_path: P, options: CaptionOptions) -> DamResult<Option<String>> {
+        let path = image_path.as_ref();
+        let tier = self.current_tier();
+        let models = self.models.lock().unwrap().get(&tier).cloned()
+            .filter(|models| !models.is_empty())
+            .ok_or_else(|| ProcessError::ModelNotLoaded(format!("Models not loaded for tier: {:?}", tier)))?;
+        let Some(blip) = models.get("blip").cloned() else {
+            debug!("No BLIP model loaded for tier {:?}, not captioning {}", tier, path.display());
+            return Ok(None);
+        };
+        
+        let owned_path = path.to_path_buf();
+        let caption = tokio::task::spawn_blocking(move || {
+            let image = open_image(&owned_path)?;
+            caption_with(&blip, &image, &tier, &options)
+        })
+        .await
+        .map_err(|e| ProcessError::InferenceFailed(format!("Caption task failed: {}", e)))??;
+        
+        Ok(Some(caption))
+    }
+    
+    /// Compute the visual embedding of an uploaded query image
+    /// 
+    /// Used for "find images like this one" searches. Oversized or
+    /// undecodable uploads are rejected, and `ModelNotLoaded` is returned
+    /// when no vision model can produce an embedding.
+    pub async fn embed_query_image(&self, data: &[u8]) -> DamResult<Vec<f32>> {
+        if data.len() > MAX_QUERY_IMAGE_BYTES {
+            return Err(ProcessError::ImageLoadFailed(format!(
+                "Query image is {} bytes, limit is {}", data.len(), MAX_QUERY_IMAGE_BYTES
+            )).into());
+        }
+        
+        let data = data.to_vec();
+        let image = tokio::task::spawn_blocking(move || decode_image(&data))
+            .await
+            .map_err(|e| ProcessError::ImageLoadFailed(format!("Image decode task failed: {}", e)))??;
+        
+        let result = self.tag_image_data(&image).await?;
+        if result.embedding.is_empty() {
+            return Err(ProcessError::ModelNotLoaded(format!(
+                "No CLIP model loaded for tier {:?}; visual embeddings unavailable", result.tier
+            )).into());
+        }
+        
+        Ok(result.embedding)
+    }
+    
     /// Set AI quality tier
     pub async fn set_tier(&self, tier: ModelTier) -> DamResult<()> {
-        {
+        self.set_tier_with_eviction(tier, false).await
+    }
+    
+    /// Set AI quality tier, optionally unloading the previous tier's models
+    /// 
+    /// The previous tier is only evicted once the new tier has loaded, so a
+    /// failed switch leaves the old models usable.
+    pub async fn set_tier_with_eviction(&self, tier: ModelTier, evict_previous: bool) -> DamResult<()> {
+        self.registry.lock().unwrap().check_tier(&tier)
+            .map_err(|e| ProcessError::InvalidTier(e))?;
+        
+        // Load models if not already loaded; the tier only changes once it has loaded
+        if !self.are_models_loaded(&tier) {
+            self.load_models(tier.clone()).await?;
+        }
+        
+        let previous = {
             let mut registry = self.registry.lock().unwrap();
+            let previous = registry.current_tier.clone();
             registry.set_tier(tier.clone())
                 .map_err(|e| ProcessError::InvalidTier(e))?;
-        }
+            previous
+        };
         
-        // Load models if not already loaded
-        if !self.are_models_loaded(&tier) {
-            self.load_models(tier.clone()).await?;
+        if evict_previous && previous != tier {
+            self.unload_models(&previous);
         }
         
         info!("Switched image tagging to tier: {:?}", tier);
         Ok(())
     }
     
+    /// Set AI quality tier, stepping down to a lower tier if its models fail to load
+    /// 
+    /// Tries `tier` first, then each lower available tier, and returns the
+    /// tier that was activated. A tier without any of its vision models on
+    /// disk counts as failed. If no tier works, the previous tier stays
+    /// selected and the last error is returned. Use `set_tier` to fail on
+    /// the requested tier instead.
+    pub async fn set_tier_with_fallback(&self, tier: ModelTier) -> DamResult<ModelTier> {
+        let (previous, candidates) = {
+            let registry = self.registry.lock().unwrap();
+            (registry.current_tier.clone(), registry.fallback_tiers(&tier))
+        };
+        
+        let mut last_error = None;
+        for candidate in candidates {
+            let error: schema::DamError = match self.set_tier(candidate.clone()).await {
+                Ok(()) if matches!(self.model_status(&candidate), ModelStatus::Failed { .. }) => {
+                    self.unload_models(&candidate);
+                    ProcessError::ModelNotFound(format!("No vision models found for tier: {:?}", candidate)).into()
+                }
+                Ok(()) => {
+                    if candidate != tier {
+                        warn!("Vision models for tier {:?} unavailable, downgraded to {:?}", tier, candidate);
+                    }
+                    return Ok(candidate);
+                }
+                Err(e) => e,
+            };
+            warn!("Could not activate image tagging tier {:?}: {}", candidate, error);
+            last_error = Some(error);
+        }
+        
+        let _ = self.registry.lock().unwrap().set_tier(previous);
+        Err(last_error.expect("fallback always tries the requested tier"))
+    }
+    
+    /// Drop the loaded models for a tier
+    /// 
+    /// Returns `true` if models were loaded. Tagging with this tier fails
+    /// with `ModelNotLoaded` until it is loaded again.
+    pub fn unload_models(&self, tier: &ModelTier) -> bool {
+        let removed = self.models.lock().unwrap().remove(tier);
+        self.missing_models.lock().unwrap().remove(tier);
+        if removed.is_some() {
+            info!("Unloaded vision models for tier {:?}", tier);
+        }
+        removed.is_some()
+    }
+    
+    /// Drop the loaded models for every tier
+    pub fn unload_all(&self) {
+        let mut models = self.models.lock().unwrap();
+        if !models.is_empty() {
+            info!("Unloading vision models for {} tiers", models.len());
+            models.clear();
+        }
+        self.missing_models.lock().unwrap().clear();
+    }
+    
     /// Get current tier
     pub fn current_tier(&self) -> ModelTier {
         let registry = self.registry.lock().unwrap();
@@ -387,20 +783,49 @@ impl TaggingService {
     }
     
     /// Get model status for tier
+    /// 
+    /// A tier missing some of its models is `Degraded`; one with none of
+    /// them on disk is `Failed`.
     pub fn model_status(&self, tier: &ModelTier) -> ModelStatus {
-        if self.are_models_loaded(tier) {
-            ModelStatus::Loaded { memory_usage_mb: 500 } // Placeholder value
+        let models = self.models.lock().unwrap();
+        let Some(tier_models) = models.get(tier) else {
+            return ModelStatus::NotLoaded;
+        };
+        
+        let missing = self.missing_models.lock().unwrap().get(tier).cloned().unwrap_or_default();
+        let memory_usage_mb = bytes_to_mb(tier_models.values().map(|m| m.memory_usage_bytes()).sum());
+        let device = tier_models.values().next().map(|m| m.compute_device());
+        if tier_models.is_empty() {
+            ModelStatus::Failed {
+                error: format!("No vision models found, missing: {}", missing.join(", ")),
+            }
+        } else if !missing.is_empty() {
+            ModelStatus::Degraded { memory_usage_mb, missing, device }
         } else {
-            ModelStatus::NotLoaded
+            ModelStatus::Loaded { memory_usage_mb, device }
         }
     }
     
+    /// Memory held by all loaded vision models, across tiers
+    pub fn memory_usage_mb(&self) -> u32 {
+        let models = self.models.lock().unwrap();
+        bytes_to_mb(models.values()
+            .flat_map(|tier_models| tier_models.values())
+            .map(|m| m.memory_usage_bytes())
+            .sum())
+    }
+    
     /// Update system capabilities
     pub fn update_system_info(&self, vram_mb: u32, cuda_available: bool) {
         let mut registry = self.registry.lock().unwrap();
         registry.update_system_info(vram_mb, cuda_available);
     }
     
+    /// VRAM reported by the last `update_system_info`, in MB
+    pub fn available_vram_mb(&self) -> u32 {
+        self.registry.lock().unwrap().available_vram_mb
+    }
+    
     /// Get available tiers for current system
     pub fn available_tiers(&self) -> Vec<ModelTier> {
         let registry = self.registry.lock().unwrap();
@@ -443,18 +868,6 @@ impl TaggingService {
         tags
     }
     
-    /// Generate caption from BLIP features
-    fn generate_caption_from_features(&self, _features: &[f32], config: &schema::TierModelConfig) -> String {
-        // Placeholder implementation
-        // In real implementation, this would use BLIP's text decoder
-        
-        match config.tier {
-            ModelTier::Low => "An image".to_string(),
-            ModelTier::Medium => "A digital artwork with various elements".to_string(),
-            ModelTier::High => "A detailed digital artwork featuring intricate design elements with vibrant colors and professional composition".to_string(),
-        }
-    }
-    
     /// Create default tag vocabulary for zero-shot classification
     fn create_default_vocabulary() -> Vec<String> {
         vec![
@@ -501,6 +914,16 @@ impl Default for TaggingService {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tempfile::TempDir;
+    
+    /// Temporary models directory holding a small fake weights file per model
+    fn models_dir(models: &[&str]) -> TempDir {
+        let dir = TempDir::new().unwrap();
+        for model in models {
+            std::fs::write(dir.path().join(format!("{}.safetensors", model)), vec![0u8; 1024]).unwrap();
+        }
+        dir
+    }
     
     #[tokio::test]
     async fn test_tagging_service_creation() {
@@ -515,6 +938,189 @@ mod tests {
         assert_eq!(current, ModelTier::Medium); // Default tier
     }
     
+    #[tokio::test]
+    async fn test_model_memory_usage() {
+        let dir = models_dir(&[]);
+        std::fs::write(dir.path().join("clip-vit-b-32.safetensors"), vec![0u8; 3 * 1024 * 1024]).unwrap();
+        
+        let service = TaggingService::with_models_dir(dir.path()).unwrap();
+        assert!(matches!(service.model_status(&ModelTier::Low), ModelStatus::NotLoaded));
+        assert_eq!(service.memory_usage_mb(), 0);
+        
+        service.load_models(ModelTier::Low).await.unwrap();
+        assert!(matches!(service.model_status(&ModelTier::Low), ModelStatus::Loaded { memory_usage_mb: 3, .. }));
+        assert_eq!(service.memory_usage_mb(), 3);
+    }
+    
+    #[tokio::test]
+    async fn test_tag_image_uses_embedding_cache() {
+        let dir = models_dir(&[]);
+        let image_path = dir.path().join("pixel.png");
+        DynamicImage::new_rgb8(4, 4).save(&image_path).unwrap();
+        
+        let cache = Arc::new(EmbeddingCache::open(dir.path().join("cache"), 10).unwrap());
+        let service = TaggingService::with_models_dir(dir.path()).unwrap().with_embedding_cache(cache.clone());
+        
+        // Without models only a cache hit can produce a result
+        assert!(service.tag_image(&image_path).await.is_err());
+        
+        let hash = ingest::compute_file_hash(&image_path).await.unwrap();
+        let inference = CachedInference { embedding: vec![0.5; 512], caption: Some("a black square".to_string()) };
+        cache.insert(&hash, &service.current_tier(), inference).unwrap();
+        
+        let result = service.tag_image(&image_path).await.unwrap();
+        assert_eq!(result.embedding, vec![0.5; 512]);
+        assert_eq!(result.caption.as_deref(), Some("a black square"));
+        
+        service.clear_embedding_cache().unwrap();
+        assert!(service.tag_image(&image_path).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_unload_and_reload() {
+        let dir = models_dir(&["clip-vit-b-32"]);
+        
+        let service = TaggingService::with_models_dir(dir.path()).unwrap();
+        service.update_system_info(16384, true);
+        
+        service.set_tier(ModelTier::Low).await.unwrap();
+        service.set_tier_with_eviction(ModelTier::Medium, true).await.unwrap();
+        assert!(!service.are_models_loaded(&ModelTier::Low));
+        assert!(service.are_models_loaded(&ModelTier::Medium));
+        
+        // Switching back reloads the evicted tier
+        service.set_tier(ModelTier::Low).await.unwrap();
+        assert!(matches!(service.model_status(&ModelTier::Low), ModelStatus::Loaded { .. }));
+        
+        assert!(service.unload_models(&ModelTier::Low));
+        assert!(!service.unload_models(&ModelTier::Low));
+        service.unload_all();
+        assert!(matches!(service.model_status(&ModelTier::Medium), ModelStatus::NotLoaded));
+    }
+    
+    #[tokio::test]
+    async fn test_partially_present_models() {
+        let dir = models_dir(&["clip-vit-l-14"]);
+        
+        let service = TaggingService::with_models_dir(dir.path()).unwrap();
+        service.update_system_info(16384, true);
+        service.set_tier(ModelTier::Medium).await.unwrap();
+        
+        // CLIP without BLIP still tags, just without a caption
+        let status = service.model_status(&ModelTier::Medium);
+        assert!(matches!(status, ModelStatus::Degraded { ref missing, .. } if missing == &["blip-base"]));
+        let result = service.tag_image_data(&DynamicImage::new_rgb8(4, 4)).await.unwrap();
+        assert!(!result.tags.is_empty());
+        assert!(result.caption.is_none());
+        
+        // Captioning needs BLIP, so CLIP alone gives no caption
+        let image_path = dir.path().join("pixel.png");
+        DynamicImage::new_rgb8(4, 4).save(&image_path).unwrap();
+        assert_eq!(service.caption_image(&image_path, CaptionOptions::greedy(10)).await.unwrap(), None);
+        
+        std::fs::write(dir.path().join("blip-base.safetensors"), vec![0u8; 1024]).unwrap();
+        service.load_models(ModelTier::Medium).await.unwrap();
+        // With BLIP the tier's placeholder caption is decoded to the requested length
+        let caption = service.caption_image(&image_path, CaptionOptions::greedy(3)).await.unwrap();
+        assert_eq!(caption.as_deref(), Some("A digital artwork"));
+        let result = service.tag_image_data(&DynamicImage::new_rgb8(4, 4)).await.unwrap();
+        assert_eq!(result.caption.as_deref(), Some(captioning::placeholder_caption(&ModelTier::Medium)));
+        assert!(!result.tags.is_empty());
+        
+        // No vision model at all is an error
+        service.set_tier(ModelTier::High).await.unwrap();
+        assert!(matches!(service.model_status(&ModelTier::High), ModelStatus::Failed { .. }));
+        assert!(service.tag_image_data(&DynamicImage::new_rgb8(4, 4)).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_tier_fallback() {
+        let dir = models_dir(&[]);
+        let service = TaggingService::with_models_dir(dir.path()).unwrap();
+        service.update_system_info(24576, true);
+        
+        // Nothing on disk: no tier works and the previous one stays selected
+        assert!(service.set_tier_with_fallback(ModelTier::High).await.is_err());
+        assert_eq!(service.current_tier(), ModelTier::Medium);
+        assert!(!service.are_models_loaded(&ModelTier::High));
+        
+        // Only the medium tier's CLIP is present, so High steps down to Medium
+        std::fs::write(dir.path().join("clip-vit-l-14.safetensors"), vec![0u8; 1024]).unwrap();
+        assert_eq!(service.set_tier_with_fallback(ModelTier::High).await.unwrap(), ModelTier::Medium);
+        assert_eq!(service.current_tier(), ModelTier::Medium);
+        
+        // The strict switch keeps the requested tier even without its models
+        service.set_tier(ModelTier::High).await.unwrap();
+        assert!(matches!(service.model_status(&ModelTier::High), ModelStatus::Failed { .. }));
+    }
+    
+    #[tokio::test]
+    async fn test_embed_query_image_validation() {
+        let service = TaggingService::new().unwrap();
+        
+        let oversized = vec![0u8; MAX_QUERY_IMAGE_BYTES + 1];
+        let err = service.embed_query_image(&oversized).await.unwrap_err();
+        assert!(err.to_string().contains("limit"));
+        
+        let err = service.embed_query_image(b"not an image").await.unwrap_err();
+        assert!(err.to_string().contains("decodable"));
+        
+        let mut png = Vec::new();
+        DynamicImage::new_rgb8(8, 8)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageOutputFormat::Png)
+            .unwrap();
+        let err = service.embed_query_image(&png).await.unwrap_err();
+        assert!(err.to_string().contains("not loaded"));
+    }
+    
+    #[tokio::test]
+    async fn test_device_selection() {
+        // Test builds have no CUDA or Metal support, so every choice ends on the CPU
+        assert!(select_device(ComputeDevice::Cpu, true).is_cpu());
+        assert!(select_device(ComputeDevice::Auto, false).is_cpu());
+        assert!(select_device(ComputeDevice::Cuda, true).is_cpu());
+        
+        let dir = models_dir(&["clip-vit-b-32"]);
+        
+        let service = TaggingService::with_models_dir(dir.path()).unwrap().with_device(ComputeDevice::Cuda);
+        service.load_models(ModelTier::Low).await.unwrap();
+        assert!(matches!(
+            service.model_status(&ModelTier::Low),
+            ModelStatus::Loaded { device: Some(ComputeDevice::Cpu), .. }
+        ));
+        assert!(service.tag_image_data(&DynamicImage::new_rgb8(4, 4)).await.is_ok());
+    }
+    
+    #[tokio::test]
+    async fn test_concurrent_tagging_does_not_block_runtime() {
+        let dir = models_dir(&["clip-vit-b-32"]);
+        
+        let service = TaggingService::with_models_dir(dir.path()).unwrap();
+        service.update_system_info(16384, true);
+        service.set_tier(ModelTier::Low).await.unwrap();
+        
+        // The test runtime is single-threaded: the ticker only advances if
+        // inference yields the executor thread while it runs
+        let ticks = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let ticker = tokio::spawn({
+            let ticks = ticks.clone();
+            async move {
+                loop {
+                    tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+                    ticks.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                }
+            }
+        });
+        
+        let large = DynamicImage::new_rgb8(2048, 2048);
+        let (first, second) = tokio::join!(service.tag_image_data(&large), service.tag_image_data(&large));
+        ticker.abort();
+        
+        assert_eq!(first.unwrap().embedding.len(), 512);
+        assert_eq!(second.unwrap().embedding.len(), 512);
+        assert!(ticks.load(std::sync::atomic::Ordering::SeqCst) > 0);
+    }
+    
     #[test]
     fn test_preprocessing_configs() {
         let clip_config = ImagePreprocessConfig::clip();
@@ -5,7 +5,8 @@
 
 use schema::{DamResult, ModelTier, ModelRegistry, ModelStatus};
 use crate::error::ProcessError;
-use crate::whisper_ffi::{WhisperContext, TranscriptResult, resample_to_16khz};
+use crate::bytes_to_mb;
+use crate::whisper_ffi::{WhisperContext, TranscriptResult, ResampleQuality, default_thread_count, resample_to_16khz};
 use std::path::{Path, PathBuf};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
@@ -20,6 +21,12 @@ pub struct TranscriptionService {
     contexts: Arc<Mutex<HashMap<ModelTier, WhisperContext>>>,
     /// Model storage directory
     models_dir: PathBuf,
+    /// How input audio is converted to whisper's 16kHz
+    resample_quality: Mutex<ResampleQuality>,
+    /// Worker threads per transcription
+    threads: usize,
+    /// Whether segments carry per-token timings
+    token_timings: bool,
 }
 
 impl TranscriptionService {
@@ -33,6 +40,9 @@ impl TranscriptionService {
             registry: Arc::new(Mutex::new(ModelRegistry::new())),
             contexts: Arc::new(Mutex::new(HashMap::new())),
             models_dir,
+            resample_quality: Mutex::new(ResampleQuality::default()),
+            threads: default_thread_count(),
+            token_timings: false,
         })
     }
     
@@ -45,9 +55,56 @@ impl TranscriptionService {
             registry: Arc::new(Mutex::new(ModelRegistry::new())),
             contexts: Arc::new(Mutex::new(HashMap::new())),
             models_dir,
+            resample_quality: Mutex::new(ResampleQuality::default()),
+            threads: default_thread_count(),
+            token_timings: false,
         })
     }
     
+    /// Resample input audio with another quality/speed trade-off
+    pub fn with_resample_quality(mut self, quality: ResampleQuality) -> Self {
+        self.resample_quality = Mutex::new(quality);
+        self
+    }
+    
+    /// Change the resample quality of later transcriptions
+    pub fn set_resample_quality(&self, quality: ResampleQuality) {
+        *self.resample_quality.lock().unwrap() = quality;
+    }
+    
+    pub fn resample_quality(&self) -> ResampleQuality {
+        *self.resample_quality.lock().unwrap()
+    }
+    
+    /// Run whisper with `threads` worker threads (at least one)
+    ///
+    /// Defaults to half the cores, capped at eight. More threads speed up a
+    /// single transcription only up to a point and take cores away from
+    /// everything else running; fewer leave room for parallel ingestion.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+    
+    /// Worker threads used per transcription
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+    
+    /// Include per-token timings in transcript segments
+    ///
+    /// Off by default since it multiplies the size of each result; turn it
+    /// on for word highlighting or word-level search.
+    pub fn with_token_timings(mut self, enabled: bool) -> Self {
+        self.token_timings = enabled;
+        self
+    }
+    
+    /// Whether transcripts include per-token timings
+    pub fn token_timings(&self) -> bool {
+        self.token_timings
+    }
+    
     /// Load model for specific tier
     pub async fn load_model(&self, tier: ModelTier) -> DamResult<()> {
         let config = {
@@ -109,30 +166,23 @@ impl TranscriptionService {
             registry.current_tier.clone()
         };
         
-        let context = {
-            let contexts = self.contexts.lock().unwrap();
-            if !contexts.contains_key(&tier) {
-                return Err(ProcessError::ModelNotLoaded(format!("Model not loaded for tier: {:?}", tier)).into());
-            }
-            // We can't return a reference here due to lifetime issues, so we'll need to handle this differently
-            // For now, let's check if the model is loaded and then access it in the transcription
-        };
-        
-        // Resample to 16kHz if needed
-        let resampled = if sample_rate != 16000 {
-            debug!("Resampling from {}Hz to 16kHz", sample_rate);
-            resample_to_16khz(samples, sample_rate)
-        } else {
-            samples.to_vec()
-        };
-        
-        // Perform transcription
+        // One lock for the lookup and the transcription, so the context
+        // cannot be unloaded in between
         let result = {
             let contexts = self.contexts.lock().unwrap();
             let context = contexts.get(&tier)
                 .ok_or_else(|| ProcessError::ModelNotLoaded(format!("Model not loaded for tier: {:?}", tier)))?;
             
-            context.transcribe(&resampled, language)
+            // Resample to 16kHz if needed
+            let resampled = if sample_rate != 16000 {
+                let quality = self.resample_quality();
+                debug!("Resampling from {}Hz to 16kHz ({:?})", sample_rate, quality);
+                resample_to_16khz(samples, sample_rate, quality)
+            } else {
+                samples.to_vec()
+            };
+            
+            context.transcribe(&resampled, language, self.threads, self.token_timings)
                 .map_err(|e| ProcessError::TranscriptionFailed(e))?
         };
         
@@ -152,21 +202,98 @@ impl TranscriptionService {
     
     /// Set AI quality tier
     pub async fn set_tier(&self, tier: ModelTier) -> DamResult<()> {
-        {
+        self.set_tier_with_eviction(tier, false).await
+    }
+    
+    /// Set AI quality tier, optionally unloading the previous tier's model
+    /// 
+    /// The previous tier is only evicted once the new model has loaded, so a
+    /// failed switch leaves the old context usable.
+    pub async fn set_tier_with_eviction(&self, tier: ModelTier, evict_previous: bool) -> DamResult<()> {
+        self.registry.lock().unwrap().check_tier(&tier)
+            .map_err(|e| ProcessError::InvalidTier(e))?;
+        
+        // Load model if not already loaded; the tier only changes once it has loaded
+        if !self.is_model_loaded(&tier) {
+            self.load_model(tier.clone()).await?;
+        }
+        
+        let previous = {
             let mut registry = self.registry.lock().unwrap();
+            let previous = registry.current_tier.clone();
             registry.set_tier(tier.clone())
                 .map_err(|e| ProcessError::InvalidTier(e))?;
-        }
+            previous
+        };
         
-        // Load model if not already loaded
-        if !self.is_model_loaded(&tier) {
-            self.load_model(tier.clone()).await?;
+        if evict_previous && previous != tier {
+            self.unload_models(&previous);
         }
         
         info!("Switched transcription to tier: {:?}", tier);
         Ok(())
     }
     
+    /// Set AI quality tier, stepping down to a lower tier if its model fails to load
+    /// 
+    /// Tries `tier` first, then each lower available tier, and returns the
+    /// tier that was activated. If none loads, the previous tier stays
+    /// selected and the last load error is returned. Use `set_tier` to
+    /// fail on the requested tier instead.
+    pub async fn set_tier_with_fallback(&self, tier: ModelTier) -> DamResult<ModelTier> {
+        let (previous, candidates) = {
+            let registry = self.registry.lock().unwrap();
+            (registry.current_tier.clone(), registry.fallback_tiers(&tier))
+        };
+        
+        let mut last_error = None;
+        for candidate in candidates {
+            match self.set_tier(candidate.clone()).await {
+                Ok(()) => {
+                    if candidate != tier {
+                        warn!("Transcription model for tier {:?} unavailable, downgraded to {:?}", tier, candidate);
+                    }
+                    return Ok(candidate);
+                }
+                Err(e) => {
+                    warn!("Could not activate transcription tier {:?}: {}", candidate, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        
+        let _ = self.registry.lock().unwrap().set_tier(previous);
+        Err(last_error.expect("fallback always tries the requested tier"))
+    }
+    
+    /// Free the whisper context for a tier
+    /// 
+    /// The context is released through `WhisperContext`'s `Drop`, which
+    /// calls `whisper_free`. Returns `true` if a model was loaded.
+    pub fn unload_models(&self, tier: &ModelTier) -> bool {
+        // Remove under the lock, drop outside it so freeing cannot block transcriptions
+        let removed = self.contexts.lock().unwrap().remove(tier);
+        let was_loaded = removed.is_some();
+        drop(removed);
+        
+        if was_loaded {
+            info!("Unloaded whisper model for tier {:?}", tier);
+        }
+        was_loaded
+    }
+    
+    /// Free the whisper contexts of every tier
+    pub fn unload_all(&self) {
+        let contexts: Vec<WhisperContext> = self.contexts.lock().unwrap()
+            .drain()
+            .map(|(_, context)| context)
+            .collect();
+        
+        if !contexts.is_empty() {
+            info!("Unloading whisper models for {} tiers", contexts.len());
+        }
+    }
+    
     /// Get current tier
     pub fn current_tier(&self) -> ModelTier {
         let registry = self.registry.lock().unwrap();
@@ -181,13 +308,22 @@ impl TranscriptionService {
     
     /// Get model status for tier
     pub fn model_status(&self, tier: &ModelTier) -> ModelStatus {
-        if self.is_model_loaded(tier) {
-            ModelStatus::Loaded { memory_usage_mb: 100 } // Placeholder value
-        } else {
-            ModelStatus::NotLoaded
+        let contexts = self.contexts.lock().unwrap();
+        match contexts.get(tier) {
+            Some(context) => ModelStatus::Loaded {
+                memory_usage_mb: bytes_to_mb(context.memory_usage_bytes()),
+                device: None,
+            },
+            None => ModelStatus::NotLoaded,
         }
     }
     
+    /// Memory held by all loaded whisper models, across tiers
+    pub fn memory_usage_mb(&self) -> u32 {
+        let contexts = self.contexts.lock().unwrap();
+        bytes_to_mb(contexts.values().map(|c| c.memory_usage_bytes()).sum())
+    }
+    
     /// Update system capabilities (VRAM, CUDA)
     pub fn update_system_info(&self, vram_mb: u32, cuda_available: bool) {
         let mut registry = self.registry.lock().unwrap();
@@ -327,6 +463,39 @@ mod tests {
         assert_eq!(current, ModelTier::Medium); // Default tier
     }
     
+    #[test]
+    fn test_thread_count() {
+        let service = TranscriptionService::new().unwrap();
+        assert_eq!(service.threads(), default_thread_count());
+        
+        assert_eq!(service.with_threads(12).threads(), 12);
+        let service = TranscriptionService::new().unwrap().with_threads(0);
+        assert_eq!(service.threads(), 1);
+    }
+    
+    #[test]
+    fn test_token_timings_flag() {
+        let service = TranscriptionService::new().unwrap();
+        assert!(!service.token_timings());
+        assert!(service.with_token_timings(true).token_timings());
+    }
+    
+    #[tokio::test]
+    async fn test_tier_fallback() {
+        let dir = std::env::temp_dir().join(format!("dam-whisper-fallback-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let service = TranscriptionService::with_models_dir(&dir).unwrap();
+        service.update_system_info(24576, true);
+        
+        // No model on disk: every tier fails and the previous one stays selected
+        assert!(service.set_tier_with_fallback(ModelTier::High).await.is_err());
+        assert_eq!(service.current_tier(), ModelTier::Medium);
+        assert!(service.set_tier_with_eviction(ModelTier::Low, true).await.is_err());
+        assert_eq!(service.current_tier(), ModelTier::Medium);
+        
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    
     #[test]
     fn test_available_tiers() {
         let service = TranscriptionService::new().unwrap();
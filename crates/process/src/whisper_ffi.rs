@@ -2,12 +2,40 @@
 //! 
 //! Provides Rust bindings to the whisper.cpp library for offline
 //! speech-to-text transcription.
+//!
+//! The structs passed by value are written by hand against whisper.cpp
+//! [`WHISPER_CPP_VERSION`]; link against exactly that release, since a
+//! different struct layout is undefined behavior rather than an error.
 
 use std::ffi::{CStr, CString};
 use std::os::raw::{c_char, c_float, c_int, c_void};
 use std::path::Path;
 use tracing::{debug, error, warn};
 
+pub use schema::ResampleQuality;
+
+/// whisper.cpp release these bindings match
+pub const WHISPER_CPP_VERSION: &str = "1.5.5";
+
+/// Most threads used by default, however many cores there are
+///
+/// whisper.cpp stops getting faster somewhere around eight threads; past
+/// that the extra threads mostly contend for memory bandwidth.
+pub const MAX_DEFAULT_THREADS: usize = 8;
+
+/// Default worker thread count: half the available cores, at most
+/// `MAX_DEFAULT_THREADS`
+///
+/// Using every core oversubscribes machines that also run ingestion,
+/// tagging and the UI, and on large servers it can make transcription
+/// itself slower. Leaving half the cores free keeps the rest responsive.
+pub fn default_thread_count() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get() / 2)
+        .unwrap_or(4)
+        .clamp(1, MAX_DEFAULT_THREADS)
+}
+
 // FFI declarations for whisper.cpp
 #[link(name = "whisper")]
 extern "C" {
@@ -24,14 +52,35 @@ extern "C" {
     fn whisper_full_get_segment_text(ctx: *mut c_void, i_segment: c_int) -> *const c_char;
     fn whisper_full_get_segment_t0(ctx: *mut c_void, i_segment: c_int) -> i64;
     fn whisper_full_get_segment_t1(ctx: *mut c_void, i_segment: c_int) -> i64;
+    fn whisper_full_n_tokens(ctx: *mut c_void, i_segment: c_int) -> c_int;
+    fn whisper_full_get_token_text(ctx: *mut c_void, i_segment: c_int, i_token: c_int) -> *const c_char;
+    fn whisper_full_get_token_data(ctx: *mut c_void, i_segment: c_int, i_token: c_int) -> WhisperTokenData;
+    fn whisper_full_lang_id(ctx: *mut c_void) -> c_int;
+    fn whisper_lang_str(id: c_int) -> *const c_char;
+    fn whisper_token_eot(ctx: *mut c_void) -> c_int;
     fn whisper_print_system_info() -> *const c_char;
 }
 
+/// whisper.cpp reports times in 10 ms units
+const WHISPER_TIME_UNIT_MS: i64 = 10;
+
 // Whisper strategy constants
 const WHISPER_SAMPLING_GREEDY: c_int = 0;
 const WHISPER_SAMPLING_BEAM_SEARCH: c_int = 1;
 
-// Simplified whisper parameters struct
+// Callbacks of whisper_full_params; contexts, states and token data are opaque here
+type WhisperNewSegmentCallback = Option<unsafe extern "C" fn(*mut c_void, *mut c_void, c_int, *mut c_void)>;
+type WhisperProgressCallback = Option<unsafe extern "C" fn(*mut c_void, *mut c_void, c_int, *mut c_void)>;
+type WhisperEncoderBeginCallback = Option<unsafe extern "C" fn(*mut c_void, *mut c_void, *mut c_void) -> bool>;
+type WhisperAbortCallback = Option<unsafe extern "C" fn(*mut c_void) -> bool>;
+type WhisperLogitsFilterCallback = Option<
+    unsafe extern "C" fn(*mut c_void, *mut c_void, *const c_void, c_int, *mut c_float, *mut c_void),
+>;
+
+/// `whisper_full_params` of whisper.cpp `WHISPER_CPP_VERSION`, field by field
+///
+/// Returned from and passed to C by value, so every field is mirrored in
+/// order, including the ones never set here.
 #[repr(C)]
 #[derive(Clone, Copy)]
 pub struct WhisperFullParams {
@@ -42,6 +91,7 @@ pub struct WhisperFullParams {
     pub duration_ms: c_int,
     pub translate: bool,
     pub no_context: bool,
+    pub no_timestamps: bool,
     pub single_segment: bool,
     pub print_special: bool,
     pub print_progress: bool,
@@ -54,24 +104,97 @@ pub struct WhisperFullParams {
     pub split_on_word: bool,
     pub max_tokens: c_int,
     pub speed_up: bool,
+    pub debug_mode: bool,
     pub audio_ctx: c_int,
-    pub prompt_tokens: *mut c_int,
+    pub tdrz_enable: bool,
+    pub initial_prompt: *const c_char,
+    pub prompt_tokens: *const c_int,
     pub prompt_n_tokens: c_int,
     pub language: *const c_char,
+    /// Only detect the language: whisper_full returns before transcribing
     pub detect_language: bool,
     pub suppress_blank: bool,
     pub suppress_non_speech_tokens: bool,
     pub temperature: c_float,
     pub max_initial_ts: c_float,
     pub length_penalty: c_float,
+    pub temperature_inc: c_float,
+    pub entropy_thold: c_float,
+    pub logprob_thold: c_float,
+    pub no_speech_thold: c_float,
+    pub greedy_best_of: c_int,
+    pub beam_search_beam_size: c_int,
+    pub beam_search_patience: c_float,
+    pub new_segment_callback: WhisperNewSegmentCallback,
+    pub new_segment_callback_user_data: *mut c_void,
+    pub progress_callback: WhisperProgressCallback,
+    pub progress_callback_user_data: *mut c_void,
+    pub encoder_begin_callback: WhisperEncoderBeginCallback,
+    pub encoder_begin_callback_user_data: *mut c_void,
+    pub abort_callback: WhisperAbortCallback,
+    pub abort_callback_user_data: *mut c_void,
+    pub logits_filter_callback: WhisperLogitsFilterCallback,
+    pub logits_filter_callback_user_data: *mut c_void,
+    pub grammar_rules: *mut *const c_void,
+    pub n_grammar_rules: usize,
+    pub i_start_rule: usize,
+    pub grammar_penalty: c_float,
+}
+
+// sizeof(struct whisper_full_params) of whisper.cpp 1.5.5 on 64-bit targets;
+// the nested greedy and beam_search structs are flattened without changing it
+#[cfg(target_pointer_width = "64")]
+const _: () = assert!(std::mem::size_of::<WhisperFullParams>() == 256);
+
+// Per-token data returned by whisper_full_get_token_data, as laid out
+// in whisper.cpp `WHISPER_CPP_VERSION`; unread fields keep the layout
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[allow(dead_code)]
+struct WhisperTokenData {
+    id: c_int,
+    tid: c_int,
+    p: c_float,
+    plog: c_float,
+    pt: c_float,
+    ptsum: c_float,
+    t0: i64,
+    t1: i64,
+    t_dtw: i64,
+    vlen: c_float,
 }
 
+// Returned by value, so the size must match the C struct exactly
+const _: () = assert!(std::mem::size_of::<WhisperTokenData>() == 56);
+
 /// Transcript segment with timing information
 #[derive(Debug, Clone)]
 pub struct TranscriptSegment {
     pub text: String,
     pub start_time_ms: i64,
     pub end_time_ms: i64,
+    /// Timing of each text token; only filled when token timings are requested
+    pub tokens: Option<Vec<TokenTiming>>,
+}
+
+/// Timing of a single text token within a segment
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenTiming {
+    /// Token text, usually a word piece with its leading space
+    pub text: String,
+    pub start_time_ms: i64,
+    pub end_time_ms: i64,
+    /// Probability whisper assigned to the token
+    pub probability: f32,
+}
+
+/// Whether a token is one of whisper's special tokens
+///
+/// End-of-text and every id after it (start-of-transcript, language,
+/// task, no-timestamps and the timestamp tokens) are control tokens, not
+/// transcribed text.
+fn is_special_token(id: c_int, eot: c_int) -> bool {
+    id >= eot
 }
 
 /// Complete transcript result
@@ -87,6 +210,8 @@ pub struct TranscriptResult {
 pub struct WhisperContext {
     ctx: *mut c_void,
     model_path: String,
+    /// Size of the loaded model weights in bytes
+    model_size_bytes: u64,
 }
 
 impl WhisperContext {
@@ -98,6 +223,12 @@ impl WhisperContext {
         
         debug!("Loading whisper model from: {}", path_str);
         
+        // whisper.cpp keeps the full weight tensors resident, so the file
+        // size is a close measure of the context's memory footprint
+        let model_size_bytes = std::fs::metadata(model_path.as_ref())
+            .map(|m| m.len())
+            .unwrap_or(0);
+        
         unsafe {
             let ctx = whisper_init_from_file(c_path.as_ptr());
             if ctx.is_null() {
@@ -107,33 +238,47 @@ impl WhisperContext {
             Ok(Self {
                 ctx,
                 model_path: path_str.to_string(),
+                model_size_bytes,
             })
         }
     }
     
-    /// Transcribe audio samples
-    pub fn transcribe(&self, samples: &[f32], language: Option<&str>) -> Result<TranscriptResult, String> {
+    /// Memory held by the loaded model weights in bytes
+    pub fn memory_usage_bytes(&self) -> u64 {
+        self.model_size_bytes
+    }
+    
+    /// Transcribe audio samples using `n_threads` worker threads
+    ///
+    /// With `token_timings` each segment also lists the timing of its text
+    /// tokens, which makes the result considerably larger.
+    pub fn transcribe(
+        &self,
+        samples: &[f32],
+        language: Option<&str>,
+        n_threads: usize,
+        token_timings: bool,
+    ) -> Result<TranscriptResult, String> {
         let start_time = std::time::Instant::now();
         
+        // Invariant: params.language borrows from c_language, so c_language
+        // must stay bound until whisper_full has returned
+        let c_language = language_param(language)?;
+        let mut language = c_language.as_ref().map(|lang| lang.to_string_lossy().into_owned());
+        
         unsafe {
             // Get default parameters
             let mut params = whisper_full_default_params(WHISPER_SAMPLING_GREEDY);
             
             // Configure parameters
-            params.n_threads = std::thread::available_parallelism()
-                .map(|n| n.get() as c_int)
-                .unwrap_or(4);
+            params.n_threads = n_threads.clamp(1, c_int::MAX as usize) as c_int;
             params.translate = false;
-            params.language = if let Some(lang) = language {
-                let c_lang = CString::new(lang).unwrap();
-                c_lang.as_ptr()
-            } else {
-                std::ptr::null()
-            };
-            params.detect_language = language.is_none();
+            params.language = c_language.as_ref().map_or(std::ptr::null(), |lang| lang.as_ptr());
+            // A null language already auto-detects; detect_language would stop after detection
+            params.detect_language = false;
             params.print_progress = false;
             params.print_timestamps = true;
-            params.token_timestamps = true;
+            params.token_timestamps = token_timings;
             
             // Run transcription
             let result = whisper_full(
@@ -159,13 +304,15 @@ impl WhisperContext {
                 }
                 
                 let text = CStr::from_ptr(text_ptr).to_string_lossy().to_string();
-                let start_time = whisper_full_get_segment_t0(self.ctx, i);
-                let end_time = whisper_full_get_segment_t1(self.ctx, i);
+                let start_time = whisper_full_get_segment_t0(self.ctx, i) * WHISPER_TIME_UNIT_MS;
+                let end_time = whisper_full_get_segment_t1(self.ctx, i) * WHISPER_TIME_UNIT_MS;
+                let tokens = token_timings.then(|| self.segment_tokens(i));
                 
                 segments.push(TranscriptSegment {
                     text: text.clone(),
                     start_time_ms: start_time,
                     end_time_ms: end_time,
+                    tokens,
                 });
                 
                 if !full_text.is_empty() {
@@ -174,23 +321,75 @@ impl WhisperContext {
                 full_text.push_str(&text);
             }
             
+            // Keep whichever language whisper settled on when asked to detect it
+            if language.is_none() {
+                let lang_ptr = whisper_lang_str(whisper_full_lang_id(self.ctx));
+                if !lang_ptr.is_null() {
+                    language = Some(CStr::from_ptr(lang_ptr).to_string_lossy().into_owned());
+                }
+            }
+            
             let processing_time = start_time.elapsed().as_millis() as u64;
             
             Ok(TranscriptResult {
                 segments,
                 full_text,
-                language: language.map(|s| s.to_string()),
+                language,
                 processing_time_ms: processing_time,
             })
         }
     }
     
+    /// Text tokens of a segment from the last run, without special tokens
+    unsafe fn segment_tokens(&self, i_segment: c_int) -> Vec<TokenTiming> {
+        let eot = whisper_token_eot(self.ctx);
+        let n_tokens = whisper_full_n_tokens(self.ctx, i_segment);
+        let mut tokens = Vec::new();
+        
+        for j in 0..n_tokens {
+            let data = whisper_full_get_token_data(self.ctx, i_segment, j);
+            if is_special_token(data.id, eot) {
+                continue;
+            }
+            
+            let text_ptr = whisper_full_get_token_text(self.ctx, i_segment, j);
+            if text_ptr.is_null() {
+                continue;
+            }
+            let text = CStr::from_ptr(text_ptr).to_string_lossy().into_owned();
+            if text.trim().is_empty() {
+                continue;
+            }
+            
+            tokens.push(TokenTiming {
+                text,
+                start_time_ms: data.t0 * WHISPER_TIME_UNIT_MS,
+                end_time_ms: data.t1 * WHISPER_TIME_UNIT_MS,
+                probability: data.p,
+            });
+        }
+        tokens
+    }
+    
     /// Get model path
     pub fn model_path(&self) -> &str {
         &self.model_path
     }
 }
 
+/// Language code as whisper expects it; None asks whisper to detect it
+///
+/// Blank codes mean auto-detection. The returned string owns the bytes
+/// `params.language` points at, so it has to outlive the `whisper_full` call.
+fn language_param(language: Option<&str>) -> Result<Option<CString>, String> {
+    match language.map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(lang) => CString::new(lang)
+            .map(Some)
+            .map_err(|e| format!("Invalid language code {:?}: {}", lang, e)),
+    }
+}
+
 impl Drop for WhisperContext {
     fn drop(&mut self) {
         if !self.ctx.is_null() {
@@ -260,27 +459,62 @@ pub enum AudioFormat {
     I32,
 }
 
+/// Sample rate whisper expects
+const WHISPER_SAMPLE_RATE: u32 = 16000;
+
+/// Zero crossings of the sinc kernel on each side of a sample
+const SINC_ZERO_CROSSINGS: f64 = 16.0;
+
 /// Resample audio to 16kHz (whisper's expected sample rate)
-pub fn resample_to_16khz(samples: &[f32], original_rate: u32) -> Vec<f32> {
-    const TARGET_RATE: u32 = 16000;
-    
-    if original_rate == TARGET_RATE {
+pub fn resample_to_16khz(samples: &[f32], original_rate: u32, quality: ResampleQuality) -> Vec<f32> {
+    if original_rate == WHISPER_SAMPLE_RATE || samples.is_empty() {
         return samples.to_vec();
     }
     
-    // Simple linear interpolation resampling
-    let ratio = original_rate as f64 / TARGET_RATE as f64;
+    let ratio = original_rate as f64 / WHISPER_SAMPLE_RATE as f64;
     let output_len = (samples.len() as f64 / ratio) as usize;
-    let mut output = Vec::with_capacity(output_len);
+    let positions = (0..output_len).map(|i| i as f64 * ratio);
     
-    for i in 0..output_len {
-        let src_index = (i as f64 * ratio) as usize;
-        if src_index < samples.len() {
-            output.push(samples[src_index]);
+    match quality {
+        ResampleQuality::Fastest => positions
+            .map(|position| samples[(position as usize).min(samples.len() - 1)])
+            .collect(),
+        ResampleQuality::Linear => positions
+            .map(|position| {
+                let index = position as usize;
+                let current = samples[index.min(samples.len() - 1)];
+                let next = samples[(index + 1).min(samples.len() - 1)];
+                let frac = (position - index as f64) as f32;
+                current + (next - current) * frac
+            })
+            .collect(),
+        ResampleQuality::Sinc => {
+            // Low-pass below the lower of the two Nyquist frequencies
+            let cutoff = (1.0 / ratio).min(1.0);
+            positions.map(|position| sinc_interpolate(samples, position, cutoff)).collect()
         }
     }
+}
+
+/// Value of the band-limited signal at a fractional sample position
+/// 
+/// `cutoff` is relative to the input Nyquist frequency. The kernel is a
+/// Blackman-windowed sinc spanning `SINC_ZERO_CROSSINGS` on each side.
+fn sinc_interpolate(samples: &[f32], position: f64, cutoff: f64) -> f32 {
+    let half_width = SINC_ZERO_CROSSINGS / cutoff;
+    let first = (position - half_width).ceil().max(0.0) as usize;
+    let last = ((position + half_width).floor() as usize).min(samples.len() - 1);
     
-    output
+    let mut value = 0.0;
+    for (index, sample) in samples.iter().enumerate().take(last + 1).skip(first) {
+        let offset = position - index as f64;
+        let x = cutoff * offset;
+        let sinc = if x == 0.0 { 1.0 } else { (std::f64::consts::PI * x).sin() / (std::f64::consts::PI * x) };
+        let t = offset / half_width;
+        let window = 0.42 + 0.5 * (std::f64::consts::PI * t).cos() + 0.08 * (2.0 * std::f64::consts::PI * t).cos();
+        value += *sample as f64 * cutoff * sinc * window;
+    }
+    value as f32
 }
 
 #[cfg(test)]
@@ -294,10 +528,70 @@ mod tests {
         assert_eq!(samples.len(), 2);
     }
     
+    #[test]
+    fn test_language_param() {
+        assert!(language_param(None).unwrap().is_none());
+        assert!(language_param(Some("  ")).unwrap().is_none());
+        assert!(language_param(Some("de\0")).is_err());
+        
+        // The pointer handed to whisper reads back the requested code
+        let c_language = language_param(Some(" de ")).unwrap();
+        let ptr = c_language.as_ref().map_or(std::ptr::null(), |lang| lang.as_ptr());
+        let read_back = unsafe { CStr::from_ptr(ptr) };
+        assert_eq!(read_back.to_str().unwrap(), "de");
+    }
+    
+    #[test]
+    fn test_special_tokens() {
+        // Multilingual vocabulary: text tokens end just before end-of-text
+        let eot = 50257;
+        assert!(!is_special_token(0, eot));
+        assert!(!is_special_token(eot - 1, eot));
+        assert!(is_special_token(eot, eot));
+        // Start-of-transcript and the first timestamp token
+        assert!(is_special_token(50258, eot));
+        assert!(is_special_token(50364, eot));
+    }
+    
+    #[test]
+    fn test_default_thread_count() {
+        let threads = default_thread_count();
+        assert!((1..=MAX_DEFAULT_THREADS).contains(&threads));
+    }
+    
     #[test]
     fn test_resampling() {
         let samples = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0];
-        let resampled = resample_to_16khz(&samples, 32000);
+        let resampled = resample_to_16khz(&samples, 32000, ResampleQuality::Fastest);
         assert_eq!(resampled.len(), 4); // Half the samples
     }
+    
+    #[test]
+    fn test_resampling_quality() {
+        const SOURCE_RATE: u32 = 44100;
+        const FREQUENCY: f64 = 3000.0;
+        let tone = |t: f64| (2.0 * std::f64::consts::PI * FREQUENCY * t).sin() as f32;
+        
+        let samples: Vec<f32> = (0..SOURCE_RATE).map(|i| tone(i as f64 / SOURCE_RATE as f64)).collect();
+        
+        // RMS error against the ideal tone, ignoring the kernel's edge effects
+        let error = |quality| {
+            let resampled = resample_to_16khz(&samples, SOURCE_RATE, quality);
+            let inner = &resampled[100..resampled.len() - 100];
+            let sum: f64 = inner.iter().enumerate()
+                .map(|(i, &sample)| {
+                    let expected = tone((i + 100) as f64 / WHISPER_SAMPLE_RATE as f64);
+                    ((sample - expected) as f64).powi(2)
+                })
+                .sum();
+            (sum / inner.len() as f64).sqrt()
+        };
+        
+        let fastest = error(ResampleQuality::Fastest);
+        let linear = error(ResampleQuality::Linear);
+        let sinc = error(ResampleQuality::Sinc);
+        assert!(linear < fastest, "linear {} vs fastest {}", linear, fastest);
+        assert!(sinc < linear, "sinc {} vs linear {}", sinc, linear);
+        assert!(sinc < 0.01);
+    }
 }
@@ -5,9 +5,12 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
+/// Highest star rating an asset can have
+pub const MAX_RATING: u8 = 5;
+
 /// A digital asset in the DAM system
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Asset {
@@ -38,6 +41,14 @@ pub struct Asset {
     /// AI-generated tags describing the asset
     pub tags: Vec<String>,
     
+    /// User star rating (0-5), `None` if unrated
+    #[serde(default)]
+    pub rating: Option<u8>,
+    
+    /// Whether the user marked the asset as a favorite
+    #[serde(default)]
+    pub favorite: bool,
+    
     /// Additional metadata extracted from the file
     pub metadata: AssetMetadata,
     
@@ -49,6 +60,59 @@ pub struct Asset {
     
     /// Version control information
     pub version_info: VersionInfo,
+    
+    /// Result of the optional integrity check run during ingest
+    #[serde(default)]
+    pub integrity: IntegrityStatus,
+    
+    /// Set by a fast import that skipped metadata parsing; cleared once a
+    /// deep pass has extracted metadata and run the AI steps
+    #[serde(default)]
+    pub needs_deep_processing: bool,
+}
+
+/// Numbered frames (`shot_0001.png`...) grouped into one asset
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SequenceMetadata {
+    /// File name pattern with `#` for the frame digits, e.g. `shot_####.png`
+    pub pattern: String,
+    
+    /// Number of frames present on disk
+    pub frame_count: usize,
+    
+    /// Lowest and highest frame number
+    pub first_frame: u64,
+    pub last_frame: u64,
+    
+    /// Frame numbers missing between the first and last frame
+    pub missing_frames: Vec<u64>,
+}
+
+/// Whether an asset's file was found to be complete and decodable
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum IntegrityStatus {
+    /// No check was run, or the format has no check
+    #[default]
+    Unchecked,
+    /// The file passed the check
+    Intact,
+    /// The file ends early, e.g. an interrupted download
+    Truncated { reason: String },
+    /// The file is complete but its content is damaged
+    Corrupt { reason: String },
+    /// The file could not be read to check it
+    Unreadable { reason: String },
+}
+
+impl IntegrityStatus {
+    /// Whether the check found a problem with the file
+    pub fn is_suspect(&self) -> bool {
+        matches!(
+            self,
+            IntegrityStatus::Truncated { .. } | IntegrityStatus::Corrupt { .. } | IntegrityStatus::Unreadable { .. }
+        )
+    }
 }
 
 /// Categories of digital assets
@@ -90,6 +154,49 @@ pub struct FileFormat {
     
     /// Whether this format is fully supported
     pub supported: bool,
+    
+    /// How the format was determined
+    #[serde(default)]
+    pub detection_method: DetectionMethod,
+    
+    /// Confidence in the detected format, from 0 to 1
+    #[serde(default)]
+    pub confidence: f32,
+}
+
+impl FileFormat {
+    /// Whether the file content contradicts its extension, e.g. a `.jpg`
+    /// that is actually a PNG
+    pub fn contradicts_extension(&self, path: &Path) -> bool {
+        let declared = path.extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        self.detection_method.is_content_based() && declared != self.extension
+    }
+}
+
+/// How a file format was determined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DetectionMethod {
+    /// Not detected yet
+    #[default]
+    Unknown,
+    
+    /// From the file extension only; the content was not recognized
+    Extension,
+    
+    /// From a file signature (magic bytes) in the content
+    MagicBytes,
+    
+    /// From sniffing text content, e.g. an `<svg>` root element
+    ContentSniffing,
+}
+
+impl DetectionMethod {
+    /// Whether the format was read from the file content
+    pub fn is_content_based(&self) -> bool {
+        matches!(self, DetectionMethod::MagicBytes | DetectionMethod::ContentSniffing)
+    }
 }
 
 /// Asset-specific metadata
@@ -107,6 +214,18 @@ pub struct AssetMetadata {
     /// Video metadata
     pub video: Option<VideoMetadata>,
     
+    /// Archive content listing
+    #[serde(default)]
+    pub archive: Option<ArchiveMetadata>,
+    
+    /// Extracted text of text-based documents
+    #[serde(default)]
+    pub document: Option<DocumentMetadata>,
+    
+    /// Frame range of an image sequence ingested as one asset
+    #[serde(default)]
+    pub sequence: Option<SequenceMetadata>,
+    
     /// Custom metadata fields
     pub custom: HashMap<String, String>,
 }
@@ -129,6 +248,36 @@ pub struct ImageMetadata {
     
     /// PSD-specific layer information
     pub layers: Option<Vec<PsdLayer>>,
+    
+    /// Number of frames for animated formats (GIF, WebP)
+    #[serde(default)]
+    pub frame_count: Option<u32>,
+    
+    /// Whether the image contains more than one frame
+    #[serde(default)]
+    pub is_animated: bool,
+    
+    /// Keywords embedded by photo tools (XMP `dc:subject`, IPTC Keywords)
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    
+    /// Number of pages for multi-page formats (TIFF)
+    #[serde(default)]
+    pub page_count: Option<u32>,
+    
+    /// Size of each page, in file order; `width`/`height` are the first page's
+    #[serde(default)]
+    pub pages: Vec<ImagePage>,
+}
+
+/// One page of a multi-page image
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImagePage {
+    pub width: u32,
+    pub height: u32,
+    
+    /// Whether the page is stored in tiles rather than strips
+    pub tiled: bool,
 }
 
 /// Photoshop layer information
@@ -232,6 +381,36 @@ pub struct VideoMetadata {
     pub bit_rate: Option<u32>,
 }
 
+/// Archive content listing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveMetadata {
+    /// Total number of entries in the archive
+    pub entry_count: usize,
+    
+    /// Sum of uncompressed entry sizes in bytes
+    pub total_uncompressed_size: u64,
+    
+    /// Entry names (capped; `entry_count` holds the true count)
+    pub entries: Vec<String>,
+}
+
+/// Text extracted from a document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentMetadata {
+    /// Plain text content (Markdown syntax stripped), possibly truncated
+    pub extracted_text: String,
+    
+    /// Number of whitespace-separated words in `extracted_text`
+    pub word_count: usize,
+    
+    /// Whether the file was longer than the extraction limit
+    pub truncated: bool,
+    
+    /// Encoding the text was decoded from, e.g. `UTF-8` or `Shift_JIS`
+    #[serde(default)]
+    pub encoding: Option<String>,
+}
+
 /// Preview/thumbnail information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PreviewInfo {
@@ -241,11 +420,61 @@ pub struct PreviewInfo {
     /// Thumbnail dimensions
     pub thumbnail_size: (u32, u32),
     
-    /// For 3D models, path to rendered preview
+    /// For 3D models, path to rendered preview; for videos, the contact sheet
     pub rendered_preview: Option<PathBuf>,
     
     /// Preview generation timestamp
     pub generated_at: DateTime<Utc>,
+    
+    /// File extension of the thumbnail (`jpg`, `png` or `webp`)
+    #[serde(default = "default_preview_extension")]
+    pub extension: String,
+    
+    /// For audio, the peaks the waveform thumbnail was drawn from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub waveform: Option<Waveform>,
+    
+    /// For videos, `(rows, columns)` of the contact sheet in `rendered_preview`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contact_sheet_grid: Option<(u32, u32)>,
+}
+
+/// Min/max sample peaks of an audio file, for drawing waveforms
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Waveform {
+    /// `(min, max)` sample per bucket in [-1, 1], all channels combined
+    pub peaks: Vec<(f32, f32)>,
+}
+
+impl Waveform {
+    /// Peaks combined into `buckets` buckets
+    /// 
+    /// Asking for more buckets than are stored returns the stored peaks;
+    /// peaks are never interpolated.
+    pub fn resample(&self, buckets: usize) -> Vec<(f32, f32)> {
+        let len = self.peaks.len();
+        if buckets == 0 || len == 0 {
+            return Vec::new();
+        }
+        if buckets >= len {
+            return self.peaks.clone();
+        }
+        
+        (0..buckets)
+            .map(|bucket| {
+                let start = bucket * len / buckets;
+                let end = (bucket + 1) * len / buckets;
+                self.peaks[start..end].iter().fold((f32::MAX, f32::MIN), |(low, high), &(min, max)| {
+                    (low.min(min), high.max(max))
+                })
+            })
+            .collect()
+    }
+}
+
+/// Previews stored before the format was configurable are JPEG
+fn default_preview_extension() -> String {
+    "jpg".to_string()
 }
 
 /// Version control information
@@ -279,15 +508,22 @@ impl Asset {
                 mime_type: None,
                 version: None,
                 supported: false,
+                detection_method: DetectionMethod::Unknown,
+                confidence: 0.0,
             },
             created_at: now,
             modified_at: now,
             tags: Vec::new(),
+            rating: None,
+            favorite: false,
             metadata: AssetMetadata {
                 image: None,
                 three_d: None,
                 audio: None,
                 video: None,
+                archive: None,
+                document: None,
+                sequence: None,
                 custom: HashMap::new(),
             },
             preview: None,
@@ -298,6 +534,8 @@ impl Asset {
                 last_snapshot: now,
                 has_changes: false,
             },
+            integrity: IntegrityStatus::Unchecked,
+            needs_deep_processing: false,
         }
     }
     
@@ -329,6 +567,9 @@ impl Default for AssetMetadata {
             three_d: None,
             audio: None,
             video: None,
+            archive: None,
+            document: None,
+            sequence: None,
             custom: HashMap::new(),
         }
     }
@@ -339,10 +580,12 @@ impl AssetType {
     pub fn from_extension(ext: &str) -> Self {
         match ext.to_lowercase().as_str() {
             // Images
-            "png" | "jpg" | "jpeg" | "gif" | "bmp" | "tiff" | "tga" | "webp" | "psd" => Self::Image,
+            "png" | "jpg" | "jpeg" | "gif" | "bmp" | "tiff" | "tif" | "tga" | "webp" | "psd" | "psb" | "svg" | "exr" | "hdr" => Self::Image,
+            "heic" | "heif" | "avif" => Self::Image,
+            "cr2" | "cr3" | "nef" | "nrw" | "arw" | "dng" | "orf" | "rw2" | "raf" | "pef" | "srw" => Self::Image,
             
             // 3D formats
-            "blend" | "fbx" | "obj" | "gltf" | "glb" | "dae" | "3ds" | "max" | "c4d" => Self::ThreeD,
+            "blend" | "fbx" | "obj" | "stl" | "gltf" | "glb" | "dae" | "3ds" | "max" | "c4d" => Self::ThreeD,
             
             // Audio
             "wav" | "mp3" | "flac" | "ogg" | "aac" | "m4a" | "wma" => Self::Audio,
@@ -351,7 +594,7 @@ impl AssetType {
             "mp4" | "mov" | "avi" | "mkv" | "wmv" | "flv" | "webm" => Self::Video,
             
             // Documents
-            "txt" | "md" | "pdf" | "doc" | "docx" | "rtf" => Self::Document,
+            "txt" | "md" | "csv" | "tsv" | "srt" | "vtt" | "log" | "pdf" | "doc" | "docx" | "rtf" => Self::Document,
             
             // Archives
             "zip" | "rar" | "tar" | "gz" | "7z" => Self::Archive,
@@ -2,6 +2,7 @@
 //! 
 //! Defines standardized error types used throughout the system.
 
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use std::path::PathBuf;
 use uuid::Uuid;
@@ -48,6 +49,10 @@ pub enum DamError {
     #[error("Asset not found: {asset_id}")]
     AssetNotFound { asset_id: Uuid },
     
+    /// Indexed document, or its embedding, not found
+    #[error("Document not found: {document_id}")]
+    DocumentNotFound { document_id: Uuid },
+    
     /// Invalid asset data
     #[error("Invalid asset data: {message}")]
     InvalidAssetData { message: String },
@@ -96,7 +101,7 @@ pub enum DamError {
 pub type DamResult<T> = Result<T, DamError>;
 
 /// Error categories for easier error handling
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ErrorCategory {
     /// File system and I/O errors
     FileSystem,
@@ -142,6 +147,7 @@ impl DamError {
             DamError::Configuration { .. } => ErrorCategory::Configuration,
             DamError::UnsupportedFormat { .. } => ErrorCategory::Asset,
             DamError::AssetNotFound { .. } => ErrorCategory::Asset,
+            DamError::DocumentNotFound { .. } => ErrorCategory::Search,
             DamError::InvalidAssetData { .. } => ErrorCategory::Asset,
             DamError::Serialization(_) => ErrorCategory::System,
             DamError::AiProcessing { .. } => ErrorCategory::Processing,
@@ -167,6 +173,7 @@ impl DamError {
             DamError::Configuration { .. } => false,
             DamError::UnsupportedFormat { .. } => false,
             DamError::AssetNotFound { .. } => false,
+            DamError::DocumentNotFound { .. } => false,
             DamError::InvalidAssetData { .. } => false,
             DamError::Serialization(_) => false,
             DamError::AiProcessing { .. } => true,
@@ -194,6 +201,7 @@ impl DamError {
                 format!("Unsupported file format: {}", format)
             }
             DamError::AssetNotFound { .. } => "Asset not found".to_string(),
+            DamError::DocumentNotFound { .. } => "Indexed document not found".to_string(),
             DamError::InvalidAssetData { .. } => "Invalid asset data".to_string(),
             DamError::Serialization(_) => "Data serialization error".to_string(),
             DamError::AiProcessing { .. } => "AI processing failed".to_string(),
@@ -214,6 +222,61 @@ impl DamError {
     }
 }
 
+/// Serializable summary of an error for frontends
+/// 
+/// `DamError` wraps I/O and JSON errors and cannot cross a process
+/// boundary, so Tauri commands and the web server send this instead. The
+/// category and recoverability let the frontend decide between offering a
+/// retry and explaining that something cannot work.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorInfo {
+    pub category: ErrorCategory,
+    pub recoverable: bool,
+    /// Full error message, for logs and details views
+    pub message: String,
+    /// Short message suitable for showing to users
+    pub user_message: String,
+}
+
+impl From<&DamError> for ErrorInfo {
+    fn from(err: &DamError) -> Self {
+        Self {
+            category: err.category(),
+            recoverable: err.is_recoverable(),
+            message: err.to_string(),
+            user_message: err.user_message(),
+        }
+    }
+}
+
+impl From<DamError> for ErrorInfo {
+    fn from(err: DamError) -> Self {
+        Self::from(&err)
+    }
+}
+
+impl From<ErrorInfo> for DamError {
+    /// Nearest `DamError` with the same category and recoverability
+    fn from(info: ErrorInfo) -> Self {
+        let ErrorInfo { category, recoverable, message, .. } = info;
+        match (category, recoverable) {
+            (ErrorCategory::FileSystem, _) => DamError::FileSystem(std::io::Error::other(message)),
+            (ErrorCategory::Asset, true) => DamError::ingestion(message),
+            (ErrorCategory::Asset, false) => DamError::invalid_asset_data(message),
+            (ErrorCategory::Processing, _) => DamError::processing(message),
+            (ErrorCategory::Search, _) => DamError::search(message),
+            (ErrorCategory::VersionControl, _) => DamError::version_control(message),
+            (ErrorCategory::Network, _) => DamError::server(message),
+            (ErrorCategory::Configuration, _) => DamError::configuration(message),
+            (ErrorCategory::Security, true) => DamError::authentication(message),
+            (ErrorCategory::Security, false) => DamError::permission_denied(message),
+            (ErrorCategory::External, _) => DamError::external_dependency("unknown", message),
+            (ErrorCategory::System, true) => DamError::storage(message),
+            (ErrorCategory::System, false) => DamError::invalid_operation(message),
+        }
+    }
+}
+
 /// Convenience functions for creating specific error types
 impl DamError {
     /// Create an ingestion error
@@ -271,6 +334,11 @@ impl DamError {
         Self::AssetNotFound { asset_id }
     }
     
+    /// Create a document not found error
+    pub fn document_not_found(document_id: Uuid) -> Self {
+        Self::DocumentNotFound { document_id }
+    }
+    
     /// Create an invalid asset data error
     pub fn invalid_asset_data<S: Into<String>>(message: S) -> Self {
         Self::InvalidAssetData {
@@ -364,6 +432,34 @@ mod tests {
         assert!(DamError::processing("test").is_recoverable());
         assert!(!DamError::unsupported_format("test", PathBuf::new()).is_recoverable());
         assert!(!DamError::configuration("test").is_recoverable());
+        assert!(!DamError::document_not_found(Uuid::new_v4()).is_recoverable());
+    }
+    
+    #[test]
+    fn test_error_info_round_trip() {
+        let errors = [
+            DamError::FileSystem(std::io::Error::other("disk")),
+            DamError::ingestion("test"),
+            DamError::unsupported_format("xyz", PathBuf::new()),
+            DamError::asset_not_found(Uuid::nil()),
+            DamError::processing("test"),
+            DamError::search("test"),
+            DamError::configuration("test"),
+            DamError::storage("test"),
+            DamError::invalid_operation("test"),
+            DamError::permission_denied("test"),
+            DamError::authentication("test"),
+            DamError::timeout("test"),
+            DamError::external_dependency("ffmpeg", "test"),
+        ];
+        
+        for error in errors {
+            let info = ErrorInfo::from(&error);
+            let json = serde_json::to_string(&info).unwrap();
+            let restored = DamError::from(serde_json::from_str::<ErrorInfo>(&json).unwrap());
+            assert_eq!(restored.category(), error.category(), "{}", error);
+            assert_eq!(restored.is_recoverable(), error.is_recoverable(), "{}", error);
+        }
     }
     
     #[test]
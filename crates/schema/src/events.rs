@@ -0,0 +1,108 @@
+//! Progress and notification events for the user interface
+//!
+//! Long operations such as directory imports, reprocessing and index
+//! compaction run for minutes. Services report on them through `UiEvents`,
+//! a broadcast channel of `UiMessage::ShowProgress`, `HideProgress` and
+//! `Notification` messages. The desktop app forwards them to its webview
+//! and a web frontend can forward them over a socket; with no subscriber
+//! the messages are simply dropped.
+
+use crate::{NotificationLevel, UiMessage};
+use tokio::sync::broadcast;
+
+/// Messages buffered per subscriber before slow subscribers miss some
+pub const UI_EVENT_CAPACITY: usize = 256;
+
+/// Sending side of the UI event channel; clones share the same channel
+#[derive(Debug, Clone)]
+pub struct UiEvents {
+    sender: broadcast::Sender<UiMessage>,
+}
+
+impl UiEvents {
+    /// Create a new channel
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(UI_EVENT_CAPACITY);
+        Self { sender }
+    }
+
+    /// Receive every message emitted from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<UiMessage> {
+        self.sender.subscribe()
+    }
+
+    /// Number of current subscribers
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+
+    /// Send a message to all subscribers
+    pub fn emit(&self, message: UiMessage) {
+        // Sending only fails when nobody is listening
+        let _ = self.sender.send(message);
+    }
+
+    /// Report progress of an operation as `completed` out of `total` steps
+    pub fn progress(&self, title: &str, completed: usize, total: usize, message: Option<String>) {
+        let progress = if total == 0 { 1.0 } else { (completed as f32 / total as f32).min(1.0) };
+        self.emit(UiMessage::ShowProgress {
+            title: title.to_string(),
+            progress,
+            message,
+        });
+    }
+
+    /// Report that the operation showing progress has ended
+    pub fn hide_progress(&self) {
+        self.emit(UiMessage::HideProgress);
+    }
+
+    /// Show a notification
+    pub fn notify(&self, level: NotificationLevel, title: &str, message: impl Into<String>) {
+        self.emit(UiMessage::Notification {
+            level,
+            title: title.to_string(),
+            message: message.into(),
+        });
+    }
+}
+
+impl Default for UiEvents {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_reach_every_subscriber() {
+        let events = UiEvents::new();
+        // Emitting without subscribers is not an error
+        events.hide_progress();
+
+        let mut first = events.subscribe();
+        let mut second = events.clone().subscribe();
+        assert_eq!(events.subscriber_count(), 2);
+
+        events.progress("Importing", 3, 4, Some("photo.jpg".to_string()));
+        events.notify(NotificationLevel::Success, "Import finished", "4 files imported");
+
+        for receiver in [&mut first, &mut second] {
+            match receiver.try_recv().unwrap() {
+                UiMessage::ShowProgress { title, progress, message } => {
+                    assert_eq!(title, "Importing");
+                    assert_eq!(progress, 0.75);
+                    assert_eq!(message.as_deref(), Some("photo.jpg"));
+                }
+                other => panic!("unexpected message {:?}", other),
+            }
+            assert!(matches!(
+                receiver.try_recv().unwrap(),
+                UiMessage::Notification { level: NotificationLevel::Success, .. }
+            ));
+        }
+    }
+}
@@ -120,18 +120,21 @@ pub enum ProcessMessage {
     /// Processing completed
     Completed { 
         task_id: Uuid, 
+        asset_id: Uuid,
         result: ProcessingResult 
     },
     
     /// Processing failed
     Failed { 
         task_id: Uuid, 
+        asset_id: Uuid,
+        task_type: ProcessingTaskType,
         error: String 
     },
 }
 
 /// Types of AI processing tasks
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ProcessingTaskType {
     Transcription,
     ImageTagging,
@@ -140,6 +143,70 @@ pub enum ProcessingTaskType {
     VideoAnalysis,
 }
 
+/// Progress of one AI processing step on an asset
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum StepStatus {
+    #[default]
+    NotStarted,
+    InProgress,
+    Done,
+    Failed { reason: String },
+}
+
+impl StepStatus {
+    /// Whether the step failed and can be retried
+    pub fn is_failed(&self) -> bool {
+        matches!(self, StepStatus::Failed { .. })
+    }
+}
+
+/// Which AI processing steps have run on an asset
+/// 
+/// Persisted with the asset so "not started", "in progress" and "failed"
+/// can be told apart and failed steps retried on their own.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProcessingStatus {
+    pub tagging: StepStatus,
+    pub transcription: StepStatus,
+    pub embedding: StepStatus,
+}
+
+impl ProcessingStatus {
+    /// Status of the step a task type runs, if it is a tracked step
+    pub fn step(&self, task_type: &ProcessingTaskType) -> Option<&StepStatus> {
+        match task_type {
+            ProcessingTaskType::ImageTagging => Some(&self.tagging),
+            ProcessingTaskType::Transcription => Some(&self.transcription),
+            ProcessingTaskType::EmbeddingGeneration => Some(&self.embedding),
+            ProcessingTaskType::ImageEditing | ProcessingTaskType::VideoAnalysis => None,
+        }
+    }
+    
+    /// Mutable status of the step a task type runs
+    pub fn step_mut(&mut self, task_type: &ProcessingTaskType) -> Option<&mut StepStatus> {
+        match task_type {
+            ProcessingTaskType::ImageTagging => Some(&mut self.tagging),
+            ProcessingTaskType::Transcription => Some(&mut self.transcription),
+            ProcessingTaskType::EmbeddingGeneration => Some(&mut self.embedding),
+            ProcessingTaskType::ImageEditing | ProcessingTaskType::VideoAnalysis => None,
+        }
+    }
+    
+    /// Task types of the steps that failed
+    pub fn failed_steps(&self) -> Vec<ProcessingTaskType> {
+        [
+            ProcessingTaskType::ImageTagging,
+            ProcessingTaskType::Transcription,
+            ProcessingTaskType::EmbeddingGeneration,
+        ]
+        .into_iter()
+        .filter(|task_type| self.step(task_type).is_some_and(StepStatus::is_failed))
+        .collect()
+    }
+}
+
 /// Results from AI processing
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ProcessingResult {
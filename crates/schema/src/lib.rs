@@ -8,9 +8,13 @@ pub mod search;
 pub mod ipc;
 pub mod error;
 pub mod models;
+pub mod retry;
+pub mod events;
 
 pub use asset::*;
 pub use search::*;
 pub use ipc::*;
 pub use error::*;
 pub use models::*;
+pub use retry::*;
+pub use events::*;
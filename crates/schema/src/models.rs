@@ -46,6 +46,42 @@ impl ModelTier {
     }
 }
 
+/// Hardware that model inference runs on
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum ComputeDevice {
+    /// CUDA or Metal when available, otherwise the CPU
+    #[default]
+    Auto,
+    Cpu,
+    Cuda,
+    Metal,
+}
+
+impl ComputeDevice {
+    /// Get human-readable name
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Self::Auto => "Automatic",
+            Self::Cpu => "CPU",
+            Self::Cuda => "CUDA",
+            Self::Metal => "Metal",
+        }
+    }
+}
+
+/// How audio is resampled to whisper's sample rate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResampleQuality {
+    /// Nearest preceding sample; fastest, but aliases high frequencies
+    Fastest,
+    /// Linear interpolation between neighbouring samples
+    #[default]
+    Linear,
+    /// Band-limited windowed-sinc interpolation; slowest, least aliasing
+    Sinc,
+}
+
 /// Configuration for audio transcription models
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AudioModelConfig {
@@ -253,24 +289,26 @@ impl ModelRegistry {
     
     /// Set current tier (validates VRAM requirements)
     pub fn set_tier(&mut self, tier: ModelTier) -> Result<(), String> {
-        if let Some(config) = self.tiers.get(&tier) {
-            if self.available_vram_mb < config.tier.min_vram_mb() {
-                return Err(format!(
-                    "Insufficient VRAM: {} MB available, {} MB required",
-                    self.available_vram_mb,
-                    config.tier.min_vram_mb()
-                ));
-            }
-            
-            if config.cuda_required && !self.cuda_available {
-                return Err("CUDA required but not available".to_string());
-            }
-            
-            self.current_tier = tier;
-            Ok(())
-        } else {
-            Err("Invalid tier".to_string())
+        self.check_tier(&tier)?;
+        self.current_tier = tier;
+        Ok(())
+    }
+    
+    /// Check that a tier is configured and fits this system
+    pub fn check_tier(&self, tier: &ModelTier) -> Result<(), String> {
+        let config = self.tiers.get(tier).ok_or_else(|| "Invalid tier".to_string())?;
+        if self.available_vram_mb < config.tier.min_vram_mb() {
+            return Err(format!(
+                "Insufficient VRAM: {} MB available, {} MB required",
+                self.available_vram_mb,
+                config.tier.min_vram_mb()
+            ));
+        }
+        
+        if config.cuda_required && !self.cuda_available {
+            return Err("CUDA required but not available".to_string());
         }
+        Ok(())
     }
     
     /// Update system capabilities
@@ -309,6 +347,19 @@ impl ModelRegistry {
         
         available
     }
+    
+    /// Tiers to try when activating `tier`, best first
+    /// 
+    /// The requested tier comes first, followed by the available tiers
+    /// below it from highest to lowest.
+    pub fn fallback_tiers(&self, tier: &ModelTier) -> Vec<ModelTier> {
+        let mut tiers = vec![tier.clone()];
+        tiers.extend(self.available_tiers()
+            .into_iter()
+            .rev()
+            .filter(|candidate| candidate.min_vram_mb() < tier.min_vram_mb()));
+        tiers
+    }
 }
 
 impl Default for ModelRegistry {
@@ -324,8 +375,19 @@ pub enum ModelStatus {
     NotLoaded,
     /// Currently loading
     Loading { progress: f32 },
-    /// Successfully loaded
-    Loaded { memory_usage_mb: u32 },
+    /// Successfully loaded, on `device` if the service reports one
+    Loaded {
+        memory_usage_mb: u32,
+        #[serde(default)]
+        device: Option<ComputeDevice>,
+    },
+    /// Usable, but some of the tier's models are missing
+    Degraded {
+        memory_usage_mb: u32,
+        missing: Vec<String>,
+        #[serde(default)]
+        device: Option<ComputeDevice>,
+    },
     /// Failed to load
     Failed { error: String },
 }
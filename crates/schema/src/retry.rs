@@ -0,0 +1,137 @@
+//! Retrying operations that fail with recoverable errors
+//!
+//! Transient failures (a busy database, a failed inference) are
+//! retried with exponential backoff. Errors for which
+//! `DamError::is_recoverable` is false are returned immediately.
+
+use crate::error::{DamError, DamResult};
+use std::future::Future;
+use std::time::Duration;
+use tracing::warn;
+
+/// Default number of attempts, including the first one
+pub const DEFAULT_RETRY_ATTEMPTS: u32 = 3;
+
+/// Default delay before the first retry; doubled for each further retry
+pub const DEFAULT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Run a blocking operation, retrying recoverable errors
+///
+/// `op` is called up to `attempts` times (at least once). After the n-th
+/// failed attempt the thread sleeps `base_delay * 2^(n-1)`.
+///
+/// Only for code that is not running on the async runtime, as the sleep
+/// blocks the thread; async code uses [`retry_recoverable_async`].
+pub fn retry_recoverable<F, T>(attempts: u32, base_delay: Duration, mut op: F) -> DamResult<T>
+where
+    F: FnMut() -> DamResult<T>,
+{
+    let mut attempt = 1;
+    loop {
+        match op() {
+            Err(e) if should_retry(&e, attempt, attempts) => {
+                let delay = backoff_delay(base_delay, attempt);
+                warn!("Attempt {}/{} failed, retrying in {:?}: {}", attempt, attempts, delay, e);
+                std::thread::sleep(delay);
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Run an async operation, retrying recoverable errors
+///
+/// Async counterpart of [`retry_recoverable`]; waits with `tokio::time::sleep`
+/// so the runtime is not blocked between attempts.
+pub async fn retry_recoverable_async<F, Fut, T>(attempts: u32, base_delay: Duration, mut op: F) -> DamResult<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = DamResult<T>>,
+{
+    let mut attempt = 1;
+    loop {
+        match op().await {
+            Err(e) if should_retry(&e, attempt, attempts) => {
+                let delay = backoff_delay(base_delay, attempt);
+                warn!("Attempt {}/{} failed, retrying in {:?}: {}", attempt, attempts, delay, e);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+fn should_retry(error: &DamError, attempt: u32, attempts: u32) -> bool {
+    attempt < attempts && error.is_recoverable()
+}
+
+/// Delay after the given (1-based) failed attempt
+fn backoff_delay(base_delay: Duration, attempt: u32) -> Duration {
+    base_delay.saturating_mul(1 << (attempt - 1).min(16))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    
+    #[test]
+    fn test_recoverable_errors_are_retried() {
+        let mut calls = 0;
+        let result = retry_recoverable(3, Duration::from_millis(1), || {
+            calls += 1;
+            if calls < 3 {
+                Err(DamError::storage("database busy"))
+            } else {
+                Ok(calls)
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+        
+        // Gives up after the last attempt
+        let mut calls = 0;
+        let result: DamResult<()> = retry_recoverable(2, Duration::from_millis(1), || {
+            calls += 1;
+            Err(DamError::timeout("inference"))
+        });
+        assert!(matches!(result, Err(DamError::Timeout { .. })));
+        assert_eq!(calls, 2);
+    }
+    
+    #[test]
+    fn test_unrecoverable_errors_fail_fast() {
+        let mut calls = 0;
+        let result: DamResult<()> = retry_recoverable(5, Duration::from_secs(60), || {
+            calls += 1;
+            Err(DamError::unsupported_format("xyz", PathBuf::from("file.xyz")))
+        });
+        assert!(matches!(result, Err(DamError::UnsupportedFormat { .. })));
+        assert_eq!(calls, 1);
+    }
+    
+    #[tokio::test]
+    async fn test_async_retry() {
+        let mut calls = 0;
+        let result = retry_recoverable_async(3, Duration::from_millis(1), || {
+            calls += 1;
+            let attempt = calls;
+            async move {
+                if attempt == 1 {
+                    Err(DamError::ai_processing("model still loading"))
+                } else {
+                    Ok(attempt)
+                }
+            }
+        }).await;
+        assert_eq!(result.unwrap(), 2);
+    }
+    
+    #[test]
+    fn test_backoff_delay() {
+        let base = Duration::from_millis(100);
+        assert_eq!(backoff_delay(base, 1), Duration::from_millis(100));
+        assert_eq!(backoff_delay(base, 3), Duration::from_millis(400));
+    }
+}
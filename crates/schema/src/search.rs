@@ -29,6 +29,32 @@ pub struct SearchQuery {
     /// File size range filter
     pub size_range: Option<SizeRange>,
     
+    /// Minimum star rating; unrated assets are excluded
+    #[serde(default)]
+    pub min_rating: Option<u8>,
+    
+    /// Only return assets marked as favorite
+    #[serde(default)]
+    pub favorites_only: bool,
+    
+    /// Only return assets that failed the ingest integrity check
+    #[serde(default)]
+    pub suspect_only: bool,
+    
+    /// Custom metadata filters (all must match)
+    #[serde(default)]
+    pub custom: Vec<CustomFilter>,
+    
+    /// Pixel size, orientation and aspect ratio filter; assets without
+    /// stored dimensions are excluded when set
+    #[serde(default)]
+    pub dimensions: Option<DimensionFilter>,
+    
+    /// Only match transcripts of this speaker; text terms must then occur
+    /// in what the speaker said
+    #[serde(default)]
+    pub speaker: Option<String>,
+    
     /// Semantic similarity search
     pub semantic_query: Option<String>,
     
@@ -40,6 +66,162 @@ pub struct SearchQuery {
     
     /// Sort criteria
     pub sort: Option<SortCriteria>,
+    
+    /// Attach a score breakdown to every result, for tuning ranking
+    #[serde(default)]
+    pub explain: bool,
+}
+
+/// Prefix of custom metadata filters in query text, as in `custom:client=acme`
+pub const CUSTOM_FILTER_PREFIX: &str = "custom:";
+
+/// Prefix of the speaker filter in query text, as in `speaker:2 budget`
+pub const SPEAKER_FILTER_PREFIX: &str = "speaker:";
+
+/// Filter on a custom metadata field
+/// 
+/// Keys and values are compared case-insensitively. Without a value, any
+/// asset that has the key matches.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomFilter {
+    pub key: String,
+    pub value: Option<String>,
+}
+
+impl CustomFilter {
+    /// Parse a `custom:key=value` or `custom:key` query term
+    pub fn parse(term: &str) -> Option<Self> {
+        let filter = term.get(..CUSTOM_FILTER_PREFIX.len())
+            .filter(|prefix| prefix.eq_ignore_ascii_case(CUSTOM_FILTER_PREFIX))
+            .map(|_| &term[CUSTOM_FILTER_PREFIX.len()..])?;
+        let (key, value) = match filter.split_once('=') {
+            Some((key, value)) => (key, Some(value.to_string())),
+            None => (filter, None),
+        };
+        if key.is_empty() {
+            return None;
+        }
+        Some(Self { key: key.to_string(), value })
+    }
+    
+    /// Whether a set of custom metadata passes this filter
+    pub fn matches(&self, custom: &HashMap<String, String>) -> bool {
+        custom.iter().any(|(key, value)| {
+            key.eq_ignore_ascii_case(&self.key)
+                && self.value.as_ref().map_or(true, |wanted| wanted.eq_ignore_ascii_case(value))
+        })
+    }
+}
+
+/// Speaker named by a `speaker:label` query term
+fn parse_speaker(term: &str) -> Option<&str> {
+    term.get(..SPEAKER_FILTER_PREFIX.len())
+        .filter(|prefix| prefix.eq_ignore_ascii_case(SPEAKER_FILTER_PREFIX))
+        .map(|_| &term[SPEAKER_FILTER_PREFIX.len()..])
+        .filter(|speaker| !speaker.is_empty())
+}
+
+/// Orientation of an image
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Orientation {
+    Landscape,
+    Portrait,
+    Square,
+}
+
+impl Orientation {
+    /// Orientation of a `width` by `height` image; only equal sides are square
+    pub fn of(width: u32, height: u32) -> Self {
+        match width.cmp(&height) {
+            std::cmp::Ordering::Greater => Orientation::Landscape,
+            std::cmp::Ordering::Less => Orientation::Portrait,
+            std::cmp::Ordering::Equal => Orientation::Square,
+        }
+    }
+}
+
+/// Filter on pixel dimensions
+/// 
+/// All set bounds must hold; bounds are inclusive. The aspect ratio is
+/// width divided by height, so 16:9 is about 1.78 and portrait images are
+/// below 1.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DimensionFilter {
+    /// Exact width and height
+    pub exact: Option<(u32, u32)>,
+    pub min_width: Option<u32>,
+    pub max_width: Option<u32>,
+    pub min_height: Option<u32>,
+    pub max_height: Option<u32>,
+    pub orientation: Option<Orientation>,
+    pub min_aspect_ratio: Option<f32>,
+    pub max_aspect_ratio: Option<f32>,
+}
+
+impl DimensionFilter {
+    /// Only images of exactly `width` by `height` pixels
+    pub fn exact(width: u32, height: u32) -> Self {
+        Self {
+            exact: Some((width, height)),
+            ..Self::default()
+        }
+    }
+    
+    /// Only images of one orientation
+    pub fn oriented(orientation: Orientation) -> Self {
+        Self {
+            orientation: Some(orientation),
+            ..Self::default()
+        }
+    }
+    
+    /// At least `width` by `height` pixels
+    pub fn with_min_size(mut self, width: u32, height: u32) -> Self {
+        self.min_width = Some(width);
+        self.min_height = Some(height);
+        self
+    }
+    
+    /// At most `width` by `height` pixels
+    pub fn with_max_size(mut self, width: u32, height: u32) -> Self {
+        self.max_width = Some(width);
+        self.max_height = Some(height);
+        self
+    }
+    
+    /// Aspect ratio (width / height) between `min` and `max`
+    pub fn with_aspect_ratio(mut self, min: Option<f32>, max: Option<f32>) -> Self {
+        self.min_aspect_ratio = min;
+        self.max_aspect_ratio = max;
+        self
+    }
+    
+    /// Whether an image of `width` by `height` pixels passes the filter
+    pub fn matches(&self, width: u32, height: u32) -> bool {
+        if self.exact.is_some_and(|exact| exact != (width, height)) {
+            return false;
+        }
+        if self.min_width.is_some_and(|min| width < min)
+            || self.max_width.is_some_and(|max| width > max)
+            || self.min_height.is_some_and(|min| height < min)
+            || self.max_height.is_some_and(|max| height > max) {
+            return false;
+        }
+        if self.orientation.is_some_and(|orientation| orientation != Orientation::of(width, height)) {
+            return false;
+        }
+        if self.min_aspect_ratio.is_some() || self.max_aspect_ratio.is_some() {
+            if height == 0 {
+                return false;
+            }
+            let ratio = width as f32 / height as f32;
+            if self.min_aspect_ratio.is_some_and(|min| ratio < min)
+                || self.max_aspect_ratio.is_some_and(|max| ratio > max) {
+                return false;
+            }
+        }
+        true
+    }
 }
 
 /// Date range for filtering search results
@@ -76,6 +258,9 @@ pub enum SortCriteria {
     
     /// Sort by asset type
     AssetType { ascending: bool },
+    
+    /// Sort by star rating; unrated assets come last
+    Rating { ascending: bool },
 }
 
 /// Search results container
@@ -279,10 +464,17 @@ impl Default for SearchQuery {
             extensions: Vec::new(),
             date_range: None,
             size_range: None,
+            min_rating: None,
+            favorites_only: false,
+            suspect_only: false,
+            custom: Vec::new(),
+            dimensions: None,
+            speaker: None,
             semantic_query: None,
             limit: Some(50),
             offset: Some(0),
             sort: Some(SortCriteria::Relevance),
+            explain: false,
         }
     }
 }
@@ -316,11 +508,85 @@ impl SearchQuery {
         self
     }
     
+    /// Only match assets rated at least `rating` stars
+    pub fn with_min_rating(mut self, rating: u8) -> Self {
+        self.min_rating = Some(rating);
+        self
+    }
+    
+    /// Only match favorites
+    pub fn favorites_only(mut self) -> Self {
+        self.favorites_only = true;
+        self
+    }
+    
+    /// Only match truncated or corrupt files
+    pub fn suspect_only(mut self) -> Self {
+        self.suspect_only = true;
+        self
+    }
+    
+    /// Only match assets whose custom metadata has `key` set to `value`
+    pub fn with_custom(mut self, key: &str, value: &str) -> Self {
+        self.custom.push(CustomFilter {
+            key: key.to_string(),
+            value: Some(value.to_string()),
+        });
+        self
+    }
+    
+    /// Only match assets whose pixel dimensions pass `filter`
+    pub fn with_dimensions(mut self, filter: DimensionFilter) -> Self {
+        self.dimensions = Some(filter);
+        self
+    }
+    
+    /// Only match what `speaker` said in transcripts
+    pub fn with_speaker(mut self, speaker: &str) -> Self {
+        self.speaker = Some(speaker.to_string());
+        self
+    }
+    
+    /// Move `custom:` and `speaker:` filter terms out of the query text
+    /// 
+    /// The last `speaker:` term wins if several are given.
+    pub fn extract_filters(&mut self) {
+        let Some(text) = &self.text else {
+            return;
+        };
+        
+        let (filters, remaining): (Vec<&str>, Vec<&str>) = text.split_whitespace()
+            .partition(|term| CustomFilter::parse(term).is_some() || parse_speaker(term).is_some());
+        if filters.is_empty() {
+            return;
+        }
+        
+        for term in filters {
+            match parse_speaker(term) {
+                Some(speaker) => self.speaker = Some(speaker.to_string()),
+                None => self.custom.extend(CustomFilter::parse(term)),
+            }
+        }
+        self.text = Some(remaining.join(" "));
+    }
+    
+    /// Set sort order
+    pub fn sorted_by(mut self, sort: SortCriteria) -> Self {
+        self.sort = Some(sort);
+        self
+    }
+    
     /// Set result limit
     pub fn limit(mut self, limit: usize) -> Self {
         self.limit = Some(limit);
         self
     }
+    
+    /// Explain how each result was scored
+    pub fn explained(mut self) -> Self {
+        self.explain = true;
+        self
+    }
 }
 
 impl Default for SimilaritySearchParams {
@@ -1,36 +1,80 @@
 //! Main application state and initialization
 
 use crate::error::{UiError, UiResult};
-use index::IndexService;
-use ingest::IngestService;
-// use process::{TranscriptionService, TaggingService};  // Temporarily disabled
-use schema::{Asset, DamResult, ModelTier};
+use index::{IndexService, SharedIndex};
+use ingest::{AssetTypeOverrides, FilenameTagRules, HiddenFilePolicy, ImportLog, IngestMode, IngestService};
+#[cfg(feature = "ai")]
+use process::cache::{EmbeddingCache, DEFAULT_CACHE_ENTRIES};
+#[cfg(feature = "ai")]
+use process::{AiStep, ImportOptions, ProcessingQueue, ProcessingService};
+use schema::{Asset, ComputeDevice, DamError, DamResult, ModelTier, ResampleQuality, UiEvents};
+use futures::stream::BoxStream;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+#[cfg(feature = "ai")]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "ai")]
+use tokio::sync::broadcast::error::RecvError;
+use std::sync::Arc;
 use tracing::{info, warn, error};
 use uuid::Uuid;
 
 /// Main application state
 pub struct DamApp {
-    /// Search and indexing service
-    pub index_service: IndexService,
+    /// Search and indexing service, shared with background passes
+    pub index_service: SharedIndex,
     
     /// File ingestion service
     pub ingest_service: IngestService,
     
-    /// AI transcription service (temporarily disabled)
-    // pub transcription_service: TranscriptionService,
+    /// AI transcription, tagging and embedding
+    #[cfg(feature = "ai")]
+    pub processing_service: Arc<ProcessingService>,
     
-    /// AI image tagging service (temporarily disabled)
-    // pub tagging_service: TaggingService,
+    /// Queue running AI tasks, sized for the AI tier
+    #[cfg(feature = "ai")]
+    pub processing_queue: ProcessingQueue,
+    
+    /// Background pass finishing fast-imported assets, if one was started
+    #[cfg(feature = "ai")]
+    deep_pass: Option<tokio::task::JoinHandle<()>>,
+    
+    /// Set when assets were imported while the deep pass was running, so
+    /// it runs once more before stopping
+    #[cfg(feature = "ai")]
+    deep_pass_pending: Arc<AtomicBool>,
+    
+    /// Stops the deep pass before its next batch
+    #[cfg(feature = "ai")]
+    deep_pass_cancel: Arc<AtomicBool>,
     
     /// Application settings
     pub settings: AppSettings,
     
+    /// Progress and notifications from the services, forwarded to the frontend
+    pub events: UiEvents,
+    
     /// Current library path
     pub library_path: Option<PathBuf>,
 }
 
+/// Outcome of an import command
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportSummary {
+    /// Assets that were ingested and indexed
+    pub imported: Vec<Uuid>,
+    
+    /// Files that were not imported or whose processing failed
+    pub failures: Vec<ImportFailureInfo>,
+}
+
+/// A file an import could not fully handle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportFailureInfo {
+    pub path: PathBuf,
+    pub error: String,
+}
+
 /// Application settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppSettings {
@@ -40,6 +84,36 @@ pub struct AppSettings {
     /// AI processing settings
     pub ai_enabled: bool,
     pub ai_tier: ModelTier,
+    #[serde(default)]
+    pub ai_device: ComputeDevice,
+    /// Speed/quality trade-off of resampling audio for transcription
+    #[serde(default)]
+    pub resample_quality: ResampleQuality,
+    
+    /// Fast imports index previews and file info first and read metadata
+    /// and run AI steps in a background pass (needs the `ai` feature)
+    #[serde(default)]
+    pub ingest_mode: IngestMode,
+    
+    /// Largest `(rows, columns)` grid of video contact sheets; None gives
+    /// videos a placeholder preview. Contact sheets need ffmpeg.
+    #[serde(default = "default_video_contact_sheet")]
+    pub video_contact_sheet: Option<(u32, u32)>,
+    
+    /// Whether dot-prefixed files and folders are ingested, and which
+    /// patterns are ingested anyway
+    #[serde(default)]
+    pub hidden_files: HiddenFilePolicy,
+    
+    /// Extension to asset type mappings consulted before the built-in ones,
+    /// e.g. `{"xyz": "Image"}` for an in-house format
+    #[serde(default)]
+    pub type_overrides: AssetTypeOverrides,
+    
+    /// Rules deriving tags from file names at import, e.g.
+    /// `{"pattern": "^([A-Z]+)_", "template": "client:{1}"}`
+    #[serde(default)]
+    pub tag_rules: FilenameTagRules,
     
     /// UI preferences
     pub theme: ThemeMode,
@@ -73,6 +147,13 @@ impl Default for AppSettings {
             default_library_path: None,
             ai_enabled: true,
             ai_tier: ModelTier::Medium,
+            ai_device: ComputeDevice::Auto,
+            resample_quality: ResampleQuality::Linear,
+            ingest_mode: IngestMode::Full,
+            video_contact_sheet: default_video_contact_sheet(),
+            hidden_files: HiddenFilePolicy::default(),
+            type_overrides: AssetTypeOverrides::new(),
+            tag_rules: FilenameTagRules::new(),
             theme: ThemeMode::System,
             preview_size: PreviewSize::Medium,
             auto_tag: true,
@@ -84,6 +165,10 @@ impl Default for AppSettings {
     }
 }
 
+fn default_video_contact_sheet() -> Option<(u32, u32)> {
+    Some((3, 4))
+}
+
 impl DamApp {
     /// Initialize the application
     pub async fn new() -> UiResult<Self> {
@@ -92,39 +177,89 @@ impl DamApp {
         // Load or create settings
         let settings = Self::load_settings().unwrap_or_default();
         
-        // Initialize services
+        // Initialize services, sharing one event channel
+        let events = UiEvents::new();
         let index_service = IndexService::new()
-            .map_err(|e| UiError::InitializationFailed(format!("Failed to initialize search service: {}", e)))?;
+            .map_err(|e| UiError::InitializationFailed(format!("Failed to initialize search service: {}", e)))?
+            .with_preview_dir(ingest::preview::default_preview_dir())
+            .with_events(events.clone());
+        let index_service = SharedIndex::new(index_service);
         
         let ingest_service = IngestService::new()
-            .map_err(|e| UiError::InitializationFailed(format!("Failed to initialize ingest service: {}", e)))?;
-        
-        // Temporarily disabled until whisper.lib is compiled
-        // let transcription_service = TranscriptionService::new()
-        //     .map_err(|e| UiError::InitializationFailed(format!("Failed to initialize transcription service: {}", e)))?;
+            .map_err(|e| UiError::InitializationFailed(format!("Failed to initialize ingest service: {}", e)))?
+            .with_mode(settings.ingest_mode)
+            .with_video_contact_sheet(settings.video_contact_sheet)
+            .with_hidden_files(settings.hidden_files.clone())
+            .with_type_overrides(settings.type_overrides.clone())
+            .with_tag_rules(settings.tag_rules.clone())
+            .with_import_log(Arc::new(ImportLog::new(ingest::default_import_log_path())))
+            .with_events(events.clone());
         
-        // let tagging_service = TaggingService::new()
-        //     .map_err(|e| UiError::InitializationFailed(format!("Failed to initialize tagging service: {}", e)))?;
+        #[cfg(feature = "ai")]
+        let processing_service = {
+            let service = ProcessingService::new()
+                .map_err(|e| UiError::InitializationFailed(format!("Failed to initialize AI processing: {}", e)))?
+                .with_device(settings.ai_device)
+                .with_resample_quality(settings.resample_quality)
+                .with_events(events.clone());
+            // Without a cache, unchanged content is just processed again
+            let cache_dir = dirs::data_dir()
+                .map(|dir| dir.join("dam").join("embedding-cache"))
+                .unwrap_or_else(|| PathBuf::from("embedding-cache"));
+            let service = match EmbeddingCache::open(&cache_dir, DEFAULT_CACHE_ENTRIES) {
+                Ok(cache) => service.with_embedding_cache(Arc::new(cache)),
+                Err(e) => {
+                    warn!("Embedding cache unavailable: {}", e);
+                    service
+                }
+            };
+            Arc::new(service)
+        };
+        #[cfg(feature = "ai")]
+        let processing_queue = ProcessingQueue::new(
+            processing_service.clone(),
+            &settings.ai_tier,
+            processing_service.tagging().available_vram_mb(),
+        );
         
-        let app = Self {
+        #[allow(unused_mut)]
+        let mut app = Self {
             index_service,
             ingest_service,
-            // transcription_service,
-            // tagging_service,
+            #[cfg(feature = "ai")]
+            processing_service,
+            #[cfg(feature = "ai")]
+            processing_queue,
+            #[cfg(feature = "ai")]
+            deep_pass: None,
+            #[cfg(feature = "ai")]
+            deep_pass_pending: Arc::new(AtomicBool::new(false)),
+            #[cfg(feature = "ai")]
+            deep_pass_cancel: Arc::new(AtomicBool::new(false)),
             settings,
+            events,
             library_path: None,
         };
         
-        // Temporarily disabled AI tier setting
         // Set AI tier from settings
-        // if app.settings.ai_enabled {
-        //     if let Err(e) = app.transcription_service.set_tier(app.settings.ai_tier.clone()).await {
-        //         warn!("Failed to set transcription tier: {}", e);
-        //     }
-        //     if let Err(e) = app.tagging_service.set_tier(app.settings.ai_tier.clone()).await {
-        //         warn!("Failed to set tagging tier: {}", e);
-        //     }
-        // }
+        #[cfg(feature = "ai")]
+        if app.settings.ai_enabled {
+            let processing = &app.processing_service;
+            if let Err(e) = processing.transcription().set_tier(app.settings.ai_tier.clone()).await {
+                warn!("Failed to set transcription tier: {}", e);
+            }
+            if let Err(e) = processing.tagging().set_tier(app.settings.ai_tier.clone()).await {
+                warn!("Failed to set tagging tier: {}", e);
+            }
+        }
+        
+        // Keep documents up to date with queued AI tasks
+        #[cfg(feature = "ai")]
+        app.apply_queue_events();
+        
+        // Resume a deep pass an earlier session did not finish
+        #[cfg(feature = "ai")]
+        app.start_deep_pass();
         
         // Load default library if specified
         // if let Some(ref library_path) = app.settings.default_library_path {
@@ -139,52 +274,251 @@ impl DamApp {
     }
     
     /// Import a single file
-    pub async fn import_file(&mut self, file_path: PathBuf) -> UiResult<Asset> {
+    /// 
+    /// Fails if the file could not be ingested or indexed; failed AI steps
+    /// are listed in the summary.
+    pub async fn import_file(&mut self, file_path: PathBuf) -> UiResult<ImportSummary> {
         info!("Importing file: {}", file_path.display());
         
-        // Ingest the file
-        let asset = self.ingest_service.ingest_file(&file_path).await?;
-        
-        // Add to search index
-        self.index_service.index_asset(&asset).await?;
-        
-        // AI processing temporarily disabled
-        // Process with AI if enabled
-        // if self.settings.ai_enabled {
-        //     self.process_asset_with_ai(&mut asset).await?;
-        // }
+        let summary = self.import_paths(vec![file_path.clone()]).await?;
+        if summary.imported.is_empty() {
+            let reason = summary.failures.first().map(|failure| failure.error.clone()).unwrap_or_default();
+            return Err(UiError::ImportFailed(format!("{}: {}", file_path.display(), reason)));
+        }
         
         info!("Successfully imported: {}", file_path.display());
-        Ok(asset)
+        Ok(summary)
     }
     
     /// Import all files in a directory
-    pub async fn import_directory(&mut self, dir_path: PathBuf) -> UiResult<Vec<Asset>> {
+    pub async fn import_directory(&mut self, dir_path: PathBuf) -> UiResult<ImportSummary> {
         info!("Importing directory: {}", dir_path.display());
         
-        let assets = self.ingest_service.ingest_directory(&dir_path).await?;
-        let mut imported_assets = Vec::new();
+        let summary = self.import_paths(vec![dir_path]).await?;
         
-        for asset in assets {
-            // Add to search index
-            if let Err(e) = self.index_service.index_asset(&asset).await {
-                error!("Failed to index asset {}: {}", asset.id, e);
-                continue;
-            }
-            
-            // AI processing temporarily disabled
-            // Process with AI if enabled
-            // if self.settings.ai_enabled {
-            //     if let Err(e) = self.process_asset_with_ai(&mut asset).await {
-            //         warn!("Failed to process asset {} with AI: {}", asset.id, e);
-            //     }
-            // }
+        info!(
+            "Imported {} assets from directory, {} failures",
+            summary.imported.len(), summary.failures.len()
+        );
+        Ok(summary)
+    }
+    
+    /// Ingest, index and process files with `ProcessingService::import_and_process`
+    /// 
+    /// Fast imports leave metadata and AI steps to the background deep pass.
+    #[cfg(feature = "ai")]
+    async fn import_paths(&mut self, paths: Vec<PathBuf>) -> UiResult<ImportSummary> {
+        let report = self.processing_service.import_and_process(
+            &self.ingest_service,
+            &self.index_service,
+            &paths,
+            &self.import_options(),
+            |_| {},
+            &AtomicBool::new(false),
+        ).await?;
+        
+        if self.ingest_service.mode() == IngestMode::Fast && !report.indexed.is_empty() {
+            self.start_deep_pass();
+        }
+        
+        Ok(ImportSummary {
+            imported: report.indexed,
+            failures: report.failures.into_iter()
+                .map(|failure| ImportFailureInfo { path: failure.path, error: failure.error })
+                .collect(),
+        })
+    }
+    
+    /// Ingest and index files; AI steps need the `ai` feature
+    #[cfg(not(feature = "ai"))]
+    async fn import_paths(&mut self, paths: Vec<PathBuf>) -> UiResult<ImportSummary> {
+        let mut summary = ImportSummary::default();
+        for path in paths {
+            let (operation, assets) = if path.is_dir() {
+                let report = self.ingest_service.ingest_directory_report(&path).await?;
+                summary.failures.extend(report.failures.into_iter()
+                    .map(|file| ImportFailureInfo { path: file.path, error: file.error }));
+                summary.failures.extend(report.inaccessible.into_iter()
+                    .map(|location| ImportFailureInfo { path: location.path, error: location.message }));
+                (ingest::ImportOperation::Directory, report.assets)
+            } else {
+                match self.ingest_service.ingest_file(&path).await {
+                    Ok(asset) => (ingest::ImportOperation::File, vec![asset]),
+                    Err(e) => {
+                        summary.failures.push(ImportFailureInfo { path, error: e.to_string() });
+                        continue;
+                    }
+                }
+            };
             
-            imported_assets.push(asset);
+            for asset in assets {
+                let stored = self.index_service.index_asset(&asset).await;
+                self.ingest_service.record_stored(operation, &asset, &stored).await;
+                match stored {
+                    Ok(()) => summary.imported.push(asset.id),
+                    Err(e) => {
+                        error!("Failed to index asset {}: {}", asset.id, e);
+                        summary.failures.push(ImportFailureInfo { path: asset.current_path, error: e.to_string() });
+                    }
+                }
+            }
+        }
+        Ok(summary)
+    }
+    
+    /// Finish fast-imported assets in the background
+    /// 
+    /// Runs `ProcessingService::complete_deep_processing` with the AI steps
+    /// from the settings, handling as many assets at a time as the
+    /// processing queue has workers. Only one pass runs at a time; assets
+    /// imported while it runs are picked up by one more pass before it stops.
+    #[cfg(feature = "ai")]
+    pub fn start_deep_pass(&mut self) {
+        self.deep_pass_pending.store(true, Ordering::SeqCst);
+        if self.deep_pass.as_ref().map_or(false, |task| !task.is_finished()) {
+            return;
         }
         
-        info!("Successfully imported {} assets from directory", imported_assets.len());
-        Ok(imported_assets)
+        let processing = self.processing_service.clone();
+        let ingest = self.ingest_service.clone();
+        let index = self.index_service.clone();
+        let options = self.import_options();
+        let concurrency = self.processing_queue.concurrency();
+        let pending = self.deep_pass_pending.clone();
+        let cancel = self.deep_pass_cancel.clone();
+        self.deep_pass = Some(tokio::spawn(async move {
+            while pending.swap(false, Ordering::SeqCst) && !cancel.load(Ordering::Relaxed) {
+                match processing.complete_deep_processing(&ingest, &index, &options, concurrency, |_| {}, &cancel).await {
+                    Ok(report) => info!(
+                        "Deep pass completed {} assets with {} failures",
+                        report.indexed.len(), report.failures.len()
+                    ),
+                    Err(e) => {
+                        error!("Deep pass failed: {}", e);
+                        break;
+                    }
+                }
+            }
+        }));
+    }
+    
+    /// Record the progress and results of queued tasks on their documents
+    /// 
+    /// Runs until the processing queue shuts down. Events missed while the
+    /// index was busy are logged; their steps stay as they were.
+    #[cfg(feature = "ai")]
+    fn apply_queue_events(&self) {
+        let mut events = self.processing_queue.subscribe();
+        let index = self.index_service.clone();
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => {
+                        if let Err(e) = index.write().await.apply_processing_event(&event).await {
+                            warn!("Failed to record processing event: {}", e);
+                        }
+                    }
+                    Err(RecvError::Lagged(missed)) => warn!("Missed {} processing events", missed),
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+    
+    /// Stop the background deep pass before its next batch
+    /// 
+    /// Assets it did not finish stay marked and are picked up by the next pass.
+    #[cfg(feature = "ai")]
+    pub fn cancel_deep_pass(&self) {
+        self.deep_pass_cancel.store(true, Ordering::SeqCst);
+    }
+    
+    /// AI steps run on imported assets, from the settings
+    #[cfg(feature = "ai")]
+    fn import_options(&self) -> ImportOptions {
+        if !self.settings.ai_enabled {
+            return ImportOptions::without_ai();
+        }
+        let mut ai_steps = Vec::new();
+        if self.settings.auto_tag {
+            ai_steps.push(AiStep::Tagging);
+        }
+        if self.settings.auto_transcribe {
+            ai_steps.push(AiStep::Transcription);
+        }
+        ai_steps.push(AiStep::TextEmbedding);
+        ImportOptions { ai_steps }
+    }
+    
+    /// Move an asset's file on disk and keep the index consistent
+    pub async fn move_asset(&mut self, asset_id: Uuid, new_path: PathBuf) -> UiResult<()> {
+        let document = self.index_service.read().await.get_asset_document(asset_id)?
+            .ok_or_else(|| DamError::asset_not_found(asset_id))?;
+        let old_path = document.file_path;
+        
+        info!("Moving asset {} from {} to {}", asset_id, old_path.display(), new_path.display());
+        
+        ingest::move_file(&old_path, &new_path).await?;
+        
+        // Roll back the file move if the index could not be updated
+        let updated = self.index_service.write().await.update_asset_path(asset_id, &new_path).await;
+        if let Err(e) = updated {
+            error!("Failed to update index after moving asset {}: {}", asset_id, e);
+            if let Err(rollback) = ingest::move_file(&new_path, &old_path).await {
+                error!("Failed to restore {}: {}", old_path.display(), rollback);
+            }
+            return Err(e.into());
+        }
+        
+        Ok(())
+    }
+    
+    /// Regenerate previews for the given assets, or for every indexed asset
+    /// 
+    /// Each asset is handled independently; failures are collected in the
+    /// report rather than aborting the whole run.
+    pub async fn regenerate_previews(&mut self, asset_ids: Option<Vec<Uuid>>) -> UiResult<PreviewRegenerationReport> {
+        let asset_ids = match asset_ids {
+            Some(ids) => ids,
+            None => self.index_service.read().await.asset_ids()?,
+        };
+        
+        info!("Regenerating previews for {} assets", asset_ids.len());
+        let mut report = PreviewRegenerationReport::default();
+        
+        for asset_id in asset_ids {
+            match self.regenerate_preview(asset_id).await {
+                Ok(()) => report.regenerated.push(asset_id),
+                Err(e) => {
+                    warn!("Failed to regenerate preview for {}: {}", asset_id, e);
+                    report.failed.push(PreviewFailure { asset_id, error: e.to_string() });
+                }
+            }
+        }
+        
+        info!(
+            "Regenerated {} previews ({} failed)",
+            report.regenerated.len(),
+            report.failed.len()
+        );
+        Ok(report)
+    }
+    
+    /// Regenerate and store the preview of a single asset
+    async fn regenerate_preview(&mut self, asset_id: Uuid) -> UiResult<()> {
+        let document = self.index_service.read().await.get_asset_document(asset_id)?
+            .ok_or_else(|| DamError::asset_not_found(asset_id))?;
+        
+        let mut asset = Asset::new(document.file_path, document.asset_type);
+        asset.id = document.asset_id;
+        asset.file_size = document.file_size;
+        asset.created_at = document.created_at;
+        asset.modified_at = document.modified_at;
+        asset.format.extension = asset.extension().unwrap_or_default().to_lowercase();
+        
+        let preview = self.ingest_service.regenerate_preview(&asset).await?;
+        self.index_service.write().await.update_preview(asset_id, &preview).await?;
+        Ok(())
     }
     
     /// Process an asset with AI services (temporarily disabled)
@@ -199,27 +533,43 @@ impl DamApp {
         Ok(results)
     }
     
-    /// Find similar assets (temporarily disabled)
+    /// Search for assets, with results arriving best first as they load
+    pub async fn search_assets_stream(&self, query: &str, limit: usize) -> UiResult<BoxStream<'static, index::SearchResult>> {
+        let results = self.index_service.search_text_stream(query, limit).await?;
+        Ok(results)
+    }
+    
+    /// Search for assets, grouped into one ranked section per asset type
+    pub async fn search_assets_grouped(&self, query: &str, per_group_limit: usize) -> UiResult<Vec<index::SearchGroup>> {
+        let groups = self.index_service.search_grouped(query, per_group_limit).await?;
+        Ok(groups)
+    }
+    
+    /// Find visually similar assets, using the similarity threshold setting
+    /// 
+    /// Under a distance metric the index's `max_distance` applies instead.
     pub async fn find_similar(&self, asset_id: Uuid, limit: usize) -> UiResult<Vec<index::SearchResult>> {
-        // Temporarily return empty results
-        Ok(vec![])
-        // let results = self.index_service.find_similar(
-        //     asset_id,
-        //     index::EmbeddingType::Visual,
-        //     limit
-        // ).await?;
-        // Ok(results)
+        let service = self.index_service.read().await;
+        let min_similarity = (!service.config().distance_metric.is_distance())
+            .then_some(self.settings.similarity_threshold);
+        let results = service.find_similar(
+            asset_id,
+            index::EmbeddingType::Visual,
+            limit,
+            min_similarity
+        ).await?;
+        Ok(results)
     }
     
     /// Get library statistics
-    pub fn get_library_stats(&self) -> LibraryStats {
-        let index_stats = self.index_service.get_stats();
+    pub async fn get_library_stats(&self) -> LibraryStats {
+        let index_stats = self.index_service.get_stats().await;
         
         LibraryStats {
             total_assets: index_stats.total_documents,
             total_size: 0, // TODO: Calculate from assets
             asset_types: vec![], // TODO: Aggregate by type
-            ai_processed: index_stats.visual_embeddings + index_stats.text_embeddings,
+            ai_processed: index_stats.processing.fully_processed,
         }
     }
     
@@ -232,6 +582,21 @@ impl DamApp {
         //     self.tagging_service.set_tier(new_settings.ai_tier.clone()).await?;
         // }
         
+        self.ingest_service.set_mode(new_settings.ingest_mode);
+        self.ingest_service.set_video_contact_sheet(new_settings.video_contact_sheet);
+        self.ingest_service.set_hidden_files(new_settings.hidden_files.clone());
+        self.ingest_service.set_type_overrides(new_settings.type_overrides.clone());
+        self.ingest_service.set_tag_rules(new_settings.tag_rules.clone());
+        
+        #[cfg(feature = "ai")]
+        if new_settings.ai_device != self.settings.ai_device {
+            if let Err(e) = self.processing_service.set_device(new_settings.ai_device).await {
+                warn!("Failed to switch inference device: {}", e);
+            }
+        }
+        #[cfg(feature = "ai")]
+        self.processing_service.set_resample_quality(new_settings.resample_quality);
+        
         // Save settings
         self.settings = new_settings;
         self.save_settings()?;
@@ -287,6 +652,20 @@ impl DamApp {
     }
 }
 
+/// Outcome of a preview regeneration run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PreviewRegenerationReport {
+    pub regenerated: Vec<Uuid>,
+    pub failed: Vec<PreviewFailure>,
+}
+
+/// An asset whose preview could not be regenerated
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreviewFailure {
+    pub asset_id: Uuid,
+    pub error: String,
+}
+
 /// Library statistics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LibraryStats {
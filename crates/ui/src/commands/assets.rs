@@ -1,13 +1,15 @@
 //! Asset management command handlers
 
-use crate::app::DamApp;
+use crate::app::{DamApp, ImportSummary};
 use crate::commands::CommandResponse;
+use crate::error::UiError;
+use index::AssetDetails;
 use schema::Asset;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::sync::Arc;
 use tauri::State;
-use tokio::sync::Mutex;
+use tokio::sync::RwLock;
 use uuid::Uuid;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -20,6 +22,12 @@ pub struct ImportDirectoryRequest {
     pub directory_path: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MoveAssetRequest {
+    pub asset_id: String,
+    pub new_path: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AssetDetailsRequest {
     pub asset_id: String,
@@ -29,75 +37,103 @@ pub struct AssetDetailsRequest {
 #[tauri::command]
 pub async fn get_asset_details(
     request: AssetDetailsRequest,
-    app_state: State<'_, Arc<Mutex<DamApp>>>,
+    app_state: State<'_, Arc<RwLock<DamApp>>>,
 ) -> Result<CommandResponse<Option<Asset>>, String> {
-    let app = app_state.lock().await;
+    let app = app_state.read().await;
     
     // Parse UUID
     let asset_id = match Uuid::parse_str(&request.asset_id) {
         Ok(id) => id,
-        Err(_) => return Ok(CommandResponse::error("Invalid asset ID".to_string())),
+        Err(_) => return Ok(CommandResponse::invalid_request("Invalid asset ID")),
     };
     
-    // For now, we'll need to search for the asset since we don't have direct lookup
-    // This could be optimized later with a direct asset lookup method
-    let search_results = match app.search_assets("", 1000).await {
-        Ok(results) => results,
-        Err(e) => return Ok(CommandResponse::error(e.to_string())),
+    let document = match app.index_service.read().await.get_asset_document(asset_id) {
+        Ok(document) => document,
+        Err(e) => return Ok(CommandResponse::failure(&UiError::from(e))),
     };
     
-    let asset = search_results
-        .into_iter()
-        .find(|result| result.document.asset_id == asset_id)
-        .map(|result| {
-            // Convert AssetDocument back to Asset
-            // This is a simplified conversion for now
-            Asset {
-                id: result.document.asset_id,
-                original_path: result.document.file_path.clone(),
-                current_path: result.document.file_path,
-                asset_type: result.document.asset_type,
-                file_size: result.document.file_size,
-                format: schema::FileFormat {
-                    extension: result.document.filename
-                        .split('.')
-                        .last()
-                        .unwrap_or("unknown")
-                        .to_string(),
-                    mime_type: None,
-                    version: None,
-                    supported: true,
-                },
-                created_at: result.document.created_at,
-                modified_at: result.document.modified_at,
-                tags: result.document.tags,
-                metadata: schema::AssetMetadata::default(), // TODO: Reconstruct from document
-                preview: result.document.preview_path.map(|path| schema::PreviewInfo {
-                    thumbnail_path: path.clone(),
-                    thumbnail_size: (256, 256), // Default thumbnail size
-                    rendered_preview: Some(path),
-                    generated_at: result.document.indexed_at,
-                }),
-                embedding: result.document.visual_embedding,
-                version_info: schema::VersionInfo {
-                    current_version: "v1".to_string(),
-                    version_count: 1,
-                    last_snapshot: result.document.created_at,
-                    has_changes: false,
-                },
-            }
-        });
+    let asset = document.map(|document| {
+        // Convert AssetDocument back to Asset
+        // This is a simplified conversion for now
+        Asset {
+            id: document.asset_id,
+            original_path: document.original_path.clone()
+                .unwrap_or_else(|| document.file_path.clone()),
+            current_path: document.file_path,
+            asset_type: document.asset_type,
+            file_size: document.file_size,
+            format: schema::FileFormat {
+                extension: document.filename
+                    .split('.')
+                    .last()
+                    .unwrap_or("unknown")
+                    .to_string(),
+                mime_type: None,
+                version: None,
+                supported: document.format_supported,
+                detection_method: document.detection_method,
+                confidence: document.format_confidence,
+            },
+            created_at: document.created_at,
+            modified_at: document.modified_at,
+            tags: document.tags,
+            rating: document.rating,
+            favorite: document.favorite,
+            // TODO: Reconstruct the format-specific metadata from the document
+            metadata: schema::AssetMetadata {
+                custom: document.custom_metadata(),
+                ..Default::default()
+            },
+            preview: document.preview_path.map(|path| schema::PreviewInfo {
+                thumbnail_path: path.clone(),
+                thumbnail_size: (256, 256), // Default thumbnail size
+                extension: path.extension()
+                    .map(|ext| ext.to_string_lossy().to_lowercase())
+                    .unwrap_or_else(|| "jpg".to_string()),
+                rendered_preview: document.contact_sheet_path.or(Some(path)),
+                generated_at: document.indexed_at,
+                waveform: document.waveform,
+                contact_sheet_grid: document.contact_sheet_grid,
+            }),
+            embedding: document.visual_embedding,
+            version_info: schema::VersionInfo {
+                current_version: "v1".to_string(),
+                version_count: 1,
+                last_snapshot: document.created_at,
+                has_changes: false,
+            },
+            integrity: document.integrity,
+            needs_deep_processing: document.needs_deep_processing,
+        }
+    });
     
     Ok(CommandResponse::success(asset))
 }
 
+/// Get an asset's document and similar assets in one call
+#[tauri::command]
+pub async fn get_asset_overview(
+    request: AssetDetailsRequest,
+    app_state: State<'_, Arc<RwLock<DamApp>>>,
+) -> Result<CommandResponse<AssetDetails>, String> {
+    let app = app_state.read().await;
+    
+    let asset_id = match Uuid::parse_str(&request.asset_id) {
+        Ok(id) => id,
+        Err(_) => return Ok(CommandResponse::invalid_request("Invalid asset ID")),
+    };
+    
+    let result = app.index_service.read().await.get_asset_details(asset_id).map_err(UiError::from);
+    Ok(result.into())
+}
+
 /// Import a single file
 #[tauri::command]
 pub async fn import_file(
     request: ImportFileRequest,
-    app_state: State<'_, Arc<Mutex<DamApp>>>,
-) -> Result<CommandResponse<Asset>, String> {
-    let mut app = app_state.lock().await;
+    app_state: State<'_, Arc<RwLock<DamApp>>>,
+) -> Result<CommandResponse<ImportSummary>, String> {
+    let mut app = app_state.write().await;
     let file_path = PathBuf::from(request.file_path);
     
     let result = app.import_file(file_path).await;
@@ -108,11 +144,28 @@ pub async fn import_file(
 #[tauri::command]
 pub async fn import_directory(
     request: ImportDirectoryRequest,
-    app_state: State<'_, Arc<Mutex<DamApp>>>,
-) -> Result<CommandResponse<Vec<Asset>>, String> {
-    let mut app = app_state.lock().await;
+    app_state: State<'_, Arc<RwLock<DamApp>>>,
+) -> Result<CommandResponse<ImportSummary>, String> {
+    let mut app = app_state.write().await;
     let directory_path = PathBuf::from(request.directory_path);
     
     let result = app.import_directory(directory_path).await;
     Ok(result.into())
 }
+
+/// Move an asset to a new location on disk
+#[tauri::command]
+pub async fn move_asset(
+    request: MoveAssetRequest,
+    app_state: State<'_, Arc<RwLock<DamApp>>>,
+) -> Result<CommandResponse<()>, String> {
+    let mut app = app_state.write().await;
+    
+    let asset_id = match Uuid::parse_str(&request.asset_id) {
+        Ok(id) => id,
+        Err(_) => return Ok(CommandResponse::invalid_request("Invalid asset ID")),
+    };
+    
+    let result = app.move_asset(asset_id, PathBuf::from(request.new_path)).await;
+    Ok(result.into())
+}
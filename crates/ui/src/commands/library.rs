@@ -1,18 +1,26 @@
 //! Library management command handlers
 
-use crate::app::{DamApp, LibraryStats};
+use crate::app::{DamApp, LibraryStats, PreviewRegenerationReport};
 use crate::commands::CommandResponse;
+use ingest::FormatCapabilities;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::sync::Arc;
 use tauri::State;
-use tokio::sync::Mutex;
+use tokio::sync::RwLock;
+use uuid::Uuid;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ScanLibraryRequest {
     pub library_path: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RegeneratePreviewsRequest {
+    /// Assets to rebuild; all indexed assets when omitted
+    pub asset_ids: Option<Vec<String>>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LibraryStatsResponse {
     pub stats: LibraryStats,
@@ -22,11 +30,11 @@ pub struct LibraryStatsResponse {
 /// Get current library statistics
 #[tauri::command]
 pub async fn get_library_stats(
-    app_state: State<'_, Arc<Mutex<DamApp>>>,
+    app_state: State<'_, Arc<RwLock<DamApp>>>,
 ) -> Result<CommandResponse<LibraryStatsResponse>, String> {
-    let app = app_state.lock().await;
+    let app = app_state.read().await;
     
-    let stats = app.get_library_stats();
+    let stats = app.get_library_stats().await;
     let library_path = app.library_path.as_ref().map(|p| p.to_string_lossy().to_string());
     
     let response = LibraryStatsResponse {
@@ -37,13 +45,22 @@ pub async fn get_library_stats(
     Ok(CommandResponse::success(response))
 }
 
+/// List supported file formats and what can be done with each
+#[tauri::command]
+pub async fn get_format_capabilities(
+    app_state: State<'_, Arc<RwLock<DamApp>>>,
+) -> Result<CommandResponse<FormatCapabilities>, String> {
+    let app = app_state.read().await;
+    Ok(CommandResponse::success(app.ingest_service.capabilities()))
+}
+
 /// Scan and import all assets from a library directory
 #[tauri::command]
 pub async fn scan_library(
     request: ScanLibraryRequest,
-    app_state: State<'_, Arc<Mutex<DamApp>>>,
+    app_state: State<'_, Arc<RwLock<DamApp>>>,
 ) -> Result<CommandResponse<usize>, String> {
-    let mut app = app_state.lock().await;
+    let mut app = app_state.write().await;
     let library_path = PathBuf::from(request.library_path);
     
     // Set as current library
@@ -53,7 +70,28 @@ pub async fn scan_library(
     let result = app.import_directory(library_path).await;
     
     match result {
-        Ok(assets) => Ok(CommandResponse::success(assets.len())),
-        Err(e) => Ok(CommandResponse::error(e.to_string())),
+        Ok(summary) => Ok(CommandResponse::success(summary.imported.len())),
+        Err(e) => Ok(CommandResponse::failure(&e)),
     }
 }
+
+/// Rebuild thumbnails for some or all assets
+#[tauri::command]
+pub async fn regenerate_previews(
+    request: RegeneratePreviewsRequest,
+    app_state: State<'_, Arc<RwLock<DamApp>>>,
+) -> Result<CommandResponse<PreviewRegenerationReport>, String> {
+    let mut app = app_state.write().await;
+    
+    // Parse UUIDs
+    let asset_ids = match request.asset_ids {
+        Some(ids) => match ids.iter().map(|id| Uuid::parse_str(id)).collect::<Result<Vec<_>, _>>() {
+            Ok(ids) => Some(ids),
+            Err(_) => return Ok(CommandResponse::invalid_request("Invalid asset ID")),
+        },
+        None => None,
+    };
+    
+    let result = app.regenerate_previews(asset_ids).await;
+    Ok(result.into())
+}
@@ -8,7 +8,8 @@ pub mod assets;
 pub mod library;
 pub mod settings;
 
-use crate::error::UiResult;
+use crate::error::{UiError, UiResult};
+use schema::ErrorCategory;
 use serde::{Deserialize, Serialize};
 
 /// Standard response wrapper for commands
@@ -17,6 +18,10 @@ pub struct CommandResponse<T> {
     pub success: bool,
     pub data: Option<T>,
     pub error: Option<String>,
+    /// Category of the error, so the frontend can choose how to react
+    pub error_category: Option<ErrorCategory>,
+    /// Whether retrying the command may succeed
+    pub recoverable: Option<bool>,
 }
 
 impl<T> CommandResponse<T> {
@@ -25,6 +30,8 @@ impl<T> CommandResponse<T> {
             success: true,
             data: Some(data),
             error: None,
+            error_category: None,
+            recoverable: None,
         }
     }
     
@@ -33,6 +40,28 @@ impl<T> CommandResponse<T> {
             success: false,
             data: None,
             error: Some(message),
+            error_category: None,
+            recoverable: None,
+        }
+    }
+    
+    /// Failed response carrying the error's category and recoverability
+    pub fn failure(error: &UiError) -> Self {
+        Self {
+            success: false,
+            data: None,
+            error: Some(error.to_string()),
+            error_category: Some(error.category()),
+            recoverable: Some(error.is_recoverable()),
+        }
+    }
+    
+    /// Failed response for a malformed request
+    pub fn invalid_request(message: &str) -> Self {
+        Self {
+            error_category: Some(ErrorCategory::System),
+            recoverable: Some(false),
+            ..Self::error(message.to_string())
         }
     }
 }
@@ -41,7 +70,7 @@ impl<T> From<UiResult<T>> for CommandResponse<T> {
     fn from(result: UiResult<T>) -> Self {
         match result {
             Ok(data) => CommandResponse::success(data),
-            Err(error) => CommandResponse::error(error.to_string()),
+            Err(error) => CommandResponse::failure(&error),
         }
     }
 }
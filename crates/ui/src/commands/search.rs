@@ -2,11 +2,13 @@
 
 use crate::app::DamApp;
 use crate::commands::CommandResponse;
-use index::SearchResult;
+use futures::StreamExt;
+use index::{SearchGroup, SearchResult};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use tauri::ipc::Channel;
 use tauri::State;
-use tokio::sync::Mutex;
+use tokio::sync::RwLock;
 use uuid::Uuid;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -15,6 +17,13 @@ pub struct SearchRequest {
     pub limit: Option<usize>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GroupedSearchRequest {
+    pub query: String,
+    /// Results shown per asset type
+    pub per_group_limit: Option<usize>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SimilarSearchRequest {
     pub asset_id: String,
@@ -25,28 +34,71 @@ pub struct SimilarSearchRequest {
 #[tauri::command]
 pub async fn search_assets(
     request: SearchRequest,
-    app_state: State<'_, Arc<Mutex<DamApp>>>,
+    app_state: State<'_, Arc<RwLock<DamApp>>>,
 ) -> Result<CommandResponse<Vec<SearchResult>>, String> {
-    let app = app_state.lock().await;
+    let app = app_state.read().await;
     let limit = request.limit.unwrap_or(50);
     
     let result = app.search_assets(&request.query, limit).await;
     Ok(result.into())
 }
 
+/// Search for assets by text query, sending each result over `on_result`
+/// as soon as it is loaded, best first
+/// 
+/// Responds with the number of results sent once the search is done.
+#[tauri::command]
+pub async fn search_assets_streamed(
+    request: SearchRequest,
+    on_result: Channel<SearchResult>,
+    app_state: State<'_, Arc<RwLock<DamApp>>>,
+) -> Result<CommandResponse<usize>, String> {
+    let limit = request.limit.unwrap_or(50);
+    
+    // The stream owns what it reads, so the app lock is not held while it runs
+    let results = app_state.read().await.search_assets_stream(&request.query, limit).await;
+    let mut results = match results {
+        Ok(results) => results,
+        Err(e) => return Ok(CommandResponse::failure(&e)),
+    };
+    
+    let mut sent = 0;
+    while let Some(result) = results.next().await {
+        if let Err(e) = on_result.send(result) {
+            // The frontend has gone away; stop reading
+            return Ok(CommandResponse::error(format!("Failed to send search result: {}", e)));
+        }
+        sent += 1;
+    }
+    Ok(CommandResponse::success(sent))
+}
+
+/// Search for assets by text query, grouped by asset type
+#[tauri::command]
+pub async fn search_assets_grouped(
+    request: GroupedSearchRequest,
+    app_state: State<'_, Arc<RwLock<DamApp>>>,
+) -> Result<CommandResponse<Vec<SearchGroup>>, String> {
+    let app = app_state.read().await;
+    let per_group_limit = request.per_group_limit.unwrap_or(5);
+    
+    let result = app.search_assets_grouped(&request.query, per_group_limit).await;
+    Ok(result.into())
+}
+
 /// Find assets similar to a given asset
 #[tauri::command]
 pub async fn search_similar(
     request: SimilarSearchRequest,
-    app_state: State<'_, Arc<Mutex<DamApp>>>,
+    app_state: State<'_, Arc<RwLock<DamApp>>>,
 ) -> Result<CommandResponse<Vec<SearchResult>>, String> {
-    let app = app_state.lock().await;
+    let app = app_state.read().await;
     let limit = request.limit.unwrap_or(10);
     
     // Parse UUID
     let asset_id = match Uuid::parse_str(&request.asset_id) {
         Ok(id) => id,
-        Err(_) => return Ok(CommandResponse::error("Invalid asset ID".to_string())),
+        Err(_) => return Ok(CommandResponse::invalid_request("Invalid asset ID")),
     };
     
     let result = app.find_similar(asset_id, limit).await;
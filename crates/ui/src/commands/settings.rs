@@ -5,7 +5,7 @@ use crate::commands::CommandResponse;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tauri::State;
-use tokio::sync::Mutex;
+use tokio::sync::RwLock;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UpdateSettingsRequest {
@@ -15,9 +15,9 @@ pub struct UpdateSettingsRequest {
 /// Get current application settings
 #[tauri::command]
 pub async fn get_settings(
-    app_state: State<'_, Arc<Mutex<DamApp>>>,
+    app_state: State<'_, Arc<RwLock<DamApp>>>,
 ) -> Result<CommandResponse<AppSettings>, String> {
-    let app = app_state.lock().await;
+    let app = app_state.read().await;
     let settings = app.settings.clone();
     Ok(CommandResponse::success(settings))
 }
@@ -26,9 +26,9 @@ pub async fn get_settings(
 #[tauri::command]
 pub async fn update_settings(
     request: UpdateSettingsRequest,
-    app_state: State<'_, Arc<Mutex<DamApp>>>,
+    app_state: State<'_, Arc<RwLock<DamApp>>>,
 ) -> Result<CommandResponse<()>, String> {
-    let mut app = app_state.lock().await;
+    let mut app = app_state.write().await;
     let result = app.update_settings(request.settings).await;
     Ok(result.into())
 }
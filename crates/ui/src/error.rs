@@ -1,5 +1,6 @@
 //! UI-specific error types
 
+use schema::{DamError, ErrorCategory, ErrorInfo};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
@@ -22,11 +23,60 @@ pub enum UiError {
     
     #[error("Internal error: {0}")]
     InternalError(String),
+    
+    /// Error from one of the services, keeping its category
+    #[error("{}", .0.message)]
+    Service(ErrorInfo),
+}
+
+impl UiError {
+    /// Error category, for the frontend to pick how to react
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            UiError::InitializationFailed(_) | UiError::SettingsError(_) => ErrorCategory::Configuration,
+            UiError::SearchFailed(_) => ErrorCategory::Search,
+            UiError::FileOperationFailed(_) => ErrorCategory::FileSystem,
+            UiError::ImportFailed(_) => ErrorCategory::Asset,
+            UiError::InternalError(_) => ErrorCategory::System,
+            UiError::Service(info) => info.category,
+        }
+    }
+    
+    /// Whether retrying the command may succeed
+    pub fn is_recoverable(&self) -> bool {
+        match self {
+            UiError::SearchFailed(_) | UiError::ImportFailed(_) => true,
+            UiError::InitializationFailed(_)
+            | UiError::FileOperationFailed(_)
+            | UiError::SettingsError(_)
+            | UiError::InternalError(_) => false,
+            UiError::Service(info) => info.recoverable,
+        }
+    }
+    
+    /// Serializable summary of the error
+    pub fn info(&self) -> ErrorInfo {
+        match self {
+            UiError::Service(info) => info.clone(),
+            err => ErrorInfo {
+                category: err.category(),
+                recoverable: err.is_recoverable(),
+                message: err.to_string(),
+                user_message: err.to_string(),
+            },
+        }
+    }
+}
+
+impl From<DamError> for UiError {
+    fn from(err: DamError) -> Self {
+        UiError::Service(err.into())
+    }
 }
 
-impl From<schema::DamError> for UiError {
-    fn from(err: schema::DamError) -> Self {
-        UiError::InternalError(err.to_string())
+impl From<UiError> for DamError {
+    fn from(err: UiError) -> Self {
+        err.info().into()
     }
 }
 
@@ -9,6 +9,6 @@ pub mod commands;
 pub mod error;
 pub mod state;
 
-pub use app::{DamApp, AppSettings, LibraryStats};
+pub use app::{DamApp, AppSettings, ImportFailureInfo, ImportSummary, LibraryStats};
 pub use error::{UiError, UiResult};
 pub use state::{AppState, init_app_state};
@@ -7,10 +7,10 @@
     windows_subsystem = "windows"
 )]
 
-use tauri::Manager;
-use tracing::{info, error};
+use tauri::{Emitter, Manager};
+use tracing::{info, warn, error};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, RwLock};
 
 mod app;
 mod commands;
@@ -20,6 +20,9 @@ mod state;
 use app::DamApp;
 use error::UiError;
 
+/// Event name under which `UiMessage`s reach the frontend
+const UI_EVENT: &str = "dam://ui-event";
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Initialize logging
@@ -28,23 +31,49 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     info!("Starting Digital Asset Manager");
     
     // Create application state
-    let app_state = Arc::new(Mutex::new(DamApp::new().await?));
+    let app = DamApp::new().await?;
+    let mut ui_events = app.events.subscribe();
+    let app_state = Arc::new(RwLock::new(app));
     
     // Build Tauri application
     tauri::Builder::default()
         .manage(app_state)
         .invoke_handler(tauri::generate_handler![
             commands::search::search_assets,
+            commands::search::search_assets_streamed,
+            commands::search::search_assets_grouped,
             commands::search::search_similar,
             commands::assets::get_asset_details,
+            commands::assets::get_asset_overview,
             commands::assets::import_file,
             commands::assets::import_directory,
+            commands::assets::move_asset,
             commands::library::get_library_stats,
+            commands::library::get_format_capabilities,
             commands::library::scan_library,
+            commands::library::regenerate_previews,
             commands::settings::get_settings,
             commands::settings::update_settings,
         ])
-        .setup(|_app| {
+        .setup(|app| {
+            // Forward service progress and notifications to the frontend
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    match ui_events.recv().await {
+                        Ok(message) => {
+                            if let Err(e) = handle.emit(UI_EVENT, &message) {
+                                error!("Failed to emit UI event: {}", e);
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(missed)) => {
+                            warn!("Frontend missed {} UI events", missed);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    }
+                }
+            });
+            
             info!("Tauri application setup complete");
             Ok(())
         })
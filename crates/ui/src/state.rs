@@ -2,13 +2,16 @@
 
 use crate::app::DamApp;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::RwLock;
 
 /// Type alias for the shared application state
-pub type AppState = Arc<Mutex<DamApp>>;
+/// 
+/// Commands that only read (searches, stats, settings) take the read lock
+/// and run concurrently; imports and other changes take the write lock.
+pub type AppState = Arc<RwLock<DamApp>>;
 
 /// Initialize the application state
 pub async fn init_app_state() -> Result<AppState, Box<dyn std::error::Error>> {
     let app = DamApp::new().await?;
-    Ok(Arc::new(Mutex::new(app)))
+    Ok(Arc::new(RwLock::new(app)))
 }
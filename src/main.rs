@@ -3,13 +3,15 @@
 //! This demonstrates the core functionality of the DAM system.
 
 use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
 use tokio;
 use tracing::{info, warn, error};
 use tracing_subscriber;
 
 use schema::{Asset, AssetType};
 use ingest::IngestService;
-use index::IndexService;
+use index::{IndexService, SharedIndex};
+use process::{ImportOptions, ProcessingService};
 use server::DamServer;
 
 #[tokio::main]
@@ -23,7 +25,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Initialize core services
     println!("\n📚 Initializing services...");
     
-    let mut ingest_service = match IngestService::new() {
+    let ingest_service = match IngestService::new() {
         Ok(service) => {
             println!("✅ Ingest service initialized");
             service
@@ -34,10 +36,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
     
-    let mut index_service = match IndexService::new() {
+    let index_service = match IndexService::new() {
         Ok(service) => {
             println!("✅ Search index initialized");
-            service
+            SharedIndex::new(service)
         }
         Err(e) => {
             println!("❌ Failed to initialize search service: {}", e);
@@ -51,11 +53,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     
     // Demonstrate file ingestion
     println!("\n📥 Testing file ingestion...");
-    demo_file_ingestion(&mut ingest_service, &mut index_service).await?;
+    demo_file_ingestion(&ingest_service, &index_service).await?;
     
     // Demonstrate search functionality
     println!("\n🔍 Testing search functionality...");
-    demo_search_functionality(&index_service).await?;
+    demo_search_functionality(&*index_service.read().await).await?;
     
     // Start the LAN server
     println!("\n🌐 Starting LAN server...");
@@ -113,31 +115,29 @@ async fn create_sample_files() -> Result<(), Box<dyn std::error::Error>> {
 }
 
 async fn demo_file_ingestion(
-    ingest_service: &mut IngestService, 
-    index_service: &mut IndexService
+    ingest_service: &IngestService, 
+    index_service: &SharedIndex
 ) -> Result<(), Box<dyn std::error::Error>> {
     
     let test_files = vec![
-        "test_assets/sample_text.txt",
-        "test_assets/sample_metadata.json", 
-        "test_assets/sample_data.csv"
+        PathBuf::from("test_assets/sample_text.txt"),
+        PathBuf::from("test_assets/sample_metadata.json"), 
+        PathBuf::from("test_assets/sample_data.csv")
     ];
     
-    for file_path in test_files {
-        match ingest_service.ingest_file(&PathBuf::from(file_path)).await {
-            Ok(asset) => {
-                println!("✅ Ingested: {} ({})", file_path, asset.asset_type);
-                
-                // Add to search index
-                match index_service.index_asset(&asset).await {
-                    Ok(_) => println!("   📇 Added to search index"),
-                    Err(e) => println!("   ⚠️  Failed to index: {}", e),
-                }
-            }
-            Err(e) => {
-                println!("⚠️  Failed to ingest {}: {}", file_path, e);
-            }
-        }
+    let processing_service = ProcessingService::new()?;
+    let report = processing_service.import_and_process(
+        ingest_service,
+        index_service,
+        &test_files,
+        &ImportOptions::without_ai(),
+        |_| {},
+        &AtomicBool::new(false),
+    ).await?;
+    
+    println!("✅ Ingested and indexed {} files", report.indexed.len());
+    for failure in &report.failures {
+        println!("⚠️  Failed to import {} ({:?}): {}", failure.path.display(), failure.stage, failure.error);
     }
     
     Ok(())